@@ -20,6 +20,8 @@ pub enum OckamError {
     UnknownForwarderDestinationAddress,
     UnknownForwarderNextHopAddress,
     InvalidHex,
+    ForwarderNameNotAuthorized,
+    ForwarderNameAlreadyClaimed,
 }
 
 impl ockam_core::compat::error::Error for OckamError {}
@@ -36,6 +38,8 @@ impl From<OckamError> for Error {
         // TODO: improve this mapping
         let kind = match err {
             SystemAddressNotBound | SystemInvalidConfiguration | InvalidParameter => Kind::Misuse,
+            ForwarderNameNotAuthorized => Kind::Invalid,
+            ForwarderNameAlreadyClaimed => Kind::Conflict,
             _ => Kind::Protocol,
         };
 
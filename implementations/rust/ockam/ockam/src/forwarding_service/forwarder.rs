@@ -1,12 +1,15 @@
+use crate::forwarding_service::relay_mailbox::{RelayMailbox, RelayMailboxRequest};
+use crate::forwarding_service::relay_names::RelayNameLease;
 use crate::Context;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::{
-    Address, AllowAll, AllowOnwardAddress, Any, IncomingAccessControl, LocalMessage,
-    OutgoingAccessControl, Result, Route, Routed, TransportMessage, Worker,
+    Address, AllowAll, AllowOnwardAddress, AllowSourceAddress, Any, Decodable, DenyAll, Encodable,
+    IncomingAccessControl, LocalMessage, Mailbox, Mailboxes, OutgoingAccessControl, Result, Route,
+    Routed, TransportMessage, Worker,
 };
-use ockam_node::WorkerBuilder;
-use tracing::info;
+use ockam_node::{DelayedEvent, WorkerBuilder};
+use tracing::{debug, info, warn};
 
 pub(super) struct Forwarder {
     forward_route: Route,
@@ -14,6 +17,22 @@ pub(super) struct Forwarder {
     // while initializing, the worker will send the payload contained in this
     // field to the `forward_route`, to indicate a successful connection
     payload: Option<Vec<u8>>,
+    mailbox: Option<ForwarderMailbox>,
+    // claim of the relay name, released when this worker stops
+    lease: Option<RelayNameLease>,
+}
+
+/// State of the mailbox of a Forwarder whose target requested store-and-forward
+struct ForwarderMailbox {
+    messages: RelayMailbox,
+    tick_address: Address,
+    tick: DelayedEvent<Vec<u8>>,
+    // return route of the drain requests of the target
+    subscriber: Option<Route>,
+    online: bool,
+    refreshed: bool,
+    // last sequence number sent to the subscriber
+    sent: Option<u64>,
 }
 
 impl Forwarder {
@@ -23,6 +42,8 @@ impl Forwarder {
         forward_route: Route,
         registration_payload: Vec<u8>,
         incoming_access_control: Arc<dyn IncomingAccessControl>,
+        mailbox: Option<RelayMailbox>,
+        lease: Option<RelayNameLease>,
     ) -> Result<()> {
         info!("Created new alias {} for {}", address, forward_route);
 
@@ -35,15 +56,42 @@ impl Forwarder {
             Arc::new(AllowOnwardAddress(next_hop))
         };
 
+        let mut additional_mailboxes = vec![];
+        let mailbox = match mailbox {
+            Some(messages) => {
+                let tick_address = Address::random_tagged("Forwarder.mailbox_tick");
+                let tick = DelayedEvent::create(ctx, tick_address.clone(), vec![]).await?;
+                additional_mailboxes.push(Mailbox::new(
+                    tick_address.clone(),
+                    Arc::new(AllowSourceAddress(tick.address())),
+                    Arc::new(DenyAll),
+                ));
+                Some(ForwarderMailbox {
+                    messages,
+                    tick_address,
+                    tick,
+                    subscriber: None,
+                    online: true,
+                    refreshed: false,
+                    sent: None,
+                })
+            }
+            None => None,
+        };
+
         let forwarder = Self {
             forward_route,
             payload: Some(registration_payload.clone()),
+            mailbox,
+            lease,
         };
 
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(address, incoming_access_control, outgoing_access_control),
+            additional_mailboxes,
+        );
         WorkerBuilder::new(forwarder)
-            .with_address(address)
-            .with_incoming_access_control_arc(incoming_access_control)
-            .with_outgoing_access_control_arc(outgoing_access_control)
+            .with_mailboxes(mailboxes)
             .start(ctx)
             .await?;
 
@@ -68,6 +116,22 @@ impl Worker for Forwarder {
         // Remove the last hop so that just route to the node itself is left
         self.forward_route.modify().pop_back();
 
+        if let Some(mailbox) = &mut self.mailbox {
+            // The messages are loaded once the replaced Forwarder, if any, is stopped
+            mailbox.messages.load().await?;
+            mailbox
+                .tick
+                .schedule(mailbox.messages.heartbeat_timeout())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        if let Some(lease) = &self.lease {
+            lease.release();
+        }
         Ok(())
     }
 
@@ -76,37 +140,198 @@ impl Worker for Forwarder {
         ctx: &mut Self::Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
-        let mut message = msg.into_local_message();
-        let transport_message = message.transport_mut();
+        let mailbox = match &mut self.mailbox {
+            Some(mailbox) => mailbox,
+            None => return forward_message(ctx, msg, &self.forward_route).await,
+        };
+
+        if msg.msg_addr() == mailbox.tick_address {
+            return mailbox.on_tick().await;
+        }
 
+        let return_route = msg.return_route();
+        let mut message = msg.into_local_message();
         // Remove my address from the onward_route
-        transport_message.onward_route.step()?;
-
-        // Prepend forward route
-        transport_message
-            .onward_route
-            .modify()
-            .prepend_route(self.forward_route.clone());
-
-        let next_hop = transport_message.onward_route.next()?.clone();
-        let prev_hop = transport_message.return_route.next()?.clone();
-
-        if let Some(info) = ctx
-            .flow_controls()
-            .find_flow_control_with_producer_address(&next_hop)
-        {
-            ctx.flow_controls()
-                .add_consumer(prev_hop.clone(), info.flow_control_id());
+        message.transport_mut().onward_route.step()?;
+
+        if message.transport().onward_route.is_empty() {
+            // Only the target of the relay can send requests to its mailbox
+            let mut source = return_route.clone();
+            source.modify().pop_back();
+            if source != self.forward_route {
+                warn!(
+                    "Dropping a message without onward route sent to alias {}",
+                    ctx.address()
+                );
+                return Ok(());
+            }
+            let request = RelayMailboxRequest::decode(&message.transport().payload)?;
+            return mailbox.on_request(ctx, request, return_route).await;
         }
 
-        if let Some(info) = ctx
-            .flow_controls()
-            .find_flow_control_with_producer_address(&prev_hop)
-        {
-            ctx.flow_controls()
-                .add_consumer(next_hop, info.flow_control_id());
+        if mailbox.online && mailbox.subscriber.is_some() && mailbox.messages.is_empty() {
+            let transport_message = message.transport().clone();
+            match forward_local_message(ctx, message, &self.forward_route).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    warn!(
+                        "The target of alias {} is offline, storing its messages: {}",
+                        ctx.address(),
+                        e
+                    );
+                    mailbox.online = false;
+                    mailbox.store(ctx, transport_message).await
+                }
+            }
+        } else {
+            mailbox.store(ctx, message.into_transport_message()).await
+        }
+    }
+}
+
+impl ForwarderMailbox {
+    /// Store a message, and send it right away if the target is online
+    async fn store(&mut self, ctx: &Context, message: TransportMessage) -> Result<()> {
+        if self.messages.store(message).await?.is_some() && self.online {
+            self.send_stored(ctx).await;
         }
+        Ok(())
+    }
+
+    async fn on_request(
+        &mut self,
+        ctx: &Context,
+        request: RelayMailboxRequest,
+        return_route: Route,
+    ) -> Result<()> {
+        match request {
+            RelayMailboxRequest::Drain(delivered) => {
+                if let Some(delivered) = delivered {
+                    self.messages.acknowledge(delivered).await?;
+                }
+                // The messages delivered by a replaced Forwarder are not in this mailbox
+                let delivered = self.messages.sequence(delivered);
+                if !self.online || self.subscriber.as_ref() != Some(&return_route) {
+                    debug!("The target of the relay {} is online", self.messages.name());
+                    // The stored messages which were not acknowledged are sent again
+                    self.sent = None;
+                }
+                self.sent = self.sent.max(delivered);
+                self.subscriber = Some(return_route);
+                self.online = true;
+                self.refreshed = true;
+                self.send_stored(ctx).await;
+                Ok(())
+            }
+            RelayMailboxRequest::Acknowledge(position) => self.messages.acknowledge(position).await,
+        }
+    }
+
+    /// Send the stored messages which were not sent yet to the subscriber
+    async fn send_stored(&mut self, ctx: &Context) {
+        let subscriber = match &self.subscriber {
+            Some(subscriber) => subscriber.clone(),
+            None => return,
+        };
+        let res: Result<()> = async {
+            for delivery in self.messages.stored_after(self.sent).await? {
+                let sequence = delivery.position.sequence;
+                if let Ok(prev_hop) = delivery.message.return_route.next() {
+                    allow_replies(ctx, subscriber.next()?, prev_hop);
+                }
+                let msg =
+                    TransportMessage::v1(subscriber.clone(), ctx.address(), delivery.encode()?);
+                ctx.forward(LocalMessage::new(msg, Vec::new())).await?;
+                self.sent = Some(sequence);
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = res {
+            warn!(
+                "The target of the relay {} is offline, keeping its messages: {}",
+                self.messages.name(),
+                e
+            );
+            self.online = false;
+        }
+    }
+
+    /// Mark the target offline if it stopped sending drain requests, and drop the expired messages
+    async fn on_tick(&mut self) -> Result<()> {
+        if self.subscriber.is_some() && self.online && !self.refreshed {
+            warn!(
+                "The target of the relay {} stopped sending heartbeats, storing its messages",
+                self.messages.name()
+            );
+            self.online = false;
+        }
+        self.refreshed = false;
+
+        let expired = self.messages.expire().await?;
+        if expired > 0 {
+            warn!(
+                "Dropped {} expired messages of the relay {}",
+                expired,
+                self.messages.name()
+            );
+        }
+
+        self.tick.schedule(self.messages.heartbeat_timeout()).await
+    }
+}
+
+/// Forward a message received by a Forwarder to the given route
+pub(super) async fn forward_message(
+    ctx: &Context,
+    msg: Routed<Any>,
+    forward_route: &Route,
+) -> Result<()> {
+    let mut message = msg.into_local_message();
+
+    // Remove my address from the onward_route
+    message.transport_mut().onward_route.step()?;
+
+    forward_local_message(ctx, message, forward_route).await
+}
+
+/// Forward a message whose onward route follows the address of the Forwarder
+async fn forward_local_message(
+    ctx: &Context,
+    mut message: LocalMessage,
+    forward_route: &Route,
+) -> Result<()> {
+    let transport_message = message.transport_mut();
+
+    // Prepend forward route
+    transport_message
+        .onward_route
+        .modify()
+        .prepend_route(forward_route.clone());
+
+    let next_hop = transport_message.onward_route.next()?.clone();
+    let prev_hop = transport_message.return_route.next()?.clone();
+    allow_replies(ctx, &next_hop, &prev_hop);
+
+    ctx.forward(message).await
+}
+
+/// Allow the messages to flow between the target of the relay and the sender
+fn allow_replies(ctx: &Context, next_hop: &Address, prev_hop: &Address) {
+    if let Some(info) = ctx
+        .flow_controls()
+        .find_flow_control_with_producer_address(next_hop)
+    {
+        ctx.flow_controls()
+            .add_consumer(prev_hop.clone(), info.flow_control_id());
+    }
 
-        ctx.forward(message).await
+    if let Some(info) = ctx
+        .flow_controls()
+        .find_flow_control_with_producer_address(prev_hop)
+    {
+        ctx.flow_controls()
+            .add_consumer(next_hop.clone(), info.flow_control_id());
     }
 }
@@ -1,18 +1,48 @@
 use crate::forwarding_service::forwarder::Forwarder;
-use crate::{Context, ForwardingServiceOptions};
+use crate::forwarding_service::relay_mailbox::{
+    RelayMailbox, RelayMailboxOptions, RELAY_MAILBOX_PREFIX,
+};
+use crate::forwarding_service::relay_names::{RelayNameClaim, RelayNameLease, RelayNames};
+use crate::forwarding_service::replicas::{
+    ReplicaForwarder, ReplicaRegistrationAddresses, RELAY_REPLICA_PREFIX,
+};
+use crate::{Context, ForwardingServiceOptions, OckamError};
 use core::str::from_utf8;
+use core::time::Duration;
 use ockam_core::compat::boxed::Box;
-use ockam_core::{Address, Any, DenyAll, Result, Routed, Worker};
+use ockam_core::compat::string::ToString;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::Kind;
+use ockam_core::{route, Address, Any, RelayMessage, Result, Route, Routed, Worker};
+use ockam_identity::IdentitySecureChannelLocalInfo;
 use ockam_node::WorkerBuilder;
+use tracing::{info, warn};
+
+/// Number of attempts to start the Forwarder replacing a Forwarder which is stopping
+const FORWARDER_REPLACEMENT_ATTEMPTS: usize = 50;
 
 /// Alias worker to register remote workers under local names.
 ///
 /// To talk with this worker, you can use the
 /// [`RemoteForwarder`](crate::remote::RemoteForwarder) which is a
 /// compatible client for this server.
+///
+/// A relay name is bound to the identity which registered it through a secure channel.
+/// Registering a name which is already held is rejected or replaces the previous Forwarder,
+/// depending on the [`RelayNameTakeover`](crate::RelayNameTakeover) option.
+///
+/// Names registered with the `replica:` prefix can be registered by several nodes.
+/// The messages of each new sender are then sent to the next replica in turn, and the
+/// replicas which stop registering again are dropped.
+///
+/// Names registered with the `mailbox:` prefix request a mailbox, when the service was created
+/// with [`ForwardingServiceOptions::with_relay_mailboxes`]. The messages sent to the name while
+/// its target is offline are then stored, and delivered once it registers again.
 #[non_exhaustive]
 pub struct ForwardingService {
     options: ForwardingServiceOptions,
+    relay_names: RelayNames,
+    replica_registrations: ReplicaRegistrationAddresses,
 }
 
 impl ForwardingService {
@@ -28,12 +58,19 @@ impl ForwardingService {
 
         let service_incoming_access_control = options.service_incoming_access_control.clone();
 
-        let s = Self { options };
+        // The service only sends the registrations of replicas to their Forwarder
+        let replica_registrations = ReplicaRegistrationAddresses::default();
+
+        let s = Self {
+            options,
+            relay_names: RelayNames::default(),
+            replica_registrations: replica_registrations.clone(),
+        };
 
         WorkerBuilder::new(s)
             .with_address(address)
             .with_incoming_access_control_arc(service_incoming_access_control)
-            .with_outgoing_access_control(DenyAll)
+            .with_outgoing_access_control(replica_registrations)
             .start(ctx)
             .await?;
 
@@ -52,32 +89,176 @@ impl Worker for ForwardingService {
         msg: Routed<Self::Message>,
     ) -> Result<()> {
         let forward_route = msg.return_route();
-        let payload = msg.into_transport_message().payload;
-
-        let random_address = Address::random_tagged("Forwarder.service");
+        let identifier = IdentitySecureChannelLocalInfo::find_info(msg.local_message())
+            .ok()
+            .map(|info| info.their_identity_id());
+        let registration =
+            RelayMessage::new(msg.src_addr(), msg.msg_addr(), msg.local_message().clone());
+        let mut local_message = msg.into_local_message();
+        let payload = local_message.transport().payload.clone();
 
         // TODO: assume that the first byte is length, ignore it.
         // We have to improve this actually parse the payload.
-        let address = match payload.get(1..) {
-            Some(address) => match from_utf8(address) {
-                Ok(v) if v != "register" => Address::from_string(v),
-                _ => random_address,
+        let name = match payload.get(1..) {
+            Some(name) => match from_utf8(name) {
+                Ok(v) => match v.strip_prefix(RELAY_REPLICA_PREFIX) {
+                    Some(v) => Some((v.to_string(), true, false)),
+                    None => match v.strip_prefix(RELAY_MAILBOX_PREFIX) {
+                        Some(v) => Some((v.to_string(), false, true)),
+                        None => Some((v.to_string(), false, false)),
+                    },
+                },
+                _ => None,
             },
-            None => random_address,
+            None => None,
         };
 
-        self.options
-            .setup_flow_control_for_forwarder(ctx.flow_controls(), &address);
+        // A random address is never held by another Forwarder
+        let (name, replica, mailbox) = match name {
+            Some((name, replica, mailbox)) if name != "register" => (name, replica, mailbox),
+            _ => {
+                let address = Address::random_tagged("Forwarder.service");
+                return self
+                    .create_forwarder(ctx, address, forward_route, payload, false, None, None)
+                    .await
+                    .map(|_| ());
+            }
+        };
+
+        if let Some(authorization) = &self.options.relay_name_authorization {
+            if !authorization.is_authorized(&name, &registration).await? {
+                warn!(%name, ?identifier, "the relay name can not be claimed by this identity");
+                return Err(OckamError::ForwarderNameNotAuthorized.into());
+            }
+        }
+
+        let claim = self.relay_names.check_claim(
+            &name,
+            identifier.as_ref(),
+            self.options.relay_name_takeover,
+            replica,
+        );
+        let claim = match claim {
+            Ok(claim) => claim,
+            Err(e) => {
+                warn!(%name, ?identifier, "the relay name is already held by another identity");
+                return Err(e);
+            }
+        };
 
-        Forwarder::create(
-            ctx,
-            address,
-            forward_route,
-            payload,
-            self.options.forwarders_incoming_access_control.clone(),
-        )
-        .await?;
+        let address = Address::from_string(&name);
+        match claim {
+            RelayNameClaim::New => {}
+            RelayNameClaim::Replace(previous) => {
+                info!(%name, ?identifier, "replacing the Forwarder of the relay name");
+                // The Forwarder may have been stopped already
+                let _ = ctx.stop_worker(previous).await;
+            }
+            RelayNameClaim::JoinReplicas(registration_address) => {
+                // The Forwarder registers the replica and confirms the registration
+                local_message.transport_mut().onward_route = route![registration_address];
+                return ctx.forward(local_message).await;
+            }
+        }
+
+        let mailbox = match (mailbox, &self.options.relay_mailboxes) {
+            (true, Some(options)) => Some(options),
+            (true, None) => {
+                warn!(%name, "relay mailboxes are not enabled, the messages will not be stored");
+                None
+            }
+            (false, _) => None,
+        };
+
+        // The Forwarder releases the name when it stops
+        let lease = self.relay_names.lease(&name);
+        let replica_registration_address = self
+            .create_forwarder(
+                ctx,
+                address.clone(),
+                forward_route,
+                payload,
+                replica,
+                mailbox,
+                Some(lease.clone()),
+            )
+            .await?;
+        if let Some(registration_address) = &replica_registration_address {
+            self.replica_registrations
+                .insert(registration_address.clone());
+        }
+        let replaced_registration_address =
+            self.relay_names
+                .claim(&lease, identifier, address, replica_registration_address);
+        if let Some(registration_address) = replaced_registration_address {
+            self.replica_registrations.remove(&registration_address);
+        }
 
         Ok(())
     }
 }
+
+impl ForwardingService {
+    /// Create a Forwarder, waiting for a replaced Forwarder using the same address to stop.
+    /// Return the address receiving the registrations of the replicas, for a replicated name
+    #[allow(clippy::too_many_arguments)]
+    async fn create_forwarder(
+        &self,
+        ctx: &Context,
+        address: Address,
+        forward_route: Route,
+        payload: Vec<u8>,
+        replica: bool,
+        mailbox: Option<&RelayMailboxOptions>,
+        lease: Option<RelayNameLease>,
+    ) -> Result<Option<Address>> {
+        self.options
+            .setup_flow_control_for_forwarder(ctx.flow_controls(), &address);
+
+        let mut attempts = 0;
+        loop {
+            let res = match (replica, lease.clone()) {
+                (true, Some(lease)) => ReplicaForwarder::create(
+                    ctx,
+                    address.clone(),
+                    ctx.address(),
+                    forward_route.clone(),
+                    payload.clone(),
+                    self.options.forwarders_incoming_access_control.clone(),
+                    self.options.replica_heartbeat_timeout,
+                    lease,
+                    self.replica_registrations.clone(),
+                )
+                .await
+                .map(Some),
+                _ => Forwarder::create(
+                    ctx,
+                    address.clone(),
+                    forward_route.clone(),
+                    payload.clone(),
+                    self.options.forwarders_incoming_access_control.clone(),
+                    mailbox.map(|options| RelayMailbox::new(address.address(), options.clone())),
+                    lease.clone(),
+                )
+                .await
+                .map(|_| None),
+            };
+            match res {
+                Err(e) if e.code().kind == Kind::AlreadyExists => {
+                    attempts += 1;
+                    if attempts >= FORWARDER_REPLACEMENT_ATTEMPTS {
+                        return Err(e);
+                    }
+                    ctx.sleep(Duration::from_millis(20)).await;
+                }
+                res => {
+                    // The flow control of the address is cleaned up when a replaced
+                    // Forwarder stops, so it is set up again once it is replaced
+                    self.options
+                        .setup_flow_control_for_forwarder(ctx.flow_controls(), &address);
+                    return res;
+                }
+            }
+        }
+    }
+}
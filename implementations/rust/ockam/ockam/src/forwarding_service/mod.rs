@@ -2,6 +2,21 @@ mod forwarder;
 #[allow(clippy::module_inception)]
 mod forwarding_service;
 mod options;
+mod relay_mailbox;
+mod relay_names;
+mod replicas;
 
 pub use forwarding_service::*;
 pub use options::*;
+pub use relay_mailbox::{
+    RelayMailboxOptions, RelayMailboxStorage, StoredRelayMessage,
+    DEFAULT_RELAY_MAILBOX_HEARTBEAT_TIMEOUT, DEFAULT_RELAY_MAILBOX_MAX_BYTES,
+    DEFAULT_RELAY_MAILBOX_MAX_MESSAGES, DEFAULT_RELAY_MAILBOX_TTL,
+};
+pub use relay_names::{RelayNameAuthorization, RelayNameTakeover};
+pub use replicas::DEFAULT_REPLICA_HEARTBEAT_TIMEOUT;
+
+pub(crate) use relay_mailbox::{
+    RelayMailboxDelivery, RelayMailboxPosition, RelayMailboxRequest, RELAY_MAILBOX_PREFIX,
+};
+pub(crate) use replicas::RELAY_REPLICA_PREFIX;
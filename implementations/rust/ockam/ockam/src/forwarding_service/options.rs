@@ -1,3 +1,7 @@
+use crate::forwarding_service::relay_mailbox::RelayMailboxOptions;
+use crate::forwarding_service::relay_names::{RelayNameAuthorization, RelayNameTakeover};
+use crate::forwarding_service::replicas::DEFAULT_REPLICA_HEARTBEAT_TIMEOUT;
+use core::time::Duration;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::flow_control::{FlowControlId, FlowControls};
@@ -9,6 +13,10 @@ pub struct ForwardingServiceOptions {
     pub(super) forwarders_incoming_access_control: Arc<dyn IncomingAccessControl>,
     pub(super) consumer_service: Vec<FlowControlId>,
     pub(super) consumer_forwarder: Vec<FlowControlId>,
+    pub(super) relay_name_authorization: Option<Arc<dyn RelayNameAuthorization>>,
+    pub(super) relay_name_takeover: RelayNameTakeover,
+    pub(super) replica_heartbeat_timeout: Duration,
+    pub(super) relay_mailboxes: Option<RelayMailboxOptions>,
 }
 
 impl ForwardingServiceOptions {
@@ -19,6 +27,10 @@ impl ForwardingServiceOptions {
             forwarders_incoming_access_control: Arc::new(AllowAll),
             consumer_service: vec![],
             consumer_forwarder: vec![],
+            relay_name_authorization: None,
+            relay_name_takeover: RelayNameTakeover::default(),
+            replica_heartbeat_timeout: DEFAULT_REPLICA_HEARTBEAT_TIMEOUT,
+            relay_mailboxes: None,
         }
     }
 
@@ -72,6 +84,36 @@ impl ForwardingServiceOptions {
         self
     }
 
+    /// Check which senders may claim each relay name. All the names can be claimed by default
+    pub fn with_relay_name_authorization(
+        mut self,
+        authorization: Arc<dyn RelayNameAuthorization>,
+    ) -> Self {
+        self.relay_name_authorization = Some(authorization);
+        self
+    }
+
+    /// Set what happens when a relay name is registered while it is already held
+    pub fn with_relay_name_takeover(mut self, takeover: RelayNameTakeover) -> Self {
+        self.relay_name_takeover = takeover;
+        self
+    }
+
+    /// Set the duration after which a replica of a relay name which did not register again
+    /// is dropped. Replicas are dropped between one and two times this duration after their
+    /// last registration
+    pub fn with_replica_heartbeat_timeout(mut self, timeout: Duration) -> Self {
+        self.replica_heartbeat_timeout = timeout;
+        self
+    }
+
+    /// Store the messages sent to named relays while their target is offline, and deliver
+    /// them when it registers again. Messages are only stored for targets which requested it
+    pub fn with_relay_mailboxes(mut self, options: RelayMailboxOptions) -> Self {
+        self.relay_mailboxes = Some(options);
+        self
+    }
+
     pub(super) fn setup_flow_control_for_forwarding_service(
         &self,
         flow_controls: &FlowControls,
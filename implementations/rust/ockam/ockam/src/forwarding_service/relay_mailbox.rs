@@ -0,0 +1,461 @@
+use crate::Message;
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::VecDeque;
+use ockam_core::compat::rand::random;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Decodable, Encodable, Result, TransportMessage};
+use ockam_identity::storage::Storage;
+use ockam_identity::utils::now;
+use ockam_node::{InMemoryKeyValueStorage, KeyValueStorage};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Registrations whose name starts with this prefix request a mailbox storing the
+/// messages sent to the relay name while its target is offline
+pub(crate) const RELAY_MAILBOX_PREFIX: &str = "mailbox:";
+
+/// Default maximum number of messages stored for a relay name
+pub const DEFAULT_RELAY_MAILBOX_MAX_MESSAGES: usize = 1000;
+
+/// Default maximum number of payload bytes stored for a relay name
+pub const DEFAULT_RELAY_MAILBOX_MAX_BYTES: usize = 1024 * 1024;
+
+/// Default duration after which a stored message is dropped
+pub const DEFAULT_RELAY_MAILBOX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Default duration after which a relay target which stopped sending heartbeats is
+/// considered offline
+pub const DEFAULT_RELAY_MAILBOX_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Options of the mailboxes storing the messages sent to offline relay targets.
+///
+/// The messages of each relay name are stored in order, until the target registers again
+/// and acknowledges them. When a mailbox is full, new messages are dropped
+#[derive(Clone)]
+pub struct RelayMailboxOptions {
+    pub(super) storage: Arc<dyn KeyValueStorage<String, StoredRelayMessage>>,
+    pub(super) max_messages: usize,
+    pub(super) max_bytes: usize,
+    pub(super) ttl: Duration,
+    pub(super) heartbeat_timeout: Duration,
+}
+
+impl RelayMailboxOptions {
+    /// Store the messages in the given storage, which can be persistent
+    pub fn new(storage: Arc<dyn KeyValueStorage<String, StoredRelayMessage>>) -> Self {
+        Self {
+            storage,
+            max_messages: DEFAULT_RELAY_MAILBOX_MAX_MESSAGES,
+            max_bytes: DEFAULT_RELAY_MAILBOX_MAX_BYTES,
+            ttl: DEFAULT_RELAY_MAILBOX_TTL,
+            heartbeat_timeout: DEFAULT_RELAY_MAILBOX_HEARTBEAT_TIMEOUT,
+        }
+    }
+
+    /// Store the messages in memory, they are lost when the node stops.
+    /// This is mostly useful for tests, see [`RelayMailboxStorage`] for a persistent storage
+    pub fn in_memory() -> Self {
+        Self::new(InMemoryKeyValueStorage::create())
+    }
+
+    /// Set the maximum number of messages stored for each relay name
+    pub fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = max_messages;
+        self
+    }
+
+    /// Set the maximum number of payload bytes stored for each relay name
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Set the duration after which a stored message is dropped
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the duration after which a target which stopped sending heartbeats is
+    /// considered offline
+    pub fn with_heartbeat_timeout(mut self, heartbeat_timeout: Duration) -> Self {
+        self.heartbeat_timeout = heartbeat_timeout;
+        self
+    }
+}
+
+/// Message stored in a relay mailbox, with the routes it had after the Forwarder
+#[derive(Clone, Debug, Serialize, Deserialize, Message, PartialEq, Eq)]
+pub struct StoredRelayMessage {
+    message: TransportMessage,
+    stored_at: u64,
+}
+
+/// Key of the stored relay messages in a [`Storage`]
+const RELAY_MAILBOX_KEY: &str = "relay_mailbox";
+
+/// Storage of the relay mailboxes backed by a [`Storage`], for example an LMDB database,
+/// so that the stored messages are kept when the node restarts
+pub struct RelayMailboxStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl RelayMailboxStorage {
+    /// Store the relay messages in the given storage
+    pub fn create(storage: Arc<dyn Storage>) -> Arc<Self> {
+        Arc::new(Self { storage })
+    }
+}
+
+#[async_trait]
+impl KeyValueStorage<String, StoredRelayMessage> for RelayMailboxStorage {
+    async fn put(&self, key: String, value: StoredRelayMessage) -> Result<()> {
+        self.storage
+            .set(&key, RELAY_MAILBOX_KEY.to_string(), value.encode()?)
+            .await
+    }
+
+    async fn get(&self, key: &String) -> Result<Option<StoredRelayMessage>> {
+        self.storage
+            .get(key, RELAY_MAILBOX_KEY)
+            .await?
+            .map(|value| StoredRelayMessage::decode(&value))
+            .transpose()
+    }
+
+    async fn delete(&self, key: &String) -> Result<Option<StoredRelayMessage>> {
+        let value = self.get(key).await?;
+        self.storage.del(key, RELAY_MAILBOX_KEY).await?;
+        Ok(value)
+    }
+
+    async fn keys(&self) -> Result<Vec<String>> {
+        self.storage.keys(RELAY_MAILBOX_KEY).await
+    }
+}
+
+/// Position of a stored message in a relay mailbox.
+///
+/// The sequence numbers start again when the mailbox of a relay name is created again,
+/// for example when its Forwarder is replaced, so each mailbox has its own random epoch
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct RelayMailboxPosition {
+    pub(crate) epoch: u64,
+    pub(crate) sequence: u64,
+}
+
+/// Request sent by a [`RemoteForwarder`](crate::remote::RemoteForwarder) to its Forwarder
+#[derive(Clone, Debug, Serialize, Deserialize, Message, PartialEq, Eq)]
+pub(crate) enum RelayMailboxRequest {
+    /// The target is online and wants the stored messages. This request is sent again
+    /// periodically as a heartbeat, with the position of the last delivered message
+    Drain(Option<RelayMailboxPosition>),
+    /// The stored messages were delivered, up to this position
+    Acknowledge(RelayMailboxPosition),
+}
+
+/// Stored message sent by a Forwarder to its [`RemoteForwarder`](crate::remote::RemoteForwarder)
+#[derive(Clone, Debug, Serialize, Deserialize, Message, PartialEq, Eq)]
+pub(crate) struct RelayMailboxDelivery {
+    pub(crate) position: RelayMailboxPosition,
+    pub(crate) message: TransportMessage,
+}
+
+/// Messages stored for a relay name, in the order they were received
+pub(super) struct RelayMailbox {
+    name: String,
+    options: RelayMailboxOptions,
+    // sequence number, payload size and storage time of the stored messages
+    index: VecDeque<(u64, usize, u64)>,
+    bytes: usize,
+    next_sequence: u64,
+    epoch: u64,
+}
+
+impl RelayMailbox {
+    /// Create the mailbox of a relay name, the stored messages are read by [`Self::load`]
+    pub(super) fn new(name: &str, options: RelayMailboxOptions) -> Self {
+        Self {
+            name: name.to_string(),
+            options,
+            index: VecDeque::new(),
+            bytes: 0,
+            next_sequence: 0,
+            epoch: random(),
+        }
+    }
+
+    /// Load the messages stored for the relay name
+    pub(super) async fn load(&mut self) -> Result<()> {
+        let prefix = Self::prefix(&self.name);
+        let mut keys: Vec<(u64, String)> = self
+            .options
+            .storage
+            .keys()
+            .await?
+            .into_iter()
+            .filter_map(|key| {
+                let sequence = key.strip_prefix(&prefix)?.parse().ok()?;
+                Some((sequence, key))
+            })
+            .collect();
+        keys.sort();
+
+        self.index.clear();
+        self.bytes = 0;
+        for (sequence, key) in keys {
+            if let Some(stored) = self.options.storage.get(&key).await? {
+                let size = stored.message.payload.len();
+                self.index.push_back((sequence, size, stored.stored_at));
+                self.bytes += size;
+            }
+            self.next_sequence = sequence + 1;
+        }
+        Ok(())
+    }
+
+    fn prefix(name: &str) -> String {
+        format!("{name}/")
+    }
+
+    fn key(&self, sequence: u64) -> String {
+        // the sequence is padded so that the keys are sorted like the sequence numbers
+        format!("{}{:020}", Self::prefix(&self.name), sequence)
+    }
+
+    /// Return true if no message is stored
+    pub(super) fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Store a message and return its sequence number, or None if the mailbox is full
+    pub(super) async fn store(&mut self, message: TransportMessage) -> Result<Option<u64>> {
+        let size = message.payload.len();
+        if self.index.len() >= self.options.max_messages
+            || self.bytes + size > self.options.max_bytes
+        {
+            warn!(
+                "The mailbox of the relay {} is full, dropping the message",
+                self.name
+            );
+            return Ok(None);
+        }
+
+        let sequence = self.next_sequence;
+        let stored_at = now().map(|t| t.0).unwrap_or_default();
+        self.options
+            .storage
+            .put(
+                self.key(sequence),
+                StoredRelayMessage { message, stored_at },
+            )
+            .await?;
+        self.next_sequence += 1;
+        self.index.push_back((sequence, size, stored_at));
+        self.bytes += size;
+        Ok(Some(sequence))
+    }
+
+    /// Return the sequence number of a position in this mailbox.
+    /// The positions in the mailboxes previously created for the same name are ignored
+    pub(super) fn sequence(&self, position: Option<RelayMailboxPosition>) -> Option<u64> {
+        position
+            .filter(|p| p.epoch == self.epoch)
+            .map(|p| p.sequence)
+    }
+
+    /// Return the stored messages following a sequence number, in order
+    pub(super) async fn stored_after(
+        &self,
+        after: Option<u64>,
+    ) -> Result<Vec<RelayMailboxDelivery>> {
+        let mut deliveries = vec![];
+        for (sequence, _, _) in &self.index {
+            if after.map(|a| *sequence <= a).unwrap_or(false) {
+                continue;
+            }
+            if let Some(stored) = self.options.storage.get(&self.key(*sequence)).await? {
+                deliveries.push(RelayMailboxDelivery {
+                    position: RelayMailboxPosition {
+                        epoch: self.epoch,
+                        sequence: *sequence,
+                    },
+                    message: stored.message,
+                });
+            }
+        }
+        Ok(deliveries)
+    }
+
+    /// Delete the messages delivered up to a position in this mailbox
+    pub(super) async fn acknowledge(&mut self, position: RelayMailboxPosition) -> Result<()> {
+        let sequence = match self.sequence(Some(position)) {
+            Some(sequence) => sequence,
+            None => return Ok(()),
+        };
+        while let Some((first, _, _)) = self.index.front() {
+            if *first > sequence {
+                break;
+            }
+            self.remove_first().await?;
+        }
+        Ok(())
+    }
+
+    /// Delete the messages stored for longer than the TTL, and return their number
+    pub(super) async fn expire(&mut self) -> Result<usize> {
+        let now = match now() {
+            Ok(now) => now.0,
+            // the messages can not expire without a clock
+            Err(_) => return Ok(0),
+        };
+        let ttl = self.options.ttl.as_secs();
+        let mut expired = 0;
+        while let Some((_, _, stored_at)) = self.index.front() {
+            if stored_at.saturating_add(ttl) > now {
+                break;
+            }
+            self.remove_first().await?;
+            expired += 1;
+        }
+        Ok(expired)
+    }
+
+    async fn remove_first(&mut self) -> Result<()> {
+        if let Some((sequence, size, _)) = self.index.pop_front() {
+            self.bytes -= size;
+            self.options.storage.delete(&self.key(sequence)).await?;
+        }
+        Ok(())
+    }
+
+    /// Name of the relay
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(super) fn heartbeat_timeout(&self) -> Duration {
+        self.options.heartbeat_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+    use ockam_core::route;
+    use ockam_identity::storage::InMemoryStorage;
+
+    fn message(payload: &[u8]) -> TransportMessage {
+        TransportMessage::v1(route!["echoer"], route!["client"], payload.to_vec())
+    }
+
+    #[ockam_macros::test]
+    async fn test_relay_mailbox(ctx: &mut Context) -> Result<()> {
+        let options = RelayMailboxOptions::in_memory()
+            .with_max_messages(3)
+            .with_max_bytes(10);
+        let mut mailbox = RelayMailbox::new("db", options.clone());
+        mailbox.load().await?;
+        assert_eq!(mailbox.store(message(b"1")).await?, Some(0));
+        assert_eq!(mailbox.store(message(b"2")).await?, Some(1));
+        assert_eq!(mailbox.store(message(&[0; 9])).await?, None);
+        assert_eq!(mailbox.store(message(b"3")).await?, Some(2));
+        assert_eq!(mailbox.store(message(b"4")).await?, None);
+
+        let stored = mailbox.stored_after(Some(0)).await?;
+        assert_eq!(
+            stored
+                .iter()
+                .map(|d| d.position.sequence)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        // the messages are loaded again from the storage
+        mailbox.acknowledge(stored[0].position).await?;
+        let mut mailbox = RelayMailbox::new("db", options.clone());
+        mailbox.load().await?;
+        let stored = mailbox.stored_after(None).await?;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].message.payload, b"3".to_vec());
+        assert_eq!(mailbox.store(message(b"4")).await?, Some(3));
+
+        // the mailboxes of other names are independent
+        let mut other = RelayMailbox::new("db2", options);
+        other.load().await?;
+        assert!(other.is_empty());
+
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_relay_mailbox_storage(ctx: &mut Context) -> Result<()> {
+        let storage = InMemoryStorage::create();
+        let options = RelayMailboxOptions::new(RelayMailboxStorage::create(storage.clone()));
+        let mut mailbox = RelayMailbox::new("mailbox:db", options);
+        mailbox.load().await?;
+        mailbox.store(message(b"1")).await?;
+        mailbox.store(message(b"2")).await?;
+
+        // the messages are read again from the underlying storage, for example after a restart
+        let options = RelayMailboxOptions::new(RelayMailboxStorage::create(storage));
+        let mut mailbox = RelayMailbox::new("mailbox:db", options);
+        mailbox.load().await?;
+        let stored = mailbox.stored_after(None).await?;
+        assert_eq!(
+            stored
+                .iter()
+                .map(|d| d.message.payload.clone())
+                .collect::<Vec<_>>(),
+            vec![b"1".to_vec(), b"2".to_vec()]
+        );
+        mailbox.acknowledge(stored[1].position).await?;
+        assert!(mailbox.is_empty());
+
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_relay_mailbox_ttl(ctx: &mut Context) -> Result<()> {
+        let options = RelayMailboxOptions::in_memory().with_ttl(Duration::ZERO);
+        let mut mailbox = RelayMailbox::new("db", options);
+        mailbox.store(message(b"1")).await?;
+        assert_eq!(mailbox.expire().await?, 1);
+        assert!(mailbox.is_empty());
+
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_relay_mailbox_created_again(ctx: &mut Context) -> Result<()> {
+        let options = RelayMailboxOptions::in_memory();
+        let mut mailbox = RelayMailbox::new("db", options.clone());
+        mailbox.load().await?;
+        mailbox.store(message(b"1")).await?;
+        mailbox.store(message(b"2")).await?;
+        let delivered = mailbox.stored_after(None).await?[1].position;
+        mailbox.acknowledge(delivered).await?;
+        assert!(mailbox.is_empty());
+
+        // the Forwarder is replaced, and its sequence numbers start again
+        let mut mailbox = RelayMailbox::new("db", options);
+        mailbox.load().await?;
+        assert_eq!(mailbox.store(message(b"3")).await?, Some(0));
+
+        // the position delivered by the previous Forwarder doesn't apply to the new messages
+        assert_eq!(mailbox.sequence(Some(delivered)), None);
+        mailbox.acknowledge(delivered).await?;
+        let stored = mailbox
+            .stored_after(mailbox.sequence(Some(delivered)))
+            .await?;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].message.payload, b"3".to_vec());
+        assert_ne!(stored[0].position, delivered);
+
+        ctx.stop().await
+    }
+}
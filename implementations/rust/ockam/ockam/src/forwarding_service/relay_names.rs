@@ -0,0 +1,325 @@
+use crate::OckamError;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::{async_trait, Address, RelayMessage, Result};
+use ockam_identity::Identifier;
+
+/// Decides which senders may claim a relay name
+#[async_trait]
+pub trait RelayNameAuthorization: Send + Sync + 'static {
+    /// Return true if the sender of the registration message can claim the relay name.
+    /// When the registration is sent through a secure channel, the message contains
+    /// the identifier of the registering identity
+    async fn is_authorized(&self, name: &str, registration: &RelayMessage) -> Result<bool>;
+}
+
+/// What happens when a relay name is registered while another Forwarder holds it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RelayNameTakeover {
+    /// The identity holding the name can register it again, for example after
+    /// reconnecting, and its previous Forwarder is replaced. Other identities are rejected,
+    /// and so are the registrations sent without a secure channel, until the Forwarder stops
+    #[default]
+    SameIdentity,
+    /// Any sender authorized to claim the name takes it over
+    Authorized,
+    /// The name can not be registered again while it is held
+    Never,
+}
+
+/// Relay names registered with a Forwarding service, and the identity holding each of them.
+/// Names registered without a secure channel have no identity.
+///
+/// The names are shared with the Forwarders, which release their name when they stop
+#[derive(Clone, Default)]
+pub(super) struct RelayNames {
+    state: Arc<Mutex<RelayNamesState>>,
+}
+
+#[derive(Default)]
+struct RelayNamesState {
+    names: BTreeMap<String, RelayNameOwner>,
+    // identifies each claim, so that a replaced Forwarder doesn't release the name
+    // of the Forwarder replacing it
+    next_claim: u64,
+}
+
+struct RelayNameOwner {
+    claim: u64,
+    identifier: Option<Identifier>,
+    forwarder_address: Address,
+    // address receiving the registrations of the replicas, if the name is replicated
+    replica_registration_address: Option<Address>,
+}
+
+/// Outcome of a valid claim of a relay name
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum RelayNameClaim {
+    /// The name is not held yet
+    New,
+    /// The Forwarder at the given address must be replaced
+    Replace(Address),
+    /// The claim adds a replica to the name, or refreshes it, by sending the registration
+    /// to the given address
+    JoinReplicas(Address),
+}
+
+impl RelayNames {
+    /// Check that a name can be claimed by an identity, as a single Forwarder or as a replica.
+    ///
+    /// The identity holding a replicated name can always add replicas, and other identities
+    /// can only add replicas when takeovers are authorized. A sender without identity never
+    /// holds a name
+    pub(super) fn check_claim(
+        &self,
+        name: &str,
+        identifier: Option<&Identifier>,
+        takeover: RelayNameTakeover,
+        replica: bool,
+    ) -> Result<RelayNameClaim> {
+        let state = self.state.lock().unwrap();
+        let owner = match state.names.get(name) {
+            Some(owner) => owner,
+            None => return Ok(RelayNameClaim::New),
+        };
+        let same_identity = identifier.is_some() && owner.identifier.as_ref() == identifier;
+
+        if let (true, Some(address)) = (replica, &owner.replica_registration_address) {
+            return if same_identity || takeover == RelayNameTakeover::Authorized {
+                Ok(RelayNameClaim::JoinReplicas(address.clone()))
+            } else {
+                Err(OckamError::ForwarderNameAlreadyClaimed.into())
+            };
+        }
+
+        let allowed = match takeover {
+            RelayNameTakeover::SameIdentity => same_identity,
+            RelayNameTakeover::Authorized => true,
+            RelayNameTakeover::Never => false,
+        };
+        if allowed {
+            Ok(RelayNameClaim::Replace(owner.forwarder_address.clone()))
+        } else {
+            Err(OckamError::ForwarderNameAlreadyClaimed.into())
+        }
+    }
+
+    /// Return the lease of a name, given to the Forwarder created for a claim.
+    /// The name is claimed once the Forwarder is started
+    pub(super) fn lease(&self, name: &str) -> RelayNameLease {
+        let mut state = self.state.lock().unwrap();
+        state.next_claim += 1;
+        RelayNameLease {
+            names: self.clone(),
+            name: name.to_string(),
+            claim: state.next_claim,
+        }
+    }
+
+    /// Record the identity holding a name, and return the address receiving the registrations
+    /// of the replicas of the replaced Forwarder, if any
+    pub(super) fn claim(
+        &self,
+        lease: &RelayNameLease,
+        identifier: Option<Identifier>,
+        forwarder_address: Address,
+        replica_registration_address: Option<Address>,
+    ) -> Option<Address> {
+        self.state
+            .lock()
+            .unwrap()
+            .names
+            .insert(
+                lease.name.clone(),
+                RelayNameOwner {
+                    claim: lease.claim,
+                    identifier,
+                    forwarder_address,
+                    replica_registration_address,
+                },
+            )
+            .and_then(|previous| previous.replica_registration_address)
+    }
+
+    /// Return the identity holding a name
+    #[cfg(test)]
+    pub(super) fn owner(&self, name: &str) -> Option<Option<Identifier>> {
+        self.state
+            .lock()
+            .unwrap()
+            .names
+            .get(name)
+            .map(|o| o.identifier.clone())
+    }
+}
+
+/// Claim of a relay name by a Forwarder, released when the Forwarder stops
+#[derive(Clone)]
+pub(super) struct RelayNameLease {
+    names: RelayNames,
+    name: String,
+    claim: u64,
+}
+
+impl RelayNameLease {
+    /// Release the name, unless it was claimed again by another Forwarder
+    pub(super) fn release(&self) {
+        let mut state = self.names.state.lock().unwrap();
+        if state.names.get(&self.name).map(|o| o.claim) == Some(self.claim) {
+            state.names.remove(&self.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_name_takeover() {
+        let alice = Identifier([1; 20]);
+        let bob = Identifier([2; 20]);
+        let names = RelayNames::default();
+        let claim = |names: &RelayNames, identifier, takeover| {
+            names.check_claim("db", identifier, takeover, false)
+        };
+
+        assert_eq!(
+            claim(&names, Some(&alice), RelayNameTakeover::SameIdentity).unwrap(),
+            RelayNameClaim::New
+        );
+        names.claim(
+            &names.lease("db"),
+            Some(alice.clone()),
+            Address::from_string("db"),
+            None,
+        );
+        assert_eq!(names.owner("db"), Some(Some(alice.clone())));
+
+        // the same identity replaces its forwarder
+        assert_eq!(
+            claim(&names, Some(&alice), RelayNameTakeover::SameIdentity).unwrap(),
+            RelayNameClaim::Replace(Address::from_string("db"))
+        );
+
+        // another identity, or no identity, is rejected
+        assert!(claim(&names, Some(&bob), RelayNameTakeover::SameIdentity).is_err());
+        assert!(claim(&names, None, RelayNameTakeover::SameIdentity).is_err());
+        assert!(claim(&names, Some(&alice), RelayNameTakeover::Never).is_err());
+
+        // unless takeovers are allowed
+        assert!(claim(&names, Some(&bob), RelayNameTakeover::Authorized).is_ok());
+    }
+
+    #[test]
+    fn test_relay_name_replicas() {
+        let alice = Identifier([1; 20]);
+        let bob = Identifier([2; 20]);
+        let registration = Address::from_string("registration");
+        let names = RelayNames::default();
+        names.claim(
+            &names.lease("db"),
+            Some(alice.clone()),
+            Address::from_string("db"),
+            Some(registration.clone()),
+        );
+
+        // the identity holding the name adds replicas, even when takeovers are forbidden
+        assert_eq!(
+            names
+                .check_claim("db", Some(&alice), RelayNameTakeover::Never, true)
+                .unwrap(),
+            RelayNameClaim::JoinReplicas(registration.clone())
+        );
+
+        // other identities only add replicas when takeovers are authorized
+        assert!(names
+            .check_claim("db", Some(&bob), RelayNameTakeover::SameIdentity, true)
+            .is_err());
+        assert_eq!(
+            names
+                .check_claim("db", Some(&bob), RelayNameTakeover::Authorized, true)
+                .unwrap(),
+            RelayNameClaim::JoinReplicas(registration.clone())
+        );
+
+        // a single forwarder replaces all the replicas
+        assert_eq!(
+            names
+                .check_claim("db", Some(&alice), RelayNameTakeover::SameIdentity, false)
+                .unwrap(),
+            RelayNameClaim::Replace(Address::from_string("db"))
+        );
+        assert_eq!(
+            names.claim(
+                &names.lease("db"),
+                Some(alice),
+                Address::from_string("db"),
+                None
+            ),
+            Some(registration)
+        );
+    }
+
+    #[test]
+    fn test_unauthenticated_relay_names() {
+        let names = RelayNames::default();
+        names.claim(&names.lease("db"), None, Address::from_string("db"), None);
+        names.claim(
+            &names.lease("replicated"),
+            None,
+            Address::from_string("replicated"),
+            Some(Address::from_string("registration")),
+        );
+
+        // a name registered without a secure channel can't be taken over without one
+        assert!(names
+            .check_claim("db", None, RelayNameTakeover::SameIdentity, false)
+            .is_err());
+        assert!(names
+            .check_claim("replicated", None, RelayNameTakeover::SameIdentity, true)
+            .is_err());
+
+        // nor by an identity
+        let alice = Identifier([1; 20]);
+        assert!(names
+            .check_claim("db", Some(&alice), RelayNameTakeover::SameIdentity, false)
+            .is_err());
+
+        // unless takeovers are allowed
+        assert!(names
+            .check_claim("db", None, RelayNameTakeover::Authorized, false)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_relay_names_are_released() {
+        let alice = Identifier([1; 20]);
+        let names = RelayNames::default();
+        let first = names.lease("db");
+        names.claim(
+            &first,
+            Some(alice.clone()),
+            Address::from_string("db"),
+            None,
+        );
+
+        // the name is claimed again, then the replaced Forwarder stops
+        let second = names.lease("db");
+        names.claim(&second, Some(alice), Address::from_string("db"), None);
+        first.release();
+        assert!(names.owner("db").is_some());
+
+        // the name can be claimed by anyone once its Forwarder stops
+        second.release();
+        assert_eq!(names.owner("db"), None);
+        assert_eq!(
+            names
+                .check_claim("db", None, RelayNameTakeover::Never, false)
+                .unwrap(),
+            RelayNameClaim::New
+        );
+    }
+}
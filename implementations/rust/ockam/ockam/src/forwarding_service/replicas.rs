@@ -0,0 +1,407 @@
+use crate::forwarding_service::forwarder::forward_message;
+use crate::forwarding_service::relay_names::RelayNameLease;
+use crate::Context;
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::{BTreeMap, BTreeSet};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use ockam_core::{
+    async_trait, Address, AllowSourceAddress, Any, DenyAll, IncomingAccessControl, LocalMessage,
+    Mailbox, Mailboxes, OutgoingAccessControl, RelayMessage, Result, Route, Routed,
+    TransportMessage, Worker,
+};
+use ockam_node::{DelayedEvent, WorkerBuilder};
+use tracing::{debug, info, warn};
+
+/// Registrations whose name starts with this prefix add a replica to the relay name,
+/// instead of replacing its Forwarder
+pub(crate) const RELAY_REPLICA_PREFIX: &str = "replica:";
+
+/// Default duration after which a replica which did not register again is dropped
+pub const DEFAULT_REPLICA_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Replicas registered under the same relay name.
+///
+/// New senders are assigned the replicas in turn, and keep sending their messages
+/// to the same replica while it is registered
+#[derive(Default)]
+pub(super) struct Replicas {
+    replicas: Vec<Replica>,
+    sessions: BTreeMap<Route, Session>,
+    next: usize,
+    // Forward routes of the replicas, shared with the outgoing access control of the Forwarder
+    forward_routes: Arc<Mutex<Vec<Route>>>,
+}
+
+struct Replica {
+    registration_route: Route,
+    forward_route: Route,
+    refreshed: bool,
+}
+
+struct Session {
+    registration_route: Route,
+    used: bool,
+}
+
+impl Replicas {
+    /// Add a replica, or refresh it when it registers again. Return true for a new replica
+    pub(super) fn register(&mut self, registration_route: Route) -> bool {
+        if let Some(replica) = self
+            .replicas
+            .iter_mut()
+            .find(|r| r.registration_route == registration_route)
+        {
+            replica.refreshed = true;
+            return false;
+        }
+
+        // Remove the last hop so that just route to the node itself is left
+        let mut forward_route = registration_route.clone();
+        forward_route.modify().pop_back();
+
+        self.replicas.push(Replica {
+            registration_route,
+            forward_route,
+            refreshed: true,
+        });
+        self.update_forward_routes();
+        true
+    }
+
+    /// Return the forward route of the replica handling the messages of a sender
+    pub(super) fn select(&mut self, return_route: &Route) -> Option<Route> {
+        if let Some(session) = self.sessions.get_mut(return_route) {
+            if let Some(replica) = self
+                .replicas
+                .iter()
+                .find(|r| r.registration_route == session.registration_route)
+            {
+                session.used = true;
+                return Some(replica.forward_route.clone());
+            }
+        }
+
+        if self.replicas.is_empty() {
+            return None;
+        }
+        let replica = &self.replicas[self.next % self.replicas.len()];
+        self.next = self.next.wrapping_add(1);
+        self.sessions.insert(
+            return_route.clone(),
+            Session {
+                registration_route: replica.registration_route.clone(),
+                used: true,
+            },
+        );
+        Some(replica.forward_route.clone())
+    }
+
+    /// Drop the replicas which did not register again, and the sessions which were not used,
+    /// since the previous call. Return the number of dropped replicas
+    pub(super) fn expire(&mut self) -> usize {
+        let before = self.replicas.len();
+        self.replicas.retain(|r| r.refreshed);
+        self.replicas.iter_mut().for_each(|r| r.refreshed = false);
+
+        let replicas = &self.replicas;
+        self.sessions.retain(|_, s| {
+            s.used
+                && replicas
+                    .iter()
+                    .any(|r| r.registration_route == s.registration_route)
+        });
+        self.sessions.values_mut().for_each(|s| s.used = false);
+
+        let dropped = before - self.replicas.len();
+        if dropped > 0 {
+            self.update_forward_routes();
+        }
+        dropped
+    }
+
+    /// Number of registered replicas
+    pub(super) fn len(&self) -> usize {
+        self.replicas.len()
+    }
+
+    /// Return true if no replica is registered
+    pub(super) fn is_empty(&self) -> bool {
+        self.replicas.is_empty()
+    }
+
+    fn update_forward_routes(&self) {
+        *self.forward_routes.lock().unwrap() = self
+            .replicas
+            .iter()
+            .map(|r| r.forward_route.clone())
+            .collect();
+    }
+}
+
+/// Allow the messages sent to the replicas of a relay name
+#[derive(Debug)]
+struct AllowReplicas(Arc<Mutex<Vec<Route>>>);
+
+#[async_trait]
+impl OutgoingAccessControl for AllowReplicas {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        let next_hop = relay_msg.onward_route().next()?;
+        let forward_routes = self.0.lock().unwrap();
+        // A replica registered by this node can reach any of its workers,
+        // like a Forwarder accessed with our node
+        if forward_routes
+            .iter()
+            .any(|r| r.is_empty() || r.next().ok() == Some(next_hop))
+        {
+            ockam_core::allow()
+        } else {
+            ockam_core::deny()
+        }
+    }
+}
+
+/// Addresses of the Forwarders of replicated relay names, which receive the registrations
+/// forwarded by the Forwarding service
+#[derive(Clone, Debug, Default)]
+pub(super) struct ReplicaRegistrationAddresses(Arc<Mutex<BTreeSet<Address>>>);
+
+impl ReplicaRegistrationAddresses {
+    pub(super) fn insert(&self, address: Address) {
+        self.0.lock().unwrap().insert(address);
+    }
+
+    pub(super) fn remove(&self, address: &Address) {
+        self.0.lock().unwrap().remove(address);
+    }
+}
+
+#[async_trait]
+impl OutgoingAccessControl for ReplicaRegistrationAddresses {
+    async fn is_authorized(&self, relay_msg: &RelayMessage) -> Result<bool> {
+        if self
+            .0
+            .lock()
+            .unwrap()
+            .contains(relay_msg.onward_route().next()?)
+        {
+            ockam_core::allow()
+        } else {
+            ockam_core::deny()
+        }
+    }
+}
+
+/// Forwarder of a relay name registered by several replicas.
+///
+/// The registrations of the replicas are forwarded by the Forwarding service to a dedicated
+/// address. Replicas which do not register again within the heartbeat timeout are dropped,
+/// and the Forwarder stops, releasing the relay name, once all the replicas are dropped
+pub(super) struct ReplicaForwarder {
+    registration_address: Address,
+    expiry_address: Address,
+    expiry: DelayedEvent<Vec<u8>>,
+    heartbeat_timeout: Duration,
+    replicas: Replicas,
+    // this option will be `None` after this worker is initialized, because
+    // while initializing, the worker registers the first replica
+    first_registration: Option<(Route, Vec<u8>)>,
+    // claim of the relay name, released when this worker stops
+    lease: RelayNameLease,
+    replica_registrations: ReplicaRegistrationAddresses,
+}
+
+impl ReplicaForwarder {
+    /// Create a Forwarder for the first replica of a relay name,
+    /// and return the address receiving the registrations of the other replicas
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn create(
+        ctx: &Context,
+        address: Address,
+        service_address: Address,
+        registration_route: Route,
+        registration_payload: Vec<u8>,
+        incoming_access_control: Arc<dyn IncomingAccessControl>,
+        heartbeat_timeout: Duration,
+        lease: RelayNameLease,
+        replica_registrations: ReplicaRegistrationAddresses,
+    ) -> Result<Address> {
+        info!("Created new replicated alias {}", address);
+
+        let registration_address = Address::random_tagged("ReplicaForwarder.registration");
+        let expiry_address = Address::random_tagged("ReplicaForwarder.expiry");
+        let expiry = DelayedEvent::create(ctx, expiry_address.clone(), vec![]).await?;
+
+        let replicas = Replicas::default();
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(
+                address,
+                incoming_access_control,
+                Arc::new(AllowReplicas(replicas.forward_routes.clone())),
+            ),
+            vec![
+                Mailbox::new(
+                    registration_address.clone(),
+                    Arc::new(AllowSourceAddress(service_address)),
+                    Arc::new(DenyAll),
+                ),
+                Mailbox::new(
+                    expiry_address.clone(),
+                    Arc::new(AllowSourceAddress(expiry.address())),
+                    Arc::new(DenyAll),
+                ),
+            ],
+        );
+
+        let forwarder = Self {
+            registration_address: registration_address.clone(),
+            expiry_address,
+            expiry,
+            heartbeat_timeout,
+            replicas,
+            first_registration: Some((registration_route, registration_payload)),
+            lease,
+            replica_registrations,
+        };
+
+        WorkerBuilder::new(forwarder)
+            .with_mailboxes(mailboxes)
+            .start(ctx)
+            .await?;
+
+        Ok(registration_address)
+    }
+
+    /// Register a replica and confirm the registration
+    async fn register(
+        &mut self,
+        ctx: &Context,
+        registration_route: Route,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        if self.replicas.register(registration_route.clone()) {
+            info!(
+                "Added replica {} to alias {}, {} replicas registered",
+                registration_route,
+                ctx.address(),
+                self.replicas.len()
+            );
+        } else {
+            debug!("Refreshed replica {}", registration_route);
+        }
+
+        let msg = TransportMessage::v1(registration_route, ctx.address(), payload);
+        ctx.forward(LocalMessage::new(msg, Vec::new())).await
+    }
+}
+
+#[crate::worker]
+impl Worker for ReplicaForwarder {
+    type Context = Context;
+    type Message = Any;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        let (registration_route, payload) = self
+            .first_registration
+            .take()
+            .expect("registration must be available on init");
+        self.register(ctx, registration_route, payload).await?;
+        self.expiry.schedule(self.heartbeat_timeout).await
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        self.replica_registrations
+            .remove(&self.registration_address);
+        self.lease.release();
+        Ok(())
+    }
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        if msg.msg_addr() == self.registration_address {
+            let registration_route = msg.return_route();
+            let payload = msg.into_transport_message().payload;
+            self.register(ctx, registration_route, payload).await
+        } else if msg.msg_addr() == self.expiry_address {
+            let dropped = self.replicas.expire();
+            if dropped > 0 {
+                warn!(
+                    "Dropped {} replicas of alias {} without heartbeats, {} replicas registered",
+                    dropped,
+                    ctx.address(),
+                    self.replicas.len()
+                );
+            }
+            if self.replicas.is_empty() {
+                info!("No replica left for alias {}, stopping it", ctx.address());
+                return ctx.stop_worker(ctx.address()).await;
+            }
+            self.expiry.schedule(self.heartbeat_timeout).await
+        } else {
+            let return_route = msg.return_route();
+            match self.replicas.select(&return_route) {
+                Some(forward_route) => forward_message(ctx, msg, &forward_route).await,
+                None => {
+                    warn!(
+                        "No replica registered for alias {}, dropping the message",
+                        ctx.address()
+                    );
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::route;
+
+    #[test]
+    fn test_sessions_are_sticky() {
+        let mut replicas = Replicas::default();
+        assert!(replicas.register(route!["tcp_a", "remote_forwarder"]));
+        assert!(replicas.register(route!["tcp_b", "remote_forwarder"]));
+        assert!(!replicas.register(route!["tcp_a", "remote_forwarder"]));
+
+        let alice = route!["alice"];
+        let bob = route!["bob"];
+        let alice_replica = replicas.select(&alice).unwrap();
+        let bob_replica = replicas.select(&bob).unwrap();
+        assert_ne!(alice_replica, bob_replica);
+        assert_eq!(replicas.select(&alice), Some(alice_replica));
+        assert_eq!(replicas.select(&bob), Some(bob_replica));
+    }
+
+    #[test]
+    fn test_replicas_without_heartbeats_are_dropped() {
+        let mut replicas = Replicas::default();
+        replicas.register(route!["tcp_a", "remote_forwarder"]);
+        replicas.register(route!["tcp_b", "remote_forwarder"]);
+        let alice = route!["alice"];
+        assert_eq!(replicas.select(&alice), Some(route!["tcp_a"]));
+
+        // both replicas registered during the first period
+        assert_eq!(replicas.expire(), 0);
+
+        // only the second replica registers again
+        replicas.register(route!["tcp_b", "remote_forwarder"]);
+        assert_eq!(replicas.expire(), 1);
+        assert_eq!(replicas.len(), 1);
+        assert_eq!(
+            *replicas.forward_routes.lock().unwrap(),
+            vec![route!["tcp_b"]]
+        );
+
+        // the session moves to the remaining replica
+        assert_eq!(replicas.select(&alice), Some(route!["tcp_b"]));
+
+        replicas.expire();
+        assert_eq!(replicas.select(&alice), None);
+    }
+}
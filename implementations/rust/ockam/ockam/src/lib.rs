@@ -74,7 +74,13 @@ mod system;
 mod unique;
 
 pub use error::OckamError;
-pub use forwarding_service::{ForwardingService, ForwardingServiceOptions};
+pub use forwarding_service::{
+    ForwardingService, ForwardingServiceOptions, RelayMailboxOptions, RelayMailboxStorage,
+    RelayNameAuthorization, RelayNameTakeover, StoredRelayMessage,
+    DEFAULT_RELAY_MAILBOX_HEARTBEAT_TIMEOUT, DEFAULT_RELAY_MAILBOX_MAX_BYTES,
+    DEFAULT_RELAY_MAILBOX_MAX_MESSAGES, DEFAULT_RELAY_MAILBOX_TTL,
+    DEFAULT_REPLICA_HEARTBEAT_TIMEOUT,
+};
 pub use metadata::OckamMessage;
 pub use system::{SystemBuilder, SystemHandler, WorkerSystem};
 pub use unique::unique_with_prefix;
@@ -11,6 +11,8 @@ pub(super) struct Addresses {
     pub(super) heartbeat: Address,
     // Used to receive completion callback
     pub(super) completion_callback: Address,
+    // Used to receive the messages stored by the Forwarder while we were offline
+    pub(super) mailbox: Address,
 }
 
 impl Addresses {
@@ -23,12 +25,14 @@ impl Addresses {
         let heartbeat = Address::random_tagged(&format!("RemoteForwarder.{}.heartbeat", type_str));
         let completion_callback =
             Address::random_tagged(&format!("RemoteForwarder.{}.child", type_str));
+        let mailbox = Address::random_tagged(&format!("RemoteForwarder.{}.mailbox", type_str));
 
         Self {
             main_remote,
             main_internal,
             heartbeat,
             completion_callback,
+            mailbox,
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::forwarding_service::{RELAY_MAILBOX_PREFIX, RELAY_REPLICA_PREFIX};
 use crate::remote::{Addresses, RemoteForwarder, RemoteForwarderInfo, RemoteForwarderOptions};
 use crate::Context;
 use core::time::Duration;
@@ -19,6 +20,7 @@ pub(super) enum ForwardType {
     Static,
     Ephemeral,
     StaticWithoutHeartbeats,
+    Replica,
 }
 
 impl ForwardType {
@@ -27,6 +29,7 @@ impl ForwardType {
             ForwardType::Static => "static",
             ForwardType::Ephemeral => "ephemeral",
             ForwardType::StaticWithoutHeartbeats => "static_w/o_heartbeats",
+            ForwardType::Replica => "replica",
         }
     }
 }
@@ -36,6 +39,7 @@ impl RemoteForwarder {
         addresses: Addresses,
         heartbeat_source_address: Option<Address>,
         outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+        store_and_forward: bool,
     ) -> Mailboxes {
         let main_internal = Mailbox::new(
             addresses.main_internal,
@@ -60,6 +64,11 @@ impl RemoteForwarder {
             additional_mailboxes.push(heartbeat);
         }
 
+        if store_and_forward {
+            let mailbox = Mailbox::new(addresses.mailbox, Arc::new(AllowAll), Arc::new(AllowAll));
+            additional_mailboxes.push(mailbox);
+        }
+
         Mailboxes::new(main_internal, additional_mailboxes)
     }
 }
@@ -81,6 +90,11 @@ impl RemoteForwarder {
             flow_control_id,
             heartbeat,
             heartbeat_interval,
+            reset_heartbeat_on_traffic: true,
+            register_on_heartbeat: true,
+            store_and_forward: false,
+            forwarder_route: None,
+            delivered: None,
         }
     }
 
@@ -117,7 +131,7 @@ impl RemoteForwarder {
             alias.into(),
             flow_control_id,
             Some(heartbeat),
-            Duration::from_secs(5),
+            options.heartbeat_interval,
         );
 
         debug!(
@@ -128,6 +142,7 @@ impl RemoteForwarder {
             addresses,
             Some(heartbeat_source_address),
             outgoing_access_control,
+            false,
         );
         WorkerBuilder::new(forwarder)
             .with_mailboxes(mailboxes)
@@ -175,7 +190,7 @@ impl RemoteForwarder {
             "Starting ephemeral RemoteForwarder at {}",
             &addresses.main_internal
         );
-        let mailboxes = Self::mailboxes(addresses, None, outgoing_access_control);
+        let mailboxes = Self::mailboxes(addresses, None, outgoing_access_control, false);
         WorkerBuilder::new(forwarder)
             .with_mailboxes(mailboxes)
             .start(ctx)
@@ -190,6 +205,9 @@ impl RemoteForwarder {
     /// This is a temporary kind of RemoteForwarder that will only run on
     /// rust nodes (hence the `forwarding_service` addr to create static forwarders).
     /// We will use it while we don't have heartbeats implemented on rust nodes.
+    ///
+    /// With [`RemoteForwarderOptions::with_store_and_forward`], the heartbeats tell the
+    /// Forwarder that this node is online, and receive the messages it stored meanwhile
     pub async fn create_static_without_heartbeats(
         ctx: &Context,
         hub_route: impl Into<Route>,
@@ -208,25 +226,100 @@ impl RemoteForwarder {
 
         let registration_route = route![hub_route.into(), "forwarding_service"];
 
+        let (heartbeat, heartbeat_source_address, alias) = if options.store_and_forward {
+            let heartbeat = DelayedEvent::create(ctx, addresses.heartbeat.clone(), vec![]).await?;
+            let heartbeat_source_address = heartbeat.address();
+            let alias = format!("{}{}", RELAY_MAILBOX_PREFIX, alias.into());
+            (Some(heartbeat), Some(heartbeat_source_address), alias)
+        } else {
+            (None, None, alias.into())
+        };
+
         let flow_control_id =
             options.setup_flow_control(ctx.flow_controls(), &addresses, registration_route.next()?);
         let outgoing_access_control =
             options.create_access_control(ctx.flow_controls(), flow_control_id.clone());
 
-        let forwarder = Self::new(
+        let mut forwarder = Self::new(
             addresses.clone(),
             registration_route,
-            alias.into(),
+            alias,
             flow_control_id,
-            None,
-            Duration::from_secs(10),
+            heartbeat,
+            options.heartbeat_interval,
         );
+        forwarder.register_on_heartbeat = false;
+        forwarder.store_and_forward = options.store_and_forward;
 
         debug!(
             "Starting static RemoteForwarder without heartbeats at {}",
             &addresses.main_internal
         );
-        let mailboxes = Self::mailboxes(addresses, None, outgoing_access_control);
+        let mailboxes = Self::mailboxes(
+            addresses,
+            heartbeat_source_address,
+            outgoing_access_control,
+            options.store_and_forward,
+        );
+        WorkerBuilder::new(forwarder)
+            .with_mailboxes(mailboxes)
+            .start(ctx)
+            .await?;
+
+        let resp = callback_ctx.receive::<RemoteForwarderInfo>().await?.body();
+
+        Ok(resp)
+    }
+
+    /// Create and start a RemoteForwarder registering a replica of a relay name on a rust node.
+    /// Several nodes can register replicas of the same name, the new senders are then
+    /// distributed across the replicas which keep sending heartbeats
+    pub async fn create_replica(
+        ctx: &Context,
+        hub_route: impl Into<Route>,
+        alias: impl Into<String>,
+        options: RemoteForwarderOptions,
+    ) -> Result<RemoteForwarderInfo> {
+        let addresses = Addresses::generate(ForwardType::Replica);
+
+        let mut callback_ctx = ctx
+            .new_detached_with_mailboxes(Mailboxes::main(
+                addresses.completion_callback.clone(),
+                Arc::new(AllowSourceAddress(addresses.main_remote.clone())),
+                Arc::new(DenyAll),
+            ))
+            .await?;
+
+        let registration_route = route![hub_route.into(), "forwarding_service"];
+
+        let heartbeat = DelayedEvent::create(ctx, addresses.heartbeat.clone(), vec![]).await?;
+        let heartbeat_source_address = heartbeat.address();
+
+        let flow_control_id =
+            options.setup_flow_control(ctx.flow_controls(), &addresses, registration_route.next()?);
+        let outgoing_access_control =
+            options.create_access_control(ctx.flow_controls(), flow_control_id.clone());
+
+        let mut forwarder = Self::new(
+            addresses.clone(),
+            registration_route,
+            format!("{}{}", RELAY_REPLICA_PREFIX, alias.into()),
+            flow_control_id,
+            Some(heartbeat),
+            options.heartbeat_interval,
+        );
+        forwarder.reset_heartbeat_on_traffic = false;
+
+        debug!(
+            "Starting replica RemoteForwarder at {}",
+            &addresses.heartbeat
+        );
+        let mailboxes = Self::mailboxes(
+            addresses,
+            Some(heartbeat_source_address),
+            outgoing_access_control,
+            false,
+        );
         WorkerBuilder::new(forwarder)
             .with_mailboxes(mailboxes)
             .start(ctx)
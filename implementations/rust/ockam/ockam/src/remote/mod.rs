@@ -10,6 +10,7 @@ mod worker;
 pub use info::*;
 pub use options::*;
 
+use crate::forwarding_service::RelayMailboxPosition;
 use crate::remote::addresses::Addresses;
 use core::time::Duration;
 use ockam_core::compat::{string::String, vec::Vec};
@@ -28,4 +29,15 @@ pub struct RemoteForwarder {
     // We only use Heartbeat for static RemoteForwarder
     heartbeat: Option<DelayedEvent<Vec<u8>>>,
     heartbeat_interval: Duration,
+    // The traffic delays the heartbeats, unless the Forwarding service expects
+    // regular heartbeats from the replicas of a relay name
+    reset_heartbeat_on_traffic: bool,
+    // The heartbeats register the RemoteForwarder again, unless they only
+    // tell the Forwarder storing our messages that we are online
+    register_on_heartbeat: bool,
+    store_and_forward: bool,
+    // Route to the Forwarder, once registered with store-and-forward
+    forwarder_route: Option<Route>,
+    // Position of the last stored message delivered by the Forwarder
+    delivered: Option<RelayMailboxPosition>,
 }
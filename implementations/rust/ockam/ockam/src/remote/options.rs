@@ -1,10 +1,18 @@
 use crate::remote::Addresses;
+use core::time::Duration;
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
 use ockam_core::{Address, AllowAll, OutgoingAccessControl};
 
+/// Default interval of the heartbeats of a static [`RemoteForwarder`](super::RemoteForwarder)
+/// or of a replica
+pub const DEFAULT_REMOTE_FORWARDER_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Trust options for [`RemoteForwarder`](super::RemoteForwarder)
-pub struct RemoteForwarderOptions {}
+pub struct RemoteForwarderOptions {
+    pub(super) heartbeat_interval: Duration,
+    pub(super) store_and_forward: bool,
+}
 
 impl RemoteForwarderOptions {
     /// Usually [`FlowControlId`] should be shared with the Producer that was used to create this
@@ -15,7 +23,25 @@ impl RemoteForwarderOptions {
     /// through the [`RemoteForwarder`](super::RemoteForwarder) through the same Secure Channel.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self {}
+        Self {
+            heartbeat_interval: DEFAULT_REMOTE_FORWARDER_HEARTBEAT_INTERVAL,
+            store_and_forward: false,
+        }
+    }
+
+    /// Set the interval of the heartbeats, which register a static RemoteForwarder
+    /// or a replica again. It must be shorter than the heartbeat timeout of the service
+    pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = heartbeat_interval;
+        self
+    }
+
+    /// Ask the Forwarder to store the messages sent while this node is offline, and to deliver
+    /// them once it registers again. This is only supported by the static RemoteForwarders
+    /// registered on rust nodes, whose Forwarding service enables relay mailboxes
+    pub fn with_store_and_forward(mut self) -> Self {
+        self.store_and_forward = true;
+        self
     }
 
     pub(super) fn setup_flow_control(
@@ -30,6 +56,9 @@ impl RemoteForwarderOptions {
         {
             // Allow a sender with corresponding flow_control_id send messages to this address
             flow_controls.add_consumer(addresses.main_remote.clone(), &flow_control_id);
+            if self.store_and_forward {
+                flow_controls.add_consumer(addresses.mailbox.clone(), &flow_control_id);
+            }
 
             flow_controls.add_producer(
                 addresses.main_internal.clone(),
@@ -1,3 +1,4 @@
+use crate::forwarding_service::{RelayMailboxDelivery, RelayMailboxRequest};
 use crate::remote::{RemoteForwarder, RemoteForwarderInfo};
 use crate::{Context, OckamError};
 use ockam_core::compat::{
@@ -5,7 +6,7 @@ use ockam_core::compat::{
     string::{String, ToString},
     vec::Vec,
 };
-use ockam_core::{Any, Decodable, Result, Routed, Worker};
+use ockam_core::{Any, Decodable, LocalMessage, Result, Route, Routed, Worker};
 use tracing::{debug, info};
 
 #[crate::worker]
@@ -33,12 +34,18 @@ impl Worker for RemoteForwarder {
     ) -> Result<()> {
         if msg.msg_addr() == self.addresses.heartbeat {
             // Heartbeat message, send registration message
-            ctx.send_from_address(
-                self.registration_route.clone(),
-                self.registration_payload.clone(),
-                self.addresses.main_remote.clone(),
-            )
-            .await?;
+            if self.register_on_heartbeat {
+                ctx.send_from_address(
+                    self.registration_route.clone(),
+                    self.registration_payload.clone(),
+                    self.addresses.main_remote.clone(),
+                )
+                .await?;
+            }
+
+            if let Some(forwarder_route) = self.forwarder_route.clone() {
+                self.drain(ctx, forwarder_route).await?;
+            }
 
             if let Some(heartbeat) = &mut self.heartbeat {
                 heartbeat.schedule(self.heartbeat_interval).await?;
@@ -66,6 +73,12 @@ impl Worker for RemoteForwarder {
                         return Err(OckamError::InvalidHubResponse.into());
                     }
 
+                    if self.store_and_forward {
+                        // Receive the messages stored while we were offline
+                        self.forwarder_route = Some(return_route.clone());
+                        self.drain(ctx, return_route.clone()).await?;
+                    }
+
                     if !self.completion_msg_sent {
                         info!("RemoteForwarder registered with route: {}", return_route);
                         let address = match return_route.recipient()?.to_string().strip_prefix("0#")
@@ -111,15 +124,69 @@ impl Worker for RemoteForwarder {
 
                     // We received message from the other node, our registration is still alive, let's reset
                     // heartbeat timer
-                    if let Some(heartbeat) = &mut self.heartbeat {
-                        heartbeat.schedule(self.heartbeat_interval).await?;
+                    if self.reset_heartbeat_on_traffic {
+                        if let Some(heartbeat) = &mut self.heartbeat {
+                            heartbeat.schedule(self.heartbeat_interval).await?;
+                        }
                     }
 
                     Ok(())
                 }
             }
+        } else if self.store_and_forward && msg.msg_addr() == self.addresses.mailbox {
+            let return_route = msg.return_route();
+            let delivery = RelayMailboxDelivery::decode(msg.payload())?;
+            let position = delivery.position;
+            let sequence = position.sequence;
+
+            // The sequence numbers of a new Forwarder start again
+            if self
+                .delivered
+                .map(|d| d.epoch == position.epoch && sequence <= d.sequence)
+                .unwrap_or(false)
+            {
+                debug!("RemoteForwarder received stored message {} again", sequence);
+            } else {
+                debug!("RemoteForwarder received stored message {}", sequence);
+                let mut message = delivery.message;
+                if message.onward_route.next()? == &self.addresses.main_remote {
+                    return Err(OckamError::UnknownForwarderNextHopAddress.into());
+                }
+
+                // The replies go back through the Forwarder, like the forwarded messages
+                let mut forward_route = return_route.clone();
+                forward_route.modify().pop_back();
+                message.return_route.modify().prepend_route(forward_route);
+
+                ctx.forward_from_address(
+                    LocalMessage::new(message, Vec::new()),
+                    self.addresses.main_internal.clone(),
+                )
+                .await?;
+                self.delivered = Some(position);
+            }
+
+            // The Forwarder deletes the stored message once it is acknowledged
+            ctx.send_from_address(
+                return_route,
+                RelayMailboxRequest::Acknowledge(position),
+                self.addresses.mailbox.clone(),
+            )
+            .await
         } else {
             Err(OckamError::UnknownForwarderDestinationAddress.into())
         }
     }
 }
+
+impl RemoteForwarder {
+    /// Tell the Forwarder that we are online, and acknowledge the delivered messages
+    async fn drain(&self, ctx: &Context, forwarder_route: Route) -> Result<()> {
+        ctx.send_from_address(
+            forwarder_route,
+            RelayMailboxRequest::Drain(self.delivered),
+            self.addresses.mailbox.clone(),
+        )
+        .await
+    }
+}
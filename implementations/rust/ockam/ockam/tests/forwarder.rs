@@ -1,10 +1,10 @@
 use ockam::identity::{secure_channels, SecureChannelListenerOptions, SecureChannelOptions};
 use ockam::remote::{RemoteForwarder, RemoteForwarderOptions};
 use ockam::workers::Echoer;
-use ockam::{ForwardingService, ForwardingServiceOptions};
-use ockam_core::{route, AllowAll, Result};
-use ockam_node::{Context, MessageReceiveOptions};
-use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
+use ockam::{ForwardingService, ForwardingServiceOptions, RelayMailboxOptions, RelayNameTakeover};
+use ockam_core::{route, Address, AllowAll, Result, Routed};
+use ockam_node::{Context, MessageReceiveOptions, MessageSendReceiveOptions};
+use ockam_transport_tcp::{TcpConnection, TcpConnectionOptions, TcpListenerOptions, TcpTransport};
 use std::time::Duration;
 
 // Node creates a Forwarding service and a Remote Forwarder, Echoer is reached through the Forwarder. No flow control
@@ -259,3 +259,334 @@ async fn test4(ctx: &mut Context) -> Result<()> {
 
     ctx.stop().await
 }
+
+// Two identities register the same relay name through secure channels to the Forwarding service.
+// The name stays bound to the first identity, which can register it again
+#[ockam_macros::test]
+async fn test5(ctx: &mut Context) -> Result<()> {
+    let listener_options = SecureChannelListenerOptions::new();
+    let options = ForwardingServiceOptions::new()
+        .service_as_consumer(&listener_options.spawner_flow_control_id())
+        .forwarder_as_consumer(&listener_options.spawner_flow_control_id());
+    ForwardingService::create(ctx, "forwarding_service", options).await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let cloud_identity = identities_creation.create_identity().await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            cloud_identity.identifier(),
+            "cloud_listener",
+            listener_options,
+        )
+        .await?;
+
+    let alice = identities_creation.create_identity().await?;
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["cloud_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let bob = identities_creation.create_identity().await?;
+    let bob_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            bob.identifier(),
+            route!["cloud_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let resp = register(ctx, alice_channel.clone().into()).await?.body();
+    assert_eq!(resp, "db");
+
+    // The name is held by alice
+    assert!(register(ctx, bob_channel.into()).await.is_err());
+
+    // Alice registers the name again, for example after reconnecting
+    let resp = register(ctx, alice_channel.into()).await?.body();
+    assert_eq!(resp, "db");
+
+    ctx.stop().await
+}
+
+// Cloud: Hosts a Forwarding service and listens on a tcp port
+// Server: Connects twice to the Cloud and registers a replica of the same relay name on each connection
+// A replica stops sending heartbeats when its connection is closed, then all the messages go to the other replica
+#[ockam_macros::test]
+async fn test6(ctx: &mut Context) -> Result<()> {
+    let tcp_listener_options = TcpListenerOptions::new();
+    let options = ForwardingServiceOptions::new()
+        .service_as_consumer(&tcp_listener_options.spawner_flow_control_id())
+        .forwarder_as_consumer(&tcp_listener_options.spawner_flow_control_id())
+        .with_replica_heartbeat_timeout(Duration::from_millis(500))
+        // the replicas are registered without a secure channel
+        .with_relay_name_takeover(RelayNameTakeover::Authorized);
+    ForwardingService::create(ctx, "forwarding_service", options).await?;
+    let cloud_tcp = TcpTransport::create(ctx).await?;
+    let cloud_listener = cloud_tcp
+        .listen("127.0.0.1:0", tcp_listener_options)
+        .await?;
+
+    ctx.start_worker("echoer", Echoer).await?;
+    let server_tcp = TcpTransport::create(ctx).await?;
+    let mut connections = vec![];
+    for _ in 0..2 {
+        let tcp_options = TcpConnectionOptions::new();
+        ctx.flow_controls()
+            .add_consumer("echoer", &tcp_options.flow_control_id());
+        let connection = server_tcp
+            .connect(cloud_listener.socket_string(), tcp_options)
+            .await?;
+        let remote_info = RemoteForwarder::create_replica(
+            ctx,
+            connection.clone(),
+            "db",
+            RemoteForwarderOptions::new().with_heartbeat_interval(Duration::from_millis(100)),
+        )
+        .await?;
+        assert_eq!(remote_info.remote_address(), "db");
+        connections.push(connection);
+    }
+
+    // Each new sender is sent to the next replica
+    for _ in 0..4 {
+        assert_eq!(echo(ctx).await?.body(), "Hello");
+    }
+
+    server_tcp
+        .disconnect(connections[0].sender_address().clone())
+        .await?;
+    ctx.sleep(Duration::from_millis(1500)).await;
+
+    // The first replica was dropped
+    for _ in 0..4 {
+        assert_eq!(echo(ctx).await?.body(), "Hello");
+    }
+
+    ctx.stop().await
+}
+
+// Cloud: Hosts a Forwarding service with relay mailboxes and listens on a tcp port
+// Server: Connects to the Cloud and registers a relay name with store-and-forward
+// The messages sent while the Server is disconnected are delivered once, in order, when it registers again
+#[ockam_macros::test]
+async fn test7(ctx: &mut Context) -> Result<()> {
+    let tcp_listener_options = TcpListenerOptions::new();
+    let options = ForwardingServiceOptions::new()
+        .service_as_consumer(&tcp_listener_options.spawner_flow_control_id())
+        .forwarder_as_consumer(&tcp_listener_options.spawner_flow_control_id())
+        .with_relay_mailboxes(
+            RelayMailboxOptions::in_memory().with_heartbeat_timeout(Duration::from_millis(500)),
+        )
+        // the name is registered again without a secure channel
+        .with_relay_name_takeover(RelayNameTakeover::Authorized);
+    ForwardingService::create(ctx, "forwarding_service", options).await?;
+    let cloud_tcp = TcpTransport::create(ctx).await?;
+    let cloud_listener = cloud_tcp
+        .listen("127.0.0.1:0", tcp_listener_options)
+        .await?;
+
+    ctx.start_worker("echoer", Echoer).await?;
+    let server_tcp = TcpTransport::create(ctx).await?;
+    let cloud_address = cloud_listener.socket_string();
+
+    let connection = register_with_store_and_forward(ctx, &server_tcp, &cloud_address).await?;
+    ctx.send(route!["db", "echoer"], "1".to_string()).await?;
+    assert_eq!(ctx.receive::<String>().await?.body(), "1");
+
+    server_tcp
+        .disconnect(connection.sender_address().clone())
+        .await?;
+    // The Forwarder notices the missing heartbeats
+    ctx.sleep(Duration::from_millis(1200)).await;
+
+    // The Server is offline, the messages are stored
+    for msg in ["2", "3", "4"] {
+        ctx.send(route!["db", "echoer"], msg.to_string()).await?;
+    }
+    ctx.sleep(Duration::from_millis(200)).await;
+
+    register_with_store_and_forward(ctx, &server_tcp, &cloud_address).await?;
+    for msg in ["2", "3", "4"] {
+        assert_eq!(ctx.receive::<String>().await?.body(), msg);
+    }
+
+    // The stored messages are not delivered again with the next heartbeats
+    let res = ctx
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    ctx.stop().await
+}
+
+// A relay name registered without a secure channel can't be registered again while its Forwarder runs.
+// The names are released when their Forwarder stops, and can then be registered by another identity
+#[ockam_macros::test]
+async fn test8(ctx: &mut Context) -> Result<()> {
+    let listener_options = SecureChannelListenerOptions::new();
+    let options = ForwardingServiceOptions::new()
+        .service_as_consumer(&listener_options.spawner_flow_control_id())
+        .forwarder_as_consumer(&listener_options.spawner_flow_control_id());
+    ForwardingService::create(ctx, "forwarding_service", options).await?;
+
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let cloud_identity = identities_creation.create_identity().await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            cloud_identity.identifier(),
+            "cloud_listener",
+            listener_options,
+        )
+        .await?;
+
+    let mut channels = vec![];
+    for _ in 0..2 {
+        let identity = identities_creation.create_identity().await?;
+        let channel = secure_channels
+            .create_secure_channel(
+                ctx,
+                identity.identifier(),
+                route!["cloud_listener"],
+                SecureChannelOptions::new(),
+            )
+            .await?;
+        channels.push(Address::from(channel));
+    }
+
+    // Without a secure channel
+    let register_locally = || {
+        ctx.send_and_receive_extended::<String>(
+            route!["forwarding_service"],
+            "db".to_string(),
+            MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+    };
+    assert_eq!(register_locally().await?.body(), "db");
+    assert!(register_locally().await.is_err());
+    assert!(register(ctx, channels[0].clone()).await.is_err());
+
+    ctx.stop_worker("db").await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(register(ctx, channels[0].clone()).await?.body(), "db");
+    assert!(register(ctx, channels[1].clone()).await.is_err());
+
+    ctx.stop_worker("db").await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(register(ctx, channels[1].clone()).await?.body(), "db");
+
+    ctx.stop().await
+}
+
+// Cloud: Hosts a Forwarding service and listens on a tcp port
+// Server: Registers a single replica of a relay name, then its connection is closed
+// The Forwarder stops once its last replica is dropped, and the name can be registered again
+#[ockam_macros::test]
+async fn test9(ctx: &mut Context) -> Result<()> {
+    let tcp_listener_options = TcpListenerOptions::new();
+    let options = ForwardingServiceOptions::new()
+        .service_as_consumer(&tcp_listener_options.spawner_flow_control_id())
+        .forwarder_as_consumer(&tcp_listener_options.spawner_flow_control_id())
+        .with_replica_heartbeat_timeout(Duration::from_millis(500));
+    ForwardingService::create(ctx, "forwarding_service", options).await?;
+    let cloud_tcp = TcpTransport::create(ctx).await?;
+    let cloud_listener = cloud_tcp
+        .listen("127.0.0.1:0", tcp_listener_options)
+        .await?;
+
+    ctx.start_worker("echoer", Echoer).await?;
+    let server_tcp = TcpTransport::create(ctx).await?;
+    let tcp_options = TcpConnectionOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &tcp_options.flow_control_id());
+    let connection = server_tcp
+        .connect(cloud_listener.socket_string(), tcp_options)
+        .await?;
+    RemoteForwarder::create_replica(
+        ctx,
+        connection.clone(),
+        "db",
+        RemoteForwarderOptions::new().with_heartbeat_interval(Duration::from_millis(100)),
+    )
+    .await?;
+    assert_eq!(echo(ctx).await?.body(), "Hello");
+
+    server_tcp
+        .disconnect(connection.sender_address().clone())
+        .await?;
+    ctx.sleep(Duration::from_millis(1500)).await;
+
+    // The Forwarder was stopped
+    assert!(echo(ctx).await.is_err());
+
+    // The name was released, and can be registered by another node
+    let tcp_options = TcpConnectionOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &tcp_options.flow_control_id());
+    let connection = server_tcp
+        .connect(cloud_listener.socket_string(), tcp_options)
+        .await?;
+    let remote_info = RemoteForwarder::create_static_without_heartbeats(
+        ctx,
+        connection,
+        "db",
+        RemoteForwarderOptions::new(),
+    )
+    .await?;
+    assert_eq!(remote_info.remote_address(), "db");
+    assert_eq!(echo(ctx).await?.body(), "Hello");
+
+    ctx.stop().await
+}
+
+/// Register the "db" relay name through a secure channel
+async fn register(ctx: &Context, channel: Address) -> Result<Routed<String>> {
+    ctx.send_and_receive_extended::<String>(
+        route![channel, "forwarding_service"],
+        "db".to_string(),
+        MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+    )
+    .await
+}
+
+/// Send a message to the echoer behind the "db" relay
+async fn echo(ctx: &Context) -> Result<Routed<String>> {
+    ctx.send_and_receive_extended::<String>(
+        route!["db", "echoer"],
+        "Hello".to_string(),
+        MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+    )
+    .await
+}
+
+async fn register_with_store_and_forward(
+    ctx: &Context,
+    tcp: &TcpTransport,
+    address: &str,
+) -> Result<TcpConnection> {
+    let tcp_options = TcpConnectionOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &tcp_options.flow_control_id());
+    let connection = tcp.connect(address, tcp_options).await?;
+    let remote_info = RemoteForwarder::create_static_without_heartbeats(
+        ctx,
+        connection.clone(),
+        "db",
+        RemoteForwarderOptions::new()
+            .with_heartbeat_interval(Duration::from_millis(100))
+            .with_store_and_forward(),
+    )
+    .await?;
+    assert_eq!(remote_info.remote_address(), "db");
+    Ok(connection)
+}
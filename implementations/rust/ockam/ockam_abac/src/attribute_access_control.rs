@@ -12,10 +12,11 @@ use tracing as log;
 
 use crate::expr::str;
 use crate::Expr::*;
-use crate::{eval, Env, Expr};
+use crate::{eval, explain, Action, AuditLog, Decision, Env, Explanation, Expr, Resource};
 use ockam_core::compat::format;
-use ockam_core::compat::string::ToString;
+use ockam_core::compat::string::{String, ToString};
 use ockam_core::compat::sync::Arc;
+use ockam_identity::utils::now;
 use ockam_identity::{Identifier, IdentitiesRepository, IdentitySecureChannelLocalInfo};
 
 /// This AccessControl uses a storage for authenticated attributes in order
@@ -26,6 +27,9 @@ pub struct AbacAccessControl {
     repository: Arc<dyn IdentitiesRepository>,
     expression: Expr,
     environment: Env,
+    resource: Option<Resource>,
+    action: Option<Action>,
+    audit_log: Option<Arc<dyn AuditLog>>,
 }
 
 /// Debug implementation printing out the policy expression only
@@ -47,9 +51,25 @@ impl AbacAccessControl {
             repository,
             expression,
             environment,
+            resource: None,
+            action: None,
+            audit_log: None,
         }
     }
 
+    /// Record each decision of this AccessControl in an audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Set the resource and action of the policy expression, used in the audit log
+    pub(crate) fn with_resource_action(mut self, resource: Resource, action: Action) -> Self {
+        self.resource = Some(resource);
+        self.action = Some(action);
+        self
+    }
+
     /// Create an AccessControl which will verify that the sender of
     /// a message has an authenticated attribute with the correct name and value
     pub fn create(
@@ -68,12 +88,15 @@ where {
 }
 
 impl AbacAccessControl {
-    /// Returns true if the identity is authorized
-    pub async fn is_identity_authorized(&self, id: Identifier) -> Result<bool> {
+    /// Return the environment in which the policy expression is evaluated for an identity.
+    ///
+    /// It contains the attributes of the identity, its identifier and the current time,
+    /// in addition to the environment of this AccessControl
+    pub async fn environment(&self, id: &Identifier) -> Result<Env> {
         let mut environment = self.environment.clone();
 
         // Get identity attributes and populate the environment:
-        if let Some(attrs) = self.repository.get_attributes(&id).await? {
+        if let Some(attrs) = self.repository.get_attributes(id).await? {
             for (key, value) in attrs.attrs() {
                 let key = match from_utf8(key) {
                     Ok(key) => key,
@@ -123,8 +146,27 @@ impl AbacAccessControl {
         // add the identifier itself as a subject parameter
         environment.put("subject.identifier", str(id.to_string()));
 
+        // add the current time, unless the environment already defines it
+        if let Ok(now) = now() {
+            environment.put_time(now.0);
+        }
+
+        Ok(environment)
+    }
+
+    /// Evaluate the policy expression for an identity and record the value of
+    /// each sub-expression, without recording a decision in the audit log
+    pub async fn explain(&self, id: &Identifier) -> Result<Explanation> {
+        let environment = self.environment(id).await?;
+        Ok(explain(&self.expression, &environment))
+    }
+
+    /// Returns true if the identity is authorized
+    pub async fn is_identity_authorized(&self, id: Identifier) -> Result<bool> {
+        let environment = self.environment(&id).await?;
+
         // Finally, evaluate the expression and return the result:
-        match eval(&self.expression, &environment) {
+        let (is_authorized, reason) = match eval(&self.expression, &environment) {
             Ok(Expr::Bool(b)) => {
                 log::debug! {
                     policy        = %self.expression,
@@ -132,7 +174,7 @@ impl AbacAccessControl {
                     is_authorized = %b,
                     "policy evaluated"
                 }
-                Ok(b)
+                (b, None)
             }
             Ok(x) => {
                 log::warn! {
@@ -141,7 +183,10 @@ impl AbacAccessControl {
                     expr   = %x,
                     "evaluation did not yield a boolean result"
                 }
-                Ok(false)
+                (
+                    false,
+                    Some(format!("evaluation did not yield a boolean result: {x}")),
+                )
             }
             Err(e) => {
                 log::warn! {
@@ -150,7 +195,36 @@ impl AbacAccessControl {
                     err    = %e,
                     "policy evaluation failed"
                 }
-                Ok(false)
+                (false, Some(format!("policy evaluation failed: {e}")))
+            }
+        };
+
+        if !is_authorized && log::enabled!(log::Level::DEBUG) {
+            log::debug! {
+                id          = %id,
+                explanation = %explain(&self.expression, &environment),
+                "access denied"
+            }
+        }
+
+        self.record(Some(id), is_authorized, reason).await;
+        Ok(is_authorized)
+    }
+
+    /// Record a decision in the audit log, if there is one
+    async fn record(&self, subject: Option<Identifier>, allowed: bool, reason: Option<String>) {
+        if let Some(audit_log) = &self.audit_log {
+            let mut decision = Decision::new(allowed)
+                .with_resource_action(self.resource.clone(), self.action.clone())
+                .with_subject(subject)
+                .with_policy(Some(self.expression.clone()));
+            decision.reason = reason;
+            if let Err(e) = audit_log.record(&decision).await {
+                log::warn! {
+                    policy = %self.expression,
+                    err    = %e,
+                    "failed to record an access control decision"
+                }
             }
         }
     }
@@ -168,9 +242,67 @@ impl IncomingAccessControl for AbacAccessControl {
                 policy = %self.expression,
                 "identity identifier not found; access denied"
             }
+            self.record(
+                None,
+                false,
+                Some("identity identifier not found".to_string()),
+            )
+            .await;
             return Ok(false);
         };
 
         self.is_identity_authorized(id).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_core::compat::sync::Mutex;
+    use ockam_identity::{AttributesEntry, IdentitiesStorage, IdentityAttributesWriter};
+
+    #[derive(Default)]
+    struct MemoryAuditLog(Mutex<Vec<Decision>>);
+
+    #[async_trait]
+    impl AuditLog for MemoryAuditLog {
+        async fn record(&self, decision: &Decision) -> Result<()> {
+            self.0.lock().unwrap().push(decision.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_explain_and_audit_log() -> Result<()> {
+        let repository = IdentitiesStorage::create();
+        let id = Identifier::try_from("I0123456789abcdef0123456789abcdef01234567")?;
+        let attributes = BTreeMap::from([(b"role".to_vec(), b"sensor".to_vec())]);
+        repository
+            .put_attributes(&id, AttributesEntry::new(attributes, now()?, None, None))
+            .await?;
+
+        let audit_log = Arc::new(MemoryAuditLog::default());
+        let expr = Expr::from_str(r#"(= subject.role "admin")"#)?;
+        let abac =
+            AbacAccessControl::new(repository, expr, Env::new()).with_audit_log(audit_log.clone());
+        assert!(!abac.is_identity_authorized(id.clone()).await?);
+
+        let explanation = abac.explain(&id).await?;
+        assert!(!explanation.is_authorized());
+        assert_eq!(
+            explanation.environment()["subject.role"].to_string(),
+            r#""sensor""#
+        );
+        assert!(explanation.environment().contains_key("now"));
+
+        // explaining a decision does not record it
+        let decisions = audit_log.0.lock().unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].subject, Some(id));
+        assert!(!decisions[0].allowed);
+        assert!(decisions[0].reason.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{Action, Expr, Resource};
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::Result;
+use ockam_identity::utils::now;
+use ockam_identity::{Identifier, TimestampInSeconds};
+
+/// Access control decision, recorded in an [`AuditLog`]
+#[derive(Debug, Clone)]
+pub struct Decision {
+    /// Time of the decision, if the clock is available
+    pub timestamp: Option<TimestampInSeconds>,
+    /// Resource and action of the policy, if the decision was made for a policy
+    pub resource: Option<Resource>,
+    pub action: Option<Action>,
+    /// Identifier of the sender of the message, if it was sent over a secure channel
+    pub subject: Option<Identifier>,
+    /// Policy expression, if one was evaluated
+    pub policy: Option<Expr>,
+    pub allowed: bool,
+    /// Reason of the decision when the policy was not evaluated to a boolean
+    pub reason: Option<String>,
+}
+
+impl Decision {
+    /// Create a decision taken now
+    pub fn new(allowed: bool) -> Self {
+        Decision {
+            timestamp: now().ok(),
+            resource: None,
+            action: None,
+            subject: None,
+            policy: None,
+            allowed,
+            reason: None,
+        }
+    }
+
+    pub fn with_resource_action(
+        mut self,
+        resource: Option<Resource>,
+        action: Option<Action>,
+    ) -> Self {
+        self.resource = resource;
+        self.action = action;
+        self
+    }
+
+    pub fn with_subject(mut self, subject: Option<Identifier>) -> Self {
+        self.subject = subject;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Option<Expr>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_reason<S: Into<String>>(mut self, reason: S) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// Log recording the decisions of the access controls evaluating policies
+#[async_trait]
+pub trait AuditLog: Send + Sync + 'static {
+    /// Record an access control decision
+    async fn record(&self, decision: &Decision) -> Result<()>;
+}
+
+/// Audit log emitting each decision as a structured event
+/// with the `ockam_abac::audit` target
+#[derive(Debug, Clone, Default)]
+pub struct TracingAuditLog;
+
+#[async_trait]
+impl AuditLog for TracingAuditLog {
+    async fn record(&self, decision: &Decision) -> Result<()> {
+        tracing::info! {
+            target:    "ockam_abac::audit",
+            timestamp = ?decision.timestamp.map(|t| t.0),
+            resource  = ?decision.resource.as_ref().map(|r| r.as_str()),
+            action    = ?decision.action.as_ref().map(|a| a.as_str()),
+            subject   = ?decision.subject.as_ref().map(|s| s.to_string()),
+            policy    = ?decision.policy.as_ref().map(|p| p.to_string()),
+            reason    = ?decision.reason,
+            allowed   = %decision.allowed,
+            "access control decision"
+        }
+        Ok(())
+    }
+}
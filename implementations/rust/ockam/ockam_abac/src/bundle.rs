@@ -0,0 +1,683 @@
+use crate::{Action, Expr, PolicyStorage, Resource};
+use core::fmt;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::format;
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_identity::models::{
+    CredentialSignature, Identifier, PurposeKeyAttestation, PurposePublicKey, VersionedData,
+};
+use ockam_identity::storage::{InMemoryStorage, Storage};
+use ockam_identity::{Identities, IdentityError, Purpose};
+use tracing::info;
+
+const BUNDLES_ID: &str = "policy_bundles";
+const CURRENT_VERSION_KEY: &str = "current";
+
+/// Domain separation tag prepended to the [`SignedPolicyBundle`] data before it is hashed and signed.
+/// Credentials are signed with the same Purpose Key, so a signature over one of them
+/// must never be accepted for a policy bundle
+pub const POLICY_BUNDLE_SIGNATURE_DOMAIN: &[u8] = b"ockam/policy_bundle/v1";
+
+/// Versioned set of policies, applied to a node as a whole
+#[derive(Debug, Clone, Default, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PolicyBundle {
+    #[n(1)] version: u64,
+    #[n(2)] policies: Vec<BundledPolicy>,
+}
+
+/// Policy of a resource and action in a [`PolicyBundle`]
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct BundledPolicy {
+    #[n(1)] resource: Resource,
+    #[n(2)] action: Action,
+    #[n(3)] expression: Expr,
+}
+
+impl BundledPolicy {
+    pub fn new(resource: Resource, action: Action, expression: Expr) -> Self {
+        Self {
+            resource,
+            action,
+            expression,
+        }
+    }
+
+    pub fn resource(&self) -> &Resource {
+        &self.resource
+    }
+
+    pub fn action(&self) -> &Action {
+        &self.action
+    }
+
+    pub fn expression(&self) -> &Expr {
+        &self.expression
+    }
+}
+
+impl PolicyBundle {
+    /// Create a bundle, sorted by resource and action.
+    /// When a resource and action appear several times, the last policy is kept
+    pub fn new(version: u64, policies: Vec<BundledPolicy>) -> Self {
+        let policies = policies
+            .into_iter()
+            .map(|p| ((p.resource.clone(), p.action.clone()), p))
+            .collect::<BTreeMap<_, _>>()
+            .into_values()
+            .collect();
+        Self { version, policies }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn policies(&self) -> &[BundledPolicy] {
+        &self.policies
+    }
+
+    /// Return the changes turning the policies of this bundle into the policies of another bundle
+    pub fn diff(&self, other: &PolicyBundle) -> Vec<PolicyChange> {
+        let current: BTreeMap<_, _> = self
+            .policies
+            .iter()
+            .map(|p| ((&p.resource, &p.action), p))
+            .collect();
+        let target: BTreeMap<_, _> = other
+            .policies
+            .iter()
+            .map(|p| ((&p.resource, &p.action), p))
+            .collect();
+
+        let mut changes = Vec::new();
+        for (key, policy) in &current {
+            match target.get(key) {
+                None => changes.push(PolicyChange::Removed((*policy).clone())),
+                Some(new) if !policy.expression.equals(&new.expression).unwrap_or(false) => changes
+                    .push(PolicyChange::Changed {
+                        resource: policy.resource.clone(),
+                        action: policy.action.clone(),
+                        from: policy.expression.clone(),
+                        to: new.expression.clone(),
+                    }),
+                Some(_) => {}
+            }
+        }
+        for (key, policy) in &target {
+            if !current.contains_key(key) {
+                changes.push(PolicyChange::Added((*policy).clone()))
+            }
+        }
+        changes
+    }
+
+    fn entries(&self) -> Vec<(Resource, Action, Expr)> {
+        self.policies
+            .iter()
+            .map(|p| (p.resource.clone(), p.action.clone(), p.expression.clone()))
+            .collect()
+    }
+}
+
+/// Difference between the policies of two bundles
+#[derive(Debug, Clone)]
+pub enum PolicyChange {
+    Added(BundledPolicy),
+    Removed(BundledPolicy),
+    Changed {
+        resource: Resource,
+        action: Action,
+        from: Expr,
+        to: Expr,
+    },
+}
+
+impl fmt::Display for PolicyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyChange::Added(p) => write!(f, "+ {} {} {}", p.resource, p.action, p.expression),
+            PolicyChange::Removed(p) => {
+                write!(f, "- {} {} {}", p.resource, p.action, p.expression)
+            }
+            PolicyChange::Changed {
+                resource,
+                action,
+                from,
+                to,
+            } => write!(f, "~ {resource} {action} {from} -> {to}"),
+        }
+    }
+}
+
+/// [`PolicyBundle`] signed with the Credentials Purpose Key of an identity,
+/// along with the [`PurposeKeyAttestation`] used to verify it
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SignedPolicyBundle {
+    /// CBOR serialized [`VersionedData`] where VersionedData::data is CBOR serialized
+    /// [`PolicyBundleData`]
+    #[cbor(with = "minicbor::bytes")]
+    #[n(1)] data: Vec<u8>,
+    /// Signature over [`POLICY_BUNDLE_SIGNATURE_DOMAIN`] followed by the data field
+    #[n(2)] signature: CredentialSignature,
+    #[n(3)] purpose_key_attestation: PurposeKeyAttestation,
+}
+
+/// Data signed in a [`SignedPolicyBundle`]: the bundle and the identifier of the node
+/// it must be applied to, so that it can not be replayed to other nodes
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct PolicyBundleData {
+    #[n(1)] target: Identifier,
+    #[n(2)] bundle: PolicyBundle,
+}
+
+impl SignedPolicyBundle {
+    /// Sign a bundle, to be applied to the node with the target identifier,
+    /// with the Credentials Purpose Key of an identity
+    pub async fn sign(
+        identities: &Identities,
+        signer: &Identifier,
+        target: &Identifier,
+        bundle: &PolicyBundle,
+    ) -> Result<Self> {
+        let purpose_key = identities
+            .purpose_keys()
+            .purpose_keys_creation()
+            .get_or_create_purpose_key(signer, Purpose::Credentials)
+            .await?;
+
+        let data = minicbor::to_vec(VersionedData {
+            version: 1,
+            data: minicbor::to_vec(PolicyBundleData {
+                target: target.clone(),
+                bundle: bundle.clone(),
+            })?,
+        })?;
+
+        let vault = identities.vault();
+        let data_hash = vault
+            .verifying_vault
+            .sha256(&Self::signed_data(&data))
+            .await?;
+        let signature = vault
+            .credential_vault
+            .sign(purpose_key.key_id(), &data_hash)
+            .await?;
+        let signature = CredentialSignature::try_from_signature(signature, purpose_key.stype())?;
+
+        Ok(Self {
+            data,
+            signature,
+            purpose_key_attestation: purpose_key.attestation().clone(),
+        })
+    }
+
+    /// Verify that the bundle was signed by one of the given identities, to be applied
+    /// to the node with the target identifier, and return the signer and the bundle
+    pub async fn verify(
+        &self,
+        identities: &Identities,
+        signers: &[Identifier],
+        target: &Identifier,
+    ) -> Result<(Identifier, PolicyBundle)> {
+        let purpose_key_data = identities
+            .purpose_keys()
+            .purpose_keys_verification()
+            .verify_purpose_key_attestation(None, &self.purpose_key_attestation)
+            .await?;
+
+        if !signers.contains(&purpose_key_data.subject) {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                format!(
+                    "the policy bundle is signed by {}, which is not trusted",
+                    purpose_key_data.subject
+                ),
+            ));
+        }
+
+        let public_key = match purpose_key_data.public_key {
+            PurposePublicKey::CredentialSigningKey(public_key) => public_key,
+            PurposePublicKey::SecureChannelStaticKey(_) => {
+                return Err(IdentityError::InvalidKeyType.into())
+            }
+        };
+
+        let vault = identities.vault();
+        let data_hash = vault
+            .verifying_vault
+            .sha256(&Self::signed_data(&self.data))
+            .await?;
+        if !vault
+            .verifying_vault
+            .verify(
+                &public_key.into(),
+                &data_hash,
+                &self.signature.clone().into(),
+            )
+            .await?
+        {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                "the signature of the policy bundle is invalid",
+            ));
+        }
+
+        let versioned_data: VersionedData = minicbor::decode(&self.data)?;
+        if versioned_data.version != 1 {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Unsupported,
+                "unknown policy bundle version",
+            ));
+        }
+
+        let data: PolicyBundleData = minicbor::decode(&versioned_data.data)?;
+        if &data.target != target {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                format!(
+                    "the policy bundle must be applied to the node {}, not {target}",
+                    data.target
+                ),
+            ));
+        }
+
+        Ok((purpose_key_data.subject, data.bundle))
+    }
+
+    /// Bytes signed for the given data: the policy bundle domain separation tag,
+    /// followed by the data
+    fn signed_data(data: &[u8]) -> Vec<u8> {
+        let mut signed_data = POLICY_BUNDLE_SIGNATURE_DOMAIN.to_vec();
+        signed_data.extend_from_slice(data);
+        signed_data
+    }
+}
+
+/// Policy bundles applied to a [`PolicyStorage`], persisted in a [`Storage`] so that a
+/// previous version can be restored.
+///
+/// The policies set before the first bundle is applied are kept as version 0.
+///
+/// WARNING: the bundles are expected to be applied by a single worker
+pub struct PolicyBundles {
+    storage: Arc<dyn Storage>,
+    policies: Arc<dyn PolicyStorage>,
+}
+
+impl PolicyBundles {
+    /// Create policy bundles persisted in a storage
+    pub fn new(storage: Arc<dyn Storage>, policies: Arc<dyn PolicyStorage>) -> Self {
+        Self { storage, policies }
+    }
+
+    /// Create policy bundles kept in memory
+    pub fn create(policies: Arc<dyn PolicyStorage>) -> Self {
+        Self::new(InMemoryStorage::create(), policies)
+    }
+
+    /// Return the policies currently enforced, with the version of the last bundle applied.
+    /// The policies may differ from that bundle if they were modified individually since then
+    pub async fn current(&self) -> Result<PolicyBundle> {
+        let version = self.current_version().await?;
+        let policies = self
+            .policies
+            .all_policies()
+            .await?
+            .into_iter()
+            .map(|(r, a, e)| BundledPolicy::new(r, a, e))
+            .collect();
+        Ok(PolicyBundle::new(version, policies))
+    }
+
+    /// Return the versions of the applied bundles, in increasing order
+    pub async fn versions(&self) -> Result<Vec<u64>> {
+        let mut versions: Vec<u64> = self
+            .storage
+            .keys(BUNDLES_ID)
+            .await?
+            .iter()
+            .filter_map(|k| k.parse().ok())
+            .collect();
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Replace all the policies with the policies of a bundle.
+    /// The bundle version must be greater than the version of all the bundles applied before
+    pub async fn apply(&self, bundle: PolicyBundle) -> Result<()> {
+        let versions = self.versions().await?;
+        let latest = versions.last().copied().unwrap_or_default();
+        if bundle.version <= latest {
+            return Err(Error::new(
+                Origin::Application,
+                Kind::Conflict,
+                format!(
+                    "the policy bundle version {} must be greater than {latest}",
+                    bundle.version
+                ),
+            ));
+        }
+        // the bundles are saved once they are enforced, so that a bundle which could not
+        // be activated can be applied again
+        let initial = if versions.is_empty() {
+            Some(self.current().await?)
+        } else {
+            None
+        };
+        self.activate(&bundle).await?;
+        if let Some(initial) = initial {
+            self.save(&initial).await?;
+        }
+        self.save(&bundle).await?;
+        info!(version = %bundle.version, "applied a policy bundle");
+        Ok(())
+    }
+
+    /// Apply again a bundle applied before, by default the one preceding the current version
+    pub async fn rollback(&self, version: Option<u64>) -> Result<PolicyBundle> {
+        let version = match version {
+            Some(version) => version,
+            None => {
+                let current = self.current_version().await?;
+                self.versions()
+                    .await?
+                    .into_iter()
+                    .filter(|v| *v < current)
+                    .last()
+                    .ok_or_else(|| {
+                        Error::new(
+                            Origin::Application,
+                            Kind::NotFound,
+                            format!("there is no policy bundle before version {current}"),
+                        )
+                    })?
+            }
+        };
+        let bundle = match self.storage.get(&Self::key(version), BUNDLES_ID).await? {
+            Some(data) => minicbor::decode(&data)?,
+            None => {
+                return Err(Error::new(
+                    Origin::Application,
+                    Kind::NotFound,
+                    format!("the policy bundle version {version} was not applied"),
+                ))
+            }
+        };
+        self.activate(&bundle).await?;
+        info!(%version, "rolled back to a policy bundle");
+        Ok(bundle)
+    }
+
+    async fn current_version(&self) -> Result<u64> {
+        match self.storage.get(CURRENT_VERSION_KEY, BUNDLES_ID).await? {
+            Some(data) => Ok(minicbor::decode(&data)?),
+            None => Ok(0),
+        }
+    }
+
+    async fn save(&self, bundle: &PolicyBundle) -> Result<()> {
+        self.storage
+            .set(
+                &Self::key(bundle.version),
+                BUNDLES_ID.into(),
+                minicbor::to_vec(bundle)?,
+            )
+            .await
+    }
+
+    async fn activate(&self, bundle: &PolicyBundle) -> Result<()> {
+        self.policies.set_all_policies(&bundle.entries()).await?;
+        self.storage
+            .set(
+                CURRENT_VERSION_KEY,
+                BUNDLES_ID.into(),
+                minicbor::to_vec(bundle.version)?,
+            )
+            .await
+    }
+
+    fn key(version: u64) -> String {
+        format!("{version}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{eq, ident, str};
+    use crate::mem::Memory;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use ockam_core::async_trait;
+    use ockam_core::compat::boxed::Box;
+    use ockam_core::compat::string::ToString;
+    use ockam_identity::identities;
+
+    fn policy(resource: &str, action: &str, value: &str) -> BundledPolicy {
+        BundledPolicy::new(
+            Resource::new(resource),
+            Action::new(action),
+            eq([ident("subject.role"), str(value)]),
+        )
+    }
+
+    #[test]
+    fn test_diff() {
+        let current = PolicyBundle::new(
+            1,
+            vec![
+                policy("outlet", "handle_message", "admin"),
+                policy("inlet", "handle_message", "user"),
+            ],
+        );
+        let target = PolicyBundle::new(
+            2,
+            vec![
+                policy("outlet", "handle_message", "operator"),
+                policy("relay", "claim", "admin"),
+            ],
+        );
+        let changes: Vec<String> = current
+            .diff(&target)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                r#"- inlet handle_message (= subject.role "user")"#,
+                r#"~ outlet handle_message (= subject.role "admin") -> (= subject.role "operator")"#,
+                r#"+ relay claim (= subject.role "admin")"#,
+            ]
+        );
+        assert!(target.diff(&target).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_and_rollback() -> Result<()> {
+        let policies = Arc::new(Memory::new());
+        let initial = policy("outlet", "handle_message", "admin");
+        policies
+            .set_policy(initial.resource(), initial.action(), initial.expression())
+            .await?;
+
+        let bundles = PolicyBundles::create(policies.clone());
+        let bundle = PolicyBundle::new(1, vec![policy("relay", "claim", "admin")]);
+        bundles.apply(bundle.clone()).await?;
+        assert!(bundles.apply(bundle).await.is_err());
+        assert_eq!(bundles.current().await?.version(), 1);
+        assert_eq!(policies.all_policies().await?.len(), 1);
+        assert!(policies
+            .get_policy(initial.resource(), initial.action())
+            .await?
+            .is_none());
+
+        // the policies set before the first bundle are restored
+        let restored = bundles.rollback(None).await?;
+        assert_eq!(restored.version(), 0);
+        assert!(policies
+            .get_policy(initial.resource(), initial.action())
+            .await?
+            .is_some());
+        assert!(bundles.rollback(None).await.is_err());
+        assert_eq!(bundles.versions().await?, vec![0, 1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_not_activated_is_not_saved() -> Result<()> {
+        let policies = Arc::new(FailingPolicies {
+            policies: Memory::new(),
+            fail: AtomicBool::new(true),
+        });
+        let bundles = PolicyBundles::create(policies.clone());
+        let bundle = PolicyBundle::new(1, vec![policy("relay", "claim", "admin")]);
+        assert!(bundles.apply(bundle.clone()).await.is_err());
+        assert!(bundles.versions().await?.is_empty());
+        assert_eq!(bundles.current().await?.version(), 0);
+
+        // the bundle can be applied once the policies can be stored
+        policies.fail.store(false, Ordering::Relaxed);
+        bundles.apply(bundle).await?;
+        assert_eq!(bundles.versions().await?, vec![0, 1]);
+        assert_eq!(bundles.current().await?.version(), 1);
+        Ok(())
+    }
+
+    /// Policies which can not be replaced while `fail` is set
+    struct FailingPolicies {
+        policies: Memory,
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl PolicyStorage for FailingPolicies {
+        async fn get_policy(&self, r: &Resource, a: &Action) -> Result<Option<Expr>> {
+            self.policies.get_policy(r, a).await
+        }
+
+        async fn set_policy(&self, r: &Resource, a: &Action, c: &Expr) -> Result<()> {
+            self.policies.set_policy(r, a, c).await
+        }
+
+        async fn del_policy(&self, r: &Resource, a: &Action) -> Result<()> {
+            self.policies.del_policy(r, a).await
+        }
+
+        async fn policies(&self, r: &Resource) -> Result<Vec<(Action, Expr)>> {
+            self.policies.policies(r).await
+        }
+
+        async fn all_policies(&self) -> Result<Vec<(Resource, Action, Expr)>> {
+            self.policies.all_policies().await
+        }
+
+        async fn set_all_policies(&self, policies: &[(Resource, Action, Expr)]) -> Result<()> {
+            if self.fail.load(Ordering::Relaxed) {
+                return Err(Error::new(
+                    Origin::Application,
+                    Kind::Io,
+                    "the policies can not be stored",
+                ));
+            }
+            self.policies.set_all_policies(policies).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_bundle() -> Result<()> {
+        let identities = identities();
+        let signer = identities.identities_creation().create_identity().await?;
+        let other = identities.identities_creation().create_identity().await?;
+        let node = identities.identities_creation().create_identity().await?;
+
+        let bundle = PolicyBundle::new(3, vec![policy("relay", "claim", "admin")]);
+        let signed =
+            SignedPolicyBundle::sign(&identities, signer.identifier(), node.identifier(), &bundle)
+                .await?;
+        let (identifier, verified) = signed
+            .verify(
+                &identities,
+                &[signer.identifier().clone()],
+                node.identifier(),
+            )
+            .await?;
+        assert_eq!(&identifier, signer.identifier());
+        assert_eq!(verified.version(), 3);
+        assert!(signed
+            .verify(
+                &identities,
+                &[other.identifier().clone()],
+                node.identifier()
+            )
+            .await
+            .is_err());
+
+        // the bundle can not be applied to another node
+        assert!(signed
+            .verify(
+                &identities,
+                &[signer.identifier().clone()],
+                other.identifier(),
+            )
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_signature_without_domain_is_rejected() -> Result<()> {
+        let identities = identities();
+        let signer = identities.identities_creation().create_identity().await?;
+        let bundle = PolicyBundle::new(1, vec![policy("relay", "claim", "admin")]);
+        let signed = SignedPolicyBundle::sign(
+            &identities,
+            signer.identifier(),
+            signer.identifier(),
+            &bundle,
+        )
+        .await?;
+
+        // sign the same data as a credential would be signed, without the domain separation tag
+        let purpose_key = identities
+            .purpose_keys()
+            .purpose_keys_creation()
+            .get_or_create_purpose_key(signer.identifier(), Purpose::Credentials)
+            .await?;
+        let vault = identities.vault();
+        let data_hash = vault.verifying_vault.sha256(&signed.data).await?;
+        let signature = vault
+            .credential_vault
+            .sign(purpose_key.key_id(), &data_hash)
+            .await?;
+        let forged = SignedPolicyBundle {
+            signature: CredentialSignature::try_from_signature(signature, purpose_key.stype())?,
+            ..signed
+        };
+        assert!(forged
+            .verify(
+                &identities,
+                &[signer.identifier().clone()],
+                signer.identifier(),
+            )
+            .await
+            .is_err());
+        Ok(())
+    }
+}
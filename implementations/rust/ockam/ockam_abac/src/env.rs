@@ -3,6 +3,8 @@ use crate::expr::Expr;
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::compat::string::{String, ToString};
 
+const SECONDS_PER_DAY: u64 = 24 * 3600;
+
 #[derive(Debug, Clone, Default)]
 pub struct Env(BTreeMap<String, Expr>);
 
@@ -29,6 +31,23 @@ impl Env {
         self
     }
 
+    /// Add the current time, given in seconds since the Unix epoch, with the
+    /// following integer bindings, unless they are already present:
+    ///
+    /// - `now`: the number of seconds since the Unix epoch
+    /// - `now.hour`: the hour of the day in UTC, from 0 to 23
+    /// - `now.weekday`: the day of the week in UTC, from 0 (Monday) to 6 (Sunday)
+    pub fn put_time(&mut self, now: u64) -> &mut Self {
+        let days = now / SECONDS_PER_DAY;
+        let hour = (now % SECONDS_PER_DAY) / 3600;
+        // the Unix epoch is a Thursday
+        let weekday = (days + 3) % 7;
+        for (k, v) in [("now", now), ("now.hour", hour), ("now.weekday", weekday)] {
+            self.0.entry(k.to_string()).or_insert(Expr::Int(v as i64));
+        }
+        self
+    }
+
     pub fn del(&mut self, k: &str) {
         self.0.remove(k);
     }
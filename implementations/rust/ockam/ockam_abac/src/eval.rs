@@ -2,12 +2,23 @@ use core::cmp::Ordering;
 
 use crate::env::Env;
 use crate::error::EvalError;
+use crate::explain::TraceEntry;
 use crate::expr::{unit, Expr};
 use ockam_core::compat::string::ToString;
 use ockam_core::compat::vec::Vec;
 
-#[rustfmt::skip]
 pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
+    eval_with_trace(expr, env, None)
+}
+
+/// Evaluate an expression and, if a trace is given, record the value of each
+/// list expression and identifier after it has been evaluated.
+#[rustfmt::skip]
+pub(crate) fn eval_with_trace(
+    expr: &Expr,
+    env: &Env,
+    mut trace: Option<&mut Vec<TraceEntry>>
+) -> Result<Expr, EvalError> {
     /// A stack operation.
     ///
     /// Each operation uses the arguments stack as input. The number of
@@ -23,7 +34,12 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
         Gt(usize),
         Lt(usize),
         Member,
+        StartsWith,
+        EndsWith,
+        Contains,
+        Matches,
         Seq(usize),
+        Trace(&'a Expr),
     }
 
     // Control stack.
@@ -31,15 +47,32 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
     // Arguments stack.
     let mut args: Vec<Expr> = Vec::new();
 
+    // Nesting level of the traced expression being evaluated.
+    let mut depth: u32 = 0;
+
     // Start with the toplevel expression.
     ctrl.push(Op::Eval(expr));
 
     while let Some(x) = ctrl.pop() {
         match x {
-            Op::Eval(Expr::Ident(id)) => ctrl.push(Op::Eval(env.get(id)?)),
-            Op::Eval(Expr::List(xs))  => match &xs[..] {
+            Op::Eval(e @ Expr::Ident(id)) => {
+                let value = env.get(id)?;
+                if trace.is_some() {
+                    ctrl.push(Op::Trace(e));
+                    depth += 1
+                }
+                ctrl.push(Op::Eval(value))
+            }
+            Op::Eval(e @ Expr::List(xs)) => match &xs[..] {
                 []                    => args.push(unit()),
                 [Expr::Ident(id), ..] => {
+                    // The trace operation is put behind the operation so that
+                    // the top-level element of the arg stack is the value of
+                    // the expression when it is popped off the control stack.
+                    if trace.is_some() {
+                        ctrl.push(Op::Trace(e));
+                        depth += 1
+                    }
                     let nargs = xs.len() - 1; // number of arguments
                     match id.as_str() {
                         "and" => {
@@ -132,6 +165,34 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
                             }
                             ctrl.push(Op::Member)
                         }
+                        "starts-with?" => {
+                            if nargs != 2 {
+                                let msg = "'starts-with?' requires two arguments";
+                                return Err(EvalError::malformed(msg))
+                            }
+                            ctrl.push(Op::StartsWith)
+                        }
+                        "ends-with?" => {
+                            if nargs != 2 {
+                                let msg = "'ends-with?' requires two arguments";
+                                return Err(EvalError::malformed(msg))
+                            }
+                            ctrl.push(Op::EndsWith)
+                        }
+                        "contains?" => {
+                            if nargs != 2 {
+                                let msg = "'contains?' requires two arguments";
+                                return Err(EvalError::malformed(msg))
+                            }
+                            ctrl.push(Op::Contains)
+                        }
+                        "matches?" => {
+                            if nargs != 2 {
+                                let msg = "'matches?' requires two arguments";
+                                return Err(EvalError::malformed(msg))
+                            }
+                            ctrl.push(Op::Matches)
+                        }
                         "exists?" => {
                             let mut b = true;
                             for x in &xs[1 ..] {
@@ -228,13 +289,34 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
                     }
                 }
             }
-            Op::Eq(n) => eval_predicate(n, &mut args, |x, y| x.equals(y))?,
+            Op::Eq(n) => eval_predicate(n, &mut args, |x, y| match coerce(x, y) {
+                Some((x, y)) => x.equals(&y),
+                None         => x.equals(y)
+            })?,
             Op::Lt(n) => eval_predicate(n, &mut args, |x, y| {
-                x.compare(y).map(|o| o == Some(Ordering::Less))
+                compare(x, y).map(|o| o == Some(Ordering::Less))
             })?,
             Op::Gt(n) => eval_predicate(n, &mut args, |x, y| {
-                x.compare(y).map(|o| o == Some(Ordering::Greater))
+                compare(x, y).map(|o| o == Some(Ordering::Greater))
             })?,
+            Op::StartsWith => {
+                eval_str_predicate(&mut args, "'starts-with?' expects string arguments", |s, p| {
+                    Ok(s.starts_with(p))
+                })?
+            }
+            Op::EndsWith => {
+                eval_str_predicate(&mut args, "'ends-with?' expects string arguments", |s, p| {
+                    Ok(s.ends_with(p))
+                })?
+            }
+            Op::Contains => {
+                eval_str_predicate(&mut args, "'contains?' expects string arguments", |s, p| {
+                    Ok(s.contains(p))
+                })?
+            }
+            Op::Matches => {
+                eval_str_predicate(&mut args, "'matches?' expects string arguments", is_match)?
+            }
             Op::Member => {
                 let s = pop(&mut args);
                 let y = pop(&mut args);
@@ -259,6 +341,13 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
                 let s = args.split_off(args.len() - n);
                 args.push(Expr::Seq(s))
             }
+            Op::Trace(e) => {
+                depth -= 1;
+                if let Some(t) = &mut trace {
+                    let value = args.last().expect("stack is not empty").clone();
+                    t.push(TraceEntry::new(depth, e.clone(), value))
+                }
+            }
         }
     }
 
@@ -292,3 +381,184 @@ where
     args.push(Expr::Bool(b));
     Ok(())
 }
+
+/// Evaluate a predicate against the two topmost arguments, which must be strings.
+fn eval_str_predicate<F>(args: &mut Vec<Expr>, msg: &'static str, f: F) -> Result<(), EvalError>
+where
+    F: Fn(&str, &str) -> Result<bool, EvalError>,
+{
+    let y = pop(args);
+    let x = pop(args);
+    match (x, y) {
+        (Expr::Str(x), Expr::Str(y)) => {
+            args.push(Expr::Bool(f(&x, &y)?));
+            Ok(())
+        }
+        (Expr::Str(_), other) | (other, _) => Err(EvalError::InvalidType(other, msg)),
+    }
+}
+
+/// Check if a string matches a regular expression.
+#[cfg(feature = "std")]
+fn is_match(s: &str, pattern: &str) -> Result<bool, EvalError> {
+    Ok(compiled_regex(pattern)?.is_match(s))
+}
+
+/// Maximum number of compiled regular expressions kept in memory.
+#[cfg(feature = "std")]
+const MAX_COMPILED_REGEXES: usize = 1024;
+
+/// Return the compiled regular expression of a pattern.
+///
+/// The regular expressions are compiled once, usually when a policy is parsed,
+/// and then reused for all the evaluations of the policy.
+#[cfg(feature = "std")]
+pub(crate) fn compiled_regex(pattern: &str) -> Result<regex::Regex, EvalError> {
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_core::compat::sync::Mutex;
+    use once_cell::race::OnceBox;
+
+    static REGEXES: OnceBox<Mutex<BTreeMap<String, regex::Regex>>> = OnceBox::new();
+    let regexes = REGEXES.get_or_init(|| Box::new(Mutex::new(BTreeMap::new())));
+
+    if let Some(regex) = regexes.lock().unwrap().get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = regex::Regex::new(pattern).map_err(|e| {
+        EvalError::malformed(format!("invalid regular expression {pattern:?}: {e}"))
+    })?;
+    let mut regexes = regexes.lock().unwrap();
+    // patterns computed from attributes are not bounded, so the cache is emptied when full
+    if regexes.len() >= MAX_COMPILED_REGEXES {
+        regexes.clear();
+    }
+    regexes.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+#[cfg(not(feature = "std"))]
+fn is_match(_s: &str, _pattern: &str) -> Result<bool, EvalError> {
+    Err(EvalError::Unknown("matches?".to_string()))
+}
+
+/// Like `Expr::compare` but numbers can be compared to strings holding numbers.
+fn compare(x: &Expr, y: &Expr) -> Result<Option<Ordering>, EvalError> {
+    match coerce(x, y) {
+        Some((x, y)) => x.compare(&y),
+        None => x.compare(y),
+    }
+}
+
+/// Convert the arguments of a comparison to the same numeric type.
+///
+/// Subject attributes are always strings, so a string is converted to a number
+/// when it is compared to a number, and an integer is converted to a float
+/// when it is compared to a float. Return `None` if no conversion applies.
+fn coerce(x: &Expr, y: &Expr) -> Option<(Expr, Expr)> {
+    match (x, y) {
+        (Expr::Int(a), Expr::Float(b)) => Some((Expr::Float(*a as f64), Expr::Float(*b))),
+        (Expr::Float(a), Expr::Int(b)) => Some((Expr::Float(*a), Expr::Float(*b as f64))),
+        (Expr::Str(s), Expr::Int(_) | Expr::Float(_)) => {
+            let x = parse_number(s)?;
+            Some(coerce(&x, y).unwrap_or((x, y.clone())))
+        }
+        (Expr::Int(_) | Expr::Float(_), Expr::Str(s)) => {
+            let y = parse_number(s)?;
+            Some(coerce(x, &y).unwrap_or((x.clone(), y)))
+        }
+        _ => None,
+    }
+}
+
+fn parse_number(s: &str) -> Option<Expr> {
+    let s = s.trim();
+    if let Ok(i) = s.parse::<i64>() {
+        Some(Expr::Int(i))
+    } else {
+        s.parse::<f64>().ok().map(Expr::Float)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::{int, str};
+    use crate::{eval, parse, Env, EvalError, Expr};
+
+    fn run(input: &str, env: &Env) -> Result<Expr, EvalError> {
+        eval(&parse(input).unwrap().unwrap(), env)
+    }
+
+    fn is_true(input: &str, env: &Env) -> bool {
+        run(input, env).unwrap().is_true()
+    }
+
+    #[test]
+    fn string_operators() {
+        let mut env = Env::new();
+        env.put("subject.cluster", str("prod-eu-1"));
+
+        assert!(is_true(r#"(starts-with? subject.cluster "prod-")"#, &env));
+        assert!(!is_true(r#"(starts-with? subject.cluster "dev-")"#, &env));
+        assert!(is_true(r#"(ends-with? subject.cluster "-1")"#, &env));
+        assert!(is_true(r#"(contains? subject.cluster "eu")"#, &env));
+        assert!(!is_true(r#"(contains? subject.cluster "us")"#, &env));
+        assert!(is_true(
+            r#"(matches? subject.cluster "^prod-[a-z]+-[0-9]+$")"#,
+            &env
+        ));
+        assert!(!is_true(r#"(matches? subject.cluster "^dev")"#, &env));
+
+        assert!(matches!(
+            run(r#"(matches? subject.cluster "[")"#, &env),
+            Err(EvalError::Malformed(_))
+        ));
+        assert!(matches!(
+            run("(starts-with? subject.cluster 1)", &env),
+            Err(EvalError::InvalidType(..))
+        ));
+        assert!(matches!(
+            run(r#"(contains? "prod")"#, &env),
+            Err(EvalError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn numeric_coercion() {
+        let mut env = Env::new();
+        env.put("subject.level", str("3"));
+        env.put("subject.ratio", str("0.5"));
+        env.put("subject.name", str("alice"));
+
+        assert!(is_true("(> subject.level 2)", &env));
+        assert!(is_true("(< 2 subject.level 4)", &env));
+        assert!(is_true("(= subject.level 3)", &env));
+        assert!(is_true("(= subject.level 3.0)", &env));
+        assert!(is_true("(< subject.ratio 1)", &env));
+        assert!(is_true("(!= subject.ratio 0.25)", &env));
+        // strings are still compared as strings
+        assert!(is_true(r#"(= subject.level "3")"#, &env));
+        assert!(matches!(
+            run("(> subject.name 2)", &env),
+            Err(EvalError::TypeMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn time() {
+        // Monday 2023-10-02 14:30:00 UTC
+        let mut env = Env::new();
+        env.put_time(1_696_257_000);
+        assert!(env.get("now").unwrap().equals(&int(1_696_257_000)).unwrap());
+        assert!(is_true(
+            "(and (< now.weekday 5) (> now.hour 8) (< now.hour 18))",
+            &env
+        ));
+
+        // the bindings already present are kept
+        let mut env = Env::new();
+        env.put("now.hour", int(3));
+        env.put_time(1_696_257_000);
+        assert!(is_true("(= now.hour 3)", &env));
+        assert!(is_true("(= now.weekday 0)", &env));
+    }
+}
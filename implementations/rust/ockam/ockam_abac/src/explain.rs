@@ -0,0 +1,186 @@
+use crate::env::Env;
+use crate::eval::eval_with_trace;
+use crate::expr::Expr;
+use core::fmt;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+
+/// Value of a sub-expression, recorded while evaluating a policy expression.
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TraceEntry {
+    /// Nesting level of the sub-expression, 0 for the policy expression itself.
+    #[n(1)] pub depth: u32,
+    #[n(2)] pub expr: Expr,
+    #[n(3)] pub value: Expr,
+}
+
+impl TraceEntry {
+    pub fn new(depth: u32, expr: Expr, value: Expr) -> Self {
+        TraceEntry { depth, expr, value }
+    }
+}
+
+/// Evaluation of a policy expression with the environment it was evaluated in
+/// and the value of each of its sub-expressions.
+///
+/// It is used to understand why a policy grants or denies access.
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Explanation {
+    #[n(1)] expression: Expr,
+    #[n(2)] environment: BTreeMap<String, Expr>,
+    #[n(3)] trace: Vec<TraceEntry>,
+    #[n(4)] result: Option<Expr>,
+    #[n(5)] error: Option<String>,
+}
+
+/// Evaluate an expression and record the value of its sub-expressions.
+///
+/// The evaluation stops at the first error, for example an identifier which is not
+/// bound in the environment because the subject does not have this attribute.
+pub fn explain(expr: &Expr, env: &Env) -> Explanation {
+    let mut trace = Vec::new();
+    let (result, error) = match eval_with_trace(expr, env, Some(&mut trace)) {
+        Ok(x) => (Some(x), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    Explanation {
+        expression: expr.clone(),
+        environment: env
+            .entries()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect(),
+        trace,
+        result,
+        error,
+    }
+}
+
+impl Explanation {
+    /// Return true if the expression evaluated to `true`
+    pub fn is_authorized(&self) -> bool {
+        matches!(self.result, Some(Expr::Bool(true)))
+    }
+
+    pub fn expression(&self) -> &Expr {
+        &self.expression
+    }
+
+    pub fn environment(&self) -> &BTreeMap<String, Expr> {
+        &self.environment
+    }
+
+    /// Return the values of the sub-expressions, in the order in which their
+    /// evaluation completed
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    pub fn result(&self) -> Option<&Expr> {
+        self.result.as_ref()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Return the trace entries with each expression before its sub-expressions
+    pub fn trace_tree(&self) -> Vec<&TraceEntry> {
+        // The trace lists the sub-expressions of an expression right before it,
+        // so they are collected when the expression is found.
+        let mut children: Vec<Vec<usize>> = Vec::with_capacity(self.trace.len());
+        let mut roots: Vec<usize> = Vec::new();
+        for (i, entry) in self.trace.iter().enumerate() {
+            let mut first = roots.len();
+            while first > 0 && self.trace[roots[first - 1]].depth > entry.depth {
+                first -= 1
+            }
+            children.push(roots.split_off(first));
+            roots.push(i)
+        }
+
+        let mut tree = Vec::with_capacity(self.trace.len());
+        let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+        while let Some(i) = stack.pop() {
+            tree.push(&self.trace[i]);
+            stack.extend(children[i].iter().rev())
+        }
+        tree
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Policy: {}", self.expression)?;
+        match (&self.result, &self.error) {
+            (_, Some(e)) => writeln!(f, "Result: error, {e}")?,
+            (Some(r), None) => writeln!(f, "Result: {r}")?,
+            (None, None) => writeln!(f, "Result: none")?,
+        }
+        writeln!(f, "Environment:")?;
+        for (k, v) in &self.environment {
+            writeln!(f, "  {k} = {v}")?
+        }
+        write!(f, "Trace:")?;
+        for entry in self.trace_tree() {
+            let indent = 2 * (entry.depth as usize + 1);
+            write!(f, "\n{:indent$}{} => {}", "", entry.expr, entry.value)?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{int, str};
+    use crate::parse;
+
+    #[test]
+    fn trace_values() {
+        let mut env = Env::new();
+        env.put("subject.role", str("admin"));
+        env.put("subject.level", int(2));
+        let expr = parse(r#"(and (= subject.role "admin") (> subject.level 3))"#)
+            .unwrap()
+            .unwrap();
+
+        let explanation = explain(&expr, &env);
+        assert!(!explanation.is_authorized());
+        assert!(explanation.error().is_none());
+        let trace: Vec<String> = explanation
+            .trace_tree()
+            .iter()
+            .map(|e| format!("{} {} => {}", e.depth, e.expr, e.value))
+            .collect();
+        assert_eq!(
+            trace,
+            vec![
+                r#"0 (and (= subject.role "admin") (> subject.level 3)) => false"#,
+                r#"1 (= subject.role "admin") => true"#,
+                r#"2 subject.role => "admin""#,
+                r#"1 (> subject.level 3) => false"#,
+                r#"2 subject.level => 2"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_attribute() {
+        let expr = parse(r#"(or (= subject.role "admin") subject.superuser)"#)
+            .unwrap()
+            .unwrap();
+        let explanation = explain(&expr, &Env::new());
+        assert!(!explanation.is_authorized());
+        assert_eq!(
+            explanation.error(),
+            Some("unbound identifier: subject.role")
+        );
+        assert!(explanation.trace().is_empty());
+    }
+}
@@ -6,9 +6,12 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+mod audit;
+mod bundle;
 mod env;
 mod error;
 mod eval;
+mod explain;
 mod policy;
 mod traits;
 mod types;
@@ -22,9 +25,15 @@ pub mod mem;
 mod storage;
 
 pub use attribute_access_control::AbacAccessControl;
+pub use audit::{AuditLog, Decision, TracingAuditLog};
+pub use bundle::{
+    BundledPolicy, PolicyBundle, PolicyBundles, PolicyChange, SignedPolicyBundle,
+    POLICY_BUNDLE_SIGNATURE_DOMAIN,
+};
 pub use env::Env;
 pub use error::{EvalError, ParseError};
 pub use eval::eval;
+pub use explain::{explain, Explanation, TraceEntry};
 pub use expr::Expr;
 pub use policy::PolicyAccessControl;
 pub use traits::PolicyStorage;
@@ -33,6 +42,9 @@ pub use types::{Action, Resource, Subject};
 #[cfg(feature = "std")]
 pub use parser::parse;
 
+#[cfg(feature = "sqlite")]
+pub use storage::SqlitePolicyStorage;
+
 #[cfg(not(feature = "std"))]
 pub use ockam_executor::tokio;
 
@@ -58,6 +58,20 @@ impl Inner {
             .insert(a.clone(), p.clone());
     }
 
+    fn all_policies(&self) -> Vec<(Resource, Action, Expr)> {
+        self.policies
+            .iter()
+            .flat_map(|(r, p)| p.iter().map(|(a, e)| (r.clone(), a.clone(), e.clone())))
+            .collect()
+    }
+
+    fn set_all_policies(&mut self, policies: &[(Resource, Action, Expr)]) {
+        self.policies.clear();
+        for (r, a, e) in policies {
+            self.set_policy(r, a, e)
+        }
+    }
+
     fn policies(&self, r: &Resource) -> Vec<(Action, Expr)> {
         if let Some(p) = self.policies.get(r) {
             p.iter()
@@ -88,6 +102,15 @@ impl PolicyStorage for Memory {
     async fn policies(&self, r: &Resource) -> Result<Vec<(Action, Expr)>> {
         Ok(self.inner.write().unwrap().policies(r))
     }
+
+    async fn all_policies(&self) -> Result<Vec<(Resource, Action, Expr)>> {
+        Ok(self.inner.read().unwrap().all_policies())
+    }
+
+    async fn set_all_policies(&self, policies: &[(Resource, Action, Expr)]) -> Result<()> {
+        self.inner.write().unwrap().set_all_policies(policies);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -143,6 +143,13 @@ pub fn parse(s: &str) -> Result<Option<Expr>, ParseError> {
                     }
                 }
                 v.reverse();
+                // Compile the regular expressions ahead of the evaluations. An invalid
+                // regular expression is reported when the expression is evaluated
+                if let [Expr::Ident(op), _, Expr::Str(pattern)] = v.as_slice() {
+                    if op == "matches?" {
+                        let _ = crate::eval::compiled_regex(pattern);
+                    }
+                }
                 ctrl.push(Op::Value(Expr::List(v)));
                 ctrl.push(Op::Next)
             }
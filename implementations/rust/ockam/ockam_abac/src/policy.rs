@@ -1,7 +1,7 @@
 use crate::traits::PolicyStorage;
 use crate::types::{Action, Resource};
 use crate::AbacAccessControl;
-use crate::{Env, Expr};
+use crate::{AuditLog, Decision, Env, Explanation, Expr};
 use core::fmt;
 use core::fmt::{Debug, Formatter};
 use ockam_core::compat::boxed::Box;
@@ -9,7 +9,7 @@ use ockam_core::compat::format;
 use ockam_core::compat::sync::Arc;
 use ockam_core::{async_trait, RelayMessage};
 use ockam_core::{IncomingAccessControl, Result};
-use ockam_identity::IdentitiesRepository;
+use ockam_identity::{Identifier, IdentitiesRepository, IdentitySecureChannelLocalInfo};
 use tracing as log;
 
 /// Evaluates a policy expression against an environment of attributes.
@@ -22,6 +22,7 @@ pub struct PolicyAccessControl {
     policies: Arc<dyn PolicyStorage>,
     repository: Arc<dyn IdentitiesRepository>,
     environment: Env,
+    audit_log: Option<Arc<dyn AuditLog>>,
 }
 
 /// Debug implementation writing out the resource, action and initial environment
@@ -55,6 +56,57 @@ impl PolicyAccessControl {
             policies,
             repository,
             environment: env,
+            audit_log: None,
+        }
+    }
+
+    /// Record each decision of this AccessControl in an audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Evaluate the policy for an identity and record the value of each sub-expression,
+    /// without granting access. Return `None` if no policy exists for the resource and action
+    pub async fn explain(&self, id: &Identifier) -> Result<Option<Explanation>> {
+        match self
+            .policies
+            .get_policy(&self.resource, &self.action)
+            .await?
+        {
+            Some(expr) => Ok(Some(self.abac(expr).explain(id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    fn abac(&self, expr: Expr) -> AbacAccessControl {
+        let abac = AbacAccessControl::new(self.repository.clone(), expr, self.environment.clone())
+            .with_resource_action(self.resource.clone(), self.action.clone());
+        match &self.audit_log {
+            Some(audit_log) => abac.with_audit_log(audit_log.clone()),
+            None => abac,
+        }
+    }
+
+    /// Record a decision taken without evaluating the policy, if there is an audit log
+    async fn record(&self, msg: &RelayMessage, policy: Option<Expr>, allowed: bool, reason: &str) {
+        if let Some(audit_log) = &self.audit_log {
+            let subject = IdentitySecureChannelLocalInfo::find_info(msg.local_message())
+                .ok()
+                .map(|info| info.their_identity_id());
+            let decision = Decision::new(allowed)
+                .with_resource_action(Some(self.resource.clone()), Some(self.action.clone()))
+                .with_subject(subject)
+                .with_policy(policy)
+                .with_reason(reason);
+            if let Err(e) = audit_log.record(&decision).await {
+                log::warn! {
+                    resource = %self.resource,
+                    action   = %self.action,
+                    err      = %e,
+                    "failed to record an access control decision"
+                }
+            }
         }
     }
 }
@@ -71,6 +123,7 @@ impl IncomingAccessControl for PolicyAccessControl {
             if let Expr::Bool(b) = expr {
                 // If the policy is a constant there is no need to populate
                 // the environment or look for message metadata.
+                self.record(msg, Some(expr), b, "constant policy").await;
                 return Ok(b);
             } else {
                 expr
@@ -82,11 +135,10 @@ impl IncomingAccessControl for PolicyAccessControl {
                 action   = %self.action,
                 "no policy found; access denied"
             }
+            self.record(msg, None, false, "no policy found").await;
             return Ok(false);
         };
 
-        AbacAccessControl::new(self.repository.clone(), expr, self.environment.clone())
-            .is_authorized(msg)
-            .await
+        self.abac(expr).is_authorized(msg).await
     }
 }
@@ -67,6 +67,51 @@ impl PolicyStorage for LmdbStorage {
         };
         spawn_blocking(t).await.map_err(map_join_err)?
     }
+
+    async fn all_policies(&self) -> Result<Vec<(Resource, Action, Expr)>> {
+        let d = self.clone();
+        let t = move || {
+            let tx = d.env.begin_ro_txn().map_err(map_lmdb_err)?;
+            let mut c = tx.open_ro_cursor(d.map).map_err(map_lmdb_err)?;
+            let mut xs = Vec::new();
+            for entry in c.iter_start() {
+                let (k, v) = entry.map_err(map_lmdb_err)?;
+                let ks = str::from_utf8(k).map_err(from_utf8_err)?;
+                if let Some((r, a)) = ks.split_once(':') {
+                    let x: PolicyEntry = minicbor::decode(v)?;
+                    xs.push((Resource::new(r), Action::new(a), x.expr.into_owned()))
+                } else {
+                    log::warn!(key = %ks, "malformed key in policy database")
+                }
+            }
+            Ok(xs)
+        };
+        spawn_blocking(t).await.map_err(map_join_err)?
+    }
+
+    async fn set_all_policies(&self, policies: &[(Resource, Action, Expr)]) -> Result<()> {
+        let d = self.clone();
+        let entries = policies
+            .iter()
+            .map(|(r, a, e)| {
+                let v = minicbor::to_vec(PolicyEntry {
+                    expr: Cow::Borrowed(e),
+                })?;
+                Ok((format!("{r}:{a}"), v))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let t = move || {
+            let mut w = d.env.begin_rw_txn().map_err(map_lmdb_err)?;
+            w.clear_db(d.map).map_err(map_lmdb_err)?;
+            for (k, v) in entries {
+                w.put(d.map, &k, &v, lmdb::WriteFlags::empty())
+                    .map_err(map_lmdb_err)?;
+            }
+            w.commit().map_err(map_lmdb_err)?;
+            Ok(())
+        };
+        spawn_blocking(t).await.map_err(map_join_err)?
+    }
 }
 
 fn map_join_err(err: JoinError) -> Error {
@@ -2,11 +2,13 @@ use crate::tokio::task::{spawn_blocking, JoinError};
 use crate::{Action, Expr, PolicyStorage, Resource};
 use ockam_core::async_trait;
 use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::{Arc, Mutex};
 use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
-use ockam_identity::SqliteStorage;
-use rusqlite::{params, ToSql};
+use ockam_identity::storage::SqliteStorage;
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
 use std::borrow::Cow;
 
 use super::PolicyEntry;
@@ -31,19 +33,17 @@ impl PolicyStorage for SqliteStorage {
         let a = a.clone();
         let t = move || {
             let conn = conn.lock().unwrap();
-            let result = conn
-                .query_row::<Option<Expr>, _, _>(
-                    "SELECT value FROM policy WHERE resource = ?1 AND action = ?2;",
-                    params![r, a],
-                    |row| {
-                        row.get::<_, Vec<u8>>(0).map(|value| {
-                            let e: PolicyEntry = minicbor::decode(&value).unwrap();
-                            Some(e.expr.into_owned())
-                        })
-                    },
-                )
-                .map_err(map_sqlite_err);
-            result
+            conn.query_row::<Option<Expr>, _, _>(
+                "SELECT value FROM policy WHERE resource = ?1 AND action = ?2;",
+                params![r, a],
+                |row| {
+                    row.get::<_, Vec<u8>>(0).map(|value| {
+                        let e: PolicyEntry = minicbor::decode(&value).unwrap();
+                        Some(e.expr.into_owned())
+                    })
+                },
+            )
+            .map_err(map_sqlite_err)
         };
         spawn_blocking(t).await.map_err(map_join_err)?
     }
@@ -115,6 +115,246 @@ impl PolicyStorage for SqliteStorage {
         };
         spawn_blocking(t).await.map_err(map_join_err)?
     }
+
+    async fn all_policies(&self) -> Result<Vec<(Resource, Action, Expr)>> {
+        let conn = self.conn();
+        let t = move || {
+            let conn = conn.lock().unwrap();
+            let mut stmt = conn
+                .prepare("SELECT resource, action, value FROM policy;")
+                .map_err(map_sqlite_err)?;
+            let rows = stmt
+                .query_map([], |row| {
+                    let resource = Resource::from(row.get::<_, String>(0)?);
+                    let action = Action::from(row.get::<_, String>(1)?);
+                    let value: Vec<u8> = row.get(2)?;
+                    Ok((resource, action, value))
+                })
+                .map_err(map_sqlite_err)?;
+            let mut xs = Vec::new();
+            for row in rows {
+                let (resource, action, value) = row.map_err(map_sqlite_err)?;
+                let e: PolicyEntry = minicbor::decode(&value).map_err(map_decode_err)?;
+                xs.push((resource, action, e.expr.into_owned()))
+            }
+            Ok(xs)
+        };
+        spawn_blocking(t).await.map_err(map_join_err)?
+    }
+
+    async fn set_all_policies(&self, policies: &[(Resource, Action, Expr)]) -> Result<()> {
+        let conn = self.conn();
+        let entries = policies
+            .iter()
+            .map(|(r, a, e)| {
+                let v = minicbor::to_vec(PolicyEntry {
+                    expr: Cow::Borrowed(e),
+                })?;
+                Ok((r.clone(), a.clone(), v))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let t = move || {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction().map_err(map_sqlite_err)?;
+            tx.execute("DELETE FROM policy;", [])
+                .map_err(map_sqlite_err)?;
+            for (r, a, v) in entries {
+                tx.execute(
+                    "INSERT INTO policy (resource, action, value) VALUES (?1, ?2, ?3)",
+                    params![r, a, v],
+                )
+                .map_err(map_sqlite_err)?;
+            }
+            tx.commit().map_err(map_sqlite_err)?;
+            Ok(())
+        };
+        spawn_blocking(t).await.map_err(map_join_err)?
+    }
+}
+
+/// Policy storage for one node in a Sqlite database shared by several nodes,
+/// for example the database also containing the vault and the identities
+#[derive(Clone)]
+pub struct SqlitePolicyStorage {
+    conn: Arc<Mutex<Connection>>,
+    node: String,
+}
+
+impl core::fmt::Debug for SqlitePolicyStorage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SqlitePolicyStorage({})", self.node)
+    }
+}
+
+impl SqlitePolicyStorage {
+    const CREATE_NODE_POLICY_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS node_policy (
+        node TEXT NOT NULL,
+        resource TEXT NOT NULL,
+        action TEXT NOT NULL,
+        value BLOB NOT NULL,
+        PRIMARY KEY (node, resource, action)
+    );";
+
+    /// Create the policies table, if necessary, and return the storage
+    /// of the policies of a given node
+    pub async fn create(conn: Arc<Mutex<Connection>>, node: &str) -> Result<Self> {
+        let storage = SqlitePolicyStorage {
+            conn,
+            node: node.to_string(),
+        };
+        let conn = storage.conn.clone();
+        let t = move || {
+            conn.lock()
+                .unwrap()
+                .execute_batch(Self::CREATE_NODE_POLICY_TABLE_SQL)
+                .map_err(map_sqlite_err)
+        };
+        spawn_blocking(t).await.map_err(map_join_err)??;
+        Ok(storage)
+    }
+
+    /// Run a function with the connection and the node name on a blocking thread
+    async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Connection, &str) -> Result<R> + Send + 'static,
+    ) -> Result<R> {
+        let conn = self.conn.clone();
+        let node = self.node.clone();
+        let t = move || f(&mut conn.lock().unwrap(), &node);
+        spawn_blocking(t).await.map_err(map_join_err)?
+    }
+}
+
+#[async_trait]
+impl PolicyStorage for SqlitePolicyStorage {
+    async fn get_policy(&self, r: &Resource, a: &Action) -> Result<Option<Expr>> {
+        let r = r.clone();
+        let a = a.clone();
+        self.run(move |conn, node| {
+            let value = conn
+                .query_row(
+                    "SELECT value FROM node_policy WHERE node = ?1 AND resource = ?2 AND action = ?3;",
+                    params![node, r, a],
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()
+                .map_err(map_sqlite_err)?;
+            value.map(|value| decode_expr(&value)).transpose()
+        })
+        .await
+    }
+
+    async fn set_policy(&self, r: &Resource, a: &Action, c: &Expr) -> Result<()> {
+        let r = r.clone();
+        let a = a.clone();
+        let v = minicbor::to_vec(PolicyEntry {
+            expr: Cow::Borrowed(c),
+        })?;
+        self.run(move |conn, node| {
+            conn.execute(
+                "INSERT OR REPLACE INTO node_policy (node, resource, action, value) VALUES (?1, ?2, ?3, ?4)",
+                params![node, r, a, v],
+            )
+            .map_err(map_sqlite_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn del_policy(&self, r: &Resource, a: &Action) -> Result<()> {
+        let r = r.clone();
+        let a = a.clone();
+        self.run(move |conn, node| {
+            conn.execute(
+                "DELETE FROM node_policy WHERE node = ?1 AND resource = ?2 AND action = ?3;",
+                params![node, r, a],
+            )
+            .map_err(map_sqlite_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn policies(&self, r: &Resource) -> Result<Vec<(Action, Expr)>> {
+        let r = r.clone();
+        self.run(move |conn, node| {
+            let mut stmt = conn
+                .prepare("SELECT action, value FROM node_policy WHERE node = ?1 AND resource = ?2;")
+                .map_err(map_sqlite_err)?;
+            let rows = stmt
+                .query_map(params![node, r], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .map_err(map_sqlite_err)?;
+            let mut xs = Vec::new();
+            for row in rows {
+                let (action, value) = row.map_err(map_sqlite_err)?;
+                xs.push((Action::from(action), decode_expr(&value)?))
+            }
+            Ok(xs)
+        })
+        .await
+    }
+
+    async fn all_policies(&self) -> Result<Vec<(Resource, Action, Expr)>> {
+        self.run(move |conn, node| {
+            let mut stmt = conn
+                .prepare("SELECT resource, action, value FROM node_policy WHERE node = ?1;")
+                .map_err(map_sqlite_err)?;
+            let rows = stmt
+                .query_map(params![node], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                    ))
+                })
+                .map_err(map_sqlite_err)?;
+            let mut xs = Vec::new();
+            for row in rows {
+                let (resource, action, value) = row.map_err(map_sqlite_err)?;
+                xs.push((
+                    Resource::from(resource),
+                    Action::from(action),
+                    decode_expr(&value)?,
+                ))
+            }
+            Ok(xs)
+        })
+        .await
+    }
+
+    async fn set_all_policies(&self, policies: &[(Resource, Action, Expr)]) -> Result<()> {
+        let entries = policies
+            .iter()
+            .map(|(r, a, e)| {
+                let v = minicbor::to_vec(PolicyEntry {
+                    expr: Cow::Borrowed(e),
+                })?;
+                Ok((r.clone(), a.clone(), v))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.run(move |conn, node| {
+            let tx = conn.transaction().map_err(map_sqlite_err)?;
+            tx.execute("DELETE FROM node_policy WHERE node = ?1;", params![node])
+                .map_err(map_sqlite_err)?;
+            for (r, a, v) in entries {
+                tx.execute(
+                    "INSERT INTO node_policy (node, resource, action, value) VALUES (?1, ?2, ?3, ?4)",
+                    params![node, r, a, v],
+                )
+                .map_err(map_sqlite_err)?;
+            }
+            tx.commit().map_err(map_sqlite_err)?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn decode_expr(value: &[u8]) -> Result<Expr> {
+    let e: PolicyEntry = minicbor::decode(value).map_err(map_decode_err)?;
+    Ok(e.expr.into_owned())
 }
 
 fn map_join_err(err: JoinError) -> Error {
@@ -161,6 +401,43 @@ mod test {
         let policies = db.policies(&r).await?;
         assert_eq!(policies.len(), 1);
 
+        let other = Resource::from("4");
+        db.set_all_policies(&[(other.clone(), a.clone(), e.clone())])
+            .await?;
+        assert!(db.policies(&r).await?.is_empty(), "Verify replace all");
+        let policies = db.all_policies().await?;
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].0, other);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_node_policies_are_separated() -> Result<()> {
+        let temp_path = NamedTempFile::new().unwrap().into_temp_path();
+        let conn = SqliteStorage::new(temp_path.to_path_buf()).await?.conn();
+        let node1 = SqlitePolicyStorage::create(conn.clone(), "node1").await?;
+        let node2 = SqlitePolicyStorage::create(conn, "node2").await?;
+
+        let r = Resource::from("1");
+        let a = Action::from("2");
+        let e = Expr::from_str("345")?;
+        node1.set_policy(&r, &a, &e).await?;
+        assert!(node1.get_policy(&r, &a).await?.unwrap().equals(&e)?);
+        assert!(node2.get_policy(&r, &a).await?.is_none());
+        assert!(node2.all_policies().await?.is_empty());
+
+        node2
+            .set_all_policies(&[(r.clone(), Action::from("3"), e.clone())])
+            .await?;
+        assert_eq!(node1.policies(&r).await?.len(), 1);
+        assert_eq!(node1.policies(&r).await?[0].0, a);
+        assert_eq!(node2.policies(&r).await?[0].0, Action::from("3"));
+
+        node1.del_policy(&r, &a).await?;
+        assert!(node1.all_policies().await?.is_empty());
+        assert_eq!(node2.all_policies().await?.len(), 1);
+
         Ok(())
     }
 }
@@ -10,4 +10,8 @@ pub trait PolicyStorage: Send + Sync + 'static {
     async fn set_policy(&self, r: &Resource, a: &Action, c: &Expr) -> Result<()>;
     async fn del_policy(&self, r: &Resource, a: &Action) -> Result<()>;
     async fn policies(&self, r: &Resource) -> Result<Vec<(Action, Expr)>>;
+    /// Return the policies of all the resources
+    async fn all_policies(&self) -> Result<Vec<(Resource, Action, Expr)>>;
+    /// Replace all the policies with the given ones in a single operation
+    async fn set_all_policies(&self, policies: &[(Resource, Action, Expr)]) -> Result<()>;
 }
@@ -2,7 +2,7 @@ use minicbor::Decoder;
 use ockam::identity::utils::now;
 use ockam::identity::{secure_channel_required, TRUST_CONTEXT_ID};
 use ockam::identity::{AttributesEntry, IdentityAttributesReader, IdentityAttributesWriter};
-use ockam::identity::{Identifier, IdentitySecureChannelLocalInfo};
+use ockam::identity::{Identifier, IdentitySecureChannelLocalInfo, RevocationsStorage};
 use ockam_core::api::{Method, Request, Response};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{CowStr, Result, Routed, Worker};
@@ -10,12 +10,13 @@ use ockam_node::Context;
 use std::collections::HashMap;
 use tracing::trace;
 
-use crate::authenticator::direct::types::AddMember;
+use crate::authenticator::direct::types::{AddMember, RevokeCredential};
 
 pub struct DirectAuthenticator {
     trust_context: String,
     attributes_writer: Arc<dyn IdentityAttributesWriter>,
     attributes_reader: Arc<dyn IdentityAttributesReader>,
+    revocations: Arc<RevocationsStorage>,
 }
 
 impl DirectAuthenticator {
@@ -23,11 +24,13 @@ impl DirectAuthenticator {
         trust_context: String,
         attributes_writer: Arc<dyn IdentityAttributesWriter>,
         attributes_reader: Arc<dyn IdentityAttributesReader>,
+        revocations: Arc<RevocationsStorage>,
     ) -> Result<Self> {
         Ok(Self {
             trust_context,
             attributes_writer,
             attributes_reader,
+            revocations,
         })
     }
 
@@ -99,6 +102,16 @@ impl Worker for DirectAuthenticator {
                 (Some(Method::Delete), [id]) | (Some(Method::Delete), ["members", id]) => {
                     let identifier = Identifier::try_from(id.to_string())?;
                     self.attributes_writer.delete(&identifier).await?;
+                    // the credentials already issued to the member must not be accepted anymore
+                    self.revocations.revoke_subject(&identifier).await?;
+
+                    Response::ok(req.id()).to_vec()?
+                }
+                (Some(Method::Post), ["revoked_credentials"]) => {
+                    let revoke: RevokeCredential = dec.decode()?;
+                    self.revocations
+                        .revoke_credential(revoke.credential_hash())
+                        .await?;
 
                     Response::ok(req.id()).to_vec()?
                 }
@@ -1,4 +1,5 @@
 use core::str;
+use ockam::identity::models::CredentialHash;
 use ockam::identity::AttributesEntry;
 use ockam::identity::Identifier;
 use ockam_core::api::Request;
@@ -6,7 +7,7 @@ use ockam_core::Result;
 use ockam_node::RpcClient;
 use std::collections::HashMap;
 
-use crate::authenticator::direct::types::AddMember;
+use crate::authenticator::direct::types::{AddMember, RevokeCredential};
 
 pub struct DirectAuthenticatorClient(RpcClient);
 
@@ -36,4 +37,12 @@ impl DirectAuthenticatorClient {
             .request_no_resp_body(&Request::delete(format!("/{id}")))
             .await
     }
+
+    pub async fn revoke_credential(&self, credential_hash: CredentialHash) -> Result<()> {
+        self.0
+            .request_no_resp_body(
+                &Request::post("/revoked_credentials").body(RevokeCredential::new(credential_hash)),
+            )
+            .await
+    }
 }
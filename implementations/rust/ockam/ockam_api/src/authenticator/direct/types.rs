@@ -1,4 +1,5 @@
 use minicbor::{Decode, Encode};
+use ockam::identity::models::CredentialHash;
 use ockam::identity::Identifier;
 use ockam_core::CowStr;
 use std::collections::HashMap;
@@ -44,6 +45,29 @@ impl<'a> AddMember<'a> {
     }
 }
 
+#[derive(Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RevokeCredential {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<7305719>,
+    #[n(1)] credential_hash: CredentialHash,
+}
+
+impl RevokeCredential {
+    pub fn new(credential_hash: CredentialHash) -> Self {
+        RevokeCredential {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            credential_hash,
+        }
+    }
+
+    pub fn credential_hash(&self) -> &CredentialHash {
+        &self.credential_hash
+    }
+}
+
 #[derive(Debug, Decode, Encode)]
 #[rustfmt::skip]
 #[cbor(map)]
@@ -51,7 +75,8 @@ pub struct CreateToken<'a> {
     #[cfg(feature = "tag")]
     #[n(0)] tag: TypeTag<2502742>,
     #[b(1)] attributes: HashMap<CowStr<'a>, CowStr<'a>>,
-    #[b(2)] token_duration_secs: Option<u64>
+    #[b(2)] token_duration_secs: Option<u64>,
+    #[n(3)] usage_count: Option<u64>,
 }
 
 impl<'a> CreateToken<'a> {
@@ -62,6 +87,7 @@ impl<'a> CreateToken<'a> {
             tag: TypeTag,
             attributes: HashMap::new(),
             token_duration_secs: None,
+            usage_count: None,
         }
     }
 
@@ -78,6 +104,12 @@ impl<'a> CreateToken<'a> {
         self
     }
 
+    /// Set the number of times the token can be used, it can be used once by default
+    pub fn with_usage_count(mut self, usage_count: Option<u64>) -> Self {
+        self.usage_count = usage_count;
+        self
+    }
+
     pub fn into_owned_attributes(self) -> HashMap<String, String> {
         self.attributes
             .into_iter()
@@ -88,4 +120,7 @@ impl<'a> CreateToken<'a> {
     pub fn token_duration(&self) -> Option<Duration> {
         self.token_duration_secs.map(Duration::from_secs)
     }
+    pub fn usage_count(&self) -> Option<u64> {
+        self.usage_count
+    }
 }
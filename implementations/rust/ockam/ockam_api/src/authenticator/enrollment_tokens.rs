@@ -3,6 +3,7 @@ mod acceptor_client;
 mod authenticator;
 mod issuer;
 mod issuer_client;
+mod storage;
 pub mod types;
 
 pub use acceptor::*;
@@ -10,3 +11,4 @@ pub use acceptor_client::*;
 pub use authenticator::*;
 pub use issuer::*;
 pub use issuer_client::*;
+pub use storage::*;
@@ -10,7 +10,7 @@ use ockam_core::{Result, Routed, Worker};
 use ockam_node::Context;
 use tracing::trace;
 
-use crate::authenticator::enrollment_tokens::EnrollmentTokenAuthenticator;
+use crate::authenticator::enrollment_tokens::{EnrollmentTokenAuthenticator, TokenUse};
 
 pub struct EnrollmentTokenAcceptor(
     pub(super) EnrollmentTokenAuthenticator,
@@ -40,22 +40,10 @@ impl Worker for EnrollmentTokenAcceptor {
                 (Some(Method::Post), "/") | (Some(Method::Post), "/credential") => {
                     //TODO: move out of the worker handle_message implementation
                     let otc: OneTimeCode = dec.decode()?;
-                    let token = match self.0.tokens.write() {
-                        Ok(mut r) => {
-                            if let Some(tkn) = r.pop(otc.code()) {
-                                if tkn.time.elapsed() > tkn.max_token_duration {
-                                    Err(ockam_core::api::forbidden(&req, "expired token"))
-                                } else {
-                                    Ok(tkn)
-                                }
-                            } else {
-                                Err(ockam_core::api::forbidden(&req, "unknown token"))
-                            }
-                        }
-                        Err(_) => Err(ockam_core::api::internal_error(
-                            &req,
-                            "Failed to get read lock on tokens table",
-                        )),
+                    let token = match self.0.tokens.use_token(otc.code(), now()?).await? {
+                        TokenUse::Accepted(tkn) => Ok(tkn),
+                        TokenUse::Expired => Err(ockam_core::api::forbidden(&req, "expired token")),
+                        TokenUse::Unknown => Err(ockam_core::api::forbidden(&req, "unknown token")),
                     };
                     match token {
                         Ok(tkn) => {
@@ -1,19 +1,21 @@
 use minicbor::Decoder;
 use ockam::identity::secure_channel_required;
+use ockam::identity::utils::now;
 use ockam::identity::OneTimeCode;
-use ockam::identity::{Identifier, IdentitySecureChannelLocalInfo};
-use ockam_core::api::{Method, Request, Response};
-use ockam_core::errcode::{Kind, Origin};
+use ockam::identity::{Identifier, IdentitySecureChannelLocalInfo, TimestampInSeconds};
+use ockam_core::api::{Error, Method, Request, Response};
 use ockam_core::{Result, Routed, Worker};
 use ockam_node::Context;
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tracing::trace;
 
 use crate::authenticator::direct::types::CreateToken;
 use crate::authenticator::enrollment_tokens::authenticator::MAX_TOKEN_DURATION;
 use crate::authenticator::enrollment_tokens::types::Token;
-use crate::authenticator::enrollment_tokens::EnrollmentTokenAuthenticator;
+use crate::authenticator::enrollment_tokens::{
+    EnrollmentTokenAuthenticator, EnrollmentTokensStorage,
+};
 
 pub struct EnrollmentTokenIssuer(pub(super) EnrollmentTokenAuthenticator);
 
@@ -23,29 +25,21 @@ impl EnrollmentTokenIssuer {
         enroller: &Identifier,
         attrs: HashMap<String, String>,
         token_duration: Option<Duration>,
+        usage_count: Option<u64>,
     ) -> Result<OneTimeCode> {
         let otc = OneTimeCode::new();
+        let created_at = now()?;
         let max_token_duration = token_duration.unwrap_or(MAX_TOKEN_DURATION);
-        let tkn = Token {
+        let tkn = Token::new(
+            EnrollmentTokensStorage::token_id(otc.code())?,
             attrs,
-            generated_by: enroller.clone(),
-            time: Instant::now(),
-            max_token_duration,
-        };
-        self.0
-            .tokens
-            .write()
-            .map(|mut r| {
-                r.put(*otc.code(), tkn);
-                otc
-            })
-            .map_err(|_| {
-                ockam_core::Error::new(
-                    Origin::Other,
-                    Kind::Internal,
-                    "failed to get read lock on tokens table",
-                )
-            })
+            enroller.clone(),
+            created_at,
+            TimestampInSeconds(created_at.0 + max_token_duration.as_secs()),
+            usage_count.unwrap_or(1),
+        );
+        self.0.tokens.put(&tkn).await?;
+        Ok(otc)
     }
 }
 
@@ -68,20 +62,40 @@ impl Worker for EnrollmentTokenIssuer {
                 body   = %req.has_body(),
                 "request"
             }
-            let res = match (req.method(), req.path()) {
-                (Some(Method::Post), "/") | (Some(Method::Post), "/tokens") => {
+            let path_segments = req.path_segments::<5>();
+            let res = match (req.method(), path_segments.as_slice()) {
+                (Some(Method::Post), [""]) | (Some(Method::Post), ["tokens"]) => {
                     let att: CreateToken = dec.decode()?;
                     let duration = att.token_duration();
-                    match self
-                        .issue_token(&from, att.into_owned_attributes(), duration)
-                        .await
-                    {
-                        Ok(otc) => Response::ok(req.id()).body(&otc).to_vec()?,
-                        Err(error) => {
-                            ockam_core::api::internal_error(&req, &error.to_string()).to_vec()?
+                    let usage_count = att.usage_count();
+                    if usage_count == Some(0) {
+                        ockam_core::api::bad_request(&req, "the usage count must be positive")
+                            .to_vec()?
+                    } else {
+                        match self
+                            .issue_token(&from, att.into_owned_attributes(), duration, usage_count)
+                            .await
+                        {
+                            Ok(otc) => Response::ok(req.id()).body(&otc).to_vec()?,
+                            Err(error) => ockam_core::api::internal_error(&req, &error.to_string())
+                                .to_vec()?,
                         }
                     }
                 }
+                // An enroller can only list and revoke the tokens it generated
+                (Some(Method::Get), ["tokens"]) => {
+                    let tokens = self.0.tokens.list(&from, now()?).await?;
+                    Response::ok(req.id()).body(tokens).to_vec()?
+                }
+                (Some(Method::Delete), ["tokens", id]) => {
+                    if self.0.tokens.revoke(id, &from).await? {
+                        Response::ok(req.id()).to_vec()?
+                    } else {
+                        let err_body = Error::new(req.path())
+                            .with_message(format!("enrollment token {} not found", id));
+                        Response::not_found(req.id()).body(err_body).to_vec()?
+                    }
+                }
                 _ => ockam_core::api::unknown_path(&req).to_vec()?,
             };
             c.send(m.return_route(), res).await
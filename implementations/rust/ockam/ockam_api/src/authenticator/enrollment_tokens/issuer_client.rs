@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::time::Duration;
 
 use crate::authenticator::direct::types::CreateToken;
+use crate::authenticator::enrollment_tokens::types::Token;
 
 pub struct TokenIssuerClient(RpcClient);
 
@@ -19,15 +20,27 @@ impl TokenIssuerClient {
         &self,
         attributes: HashMap<&str, &str>,
         duration: Option<Duration>,
+        usage_count: Option<u64>,
     ) -> Result<OneTimeCode> {
         self.0
             .request(
                 &Request::post("/").body(
                     CreateToken::new()
                         .with_attributes(attributes)
-                        .with_duration(duration),
+                        .with_duration(duration)
+                        .with_usage_count(usage_count),
                 ),
             )
             .await
     }
+
+    pub async fn list_tokens(&self) -> Result<Vec<Token>> {
+        self.0.request(&Request::get("/tokens")).await
+    }
+
+    pub async fn revoke_token(&self, id: &str) -> Result<()> {
+        self.0
+            .request_no_resp_body(&Request::delete(format!("/tokens/{id}")))
+            .await
+    }
 }
@@ -0,0 +1,309 @@
+use ockam::compat::tokio::sync::Mutex;
+use ockam::identity::storage::{InMemoryStorage, Storage};
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+use ockam_vault::SoftwareVerifyingVault;
+use tracing::info;
+
+use crate::authenticator::enrollment_tokens::types::Token;
+
+const ENROLLMENT_TOKENS_ID: &str = "enrollment_tokens";
+
+/// Result of the presentation of a one-time code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenUse {
+    /// The token is valid, it is deleted if it reached its maximum usage count
+    Accepted(Token),
+    /// The token expired, it is deleted
+    Expired,
+    /// No token was issued for the one-time code, or it was revoked
+    Unknown,
+}
+
+/// Enrollment tokens issued by an authority, persisted in a [`Storage`]
+///
+/// The updates of the tokens are serialized, so that a revoked token can not be stored again
+/// by a concurrent use. The tokens are expected to be modified by the workers of a single authority
+pub struct EnrollmentTokensStorage {
+    storage: Arc<dyn Storage>,
+    /// Held during each read-modify-write of the stored tokens
+    updates: Mutex<()>,
+}
+
+impl EnrollmentTokensStorage {
+    /// Create enrollment tokens persisted in a storage
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            updates: Mutex::new(()),
+        }
+    }
+
+    /// Create enrollment tokens in memory
+    pub fn create() -> Arc<Self> {
+        Arc::new(Self::new(InMemoryStorage::create()))
+    }
+
+    /// Return the id of the token issued for a one-time code
+    pub fn token_id(code: &[u8; 32]) -> Result<String> {
+        Ok(hex::encode(SoftwareVerifyingVault::compute_sha256(code)?))
+    }
+
+    /// Store a new token
+    pub async fn put(&self, token: &Token) -> Result<()> {
+        info!(id = %token.id(), max_usage_count = %token.max_usage_count(), "issued an enrollment token");
+        self.store(token).await
+    }
+
+    /// Use the token issued for a one-time code
+    pub async fn use_token(&self, code: &[u8; 32], now: TimestampInSeconds) -> Result<TokenUse> {
+        let id = Self::token_id(code)?;
+        let _updates = self.updates.lock().await;
+        let mut token = match self.get(&id).await? {
+            Some(token) => token,
+            None => return Ok(TokenUse::Unknown),
+        };
+
+        if token.is_expired(now) {
+            self.delete(&id).await?;
+            return Ok(TokenUse::Expired);
+        }
+
+        token.usage_count += 1;
+        if token.usage_count >= token.max_usage_count {
+            self.delete(&id).await?;
+        } else {
+            self.store(&token).await?;
+        }
+        Ok(TokenUse::Accepted(token))
+    }
+
+    /// Return the tokens generated by an enroller which did not expire, sorted by creation time
+    pub async fn list(
+        &self,
+        generated_by: &Identifier,
+        now: TimestampInSeconds,
+    ) -> Result<Vec<Token>> {
+        let _updates = self.updates.lock().await;
+        let mut tokens = vec![];
+        for id in self.storage.keys(ENROLLMENT_TOKENS_ID).await? {
+            match self.get(&id).await? {
+                Some(token) if token.is_expired(now) => self.delete(&id).await?,
+                Some(token) if token.generated_by() == generated_by => tokens.push(token),
+                _ => {}
+            }
+        }
+        tokens.sort_by_key(|t| t.created_at());
+        Ok(tokens)
+    }
+
+    /// Revoke a token generated by an enroller.
+    /// Return false if this enroller did not generate a token with this id
+    pub async fn revoke(&self, id: &str, generated_by: &Identifier) -> Result<bool> {
+        let _updates = self.updates.lock().await;
+        match self.get(id).await? {
+            Some(token) if token.generated_by() == generated_by => {}
+            _ => return Ok(false),
+        }
+        self.delete(id).await?;
+        info!(%id, "revoked an enrollment token");
+        Ok(true)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Token>> {
+        match self.storage.get(id, ENROLLMENT_TOKENS_ID).await? {
+            Some(data) => Ok(Some(minicbor::decode(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn store(&self, token: &Token) -> Result<()> {
+        self.storage
+            .set(
+                token.id(),
+                ENROLLMENT_TOKENS_ID.to_string(),
+                minicbor::to_vec(token)?,
+            )
+            .await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.storage.del(id, ENROLLMENT_TOKENS_ID).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::OneTimeCode;
+    use ockam_core::async_trait;
+    use std::collections::HashMap;
+
+    fn enroller() -> Identifier {
+        Identifier::try_from("I0123456789abcdef0123456789abcdef01234567").unwrap()
+    }
+
+    fn token(code: &OneTimeCode, expires_at: u64, max_usage_count: u64) -> Result<Token> {
+        Ok(Token::new(
+            EnrollmentTokensStorage::token_id(code.code())?,
+            HashMap::from([("role".to_string(), "sensor".to_string())]),
+            enroller(),
+            TimestampInSeconds(100),
+            TimestampInSeconds(expires_at),
+            max_usage_count,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_usage_count() -> Result<()> {
+        let storage = InMemoryStorage::create();
+        let tokens = EnrollmentTokensStorage::new(storage.clone());
+        let code = OneTimeCode::new();
+        tokens.put(&token(&code, 200, 2)?).await?;
+
+        match tokens
+            .use_token(code.code(), TimestampInSeconds(150))
+            .await?
+        {
+            TokenUse::Accepted(token) => assert_eq!(token.usage_count(), 1),
+            other => panic!("unexpected token use {other:?}"),
+        }
+
+        // the usage count is persisted
+        let tokens = EnrollmentTokensStorage::new(storage);
+        assert_eq!(
+            tokens.list(&enroller(), TimestampInSeconds(150)).await?[0].usage_count(),
+            1
+        );
+        assert!(matches!(
+            tokens
+                .use_token(code.code(), TimestampInSeconds(150))
+                .await?,
+            TokenUse::Accepted(_)
+        ));
+        assert_eq!(
+            tokens
+                .use_token(code.code(), TimestampInSeconds(150))
+                .await?,
+            TokenUse::Unknown
+        );
+        assert!(tokens
+            .list(&enroller(), TimestampInSeconds(150))
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expiry_and_revocation() -> Result<()> {
+        let tokens = EnrollmentTokensStorage::create();
+        let expired = OneTimeCode::new();
+        let revoked = OneTimeCode::new();
+        let valid = OneTimeCode::new();
+        tokens.put(&token(&expired, 200, 10)?).await?;
+        tokens.put(&token(&revoked, 300, 10)?).await?;
+        tokens.put(&token(&valid, 300, 10)?).await?;
+
+        assert_eq!(
+            tokens
+                .use_token(expired.code(), TimestampInSeconds(200))
+                .await?,
+            TokenUse::Expired
+        );
+
+        let revoked_id = EnrollmentTokensStorage::token_id(revoked.code())?;
+        assert!(tokens.revoke(&revoked_id, &enroller()).await?);
+        assert!(!tokens.revoke(&revoked_id, &enroller()).await?);
+        assert_eq!(
+            tokens
+                .use_token(revoked.code(), TimestampInSeconds(250))
+                .await?,
+            TokenUse::Unknown
+        );
+
+        let listed = tokens.list(&enroller(), TimestampInSeconds(250)).await?;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            listed[0].id(),
+            EnrollmentTokensStorage::token_id(valid.code())?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tokens_of_other_enrollers() -> Result<()> {
+        let tokens = EnrollmentTokensStorage::create();
+        let code = OneTimeCode::new();
+        tokens.put(&token(&code, 300, 10)?).await?;
+        let id = EnrollmentTokensStorage::token_id(code.code())?;
+
+        let other_enroller = Identifier::try_from("I89abcdef0123456789abcdef0123456789abcdef")?;
+        assert!(tokens
+            .list(&other_enroller, TimestampInSeconds(250))
+            .await?
+            .is_empty());
+        assert!(!tokens.revoke(&id, &other_enroller).await?);
+
+        // the token can still be used, and revoked by its enroller
+        assert_eq!(
+            tokens
+                .list(&enroller(), TimestampInSeconds(250))
+                .await?
+                .len(),
+            1
+        );
+        assert!(tokens.revoke(&id, &enroller()).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_revocation_and_use() -> Result<()> {
+        let tokens =
+            EnrollmentTokensStorage::new(Arc::new(YieldingStorage(InMemoryStorage::create())));
+        let code = OneTimeCode::new();
+        tokens.put(&token(&code, 300, 10)?).await?;
+        let id = EnrollmentTokensStorage::token_id(code.code())?;
+        let enroller = enroller();
+
+        // the revocation starts while the use of the token is storing its new usage count
+        let (used, revoked) = tokio::join!(
+            tokens.use_token(code.code(), TimestampInSeconds(250)),
+            tokens.revoke(&id, &enroller)
+        );
+        assert!(matches!(used?, TokenUse::Accepted(_)));
+        assert!(revoked?);
+
+        // the revoked token is not stored again
+        assert_eq!(
+            tokens
+                .use_token(code.code(), TimestampInSeconds(250))
+                .await?,
+            TokenUse::Unknown
+        );
+        Ok(())
+    }
+
+    /// Storage yielding to the other tasks before storing a value
+    struct YieldingStorage(Arc<dyn Storage>);
+
+    #[async_trait]
+    impl Storage for YieldingStorage {
+        async fn get(&self, id: &str, key: &str) -> Result<Option<Vec<u8>>> {
+            self.0.get(id, key).await
+        }
+
+        async fn set(&self, id: &str, key: String, val: Vec<u8>) -> Result<()> {
+            tokio::task::yield_now().await;
+            self.0.set(id, key, val).await
+        }
+
+        async fn del(&self, id: &str, key: &str) -> Result<()> {
+            self.0.del(id, key).await
+        }
+
+        async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
+            self.0.keys(namespace).await
+        }
+    }
+}
@@ -1,10 +1,84 @@
-use ockam::identity::Identifier;
+use minicbor::{Decode, Encode};
+use ockam::identity::{Identifier, TimestampInSeconds};
+use serde::Serialize;
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
 
-pub(super) struct Token {
-    pub(super) attrs: HashMap<String, String>,
-    pub(super) generated_by: Identifier,
-    pub(super) time: Instant,
-    pub(super) max_token_duration: Duration,
+#[cfg(feature = "tag")]
+use ockam_core::TypeTag;
+
+/// Enrollment token issued by an enroller.
+///
+/// The one-time code presented by the enrolled nodes is not stored, only its id,
+/// which is the hex-encoded SHA-256 hash of the code
+#[derive(Debug, Clone, Decode, Encode, Serialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Token {
+    #[cfg(feature = "tag")]
+    #[serde(skip)]
+    #[n(0)] tag: TypeTag<4729081>,
+    #[n(1)] pub(super) id: String,
+    #[n(2)] pub(super) attrs: HashMap<String, String>,
+    #[n(3)] pub(super) generated_by: Identifier,
+    #[n(4)] pub(super) created_at: TimestampInSeconds,
+    #[n(5)] pub(super) expires_at: TimestampInSeconds,
+    #[n(6)] pub(super) usage_count: u64,
+    #[n(7)] pub(super) max_usage_count: u64,
+}
+
+impl Token {
+    pub(super) fn new(
+        id: String,
+        attrs: HashMap<String, String>,
+        generated_by: Identifier,
+        created_at: TimestampInSeconds,
+        expires_at: TimestampInSeconds,
+        max_usage_count: u64,
+    ) -> Self {
+        Token {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            id,
+            attrs,
+            generated_by,
+            created_at,
+            expires_at,
+            usage_count: 0,
+            max_usage_count,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attrs
+    }
+
+    pub fn generated_by(&self) -> &Identifier {
+        &self.generated_by
+    }
+
+    pub fn created_at(&self) -> TimestampInSeconds {
+        self.created_at
+    }
+
+    pub fn expires_at(&self) -> TimestampInSeconds {
+        self.expires_at
+    }
+
+    /// Number of times the token was used to enroll a node
+    pub fn usage_count(&self) -> u64 {
+        self.usage_count
+    }
+
+    /// Number of times the token can be used before being deleted
+    pub fn max_usage_count(&self) -> u64 {
+        self.max_usage_count
+    }
+
+    pub(super) fn is_expired(&self, now: TimestampInSeconds) -> bool {
+        now >= self.expires_at
+    }
 }
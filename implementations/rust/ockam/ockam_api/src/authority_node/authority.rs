@@ -2,12 +2,12 @@ use std::path::Path;
 
 use tracing::info;
 
-use ockam::identity::storage::LmdbStorage;
+use ockam::identity::storage::{LmdbStorage, Storage};
 use ockam::identity::Vault;
 use ockam::identity::{
     CredentialsIssuer, Identifier, Identities, IdentitiesRepository, IdentitiesStorage,
-    IdentityAttributesReader, IdentityAttributesWriter, SecureChannelListenerOptions,
-    SecureChannels, TrustEveryonePolicy,
+    IdentityAttributesReader, IdentityAttributesWriter, RevocationListIssuer, RevocationsStorage,
+    SecureChannelListenerOptions, SecureChannels, TrustEveryonePolicy,
 };
 use ockam_abac::expr::{and, eq, ident, str};
 use ockam_abac::{AbacAccessControl, Env};
@@ -18,7 +18,9 @@ use ockam_core::{Error, Result, Worker};
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_tcp::{TcpListenerOptions, TcpTransport};
 
-use crate::authenticator::enrollment_tokens::EnrollmentTokenAuthenticator;
+use crate::authenticator::enrollment_tokens::{
+    EnrollmentTokenAuthenticator, EnrollmentTokensStorage,
+};
 use crate::authority_node::authority::EnrollerCheck::{AnyMember, EnrollerOnly};
 use crate::authority_node::Configuration;
 use crate::bootstrapped_identities_store::BootstrapedIdentityStore;
@@ -30,11 +32,14 @@ use crate::{actions, DefaultAddress};
 /// An Authority is able to start a few services
 //   - a direct authenticator
 //   - a credential issuer
+//   - a revocation list issuer
 //   - an enrollment token issuer
 //   - an enrollment token acceptor
 pub struct Authority {
     identifier: Identifier,
     secure_channels: Arc<SecureChannels>,
+    revocations: Arc<RevocationsStorage>,
+    enrollment_tokens: Arc<EnrollmentTokensStorage>,
 }
 
 /// Public functions to:
@@ -57,7 +62,12 @@ impl Authority {
     pub async fn create(configuration: &Configuration) -> Result<Authority> {
         debug!(?configuration, "creating the authority");
         let vault = Self::create_secure_channels_vault(configuration).await?;
-        let repository = Self::create_identities_repository(configuration).await?;
+        let storage = Self::create_storage(configuration).await?;
+        let repository = Self::create_identities_repository(storage.clone(), configuration);
+        // the revoked credentials are stored next to the identities attributes
+        let revocations = Arc::new(RevocationsStorage::new(storage.clone()));
+        // the enrollment tokens are persisted so that they survive a restart of the authority
+        let enrollment_tokens = Arc::new(EnrollmentTokensStorage::new(storage));
         let secure_channels = SecureChannels::builder()
             .with_vault(vault)
             .with_identities_repository(repository)
@@ -69,6 +79,8 @@ impl Authority {
         Ok(Authority {
             identifier,
             secure_channels,
+            revocations,
+            enrollment_tokens,
         })
     }
 
@@ -122,6 +134,7 @@ impl Authority {
             configuration.project_identifier(),
             self.attributes_writer(),
             self.attributes_reader(),
+            self.revocations.clone(),
         )
         .await?;
 
@@ -149,6 +162,7 @@ impl Authority {
 
         let (issuer, acceptor) = EnrollmentTokenAuthenticator::new_worker_pair(
             configuration.project_identifier(),
+            self.enrollment_tokens.clone(),
             self.attributes_writer(),
         );
 
@@ -209,6 +223,31 @@ impl Authority {
         Ok(())
     }
 
+    /// Start the revocation list issuer service to publish the credentials revoked
+    /// by the authority
+    pub async fn start_revocation_list_issuer(
+        &self,
+        ctx: &Context,
+        secure_channel_flow_control_id: &FlowControlId,
+        configuration: &Configuration,
+    ) -> Result<()> {
+        let issuer = RevocationListIssuer::new(
+            self.secure_channels.identities().credentials(),
+            &self.identifier,
+            self.revocations.clone(),
+        );
+
+        let address = DefaultAddress::REVOCATION_LIST.to_string();
+        ctx.flow_controls()
+            .add_consumer(address.clone(), secure_channel_flow_control_id);
+
+        self.start(ctx, configuration, address.clone(), AnyMember, issuer)
+            .await?;
+
+        info!("started a revocation list issuer at '{address}'");
+        Ok(())
+    }
+
     /// Start the Okta service to retrieve attributes authenticated by Okta
     pub async fn start_okta(
         &self,
@@ -279,14 +318,19 @@ impl Authority {
     }
 
     /// Create an authenticated storage backed by a Lmdb database
-    async fn create_identities_repository(
-        configuration: &Configuration,
-    ) -> Result<Arc<dyn IdentitiesRepository>> {
+    async fn create_storage(configuration: &Configuration) -> Result<Arc<dyn Storage>> {
         let storage_path = &configuration.storage_path;
         Self::create_ockam_directory_if_necessary(storage_path)?;
-        let storage = Arc::new(LmdbStorage::new(&storage_path).await?);
+        Ok(Arc::new(LmdbStorage::new(&storage_path).await?))
+    }
+
+    /// Create an identities repository using the authority storage
+    fn create_identities_repository(
+        storage: Arc<dyn Storage>,
+        configuration: &Configuration,
+    ) -> Arc<dyn IdentitiesRepository> {
         let repository = Arc::new(IdentitiesStorage::new(storage));
-        Ok(Self::bootstrap_repository(repository, configuration))
+        Self::bootstrap_repository(repository, configuration)
     }
 
     /// Create a directory to save storage files if they haven't been  created before
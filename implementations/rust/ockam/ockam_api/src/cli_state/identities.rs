@@ -8,9 +8,13 @@ use time::format_description::well_known::Iso8601;
 use time::OffsetDateTime;
 
 use ockam::identity::storage::LmdbStorage;
+#[cfg(feature = "sqlite")]
+use ockam::identity::storage::{SqliteStorage, Storage};
 use ockam::identity::{Identifier, IdentitiesRepository, IdentitiesStorage};
 
 use crate::cli_state::traits::{StateDirTrait, StateItemTrait};
+#[cfg(feature = "sqlite")]
+use crate::cli_state::CliState;
 use crate::cli_state::{CliStateError, DATA_DIR_NAME};
 
 use super::Result;
@@ -39,13 +43,39 @@ impl IdentitiesState {
             })
     }
 
+    /// Return the identities repository, stored in the Sqlite database of the state directory
+    /// if the Sqlite storage is selected. In that case the entries of an existing LMDB storage
+    /// are first moved to the Sqlite database
     pub async fn identities_repository(&self) -> Result<Arc<dyn IdentitiesRepository>> {
+        #[cfg(feature = "sqlite")]
+        if let Some(database) =
+            CliState::sqlite_database(self.dir.parent().expect("Should have parent")).await?
+        {
+            let storage = SqliteStorage::from_database(&database).await?;
+            self.migrate_to_sqlite(&storage).await?;
+            return Ok(Arc::new(IdentitiesStorage::new(Arc::new(storage))));
+        }
         let lmdb_path = self.identities_repository_path()?;
         Ok(Arc::new(IdentitiesStorage::new(Arc::new(
             LmdbStorage::new(lmdb_path).await?,
         ))))
     }
 
+    /// Copy the entries of the LMDB storage to the Sqlite storage, then remove the LMDB storage
+    #[cfg(feature = "sqlite")]
+    async fn migrate_to_sqlite(&self, storage: &SqliteStorage) -> Result<()> {
+        let lmdb_path = self.identities_repository_path()?;
+        if !lmdb_path.exists() {
+            return Ok(());
+        }
+        let lmdb = LmdbStorage::new(&lmdb_path).await?;
+        for (id, key, value) in lmdb.entries().await? {
+            storage.set(&id, key, value).await?;
+        }
+        drop(lmdb);
+        CliState::remove_lmdb_storage(&lmdb_path)
+    }
+
     pub fn identities_repository_path(&self) -> Result<PathBuf> {
         let lmdb_path = self
             .dir
@@ -330,6 +360,31 @@ mod tests {
         assert_eq!(actual, expected)
     }
 
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_migrate_lmdb_storage_to_sqlite() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let state = IdentitiesState::new(dir.path());
+        let lmdb_path = state.identities_repository_path()?;
+        let lmdb = LmdbStorage::new(&lmdb_path).await?;
+        lmdb.set("I1234", "CHANGE_HISTORY".into(), vec![1, 2, 3])
+            .await?;
+        drop(lmdb);
+
+        let database = ockam_node::SqliteDatabase::create_in_memory()?;
+        let storage = SqliteStorage::from_database(&database).await?;
+        state.migrate_to_sqlite(&storage).await?;
+        assert_eq!(
+            storage.get("I1234", "CHANGE_HISTORY").await?,
+            Some(vec![1, 2, 3])
+        );
+        assert!(!lmdb_path.exists());
+
+        // there is nothing left to migrate
+        state.migrate_to_sqlite(&storage).await?;
+        Ok(())
+    }
+
     fn create_identity_config() -> IdentityConfig {
         let identifier = Identifier::try_from("Ifa804b7fca12a19eed206ae180b5b576860ae651").unwrap();
         IdentityConfig {
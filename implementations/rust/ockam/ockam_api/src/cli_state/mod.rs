@@ -31,6 +31,13 @@ use thiserror::Error;
 
 type Result<T> = std::result::Result<T, CliStateError>;
 
+/// Environment variable selecting the Sqlite storage: when set to `true` the vaults, the
+/// identities and the policies of the nodes are stored in one database of the state directory
+pub const OCKAM_SQLITE_STORAGE: &str = "OCKAM_SQLITE_STORAGE";
+
+/// Name of the Sqlite database file, at the root of the state directory
+pub const SQLITE_DATABASE_FILE_NAME: &str = "database.sqlite3";
+
 #[derive(Debug, Error, Diagnostic)]
 pub enum CliStateError {
     #[error(transparent)]
@@ -72,6 +79,13 @@ pub enum CliStateError {
     #[diagnostic(code("OCK500"))]
     InvalidOperation(String),
 
+    #[error("The vault {name} is encrypted")]
+    #[diagnostic(
+        code("OCK401"),
+        help("Please set the OCKAM_VAULT_PASSPHRASE or the OCKAM_VAULT_KEY_FILE environment variable")
+    )]
+    VaultLocked { name: String },
+
     #[error("Invalid configuration version '{0}'")]
     #[diagnostic(
         code("OCK500"),
@@ -122,12 +136,50 @@ impl CliState {
         Executor::execute_future(Self::initialize_cli_state())?
     }
 
+    /// Return true if the vaults, identities and policies are stored in a Sqlite database
+    pub fn uses_sqlite_storage() -> Result<bool> {
+        let use_sqlite = get_env_with_default(OCKAM_SQLITE_STORAGE, false)?;
+        if use_sqlite && cfg!(not(feature = "sqlite")) {
+            return Err(CliStateError::InvalidOperation(format!(
+                "{OCKAM_SQLITE_STORAGE} is set but this binary was built without the sqlite feature"
+            )));
+        }
+        Ok(use_sqlite)
+    }
+
+    /// Return the Sqlite database shared by the vaults, the identities and the policies
+    /// of the state directory `root`, if the Sqlite storage is selected
+    #[cfg(feature = "sqlite")]
+    pub(crate) async fn sqlite_database(root: &Path) -> Result<Option<ockam_node::SqliteDatabase>> {
+        if !Self::uses_sqlite_storage()? {
+            return Ok(None);
+        }
+        let database =
+            ockam_node::SqliteDatabase::create(&root.join(SQLITE_DATABASE_FILE_NAME)).await?;
+        Ok(Some(database))
+    }
+
+    /// Remove an LMDB storage, once its entries have been moved to the Sqlite database
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn remove_lmdb_storage(path: &Path) -> Result<()> {
+        let lock_path = PathBuf::from(format!("{}-lock", path.display()));
+        for path in [path, lock_path.as_path()] {
+            match std::fs::remove_file(path) {
+                // the storage may have been migrated concurrently by another process
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Create a new CliState by initializing all of its components
     /// The calls to 'init(dir)' are loading each piece of configuration and possibly doing some
     /// configuration migration if necessary
     async fn initialize_cli_state() -> Result<CliState> {
         let default = Self::default_dir()?;
         let dir = default.as_path();
+        Self::uses_sqlite_storage()?;
         let state = Self {
             vaults: VaultsState::init(dir).await?,
             identities: IdentitiesState::init(dir).await?,
@@ -220,9 +272,10 @@ impl CliState {
             let _ = std::fs::remove_dir_all(dir);
         }
 
-        // Delete config files located at the root of the state directory
+        // Delete config files and the database located at the root of the state directory
         let config_file = root_path.join("config.json");
         let _ = std::fs::remove_file(config_file);
+        let _ = std::fs::remove_file(root_path.join(SQLITE_DATABASE_FILE_NAME));
 
         // If the state directory is now empty, delete it
         let is_empty = std::fs::read_dir(root_path)
@@ -546,6 +599,59 @@ mod tests {
         assert!(!test_dir.join("config.json").exists());
     }
 
+    #[tokio::test]
+    async fn test_encrypted_vault() {
+        let sut = CliState::test().unwrap();
+        let key_file = sut.dir.join("vault.key");
+        ockam::vault::storage::StorageEncryption::create_key_file(&key_file).unwrap();
+
+        let name = random_name();
+        let config = VaultConfig::default().with_encryption(Some(key_file));
+        let state = sut.vaults.create_async(&name, config).await.unwrap();
+        let contents = std::fs::read_to_string(state.vault_file_path()).unwrap();
+        assert!(contents.contains("ciphertext"));
+
+        // the vault can be opened again with its key file
+        let got = sut.vaults.get(&name).unwrap();
+        assert!(got.get().await.is_ok());
+
+        // a plaintext vault can be encrypted
+        let name = random_name();
+        let state = sut
+            .vaults
+            .create_async(&name, VaultConfig::default())
+            .await
+            .unwrap();
+        let key_file = sut.dir.join("other_vault.key");
+        ockam::vault::storage::StorageEncryption::create_key_file(&key_file).unwrap();
+        let encrypted = state.encrypt(Some(key_file)).await.unwrap();
+        assert_eq!(sut.vaults.get(&name).unwrap(), encrypted);
+        assert!(encrypted.get().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_passphrase_encrypted_vault() {
+        let sut = CliState::test().unwrap();
+        let name = random_name();
+        sut.vaults
+            .passphrases()
+            .set(&name, "a passphrase".to_string());
+        let config = VaultConfig::default().with_encryption(None);
+        sut.vaults.create_async(&name, config).await.unwrap();
+        assert!(sut.vaults.get(&name).unwrap().get().await.is_ok());
+
+        // another process can't open the vault without its passphrase
+        let other = VaultsState::new(&sut.dir);
+        assert!(other.get(&name).unwrap().get().await.is_err());
+
+        // the passphrase is handed explicitly to the other process
+        other
+            .passphrases()
+            .import(&sut.vaults.passphrases().export().unwrap())
+            .unwrap();
+        assert!(other.get(&name).unwrap().get().await.is_ok());
+    }
+
     #[ockam_macros::test(crate = "ockam")]
     async fn integration(ctx: &mut ockam::Context) -> ockam::Result<()> {
         let sut = CliState::test()?;
@@ -1,7 +1,7 @@
 use super::Result;
 use crate::cli_state::{
     CliState, CliStateError, IdentityConfig, IdentityState, ProjectConfig, ProjectConfigCompact,
-    StateDirTrait, StateItemTrait, VaultState,
+    StateDirTrait, StateItemTrait, VaultsState,
 };
 use crate::config::lookup::ProjectLookup;
 use crate::nodes::models::transport::CreateTransportJson;
@@ -10,12 +10,17 @@ use miette::{IntoDiagnostic, WrapErr};
 use nix::errno::Errno;
 use ockam::identity::Identifier;
 use ockam::identity::Vault;
-use ockam::LmdbStorage;
+use ockam::{LmdbStorage, RelayMailboxStorage, StoredRelayMessage};
+use ockam_abac::PolicyStorage;
+#[cfg(feature = "sqlite")]
+use ockam_abac::SqlitePolicyStorage;
 use ockam_core::compat::collections::HashSet;
+use ockam_node::KeyValueStorage;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 use sysinfo::{Pid, ProcessExt, ProcessStatus, System, SystemExt};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -157,8 +162,64 @@ impl NodeState {
         self.paths.stderr()
     }
 
-    pub async fn policies_storage(&self) -> Result<LmdbStorage> {
-        Ok(LmdbStorage::new(self.paths.policies_storage()).await?)
+    /// Return the policies of the node, stored in the Sqlite database of the state directory
+    /// if the Sqlite storage is selected. In that case the policies of an existing LMDB storage
+    /// are first moved to the Sqlite database
+    pub async fn policies_storage(&self) -> Result<Arc<dyn PolicyStorage>> {
+        #[cfg(feature = "sqlite")]
+        {
+            let state_dir = self
+                .path
+                .parent()
+                .and_then(Path::parent)
+                .expect("Should have parent");
+            if let Some(database) = CliState::sqlite_database(state_dir).await? {
+                let storage = SqlitePolicyStorage::create(database.conn(), &self.name).await?;
+                let lmdb_path = self.paths.policies_storage();
+                if lmdb_path.exists() {
+                    let lmdb = LmdbStorage::new(&lmdb_path).await?;
+                    for (resource, action, expr) in lmdb.all_policies().await? {
+                        storage.set_policy(&resource, &action, &expr).await?;
+                    }
+                    drop(lmdb);
+                    CliState::remove_lmdb_storage(&lmdb_path)?;
+                }
+                return Ok(Arc::new(storage));
+            }
+        }
+        Ok(Arc::new(
+            LmdbStorage::new(self.paths.policies_storage()).await?,
+        ))
+    }
+
+    pub async fn policy_bundles_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.policy_bundles_storage()).await?)
+    }
+
+    /// Return the storage of the messages kept for the offline relay targets, in the Sqlite
+    /// database of the state directory if the Sqlite storage is selected
+    pub async fn relay_mailboxes_storage(
+        &self,
+    ) -> Result<Arc<dyn KeyValueStorage<String, StoredRelayMessage>>> {
+        #[cfg(feature = "sqlite")]
+        {
+            let state_dir = self
+                .path
+                .parent()
+                .and_then(Path::parent)
+                .expect("Should have parent");
+            if let Some(database) = CliState::sqlite_database(state_dir).await? {
+                return Ok(Arc::new(
+                    database.key_value_storage::<String, StoredRelayMessage>(&format!(
+                        "relay_mailboxes/{}",
+                        self.name
+                    )),
+                ));
+            }
+        }
+        Ok(RelayMailboxStorage::create(Arc::new(
+            LmdbStorage::new(self.paths.relay_mailboxes_storage()).await?,
+        )))
     }
 
     pub fn name(&self) -> &str {
@@ -195,8 +256,9 @@ impl NodeConfig {
         Ok(std::fs::canonicalize(&self.default_vault)?)
     }
 
-    pub async fn vault(&self) -> Result<Vault> {
-        let state = VaultState::load(self.vault_path()?)?;
+    /// Return the vault of the node, unlocked with the passphrases known by this process
+    pub async fn vault(&self, vaults: &VaultsState) -> Result<Vault> {
+        let state = vaults.load_vault(self.vault_path()?)?;
         state.get().await
     }
 
@@ -309,6 +371,8 @@ pub struct NodeSetupConfig {
     pub authority_node: Option<bool>,
     pub project: Option<ProjectLookup>,
     pub api_transport: Option<CreateTransportJson>,
+    /// Socket address of the metrics endpoint, if the node serves its metrics
+    pub metrics_address: Option<String>,
 }
 
 impl NodeSetupConfig {
@@ -332,6 +396,11 @@ impl NodeSetupConfig {
         self
     }
 
+    pub fn set_metrics_address(mut self, metrics_address: Option<String>) -> Self {
+        self.metrics_address = metrics_address;
+        self
+    }
+
     pub fn api_transport(&self) -> Result<&CreateTransportJson> {
         self.api_transport.as_ref().ok_or_else(|| {
             CliStateError::InvalidOperation(
@@ -384,6 +453,14 @@ impl NodePaths {
     fn policies_storage(&self) -> PathBuf {
         self.path.join("policies_storage.lmdb")
     }
+
+    fn policy_bundles_storage(&self) -> PathBuf {
+        self.path.join("policy_bundles_storage.lmdb")
+    }
+
+    fn relay_mailboxes_storage(&self) -> PathBuf {
+        self.path.join("relay_mailboxes_storage.lmdb")
+    }
 }
 
 mod backwards_compatibility {
@@ -492,6 +569,7 @@ mod traits {
                         authority_node: setup.authority_node,
                         project: setup.project,
                         api_transport: None,
+                        metrics_address: None,
                     };
                     if let Some(t) = setup
                         .transports
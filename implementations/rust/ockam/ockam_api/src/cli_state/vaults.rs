@@ -1,23 +1,113 @@
-use std::fmt::{Display, Formatter};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use serde::{Deserialize, Serialize};
 
 use ockam::identity::Vault;
+use ockam::vault::storage::{PersistentStorage, StorageEncryption};
+use ockam_core::env::get_env;
 use ockam_vault_aws::AwsSigningVault;
+use ockam_vault_pkcs11::{
+    Pkcs11Client, Pkcs11Config, Pkcs11SecureChannelVault, Pkcs11SigningVault, OCKAM_PKCS11_PIN,
+};
 
 use crate::cli_state::traits::StateItemTrait;
+#[cfg(feature = "sqlite")]
+use crate::cli_state::CliState;
 use crate::cli_state::{CliStateError, StateDirTrait, DATA_DIR_NAME};
 
 use super::Result;
 
+/// Environment variable containing the passphrase of encrypted vaults
+pub const OCKAM_VAULT_PASSPHRASE: &str = "OCKAM_VAULT_PASSPHRASE";
+
+/// Environment variable containing the path of the key file of encrypted vaults
+pub const OCKAM_VAULT_KEY_FILE: &str = "OCKAM_VAULT_KEY_FILE";
+
+/// Function asking the user for the passphrase of an encrypted vault, given the vault name
+pub type PassphrasePrompt = fn(&str) -> Option<String>;
+
+static PASSPHRASE_PROMPT: OnceLock<PassphrasePrompt> = OnceLock::new();
+
+/// Set the function used to ask for the passphrase of an encrypted vault when neither
+/// a key file nor the `OCKAM_VAULT_PASSPHRASE` environment variable are available
+pub fn set_passphrase_prompt(prompt: PassphrasePrompt) {
+    let _ = PASSPHRASE_PROMPT.set(prompt);
+}
+
+/// Passphrases of the encrypted vaults unlocked by this process
+///
+/// They are only kept in memory. They are handed explicitly to the vaults loaded by this
+/// process and sent to the standard input of the nodes started by this process
+#[derive(Clone, Default)]
+pub struct VaultPassphrases(Arc<Mutex<BTreeMap<String, String>>>);
+
+impl VaultPassphrases {
+    /// Return the passphrase of a vault if it is known
+    pub fn get(&self, vault_name: &str) -> Option<String> {
+        self.0.lock().unwrap().get(vault_name).cloned()
+    }
+
+    /// Set the passphrase of a vault
+    pub fn set(&self, vault_name: &str, passphrase: String) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(vault_name.to_string(), passphrase);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_empty()
+    }
+
+    /// Serialize all the passphrases in order to send them to another process
+    pub fn export(&self) -> Result<String> {
+        Ok(serde_json::to_string(&*self.0.lock().unwrap())?)
+    }
+
+    /// Add the passphrases exported by another process
+    pub fn import(&self, exported: &str) -> Result<()> {
+        let passphrases: BTreeMap<String, String> = serde_json::from_str(exported)?;
+        self.0.lock().unwrap().extend(passphrases);
+        Ok(())
+    }
+}
+
+impl Debug for VaultPassphrases {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("VaultPassphrases")
+    }
+}
+
+/// The passphrases are not part of the state stored on disk
+impl PartialEq for VaultPassphrases {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for VaultPassphrases {}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct VaultsState {
     dir: PathBuf,
+    passphrases: VaultPassphrases,
 }
 
 impl VaultsState {
+    /// Passphrases of the encrypted vaults unlocked by this process
+    pub fn passphrases(&self) -> &VaultPassphrases {
+        &self.passphrases
+    }
+
+    /// Load the vault stored at a given path, for example the vault of a node,
+    /// with the passphrases known by this process
+    pub fn load_vault(&self, path: PathBuf) -> Result<VaultState> {
+        Ok(VaultState::load(path)?.with_passphrases(self.passphrases.clone()))
+    }
+
     pub async fn create_async(&self, name: &str, config: VaultConfig) -> Result<VaultState> {
         if self.exists(name) {
             return Err(CliStateError::AlreadyExists {
@@ -25,7 +115,8 @@ impl VaultsState {
                 name: name.to_string(),
             });
         }
-        let state = VaultState::new(self.path(name), config)?;
+        let state =
+            VaultState::new(self.path(name), config)?.with_passphrases(self.passphrases.clone());
         state.get().await?;
         if !self.default_path()?.exists() {
             self.set_default(name)?;
@@ -41,9 +132,27 @@ pub struct VaultState {
     /// The path to the vault's storage config file, contained in the data directory
     data_path: PathBuf,
     config: VaultConfig,
+    passphrases: VaultPassphrases,
 }
 
 impl VaultState {
+    /// Use the passphrases known by this process to unlock the vault
+    pub fn with_passphrases(self, passphrases: VaultPassphrases) -> Self {
+        Self {
+            passphrases,
+            ..self
+        }
+    }
+
+    /// Make sure that the secret of an encrypted vault is available to this process,
+    /// asking for its passphrase if necessary
+    pub fn unlock(&self) -> Result<()> {
+        if self.config.is_encrypted() {
+            self.storage_encryption()?;
+        }
+        Ok(())
+    }
+
     pub async fn get(&self) -> Result<Vault> {
         if self.config.aws_kms {
             let mut vault = Vault::create();
@@ -52,11 +161,19 @@ impl VaultState {
             vault.credential_vault = aws_vault;
 
             Ok(vault)
-        } else {
-            let vault =
-                Vault::create_with_persistent_storage_path(self.vault_file_path().as_path())
-                    .await?;
+        } else if let Some(pkcs11) = self.config.pkcs11() {
+            let mut vault = Vault::create();
+            let client = Pkcs11Client::shared(pkcs11.client_config()?).await?;
+            let signing_vault =
+                Arc::new(Pkcs11SigningVault::create_with_client(client.clone()).await?);
+            vault.identity_vault = signing_vault.clone();
+            vault.credential_vault = signing_vault;
+            vault.secure_channel_vault =
+                Arc::new(Pkcs11SecureChannelVault::create_with_client(client).await?);
+
             Ok(vault)
+        } else {
+            self.vault().await
         }
     }
 
@@ -67,32 +184,152 @@ impl VaultState {
             .join(format!("{name}-storage.json"))
     }
 
+    /// Root of the state directory containing this vault
+    #[cfg(feature = "sqlite")]
+    fn state_dir(&self) -> &Path {
+        self.path
+            .parent()
+            .and_then(Path::parent)
+            .expect("Should have parent")
+    }
+
     pub fn vault_file_path(&self) -> &PathBuf {
         &self.data_path
     }
 
+    /// Return the vault. Its secrets are stored in the Sqlite database of the state directory
+    /// if the Sqlite storage is selected, except for encrypted vaults which are always stored
+    /// in their own file. The secrets of an existing vault file are first moved to the Sqlite
+    /// database
     pub async fn vault(&self) -> Result<Vault> {
+        #[cfg(feature = "sqlite")]
+        if !self.config.is_encrypted() {
+            if let Some(database) = CliState::sqlite_database(self.state_dir()).await? {
+                PersistentStorage::migrate_to_sqlite(self.vault_file_path(), &database, &self.name)
+                    .await?;
+                let storage = PersistentStorage::create_with_sqlite(&database, &self.name);
+                return Ok(Vault::create_with_persistent_storage(storage));
+            }
+        }
         let path = self.vault_file_path().clone();
-        let vault = Vault::create_with_persistent_storage_path(path.as_path()).await?;
+        let vault = if self.config.is_encrypted() {
+            Vault::create_with_encrypted_persistent_storage_path(
+                path.as_path(),
+                self.storage_encryption()?,
+            )
+            .await?
+        } else {
+            Vault::create_with_persistent_storage_path(path.as_path()).await?
+        };
         Ok(vault)
     }
 
+    /// Encrypt the secrets of an existing vault and update its configuration
+    pub async fn encrypt(&self, key_file: Option<PathBuf>) -> Result<VaultState> {
+        if self.config.is_aws() {
+            return Err(CliStateError::InvalidOperation(
+                "The keys of an AWS KMS vault are not stored locally".to_string(),
+            ));
+        }
+        if self.config.is_pkcs11() {
+            return Err(CliStateError::InvalidOperation(
+                "The keys of a PKCS#11 vault are stored in its token".to_string(),
+            ));
+        }
+        if self.config.is_encrypted() {
+            return Err(CliStateError::InvalidOperation(format!(
+                "The vault {} is already encrypted",
+                self.name
+            )));
+        }
+        let encrypted = VaultState {
+            config: self.config.clone().with_encryption(key_file),
+            ..self.clone()
+        };
+        let config = serde_json::to_string(&encrypted.config)?;
+        let encryption = encrypted.storage_encryption()?;
+
+        // the plaintext file is restored if the new configuration can not be written,
+        // so that the configuration always describes the content of the vault file
+        let plaintext = std::fs::read(self.vault_file_path())?;
+        PersistentStorage::encrypt(self.vault_file_path(), encryption).await?;
+        if let Err(e) = write_file_atomically(&encrypted.path, config.as_bytes()) {
+            write_file_atomically(self.vault_file_path(), &plaintext)?;
+            return Err(e);
+        }
+        Ok(encrypted)
+    }
+
+    /// Return the secret used to encrypt the vault storage. It comes, in order, from:
+    ///  - the key file configured for the vault
+    ///  - the key file given by the `OCKAM_VAULT_KEY_FILE` environment variable
+    ///  - the passphrase already known by this process, or sent by its parent process
+    ///  - the passphrase given by the `OCKAM_VAULT_PASSPHRASE` environment variable
+    ///  - the passphrase prompt, if one is set
+    fn storage_encryption(&self) -> Result<StorageEncryption> {
+        let key_file = match self.config.key_file() {
+            Some(key_file) => Some(key_file.clone()),
+            None => get_env::<String>(OCKAM_VAULT_KEY_FILE)?.map(PathBuf::from),
+        };
+        if let Some(key_file) = key_file {
+            return Ok(StorageEncryption::from_key_file(&key_file)?);
+        }
+        if let Some(passphrase) = self.passphrases.get(&self.name) {
+            return Ok(StorageEncryption::from_passphrase(&passphrase)?);
+        }
+        if let Some(passphrase) = get_env::<String>(OCKAM_VAULT_PASSPHRASE)? {
+            return Ok(StorageEncryption::from_passphrase(&passphrase)?);
+        }
+        if let Some(prompt) = PASSPHRASE_PROMPT.get() {
+            if let Some(passphrase) = prompt(&self.name) {
+                let encryption = StorageEncryption::from_passphrase(&passphrase)?;
+                // keep the passphrase for the next accesses to the vault
+                // and for the nodes started by this process
+                self.passphrases.set(&self.name, passphrase);
+                return Ok(encryption);
+            }
+        }
+        Err(CliStateError::VaultLocked {
+            name: self.name.clone(),
+        })
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 }
 
+/// Write a file through a temporary file, so that it is either fully written or left unchanged
+fn write_file_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let temp_path = PathBuf::from(format!("{}.tmp", path.display()));
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
 impl Display for VaultState {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Name: {}", self.name)?;
         writeln!(
             f,
             "Type: {}",
-            match self.config.is_aws() {
-                true => "AWS KMS",
-                false => "OCKAM",
+            if self.config.is_aws() {
+                "AWS KMS"
+            } else if self.config.is_pkcs11() {
+                "PKCS#11"
+            } else {
+                "OCKAM"
             }
         )?;
+        if let Some(pkcs11) = self.config.pkcs11() {
+            writeln!(f, "PKCS#11 module: {}", pkcs11.module.display())?;
+            if let Some(token_label) = &pkcs11.token_label {
+                writeln!(f, "PKCS#11 token: {token_label}")?;
+            }
+        }
+        if self.config.is_encrypted() {
+            writeln!(f, "Encrypted: true")?;
+        }
         Ok(())
     }
 }
@@ -101,16 +338,82 @@ impl Display for VaultState {
 pub struct VaultConfig {
     #[serde(default)]
     aws_kms: bool,
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pkcs11: Option<Pkcs11VaultConfig>,
 }
 
 impl VaultConfig {
     pub fn new(aws_kms: bool) -> Result<Self> {
-        Ok(Self { aws_kms })
+        Ok(Self {
+            aws_kms,
+            ..Default::default()
+        })
+    }
+
+    /// Encrypt the vault secrets at rest with a key file if one is given,
+    /// or with a passphrase otherwise
+    pub fn with_encryption(self, key_file: Option<PathBuf>) -> Self {
+        Self {
+            encrypted: true,
+            key_file,
+            ..self
+        }
+    }
+
+    /// Store the keys of the vault in a PKCS#11 token
+    pub fn with_pkcs11(self, module: PathBuf, token_label: Option<String>) -> Self {
+        Self {
+            pkcs11: Some(Pkcs11VaultConfig {
+                module,
+                token_label,
+            }),
+            ..self
+        }
     }
 
     pub fn is_aws(&self) -> bool {
         self.aws_kms
     }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    pub fn key_file(&self) -> Option<&PathBuf> {
+        self.key_file.as_ref()
+    }
+
+    pub fn is_pkcs11(&self) -> bool {
+        self.pkcs11.is_some()
+    }
+
+    pub fn pkcs11(&self) -> Option<&Pkcs11VaultConfig> {
+        self.pkcs11.as_ref()
+    }
+}
+
+/// Location of the keys of a PKCS#11 vault. The user PIN of the token
+/// is not stored, it is read from the `OCKAM_PKCS11_PIN` environment variable
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Pkcs11VaultConfig {
+    module: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_label: Option<String>,
+}
+
+impl Pkcs11VaultConfig {
+    fn client_config(&self) -> Result<Pkcs11Config> {
+        let pin = get_env::<String>(OCKAM_PKCS11_PIN)?.unwrap_or_default();
+        let config = Pkcs11Config::new(self.module.clone(), pin);
+        Ok(match &self.token_label {
+            Some(token_label) => config.with_token_label(token_label),
+            None => config,
+        })
+    }
 }
 
 mod traits {
@@ -131,9 +434,25 @@ mod traits {
         fn new(root_path: &Path) -> Self {
             Self {
                 dir: Self::build_dir(root_path),
+                passphrases: VaultPassphrases::default(),
             }
         }
 
+        fn get(&self, name: impl AsRef<str>) -> Result<Self::Item> {
+            if !self.exists(&name) {
+                return Err(CliStateError::ResourceNotFound {
+                    resource: Self::default_filename().to_string(),
+                    name: name.as_ref().to_string(),
+                });
+            }
+            self.load_vault(self.path(&name))
+        }
+
+        fn default(&self) -> Result<Self::Item> {
+            let path = std::fs::canonicalize(self.default_path()?)?;
+            self.load_vault(path)
+        }
+
         fn dir(&self) -> &PathBuf {
             &self.dir
         }
@@ -179,6 +498,7 @@ mod traits {
                 path,
                 data_path,
                 config,
+                passphrases: VaultPassphrases::default(),
             })
         }
 
@@ -192,6 +512,7 @@ mod traits {
                 path,
                 data_path,
                 config,
+                passphrases: VaultPassphrases::default(),
             })
         }
 
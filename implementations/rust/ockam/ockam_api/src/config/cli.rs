@@ -109,6 +109,24 @@ impl TrustContextConfig {
             .ok_or_else(|| ApiError::core("Missing authority on trust context config"))
     }
 
+    /// Return the information necessary to retrieve the revocation list of the authority,
+    /// if the authority can be reached
+    pub async fn revocation_list_issuer(&self) -> Result<Option<RemoteCredentialsRetrieverInfo>> {
+        let issuer_config = match self
+            .authority
+            .as_ref()
+            .and_then(|a| a.own_credential.as_ref())
+        {
+            Some(CredentialRetrieverConfig::FromCredentialIssuer(issuer_config)) => issuer_config,
+            _ => return Ok(None),
+        };
+        Ok(Some(RemoteCredentialsRetrieverInfo::new(
+            issuer_config.resolve_identity().await?.identifier().clone(),
+            issuer_config.resolve_route().await?,
+            DefaultAddress::REVOCATION_LIST.into(),
+        )))
+    }
+
     pub async fn to_trust_context(
         &self,
         secure_channels: Arc<SecureChannels>,
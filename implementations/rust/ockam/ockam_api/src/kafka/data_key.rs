@@ -0,0 +1,234 @@
+use minicbor::{Decode, Encode};
+use ockam_core::compat::collections::VecDeque;
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, Error, Result};
+use ockam_vault::{KeyId, Secret, SecretAttributes, SecureChannelVault};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tag")]
+use ockam_core::TypeTag;
+
+/// A data key is used to encrypt records for at most this duration
+const DATA_KEY_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// When some consumers could not be reached, a new data key is created after this duration
+/// in order to include them
+const DATA_KEY_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of data keys kept by a consumer
+const MAX_UNWRAPPED_DATA_KEYS: usize = 32;
+
+const DATA_KEY_ID_LENGTH: usize = 16;
+const DATA_KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+/// Data key used to encrypt records once for several consumers.
+/// It is sent along with each record, wrapped for each consumer
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(crate) struct WrappedDataKey {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<3936211>,
+    #[n(1)] pub(crate) key_id: Vec<u8>,
+    #[n(2)] pub(crate) consumers: Vec<ConsumerDataKey>,
+}
+
+/// Data key encrypted with the secure channel established with one consumer
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(crate) struct ConsumerDataKey {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<6811475>,
+    #[n(1)] pub(crate) consumer_decryptor_address: Address,
+    #[n(2)] pub(crate) wrapped_key: Vec<u8>,
+}
+
+impl ConsumerDataKey {
+    pub(crate) fn new(consumer_decryptor_address: Address, wrapped_key: Vec<u8>) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            consumer_decryptor_address,
+            wrapped_key,
+        }
+    }
+}
+
+/// Data key currently used by a producer
+#[derive(Clone)]
+pub(crate) struct DataKey {
+    pub(crate) secret: KeyId,
+    pub(crate) wrapped: WrappedDataKey,
+    created_at: Instant,
+    // true if the key could be wrapped for all the consumers
+    complete: bool,
+}
+
+impl DataKey {
+    /// Generate a new random data key and return its content, to be wrapped for each consumer
+    pub(crate) async fn generate(
+        vault: &Arc<dyn SecureChannelVault>,
+    ) -> Result<(Vec<u8>, Vec<u8>, KeyId)> {
+        let mut key_id = vec![0; DATA_KEY_ID_LENGTH];
+        thread_rng().fill_bytes(&mut key_id);
+        let mut key = vec![0; DATA_KEY_LENGTH];
+        thread_rng().fill_bytes(&mut key);
+        let secret = import_data_key(vault, key.clone()).await?;
+        Ok((key_id, key, secret))
+    }
+
+    pub(crate) fn new(
+        key_id: Vec<u8>,
+        secret: KeyId,
+        consumers: Vec<ConsumerDataKey>,
+        complete: bool,
+    ) -> Self {
+        Self {
+            secret,
+            wrapped: WrappedDataKey {
+                #[cfg(feature = "tag")]
+                tag: TypeTag,
+                key_id,
+                consumers,
+            },
+            created_at: Instant::now(),
+            complete,
+        }
+    }
+
+    /// Return true if a new data key must be created
+    pub(crate) fn should_rotate(&self) -> bool {
+        let lifetime = if self.complete {
+            DATA_KEY_LIFETIME
+        } else {
+            DATA_KEY_RETRY_INTERVAL
+        };
+        self.created_at.elapsed() >= lifetime
+    }
+}
+
+/// Data keys already unwrapped by a consumer, the oldest ones are removed first
+#[derive(Default)]
+pub(crate) struct UnwrappedDataKeys {
+    keys: VecDeque<(Vec<u8>, KeyId)>,
+}
+
+impl UnwrappedDataKeys {
+    pub(crate) fn get(&self, key_id: &[u8]) -> Option<KeyId> {
+        self.keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, secret)| secret.clone())
+    }
+
+    /// Add a new data key and return the secret of the removed key, if any
+    pub(crate) fn insert(&mut self, key_id: Vec<u8>, secret: KeyId) -> Option<KeyId> {
+        self.keys.push_back((key_id, secret));
+        if self.keys.len() > MAX_UNWRAPPED_DATA_KEYS {
+            self.keys.pop_front().map(|(_, secret)| secret)
+        } else {
+            None
+        }
+    }
+}
+
+/// Import the content of a data key in the vault
+pub(crate) async fn import_data_key(
+    vault: &Arc<dyn SecureChannelVault>,
+    key: Vec<u8>,
+) -> Result<KeyId> {
+    if key.len() != DATA_KEY_LENGTH {
+        return Err(Error::new(
+            Origin::Channel,
+            Kind::Invalid,
+            "invalid kafka data key length",
+        ));
+    }
+    vault
+        .import_ephemeral_secret(Secret::new(key), SecretAttributes::Aes256)
+        .await
+}
+
+/// Encrypt a record with a data key. The random nonce is prepended to the encrypted content
+pub(crate) async fn encrypt_record(
+    vault: &Arc<dyn SecureChannelVault>,
+    secret: &KeyId,
+    key_id: &[u8],
+    content: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LENGTH];
+    thread_rng().fill_bytes(&mut nonce);
+    let encrypted = vault
+        .aead_aes_gcm_encrypt(secret, &content, &nonce, key_id)
+        .await?;
+
+    let mut record = nonce.to_vec();
+    record.extend_from_slice(encrypted.as_slice());
+    Ok(record)
+}
+
+/// Decrypt a record encrypted with a data key
+pub(crate) async fn decrypt_record(
+    vault: &Arc<dyn SecureChannelVault>,
+    secret: &KeyId,
+    key_id: &[u8],
+    record: Vec<u8>,
+) -> Result<Vec<u8>> {
+    if record.len() < NONCE_LENGTH {
+        return Err(Error::new(
+            Origin::Channel,
+            Kind::Invalid,
+            "invalid kafka record length",
+        ));
+    }
+    let (nonce, encrypted) = record.split_at(NONCE_LENGTH);
+    Ok(vault
+        .aead_aes_gcm_decrypt(secret, encrypted, nonce, key_id)
+        .await?
+        .to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ockam_vault::SoftwareSecureChannelVault;
+
+    #[tokio::test]
+    async fn encrypt_decrypt_record() -> Result<()> {
+        let vault: Arc<dyn SecureChannelVault> = SoftwareSecureChannelVault::create();
+        let (key_id, key, secret) = DataKey::generate(&vault).await?;
+
+        let record = encrypt_record(&vault, &secret, &key_id, b"hello".to_vec()).await?;
+
+        // a consumer imports the unwrapped key to decrypt the record
+        let unwrapped = import_data_key(&vault, key).await?;
+        let decrypted = decrypt_record(&vault, &unwrapped, &key_id, record.clone()).await?;
+        assert_eq!(decrypted, b"hello".to_vec());
+
+        // the key identifier is authenticated
+        assert!(decrypt_record(&vault, &unwrapped, b"other", record)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unwrapped_data_keys_are_bounded() {
+        let mut keys = UnwrappedDataKeys::default();
+        for i in 0..MAX_UNWRAPPED_DATA_KEYS {
+            assert!(keys
+                .insert(vec![i as u8], KeyId::from(format!("{i}")))
+                .is_none());
+        }
+        assert_eq!(
+            keys.insert(vec![255], KeyId::from("new")),
+            Some(KeyId::from("0"))
+        );
+        assert!(keys.get(&[0]).is_none());
+        assert_eq!(keys.get(&[1]), Some(KeyId::from("1")));
+    }
+}
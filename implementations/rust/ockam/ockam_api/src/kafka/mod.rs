@@ -1,6 +1,7 @@
 //!This service allows encrypted transparent communication from the kafka producer
 //! to the kafka consumer without any modification in the existing application.
 
+mod data_key;
 mod inlet_controller;
 mod integration_test;
 mod length_delimited;
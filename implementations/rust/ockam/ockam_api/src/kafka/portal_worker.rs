@@ -128,6 +128,10 @@ impl Worker for KafkaPortalWorker {
                 }
             }
             PortalMessage::Ping => self.forward(context, routed_message).await?,
+            // The payloads are transformed, so the numbers of bytes acknowledged by each
+            // side of the portal would not match. Credits are dropped so that both sides
+            // send their payloads without waiting for acknowledgements
+            PortalMessage::Credit(_) => {}
 
             PortalMessage::Pong => {
                 match self.receiving {
@@ -1,3 +1,4 @@
+use crate::kafka::data_key::WrappedDataKey;
 use crate::kafka::portal_worker::InterceptError;
 use crate::kafka::secure_channel_map::KafkaSecureChannelController;
 use crate::kafka::KafkaInletController;
@@ -84,8 +85,9 @@ impl KafkaMessageInterceptor for InletInterceptorImpl {
 struct MessageWrapper {
     #[cfg(feature = "tag")]
     #[n(0)] tag: TypeTag<1652221>,
-    #[n(1)] consumer_decryptor_address: Address,
-    #[n(2)] content: Vec<u8>
+    #[n(1)] consumer_decryptor_address: Option<Address>,
+    #[n(2)] content: Vec<u8>,
+    #[n(3)] data_key: Option<WrappedDataKey>,
 }
 
 impl InletInterceptorImpl {
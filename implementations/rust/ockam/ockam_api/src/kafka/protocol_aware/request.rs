@@ -183,14 +183,15 @@ impl InletInterceptorImpl {
                                 .await
                                 .map_err(InterceptError::Ockam)?;
 
-                            //TODO: to target multiple consumers we could duplicate
-                            // the content with a dedicated encryption for each consumer
+                            // when records are broadcast to several consumers, the content
+                            // is encrypted once and the data key is wrapped for each consumer
                             let wrapper = MessageWrapper {
                                 #[cfg(feature = "tag")]
                                 tag: TypeTag,
                                 consumer_decryptor_address: encrypted_content
                                     .consumer_decryptor_address,
                                 content: encrypted_content.content,
+                                data_key: encrypted_content.data_key,
                             };
 
                             let mut write_buffer = Vec::with_capacity(1024);
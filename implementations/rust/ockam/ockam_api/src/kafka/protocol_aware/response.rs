@@ -235,15 +235,34 @@ impl InletInterceptorImpl {
                                     InterceptError::Io(Error::from(ErrorKind::InvalidData))
                                 })?;
 
-                            let decrypted_content = self
-                                .secure_channel_controller
-                                .decrypt_content_for(
-                                    context,
-                                    &message_wrapper.consumer_decryptor_address,
-                                    message_wrapper.content,
-                                )
-                                .await
-                                .map_err(InterceptError::Ockam)?;
+                            let decrypted_content = match (
+                                &message_wrapper.data_key,
+                                &message_wrapper.consumer_decryptor_address,
+                            ) {
+                                (Some(data_key), _) => self
+                                    .secure_channel_controller
+                                    .decrypt_broadcast_content(
+                                        context,
+                                        data_key,
+                                        message_wrapper.content,
+                                    )
+                                    .await
+                                    .map_err(InterceptError::Ockam)?,
+                                (None, Some(consumer_decryptor_address)) => self
+                                    .secure_channel_controller
+                                    .decrypt_content_for(
+                                        context,
+                                        consumer_decryptor_address,
+                                        message_wrapper.content,
+                                    )
+                                    .await
+                                    .map_err(InterceptError::Ockam)?,
+                                (None, None) => {
+                                    return Err(InterceptError::Io(Error::from(
+                                        ErrorKind::InvalidData,
+                                    )))
+                                }
+                            };
 
                             record.value = Some(decrypted_content.into());
                         }
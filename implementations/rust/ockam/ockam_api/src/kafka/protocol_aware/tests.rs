@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod test {
+    use crate::kafka::data_key::WrappedDataKey;
     use crate::kafka::inlet_controller::KafkaInletController;
     use crate::kafka::protocol_aware::utils::{encode_request, encode_response};
     use crate::kafka::protocol_aware::InletInterceptorImpl;
@@ -30,7 +31,8 @@ mod test {
         ) -> ockam_core::Result<KafkaEncryptedContent> {
             Ok(KafkaEncryptedContent {
                 content,
-                consumer_decryptor_address: Address::from_string("arbitrary string"),
+                consumer_decryptor_address: Some(Address::from_string("arbitrary string")),
+                data_key: None,
             })
         }
 
@@ -43,6 +45,15 @@ mod test {
             Ok(encrypted_content)
         }
 
+        async fn decrypt_broadcast_content(
+            &self,
+            _context: &mut Context,
+            _data_key: &WrappedDataKey,
+            encrypted_content: Vec<u8>,
+        ) -> ockam_core::Result<Vec<u8>> {
+            Ok(encrypted_content)
+        }
+
         async fn start_forwarders_for(
             &self,
             _context: &mut Context,
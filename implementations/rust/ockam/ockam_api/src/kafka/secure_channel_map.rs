@@ -1,3 +1,7 @@
+use crate::kafka::data_key::{
+    decrypt_record, encrypt_record, import_data_key, ConsumerDataKey, DataKey, UnwrappedDataKeys,
+    WrappedDataKey,
+};
 use crate::kafka::KAFKA_OUTLET_CONSUMERS;
 use crate::nodes::models::forwarder::{CreateForwarder, ForwarderInfo};
 use crate::nodes::models::secure_channel::{
@@ -26,8 +30,12 @@ use ockam_node::Context;
 pub(crate) struct KafkaEncryptedContent {
     /// The encrypted content
     pub(crate) content: Vec<u8>,
-    /// The secure channel identifier used to encrypt the content
-    pub(crate) consumer_decryptor_address: Address,
+    /// The secure channel identifier used to encrypt the content, when it is encrypted
+    /// for a single consumer
+    pub(crate) consumer_decryptor_address: Option<Address>,
+    /// The data key used to encrypt the content, wrapped for each consumer, when the content
+    /// is encrypted once for several consumers
+    pub(crate) data_key: Option<WrappedDataKey>,
 }
 
 /// Offer simple APIs to encrypt and decrypt kafka messages.
@@ -62,6 +70,17 @@ pub(crate) trait KafkaSecureChannelController: Send + Sync {
         encrypted_content: Vec<u8>,
     ) -> Result<Vec<u8>>;
 
+    /// Decrypts the content encrypted once for several consumers, with a data key
+    /// wrapped for each of them.
+    /// The data key is unwrapped with the secure channel established with the producer,
+    /// which is expected to be already initialized.
+    async fn decrypt_broadcast_content(
+        &self,
+        context: &mut Context,
+        data_key: &WrappedDataKey,
+        encrypted_content: Vec<u8>,
+    ) -> Result<Vec<u8>>;
+
     /// Starts forwarders in the orchestrator for each {topic_name}_{partition} combination
     /// should be used only by the consumer.
     /// does nothing if they were already created, but fails it they already exist.
@@ -157,10 +176,12 @@ impl<F: ForwarderCreator> Clone for KafkaSecureChannelControllerImpl<F> {
 
 /// Describe to reach the consumer node:
 /// either directly or through a relay with a forwarder
+/// or, to broadcast the records, each consumer node with its own route
 #[derive(Clone)]
 pub(crate) enum ConsumerNodeAddr {
     Direct(Option<MultiAddr>),
     Relay(MultiAddr),
+    Broadcast(Vec<MultiAddr>),
 }
 
 type TopicPartition = (String, i32);
@@ -174,6 +195,10 @@ struct InnerSecureChannelControllerImpl<F: ForwarderCreator> {
     forwarder_creator: Option<F>,
     secure_channels: Arc<SecureChannels>,
     access_control: AbacAccessControl,
+    // data key used by a producer to broadcast the records
+    data_key: Option<DataKey>,
+    // data keys used by a consumer to decrypt broadcast records
+    unwrapped_data_keys: UnwrappedDataKeys,
 }
 
 impl KafkaSecureChannelControllerImpl<NodeManagerForwarderCreator> {
@@ -183,7 +208,7 @@ impl KafkaSecureChannelControllerImpl<NodeManagerForwarderCreator> {
         trust_context_id: String,
     ) -> KafkaSecureChannelControllerImpl<NodeManagerForwarderCreator> {
         let forwarder_creator = match consumer_node_multiaddr.clone() {
-            ConsumerNodeAddr::Direct(_) => None,
+            ConsumerNodeAddr::Direct(_) | ConsumerNodeAddr::Broadcast(_) => None,
             ConsumerNodeAddr::Relay(mut orchestrator_multiaddr) => {
                 orchestrator_multiaddr
                     .push_back(Service::new(KAFKA_OUTLET_CONSUMERS))
@@ -224,6 +249,8 @@ impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
                 forwarder_creator,
                 consumer_node_multiaddr,
                 access_control,
+                data_key: None,
+                unwrapped_data_keys: Default::default(),
             })),
         }
     }
@@ -326,50 +353,60 @@ impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
 
         // when we are using direct mode, there is only one consumer, and use the same secure
         // channel for all topics
-        let topic_partition_key = match &inner.consumer_node_multiaddr {
-            ConsumerNodeAddr::Direct(_) => ("".to_string(), 0i32),
-            ConsumerNodeAddr::Relay(_) => (topic_name.to_string(), partition),
-        };
+        let (topic_partition_key, destination) = match inner.consumer_node_multiaddr.clone() {
+            ConsumerNodeAddr::Direct(destination) => {
+                if let Some(mut destination) = destination {
+                    debug!("creating new direct secure channel to consumer");
+                    destination.push_back(Service::new(DefaultAddress::SECURE_CHANNEL_LISTENER))?;
+                    (("".to_string(), 0i32), destination)
+                } else {
+                    return Err(Error::new(
+                        Origin::Transport,
+                        Kind::Invalid,
+                        "cannot encrypt messages when consumer is not specified",
+                    ));
+                }
+            }
 
-        let encryptor_address = {
-            if let Some(encryptor_address) = inner.topic_encryptor_map.get(&topic_partition_key) {
-                encryptor_address.clone()
-            } else {
-                let destination = match inner.consumer_node_multiaddr.clone() {
-                    ConsumerNodeAddr::Direct(destination) => {
-                        if let Some(mut destination) = destination {
-                            debug!("creating new direct secure channel to consumer");
-                            destination
-                                .push_back(Service::new(DefaultAddress::SECURE_CHANNEL_LISTENER))?;
-                            destination
-                        } else {
-                            return Err(Error::new(
-                                Origin::Transport,
-                                Kind::Invalid,
-                                "cannot encrypt messages when consumer is not specified",
-                            ));
-                        }
-                    }
+            ConsumerNodeAddr::Relay(mut destination) => {
+                //consumer__ prefix is added by the orchestrator
+                let topic_partition_address = format!("consumer__{topic_name}_{partition}");
 
-                    ConsumerNodeAddr::Relay(mut destination) => {
-                        //consumer__ prefix is added by the orchestrator
-                        let topic_partition_address = format!("consumer__{topic_name}_{partition}");
+                debug!("creating new secure channel via relay to {topic_partition_address}");
 
-                        debug!(
-                            "creating new secure channel via relay to {topic_partition_address}"
-                        );
+                destination.push_back(Service::new(topic_partition_address))?;
+                destination.push_back(Service::new(DefaultAddress::SECURE_CHANNEL_LISTENER))?;
+                ((topic_name.to_string(), partition), destination)
+            }
 
-                        destination.push_back(Service::new(topic_partition_address))?;
-                        destination
-                            .push_back(Service::new(DefaultAddress::SECURE_CHANNEL_LISTENER))?;
-                        destination
-                    }
-                };
+            ConsumerNodeAddr::Broadcast(_) => {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::Invalid,
+                    "records are broadcast with a data key",
+                ))
+            }
+        };
 
+        Self::get_or_create_secure_channel(context, &mut inner, topic_partition_key, destination)
+            .await
+    }
+
+    /// Return the secure channel identified by a key, creating it if it doesn't exist yet
+    async fn get_or_create_secure_channel(
+        context: &mut Context,
+        inner: &mut MutexGuard<'_, InnerSecureChannelControllerImpl<F>>,
+        key: TopicPartition,
+        destination: MultiAddr,
+    ) -> Result<SecureChannelRegistryEntry> {
+        let encryptor_address = {
+            if let Some(encryptor_address) = inner.topic_encryptor_map.get(&key) {
+                encryptor_address.clone()
+            } else {
                 let producer_encryptor_address =
                     Self::request_secure_channel_creation(context, destination).await?;
 
-                match Self::validate_consumer_credentials(&inner, &producer_encryptor_address).await
+                match Self::validate_consumer_credentials(inner, &producer_encryptor_address).await
                 {
                     Ok(producer_encryptor_address) => producer_encryptor_address,
                     Err(error) => {
@@ -381,7 +418,7 @@ impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
 
                 inner
                     .topic_encryptor_map
-                    .insert(topic_partition_key, producer_encryptor_address.clone());
+                    .insert(key, producer_encryptor_address.clone());
 
                 debug!("created secure channel");
                 producer_encryptor_address
@@ -401,6 +438,98 @@ impl<F: ForwarderCreator> KafkaSecureChannelControllerImpl<F> {
             })
     }
 
+    /// Return the data key used to broadcast records, creating a new one when it expires.
+    /// The data key is wrapped for each consumer which can be reached and is authorized, using
+    /// the secure channel established with that consumer
+    async fn get_or_rotate_data_key(
+        context: &mut Context,
+        inner: &mut MutexGuard<'_, InnerSecureChannelControllerImpl<F>>,
+    ) -> Result<DataKey> {
+        if let Some(data_key) = &inner.data_key {
+            if !data_key.should_rotate() {
+                return Ok(data_key.clone());
+            }
+        }
+
+        let routes = match &inner.consumer_node_multiaddr {
+            ConsumerNodeAddr::Broadcast(routes) => routes.clone(),
+            _ => {
+                return Err(Error::new(
+                    Origin::Transport,
+                    Kind::Invalid,
+                    "records are only broadcast to a list of consumers",
+                ))
+            }
+        };
+
+        let vault = inner.secure_channels.vault().secure_channel_vault;
+        let (key_id, key, secret) = DataKey::generate(&vault).await?;
+
+        let mut consumers = vec![];
+        for route in routes.iter() {
+            let mut destination = route.clone();
+            destination.push_back(Service::new(DefaultAddress::SECURE_CHANNEL_LISTENER))?;
+            let entry = match Self::get_or_create_secure_channel(
+                context,
+                inner,
+                (route.to_string(), 0),
+                destination,
+            )
+            .await
+            {
+                Ok(entry) => entry,
+                Err(error) => {
+                    warn!("cannot wrap the kafka data key for the consumer at {route}: {error}");
+                    continue;
+                }
+            };
+            let wrapped_key = Self::encrypt_with(context, &entry, key.clone()).await?;
+            consumers.push(ConsumerDataKey::new(
+                entry.their_decryptor_address(),
+                wrapped_key,
+            ));
+        }
+
+        if consumers.is_empty() {
+            vault.delete_secret(secret).await?;
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                "no consumer can receive the kafka data key",
+            ));
+        }
+
+        let complete = consumers.len() == routes.len();
+        let data_key = DataKey::new(key_id, secret, consumers, complete);
+        if let Some(previous) = inner.data_key.replace(data_key.clone()) {
+            vault.delete_secret(previous.secret).await?;
+        }
+        debug!("created a new kafka data key");
+        Ok(data_key)
+    }
+
+    /// Encrypt some content with the secure channel encryptor
+    async fn encrypt_with(
+        context: &mut Context,
+        secure_channel_entry: &SecureChannelRegistryEntry,
+        content: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let encryption_response: EncryptionResponse = context
+            .send_and_receive(
+                route![secure_channel_entry.encryptor_api_address().clone()],
+                EncryptionRequest(content),
+            )
+            .await?;
+
+        match encryption_response {
+            EncryptionResponse::Ok(p) => Ok(p),
+            EncryptionResponse::Err(cause) => {
+                warn!("cannot encrypt kafka message");
+                Err(cause)
+            }
+        }
+    }
+
     async fn validate_consumer_credentials(
         inner: &MutexGuard<'_, InnerSecureChannelControllerImpl<F>>,
         producer_encryptor_address: &Address,
@@ -481,6 +610,23 @@ impl<F: ForwarderCreator> KafkaSecureChannelController for KafkaSecureChannelCon
         partition_id: i32,
         content: Vec<u8>,
     ) -> Result<KafkaEncryptedContent> {
+        {
+            let mut inner = self.inner.lock().await;
+            if let ConsumerNodeAddr::Broadcast(_) = inner.consumer_node_multiaddr {
+                // the content is encrypted once, with a data key wrapped for each consumer
+                let data_key = Self::get_or_rotate_data_key(context, &mut inner).await?;
+                let vault = inner.secure_channels.vault().secure_channel_vault;
+                let content =
+                    encrypt_record(&vault, &data_key.secret, &data_key.wrapped.key_id, content)
+                        .await?;
+                return Ok(KafkaEncryptedContent {
+                    content,
+                    consumer_decryptor_address: None,
+                    data_key: Some(data_key.wrapped),
+                });
+            }
+        }
+
         let secure_channel_entry = self
             .get_or_create_secure_channel_for(context, topic_name, partition_id)
             .await?;
@@ -488,25 +634,13 @@ impl<F: ForwarderCreator> KafkaSecureChannelController for KafkaSecureChannelCon
         let consumer_decryptor_address = secure_channel_entry.their_decryptor_address();
 
         trace!("encrypting content with {consumer_decryptor_address}");
-        let encryption_response: EncryptionResponse = context
-            .send_and_receive(
-                route![secure_channel_entry.encryptor_api_address().clone()],
-                EncryptionRequest(content),
-            )
-            .await?;
-
-        let encrypted_content = match encryption_response {
-            EncryptionResponse::Ok(p) => p,
-            EncryptionResponse::Err(cause) => {
-                warn!("cannot encrypt kafka message");
-                return Err(cause);
-            }
-        };
+        let encrypted_content = Self::encrypt_with(context, &secure_channel_entry, content).await?;
 
         trace!("encrypted content with {consumer_decryptor_address}");
         Ok(KafkaEncryptedContent {
             content: encrypted_content,
-            consumer_decryptor_address,
+            consumer_decryptor_address: Some(consumer_decryptor_address),
+            data_key: None,
         })
     }
 
@@ -538,6 +672,75 @@ impl<F: ForwarderCreator> KafkaSecureChannelController for KafkaSecureChannelCon
         Ok(decrypted_content)
     }
 
+    async fn decrypt_broadcast_content(
+        &self,
+        context: &mut Context,
+        data_key: &WrappedDataKey,
+        encrypted_content: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let vault = self
+            .inner
+            .lock()
+            .await
+            .secure_channels
+            .vault()
+            .secure_channel_vault;
+
+        // the data key is only unwrapped the first time it is received
+        let known_secret = self
+            .inner
+            .lock()
+            .await
+            .unwrapped_data_keys
+            .get(&data_key.key_id);
+
+        let secret = match known_secret {
+            Some(secret) => secret,
+            None => {
+                // find the data key wrapped with one of our secure channels
+                let mut unwrapped_key = None;
+                for consumer in data_key.consumers.iter() {
+                    if self
+                        .get_secure_channel_for(&consumer.consumer_decryptor_address)
+                        .await
+                        .is_ok()
+                    {
+                        unwrapped_key = Some(
+                            self.decrypt_content_for(
+                                context,
+                                &consumer.consumer_decryptor_address,
+                                consumer.wrapped_key.clone(),
+                            )
+                            .await?,
+                        );
+                        break;
+                    }
+                }
+                let unwrapped_key = unwrapped_key.ok_or_else(|| {
+                    Error::new(
+                        Origin::Channel,
+                        Kind::NotFound,
+                        "the kafka data key was not wrapped for this consumer",
+                    )
+                })?;
+
+                let secret = import_data_key(&vault, unwrapped_key).await?;
+                let removed = self
+                    .inner
+                    .lock()
+                    .await
+                    .unwrapped_data_keys
+                    .insert(data_key.key_id.clone(), secret.clone());
+                if let Some(removed) = removed {
+                    vault.delete_secret(removed).await?;
+                }
+                secret
+            }
+        };
+
+        decrypt_record(&vault, &secret, &data_key.key_id, encrypted_content).await
+    }
+
     async fn start_forwarders_for(
         &self,
         context: &mut Context,
@@ -136,6 +136,7 @@ pub mod error;
 pub mod hop;
 pub mod identity;
 pub mod kafka;
+pub mod metrics;
 pub mod minicbor_url;
 pub mod nodes;
 pub mod okta;
@@ -168,6 +169,7 @@ impl DefaultAddress {
     pub const SECURE_CHANNEL_LISTENER: &'static str = "api";
     pub const DIRECT_AUTHENTICATOR: &'static str = "direct_authenticator";
     pub const CREDENTIAL_ISSUER: &'static str = "credential_issuer";
+    pub const REVOCATION_LIST: &'static str = "revocation_list";
     pub const ENROLLMENT_TOKEN_ISSUER: &'static str = "enrollment_token_issuer";
     pub const ENROLLMENT_TOKEN_ACCEPTOR: &'static str = "enrollment_token_acceptor";
     pub const OKTA_IDENTITY_PROVIDER: &'static str = "okta";
@@ -189,6 +191,7 @@ impl DefaultAddress {
                 | Self::SECURE_CHANNEL_LISTENER
                 | Self::DIRECT_AUTHENTICATOR
                 | Self::CREDENTIAL_ISSUER
+                | Self::REVOCATION_LIST
                 | Self::ENROLLMENT_TOKEN_ISSUER
                 | Self::ENROLLMENT_TOKEN_ACCEPTOR
                 | Self::OKTA_IDENTITY_PROVIDER
@@ -211,6 +214,7 @@ impl DefaultAddress {
             Self::SECURE_CHANNEL_LISTENER,
             Self::DIRECT_AUTHENTICATOR,
             Self::CREDENTIAL_ISSUER,
+            Self::REVOCATION_LIST,
             Self::ENROLLMENT_TOKEN_ISSUER,
             Self::ENROLLMENT_TOKEN_ACCEPTOR,
             Self::OKTA_IDENTITY_PROVIDER,
@@ -229,6 +233,7 @@ pub mod actions {
     use ockam_abac::Action;
 
     pub const HANDLE_MESSAGE: Action = Action::assert_inline("handle_message");
+    pub const CLAIM: Action = Action::assert_inline("claim");
 }
 
 pub mod resources {
@@ -236,6 +241,9 @@ pub mod resources {
 
     pub const INLET: Resource = Resource::assert_inline("tcp-inlet");
     pub const OUTLET: Resource = Resource::assert_inline("tcp-outlet");
+    pub const UDP_INLET: Resource = Resource::assert_inline("udp-inlet");
+    pub const UDP_OUTLET: Resource = Resource::assert_inline("udp-outlet");
+    pub const RELAY: Resource = Resource::assert_inline("relay");
 }
 
 use core::fmt;
@@ -317,6 +325,7 @@ mod test {
             DefaultAddress::DIRECT_AUTHENTICATOR
         ));
         assert!(DefaultAddress::is_valid(DefaultAddress::CREDENTIAL_ISSUER));
+        assert!(DefaultAddress::is_valid(DefaultAddress::REVOCATION_LIST));
         assert!(DefaultAddress::is_valid(
             DefaultAddress::ENROLLMENT_TOKEN_ISSUER
         ));
@@ -0,0 +1,116 @@
+//! Local HTTP endpoint exposing the metrics of a node in the OpenMetrics text format
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+
+use tiny_http::{Header, Method, Response, Server};
+
+use ockam_node::metrics::{MetricsRegistry, OPEN_METRICS_CONTENT_TYPE};
+
+use crate::error::ApiError;
+
+/// Path of the metrics endpoint
+pub const METRICS_PATH: &str = "/metrics";
+
+/// HTTP server answering `GET /metrics` requests with the content of a [`MetricsRegistry`]
+///
+/// The server runs on its own thread until [`MetricsServer::stop`] is called
+/// or the server is dropped.
+pub struct MetricsServer {
+    server: Arc<Server>,
+    address: SocketAddr,
+}
+
+impl MetricsServer {
+    /// Start serving the metrics of `registry` on the given socket address
+    pub fn start(address: &str, registry: Arc<MetricsRegistry>) -> Result<Self, ApiError> {
+        let server = Arc::new(Server::http(address).map_err(|e| {
+            ApiError::message(format!(
+                "failed to start the metrics server on {address}: {e}"
+            ))
+        })?);
+        let address = server.server_addr().to_ip().ok_or_else(|| {
+            ApiError::message(format!("the metrics server is not listening on {address}"))
+        })?;
+        info!("serving the node metrics at http://{address}{METRICS_PATH}");
+
+        let server_clone = server.clone();
+        thread::Builder::new()
+            .name("metrics-server".to_string())
+            .spawn(move || Self::serve(server_clone, registry))
+            .map_err(ApiError::Io)?;
+
+        Ok(Self { server, address })
+    }
+
+    /// Socket address the server is listening on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Stop serving requests
+    pub fn stop(&self) {
+        self.server.unblock()
+    }
+
+    fn serve(server: Arc<Server>, registry: Arc<MetricsRegistry>) {
+        for request in server.incoming_requests() {
+            let path = request.url().split('?').next().unwrap_or_default();
+            let result = if request.method() == &Method::Get && path == METRICS_PATH {
+                let content_type =
+                    Header::from_bytes(&b"Content-Type"[..], OPEN_METRICS_CONTENT_TYPE.as_bytes())
+                        .expect("the metrics content type is a valid header");
+                request.respond(
+                    Response::from_string(registry.render_open_metrics()).with_header(content_type),
+                )
+            } else {
+                request.respond(Response::empty(404))
+            };
+            if let Err(e) = result {
+                debug!("failed to answer a metrics request: {e}");
+            }
+        }
+        debug!("the metrics server is stopped");
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::Address;
+    use ockam_node::metrics::MetricsSink;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_serve_metrics() {
+        let registry = MetricsRegistry::create();
+        registry.resolution_failed(&Address::from_string("unknown"));
+        let server = MetricsServer::start("127.0.0.1:0", registry).unwrap();
+
+        let response = get(server.address(), METRICS_PATH);
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("ockam_router_resolution_failures_total 1\n"));
+
+        let response = get(server.address(), "/other");
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+}
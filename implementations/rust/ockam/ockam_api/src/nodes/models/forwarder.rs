@@ -28,6 +28,10 @@ pub struct CreateForwarder {
     /// Only set for non-project addresses as for projects the project's
     /// authorised identity will be used.
     #[n(4)] authorized: Option<Identifier>,
+    /// Register a replica of the relay name, which can be registered by several nodes.
+    #[n(5)] replica: Option<bool>,
+    /// Ask the relay to store the messages sent while this node is offline.
+    #[n(6)] store_and_forward: Option<bool>,
 }
 
 impl CreateForwarder {
@@ -39,6 +43,8 @@ impl CreateForwarder {
             alias,
             at_rust_node: false,
             authorized: None,
+            replica: None,
+            store_and_forward: None,
         }
     }
 
@@ -55,9 +61,21 @@ impl CreateForwarder {
             alias,
             at_rust_node,
             authorized: auth,
+            replica: None,
+            store_and_forward: None,
         }
     }
 
+    pub fn with_replica(mut self, replica: bool) -> Self {
+        self.replica = Some(replica);
+        self
+    }
+
+    pub fn with_store_and_forward(mut self, store_and_forward: bool) -> Self {
+        self.store_and_forward = Some(store_and_forward);
+        self
+    }
+
     pub fn address(&self) -> &MultiAddr {
         &self.address
     }
@@ -73,6 +91,14 @@ impl CreateForwarder {
     pub fn authorized(&self) -> Option<Identifier> {
         self.authorized.clone()
     }
+
+    pub fn replica(&self) -> bool {
+        self.replica.unwrap_or(false)
+    }
+
+    pub fn store_and_forward(&self) -> bool {
+        self.store_and_forward.unwrap_or(false)
+    }
 }
 
 /// Response body when creating a forwarder
@@ -1,4 +1,5 @@
 use minicbor::{Decode, Encode};
+use ockam::identity::Identifier;
 use ockam_abac::{Action, Expr};
 
 #[cfg(feature = "tag")]
@@ -79,3 +80,52 @@ impl Expression {
         &self.expr
     }
 }
+
+/// Request to check the policy of a resource and action against an identity
+#[derive(Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CheckPolicy {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<4108713>,
+    #[n(1)] identifier: Identifier,
+}
+
+impl CheckPolicy {
+    pub fn new(identifier: Identifier) -> Self {
+        CheckPolicy {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            identifier,
+        }
+    }
+
+    pub fn identifier(&self) -> &Identifier {
+        &self.identifier
+    }
+}
+
+/// Request to restore the policies of a bundle applied before.
+/// Without a version, the bundle preceding the current one is restored
+#[derive(Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RollbackPolicyBundle {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<6094327>,
+    #[n(1)] version: Option<u64>,
+}
+
+impl RollbackPolicyBundle {
+    pub fn new(version: Option<u64>) -> Self {
+        RollbackPolicyBundle {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            version,
+        }
+    }
+
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+}
@@ -11,6 +11,10 @@ use ockam_core::compat::borrow::Cow;
 use ockam_core::TypeTag;
 use ockam_core::{Address, CowStr, Route};
 use ockam_multiaddr::MultiAddr;
+use ockam_transport_tcp::{
+    TcpInletTls, TcpOutletLoadBalancing, TcpOutletTargetStatus, TcpOutletTls, TcpPortalLimits,
+    TcpPortalLimitsStatus,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::error::ApiError;
@@ -43,6 +47,10 @@ pub struct CreateInlet<'a> {
     #[n(6)] suffix_route: Route,
     /// The maximum duration to wait for an outlet to be available
     #[n(7)] wait_for_outlet_duration: Option<Duration>,
+    /// Accept the connections of the inlet clients with TLS
+    #[n(8)] tls: Option<InletTls>,
+    /// Limits enforced on the connections of the inlet clients
+    #[n(9)] limits: Option<PortalLimits>,
 }
 
 impl<'a> CreateInlet<'a> {
@@ -62,6 +70,8 @@ impl<'a> CreateInlet<'a> {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration: None,
+            tls: None,
+            limits: None,
         }
     }
 
@@ -82,6 +92,8 @@ impl<'a> CreateInlet<'a> {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration: None,
+            tls: None,
+            limits: None,
         }
     }
 
@@ -93,6 +105,14 @@ impl<'a> CreateInlet<'a> {
         self.wait_for_outlet_duration = Some(Duration::from_millis(ms))
     }
 
+    pub fn set_tls(&mut self, tls: InletTls) {
+        self.tls = Some(tls)
+    }
+
+    pub fn set_limits(&mut self, limits: PortalLimits) {
+        self.limits = Some(limits)
+    }
+
     pub fn listen_addr(&self) -> String {
         self.listen_addr.clone()
     }
@@ -120,6 +140,167 @@ impl<'a> CreateInlet<'a> {
     pub fn wait_for_outlet_duration(&self) -> Option<Duration> {
         self.wait_for_outlet_duration
     }
+
+    pub fn tls(&self) -> Option<&InletTls> {
+        self.tls.as_ref()
+    }
+
+    pub fn limits(&self) -> Option<&PortalLimits> {
+        self.limits.as_ref()
+    }
+}
+
+/// Limits enforced on the connections of an inlet or of an outlet
+#[derive(Clone, Debug, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PortalLimits {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<2905547>,
+    /// Maximum number of connections open at the same time
+    #[n(1)] pub max_connections: Option<u64>,
+    /// Maximum number of bytes per second transferred by each connection, in each direction
+    #[n(2)] pub connection_bytes_per_second: Option<u64>,
+    /// Maximum number of bytes per second transferred by all the connections, in each direction
+    #[n(3)] pub aggregate_bytes_per_second: Option<u64>,
+    /// Duration after which a connection without any data transferred is closed
+    #[n(4)] pub idle_timeout: Option<Duration>,
+}
+
+impl PortalLimits {
+    pub fn new(
+        max_connections: Option<u64>,
+        connection_bytes_per_second: Option<u64>,
+        aggregate_bytes_per_second: Option<u64>,
+        idle_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            max_connections,
+            connection_bytes_per_second,
+            aggregate_bytes_per_second,
+            idle_timeout,
+        }
+    }
+
+    /// Return true if no limit is set
+    pub fn is_empty(&self) -> bool {
+        self.max_connections.is_none()
+            && self.connection_bytes_per_second.is_none()
+            && self.aggregate_bytes_per_second.is_none()
+            && self.idle_timeout.is_none()
+    }
+}
+
+impl From<&PortalLimits> for TcpPortalLimits {
+    fn from(limits: &PortalLimits) -> Self {
+        let mut tcp_limits = TcpPortalLimits::new();
+        if let Some(max_connections) = limits.max_connections {
+            tcp_limits = tcp_limits.with_max_connections(max_connections as usize);
+        }
+        if let Some(bytes_per_second) = limits.connection_bytes_per_second {
+            tcp_limits = tcp_limits.with_connection_bytes_per_second(bytes_per_second);
+        }
+        if let Some(bytes_per_second) = limits.aggregate_bytes_per_second {
+            tcp_limits = tcp_limits.with_aggregate_bytes_per_second(bytes_per_second);
+        }
+        if let Some(idle_timeout) = limits.idle_timeout {
+            tcp_limits = tcp_limits.with_idle_timeout(idle_timeout);
+        }
+        tcp_limits
+    }
+}
+
+/// TLS configuration of an inlet terminating TLS, with files stored on the node
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct InletTls {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<4478372>,
+    /// Path of the PEM file containing the certificate chain served to the clients
+    #[n(1)] pub certificate_chain_path: String,
+    /// Path of the PEM file containing the private key of the certificate
+    #[n(2)] pub private_key_path: String,
+}
+
+impl InletTls {
+    pub fn new(certificate_chain_path: String, private_key_path: String) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            certificate_chain_path,
+            private_key_path,
+        }
+    }
+
+    pub fn to_tcp_inlet_tls(&self) -> ockam_core::Result<TcpInletTls> {
+        TcpInletTls::from_pem_files(&self.certificate_chain_path, &self.private_key_path)
+    }
+}
+
+/// TLS configuration of an outlet originating TLS to its targets, with files stored on the node
+#[derive(Clone, Debug, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct OutletTls {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<9137725>,
+    /// Server name sent with SNI and verified in the certificate of the targets.
+    /// The host of each target is used by default
+    #[n(1)] pub server_name: Option<String>,
+    /// Path of the PEM file containing the CA certificates used to verify the targets.
+    /// The native root certificates are used by default
+    #[n(2)] pub ca_certificates_path: Option<String>,
+    /// Path of the PEM file containing the client certificate chain presented to the targets
+    #[n(3)] pub client_certificate_chain_path: Option<String>,
+    /// Path of the PEM file containing the private key of the client certificate
+    #[n(4)] pub client_private_key_path: Option<String>,
+}
+
+impl OutletTls {
+    pub fn new(
+        server_name: Option<String>,
+        ca_certificates_path: Option<String>,
+        client_certificate_chain_path: Option<String>,
+        client_private_key_path: Option<String>,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            server_name,
+            ca_certificates_path,
+            client_certificate_chain_path,
+            client_private_key_path,
+        }
+    }
+
+    pub fn to_tcp_outlet_tls(&self) -> ockam_core::Result<TcpOutletTls> {
+        let tls = TcpOutletTls::new()?;
+        let tls = match &self.ca_certificates_path {
+            Some(path) => tls.with_ca_certificates_file(path)?,
+            None => tls,
+        };
+        let tls = match (
+            &self.client_certificate_chain_path,
+            &self.client_private_key_path,
+        ) {
+            (Some(certificate_chain_path), Some(private_key_path)) => {
+                tls.with_client_certificate_files(certificate_chain_path, private_key_path)?
+            }
+            (None, None) => tls,
+            _ => {
+                return Err(ApiError::core(
+                    "a client certificate requires both a certificate chain and a private key",
+                ))
+            }
+        };
+        match &self.server_name {
+            Some(server_name) => tls.with_server_name(server_name),
+            None => Ok(tls),
+        }
+    }
 }
 
 /// Request body to create an outlet
@@ -138,6 +319,15 @@ pub struct CreateOutlet {
     /// Allow the outlet to be reachable from the default secure channel, useful when we want to
     /// tighten the flow control
     #[n(4)] pub reachable_from_default_secure_channel: bool,
+    /// Targets the portal should connect to, as socket addresses or `host:port` names.
+    /// When set, `socket_addr` is only used for display purposes
+    #[n(5)] pub targets: Option<Vec<String>>,
+    /// How a target is selected for each connection when there are several targets
+    #[n(6)] pub load_balancing: Option<OutletLoadBalancing>,
+    /// Connect to the targets with TLS
+    #[n(7)] pub tls: Option<OutletTls>,
+    /// Limits enforced on the connections to the targets
+    #[n(8)] pub limits: Option<PortalLimits>,
 }
 
 impl CreateOutlet {
@@ -154,6 +344,132 @@ impl CreateOutlet {
             worker_addr,
             alias: alias.into(),
             reachable_from_default_secure_channel,
+            targets: None,
+            load_balancing: None,
+            tls: None,
+            limits: None,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: PortalLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    pub fn with_tls(mut self, tls: OutletTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn with_targets(
+        mut self,
+        targets: Vec<String>,
+        load_balancing: Option<OutletLoadBalancing>,
+    ) -> Self {
+        self.targets = Some(targets);
+        self.load_balancing = load_balancing;
+        self
+    }
+}
+
+/// Strategy used by an outlet to select the target of each new connection
+#[derive(Copy, Clone, Debug, Default, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum OutletLoadBalancing {
+    /// Select the targets one after the other
+    #[default]
+    #[n(0)] RoundRobin,
+    /// Select the target with the smallest number of active connections
+    #[n(1)] LeastConnections,
+}
+
+impl From<OutletLoadBalancing> for TcpOutletLoadBalancing {
+    fn from(value: OutletLoadBalancing) -> Self {
+        match value {
+            OutletLoadBalancing::RoundRobin => Self::RoundRobin,
+            OutletLoadBalancing::LeastConnections => Self::LeastConnections,
+        }
+    }
+}
+
+/// Request body to create a UDP inlet
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateUdpInlet {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<2940613>,
+    /// The address the portal should listen at.
+    #[n(1)] pub listen_addr: String,
+    /// The address of the outlet, either a direct route or a route via a relay
+    #[n(2)] pub outlet_addr: MultiAddr,
+    /// A human-friendly alias for this portal endpoint
+    #[n(3)] pub alias: Option<String>,
+    /// An authorised identity for secure channels.
+    /// Only set for non-project addresses as for projects the project's
+    /// authorised identity will be used.
+    #[n(4)] pub authorized: Option<Identifier>,
+    /// The duration after which the flow of a client is closed if no datagram is exchanged
+    #[n(5)] pub idle_timeout: Option<Duration>,
+}
+
+impl CreateUdpInlet {
+    pub fn new(
+        listen_addr: String,
+        outlet_addr: MultiAddr,
+        alias: Option<String>,
+        authorized: Option<Identifier>,
+        idle_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            listen_addr,
+            outlet_addr,
+            alias,
+            authorized,
+            idle_timeout,
+        }
+    }
+}
+
+/// Request body to create a UDP outlet
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateUdpOutlet {
+    #[cfg(feature = "tag")]
+    #[n(0)] tag: TypeTag<7305218>,
+    /// The address the portal should send the datagrams to
+    #[n(1)] pub socket_addr: SocketAddr,
+    /// The address of the outlet worker
+    #[n(2)] pub worker_addr: Address,
+    /// A human-friendly alias for this portal endpoint
+    #[n(3)] pub alias: Option<String>,
+    /// Allow the outlet to be reachable from the default secure channel, useful when we want to
+    /// tighten the flow control
+    #[n(4)] pub reachable_from_default_secure_channel: bool,
+    /// The duration after which the socket opened for a flow is closed if no datagram is exchanged
+    #[n(5)] pub idle_timeout: Option<Duration>,
+}
+
+impl CreateUdpOutlet {
+    pub fn new(
+        socket_addr: SocketAddr,
+        worker_addr: Address,
+        alias: impl Into<Option<String>>,
+        reachable_from_default_secure_channel: bool,
+        idle_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            socket_addr,
+            worker_addr,
+            alias: alias.into(),
+            reachable_from_default_secure_channel,
+            idle_timeout,
         }
     }
 }
@@ -172,6 +488,8 @@ pub struct InletStatus {
     /// An optional status payload
     #[n(4)] pub payload: Option<String>,
     #[n(5)] pub outlet_route: String,
+    /// The limits enforced on the connections, and their current state
+    #[n(6)] pub limits: Option<PortalLimitsStatus>,
 }
 
 impl InletStatus {
@@ -184,6 +502,7 @@ impl InletStatus {
             alias: "".into(),
             payload: Some(reason.into()),
             outlet_route: "".into(),
+            limits: None,
         }
     }
 
@@ -202,8 +521,14 @@ impl InletStatus {
             alias: alias.into(),
             payload: payload.into(),
             outlet_route: outlet_route.into(),
+            limits: None,
         }
     }
+
+    pub fn with_limits(mut self, limits: Option<PortalLimitsStatus>) -> Self {
+        self.limits = limits;
+        self
+    }
 }
 
 /// Response body when interacting with a portal endpoint
@@ -219,6 +544,10 @@ pub struct OutletStatus {
     #[n(3)] pub alias: String,
     /// An optional status payload
     #[n(4)] pub payload: Option<String>,
+    /// The status of each target of a TCP outlet
+    #[n(5)] pub targets: Option<Vec<OutletTargetStatus>>,
+    /// The limits enforced on the connections, and their current state
+    #[n(6)] pub limits: Option<PortalLimitsStatus>,
 }
 
 impl OutletStatus {
@@ -230,6 +559,8 @@ impl OutletStatus {
             worker_addr: "".into(),
             alias: "".into(),
             payload: Some(reason.into()),
+            targets: None,
+            limits: None,
         }
     }
 
@@ -246,9 +577,21 @@ impl OutletStatus {
             worker_addr,
             alias: alias.into(),
             payload: payload.into(),
+            targets: None,
+            limits: None,
         }
     }
 
+    pub fn with_targets(mut self, targets: Option<Vec<OutletTargetStatus>>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Option<PortalLimitsStatus>) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn worker_address(&self) -> Result<MultiAddr, ockam_core::Error> {
         route_to_multiaddr(&route![self.worker_addr.to_string()])
             .ok_or_else(|| ApiError::core("Invalid Worker Address"))
@@ -263,6 +606,77 @@ impl OutletStatus {
     }
 }
 
+/// Status of one of the targets of a TCP outlet
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct OutletTargetStatus {
+    #[cfg(feature = "tag")]
+    #[serde(skip)]
+    #[n(0)] tag: TypeTag<6170398>,
+    /// The target, as given when the outlet was created
+    #[n(1)] pub target: String,
+    /// The last resolved address of the target
+    #[n(2)] pub address: Option<SocketAddr>,
+    /// False if the last connection to the target failed recently
+    #[n(3)] pub healthy: bool,
+    #[n(4)] pub active_connections: u64,
+    #[n(5)] pub failed_connections: u32,
+}
+
+impl From<TcpOutletTargetStatus> for OutletTargetStatus {
+    fn from(status: TcpOutletTargetStatus) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            target: status.target,
+            address: status.address,
+            healthy: status.healthy,
+            active_connections: status.active_connections as u64,
+            failed_connections: status.failed_connections,
+        }
+    }
+}
+
+/// Limits enforced on the connections of an inlet or of an outlet, and their current state
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PortalLimitsStatus {
+    #[cfg(feature = "tag")]
+    #[serde(skip)]
+    #[n(0)] tag: TypeTag<7386240>,
+    #[n(1)] pub max_connections: Option<u64>,
+    #[n(2)] pub connection_bytes_per_second: Option<u64>,
+    #[n(3)] pub aggregate_bytes_per_second: Option<u64>,
+    #[n(4)] pub idle_timeout: Option<Duration>,
+    /// Number of connections currently open
+    #[n(5)] pub active_connections: u64,
+    /// Number of connections closed because too many connections were open
+    #[n(6)] pub rejected_connections: u64,
+    /// Number of connections closed because they were idle
+    #[n(7)] pub idle_disconnections: u64,
+    /// Number of times a connection had to wait before transferring data
+    #[n(8)] pub throttled_transfers: u64,
+}
+
+impl From<TcpPortalLimitsStatus> for PortalLimitsStatus {
+    fn from(status: TcpPortalLimitsStatus) -> Self {
+        Self {
+            #[cfg(feature = "tag")]
+            tag: TypeTag,
+            max_connections: status.limits.max_connections().map(|n| n as u64),
+            connection_bytes_per_second: status.limits.connection_bytes_per_second(),
+            aggregate_bytes_per_second: status.limits.aggregate_bytes_per_second(),
+            idle_timeout: status.limits.idle_timeout(),
+            active_connections: status.active_connections as u64,
+            rejected_connections: status.rejected_connections,
+            idle_disconnections: status.idle_disconnections,
+            throttled_transfers: status.throttled_transfers,
+        }
+    }
+}
+
 /// Response body when returning a list of Inlets
 #[derive(Debug, Clone, Decode, Encode)]
 #[rustfmt::skip]
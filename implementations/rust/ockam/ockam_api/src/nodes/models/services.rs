@@ -119,6 +119,7 @@ pub struct StartKafkaProducerRequest {
     #[n(1)] pub bootstrap_server_addr: SocketAddr,
     #[n(2)] brokers_port_range: (u16, u16),
     #[n(3)] project_route: String,
+    #[n(4)] broadcast_consumer_routes: Option<Vec<String>>,
 }
 
 impl StartKafkaProducerRequest {
@@ -131,9 +132,18 @@ impl StartKafkaProducerRequest {
             bootstrap_server_addr,
             brokers_port_range: brokers_port_range.into(),
             project_route: project_route.to_string(),
+            broadcast_consumer_routes: None,
         }
     }
 
+    /// Encrypt each record once, for all the consumers reachable with these routes
+    pub fn with_broadcast_consumer_routes(mut self, routes: Vec<MultiAddr>) -> Self {
+        if !routes.is_empty() {
+            self.broadcast_consumer_routes = Some(routes.iter().map(|r| r.to_string()).collect());
+        }
+        self
+    }
+
     pub fn bootstrap_server_addr(&self) -> SocketAddr {
         self.bootstrap_server_addr
     }
@@ -143,6 +153,9 @@ impl StartKafkaProducerRequest {
     pub fn project_route(&self) -> &String {
         &self.project_route
     }
+    pub fn broadcast_consumer_routes(&self) -> Vec<String> {
+        self.broadcast_consumer_routes.clone().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Decode, Encode)]
@@ -1,9 +1,11 @@
+use crate::nodes::models::portal::{OutletTargetStatus, PortalLimitsStatus};
 use crate::nodes::service::Alias;
 use ockam::identity::Identifier;
 use ockam::identity::{SecureChannel, SecureChannelListener};
 use ockam::remote::RemoteForwarderInfo;
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::{Address, Route};
+use ockam_transport_tcp::{TcpOutletTargets, TcpPortalLimiter};
 use std::fmt::Display;
 use std::net::SocketAddr;
 
@@ -149,6 +151,7 @@ pub(crate) struct InletInfo {
     pub(crate) bind_addr: String,
     pub(crate) worker_addr: Address,
     pub(crate) outlet_route: Route,
+    pub(crate) limiter: Option<TcpPortalLimiter>,
 }
 
 impl InletInfo {
@@ -165,14 +168,27 @@ impl InletInfo {
             bind_addr: bind_addr.to_owned(),
             worker_addr,
             outlet_route: outlet_route.to_owned(),
+            limiter: None,
         }
     }
+
+    pub(crate) fn with_limiter(mut self, limiter: Option<TcpPortalLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Return the current status of the limits of a TCP inlet
+    pub(crate) fn limits_status(&self) -> Option<PortalLimitsStatus> {
+        self.limiter.as_ref().map(|l| l.status().into())
+    }
 }
 
 #[derive(Clone)]
 pub struct OutletInfo {
     pub(crate) socket_addr: SocketAddr,
     pub(crate) worker_addr: Address,
+    pub(crate) targets: Option<TcpOutletTargets>,
+    pub(crate) limiter: Option<TcpPortalLimiter>,
 }
 
 impl OutletInfo {
@@ -184,8 +200,32 @@ impl OutletInfo {
         Self {
             socket_addr: *socket_addr,
             worker_addr,
+            targets: None,
+            limiter: None,
         }
     }
+
+    pub(crate) fn with_limiter(mut self, limiter: Option<TcpPortalLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Return the current status of the limits of a TCP outlet
+    pub(crate) fn limits_status(&self) -> Option<PortalLimitsStatus> {
+        self.limiter.as_ref().map(|l| l.status().into())
+    }
+
+    pub(crate) fn with_targets(mut self, targets: TcpOutletTargets) -> Self {
+        self.targets = Some(targets);
+        self
+    }
+
+    /// Return the current status of the targets of a TCP outlet
+    pub(crate) fn targets_status(&self) -> Option<Vec<OutletTargetStatus>> {
+        self.targets
+            .as_ref()
+            .map(|targets| targets.status().into_iter().map(|s| s.into()).collect())
+    }
 }
 
 #[derive(Default)]
@@ -203,4 +243,6 @@ pub(crate) struct Registry {
     pub(crate) forwarders: BTreeMap<String, RemoteForwarderInfo>,
     pub(crate) inlets: BTreeMap<Alias, InletInfo>,
     pub(crate) outlets: BTreeMap<Alias, OutletInfo>,
+    pub(crate) udp_inlets: BTreeMap<Alias, InletInfo>,
+    pub(crate) udp_outlets: BTreeMap<Alias, OutletInfo>,
 }
@@ -12,21 +12,28 @@ use ockam::identity::Vault;
 use ockam::identity::{
     Credentials, CredentialsServer, Identities, IdentitiesRepository, IdentityAttributesReader,
 };
-use ockam::identity::{CredentialsServerModule, TrustContext};
+use ockam::identity::{
+    CredentialsServerModule, RevocationListRefresher, TrustContext,
+    DEFAULT_REVOCATION_LIST_REFRESH_INTERVAL,
+};
 use ockam::identity::{Identifier, SecureChannels};
 use ockam::{
-    Address, Context, ForwardingService, ForwardingServiceOptions, Result, Routed, TcpTransport,
-    Worker,
+    Address, Context, ForwardingService, ForwardingServiceOptions, RelayMailboxOptions, Result,
+    Routed, TcpTransport, Worker,
 };
 use ockam_abac::expr::{eq, ident, str};
-use ockam_abac::{Action, Env, Expr, PolicyAccessControl, PolicyStorage, Resource};
+use ockam_abac::{
+    Action, AuditLog, Env, Expr, PolicyAccessControl, PolicyBundles, PolicyStorage, Resource,
+};
 use ockam_core::api::{Error, Method, Request, Response, ResponseBuilder, Status};
 use ockam_core::compat::{string::String, sync::Arc};
+use ockam_core::env::get_env;
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::IncomingAccessControl;
 use ockam_core::{AllowAll, AsyncTryClone};
 use ockam_multiaddr::MultiAddr;
 use ockam_node::compat::asynchronous::RwLock;
+use ockam_transport_udp::UdpTransport;
 
 use crate::bootstrapped_identities_store::BootstrapedIdentityStore;
 use crate::bootstrapped_identities_store::PreTrustedIdentities;
@@ -51,6 +58,8 @@ use crate::RpcProxyService;
 
 use super::registry::Registry;
 
+mod audit_log;
+pub use audit_log::{FileAuditLog, OCKAM_POLICY_AUDIT_LOG};
 mod credentials;
 mod flow_controls;
 mod forwarder;
@@ -59,8 +68,10 @@ mod node_identities;
 mod node_services;
 mod policy;
 mod portals;
+mod relay_names;
 mod secure_channel;
 mod transport;
+mod udp_portals;
 
 const TARGET: &str = "ockam_api::nodemanager::service";
 
@@ -89,6 +100,8 @@ pub struct NodeManager {
     node_name: String,
     api_transport_flow_control_id: FlowControlId,
     pub(crate) tcp_transport: TcpTransport,
+    // created when the first UDP portal is created
+    udp_transport: Option<UdpTransport>,
     pub(crate) controller_identity_id: Identifier,
     enable_credential_checks: bool,
     identifier: Identifier,
@@ -97,6 +110,8 @@ pub struct NodeManager {
     pub(crate) registry: Registry,
     medic_handle: MedicHandle,
     policies: Arc<dyn PolicyStorage>,
+    policy_bundles: Arc<PolicyBundles>,
+    audit_log: Option<Arc<dyn AuditLog>>,
 }
 
 impl NodeManager {
@@ -135,6 +150,8 @@ impl NodeManager {
                 .iter()
                 .map(|(alias, info)| {
                     OutletStatus::new(info.socket_addr, info.worker_addr.clone(), alias, None)
+                        .with_targets(info.targets_status())
+                        .with_limits(info.limits_status())
                 })
                 .collect(),
         )
@@ -183,10 +200,7 @@ impl NodeManager {
     ) -> Result<Arc<dyn IncomingAccessControl>> {
         if let Some(tcid) = trust_context_id {
             // Populate environment with known attributes:
-            let mut env = Env::new();
-            env.put("resource.id", str(r.as_str()));
-            env.put("action.id", str(a.as_str()));
-            env.put("resource.trust_context_id", str(tcid));
+            let env = Self::policy_environment(r, a, Some(tcid));
 
             // Check if a policy exists for (resource, action) and if not, then
             // create or use a default entry:
@@ -200,19 +214,51 @@ impl NodeManager {
                 };
                 self.policies.set_policy(r, a, &fallback).await?
             }
-            let policies = self.policies.clone();
-            Ok(Arc::new(PolicyAccessControl::new(
-                policies,
-                self.identities_repository(),
-                r.clone(),
-                a.clone(),
-                env,
-            )))
+            Ok(Arc::new(self.policy_access_control(r, a, env)))
         } else {
             Ok(Arc::new(AllowAll))
         }
     }
 
+    /// Return the environment in which the policy of a resource and action is evaluated
+    fn policy_environment(r: &Resource, a: &Action, trust_context_id: Option<&str>) -> Env {
+        let mut env = Env::new();
+        env.put("resource.id", str(r.as_str()));
+        env.put("action.id", str(a.as_str()));
+        if let Some(tcid) = trust_context_id {
+            env.put("resource.trust_context_id", str(tcid));
+        }
+        env
+    }
+
+    /// Create an access control evaluating the policy of a resource and action,
+    /// which records its decisions in the audit log of the node, if there is one
+    fn policy_access_control(&self, r: &Resource, a: &Action, env: Env) -> PolicyAccessControl {
+        let access_control = PolicyAccessControl::new(
+            self.policies.clone(),
+            self.identities_repository(),
+            r.clone(),
+            a.clone(),
+            env,
+        );
+        match &self.audit_log {
+            Some(audit_log) => access_control.with_audit_log(audit_log.clone()),
+            None => access_control,
+        }
+    }
+
+    /// Create the audit log of the policy decisions when its path is set
+    /// with the `OCKAM_POLICY_AUDIT_LOG` environment variable
+    fn create_audit_log() -> Result<Option<Arc<dyn AuditLog>>> {
+        match get_env::<PathBuf>(OCKAM_POLICY_AUDIT_LOG)? {
+            Some(path) => {
+                info!("recording the policy decisions in {}", path.display());
+                Ok(Some(Arc::new(FileAuditLog::create(&path)?)))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub(crate) fn trust_context(&self) -> Result<&TrustContext> {
         self.trust_context
             .as_ref()
@@ -311,7 +357,7 @@ impl NodeManager {
 
         //TODO: fix this.  Either don't require it to be a bootstrappedidentitystore (and use the
         //trait instead),  or pass it from the general_options always.
-        let vault: Vault = node_state.config().vault().await?;
+        let vault: Vault = node_state.config().vault(&cli_state.vaults).await?;
         let identities_repository: Arc<dyn IdentitiesRepository> =
             Arc::new(match general_options.pre_trusted_identities {
                 None => BootstrapedIdentityStore::new(
@@ -327,7 +373,12 @@ impl NodeManager {
             .with_identities_repository(identities_repository.clone())
             .build();
 
-        let policies: Arc<dyn PolicyStorage> = Arc::new(node_state.policies_storage().await?);
+        let policies: Arc<dyn PolicyStorage> = node_state.policies_storage().await?;
+        let policy_bundles = Arc::new(PolicyBundles::new(
+            Arc::new(node_state.policy_bundles_storage().await?),
+            policies.clone(),
+        ));
+        let audit_log = Self::create_audit_log()?;
 
         debug!("start the Medic");
         let medic_handle = MedicHandle::start_medic(ctx).await?;
@@ -337,6 +388,7 @@ impl NodeManager {
             node_name: general_options.node_name,
             api_transport_flow_control_id: transport_options.api_transport_flow_control_id,
             tcp_transport: transport_options.tcp_transport,
+            udp_transport: None,
             controller_identity_id: Self::load_controller_identifier()?,
             enable_credential_checks: trust_options.trust_context_config.is_some()
                 && trust_options
@@ -351,11 +403,14 @@ impl NodeManager {
             registry: Default::default(),
             medic_handle,
             policies,
+            policy_bundles,
+            audit_log,
         };
 
         if let Some(tc) = trust_options.trust_context_config {
             debug!("configuring trust context");
             s.configure_trust_context(&tc).await?;
+            s.start_revocation_list_refresher(ctx, &tc).await?;
         }
 
         s.initialize_services(ctx, general_options.start_default_services)
@@ -379,6 +434,38 @@ impl NodeManager {
         Ok(())
     }
 
+    /// Periodically retrieve the revocation list of the trust context authority, in order to
+    /// reject the credentials it revoked
+    async fn start_revocation_list_refresher(
+        &self,
+        ctx: &Context,
+        tc: &TrustContextConfig,
+    ) -> Result<()> {
+        // the node can run without refreshing revocation lists, so an issuer which can't be
+        // resolved is logged and skipped instead of failing the node startup
+        let authority = match tc.revocation_list_issuer().await {
+            Ok(authority) => authority,
+            Err(err) => {
+                warn!("NodeManager::start_revocation_list_refresher: the revocation list issuer can't be resolved, the refresher is not started: {err}");
+                return Ok(());
+            }
+        };
+        if let Some(authority) = authority {
+            RevocationListRefresher::start(
+                ctx,
+                self.secure_channels.clone(),
+                &self.identifier,
+                authority,
+                DEFAULT_REVOCATION_LIST_REFRESH_INTERVAL,
+            )
+            .await?;
+            info!(
+                "NodeManager::start_revocation_list_refresher: revocation list refresher started"
+            );
+        }
+        Ok(())
+    }
+
     async fn initialize_default_services(
         &mut self,
         ctx: &Context,
@@ -390,12 +477,26 @@ impl NodeManager {
         self.start_uppercase_service_impl(ctx, DefaultAddress::UPPERCASE_SERVICE.into())
             .await?;
 
+        // Messages are only stored for the relays created with store-and-forward,
+        // and are kept when the node restarts
+        let relay_mailboxes = self
+            .cli_state
+            .nodes
+            .get(&self.node_name)?
+            .relay_mailboxes_storage()
+            .await?;
+        let mut forwarding_service_options = ForwardingServiceOptions::new()
+            .service_as_consumer(api_flow_control_id)
+            .forwarder_as_consumer(api_flow_control_id)
+            .with_relay_mailboxes(RelayMailboxOptions::new(relay_mailboxes));
+        if let Some(authorization) = self.relay_name_authorization().await? {
+            forwarding_service_options =
+                forwarding_service_options.with_relay_name_authorization(authorization);
+        }
         ForwardingService::create(
             ctx,
             DefaultAddress::FORWARDING_SERVICE,
-            ForwardingServiceOptions::new()
-                .service_as_consumer(api_flow_control_id)
-                .forwarder_as_consumer(api_flow_control_id),
+            forwarding_service_options,
         )
         .await?;
 
@@ -709,6 +810,28 @@ impl NodeManagerWorker {
             }
             (Delete, ["node", "portal"]) => todo!(),
 
+            // ==*== UDP Inlets & Outlets ==*==
+            (Get, ["node", "udp", "inlet"]) => self.get_udp_inlets(req).await.to_vec()?,
+            (Get, ["node", "udp", "inlet", alias]) => {
+                encode_request_result(self.show_udp_inlet(req, alias).await)?
+            }
+            (Get, ["node", "udp", "outlet"]) => self.get_udp_outlets(req).await.to_vec()?,
+            (Get, ["node", "udp", "outlet", alias]) => {
+                encode_request_result(self.show_udp_outlet(req, alias).await)?
+            }
+            (Post, ["node", "udp", "inlet"]) => {
+                encode_request_result(self.create_udp_inlet(ctx, req, dec.decode()?).await)?
+            }
+            (Post, ["node", "udp", "outlet"]) => {
+                encode_request_result(self.create_udp_outlet(ctx, req, dec.decode()?).await)?
+            }
+            (Delete, ["node", "udp", "inlet", alias]) => {
+                encode_request_result(self.delete_udp_inlet(req, alias).await)?
+            }
+            (Delete, ["node", "udp", "outlet", alias]) => {
+                encode_request_result(self.delete_udp_outlet(req, alias).await)?
+            }
+
             // ==*== Flow Controls ==*==
             (Post, ["node", "flow_controls", "add_consumer"]) => {
                 encode_request_result(self.add_consumer(ctx, req, dec))?
@@ -748,6 +871,34 @@ impl NodeManagerWorker {
                 .get_policy(req, resource, action)
                 .await?
                 .either(ResponseBuilder::to_vec, ResponseBuilder::to_vec)?,
+            (Post, ["policy", resource, action, "check"]) => self
+                .node_manager
+                .read()
+                .await
+                .check_policy(req, resource, action, dec)
+                .await?
+                .either(ResponseBuilder::to_vec, ResponseBuilder::to_vec)?,
+            (Get, ["policy_bundle"]) => encode_request_result(
+                self.node_manager
+                    .read()
+                    .await
+                    .export_policy_bundle(req)
+                    .await,
+            )?,
+            (Post, ["policy_bundle"]) => encode_request_result(
+                self.node_manager
+                    .read()
+                    .await
+                    .import_policy_bundle(req, dec)
+                    .await,
+            )?,
+            (Post, ["policy_bundle", "rollback"]) => encode_request_result(
+                self.node_manager
+                    .read()
+                    .await
+                    .rollback_policy_bundle(req, dec)
+                    .await,
+            )?,
             (Delete, ["policy", resource, action]) => encode_request_result(
                 self.node_manager
                     .read()
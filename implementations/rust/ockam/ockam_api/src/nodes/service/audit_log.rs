@@ -0,0 +1,112 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+
+use ockam_abac::{AuditLog, Decision};
+use ockam_core::async_trait;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use serde_json::json;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Name of the environment variable setting the path of the file where a node records
+/// the decisions of its policies
+pub const OCKAM_POLICY_AUDIT_LOG: &str = "OCKAM_POLICY_AUDIT_LOG";
+
+/// Number of decisions which can wait to be written before recording a decision blocks
+const AUDIT_LOG_CAPACITY: usize = 1024;
+
+/// Audit log appending each decision as a JSON object on its own line of a file
+///
+/// The lines are written by a background task, so that the access controls
+/// recording the decisions do not wait for the file system
+pub struct FileAuditLog {
+    lines: mpsc::Sender<String>,
+}
+
+impl FileAuditLog {
+    /// Append the decisions to a file, which is created if necessary.
+    /// This must be called from a Tokio runtime
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::new(Origin::Node, Kind::Io, e))?;
+        let (lines, receiver) = mpsc::channel(AUDIT_LOG_CAPACITY);
+        tokio::spawn(Self::write_lines(File::from_std(file), receiver));
+        Ok(Self { lines })
+    }
+
+    /// Write the lines until the audit log is dropped
+    async fn write_lines(mut file: File, mut lines: mpsc::Receiver<String>) {
+        while let Some(line) = lines.recv().await {
+            let result = match file.write_all(line.as_bytes()).await {
+                Ok(()) => file.flush().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("cannot record a policy decision in the audit log: {e}");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AuditLog for FileAuditLog {
+    async fn record(&self, decision: &Decision) -> Result<()> {
+        let line = json!({
+            "timestamp": decision.timestamp.map(|t| t.0),
+            "resource": decision.resource.as_ref().map(|r| r.as_str()),
+            "action": decision.action.as_ref().map(|a| a.as_str()),
+            "subject": decision.subject.as_ref().map(|s| s.to_string()),
+            "policy": decision.policy.as_ref().map(|p| p.to_string()),
+            "allowed": decision.allowed,
+            "reason": decision.reason,
+        });
+        self.lines
+            .send(format!("{line}\n"))
+            .await
+            .map_err(|_| Error::new(Origin::Node, Kind::Internal, "the audit log is closed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_abac::{Action, Resource};
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_file_audit_log() -> Result<()> {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        let audit_log = FileAuditLog::create(&path)?;
+        let decision = Decision::new(false)
+            .with_resource_action(Some(Resource::new("outlet")), Some(Action::new("handle")))
+            .with_reason("no policy found");
+        audit_log.record(&decision).await?;
+        audit_log.record(&Decision::new(true)).await?;
+
+        // the decisions are written in the background
+        let mut contents = String::new();
+        for _ in 0..100 {
+            contents = std::fs::read_to_string(&path).unwrap();
+            if contents.lines().count() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["resource"], "outlet");
+        assert_eq!(lines[0]["allowed"], false);
+        assert_eq!(lines[0]["reason"], "no policy found");
+        assert_eq!(lines[1]["allowed"], true);
+        Ok(())
+    }
+}
@@ -62,14 +62,33 @@ impl NodeManagerWorker {
             connection_instance.add_consumer(ctx, hop);
         }
 
-        let options = RemoteForwarderOptions::new();
+        let mut options = RemoteForwarderOptions::new();
+        if req.store_and_forward() {
+            if !req.at_rust_node() || req.alias().is_none() || req.replica() {
+                return Err(ApiError::core(
+                    "store-and-forward is only supported by named relays at a rust node",
+                ));
+            }
+            options = options.with_store_and_forward();
+        }
 
         let route = local_multiaddr_to_route(&connection_instance.normalized_addr)
             .ok_or_else(|| ApiError::core("invalid address: {addr}"))?;
 
+        if req.replica() && (!req.at_rust_node() || req.alias().is_none()) {
+            return Err(ApiError::core(
+                "relay replicas can only be registered with a name at a rust node",
+            ));
+        }
+
         let forwarder = if req.at_rust_node() {
             if let Some(alias) = req.alias() {
-                RemoteForwarder::create_static_without_heartbeats(ctx, route, alias, options).await
+                if req.replica() {
+                    RemoteForwarder::create_replica(ctx, route, alias, options).await
+                } else {
+                    RemoteForwarder::create_static_without_heartbeats(ctx, route, alias, options)
+                        .await
+                }
             } else {
                 RemoteForwarder::create(ctx, route, options).await
             }
@@ -456,6 +456,7 @@ impl NodeManagerWorker {
                 body_req.bootstrap_server_addr.port(),
                 body_req.brokers_port_range(),
                 outlet_node_multiaddr,
+                vec![],
                 KafkaServiceKind::Consumer,
             )
             .await
@@ -476,6 +477,11 @@ impl NodeManagerWorker {
         let listener_address: Address = body.address().into();
         let body_req = body.request();
         let outlet_node_multiaddr = body_req.project_route().to_string().parse()?;
+        let broadcast_consumers = body_req
+            .broadcast_consumer_routes()
+            .iter()
+            .map(|route| route.parse())
+            .collect::<Result<Vec<MultiAddr>, _>>()?;
 
         if let Err(e) = self
             .start_kafka_service_impl(
@@ -486,6 +492,7 @@ impl NodeManagerWorker {
                 body_req.bootstrap_server_addr.port(),
                 body_req.brokers_port_range(),
                 outlet_node_multiaddr,
+                broadcast_consumers,
                 KafkaServiceKind::Producer,
             )
             .await
@@ -506,6 +513,7 @@ impl NodeManagerWorker {
         server_bootstrap_port: u16,
         brokers_port_range: (u16, u16),
         outlet_node_multiaddr: MultiAddr,
+        broadcast_consumers: Vec<MultiAddr>,
         kind: KafkaServiceKind,
     ) -> Result<(), ResponseBuilder<Error>> {
         debug!(
@@ -539,9 +547,17 @@ impl NodeManagerWorker {
             }
         }
 
+        // when records are broadcast, the consumers are reached directly instead
+        // of using a relay per topic and partition
+        let consumer_node_multiaddr = if broadcast_consumers.is_empty() {
+            ConsumerNodeAddr::Relay(outlet_node_multiaddr.clone())
+        } else {
+            ConsumerNodeAddr::Broadcast(broadcast_consumers)
+        };
+
         let secure_channel_controller = KafkaSecureChannelControllerImpl::new(
             secure_channels,
-            ConsumerNodeAddr::Relay(outlet_node_multiaddr.clone()),
+            consumer_node_multiaddr,
             trust_context_id,
         );
 
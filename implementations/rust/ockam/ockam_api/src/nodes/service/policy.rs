@@ -1,11 +1,13 @@
 use either::Either;
 use minicbor::Decoder;
 
-use ockam_abac::{Action, Resource};
-use ockam_core::api::{Error, Request, Response, ResponseBuilder};
+use ockam_abac::{Action, Explanation, PolicyBundle, Resource, SignedPolicyBundle};
+use ockam_core::api::{bad_request, Error, Request, Response, ResponseBuilder};
 use ockam_core::Result;
 
-use crate::nodes::models::policy::{Expression, Policy, PolicyList};
+use crate::nodes::models::policy::{
+    CheckPolicy, Expression, Policy, PolicyList, RollbackPolicyBundle,
+};
 
 use super::NodeManager;
 
@@ -43,6 +45,32 @@ impl NodeManager {
         }
     }
 
+    /// Evaluate the policy of a resource and action for an identity, without recording
+    /// the decision, and return the explanation of the result
+    pub(super) async fn check_policy(
+        &self,
+        req: &Request,
+        resource: &str,
+        action: &str,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Either<ResponseBuilder<Error>, ResponseBuilder<Explanation>>> {
+        let check: CheckPolicy = dec.decode()?;
+        let r = Resource::new(resource);
+        let a = Action::new(action);
+        let trust_context_id = self.trust_context.as_ref().map(|tc| tc.id());
+        let env = Self::policy_environment(&r, &a, trust_context_id);
+        let access_control = self.policy_access_control(&r, &a, env);
+        if let Some(explanation) = access_control.explain(check.identifier()).await? {
+            Ok(Either::Right(Response::ok(req.id()).body(explanation)))
+        } else {
+            let mut err = Error::new(req.path()).with_message("policy not found");
+            if let Some(m) = req.method() {
+                err.set_method(m)
+            }
+            Ok(Either::Left(Response::not_found(req.id()).body(err)))
+        }
+    }
+
     pub(super) async fn list_policies(
         &self,
         req: &Request,
@@ -65,4 +93,53 @@ impl NodeManager {
         self.policies.del_policy(&r, &a).await?;
         Ok(Response::ok(req.id()))
     }
+
+    /// Return all the policies of the node, with the version of the last bundle applied
+    pub(super) async fn export_policy_bundle(
+        &self,
+        req: &Request,
+    ) -> Result<ResponseBuilder<PolicyBundle>, ResponseBuilder<Error>> {
+        let bundle = self.policy_bundles.current().await?;
+        Ok(Response::ok(req.id()).body(bundle))
+    }
+
+    /// Replace all the policies of the node with the policies of a bundle signed
+    /// by the node identity or by an authority of its trust context
+    pub(super) async fn import_policy_bundle(
+        &self,
+        req: &Request,
+        dec: &mut Decoder<'_>,
+    ) -> Result<ResponseBuilder<()>, ResponseBuilder<Error>> {
+        let signed: SignedPolicyBundle = dec.decode()?;
+        let mut signers = vec![self.identifier()];
+        if let Some(tc) = &self.trust_context {
+            signers.extend(tc.authorities().await?);
+        }
+        // The bundle must be signed for this node, and its version must be greater
+        // than the versions of the bundles applied before
+        let bundle = match signed
+            .verify(&self.identities(), &signers, &self.identifier())
+            .await
+        {
+            Ok((_, bundle)) => bundle,
+            Err(e) => return Err(bad_request(req, &e.to_string())),
+        };
+        if let Err(e) = self.policy_bundles.apply(bundle).await {
+            return Err(bad_request(req, &e.to_string()));
+        }
+        Ok(Response::ok(req.id()))
+    }
+
+    /// Restore the policies of a bundle applied before
+    pub(super) async fn rollback_policy_bundle(
+        &self,
+        req: &Request,
+        dec: &mut Decoder<'_>,
+    ) -> Result<ResponseBuilder<PolicyBundle>, ResponseBuilder<Error>> {
+        let rollback: RollbackPolicyBundle = dec.decode()?;
+        match self.policy_bundles.rollback(rollback.version()).await {
+            Ok(bundle) => Ok(Response::ok(req.id()).body(bundle)),
+            Err(e) => Err(bad_request(req, &e.to_string())),
+        }
+    }
 }
@@ -16,7 +16,9 @@ use ockam_multiaddr::proto::Project;
 use ockam_multiaddr::MultiAddr;
 use ockam_node::compat::asynchronous::RwLock;
 use ockam_node::Context;
-use ockam_transport_tcp::{TcpInletOptions, TcpOutletOptions};
+use ockam_transport_tcp::{
+    TcpInletOptions, TcpInletTls, TcpOutletOptions, TcpPortalLimiter, TcpPortalLimits,
+};
 
 use crate::cli_state::StateDirTrait;
 use crate::config::lookup::ProjectLookup;
@@ -42,6 +44,37 @@ impl NodeManager {
         alias: Option<String>,
         reachable_from_default_secure_channel: bool,
     ) -> Result<OutletStatus> {
+        self.create_outlet_with_options(
+            ctx,
+            CreateOutlet::new(
+                socket_addr,
+                worker_addr,
+                alias,
+                reachable_from_default_secure_channel,
+            ),
+        )
+        .await
+    }
+
+    /// Create an outlet with all the options of a [`CreateOutlet`] request: several targets
+    /// selected for each new connection, TLS to the targets, limits on the connections.
+    /// If no targets are given, the outlet connects to `socket_addr`
+    pub async fn create_outlet_with_options(
+        &mut self,
+        ctx: &Context,
+        create_outlet: CreateOutlet,
+    ) -> Result<OutletStatus> {
+        let CreateOutlet {
+            socket_addr,
+            worker_addr,
+            alias,
+            reachable_from_default_secure_channel,
+            targets,
+            load_balancing,
+            tls,
+            limits,
+            ..
+        } = create_outlet;
         info!(
             "Handling request to create outlet portal at {:?}",
             socket_addr
@@ -74,7 +107,18 @@ impl NodeManager {
             .access_control(&resource, &actions::HANDLE_MESSAGE, trust_context_id, None)
             .await?;
 
-        let options = TcpOutletOptions::new().with_incoming_access_control(access_control);
+        let options = TcpOutletOptions::new()
+            .with_incoming_access_control(access_control)
+            .with_load_balancing(load_balancing.unwrap_or_default().into());
+        let options = match tls {
+            Some(tls) => options.with_tls(tls.to_tcp_outlet_tls()?),
+            None => options,
+        };
+        let limiter = limits.map(|limits| TcpPortalLimiter::new(TcpPortalLimits::from(&limits)));
+        let options = match &limiter {
+            Some(limiter) => options.with_limiter(limiter.clone()),
+            None => options,
+        };
         let options = if !check_credential {
             options.as_consumer(&self.api_transport_flow_control_id)
         } else {
@@ -95,20 +139,29 @@ impl NodeManager {
             options
         };
 
+        let targets = match targets {
+            Some(targets) if !targets.is_empty() => targets,
+            _ => vec![socket_addr.to_string()],
+        };
+
         let res = self
             .tcp_transport
-            .create_tcp_outlet(worker_addr.clone(), socket_addr, options)
+            .create_outlet_with_targets(worker_addr.clone(), targets, options)
             .await;
 
         Ok(match res {
-            Ok(_) => {
+            Ok(targets) => {
                 // TODO: Use better way to store outlets?
-                self.registry.outlets.insert(
-                    alias.clone(),
-                    OutletInfo::new(&socket_addr, Some(&worker_addr)),
-                );
+                let info = OutletInfo::new(&socket_addr, Some(&worker_addr))
+                    .with_targets(targets)
+                    .with_limiter(limiter);
+                let targets_status = info.targets_status();
+                let limits_status = info.limits_status();
+                self.registry.outlets.insert(alias.clone(), info);
 
                 OutletStatus::new(socket_addr, worker_addr, alias, None)
+                    .with_targets(targets_status)
+                    .with_limits(limits_status)
             }
             Err(e) => {
                 warn!(at = %socket_addr, err = %e, "Failed to create TCP outlet");
@@ -156,6 +209,7 @@ impl NodeManagerWorker {
                         None,
                         info.outlet_route.to_string(),
                     )
+                    .with_limits(info.limits_status())
                 })
                 .collect(),
         ))
@@ -290,7 +344,28 @@ impl NodeManagerWorker {
             .access_control(&resource, &actions::HANDLE_MESSAGE, project_id, None)
             .await?;
 
+        let tls = match req.tls() {
+            Some(tls) => Some(tls.to_tcp_inlet_tls().map_err(|e| {
+                Response::bad_request(req_id)
+                    .body(Error::new_without_path().with_message(e.to_string()))
+            })?),
+            None => None,
+        };
+
         let options = TcpInletOptions::new().with_incoming_access_control(access_control.clone());
+        let options = match &tls {
+            Some(tls) => options.with_tls(tls.clone()),
+            None => options,
+        };
+        // The limiter is kept when the inlet is recreated, so that the limits
+        // apply to all the connections of the inlet
+        let limiter = req
+            .limits()
+            .map(|limits| TcpPortalLimiter::new(TcpPortalLimits::from(limits)));
+        let options = match &limiter {
+            Some(limiter) => options.with_limiter(limiter.clone()),
+            None => options,
+        };
 
         let res = node_manager
             .tcp_transport
@@ -304,10 +379,10 @@ impl NodeManagerWorker {
                 let listen_addr = socket_address.to_string();
 
                 // TODO: Use better way to store inlets?
-                node_manager.registry.inlets.insert(
-                    alias.clone(),
-                    InletInfo::new(&listen_addr, Some(&worker_addr), &outlet_route),
-                );
+                let info = InletInfo::new(&listen_addr, Some(&worker_addr), &outlet_route)
+                    .with_limiter(limiter.clone());
+                let limits_status = info.limits_status();
+                node_manager.registry.inlets.insert(alias.clone(), info);
                 if !connection_instance.normalized_addr.is_empty() {
                     debug! {
                         %alias,
@@ -329,19 +404,24 @@ impl NodeManagerWorker {
                         req.suffix_route().clone(),
                         req.authorized(),
                         access_control.clone(),
+                        tls,
+                        limiter,
                         ctx,
                     );
                     session.set_replacer(repl);
                     node_manager.add_session(session);
                 }
 
-                Response::ok(req_id).body(InletStatus::new(
-                    listen_addr,
-                    worker_addr.to_string(),
-                    alias,
-                    None,
-                    outlet_route.to_string(),
-                ))
+                Response::ok(req_id).body(
+                    InletStatus::new(
+                        listen_addr,
+                        worker_addr.to_string(),
+                        alias,
+                        None,
+                        outlet_route.to_string(),
+                    )
+                    .with_limits(limits_status),
+                )
             }
             Err(e) => {
                 warn!(to = %req.outlet_addr(), err = %e, "Failed to create TCP inlet");
@@ -369,13 +449,16 @@ impl NodeManagerWorker {
             {
                 Ok(_) => {
                     debug!(%alias, "Successfully stopped inlet");
-                    Ok(Response::ok(req.id()).body(InletStatus::new(
-                        inlet_to_delete.bind_addr,
-                        inlet_to_delete.worker_addr.to_string(),
-                        alias,
-                        None,
-                        inlet_to_delete.outlet_route.to_string(),
-                    )))
+                    Ok(Response::ok(req.id()).body(
+                        InletStatus::new(
+                            &inlet_to_delete.bind_addr,
+                            inlet_to_delete.worker_addr.to_string(),
+                            alias,
+                            None,
+                            inlet_to_delete.outlet_route.to_string(),
+                        )
+                        .with_limits(inlet_to_delete.limits_status()),
+                    ))
                 }
                 Err(e) => {
                     error!(%alias, "Failed to remove inlet from node registry");
@@ -402,13 +485,16 @@ impl NodeManagerWorker {
         info!(%alias, "Handling request to show inlet portal");
         if let Some(inlet_to_show) = node_manager.registry.inlets.get(alias) {
             debug!(%alias, "Inlet not found in node registry");
-            Ok(Response::ok(req.id()).body(InletStatus::new(
-                inlet_to_show.bind_addr.to_string(),
-                inlet_to_show.worker_addr.to_string(),
-                alias,
-                None,
-                inlet_to_show.outlet_route.to_string(),
-            )))
+            Ok(Response::ok(req.id()).body(
+                InletStatus::new(
+                    inlet_to_show.bind_addr.to_string(),
+                    inlet_to_show.worker_addr.to_string(),
+                    alias,
+                    None,
+                    inlet_to_show.outlet_route.to_string(),
+                )
+                .with_limits(inlet_to_show.limits_status()),
+            ))
         } else {
             error!(%alias, "Inlet not found in the node registry");
             let err_body =
@@ -423,23 +509,17 @@ impl NodeManagerWorker {
         req: &Request,
         create_outlet: CreateOutlet,
     ) -> Result<ResponseBuilder<OutletStatus>, ResponseBuilder<Error>> {
-        let CreateOutlet {
-            socket_addr,
-            worker_addr,
-            alias,
-            reachable_from_default_secure_channel,
-            ..
-        } = create_outlet;
-
-        self.create_outlet_impl(
-            ctx,
-            req.id(),
-            socket_addr,
-            worker_addr,
-            alias,
-            reachable_from_default_secure_channel,
-        )
-        .await
+        let mut node_manager = self.inner().write().await;
+        match node_manager
+            .create_outlet_with_options(ctx, create_outlet)
+            .await
+        {
+            Ok(outlet_status) => Ok(Response::ok(req.id()).body(outlet_status)),
+            Err(e) => {
+                let err_body = Error::new_without_path().with_message(format!("{e:?}"));
+                Err(Response::bad_request(req.id()).body(err_body))
+            }
+        }
     }
 
     pub async fn create_outlet_impl(
@@ -479,12 +559,15 @@ impl NodeManagerWorker {
         let req_id = req.id();
         match node_manager.delete_outlet(alias).await {
             Ok(res) => match res {
-                Some(outlet_info) => Ok(Response::ok(req_id).body(OutletStatus::new(
-                    outlet_info.socket_addr,
-                    outlet_info.worker_addr.clone(),
-                    alias,
-                    None,
-                ))),
+                Some(outlet_info) => Ok(Response::ok(req_id).body(
+                    OutletStatus::new(
+                        outlet_info.socket_addr,
+                        outlet_info.worker_addr.clone(),
+                        alias,
+                        None,
+                    )
+                    .with_limits(outlet_info.limits_status()),
+                )),
                 None => {
                     let err_body = Error::new_without_path()
                         .with_message(format!("Outlet with alias {alias} not found",));
@@ -508,12 +591,16 @@ impl NodeManagerWorker {
         info!(%alias, "Handling request to show outlet portal");
         if let Some(outlet_to_show) = node_manager.registry.outlets.get(alias) {
             debug!(%alias, "Outlet not found in node registry");
-            Ok(Response::ok(req.id()).body(OutletStatus::new(
-                outlet_to_show.socket_addr,
-                outlet_to_show.worker_addr.clone(),
-                alias,
-                None,
-            )))
+            Ok(Response::ok(req.id()).body(
+                OutletStatus::new(
+                    outlet_to_show.socket_addr,
+                    outlet_to_show.worker_addr.clone(),
+                    alias,
+                    None,
+                )
+                .with_targets(outlet_to_show.targets_status())
+                .with_limits(outlet_to_show.limits_status()),
+            ))
         } else {
             error!(%alias, "Outlet not found in the node registry");
             let err_body =
@@ -543,6 +630,8 @@ fn replacer(
     suffix_route: Route,
     auth: Option<Identifier>,
     access: Arc<dyn IncomingAccessControl>,
+    tls: Option<TcpInletTls>,
+    limiter: Option<TcpPortalLimiter>,
     ctx: Arc<Context>,
 ) -> Replacer {
     let connection_instance_arc = Arc::new(Mutex::new(connection_instance));
@@ -554,6 +643,8 @@ fn replacer(
         let bind = bind.clone();
         let node_manager_arc = manager.clone();
         let access = access.clone();
+        let tls = tls.clone();
+        let limiter = limiter.clone();
         let ctx = ctx.clone();
         let connection_instance_arc = connection_instance_arc.clone();
         let inlet_address_arc = inlet_address_arc.clone();
@@ -616,6 +707,14 @@ fn replacer(
                 let node_manager = node_manager_arc.write().await;
 
                 let options = TcpInletOptions::new().with_incoming_access_control(access);
+                let options = match tls {
+                    Some(tls) => options.with_tls(tls),
+                    None => options,
+                };
+                let options = match limiter {
+                    Some(limiter) => options.with_limiter(limiter),
+                    None => options,
+                };
 
                 // Finally attempt to create a new inlet using the new route:
                 let new_inlet_address = node_manager
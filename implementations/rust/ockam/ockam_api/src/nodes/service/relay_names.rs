@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use ockam::identity::IdentitiesRepository;
+use ockam::{RelayNameAuthorization, Result};
+use ockam_abac::expr::{eq, ident, str};
+use ockam_abac::{AuditLog, Env, PolicyAccessControl, PolicyStorage};
+use ockam_core::{async_trait, IncomingAccessControl, RelayMessage};
+
+use crate::{actions, resources};
+
+use super::NodeManager;
+
+/// Authorize the claims of relay names with the policy of the `relay` resource
+/// and of the `claim` action.
+///
+/// The policy can refer to the claimed name with the `resource.relay_name` attribute,
+/// for example `(= subject.relay_name resource.relay_name)`
+struct PolicyRelayNameAuthorization {
+    policies: Arc<dyn PolicyStorage>,
+    repository: Arc<dyn IdentitiesRepository>,
+    trust_context_id: String,
+    audit_log: Option<Arc<dyn AuditLog>>,
+}
+
+#[async_trait]
+impl RelayNameAuthorization for PolicyRelayNameAuthorization {
+    async fn is_authorized(&self, name: &str, registration: &RelayMessage) -> Result<bool> {
+        let mut env = Env::new();
+        env.put("resource.id", str(resources::RELAY.as_str()));
+        env.put("action.id", str(actions::CLAIM.as_str()));
+        env.put(
+            "resource.trust_context_id",
+            str(self.trust_context_id.as_str()),
+        );
+        env.put("resource.relay_name", str(name));
+
+        let access_control = PolicyAccessControl::new(
+            self.policies.clone(),
+            self.repository.clone(),
+            resources::RELAY,
+            actions::CLAIM,
+            env,
+        );
+        match &self.audit_log {
+            Some(audit_log) => access_control.with_audit_log(audit_log.clone()),
+            None => access_control,
+        }
+        .is_authorized(registration)
+        .await
+    }
+}
+
+impl NodeManager {
+    /// Return the authorization of the relay names claimed on this node, when credentials
+    /// are checked. By default, the members of the trust context can claim any name
+    pub(super) async fn relay_name_authorization(
+        &self,
+    ) -> Result<Option<Arc<dyn RelayNameAuthorization>>> {
+        if !self.enable_credential_checks {
+            return Ok(None);
+        }
+        let trust_context_id = match self.trust_context() {
+            Ok(tc) => tc.id().to_string(),
+            Err(_) => return Ok(None),
+        };
+
+        if self
+            .policies
+            .get_policy(&resources::RELAY, &actions::CLAIM)
+            .await?
+            .is_none()
+        {
+            let fallback = eq([
+                ident("resource.trust_context_id"),
+                ident("subject.trust_context_id"),
+            ]);
+            self.policies
+                .set_policy(&resources::RELAY, &actions::CLAIM, &fallback)
+                .await?
+        }
+
+        Ok(Some(Arc::new(PolicyRelayNameAuthorization {
+            policies: self.policies.clone(),
+            repository: self.identities_repository(),
+            trust_context_id,
+            audit_log: self.audit_log.clone(),
+        })))
+    }
+}
@@ -7,8 +7,9 @@ use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::TrustEveryonePolicy;
 use ockam::identity::Vault;
 use ockam::identity::{
-    Identifier, Identities, SecureChannelListenerOptions, SecureChannelOptions, SecureChannels,
-    TrustMultiIdentifiersPolicy,
+    CredentialsRetriever, Identifier, Identities, SecureChannelListenerOptions,
+    SecureChannelOptions, SecureChannels, TrustMultiIdentifiersPolicy,
+    DEFAULT_RESUMPTION_TICKET_LIFETIME,
 };
 use ockam::identity::{SecureChannel, SecureChannelListener};
 use ockam::{Address, Result, Route};
@@ -36,6 +37,7 @@ use crate::{multiaddr_to_route, DefaultAddress};
 use super::NodeManagerWorker;
 
 impl NodeManager {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn create_secure_channel_internal(
         &mut self,
         identifier: &Identifier,
@@ -44,6 +46,7 @@ impl NodeManager {
         authorized_identifiers: Option<Vec<Identifier>>,
         timeout: Option<Duration>,
         credential: Option<CredentialAndPurposeKey>,
+        credentials_retriever: Option<Arc<dyn CredentialsRetriever>>,
     ) -> Result<SecureChannel> {
         debug!(%sc_route, "Creating secure channel");
         let options = SecureChannelOptions::new();
@@ -60,6 +63,19 @@ impl NodeManager {
             options
         };
 
+        let options = if let Some(credentials_retriever) = credentials_retriever {
+            options.with_credentials_retriever(credentials_retriever)
+        } else {
+            options
+        };
+
+        // a channel to a single known identity can be re-established in one round trip
+        // when it is recreated, for example by the Medic
+        let options = match authorized_identifiers.as_deref() {
+            Some([their_identifier]) => options.with_session_resumption(their_identifier.clone()),
+            _ => options,
+        };
+
         let options = match authorized_identifiers.clone() {
             Some(ids) => options.with_trust_policy(TrustMultiIdentifiersPolicy::new(ids)),
             None => options.with_trust_policy(TrustEveryonePolicy),
@@ -118,22 +134,22 @@ impl NodeManager {
             CredentialExchangeMode::None
         };
 
-        let credential = match actual_exchange_mode {
+        let (credential, credentials_retriever) = match actual_exchange_mode {
             CredentialExchangeMode::None => {
                 debug!("No credential presentation");
-                None
+                (None, None)
             }
             CredentialExchangeMode::Oneway | CredentialExchangeMode::Mutual => {
                 debug!("One-way credential presentation");
-                Some(match provided_credential {
-                    Some(c) => c,
+                match provided_credential {
+                    Some(c) => (Some(c), None),
+                    // the credentials retrieved from the authority are refreshed before they expire
                     None => {
-                        self.trust_context()?
-                            .authority()?
-                            .credential(ctx, &identifier)
-                            .await?
+                        let authority = self.trust_context()?.authority()?.clone();
+                        let retriever: Arc<dyn CredentialsRetriever> = Arc::new(authority);
+                        (None, Some(retriever))
                     }
-                })
+                }
             }
         };
 
@@ -145,6 +161,7 @@ impl NodeManager {
                 authorized_identifiers,
                 timeout,
                 credential,
+                credentials_retriever,
             )
             .await?;
 
@@ -168,8 +185,9 @@ impl NodeManager {
         let secure_channels = self.build_secure_channels(vault_name.clone()).await?;
         let identifier = self.get_identifier(identity_name.clone()).await?;
 
-        let options =
-            SecureChannelListenerOptions::new().as_consumer(&self.api_transport_flow_control_id);
+        let options = SecureChannelListenerOptions::new()
+            .as_consumer(&self.api_transport_flow_control_id)
+            .with_resumption_tickets(DEFAULT_RESUMPTION_TICKET_LIFETIME);
 
         let options = match authorized_identifiers {
             Some(ids) => options.with_trust_policy(TrustMultiIdentifiersPolicy::new(ids)),
@@ -177,7 +195,11 @@ impl NodeManager {
         };
 
         let options = if let Ok(trust_context) = self.trust_context() {
-            options.with_trust_context(trust_context.clone())
+            let options = options.with_trust_context(trust_context.clone());
+            match trust_context.authority() {
+                Ok(authority) => options.with_credentials_retriever(Arc::new(authority.clone())),
+                Err(_) => options,
+            }
         } else {
             options
         };
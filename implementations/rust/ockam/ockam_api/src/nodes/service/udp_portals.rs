@@ -0,0 +1,456 @@
+use std::time::Duration;
+
+use ockam::Result;
+use ockam_abac::Resource;
+use ockam_core::api::{Error, Request, Response, ResponseBuilder};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_multiaddr::proto::Project;
+use ockam_node::Context;
+use ockam_transport_udp::{UdpInletOptions, UdpOutletOptions, UdpTransport};
+
+use crate::cli_state::StateDirTrait;
+use crate::config::lookup::ProjectLookup;
+use crate::local_multiaddr_to_route;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::portal::{
+    CreateUdpInlet, CreateUdpOutlet, InletList, InletStatus, OutletList, OutletStatus,
+};
+use crate::nodes::registry::{InletInfo, OutletInfo};
+use crate::nodes::service::random_alias;
+use crate::{actions, resources, DefaultAddress};
+
+use super::{NodeManager, NodeManagerWorker};
+
+impl NodeManager {
+    /// Return the UDP transport of the node, which is created with the first UDP portal
+    async fn udp_transport(&mut self, ctx: &Context) -> Result<&UdpTransport> {
+        let udp_transport = match self.udp_transport.take() {
+            Some(udp_transport) => udp_transport,
+            None => UdpTransport::create(ctx).await?,
+        };
+        Ok(self.udp_transport.insert(udp_transport))
+    }
+
+    pub async fn create_udp_outlet(
+        &mut self,
+        ctx: &Context,
+        create_outlet: CreateUdpOutlet,
+    ) -> Result<OutletStatus> {
+        let CreateUdpOutlet {
+            socket_addr,
+            worker_addr,
+            alias,
+            reachable_from_default_secure_channel,
+            idle_timeout,
+            ..
+        } = create_outlet;
+
+        info!(
+            "Handling request to create UDP outlet portal at {:?}",
+            socket_addr
+        );
+        let resource = alias
+            .as_deref()
+            .map(Resource::new)
+            .unwrap_or(resources::UDP_OUTLET);
+
+        let alias = alias.unwrap_or_else(random_alias);
+
+        // Check that there is no entry in the registry with the same alias
+        if self.registry.udp_outlets.contains_key(&alias) {
+            let message = format!("A UDP outlet with alias '{alias}' already exists");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                message,
+            ));
+        }
+
+        let check_credential = self.enable_credential_checks;
+        let trust_context_id = if check_credential {
+            Some(self.trust_context()?.id())
+        } else {
+            None
+        };
+
+        let access_control = self
+            .access_control(&resource, &actions::HANDLE_MESSAGE, trust_context_id, None)
+            .await?;
+
+        let options = UdpOutletOptions::new().with_incoming_access_control(access_control);
+        let options = match idle_timeout {
+            Some(idle_timeout) => options.with_idle_timeout(idle_timeout),
+            None => options,
+        };
+        let options = if !check_credential {
+            options.as_consumer(&self.api_transport_flow_control_id)
+        } else {
+            options
+        };
+
+        let options = if reachable_from_default_secure_channel {
+            // Accept messages from the default secure channel listener
+            if let Some(flow_control_id) = ctx
+                .flow_controls()
+                .get_flow_control_with_spawner(&DefaultAddress::SECURE_CHANNEL_LISTENER.into())
+            {
+                options.as_consumer(&flow_control_id)
+            } else {
+                options
+            }
+        } else {
+            options
+        };
+
+        let res = self
+            .udp_transport(ctx)
+            .await?
+            .create_udp_outlet(worker_addr.clone(), socket_addr, options)
+            .await;
+
+        match res {
+            Ok(_) => {
+                self.registry.udp_outlets.insert(
+                    alias.clone(),
+                    OutletInfo::new(&socket_addr, Some(&worker_addr)),
+                );
+
+                Ok(OutletStatus::new(socket_addr, worker_addr, alias, None))
+            }
+            Err(e) => {
+                warn!(at = %socket_addr, err = %e, "Failed to create UDP outlet");
+                let message = format!("Failed to create UDP outlet: {}", e);
+                Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Internal,
+                    message,
+                ))
+            }
+        }
+    }
+
+    pub async fn delete_udp_outlet(&mut self, alias: &str) -> Result<Option<OutletInfo>> {
+        info!(%alias, "Handling request to delete UDP outlet portal");
+        if let Some(deleted_outlet) = self.registry.udp_outlets.remove(alias) {
+            debug!(%alias, "Successfully removed UDP outlet from node registry");
+            // the UDP transport exists since the outlet was created with it
+            if let Some(udp_transport) = &self.udp_transport {
+                if let Err(e) = udp_transport
+                    .stop_outlet(deleted_outlet.worker_addr.clone())
+                    .await
+                {
+                    warn!(%alias, %e, "Failed to stop UDP outlet worker");
+                }
+            }
+            Ok(Some(deleted_outlet))
+        } else {
+            warn!(%alias, "UDP outlet not found in the node registry");
+            Ok(None)
+        }
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn get_udp_inlets(&self, req: &Request) -> ResponseBuilder<InletList> {
+        let registry = &self.node_manager.read().await.registry.udp_inlets;
+        Response::ok(req.id()).body(InletList::new(
+            registry
+                .iter()
+                .map(|(alias, info)| {
+                    InletStatus::new(
+                        &info.bind_addr,
+                        info.worker_addr.to_string(),
+                        alias,
+                        None,
+                        info.outlet_route.to_string(),
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    pub(super) async fn get_udp_outlets(&self, req: &Request) -> ResponseBuilder<OutletList> {
+        let registry = &self.node_manager.read().await.registry.udp_outlets;
+        Response::ok(req.id()).body(OutletList::new(
+            registry
+                .iter()
+                .map(|(alias, info)| {
+                    OutletStatus::new(info.socket_addr, info.worker_addr.clone(), alias, None)
+                })
+                .collect(),
+        ))
+    }
+
+    pub(super) async fn create_udp_inlet(
+        &mut self,
+        ctx: &Context,
+        req: &Request,
+        create_inlet: CreateUdpInlet,
+    ) -> Result<ResponseBuilder<InletStatus>, ResponseBuilder<Error>> {
+        info!("Handling request to create UDP inlet portal");
+        let req_id = req.id();
+        let listen_addr = create_inlet.listen_addr.clone();
+        let alias = create_inlet.alias.clone().unwrap_or_else(random_alias);
+
+        {
+            let registry = &self.node_manager.read().await.registry.udp_inlets;
+
+            // Check that there is no entry in the registry with the same alias
+            if registry.contains_key(&alias) {
+                let err_body = Error::new_without_path()
+                    .with_message(format!("A UDP inlet with alias '{alias}' already exists"));
+                return Err(Response::bad_request(req_id).body(err_body));
+            }
+
+            // Check that there is no entry in the registry with the same UDP bind address
+            if registry
+                .values()
+                .any(|inlet| inlet.bind_addr == listen_addr)
+            {
+                let err_body = Error::new_without_path().with_message(format!(
+                    "A UDP inlet with bind udp address '{listen_addr}' already exists",
+                ));
+                return Err(Response::bad_request(req_id).body(err_body));
+            }
+        }
+
+        // The outlet is reached like the outlet of a TCP inlet: either directly, or via a
+        // forwarder, with secure channels established along the way
+        let connection_instance = {
+            let connection = Connection::new(ctx, &create_inlet.outlet_addr)
+                .with_authorized_identity(create_inlet.authorized.clone())
+                .with_timeout(Duration::from_secs(5));
+
+            NodeManager::connect(self.node_manager.clone(), connection).await?
+        };
+
+        let outlet_route = match local_multiaddr_to_route(&connection_instance.normalized_addr) {
+            Some(route) => route,
+            None => {
+                let err_body = Error::new_without_path().with_message("Invalid outlet route.");
+                return Err(Response::bad_request(req_id).body(err_body));
+            }
+        };
+
+        let resource = create_inlet
+            .alias
+            .as_deref()
+            .map(Resource::new)
+            .unwrap_or(resources::UDP_INLET);
+
+        let mut node_manager = self.node_manager.write().await;
+        let check_credential = node_manager.enable_credential_checks;
+        let project_id = if check_credential {
+            let projects = node_manager.cli_state.projects.list().map_err(|e| {
+                Response::bad_request(req_id)
+                    .body(Error::new_without_path().with_message(e.to_string()))
+            })?;
+            let projects = ProjectLookup::from_state(projects).await.map_err(|e| {
+                Response::bad_request(req_id)
+                    .body(Error::new_without_path().with_message(e.to_string()))
+            })?;
+            let project_id = create_inlet
+                .outlet_addr
+                .first()
+                .and_then(|p| {
+                    p.cast::<Project>()
+                        .and_then(|p| projects.get(&*p).map(|info| info.id.clone()))
+                })
+                .or_else(|| Some(node_manager.trust_context().ok()?.id().to_string()));
+            if project_id.is_none() {
+                let err_body = Error::new_without_path()
+                    .with_message("Credential check requires a project or trust context");
+                return Err(Response::bad_request(req_id).body(err_body));
+            }
+            project_id
+        } else {
+            None
+        };
+
+        let access_control = node_manager
+            .access_control(
+                &resource,
+                &actions::HANDLE_MESSAGE,
+                project_id.as_deref(),
+                None,
+            )
+            .await?;
+
+        let options = UdpInletOptions::new().with_incoming_access_control(access_control);
+        let options = match create_inlet.idle_timeout {
+            Some(idle_timeout) => options.with_idle_timeout(idle_timeout),
+            None => options,
+        };
+
+        let res = node_manager
+            .udp_transport(ctx)
+            .await?
+            .create_inlet(listen_addr, outlet_route.clone(), options)
+            .await;
+
+        match res {
+            Ok((socket_address, worker_addr)) => {
+                //when using 0 port, the chosen port will be populated
+                //in the returned socket address
+                let listen_addr = socket_address.to_string();
+
+                node_manager.registry.udp_inlets.insert(
+                    alias.clone(),
+                    InletInfo::new(&listen_addr, Some(&worker_addr), &outlet_route),
+                );
+
+                Ok(Response::ok(req_id).body(InletStatus::new(
+                    listen_addr,
+                    worker_addr.to_string(),
+                    alias,
+                    None,
+                    outlet_route.to_string(),
+                )))
+            }
+            Err(e) => {
+                warn!(to = %create_inlet.outlet_addr, err = %e, "Failed to create UDP inlet");
+                let err_body = Error::new_without_path()
+                    .with_message(format!("Failed to create UDP inlet: {}", e));
+                Err(Response::bad_request(req_id).body(err_body))
+            }
+        }
+    }
+
+    pub(super) async fn delete_udp_inlet(
+        &mut self,
+        req: &Request,
+        alias: &str,
+    ) -> Result<ResponseBuilder<InletStatus>, ResponseBuilder<Error>> {
+        let mut node_manager = self.node_manager.write().await;
+
+        info!(%alias, "Handling request to delete UDP inlet portal");
+        let inlet_to_delete = match node_manager.registry.udp_inlets.remove(alias) {
+            Some(inlet_to_delete) => inlet_to_delete,
+            None => {
+                error!(%alias, "UDP inlet not found in the node registry");
+                let err_body = Error::new(req.path())
+                    .with_message(format!("UDP inlet with alias {alias} not found"));
+                return Err(Response::not_found(req.id()).body(err_body));
+            }
+        };
+
+        // the UDP transport exists since the inlet was created with it
+        let result = match &node_manager.udp_transport {
+            Some(udp_transport) => {
+                udp_transport
+                    .stop_inlet(inlet_to_delete.worker_addr.clone())
+                    .await
+            }
+            None => Ok(()),
+        };
+
+        match result {
+            Ok(_) => {
+                debug!(%alias, "Successfully stopped UDP inlet");
+                Ok(Response::ok(req.id()).body(InletStatus::new(
+                    inlet_to_delete.bind_addr,
+                    inlet_to_delete.worker_addr.to_string(),
+                    alias,
+                    None,
+                    inlet_to_delete.outlet_route.to_string(),
+                )))
+            }
+            Err(e) => {
+                error!(%alias, "Failed to stop UDP inlet");
+                let err_body = Error::new(req.path()).with_message(format!(
+                    "Failed to remove UDP inlet with alias {alias}. {}",
+                    e
+                ));
+                Err(Response::internal_error(req.id()).body(err_body))
+            }
+        }
+    }
+
+    pub(super) async fn show_udp_inlet(
+        &self,
+        req: &Request,
+        alias: &str,
+    ) -> Result<ResponseBuilder<InletStatus>, ResponseBuilder<Error>> {
+        let node_manager = self.node_manager.read().await;
+
+        info!(%alias, "Handling request to show UDP inlet portal");
+        if let Some(inlet_to_show) = node_manager.registry.udp_inlets.get(alias) {
+            Ok(Response::ok(req.id()).body(InletStatus::new(
+                inlet_to_show.bind_addr.to_string(),
+                inlet_to_show.worker_addr.to_string(),
+                alias,
+                None,
+                inlet_to_show.outlet_route.to_string(),
+            )))
+        } else {
+            error!(%alias, "UDP inlet not found in the node registry");
+            let err_body = Error::new(req.path())
+                .with_message(format!("UDP inlet with alias {alias} not found"));
+            Err(Response::not_found(req.id()).body(err_body))
+        }
+    }
+
+    pub(super) async fn create_udp_outlet(
+        &mut self,
+        ctx: &Context,
+        req: &Request,
+        create_outlet: CreateUdpOutlet,
+    ) -> Result<ResponseBuilder<OutletStatus>, ResponseBuilder<Error>> {
+        let mut node_manager = self.node_manager.write().await;
+        match node_manager.create_udp_outlet(ctx, create_outlet).await {
+            Ok(outlet_status) => Ok(Response::ok(req.id()).body(outlet_status)),
+            Err(e) => {
+                let err_body = Error::new_without_path().with_message(format!("{e:?}"));
+                Err(Response::bad_request(req.id()).body(err_body))
+            }
+        }
+    }
+
+    pub(super) async fn delete_udp_outlet(
+        &mut self,
+        req: &Request,
+        alias: &str,
+    ) -> Result<ResponseBuilder<OutletStatus>, ResponseBuilder<Error>> {
+        let mut node_manager = self.node_manager.write().await;
+        match node_manager.delete_udp_outlet(alias).await {
+            Ok(Some(outlet_info)) => Ok(Response::ok(req.id()).body(OutletStatus::new(
+                outlet_info.socket_addr,
+                outlet_info.worker_addr,
+                alias,
+                None,
+            ))),
+            Ok(None) => {
+                let err_body = Error::new_without_path()
+                    .with_message(format!("UDP outlet with alias {alias} not found"));
+                Err(Response::not_found(req.id()).body(err_body))
+            }
+            Err(e) => {
+                let err_body = Error::new_without_path().with_message(format!("{e:?}"));
+                Err(Response::bad_request(req.id()).body(err_body))
+            }
+        }
+    }
+
+    pub(super) async fn show_udp_outlet(
+        &self,
+        req: &Request,
+        alias: &str,
+    ) -> Result<ResponseBuilder<OutletStatus>, ResponseBuilder<Error>> {
+        let node_manager = self.node_manager.read().await;
+
+        info!(%alias, "Handling request to show UDP outlet portal");
+        if let Some(outlet_to_show) = node_manager.registry.udp_outlets.get(alias) {
+            Ok(Response::ok(req.id()).body(OutletStatus::new(
+                outlet_to_show.socket_addr,
+                outlet_to_show.worker_addr.clone(),
+                alias,
+                None,
+            )))
+        } else {
+            error!(%alias, "UDP outlet not found in the node registry");
+            let err_body = Error::new(req.path())
+                .with_message(format!("UDP outlet with alias {alias} not found"));
+            Err(Response::not_found(req.id()).body(err_body))
+        }
+    }
+}
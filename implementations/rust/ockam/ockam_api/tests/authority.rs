@@ -1,6 +1,7 @@
 use ockam::identity::utils::now;
 use ockam::identity::{
-    secure_channels, AttributesEntry, Identifier, SecureChannelOptions, SecureChannels,
+    secure_channels, AttributesEntry, Identifier, RevocationListIssuerClient, SecureChannelOptions,
+    SecureChannels,
 };
 use ockam_api::authenticator::direct::DirectAuthenticatorClient;
 use ockam_api::authority_node::{Authority, Configuration};
@@ -177,6 +178,63 @@ async fn test_one_admin_one_member(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
+#[ockam_macros::test]
+async fn deleted_member_is_revoked(ctx: &mut Context) -> Result<()> {
+    use std::collections::HashMap;
+
+    let secure_channels = secure_channels();
+
+    let admins = setup(ctx, secure_channels.clone(), 1).await?;
+    let admin = &admins[0];
+
+    let member = secure_channels
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?
+        .identifier()
+        .clone();
+
+    admin
+        .client
+        .add_member(member.clone(), HashMap::<&str, &str>::default())
+        .await?;
+    admin.client.delete_member(member.clone()).await?;
+
+    let sc = secure_channels
+        .create_secure_channel(
+            ctx,
+            &admin.identifier,
+            route!["api"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+    let authority = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(sc.encryptor_address())
+        .unwrap()
+        .their_id()
+        .clone();
+
+    let client =
+        RevocationListIssuerClient::new(route![sc, DefaultAddress::REVOCATION_LIST], ctx).await?;
+    let revocation_list = client.revocation_list().await?;
+
+    let (issuer, data) = secure_channels
+        .identities()
+        .credentials()
+        .credentials_verification()
+        .verify_revocation_list(&[authority.clone()], &revocation_list)
+        .await?;
+    assert_eq!(issuer, authority);
+    assert_eq!(data.revoked_subjects.len(), 1);
+    assert_eq!(data.revoked_subjects[0].subject, member);
+
+    ctx.stop().await?;
+
+    Ok(())
+}
+
 #[ockam_macros::test]
 async fn two_admins_two_members_exist_in_one_global_scope(ctx: &mut Context) -> Result<()> {
     use std::collections::HashMap;
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use clap::Args;
+use miette::{miette, IntoDiagnostic};
+use minicbor::bytes::ByteVec;
+
+use ockam::identity::models::{CredentialAndPurposeKey, DelegationScope};
+use ockam::identity::{Identifier, MAX_CREDENTIAL_VALIDITY, TRUST_CONTEXT_ID};
+use ockam::Context;
+use ockam_api::cli_state::traits::StateDirTrait;
+
+use crate::identity::{get_identity_name, initialize_identity_if_default};
+use crate::output::{CredentialAndPurposeKeyDisplay, EncodeFormat};
+use crate::util::duration::duration_parser;
+use crate::util::node_rpc;
+use crate::util::parsers::{delegation_parser, identity_identifier_parser};
+use crate::vault::default_vault_name;
+use crate::{CommandGlobalOpts, Result};
+
+/// Value allowing an attribute to be issued with any value
+const ANY_VALUE: &str = "*";
+
+/// Issue a delegation allowing another identity to issue credentials
+/// restricted to some attributes and to a maximum validity
+#[derive(Clone, Debug, Args)]
+pub struct DelegateCommand {
+    #[arg(long = "as")]
+    pub as_identity: Option<String>,
+
+    #[arg(long = "for", value_name = "IDENTIFIER", value_parser = identity_identifier_parser)]
+    pub identity_identifier: Identifier,
+
+    /// Attributes in `key=value` format which the delegate is allowed to issue.
+    /// The value `*` allows any value, and a key can be given several times to allow several values
+    #[arg(short, long = "attribute", value_name = "ATTRIBUTE")]
+    pub attributes: Vec<String>,
+
+    /// Maximum validity of the credentials issued by the delegate
+    #[arg(long, value_name = "DURATION", default_value = "30d", value_parser = duration_parser)]
+    pub max_ttl: Duration,
+
+    /// Allow the delegate to delegate a part of its scope to other identities
+    #[arg(long)]
+    pub can_delegate: bool,
+
+    /// Hex encoded delegation received by the identity, when it is not an authority
+    #[arg(long, value_name = "DELEGATION", value_parser = delegation_parser)]
+    pub delegation: Option<CredentialAndPurposeKey>,
+
+    #[arg()]
+    pub vault: Option<String>,
+
+    /// Encoding Format
+    #[arg(long = "encoding", value_enum, default_value = "plain")]
+    encode_format: EncodeFormat,
+}
+
+impl DelegateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_identity_if_default(&opts, &self.as_identity);
+        node_rpc(run_impl, (opts, self));
+    }
+
+    fn attributes(&self) -> Result<BTreeMap<String, Vec<String>>> {
+        let mut attributes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for attr in &self.attributes {
+            let mut parts = attr.splitn(2, '=');
+            let key = parts.next().ok_or(miette!("key expected"))?;
+            let value = parts.next().ok_or(miette!("value expected)"))?;
+            attributes
+                .entry(key.to_string())
+                .or_default()
+                .push(value.to_string());
+        }
+        // An empty list of values allows any value
+        for values in attributes.values_mut() {
+            if values.iter().any(|value| value == ANY_VALUE) {
+                values.clear();
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+async fn run_impl(
+    _ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, DelegateCommand),
+) -> miette::Result<()> {
+    let identity_name = get_identity_name(&opts.state, &cmd.as_identity);
+    let ident_state = opts.state.identities.get(&identity_name)?;
+
+    let vault_name = cmd
+        .vault
+        .clone()
+        .unwrap_or_else(|| default_vault_name(&opts.state));
+    let vault = opts.state.vaults.get(&vault_name)?.get().await?;
+    let identities = opts.state.get_identities(vault).await?;
+    let issuer = ident_state.identifier();
+
+    // The delegate issues credentials for the trust context of the authority
+    // which issued the first delegation
+    let parent_scope = match &cmd.delegation {
+        Some(delegation) => Some(delegation.delegation_scope().into_diagnostic()?),
+        None => None,
+    };
+    let trust_context_ids = match &parent_scope {
+        Some(parent_scope) => parent_scope
+            .attributes
+            .get(&ByteVec::from(TRUST_CONTEXT_ID.to_vec()))
+            .map(|values| values.iter().map(|value| value.to_vec()).collect())
+            .unwrap_or_default(),
+        None => vec![issuer.to_string().into_bytes()],
+    };
+    let mut scope = DelegationScope::new(cmd.max_ttl)
+        .with_can_delegate(cmd.can_delegate)
+        .with_attribute(TRUST_CONTEXT_ID.to_vec(), trust_context_ids);
+    for (key, values) in cmd.attributes()? {
+        scope = scope.with_attribute(
+            key.into_bytes(),
+            values.into_iter().map(String::into_bytes).collect(),
+        );
+    }
+
+    // A delegated identity can't issue delegations valid for longer than its credentials
+    let ttl = match &parent_scope {
+        Some(parent_scope) => {
+            MAX_CREDENTIAL_VALIDITY.min(Duration::from_secs(parent_scope.max_ttl))
+        }
+        None => MAX_CREDENTIAL_VALIDITY,
+    };
+    let delegation = identities
+        .credentials()
+        .credentials_creation()
+        .issue_delegation(
+            &issuer,
+            &cmd.identity_identifier,
+            &scope,
+            ttl,
+            cmd.delegation.as_ref(),
+        )
+        .await
+        .into_diagnostic()?;
+
+    cmd.encode_format
+        .println_value(&CredentialAndPurposeKeyDisplay(delegation))?;
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use ockam_core::compat::collections::HashMap;
+use std::time::Duration;
 
 use crate::identity::{get_identity_name, initialize_identity_if_default};
 use crate::{
-    util::{node_rpc, parsers::identity_identifier_parser},
+    util::{
+        node_rpc,
+        parsers::{delegation_parser, identity_identifier_parser},
+    },
     vault::default_vault_name,
     CommandGlobalOpts, Result,
 };
@@ -10,6 +14,8 @@ use clap::Args;
 
 use crate::output::{CredentialAndPurposeKeyDisplay, EncodeFormat};
 use miette::{miette, IntoDiagnostic};
+use minicbor::bytes::ByteVec;
+use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::utils::AttributesBuilder;
 use ockam::identity::Identifier;
 use ockam::identity::{MAX_CREDENTIAL_VALIDITY, PROJECT_MEMBER_SCHEMA, TRUST_CONTEXT_ID};
@@ -31,6 +37,11 @@ pub struct IssueCommand {
     #[arg()]
     pub vault: Option<String>,
 
+    /// Hex encoded delegation allowing the identity to issue the credential,
+    /// when it is not an authority
+    #[arg(long, value_name = "DELEGATION", value_parser = delegation_parser)]
+    pub delegation: Option<CredentialAndPurposeKey>,
+
     /// Encoding Format
     #[arg(long = "encoding", value_enum, default_value = "plain")]
     encode_format: EncodeFormat,
@@ -74,27 +85,53 @@ async fn run_impl(
     let identities = opts.state.get_identities(vault).await?;
     let issuer = ident_state.identifier();
 
+    // A delegated credential belongs to the trust context of the authority which
+    // issued the first delegation, as recorded in the scope of the delegation
+    let trust_context_id = match &cmd.delegation {
+        Some(delegation) => delegation
+            .delegation_scope()
+            .into_diagnostic()?
+            .attributes
+            .get(&ByteVec::from(TRUST_CONTEXT_ID.to_vec()))
+            .and_then(|values| values.first())
+            .map(|value| value.to_vec())
+            .unwrap_or_else(|| auth_identity_identifier.to_string().into_bytes()),
+        None => auth_identity_identifier.to_string().into_bytes(),
+    };
     let mut attributes_builder = AttributesBuilder::with_schema(PROJECT_MEMBER_SCHEMA)
-        .with_attribute(
-            TRUST_CONTEXT_ID.to_vec(),
-            auth_identity_identifier.to_string(),
-        );
+        .with_attribute(TRUST_CONTEXT_ID.to_vec(), trust_context_id);
     for (key, value) in cmd.attributes()? {
         attributes_builder =
             attributes_builder.with_attribute(key.as_bytes().to_vec(), value.as_bytes().to_vec());
     }
 
-    let credential = identities
-        .credentials()
-        .credentials_creation()
-        .issue_credential(
-            &issuer,
-            cmd.identity_identifier(),
-            attributes_builder.build(),
-            MAX_CREDENTIAL_VALIDITY,
-        )
-        .await
-        .into_diagnostic()?;
+    let credentials_creation = identities.credentials().credentials_creation();
+    let credential = match &cmd.delegation {
+        Some(delegation) => {
+            let max_ttl =
+                Duration::from_secs(delegation.delegation_scope().into_diagnostic()?.max_ttl);
+            credentials_creation
+                .issue_delegated_credential(
+                    &issuer,
+                    cmd.identity_identifier(),
+                    attributes_builder.build(),
+                    MAX_CREDENTIAL_VALIDITY.min(max_ttl),
+                    delegation,
+                )
+                .await
+        }
+        None => {
+            credentials_creation
+                .issue_credential(
+                    &issuer,
+                    cmd.identity_identifier(),
+                    attributes_builder.build(),
+                    MAX_CREDENTIAL_VALIDITY,
+                )
+                .await
+        }
+    }
+    .into_diagnostic()?;
 
     cmd.encode_format
         .println_value(&CredentialAndPurposeKeyDisplay(credential))?;
@@ -1,3 +1,4 @@
+pub(crate) mod delegate;
 pub(crate) mod get;
 pub(crate) mod issue;
 pub(crate) mod list;
@@ -7,6 +8,7 @@ pub(crate) mod store;
 pub(crate) mod verify;
 
 use colorful::Colorful;
+pub(crate) use delegate::DelegateCommand;
 pub(crate) use get::GetCommand;
 pub(crate) use issue::IssueCommand;
 pub(crate) use list::ListCommand;
@@ -37,6 +39,7 @@ pub struct CredentialCommand {
 pub enum CredentialSubcommand {
     #[command(display_order = 900)]
     Get(GetCommand),
+    Delegate(DelegateCommand),
     Issue(IssueCommand),
     List(ListCommand),
     Present(PresentCommand),
@@ -49,6 +52,7 @@ impl CredentialCommand {
     pub fn run(self, options: CommandGlobalOpts) {
         match self.subcommand {
             CredentialSubcommand::Get(c) => c.run(options),
+            CredentialSubcommand::Delegate(c) => c.run(options),
             CredentialSubcommand::Issue(c) => c.run(options),
             CredentialSubcommand::List(c) => c.run(options),
             CredentialSubcommand::Present(c) => c.run(options),
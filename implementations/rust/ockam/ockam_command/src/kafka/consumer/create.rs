@@ -50,6 +50,7 @@ impl CreateCommand {
             bootstrap_server: self.bootstrap_server,
             brokers_port_range: self.brokers_port_range,
             project_route: self.project_route,
+            broadcast_consumers: vec![],
         };
         node_rpc(rpc, (opts, arg_opts));
     }
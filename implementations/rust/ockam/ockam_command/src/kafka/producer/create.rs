@@ -37,6 +37,10 @@ pub struct CreateCommand {
     /// The route to the project in ockam orchestrator, expected something like /project/<name>
     #[arg(long, default_value_t = kafka_default_project_route())]
     project_route: MultiAddr,
+    /// Encrypt each record once for several consumers, reached with these routes, instead
+    /// of using a secure channel per topic partition. Can be repeated
+    #[arg(long = "broadcast-to", value_name = "ROUTE")]
+    broadcast_consumers: Vec<MultiAddr>,
 }
 
 impl CreateCommand {
@@ -50,6 +54,7 @@ impl CreateCommand {
             bootstrap_server: self.bootstrap_server,
             brokers_port_range: self.brokers_port_range,
             project_route: self.project_route,
+            broadcast_consumers: self.broadcast_consumers,
         };
         node_rpc(rpc, (opts, arg_opts));
     }
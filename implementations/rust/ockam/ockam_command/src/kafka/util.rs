@@ -23,6 +23,7 @@ pub struct ArgOpts {
     pub bootstrap_server: SocketAddr,
     pub brokers_port_range: PortRange,
     pub project_route: MultiAddr,
+    pub broadcast_consumers: Vec<MultiAddr>,
 }
 
 pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> miette::Result<()> {
@@ -34,6 +35,7 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
         bootstrap_server,
         brokers_port_range,
         project_route,
+        broadcast_consumers,
     } = args;
 
     opts.terminal
@@ -42,6 +44,10 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
     display_parse_logs(&opts);
 
     let project_route = process_nodes_multiaddr(&project_route, &opts.state)?;
+    let broadcast_consumers = broadcast_consumers
+        .iter()
+        .map(|route| process_nodes_multiaddr(route, &opts.state))
+        .collect::<crate::Result<Vec<_>>>()?;
 
     let is_finished = Mutex::new(false);
     let send_req = async {
@@ -52,7 +58,8 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
             bootstrap_server.to_owned(),
             brokers_port_range,
             project_route,
-        );
+        )
+        .with_broadcast_consumer_routes(broadcast_consumers);
         let payload = StartServiceRequest::new(payload, &addr);
         let req = Request::post(endpoint).body(payload);
         start_service_impl(&mut rpc, &kafka_entity, req).await?;
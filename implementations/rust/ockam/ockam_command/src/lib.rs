@@ -56,6 +56,7 @@ mod subscription;
 pub mod tcp;
 mod terminal;
 mod trust_context;
+mod udp;
 mod upgrade;
 pub mod util;
 mod vault;
@@ -94,7 +95,7 @@ use markdown::MarkdownCommand;
 use message::MessageCommand;
 use miette::GraphicalReportHandler;
 use node::NodeCommand;
-use ockam_api::cli_state::CliState;
+use ockam_api::cli_state::{set_passphrase_prompt, CliState};
 use ockam_core::env::get_env_with_default;
 use once_cell::sync::Lazy;
 use policy::PolicyCommand;
@@ -113,6 +114,7 @@ use tcp::{
     outlet::TcpOutletCommand,
 };
 use trust_context::TrustContextCommand;
+use udp::{inlet::UdpInletCommand, outlet::UdpOutletCommand};
 use upgrade::check_if_an_upgrade_is_available;
 use util::{exitcode, exitcode::ExitCode};
 use vault::VaultCommand;
@@ -193,6 +195,11 @@ pub struct GlobalArgs {
     // but the command is not executed.
     #[arg(global = true, long, hide = true)]
     test_argument_parser: bool,
+
+    // if vault_passphrases_stdin is true, the passphrases of the encrypted vaults
+    // are read from the standard input. This is used when a node is started by another process
+    #[arg(global = true, long, hide = true)]
+    vault_passphrases_stdin: bool,
 }
 
 fn quiet_default_value() -> bool {
@@ -217,6 +224,7 @@ impl Default for GlobalArgs {
             no_input: no_input_default_value(),
             output_format: OutputFormat::Plain,
             test_argument_parser: false,
+            vault_passphrases_stdin: false,
         }
     }
 }
@@ -255,6 +263,24 @@ impl CommandGlobalOpts {
             global_args.no_input,
             global_args.output_format.clone(),
         );
+        if global_args.vault_passphrases_stdin {
+            let mut passphrases = String::new();
+            let read = std::io::stdin()
+                .read_line(&mut passphrases)
+                .map_err(|e| e.to_string())
+                .and_then(|_| {
+                    state
+                        .vaults
+                        .passphrases()
+                        .import(&passphrases)
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = read {
+                eprintln!("Failed to read the vault passphrases: {e}");
+            }
+        } else if terminal.can_ask_for_user_input() {
+            set_passphrase_prompt(vault::prompt_vault_passphrase);
+        }
         Self {
             global_args,
             state,
@@ -319,6 +345,9 @@ pub enum OckamSubcommand {
     TcpOutlet(TcpOutletCommand),
     TcpInlet(TcpInletCommand),
 
+    UdpOutlet(UdpOutletCommand),
+    UdpInlet(UdpInletCommand),
+
     KafkaOutlet(KafkaOutletCommand),
     KafkaConsumer(KafkaConsumerCommand),
     KafkaDirect(KafkaDirectCommand),
@@ -442,6 +471,8 @@ impl OckamCommand {
             OckamSubcommand::TcpConnection(c) => c.run(options),
             OckamSubcommand::TcpOutlet(c) => c.run(options),
             OckamSubcommand::TcpInlet(c) => c.run(options),
+            OckamSubcommand::UdpOutlet(c) => c.run(options),
+            OckamSubcommand::UdpInlet(c) => c.run(options),
 
             OckamSubcommand::KafkaConsumer(c) => c.run(options),
             OckamSubcommand::KafkaProducer(c) => c.run(options),
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, process, str::FromStr};
+use std::{path::PathBuf, process, str::FromStr, sync::Arc};
 
 use clap::Args;
 use colorful::Colorful;
@@ -10,10 +10,11 @@ use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 use tokio::try_join;
 
-use ockam::{Address, AsyncTryClone, TcpListenerOptions};
+use ockam::{Address, AsyncTryClone, NodeBuilder, TcpListenerOptions};
 use ockam::{Context, TcpTransport};
 use ockam_api::cli_state::traits::{StateDirTrait, StateItemTrait};
 use ockam_api::cli_state::{add_project_info_to_node_state, init_node_state, random_name};
+use ockam_api::metrics::MetricsServer;
 use ockam_api::nodes::models::transport::CreateTransportJson;
 use ockam_api::nodes::service::NodeManagerTrustOptions;
 use ockam_api::{
@@ -26,6 +27,7 @@ use ockam_api::{
 };
 use ockam_core::api::{RequestBuilder, Response, Status};
 use ockam_core::{route, LOCAL};
+use ockam_node::metrics::MetricsRegistry;
 
 use crate::node::util::spawn_node;
 use crate::secure_channel::listener::create as secure_channel_listener;
@@ -33,7 +35,7 @@ use crate::service::config::Config;
 use crate::terminal::OckamColor;
 use crate::util::api::TrustContextOpts;
 use crate::util::{api, parse_node_name, Rpc};
-use crate::util::{embedded_node_that_is_not_stopped, exitcode};
+use crate::util::{embedded_node_that_is_not_stopped_with_builder, exitcode};
 use crate::util::{local_cmd, node_rpc};
 use crate::{docs, shutdown, CommandGlobalOpts, Result};
 use crate::{fmt_log, fmt_ok};
@@ -103,6 +105,10 @@ pub struct CreateCommand {
     #[arg(long = "credential", value_name = "CREDENTIAL_NAME")]
     pub credential: Option<String>,
 
+    /// Serve the node metrics in the OpenMetrics text format at http://<SOCKET_ADDRESS>/metrics
+    #[arg(long, value_name = "SOCKET_ADDRESS")]
+    pub metrics_address: Option<String>,
+
     #[command(flatten)]
     pub trust_context_opts: TrustContextOpts,
 }
@@ -123,6 +129,7 @@ impl Default for CreateCommand {
             reload_from_trusted_identities_file: None,
             authority_identity: None,
             credential: None,
+            metrics_address: None,
             trust_context_opts: TrustContextOpts::default(),
         }
     }
@@ -240,13 +247,29 @@ pub(crate) async fn background_mode(
 
 // Create a new node in the foreground (i.e. in this OS process)
 fn foreground_mode(opts: CommandGlobalOpts, cmd: CreateCommand) -> miette::Result<()> {
-    embedded_node_that_is_not_stopped(run_foreground_node, (opts, cmd))?;
+    let mut builder = NodeBuilder::new().no_logging();
+    let metrics = cmd
+        .metrics_address
+        .as_ref()
+        .map(|_| MetricsRegistry::create());
+    if let Some(metrics) = &metrics {
+        builder = builder.with_metrics_sink(metrics.clone());
+    }
+    embedded_node_that_is_not_stopped_with_builder(
+        builder,
+        run_foreground_node,
+        (opts, cmd, metrics),
+    )?;
     Ok(())
 }
 
 async fn run_foreground_node(
     mut ctx: Context,
-    (opts, cmd): (CommandGlobalOpts, CreateCommand),
+    (opts, cmd, metrics): (
+        CommandGlobalOpts,
+        CreateCommand,
+        Option<Arc<MetricsRegistry>>,
+    ),
 ) -> miette::Result<()> {
     let node_name = parse_node_name(&cmd.node_name)?;
 
@@ -283,6 +306,14 @@ async fn run_foreground_node(
         .await
         .into_diagnostic()?;
 
+    // Keep the metrics server alive as long as the node is running
+    let metrics_server = match (&cmd.metrics_address, metrics) {
+        (Some(address), Some(metrics)) => {
+            Some(MetricsServer::start(address, metrics).into_diagnostic()?)
+        }
+        _ => None,
+    };
+
     let node_state = opts.state.nodes.get(&node_name)?;
     node_state.set_pid(process::id() as i32)?;
     node_state.set_setup(
@@ -290,6 +321,11 @@ async fn run_foreground_node(
             .config()
             .setup_mut()
             .set_verbose(opts.global_args.verbose)
+            .set_metrics_address(
+                metrics_server
+                    .as_ref()
+                    .map(|server| server.address().to_string()),
+            )
             .set_api_transport(
                 CreateTransportJson::new(
                     TransportType::Tcp,
@@ -470,6 +506,7 @@ pub async fn spawn_background_node(
         cmd.credential.as_ref(),
         trust_context_path.as_ref(),
         cmd.trust_context_opts.project.as_ref(),
+        cmd.metrics_address.as_ref(),
         cmd.logging_to_file(),
     )?;
 
@@ -72,6 +72,7 @@ async fn run_impl(
         None,                                          // Credential
         None,                                          // Trust Context
         None,                                          // Project Name
+        node_setup.metrics_address.as_ref(),           // The selected node metrics address
         true,                                          // Restarted nodes will log to files
     )?;
 
@@ -4,7 +4,7 @@ use miette::{miette, IntoDiagnostic};
 use ockam::{Context, TcpListenerOptions, TcpTransport};
 
 use ockam_api::cli_state::{
-    add_project_info_to_node_state, init_node_state, CliState, StateDirTrait,
+    add_project_info_to_node_state, init_node_state, CliState, StateDirTrait, StateItemTrait,
 };
 use ockam_api::nodes::service::{
     NodeManagerGeneralOptions, NodeManagerTransportOptions, NodeManagerTrustOptions,
@@ -14,6 +14,7 @@ use ockam_api::nodes::{NodeManager, NodeManagerWorker, NODEMANAGER_ADDR};
 use ockam_core::env::get_env_with_default;
 use std::env::current_exe;
 use std::fs::OpenOptions;
+use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
@@ -138,6 +139,7 @@ pub fn spawn_node(
     credential: Option<&String>,
     trust_context: Option<&PathBuf>,
     project_name: Option<&String>,
+    metrics_address: Option<&String>,
     logging_to_file: bool,
 ) -> miette::Result<()> {
     let mut args = vec![
@@ -214,6 +216,11 @@ pub fn spawn_node(
         args.push(project_name.to_string());
     }
 
+    if let Some(metrics_address) = metrics_address {
+        args.push("--metrics-address".to_string());
+        args.push(metrics_address.to_string());
+    }
+
     args.push(name.to_owned());
 
     run_ockam(opts, name, args, logging_to_file)
@@ -252,13 +259,31 @@ pub fn run_ockam(
         cmd.stdout(main_log_file).stderr(stderr_log_file);
     }
 
-    let child = cmd
+    // Unlock the vault of the node, and send the passphrases known by this process
+    // to the node on its standard input, since the node can't ask for them
+    opts.state
+        .vaults
+        .load_vault(node_state.config().vault_path()?)?
+        .unlock()?;
+    let passphrases = opts.state.vaults.passphrases();
+    if passphrases.is_empty() {
+        cmd.stdin(Stdio::null());
+    } else {
+        cmd.arg("--vault-passphrases-stdin").stdin(Stdio::piped());
+    }
+
+    let mut child = cmd
         .args(args)
-        .stdin(Stdio::null())
         .spawn()
         .into_diagnostic()
         .context("failed to spawn node")?;
 
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", passphrases.export()?)
+            .into_diagnostic()
+            .context("failed to send the vault passphrases to the node")?;
+    }
+
     node_state.set_pid(child.id() as i32)?;
 
     Ok(())
@@ -15,10 +15,11 @@ use ockam::identity::models::{
 use ockam::identity::{Credential, Identifier, Identity, TimestampInSeconds};
 use serde::{Serialize, Serializer};
 
+use ockam_api::authenticator::enrollment_tokens::types::Token;
 use ockam_api::cli_state::{ProjectConfigCompact, StateItemTrait, VaultState};
 use ockam_api::cloud::project::Project;
 use ockam_api::cloud::space::Space;
-use ockam_api::nodes::models::portal::{InletStatus, OutletStatus};
+use ockam_api::nodes::models::portal::{InletStatus, OutletStatus, PortalLimitsStatus};
 use ockam_api::nodes::models::secure_channel::{
     CreateSecureChannelResponse, ShowSecureChannelResponse,
 };
@@ -270,7 +271,7 @@ impl Output for ShowSecureChannelResponse {
 
 impl Output for OutletStatus {
     fn output(&self) -> Result<String> {
-        let output = format!(
+        let mut output = format!(
             r#"
 Outlet {}:
     TCP Address:    {}
@@ -281,6 +282,34 @@ Outlet {}:
             self.worker_address()?
         );
 
+        if let Some(targets) = &self.targets {
+            writeln!(output, "    Targets:")?;
+            for target in targets {
+                let address = target
+                    .address
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| "unresolved".to_string());
+                let health = if target.healthy {
+                    "healthy"
+                } else {
+                    "unhealthy"
+                };
+                writeln!(
+                    output,
+                    "        {} ({}): {}, {} active connection(s), {} failed connection(s)",
+                    target.target,
+                    address,
+                    health,
+                    target.active_connections,
+                    target.failed_connections
+                )?;
+            }
+        }
+
+        if let Some(limits) = &self.limits {
+            write_portal_limits(&mut output, limits)?;
+        }
+
         Ok(output)
     }
 
@@ -309,6 +338,43 @@ impl Output for Vec<u8> {
     }
 }
 
+/// Write the limits of a portal, and how many connections they affected
+pub(crate) fn write_portal_limits(output: &mut String, limits: &PortalLimitsStatus) -> Result<()> {
+    let unlimited = || "unlimited".to_string();
+    writeln!(output, "    Limits:")?;
+    writeln!(
+        output,
+        "        Connections: {} active, max {}, {} rejected",
+        limits.active_connections,
+        limits
+            .max_connections
+            .map(|n| n.to_string())
+            .unwrap_or_else(unlimited),
+        limits.rejected_connections
+    )?;
+    writeln!(
+        output,
+        "        Bandwidth: {} per connection, {} in total, {} throttled transfer(s)",
+        limits
+            .connection_bytes_per_second
+            .map(|n| format!("{n} B/s"))
+            .unwrap_or_else(unlimited),
+        limits
+            .aggregate_bytes_per_second
+            .map(|n| format!("{n} B/s"))
+            .unwrap_or_else(unlimited),
+        limits.throttled_transfers
+    )?;
+    if let Some(idle_timeout) = limits.idle_timeout {
+        writeln!(
+            output,
+            "        Idle timeout: {:?}, {} idle connection(s) closed",
+            idle_timeout, limits.idle_disconnections
+        )?;
+    }
+    Ok(())
+}
+
 impl Output for InletStatus {
     fn output(&self) -> Result<String> {
         let outlet = if let Some(r) = Route::parse(&self.outlet_route) {
@@ -321,12 +387,12 @@ impl Output for InletStatus {
             self.outlet_route.to_string()
         };
 
-        let output = format!(
+        let mut output = format!(
             r#"
 Inlet {}
     TCP Address: {}
     Outlet Address: {}
-            "#,
+"#,
             self.alias
                 .to_string()
                 .color(OckamColor::PrimaryResource.color()),
@@ -336,6 +402,10 @@ Inlet {}
             outlet.color(OckamColor::PrimaryResource.color())
         );
 
+        if let Some(limits) = &self.limits {
+            write_portal_limits(&mut output, limits)?;
+        }
+
         Ok(output)
     }
 
@@ -394,6 +464,68 @@ impl Output for VaultState {
     }
 }
 
+impl Output for Token {
+    fn output(&self) -> Result<String> {
+        let mut output = String::new();
+        writeln!(output, "Enrollment token {}", self.id())?;
+        writeln!(output, "Created by: {}", self.generated_by())?;
+        writeln!(
+            output,
+            "Created at: {}",
+            human_readable_time(self.created_at())
+        )?;
+        writeln!(
+            output,
+            "Expires at: {}",
+            human_readable_time(self.expires_at())
+        )?;
+        writeln!(
+            output,
+            "Usage: {}/{}",
+            self.usage_count(),
+            self.max_usage_count()
+        )?;
+        write!(output, "Attributes: {}", token_attributes(self))?;
+        Ok(output)
+    }
+
+    fn list_output(&self) -> Result<String> {
+        let mut output = String::new();
+        writeln!(
+            output,
+            "Enrollment token {}",
+            self.id().color(OckamColor::PrimaryResource.color())
+        )?;
+        writeln!(
+            output,
+            "Used {} out of {} times, expires at {}",
+            self.usage_count()
+                .to_string()
+                .color(OckamColor::PrimaryResource.color()),
+            self.max_usage_count()
+                .to_string()
+                .color(OckamColor::PrimaryResource.color()),
+            human_readable_time(self.expires_at()).color(OckamColor::PrimaryResource.color())
+        )?;
+        write!(
+            output,
+            "Attributes {}",
+            token_attributes(self).color(OckamColor::PrimaryResource.color())
+        )?;
+        Ok(output)
+    }
+}
+
+fn token_attributes(token: &Token) -> String {
+    let mut attributes: Vec<String> = token
+        .attributes()
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect();
+    attributes.sort();
+    comma_separated(&attributes)
+}
+
 fn human_readable_time(time: TimestampInSeconds) -> String {
     use time::format_description::well_known::iso8601::*;
     use time::Error::Format;
@@ -628,6 +760,11 @@ impl fmt::Display for CredentialAndPurposeKeyDisplay {
             PurposeKeyDisplay(self.0.purpose_key_attestation.clone())
         )?;
 
+        if !self.0.delegations().is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Delegations: {}", self.0.delegations().len())?;
+        }
+
         Ok(())
     }
 }
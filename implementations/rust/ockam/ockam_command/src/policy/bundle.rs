@@ -0,0 +1,119 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use miette::{miette, Context as _, IntoDiagnostic};
+use serde::{Deserialize, Serialize};
+
+use ockam_abac::{Action, BundledPolicy, Expr, PolicyBundle, Resource};
+
+/// Policy bundle as written in a file, with the expressions in their textual form
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct PolicyBundleFile {
+    version: u64,
+    #[serde(default)]
+    policies: Vec<PolicyFileEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PolicyFileEntry {
+    resource: String,
+    #[serde(default = "default_action")]
+    action: String,
+    expression: String,
+}
+
+fn default_action() -> String {
+    "handle_message".to_string()
+}
+
+impl PolicyBundleFile {
+    /// Read a bundle from a YAML or JSON file
+    pub(crate) fn read(path: &Path) -> miette::Result<PolicyBundle> {
+        let contents = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err(format!(
+                "Failed to read the policy bundle {}",
+                path.display()
+            ))?;
+        let file: PolicyBundleFile = serde_yaml::from_str(&contents)
+            .into_diagnostic()
+            .wrap_err(format!("Invalid policy bundle {}", path.display()))?;
+        file.try_into()
+    }
+
+    /// Return a bundle in YAML
+    pub(crate) fn to_yaml(bundle: &PolicyBundle) -> miette::Result<String> {
+        serde_yaml::to_string(&PolicyBundleFile::from(bundle)).into_diagnostic()
+    }
+}
+
+impl From<&PolicyBundle> for PolicyBundleFile {
+    fn from(bundle: &PolicyBundle) -> Self {
+        Self {
+            version: bundle.version(),
+            policies: bundle
+                .policies()
+                .iter()
+                .map(|p| PolicyFileEntry {
+                    resource: p.resource().to_string(),
+                    action: p.action().to_string(),
+                    expression: p.expression().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<PolicyBundleFile> for PolicyBundle {
+    type Error = miette::Report;
+
+    fn try_from(file: PolicyBundleFile) -> miette::Result<Self> {
+        let policies = file
+            .policies
+            .into_iter()
+            .map(|p| {
+                let expression = Expr::from_str(&p.expression).map_err(|e| {
+                    miette!(
+                        "Invalid expression for the resource {} and action {}: {e}",
+                        p.resource,
+                        p.action
+                    )
+                })?;
+                Ok(BundledPolicy::new(
+                    Resource::from(p.resource),
+                    Action::from(p.action),
+                    expression,
+                ))
+            })
+            .collect::<miette::Result<Vec<_>>>()?;
+        Ok(PolicyBundle::new(file.version, policies))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_bundle_file() {
+        let yaml = r#"
+version: 2
+policies:
+  - resource: tcp-outlet
+    expression: (= subject.component "db")
+  - resource: relay
+    action: claim
+    expression: (= subject.role "admin")
+"#;
+        let file: PolicyBundleFile = serde_yaml::from_str(yaml).unwrap();
+        let bundle: PolicyBundle = file.try_into().unwrap();
+        assert_eq!(bundle.version(), 2);
+        assert_eq!(bundle.policies().len(), 2);
+        assert_eq!(bundle.policies()[1].action().as_str(), "handle_message");
+
+        let exported = PolicyBundleFile::to_yaml(&bundle).unwrap();
+        let file: PolicyBundleFile = serde_yaml::from_str(&exported).unwrap();
+        let reimported: PolicyBundle = file.try_into().unwrap();
+        assert!(bundle.diff(&reimported).is_empty());
+    }
+}
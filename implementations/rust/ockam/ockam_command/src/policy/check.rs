@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+use clap::Args;
+
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_abac::{Action, Explanation, Resource};
+use ockam_api::address::extract_address_value;
+use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::models::policy::CheckPolicy;
+use ockam_core::api::Request;
+
+use crate::policy::policy_path;
+use crate::util::{node_rpc, Rpc};
+use crate::CommandGlobalOpts;
+
+/// Evaluate the policy of a resource and action for an identity, without enforcing it,
+/// and explain the decision
+#[derive(Clone, Debug, Args)]
+pub struct CheckCommand {
+    #[arg(long, display_order = 900, id = "NODE_NAME")]
+    at: String,
+
+    #[arg(short, long)]
+    resource: Resource,
+
+    #[arg(short, long)]
+    action: Action,
+
+    /// Identifier of the identity, or name of a local identity
+    #[arg(short, long)]
+    identity: String,
+}
+
+impl CheckCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(rpc, (options, self));
+    }
+}
+
+async fn rpc(
+    mut ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, CheckCommand),
+) -> miette::Result<()> {
+    run_impl(&mut ctx, opts, cmd).await
+}
+
+async fn run_impl(
+    ctx: &mut Context,
+    opts: CommandGlobalOpts,
+    cmd: CheckCommand,
+) -> miette::Result<()> {
+    let node = extract_address_value(&cmd.at)?;
+    let identifier = match Identifier::from_str(&cmd.identity) {
+        Ok(identifier) => identifier,
+        Err(_) => opts.state.identities.get(&cmd.identity)?.identifier(),
+    };
+    let path = format!("{}/check", policy_path(&cmd.resource, &cmd.action));
+    let req = Request::post(path).body(CheckPolicy::new(identifier));
+    let mut rpc = Rpc::background(ctx, &opts, &node).await?;
+    let explanation: Explanation = rpc.ask(req).await?;
+    let decision = if explanation.is_authorized() {
+        "allow"
+    } else {
+        "deny"
+    };
+    opts.terminal
+        .stdout()
+        .plain(explanation.to_string())
+        .machine(decision)
+        .write_line()?;
+    Ok(())
+}
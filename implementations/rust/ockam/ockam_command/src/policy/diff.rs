@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_abac::PolicyBundle;
+use ockam_core::api::Request;
+
+use crate::node::get_node_name;
+use crate::policy::bundle::PolicyBundleFile;
+use crate::util::{node_rpc, parse_node_name, Rpc};
+use crate::{fmt_ok, CommandGlobalOpts};
+
+/// Show the changes that importing a policy bundle would make to the policies of a node
+#[derive(Clone, Debug, Args)]
+pub struct DiffCommand {
+    #[arg(long, display_order = 900, id = "NODE_NAME")]
+    at: Option<String>,
+
+    /// Path of the policy bundle, in YAML or JSON
+    file: PathBuf,
+}
+
+impl DiffCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(rpc, (options, self));
+    }
+}
+
+async fn rpc(
+    mut ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, DiffCommand),
+) -> miette::Result<()> {
+    run_impl(&mut ctx, opts, cmd).await
+}
+
+async fn run_impl(
+    ctx: &mut Context,
+    opts: CommandGlobalOpts,
+    cmd: DiffCommand,
+) -> miette::Result<()> {
+    let bundle = PolicyBundleFile::read(&cmd.file)?;
+
+    let at = get_node_name(&opts.state, &cmd.at);
+    let node_name = parse_node_name(&at)?;
+    let mut rpc = Rpc::background(ctx, &opts, &node_name).await?;
+    let current: PolicyBundle = rpc.ask(Request::get("/policy_bundle")).await?;
+
+    let changes = current.diff(&bundle);
+    let plain = if changes.is_empty() {
+        fmt_ok!(
+            "The node {node_name} enforces the policies of the bundle (node version {}, bundle version {})",
+            current.version(),
+            bundle.version()
+        )
+    } else {
+        let mut plain = format!(
+            "node version {}, bundle version {}\n",
+            current.version(),
+            bundle.version()
+        );
+        for change in &changes {
+            plain.push_str(&format!("{change}\n"));
+        }
+        plain.trim_end().to_string()
+    };
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .machine(changes.len())
+        .write_line()?;
+    Ok(())
+}
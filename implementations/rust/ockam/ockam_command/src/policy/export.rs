@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_abac::PolicyBundle;
+use ockam_core::api::Request;
+
+use crate::node::get_node_name;
+use crate::policy::bundle::PolicyBundleFile;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name, Rpc};
+use crate::{fmt_ok, CommandGlobalOpts};
+
+/// Export all the policies of a node as a policy bundle
+#[derive(Clone, Debug, Args)]
+pub struct ExportCommand {
+    #[arg(long, display_order = 900, id = "NODE_NAME")]
+    at: Option<String>,
+
+    /// Write the bundle to this file instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl ExportCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(rpc, (options, self));
+    }
+}
+
+async fn rpc(
+    mut ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, ExportCommand),
+) -> miette::Result<()> {
+    run_impl(&mut ctx, opts, cmd).await
+}
+
+async fn run_impl(
+    ctx: &mut Context,
+    opts: CommandGlobalOpts,
+    cmd: ExportCommand,
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.at);
+    let node_name = parse_node_name(&at)?;
+    let mut rpc = Rpc::background(ctx, &opts, &node_name).await?;
+    let bundle: PolicyBundle = rpc.ask(Request::get("/policy_bundle")).await?;
+    let yaml = PolicyBundleFile::to_yaml(&bundle)?;
+
+    match cmd.output {
+        Some(path) => {
+            std::fs::write(&path, yaml).into_diagnostic()?;
+            opts.terminal
+                .stdout()
+                .plain(fmt_ok!(
+                    "Exported the policies of the node {} to {}",
+                    node_name.color(OckamColor::PrimaryResource.color()),
+                    path.display()
+                ))
+                .write_line()?;
+        }
+        None => {
+            opts.terminal.stdout().plain(yaml.trim_end()).write_line()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_abac::SignedPolicyBundle;
+use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
+use ockam_core::api::Request;
+
+use crate::identity::{get_identity_name, initialize_identity_if_default};
+use crate::node::get_node_name;
+use crate::policy::bundle::PolicyBundleFile;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name, Rpc};
+use crate::vault::default_vault_name;
+use crate::{fmt_ok, CommandGlobalOpts};
+
+/// Replace all the policies of a node with the policies of a bundle.
+///
+/// The bundle is signed for this node with an identity, which must be the identity of the node
+/// or an authority of its trust context. Its version must be greater than the versions
+/// of the bundles imported before
+#[derive(Clone, Debug, Args)]
+pub struct ImportCommand {
+    #[arg(long, display_order = 900, id = "NODE_NAME")]
+    at: Option<String>,
+
+    /// Path of the policy bundle, in YAML or JSON
+    file: PathBuf,
+
+    /// Name of the identity signing the bundle
+    #[arg(long = "as")]
+    as_identity: Option<String>,
+
+    /// Name of the vault of the signing identity
+    #[arg(long)]
+    vault: Option<String>,
+}
+
+impl ImportCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        initialize_identity_if_default(&options, &self.as_identity);
+        node_rpc(rpc, (options, self));
+    }
+}
+
+async fn rpc(
+    mut ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, ImportCommand),
+) -> miette::Result<()> {
+    run_impl(&mut ctx, opts, cmd).await
+}
+
+async fn run_impl(
+    ctx: &mut Context,
+    opts: CommandGlobalOpts,
+    cmd: ImportCommand,
+) -> miette::Result<()> {
+    let bundle = PolicyBundleFile::read(&cmd.file)?;
+
+    let identity_name = get_identity_name(&opts.state, &cmd.as_identity);
+    let signer = opts.state.identities.get(&identity_name)?.identifier();
+    let vault_name = cmd
+        .vault
+        .clone()
+        .unwrap_or_else(|| default_vault_name(&opts.state));
+    let vault = opts.state.vaults.get(&vault_name)?.get().await?;
+    let identities = opts.state.get_identities(vault).await?;
+
+    let at = get_node_name(&opts.state, &cmd.at);
+    let node_name = parse_node_name(&at)?;
+    let target = opts.state.nodes.get(&node_name)?.config().identifier()?;
+    let signed = SignedPolicyBundle::sign(&identities, &signer, &target, &bundle)
+        .await
+        .into_diagnostic()?;
+
+    let mut rpc = Rpc::background(ctx, &opts, &node_name).await?;
+    rpc.tell(Request::post("/policy_bundle").body(signed))
+        .await?;
+
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "Applied the version {} of the policy bundle to the node {}",
+            bundle.version(),
+            node_name.color(OckamColor::PrimaryResource.color())
+        ))
+        .machine(bundle.version())
+        .write_line()?;
+    Ok(())
+}
@@ -7,15 +7,26 @@ use ockam_api::nodes::models::policy::PolicyList;
 use ockam_api::{config::lookup::ProjectLookup, nodes::models::policy::Policy};
 use ockam_core::api::Request;
 
+use crate::policy::check::CheckCommand;
 use crate::policy::delete::DeleteCommand;
+use crate::policy::diff::DiffCommand;
+use crate::policy::export::ExportCommand;
+use crate::policy::import::ImportCommand;
 use crate::policy::list::ListCommand;
+use crate::policy::rollback::RollbackCommand;
 use crate::policy::show::ShowCommand;
 use crate::{policy::create::CreateCommand, util::Rpc};
 use crate::{CommandGlobalOpts, Result};
 
+mod bundle;
+mod check;
 mod create;
 mod delete;
+mod diff;
+mod export;
+mod import;
 mod list;
+mod rollback;
 mod show;
 
 #[derive(Clone, Debug, Args)]
@@ -31,6 +42,11 @@ pub enum PolicySubcommand {
     Show(ShowCommand),
     Delete(DeleteCommand),
     List(ListCommand),
+    Check(CheckCommand),
+    Export(ExportCommand),
+    Import(ImportCommand),
+    Diff(DiffCommand),
+    Rollback(RollbackCommand),
 }
 
 impl PolicyCommand {
@@ -40,6 +56,11 @@ impl PolicyCommand {
             PolicySubcommand::Show(c) => c.run(opts),
             PolicySubcommand::Delete(c) => c.run(opts),
             PolicySubcommand::List(c) => c.run(opts),
+            PolicySubcommand::Check(c) => c.run(opts),
+            PolicySubcommand::Export(c) => c.run(opts),
+            PolicySubcommand::Import(c) => c.run(opts),
+            PolicySubcommand::Diff(c) => c.run(opts),
+            PolicySubcommand::Rollback(c) => c.run(opts),
         }
     }
 }
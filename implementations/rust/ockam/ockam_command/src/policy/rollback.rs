@@ -0,0 +1,60 @@
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_abac::PolicyBundle;
+use ockam_api::nodes::models::policy::RollbackPolicyBundle;
+use ockam_core::api::Request;
+
+use crate::node::get_node_name;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name, Rpc};
+use crate::{fmt_ok, CommandGlobalOpts};
+
+/// Restore the policies of a policy bundle imported before
+#[derive(Clone, Debug, Args)]
+pub struct RollbackCommand {
+    #[arg(long, display_order = 900, id = "NODE_NAME")]
+    at: Option<String>,
+
+    /// Version of the bundle to restore. By default, the version preceding the current one.
+    /// The policies set before the first import are restored with the version 0
+    #[arg(long)]
+    version: Option<u64>,
+}
+
+impl RollbackCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        node_rpc(rpc, (options, self));
+    }
+}
+
+async fn rpc(
+    mut ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, RollbackCommand),
+) -> miette::Result<()> {
+    run_impl(&mut ctx, opts, cmd).await
+}
+
+async fn run_impl(
+    ctx: &mut Context,
+    opts: CommandGlobalOpts,
+    cmd: RollbackCommand,
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.at);
+    let node_name = parse_node_name(&at)?;
+    let mut rpc = Rpc::background(ctx, &opts, &node_name).await?;
+    let req = Request::post("/policy_bundle/rollback").body(RollbackPolicyBundle::new(cmd.version));
+    let bundle: PolicyBundle = rpc.ask(req).await?;
+
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "Restored the version {} of the policy bundle on the node {}",
+            bundle.version(),
+            node_name.color(OckamColor::PrimaryResource.color())
+        ))
+        .machine(bundle.version())
+        .write_line()?;
+    Ok(())
+}
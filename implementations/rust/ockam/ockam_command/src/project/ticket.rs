@@ -1,5 +1,6 @@
 use crate::util::duration::duration_parser;
-use clap::Args;
+use clap::{Args, Subcommand};
+use colorful::Colorful;
 use ockam_api::cloud::ORCHESTRATOR_RESTART_TIMEOUT;
 use ockam_api::config::cli::TrustContextConfig;
 use ockam_api::identity::EnrollmentTicket;
@@ -23,7 +24,7 @@ use crate::node::util::delete_embedded_node;
 use crate::project::util::create_secure_channel_to_authority;
 use crate::util::api::{CloudOpts, TrustContextOpts};
 use crate::util::{node_rpc, Rpc};
-use crate::{docs, CommandGlobalOpts, Result};
+use crate::{docs, fmt_ok, CommandGlobalOpts, Result};
 
 const LONG_ABOUT: &str = include_str!("./static/ticket/long_about.txt");
 const AFTER_LONG_HELP: &str = include_str!("./static/ticket/after_long_help.txt");
@@ -45,7 +46,7 @@ pub struct TicketCommand {
     #[arg(long, short, conflicts_with = "expires_in")]
     member: Option<Identifier>,
 
-    #[arg(long, short, default_value = "/project/default")]
+    #[arg(long, short, global = true, default_value = "/project/default")]
     to: MultiAddr,
 
     /// Attributes in `key=value` format to be attached to the member
@@ -54,6 +55,24 @@ pub struct TicketCommand {
 
     #[arg(long = "expires-in", value_name = "DURATION", conflicts_with = "member", value_parser=duration_parser)]
     expires_in: Option<Duration>,
+
+    /// Number of times the ticket can be used to enroll a node, it can be used once by default
+    #[arg(long = "usage-count", value_name = "COUNT", conflicts_with = "member", value_parser = clap::value_parser!(u64).range(1..))]
+    usage_count: Option<u64>,
+
+    #[command(subcommand)]
+    subcommand: Option<TicketSubcommand>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum TicketSubcommand {
+    /// List the enrollment tickets created by this identity which can still be used
+    List,
+    /// Revoke an enrollment ticket created by this identity, so that it can not be used anymore
+    Revoke {
+        /// Id of the enrollment ticket, as displayed by `ockam project ticket list`
+        id: String,
+    },
 }
 
 impl TicketCommand {
@@ -91,7 +110,90 @@ impl Runner {
     async fn run(self) -> miette::Result<()> {
         let mut rpc =
             Rpc::embedded_with_trust_options(&self.ctx, &self.opts, &self.cmd.trust_opts).await?;
+        let (base_addr, project, trust_context) = self.authority_addr(&mut rpc).await?;
+
+        match &self.cmd.subcommand {
+            Some(TicketSubcommand::List) => {
+                let tokens = self
+                    .token_issuer_client(&base_addr)
+                    .await?
+                    .list_tokens()
+                    .await
+                    .into_diagnostic()?;
+                let plain = self.opts.terminal.build_list(
+                    &tokens,
+                    "Enrollment tickets",
+                    "No enrollment tickets found for this project.",
+                )?;
+                let json = serde_json::to_string_pretty(&tokens).into_diagnostic()?;
+                self.opts
+                    .terminal
+                    .clone()
+                    .stdout()
+                    .plain(plain)
+                    .json(json)
+                    .write_line()?;
+            }
+            Some(TicketSubcommand::Revoke { id }) => {
+                self.token_issuer_client(&base_addr)
+                    .await?
+                    .revoke_token(id)
+                    .await
+                    .into_diagnostic()?;
+                self.opts
+                    .terminal
+                    .clone()
+                    .stdout()
+                    .plain(fmt_ok!("The enrollment ticket {} was revoked", id))
+                    .machine(id)
+                    .write_line()?;
+            }
+            // If an identity identifier is given add it as a member, otherwise
+            // request an enrollment token that future members can use to get a
+            // credential.
+            None => {
+                if let Some(id) = &self.cmd.member {
+                    let client = DirectAuthenticatorClient::new(
+                        self.rpc_client(&base_addr, DefaultAddress::DIRECT_AUTHENTICATOR)
+                            .await?,
+                    );
+                    client
+                        .add_member(id.clone(), self.cmd.attributes()?)
+                        .await
+                        .into_diagnostic()?
+                } else {
+                    let token = self
+                        .token_issuer_client(&base_addr)
+                        .await?
+                        .create_token(
+                            self.cmd.attributes()?,
+                            self.cmd.expires_in,
+                            self.cmd.usage_count,
+                        )
+                        .await
+                        .into_diagnostic()?;
+
+                    let ticket = EnrollmentTicket::new(token, project, trust_context);
+                    let ticket_serialized = ticket.hex_encoded().into_diagnostic()?;
+                    self.opts
+                        .terminal
+                        .clone()
+                        .stdout()
+                        .machine(ticket_serialized)
+                        .write_line()?;
+                }
+            }
+        }
+
+        delete_embedded_node(&self.opts, rpc.node_name()).await;
+        Ok(())
+    }
 
+    /// Return the address of the project authority, reached with a secure channel
+    async fn authority_addr(
+        &self,
+        rpc: &mut Rpc,
+    ) -> miette::Result<(MultiAddr, Option<ProjectLookup>, Option<TrustContextConfig>)> {
         let mut project: Option<ProjectLookup> = None;
         let mut trust_context: Option<TrustContextConfig> = None;
 
@@ -115,7 +217,7 @@ impl Runner {
             };
             let identity = get_identity_name(&self.opts.state, &self.cmd.cloud_opts.identity);
             create_secure_channel_to_authority(
-                &mut rpc,
+                rpc,
                 tc.authority()
                     .into_diagnostic()?
                     .identity()
@@ -130,7 +232,7 @@ impl Runner {
         } else if let (Some(p), Some(a)) = get_project(&self.opts.state, &self.cmd.to).await? {
             let identity = get_identity_name(&self.opts.state, &self.cmd.cloud_opts.identity);
             let sc_addr = create_secure_channel_to_authority(
-                &mut rpc,
+                rpc,
                 a.identity_id().clone(),
                 a.address(),
                 Some(identity),
@@ -142,74 +244,37 @@ impl Runner {
         } else {
             self.cmd.to.clone()
         };
-        // If an identity identifier is given add it as a member, otherwise
-        // request an enrollment token that a future member can use to get a
-        // credential.
-        if let Some(id) = &self.cmd.member {
-            let direct_authenticator_route = {
-                let service = MultiAddr::try_from(
-                    format!("/service/{}", DefaultAddress::DIRECT_AUTHENTICATOR).as_str(),
-                )
-                .into_diagnostic()?;
-                let mut addr = base_addr.clone();
-                for proto in service.iter() {
-                    addr.push_back_value(&proto).into_diagnostic()?;
-                }
-                ockam_api::local_multiaddr_to_route(&addr)
-                    .ok_or(miette!("Invalid MultiAddr {addr}"))?
-            };
-            let client = DirectAuthenticatorClient::new(
-                RpcClient::new(
-                    route![DefaultAddress::RPC_PROXY, direct_authenticator_route],
-                    &self.ctx,
-                )
-                .await
-                .into_diagnostic()?
-                .with_timeout(Duration::from_secs(ORCHESTRATOR_RESTART_TIMEOUT)),
-            );
-            client
-                .add_member(id.clone(), self.cmd.attributes()?)
-                .await
-                .into_diagnostic()?
-        } else {
-            let token_issuer_route = {
-                let service = MultiAddr::try_from(
-                    format!("/service/{}", DefaultAddress::ENROLLMENT_TOKEN_ISSUER).as_str(),
-                )
-                .into_diagnostic()?;
-                let mut addr = base_addr.clone();
-                for proto in service.iter() {
-                    addr.push_back_value(&proto).into_diagnostic()?;
-                }
-                ockam_api::local_multiaddr_to_route(&addr)
-                    .ok_or(miette!("Invalid MultiAddr {addr}"))?
-            };
-            let client = TokenIssuerClient::new(
-                RpcClient::new(
-                    route![DefaultAddress::RPC_PROXY, token_issuer_route],
-                    &self.ctx,
-                )
+
+        Ok((base_addr, project, trust_context))
+    }
+
+    async fn token_issuer_client(
+        &self,
+        base_addr: &MultiAddr,
+    ) -> miette::Result<TokenIssuerClient> {
+        Ok(TokenIssuerClient::new(
+            self.rpc_client(base_addr, DefaultAddress::ENROLLMENT_TOKEN_ISSUER)
+                .await?,
+        ))
+    }
+
+    /// Create a client for a service of the project authority
+    async fn rpc_client(&self, base_addr: &MultiAddr, service: &str) -> miette::Result<RpcClient> {
+        let service_route = {
+            let service =
+                MultiAddr::try_from(format!("/service/{service}").as_str()).into_diagnostic()?;
+            let mut addr = base_addr.clone();
+            for proto in service.iter() {
+                addr.push_back_value(&proto).into_diagnostic()?;
+            }
+            ockam_api::local_multiaddr_to_route(&addr).ok_or(miette!("Invalid MultiAddr {addr}"))?
+        };
+        Ok(
+            RpcClient::new(route![DefaultAddress::RPC_PROXY, service_route], &self.ctx)
                 .await
                 .into_diagnostic()?
                 .with_timeout(Duration::from_secs(ORCHESTRATOR_RESTART_TIMEOUT)),
-            );
-            let token = client
-                .create_token(self.cmd.attributes()?, self.cmd.expires_in)
-                .await
-                .into_diagnostic()?;
-
-            let ticket = EnrollmentTicket::new(token, project, trust_context);
-            let ticket_serialized = ticket.hex_encoded().into_diagnostic()?;
-            self.opts
-                .terminal
-                .clone()
-                .stdout()
-                .machine(ticket_serialized)
-                .write_line()?;
-        }
-
-        delete_embedded_node(&self.opts, rpc.node_name()).await;
-        Ok(())
+        )
     }
 }
 
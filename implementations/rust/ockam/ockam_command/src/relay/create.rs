@@ -47,6 +47,16 @@ pub struct CreateCommand {
     /// Authorized identity for secure channel connection
     #[arg(long, id = "AUTHORIZED", display_order = 900)]
     authorized: Option<Identifier>,
+
+    /// Register a replica of the relay. Several nodes can register replicas of the same relay,
+    /// new connections are then distributed across the replicas which are still alive
+    #[arg(long, display_order = 900)]
+    replica: bool,
+
+    /// Ask the relay to store the messages sent while the destination node is offline,
+    /// and to deliver them once it is connected again
+    #[arg(long, display_order = 900, conflicts_with = "replica")]
+    store_and_forward: bool,
 }
 
 impl CreateCommand {
@@ -79,6 +89,14 @@ async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> m
     let to = get_node_name(&opts.state, &cmd.to);
     let api_node = extract_address_value(&to)?;
     let at_rust_node = is_local_node(&cmd.at).wrap_err("Argument --at is not valid")?;
+    if cmd.replica && !at_rust_node {
+        return Err(miette!("--replica can only be used with a relay at a node"));
+    }
+    if cmd.store_and_forward && !at_rust_node {
+        return Err(miette!(
+            "--store-and-forward can only be used with a relay at a node"
+        ));
+    }
 
     let ma = process_nodes_multiaddr(&cmd.at, &opts.state)?;
     let alias = if at_rust_node {
@@ -101,6 +119,8 @@ async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> m
                 CreateForwarder::at_project(ma, Some(alias.clone()))
             } else {
                 CreateForwarder::at_node(ma, Some(alias.clone()), at_rust_node, cmd.authorized)
+                    .with_replica(cmd.replica)
+                    .with_store_and_forward(cmd.store_and_forward)
             };
             Request::post("/node/forwarder").body(body)
         };
@@ -1,4 +1,5 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::thread::sleep;
 use std::time::Duration;
@@ -14,8 +15,8 @@ use ockam::identity::Identifier;
 use ockam::Context;
 use ockam_abac::Resource;
 use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
-use ockam_api::nodes::models::portal::CreateInlet;
 use ockam_api::nodes::models::portal::InletStatus;
+use ockam_api::nodes::models::portal::{CreateInlet, InletTls};
 use ockam_core::api::{Reply, Request, Status};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{route, Error};
@@ -24,7 +25,7 @@ use ockam_multiaddr::{MultiAddr, Protocol as _};
 
 use crate::node::{get_node_name, initialize_node_if_default};
 use crate::policy::{add_default_project_policy, has_policy};
-use crate::tcp::util::alias_parser;
+use crate::tcp::util::{alias_parser, PortalLimitsArgs};
 use crate::terminal::OckamColor;
 use crate::util::duration::duration_parser;
 use crate::util::parsers::socket_addr_parser;
@@ -67,6 +68,28 @@ pub struct CreateCommand {
     /// Time to wait before retrying to connect to outlet.
     #[arg(long, display_order = 900, id = "RETRY", default_value = "20s", value_parser = duration_parser)]
     retry_wait: Duration,
+
+    /// PEM file containing the certificate chain served to the clients of the inlet.
+    /// The clients must then connect with TLS.
+    #[arg(
+        long,
+        display_order = 901,
+        id = "CERTIFICATE_FILE",
+        requires = "KEY_FILE"
+    )]
+    tls_certificate: Option<PathBuf>,
+
+    /// PEM file containing the private key of the certificate served to the clients of the inlet.
+    #[arg(
+        long,
+        display_order = 901,
+        id = "KEY_FILE",
+        requires = "CERTIFICATE_FILE"
+    )]
+    tls_key: Option<PathBuf>,
+
+    #[command(flatten)]
+    limits: PortalLimitsArgs,
 }
 
 pub(crate) fn default_from_addr() -> SocketAddr {
@@ -74,6 +97,12 @@ pub(crate) fn default_from_addr() -> SocketAddr {
     SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port)
 }
 
+pub(crate) fn absolute_path(path: &PathBuf) -> miette::Result<String> {
+    let path = std::fs::canonicalize(path)
+        .map_err(|e| miette!("Cannot access {}: {e}", path.display()))?;
+    Ok(path.display().to_string())
+}
+
 fn default_to_addr() -> MultiAddr {
     MultiAddr::from_str("/project/default/service/forward_to_default/secure/api/service/outlet")
         .expect("Failed to parse default multiaddr")
@@ -133,6 +162,15 @@ async fn rpc(
             false
         };
 
+        // The files are read by the node, which may run in another directory
+        let tls = match (&cmd.tls_certificate, &cmd.tls_key) {
+            (Some(certificate), Some(key)) => Some(InletTls::new(
+                absolute_path(certificate)?,
+                absolute_path(key)?,
+            )),
+            _ => None,
+        };
+
         let inlet = loop {
             let req = {
                 let mut payload = if via_project {
@@ -155,6 +193,12 @@ async fn rpc(
                     payload.set_alias(a)
                 }
                 payload.set_wait_ms(cmd.connection_wait.as_millis() as u64);
+                if let Some(tls) = tls.clone() {
+                    payload.set_tls(tls)
+                }
+                if let Some(limits) = cmd.limits.to_portal_limits() {
+                    payload.set_limits(limits)
+                }
 
                 Request::post("/node/inlet").body(payload)
             };
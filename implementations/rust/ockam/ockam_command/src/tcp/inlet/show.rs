@@ -8,6 +8,7 @@ use ockam_api::nodes::models::portal::InletStatus;
 use ockam_core::api::{Request, RequestBuilder};
 
 use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::output::write_portal_limits;
 use crate::tcp::util::alias_parser;
 use crate::util::{node_rpc, parse_node_name, Rpc};
 use crate::{docs, CommandGlobalOpts};
@@ -53,14 +54,18 @@ pub async fn run_impl(
         alias,
         bind_addr,
         outlet_route,
+        limits,
         ..
     } = inlet_status;
-    let plain = formatdoc! {r#"
+    let mut plain = formatdoc! {r#"
         Inlet:
           Alias: {alias}
           TCP Address: {bind_addr}
           To Outlet Address: {outlet_route}
     "#};
+    if let Some(limits) = &limits {
+        write_portal_limits(&mut plain, limits)?;
+    }
     let machine = bind_addr;
     opts.terminal
         .stdout()
@@ -1,6 +1,6 @@
-use std::net::SocketAddr;
+use std::path::PathBuf;
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use colorful::Colorful;
 use miette::IntoDiagnostic;
 use tokio::sync::Mutex;
@@ -10,14 +10,17 @@ use ockam::Context;
 use ockam_abac::Resource;
 use ockam_api::address::extract_address_value;
 use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
-use ockam_api::nodes::models::portal::{CreateOutlet, OutletStatus};
+use ockam_api::nodes::models::portal::{
+    CreateOutlet, OutletLoadBalancing, OutletStatus, OutletTls,
+};
 use ockam_core::api::Request;
 
 use crate::node::{get_node_name, initialize_node_if_default};
 use crate::policy::{add_default_project_policy, has_policy};
-use crate::tcp::util::alias_parser;
+use crate::tcp::inlet::create::absolute_path;
+use crate::tcp::util::{alias_parser, PortalLimitsArgs};
 use crate::terminal::OckamColor;
-use crate::util::parsers::socket_addr_parser;
+use crate::util::parsers::{outlet_target_parser, socket_addr_parser};
 use crate::util::{node_rpc, Rpc};
 use crate::{display_parse_logs, fmt_log};
 use crate::{docs, fmt_ok, CommandGlobalOpts};
@@ -36,13 +39,58 @@ pub struct CreateCommand {
     #[arg(long, display_order = 901, id = "OUTLET_ADDRESS", default_value_t = default_from_addr())]
     from: String,
 
-    /// TCP address to send raw tcp traffic.
-    #[arg(long, display_order = 902, id = "SOCKET_ADDRESS", value_parser = socket_addr_parser)]
-    to: SocketAddr,
+    /// TCP address to send raw tcp traffic. It can be repeated to balance the connections
+    /// between several targets, which can be given as host names.
+    #[arg(long, display_order = 902, id = "SOCKET_ADDRESS", required = true, value_parser = outlet_target_parser)]
+    to: Vec<String>,
+
+    /// How a target is selected for each new connection when there are several targets.
+    #[arg(long, display_order = 903, value_enum, default_value_t = LoadBalancing::RoundRobin)]
+    load_balancing: LoadBalancing,
 
     /// Assign a name to this outlet.
     #[arg(long, display_order = 900, id = "ALIAS", value_parser = alias_parser)]
     alias: Option<String>,
+
+    /// Connect to the targets with TLS. The targets are verified with the native root
+    /// certificates, unless a CA bundle is given.
+    #[arg(long, display_order = 904)]
+    tls: bool,
+
+    /// Server name sent with SNI and verified in the certificate of the targets,
+    /// instead of the host of each target.
+    #[arg(long, display_order = 904, id = "SERVER_NAME", requires = "tls")]
+    tls_server_name: Option<String>,
+
+    /// PEM file containing the CA certificates used to verify the targets.
+    #[arg(long, display_order = 904, id = "CA_FILE", requires = "tls")]
+    tls_ca_certificates: Option<PathBuf>,
+
+    /// PEM file containing the client certificate chain presented to the targets.
+    #[arg(long, display_order = 904, id = "CLIENT_CERTIFICATE_FILE", requires_all = ["tls", "CLIENT_KEY_FILE"])]
+    tls_client_certificate: Option<PathBuf>,
+
+    /// PEM file containing the private key of the client certificate.
+    #[arg(long, display_order = 904, id = "CLIENT_KEY_FILE", requires_all = ["tls", "CLIENT_CERTIFICATE_FILE"])]
+    tls_client_key: Option<PathBuf>,
+
+    #[command(flatten)]
+    limits: PortalLimitsArgs,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum LoadBalancing {
+    RoundRobin,
+    LeastConnections,
+}
+
+impl From<LoadBalancing> for OutletLoadBalancing {
+    fn from(value: LoadBalancing) -> Self {
+        match value {
+            LoadBalancing::RoundRobin => OutletLoadBalancing::RoundRobin,
+            LoadBalancing::LeastConnections => OutletLoadBalancing::LeastConnections,
+        }
+    }
 }
 
 impl CreateCommand {
@@ -50,6 +98,21 @@ impl CreateCommand {
         initialize_node_if_default(&opts, &self.at);
         node_rpc(run_impl, (opts, self))
     }
+
+    /// The TLS configuration sent to the node, with absolute paths since the files
+    /// are read by the node
+    fn outlet_tls(&self) -> miette::Result<Option<OutletTls>> {
+        if !self.tls {
+            return Ok(None);
+        }
+        let path = |p: &Option<PathBuf>| p.as_ref().map(absolute_path).transpose();
+        Ok(Some(OutletTls::new(
+            self.tls_server_name.clone(),
+            path(&self.tls_ca_certificates)?,
+            path(&self.tls_client_certificate)?,
+            path(&self.tls_client_key)?,
+        )))
+    }
 }
 
 pub fn default_from_addr() -> String {
@@ -62,9 +125,7 @@ pub async fn run_impl(
 ) -> miette::Result<()> {
     opts.terminal.write_line(&fmt_log!(
         "Creating TCP Outlet to {}...\n",
-        &cmd.to
-            .to_string()
-            .color(OckamColor::PrimaryResource.color())
+        &cmd.to.join(", ").color(OckamColor::PrimaryResource.color())
     ))?;
     display_parse_logs(&opts);
 
@@ -87,13 +148,22 @@ pub async fn run_impl(
 
     let is_finished: Mutex<bool> = Mutex::new(false);
 
+    let tls = cmd.outlet_tls()?;
+
     let send_req = async {
-        let payload = CreateOutlet::new(
-            cmd.to,
+        let mut payload = CreateOutlet::new(
+            socket_addr_parser(&cmd.to[0])?,
             extract_address_value(&cmd.from)?.into(),
-            cmd.alias,
+            cmd.alias.clone(),
             true,
-        );
+        )
+        .with_targets(cmd.to.clone(), Some(cmd.load_balancing.into()));
+        if let Some(tls) = tls.clone() {
+            payload = payload.with_tls(tls);
+        }
+        if let Some(limits) = cmd.limits.to_portal_limits() {
+            payload = payload.with_limits(limits);
+        }
         let res = send_request(&ctx, &opts, payload, node_name.clone()).await;
         *is_finished.lock().await = true;
         res
@@ -132,9 +202,7 @@ pub async fn run_impl(
                 .color(OckamColor::PrimaryResource.color()),
             format!("/service/{}", extract_address_value(&cmd.from)?)
                 .color(OckamColor::PrimaryResource.color()),
-            &cmd.to
-                .to_string()
-                .color(OckamColor::PrimaryResource.color())
+            &cmd.to.join(", ").color(OckamColor::PrimaryResource.color())
         ))
         .machine(machine)
         .json(json)
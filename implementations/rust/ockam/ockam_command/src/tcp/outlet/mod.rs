@@ -28,7 +28,7 @@ pub struct TcpOutletCommand {
 
 #[derive(Clone, Debug, Subcommand)]
 pub enum TcpOutletSubCommand {
-    Create(CreateCommand),
+    Create(Box<CreateCommand>),
     Delete(DeleteCommand),
     List(ListCommand),
     Show(ShowCommand),
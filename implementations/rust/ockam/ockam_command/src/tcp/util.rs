@@ -1,5 +1,9 @@
+use crate::util::duration::duration_parser;
 use crate::Result;
+use clap::Args;
 use miette::miette;
+use ockam_api::nodes::models::portal::PortalLimits;
+use std::time::Duration;
 
 pub fn alias_parser(arg: &str) -> Result<String> {
     if arg.contains(':') {
@@ -8,3 +12,40 @@ pub fn alias_parser(arg: &str) -> Result<String> {
         Ok(arg.to_string())
     }
 }
+
+/// Limits enforced on the connections of a portal
+#[derive(Clone, Debug, Args)]
+pub struct PortalLimitsArgs {
+    /// Maximum number of connections open at the same time. Additional connections are closed.
+    #[arg(long, display_order = 910, value_name = "CONNECTIONS")]
+    pub max_connections: Option<u64>,
+
+    /// Maximum number of bytes per second transferred by each connection, in each direction.
+    #[arg(long, display_order = 910, value_name = "BYTES_PER_SECOND")]
+    pub connection_bandwidth: Option<u64>,
+
+    /// Maximum number of bytes per second transferred by all the connections, in each direction.
+    #[arg(long, display_order = 910, value_name = "BYTES_PER_SECOND")]
+    pub bandwidth: Option<u64>,
+
+    /// Close the connections without any data transferred during that time.
+    #[arg(long, display_order = 910, value_name = "DURATION", value_parser = duration_parser)]
+    pub idle_timeout: Option<Duration>,
+}
+
+impl PortalLimitsArgs {
+    /// Return the limits to send to the node, if any limit is set
+    pub fn to_portal_limits(&self) -> Option<PortalLimits> {
+        let limits = PortalLimits::new(
+            self.max_connections,
+            self.connection_bandwidth,
+            self.bandwidth,
+            self.idle_timeout,
+        );
+        if limits.is_empty() {
+            None
+        } else {
+            Some(limits)
+        }
+    }
+}
@@ -252,7 +252,7 @@ impl<W: TerminalWriter> Terminal<W> {
         }
     }
 
-    fn can_ask_for_user_input(&self) -> bool {
+    pub fn can_ask_for_user_input(&self) -> bool {
         !self.no_input && self.stderr.is_tty()
     }
 
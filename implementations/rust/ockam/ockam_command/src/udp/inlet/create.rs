@@ -0,0 +1,168 @@
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use tokio::sync::Mutex;
+use tokio::try_join;
+
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_abac::Resource;
+use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
+use ockam_api::nodes::models::portal::{CreateUdpInlet, InletStatus};
+use ockam_core::api::Request;
+use ockam_multiaddr::proto::Project;
+use ockam_multiaddr::{MultiAddr, Protocol as _};
+
+use crate::node::{get_node_name, initialize_node_if_default};
+use crate::policy::{add_default_project_policy, has_policy};
+use crate::tcp::util::alias_parser;
+use crate::terminal::OckamColor;
+use crate::util::duration::duration_parser;
+use crate::util::parsers::socket_addr_parser;
+use crate::util::{node_rpc, parse_node_name, process_nodes_multiaddr, Rpc};
+use crate::{display_parse_logs, docs, fmt_log, fmt_ok, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
+
+/// Create UDP Inlets
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct CreateCommand {
+    /// Node on which to start the udp inlet.
+    #[arg(long, display_order = 900, id = "NODE")]
+    at: Option<String>,
+
+    /// Address on which to accept udp datagrams.
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", value_parser = socket_addr_parser)]
+    from: SocketAddr,
+
+    /// Route to a udp outlet.
+    #[arg(long, display_order = 900, id = "ROUTE", default_value_t = default_to_addr())]
+    to: MultiAddr,
+
+    /// Authorized identity for secure channel connection
+    #[arg(long, name = "AUTHORIZED", display_order = 900)]
+    authorized: Option<Identifier>,
+
+    /// Assign a name to this inlet.
+    #[arg(long, display_order = 900, id = "ALIAS", value_parser = alias_parser)]
+    alias: Option<String>,
+
+    /// Time after which a flow without any datagram is closed.
+    #[arg(long, display_order = 900, id = "IDLE_TIMEOUT", value_parser = duration_parser)]
+    idle_timeout: Option<Duration>,
+}
+
+fn default_to_addr() -> MultiAddr {
+    MultiAddr::from_str("/project/default/service/forward_to_default/secure/api/service/udp_outlet")
+        .expect("Failed to parse default multiaddr")
+}
+
+impl CreateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.at);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(
+    ctx: Context,
+    (opts, mut cmd): (CommandGlobalOpts, CreateCommand),
+) -> miette::Result<()> {
+    opts.terminal.write_line(&fmt_log!(
+        "Creating UDP Inlet at {}...\n",
+        cmd.from
+            .to_string()
+            .color(OckamColor::PrimaryResource.color())
+    ))?;
+    display_parse_logs(&opts);
+
+    cmd.to = process_nodes_multiaddr(&cmd.to, &opts.state)?;
+    if cmd.authorized.is_some() && cmd.to.matches(0, &[Project::CODE.into()]) {
+        return Err(miette!(
+            "--authorized can not be used with project addresses"
+        ));
+    }
+
+    let node_name = get_node_name(&opts.state, &cmd.at);
+    let node = parse_node_name(&node_name)?;
+
+    let project = opts
+        .state
+        .nodes
+        .get(&node)?
+        .config()
+        .setup()
+        .project
+        .to_owned();
+    let resource = Resource::new("udp-inlet");
+    if let Some(p) = project {
+        if !has_policy(&node, &ctx, &opts, &resource).await? {
+            add_default_project_policy(&node, &ctx, &opts, p, &resource).await?;
+        }
+    }
+
+    let is_finished: Mutex<bool> = Mutex::new(false);
+    let create_inlet = async {
+        let payload = CreateUdpInlet::new(
+            cmd.from.to_string(),
+            cmd.to.clone(),
+            cmd.alias.clone(),
+            cmd.authorized.clone(),
+            cmd.idle_timeout,
+        );
+        let mut rpc = Rpc::background(&ctx, &opts, &node).await?;
+        let res: crate::Result<InletStatus> = rpc
+            .ask(Request::post("/node/udp/inlet").body(payload))
+            .await;
+        *is_finished.lock().await = true;
+        res
+    };
+
+    let progress_messages = vec![
+        format!(
+            "Creating UDP Inlet on {}...",
+            &node.to_string().color(OckamColor::PrimaryResource.color())
+        ),
+        format!(
+            "Establishing connection to outlet {}...",
+            &cmd.to
+                .to_string()
+                .color(OckamColor::PrimaryResource.color())
+        ),
+    ];
+    let progress_output = opts
+        .terminal
+        .progress_output(&progress_messages, &is_finished);
+
+    let (inlet, _) = try_join!(create_inlet, progress_output)?;
+
+    let machine_output = inlet.bind_addr.to_string();
+    let json_output = serde_json::to_string_pretty(&inlet).into_diagnostic()?;
+
+    opts.terminal
+        .stdout()
+        .plain(
+            fmt_ok!(
+                "UDP Inlet {} on node {} is now sending datagrams\n",
+                &cmd.from
+                    .to_string()
+                    .color(OckamColor::PrimaryResource.color()),
+                &node.to_string().color(OckamColor::PrimaryResource.color())
+            ) + &fmt_log!(
+                "to the outlet at {}",
+                &cmd.to
+                    .to_string()
+                    .color(OckamColor::PrimaryResource.color())
+            ),
+        )
+        .machine(machine_output)
+        .json(json_output)
+        .write_line()?;
+
+    Ok(())
+}
@@ -349,7 +349,21 @@ where
     Fut: core::future::Future<Output = miette::Result<T>> + Send + 'static,
     T: Send + 'static,
 {
-    let (mut ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    embedded_node_that_is_not_stopped_with_builder(NodeBuilder::new().no_logging(), f, a)
+}
+
+pub fn embedded_node_that_is_not_stopped_with_builder<A, F, Fut, T>(
+    builder: NodeBuilder,
+    f: F,
+    a: A,
+) -> miette::Result<T>
+where
+    A: Send + Sync + 'static,
+    F: FnOnce(Context, A) -> Fut + Send + Sync + 'static,
+    Fut: core::future::Future<Output = miette::Result<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let (mut ctx, mut executor) = builder.build();
     executor
         .execute(async move {
             let child_ctx = ctx
@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use miette::miette;
 
+use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::Identifier;
 use ockam_transport_tcp::resolve_peer;
 
@@ -24,12 +25,35 @@ pub(crate) fn socket_addr_parser(input: &str) -> Result<SocketAddr> {
         .map_err(|e| miette!("cannot parse the address {address} as a socket address: {e}"))?)
 }
 
+/// Helper function for parsing the target of an outlet from user input.
+/// The target must be resolvable, but it is kept as a string so that a host name can be
+/// resolved again later. It is possible to just input a `port`, as with [`socket_addr_parser`]
+pub(crate) fn outlet_target_parser(input: &str) -> Result<String> {
+    socket_addr_parser(input)?;
+    if input.contains(':') {
+        Ok(input.to_string())
+    } else {
+        Ok(format!("127.0.0.1:{input}"))
+    }
+}
+
 /// Helper fn for parsing an identity from user input by using
 /// [`ockam_identity::Identifier::from_str()`]
 pub(crate) fn identity_identifier_parser(input: &str) -> Result<Identifier> {
     Identifier::from_str(input).map_err(|_| miette!("Invalid identity identifier: {input}").into())
 }
 
+/// Helper fn for parsing a hex encoded delegation, as issued by `ockam credential delegate`
+pub(crate) fn delegation_parser(input: &str) -> Result<CredentialAndPurposeKey> {
+    let bytes = hex::decode(input).map_err(|_| miette!("Invalid delegation: {input}"))?;
+    let delegation: CredentialAndPurposeKey =
+        minicbor::decode(&bytes).map_err(|_| miette!("Invalid delegation: {input}"))?;
+    delegation
+        .delegation_scope()
+        .map_err(|_| miette!("Invalid delegation: {input}"))?;
+    Ok(delegation)
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::Ipv6Addr;
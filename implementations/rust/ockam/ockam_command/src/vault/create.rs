@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Args;
 use colorful::Colorful;
 use rand::prelude::random;
@@ -7,6 +9,7 @@ use ockam_api::cli_state;
 use ockam_api::cli_state::traits::StateDirTrait;
 
 use crate::util::node_rpc;
+use crate::vault::prepare_vault_encryption;
 use crate::{docs, fmt_info, fmt_ok, CommandGlobalOpts};
 
 const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
@@ -26,8 +29,26 @@ pub struct CreateCommand {
     #[arg(short, long)]
     path: Option<String>,
 
-    #[arg(long, default_value = "false")]
+    #[arg(long, default_value = "false", conflicts_with = "pkcs11_module")]
     aws_kms: bool,
+
+    /// Store the keys of the vault in a PKCS#11 token, using this PKCS#11 module.
+    /// The user PIN of the token is read from the OCKAM_PKCS11_PIN environment variable
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["encrypted", "key_file"])]
+    pkcs11_module: Option<PathBuf>,
+
+    /// Label of the PKCS#11 token. The first available token is used if not specified
+    #[arg(long, value_name = "LABEL", requires = "pkcs11_module")]
+    pkcs11_token_label: Option<String>,
+
+    /// Encrypt the secrets of the vault with a passphrase, read from the
+    /// OCKAM_VAULT_PASSPHRASE environment variable or asked interactively
+    #[arg(long, default_value = "false", conflicts_with = "aws_kms")]
+    encrypted: bool,
+
+    /// Encrypt the secrets of the vault with a key file. The file is created if it doesn't exist
+    #[arg(long, value_name = "PATH", conflicts_with = "aws_kms")]
+    key_file: Option<PathBuf>,
 }
 
 impl CreateCommand {
@@ -48,8 +69,23 @@ async fn run_impl(
     opts: CommandGlobalOpts,
     cmd: CreateCommand,
 ) -> miette::Result<()> {
-    let CreateCommand { name, aws_kms, .. } = cmd;
-    let config = cli_state::VaultConfig::new(aws_kms)?;
+    let CreateCommand {
+        name,
+        aws_kms,
+        encrypted,
+        key_file,
+        pkcs11_module,
+        pkcs11_token_label,
+        ..
+    } = cmd;
+    let mut config = cli_state::VaultConfig::new(aws_kms)?;
+    if let Some(pkcs11_module) = pkcs11_module {
+        config = config.with_pkcs11(pkcs11_module, pkcs11_token_label);
+    }
+    if encrypted || key_file.is_some() {
+        let key_file = prepare_vault_encryption(&opts, &name, key_file)?;
+        config = config.with_encryption(key_file);
+    }
     if opts.state.vaults.is_empty()? {
         opts.terminal.write_line(&fmt_info!(
             "This is the first vault to be created in this environment. It will be set as the default vault"
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::cli_state::traits::StateDirTrait;
+
+use crate::util::node_rpc;
+use crate::vault::prepare_vault_encryption;
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/encrypt/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/encrypt/after_long_help.txt");
+
+/// Encrypt the secrets of an existing vault
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct EncryptCommand {
+    /// Name of the vault
+    pub name: Option<String>,
+
+    /// Encrypt the secrets with a key file instead of a passphrase. The file is created if it doesn't exist
+    #[arg(long, value_name = "PATH")]
+    key_file: Option<PathBuf>,
+}
+
+impl EncryptCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(
+    mut ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, EncryptCommand),
+) -> miette::Result<()> {
+    run_impl(&mut ctx, opts, cmd).await
+}
+
+async fn run_impl(
+    _ctx: &mut Context,
+    opts: CommandGlobalOpts,
+    cmd: EncryptCommand,
+) -> miette::Result<()> {
+    let name = cmd
+        .name
+        .unwrap_or(opts.state.vaults.default()?.name().to_string());
+    let state = opts.state.vaults.get(&name)?;
+    let key_file = prepare_vault_encryption(&opts, &name, cmd.key_file)?;
+    state.encrypt(key_file).await?;
+
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "The secrets of the vault '{name}' are now encrypted"
+        ))
+        .machine(&name)
+        .json(serde_json::json!({ "vault": { "name": &name, "encrypted": true } }))
+        .write_line()?;
+    Ok(())
+}
@@ -2,6 +2,7 @@ mod attach_key;
 mod create;
 mod default;
 mod delete;
+mod encrypt;
 mod list;
 mod show;
 
@@ -9,13 +10,18 @@ use crate::vault::attach_key::AttachKeyCommand;
 use crate::vault::create::CreateCommand;
 use crate::vault::default::DefaultCommand;
 use crate::vault::delete::DeleteCommand;
+use crate::vault::encrypt::EncryptCommand;
 use crate::vault::list::ListCommand;
 use crate::vault::show::ShowCommand;
-use crate::{docs, CommandGlobalOpts};
+use crate::{docs, fmt_log, CommandGlobalOpts};
 
 use clap::{Args, Subcommand};
+use miette::{miette, IntoDiagnostic};
 use ockam_api::cli_state::traits::StateDirTrait;
-use ockam_api::cli_state::CliState;
+use ockam_api::cli_state::{CliState, OCKAM_VAULT_PASSPHRASE};
+use ockam_core::env::get_env;
+use ockam_vault::storage::StorageEncryption;
+use std::path::PathBuf;
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
 
@@ -37,6 +43,7 @@ pub enum VaultSubcommand {
     AttachKey(AttachKeyCommand),
     Show(ShowCommand),
     Delete(DeleteCommand),
+    Encrypt(EncryptCommand),
     List(ListCommand),
     Default(DefaultCommand),
 }
@@ -49,6 +56,7 @@ impl VaultCommand {
             VaultSubcommand::Show(cmd) => cmd.run(opts),
             VaultSubcommand::List(cmd) => cmd.run(opts),
             VaultSubcommand::Delete(cmd) => cmd.run(opts),
+            VaultSubcommand::Encrypt(cmd) => cmd.run(opts),
             VaultSubcommand::Default(cmd) => cmd.run(opts),
         }
     }
@@ -60,3 +68,51 @@ pub fn default_vault_name(cli_state: &CliState) -> String {
         .default()
         .map_or("default".to_string(), |v| v.name().to_string())
 }
+
+/// Make sure that the secret used to encrypt a vault is available:
+///  - a key file is created if it doesn't exist yet, and its absolute path is returned
+///  - otherwise a new passphrase is asked if it is not set in the environment,
+///    and kept in the vaults state of this process
+pub(crate) fn prepare_vault_encryption(
+    opts: &CommandGlobalOpts,
+    vault_name: &str,
+    key_file: Option<PathBuf>,
+) -> miette::Result<Option<PathBuf>> {
+    if let Some(key_file) = key_file {
+        if !key_file.exists() {
+            StorageEncryption::create_key_file(&key_file).into_diagnostic()?;
+        }
+        return Ok(Some(std::fs::canonicalize(key_file).into_diagnostic()?));
+    }
+
+    if get_env::<String>(OCKAM_VAULT_PASSPHRASE)
+        .into_diagnostic()?
+        .is_none()
+    {
+        if !opts.terminal.can_ask_for_user_input() {
+            return Err(miette!(
+                "Please set the {OCKAM_VAULT_PASSPHRASE} environment variable or use --key-file"
+            ));
+        }
+        let passphrase = dialoguer::Password::new()
+            .with_prompt(fmt_log!("Enter a passphrase for the vault"))
+            .with_confirmation(
+                fmt_log!("Confirm the passphrase"),
+                fmt_log!("The passphrases don't match"),
+            )
+            .interact()
+            .into_diagnostic()?;
+        // the passphrase is then used to create the vault storage
+        opts.state.vaults.passphrases().set(vault_name, passphrase);
+    }
+    Ok(None)
+}
+
+/// Ask for the passphrase of an encrypted vault when it is neither given by
+/// a key file nor by an environment variable
+pub(crate) fn prompt_vault_passphrase(vault_name: &str) -> Option<String> {
+    dialoguer::Password::new()
+        .with_prompt(fmt_log!("Enter the passphrase of the vault '{vault_name}'"))
+        .interact()
+        .ok()
+}
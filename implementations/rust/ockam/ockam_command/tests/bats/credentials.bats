@@ -57,3 +57,23 @@ teardown() {
   run_failure "$OCKAM" credential show smart_la_cred
   assert_output --partial "Unable to find credential named smart_la_cred"
 }
+
+@test "credential - delegate and issue delegated credentials" {
+  run_success "$OCKAM" identity create authority
+  authority_short=$($OCKAM identity show authority)
+
+  run_success "$OCKAM" identity create lead
+  lead_short=$($OCKAM identity show lead)
+
+  run_success "$OCKAM" identity create device
+  device_short=$($OCKAM identity show device)
+
+  delegation=$($OCKAM credential delegate --as authority --for "$lead_short" --attribute role=device --attribute team="*" --max-ttl 1d --encoding hex)
+
+  "$OCKAM" credential issue --as lead --for "$device_short" --attribute role=device --attribute team=blue --delegation "$delegation" --encoding hex >"$OCKAM_HOME/credential"
+  run_success "$OCKAM" credential verify --issuer "$authority_short" --credential-path "$OCKAM_HOME/credential"
+  assert_output --partial "true"
+
+  run_failure "$OCKAM" credential issue --as lead --for "$device_short" --attribute role=admin --delegation "$delegation"
+  run_failure "$OCKAM" credential delegate --as lead --for "$device_short" --attribute role=device --delegation "$delegation"
+}
@@ -3,9 +3,10 @@ use crate::models::{CredentialAndPurposeKey, Identifier, TimestampInSeconds};
 use crate::utils::{add_seconds, now};
 use crate::{Credentials, IdentityError};
 
+use ockam_core::compat::boxed::Box;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::sync::RwLock;
-use ockam_core::Result;
+use ockam_core::{async_trait, Result};
 use ockam_node::Context;
 
 /// An AuthorityService represents an authority which issued credentials
@@ -83,3 +84,15 @@ impl AuthorityService {
         &self.identifier
     }
 }
+
+#[async_trait]
+impl CredentialsRetriever for AuthorityService {
+    /// Retrieve a credential issued by this authority, using the cached credential if still valid
+    async fn retrieve(
+        &self,
+        ctx: &Context,
+        for_identity: &Identifier,
+    ) -> Result<CredentialAndPurposeKey> {
+        self.credential(ctx, for_identity).await
+    }
+}
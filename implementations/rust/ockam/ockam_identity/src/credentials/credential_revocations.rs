@@ -0,0 +1,260 @@
+use crate::models::{
+    CredentialData, CredentialHash, Identifier, RevocationListData, TimestampInSeconds,
+};
+use crate::IdentityError;
+
+use ockam_core::compat::collections::{BTreeMap, BTreeSet};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+/// Revocation lists received from authorities, used to reject revoked [`crate::Credential`]s
+///
+/// The hashes of the credentials accepted from other identities are kept as well, until they
+/// expire, so that the attributes of an identity can be removed when one of its credentials
+/// gets revoked after having been presented.
+#[derive(Default)]
+pub struct CredentialRevocations {
+    revocation_lists: RwLock<BTreeMap<Identifier, RevocationListData>>,
+    accepted_credentials: RwLock<BTreeMap<CredentialHash, AcceptedCredential>>,
+}
+
+struct AcceptedCredential {
+    subject: Identifier,
+    authority: Identifier,
+    expires_at: TimestampInSeconds,
+}
+
+impl CredentialRevocations {
+    /// Create an empty set of revocations
+    pub fn create() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Return true if a credential issued by the given authority has been revoked
+    pub fn is_revoked(
+        &self,
+        authority: &Identifier,
+        credential_hash: &CredentialHash,
+        credential_data: &CredentialData,
+    ) -> bool {
+        let revocation_lists = self.revocation_lists.read().unwrap();
+        let revocation_list = match revocation_lists.get(authority) {
+            Some(revocation_list) => revocation_list,
+            None => return false,
+        };
+
+        let credential_revoked = revocation_list
+            .revoked_credentials
+            .iter()
+            .any(|r| &r.credential_hash == credential_hash);
+
+        // a subject can be enrolled again after having been revoked, in that case only
+        // the credentials issued before the revocation are rejected
+        let subject_revoked = match &credential_data.subject {
+            Some(subject) => revocation_list
+                .revoked_subjects
+                .iter()
+                .any(|r| &r.subject == subject && credential_data.created_at <= r.revoked_at),
+            None => false,
+        };
+
+        credential_revoked || subject_revoked
+    }
+
+    /// Return the sequence number of the last revocation list received from an authority
+    pub fn sequence(&self, authority: &Identifier) -> Option<u64> {
+        self.revocation_lists
+            .read()
+            .unwrap()
+            .get(authority)
+            .map(|r| r.sequence)
+    }
+
+    /// Remember that a credential has been accepted for a given subject, until it expires.
+    /// The credentials which expired before `now` are forgotten
+    pub(crate) fn accept(
+        &self,
+        credential_hash: CredentialHash,
+        subject: &Identifier,
+        authority: &Identifier,
+        expires_at: TimestampInSeconds,
+        now: TimestampInSeconds,
+    ) {
+        let mut accepted_credentials = self.accepted_credentials.write().unwrap();
+        accepted_credentials.retain(|_, accepted| accepted.expires_at > now);
+        accepted_credentials.insert(
+            credential_hash,
+            AcceptedCredential {
+                subject: subject.clone(),
+                authority: authority.clone(),
+                expires_at,
+            },
+        );
+    }
+
+    /// Replace the revocation list of an authority and return the subjects of the
+    /// previously accepted credentials which are now revoked.
+    /// A revocation list older than the current one is rejected
+    pub(crate) fn update(
+        &self,
+        authority: &Identifier,
+        revocation_list: RevocationListData,
+    ) -> Result<Vec<Identifier>> {
+        let mut revocation_lists = self.revocation_lists.write().unwrap();
+        if let Some(current) = revocation_lists.get(authority) {
+            if current.sequence > revocation_list.sequence {
+                return Err(IdentityError::StaleRevocationList.into());
+            }
+        }
+
+        let revoked_hashes: BTreeSet<&CredentialHash> = revocation_list
+            .revoked_credentials
+            .iter()
+            .map(|r| &r.credential_hash)
+            .collect();
+        let mut revoked_subjects: BTreeSet<Identifier> = revocation_list
+            .revoked_subjects
+            .iter()
+            .map(|r| r.subject.clone())
+            .collect();
+
+        let mut accepted_credentials = self.accepted_credentials.write().unwrap();
+        accepted_credentials.retain(|hash, accepted| {
+            if &accepted.authority != authority {
+                return true;
+            }
+            if revoked_hashes.contains(hash) || revoked_subjects.contains(&accepted.subject) {
+                revoked_subjects.insert(accepted.subject.clone());
+                return false;
+            }
+            true
+        });
+
+        revocation_lists.insert(authority.clone(), revocation_list);
+        Ok(revoked_subjects.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Attributes, RevokedCredential, RevokedSubject, SchemaId};
+
+    #[test]
+    fn test_revocations() -> Result<()> {
+        let revocations = CredentialRevocations::default();
+        let authority = Identifier([1; 20]);
+        let subject = Identifier([2; 20]);
+        let other_subject = Identifier([3; 20]);
+        let credential = credential_data(&subject, 10);
+        let hash = CredentialHash([4; 32]);
+        let other_hash = CredentialHash([5; 32]);
+        assert!(!revocations.is_revoked(&authority, &hash, &credential));
+
+        revocations.accept(
+            other_hash.clone(),
+            &other_subject,
+            &authority,
+            TimestampInSeconds(110),
+            TimestampInSeconds(10),
+        );
+        let revoked = revocations.update(
+            &authority,
+            revocation_list(
+                1,
+                vec![RevokedSubject {
+                    subject: subject.clone(),
+                    revoked_at: TimestampInSeconds(20),
+                }],
+                vec![RevokedCredential {
+                    credential_hash: other_hash.clone(),
+                    revoked_at: TimestampInSeconds(20),
+                }],
+            ),
+        )?;
+        assert_eq!(revoked, vec![subject.clone(), other_subject.clone()]);
+        assert!(revocations.is_revoked(&authority, &hash, &credential));
+        assert!(revocations.is_revoked(
+            &authority,
+            &other_hash,
+            &credential_data(&other_subject, 10)
+        ));
+
+        // credentials issued after the revocation of their subject are valid
+        assert!(!revocations.is_revoked(&authority, &hash, &credential_data(&subject, 30)));
+
+        // credentials of other authorities are not revoked
+        assert!(!revocations.is_revoked(&Identifier([6; 20]), &hash, &credential));
+
+        // older lists are rejected
+        assert!(revocations
+            .update(&authority, revocation_list(0, vec![], vec![]))
+            .is_err());
+        assert_eq!(revocations.sequence(&authority), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_expired_accepted_credentials_are_forgotten() {
+        let revocations = CredentialRevocations::default();
+        let authority = Identifier([1; 20]);
+        let subject = Identifier([2; 20]);
+
+        revocations.accept(
+            CredentialHash([4; 32]),
+            &subject,
+            &authority,
+            TimestampInSeconds(100),
+            TimestampInSeconds(0),
+        );
+        revocations.accept(
+            CredentialHash([5; 32]),
+            &subject,
+            &authority,
+            TimestampInSeconds(300),
+            TimestampInSeconds(50),
+        );
+        assert_eq!(revocations.accepted_credentials.read().unwrap().len(), 2);
+
+        revocations.accept(
+            CredentialHash([6; 32]),
+            &subject,
+            &authority,
+            TimestampInSeconds(400),
+            TimestampInSeconds(200),
+        );
+        let accepted_credentials = revocations.accepted_credentials.read().unwrap();
+        assert_eq!(
+            accepted_credentials.keys().cloned().collect::<Vec<_>>(),
+            vec![CredentialHash([5; 32]), CredentialHash([6; 32])]
+        );
+    }
+
+    fn credential_data(subject: &Identifier, created_at: u64) -> CredentialData {
+        CredentialData {
+            subject: Some(subject.clone()),
+            subject_latest_change_hash: None,
+            subject_attributes: Attributes {
+                schema: SchemaId(1),
+                map: Default::default(),
+            },
+            created_at: TimestampInSeconds(created_at),
+            expires_at: TimestampInSeconds(created_at + 100),
+        }
+    }
+
+    fn revocation_list(
+        sequence: u64,
+        revoked_subjects: Vec<RevokedSubject>,
+        revoked_credentials: Vec<RevokedCredential>,
+    ) -> RevocationListData {
+        RevocationListData {
+            sequence,
+            revoked_subjects,
+            revoked_credentials,
+            created_at: TimestampInSeconds(0),
+            expires_at: TimestampInSeconds(100),
+        }
+    }
+}
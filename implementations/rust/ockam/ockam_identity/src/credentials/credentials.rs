@@ -1,5 +1,8 @@
-use crate::models::{CredentialData, PurposeKeyAttestationData};
-use crate::{CredentialsCreation, CredentialsVerification, IdentitiesRepository, PurposeKeys};
+use crate::models::{CredentialData, Identifier, PurposeKeyAttestationData};
+use crate::{
+    CredentialRevocations, CredentialsCreation, CredentialsVerification, IdentitiesRepository,
+    PurposeKeys,
+};
 
 use ockam_core::compat::sync::Arc;
 use ockam_vault::{SigningVault, VerifyingVault};
@@ -12,6 +15,9 @@ pub struct CredentialAndPurposeKeyData {
     pub credential_data: CredentialData,
     /// [`PurposeKeyAttestationData`]
     pub purpose_key_data: PurposeKeyAttestationData,
+    /// Authority which issued the [`Credential`], or the first delegation allowing its issuer
+    /// to issue it
+    pub authority: Identifier,
 }
 
 /// Service for managing [`Credential`]s
@@ -20,6 +26,7 @@ pub struct Credentials {
     verifying_vault: Arc<dyn VerifyingVault>,
     purpose_keys: Arc<PurposeKeys>,
     identities_repository: Arc<dyn IdentitiesRepository>,
+    credential_revocations: Arc<CredentialRevocations>,
 }
 
 impl Credentials {
@@ -29,12 +36,14 @@ impl Credentials {
         verifying_vault: Arc<dyn VerifyingVault>,
         purpose_keys: Arc<PurposeKeys>,
         identities_repository: Arc<dyn IdentitiesRepository>,
+        credential_revocations: Arc<CredentialRevocations>,
     ) -> Self {
         Self {
             credential_vault,
             verifying_vault,
             purpose_keys,
             identities_repository,
+            credential_revocations,
         }
     }
 
@@ -48,6 +57,11 @@ impl Credentials {
         self.identities_repository.clone()
     }
 
+    /// [`CredentialRevocations`]
+    pub fn credential_revocations(&self) -> Arc<CredentialRevocations> {
+        self.credential_revocations.clone()
+    }
+
     /// Return [`CredentialsCreation`]
     pub fn credentials_creation(&self) -> Arc<CredentialsCreation> {
         Arc::new(CredentialsCreation::new(
@@ -64,6 +78,7 @@ impl Credentials {
             self.purpose_keys.purpose_keys_verification(),
             self.verifying_vault.clone(),
             self.identities_repository.clone(),
+            self.credential_revocations.clone(),
         ))
     }
 }
@@ -1,12 +1,14 @@
 use crate::models::{
     Attributes, Credential, CredentialAndPurposeKey, CredentialData, CredentialSignature,
-    Identifier, VersionedData,
+    DelegationScope, Identifier, RevocationList, RevocationListAndPurposeKey, RevocationListData,
+    RevokedCredential, RevokedSubject, VersionedData,
 };
 use crate::utils::{add_seconds, now};
-use crate::{IdentitiesRepository, Identity, Purpose, PurposeKeysCreation};
+use crate::{IdentitiesRepository, Identity, IdentityError, Purpose, PurposeKeysCreation};
 
 use core::time::Duration;
 use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 use ockam_vault::{SigningVault, VerifyingVault};
 
@@ -48,6 +50,74 @@ impl CredentialsCreation {
         subject: &Identifier,
         subject_attributes: Attributes,
         ttl: Duration,
+    ) -> Result<CredentialAndPurposeKey> {
+        self.issue(issuer, subject, subject_attributes, ttl, None)
+            .await
+    }
+
+    /// Issue a delegation allowing the subject to issue [`Credential`]s within a
+    /// [`DelegationScope`]. When the issuer is not an authority, the delegation it received
+    /// must be given, and must allow it to delegate a larger scope
+    pub async fn issue_delegation(
+        &self,
+        issuer: &Identifier,
+        delegate: &Identifier,
+        scope: &DelegationScope,
+        ttl: Duration,
+        issuer_delegation: Option<&CredentialAndPurposeKey>,
+    ) -> Result<CredentialAndPurposeKey> {
+        let delegations = match issuer_delegation {
+            Some(issuer_delegation) => {
+                let issuer_scope = issuer_delegation.delegation_scope()?;
+                if !issuer_scope.can_delegate
+                    || !issuer_scope.contains(scope)
+                    || ttl.as_secs() > issuer_scope.max_ttl
+                {
+                    return Err(IdentityError::DelegationScopeExceeded.into());
+                }
+                Some(issuer_delegation.delegation_chain())
+            }
+            None => None,
+        };
+        let mut delegation = self
+            .issue(issuer, delegate, scope.to_attributes()?, ttl, delegations)
+            .await?;
+        delegation.subject_change_history =
+            Some(self.identities_repository.get_identity(delegate).await?);
+        Ok(delegation)
+    }
+
+    /// Issue a [`Credential`] with the delegation received by the issuer.
+    /// The attributes and the ttl must be allowed by the [`DelegationScope`] of the delegation
+    pub async fn issue_delegated_credential(
+        &self,
+        issuer: &Identifier,
+        subject: &Identifier,
+        subject_attributes: Attributes,
+        ttl: Duration,
+        issuer_delegation: &CredentialAndPurposeKey,
+    ) -> Result<CredentialAndPurposeKey> {
+        let scope = issuer_delegation.delegation_scope()?;
+        if ttl.as_secs() > scope.max_ttl || !scope.allows_attributes(&subject_attributes) {
+            return Err(IdentityError::DelegationScopeExceeded.into());
+        }
+        self.issue(
+            issuer,
+            subject,
+            subject_attributes,
+            ttl,
+            Some(issuer_delegation.delegation_chain()),
+        )
+        .await
+    }
+
+    async fn issue(
+        &self,
+        issuer: &Identifier,
+        subject: &Identifier,
+        subject_attributes: Attributes,
+        ttl: Duration,
+        delegations: Option<Vec<CredentialAndPurposeKey>>,
     ) -> Result<CredentialAndPurposeKey> {
         // TODO: Allow manual PurposeKey management
         let issuer_purpose_key = self
@@ -64,7 +134,17 @@ impl CredentialsCreation {
         .await?;
 
         let created_at = now()?;
-        let expires_at = add_seconds(&created_at, ttl.as_secs());
+        let mut expires_at = add_seconds(&created_at, ttl.as_secs());
+
+        // A delegated Credential can't outlive the delegation allowing to issue it
+        if let Some(delegation) = delegations.as_ref().and_then(|d| d.last()) {
+            let versioned_data = delegation.credential.get_versioned_data()?;
+            let delegation_expires_at = CredentialData::get_data(&versioned_data)?.expires_at;
+            if delegation_expires_at <= created_at {
+                return Err(IdentityError::DelegationExpired.into());
+            }
+            expires_at = expires_at.min(delegation_expires_at);
+        }
 
         let credential_data = CredentialData {
             subject: Some(subject.clone()),
@@ -98,8 +178,63 @@ impl CredentialsCreation {
         let res = CredentialAndPurposeKey {
             credential,
             purpose_key_attestation: issuer_purpose_key.attestation().clone(),
+            delegations,
+            subject_change_history: None,
         };
 
         Ok(res)
     }
+
+    /// Issue a [`RevocationList`] signed with the same Purpose Key as the [`Credential`]s
+    pub async fn issue_revocation_list(
+        &self,
+        issuer: &Identifier,
+        sequence: u64,
+        revoked_subjects: Vec<RevokedSubject>,
+        revoked_credentials: Vec<RevokedCredential>,
+        ttl: Duration,
+    ) -> Result<RevocationListAndPurposeKey> {
+        let issuer_purpose_key = self
+            .purpose_keys_creation
+            .get_or_create_purpose_key(issuer, Purpose::Credentials)
+            .await?;
+
+        let created_at = now()?;
+        let expires_at = add_seconds(&created_at, ttl.as_secs());
+
+        let revocation_list_data = RevocationListData {
+            sequence,
+            revoked_subjects,
+            revoked_credentials,
+            created_at,
+            expires_at,
+        };
+        let revocation_list_data = minicbor::to_vec(revocation_list_data)?;
+
+        let versioned_data = VersionedData {
+            version: 1,
+            data: revocation_list_data,
+        };
+        let versioned_data = minicbor::to_vec(&versioned_data)?;
+
+        let signed_data_hash = self
+            .verifying_vault
+            .sha256(&RevocationList::signed_data(&versioned_data))
+            .await?;
+
+        let signature = self
+            .credential_vault
+            .sign(issuer_purpose_key.key_id(), &signed_data_hash)
+            .await?;
+        let signature =
+            CredentialSignature::try_from_signature(signature, issuer_purpose_key.stype())?;
+
+        Ok(RevocationListAndPurposeKey {
+            revocation_list: RevocationList {
+                data: versioned_data,
+                signature,
+            },
+            purpose_key_attestation: issuer_purpose_key.attestation().clone(),
+        })
+    }
 }
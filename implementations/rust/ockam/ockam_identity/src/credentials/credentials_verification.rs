@@ -1,26 +1,43 @@
 use crate::identities::AttributesEntry;
-use crate::models::{CredentialAndPurposeKey, CredentialData, Identifier, PurposePublicKey};
+use crate::models::{
+    ChangeHistory, Credential, CredentialAndPurposeKey, CredentialData, CredentialHash,
+    DelegationScope, Identifier, PurposeKeyAttestation, PurposeKeyAttestationData,
+    PurposePublicKey, RevocationList, RevocationListAndPurposeKey, RevocationListData,
+    DELEGATION_SCHEMA,
+};
 use crate::utils::now;
 use crate::{
-    CredentialAndPurposeKeyData, IdentitiesRepository, IdentityError, PurposeKeysVerification,
-    TimestampInSeconds,
+    CredentialAndPurposeKeyData, CredentialRevocations, IdentitiesRepository, Identity,
+    IdentityError, IdentityHistoryComparison, PurposeKeysVerification, TimestampInSeconds,
 };
 
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
-use ockam_vault::VerifyingVault;
+use ockam_vault::{PublicKey, VerifyingVault};
+use tracing::{debug, info};
 
 /// We allow Credentials to be created in the future related to this machine's time due to
 /// possible time dyssynchronization
 const MAX_ALLOWED_TIME_DRIFT: TimestampInSeconds = TimestampInSeconds(5);
 
+/// Last delegation of a verified chain of delegations
+struct VerifiedDelegation {
+    /// Authority which issued the first delegation
+    authority: Identifier,
+    /// Subject of the delegation, allowed to issue Credentials
+    delegate: Identifier,
+    scope: DelegationScope,
+    expires_at: TimestampInSeconds,
+}
+
 /// Service for managing [`Credential`]s
 pub struct CredentialsVerification {
     purpose_keys_verification: Arc<PurposeKeysVerification>,
     verifying_vault: Arc<dyn VerifyingVault>,
     identities_repository: Arc<dyn IdentitiesRepository>,
+    credential_revocations: Arc<CredentialRevocations>,
 }
 
 impl CredentialsVerification {
@@ -29,11 +46,13 @@ impl CredentialsVerification {
         purpose_keys_verification: Arc<PurposeKeysVerification>,
         verifying_vault: Arc<dyn VerifyingVault>,
         identities_repository: Arc<dyn IdentitiesRepository>,
+        credential_revocations: Arc<CredentialRevocations>,
     ) -> Self {
         Self {
             purpose_keys_verification,
             verifying_vault,
             identities_repository,
+            credential_revocations,
         }
     }
 
@@ -44,35 +63,199 @@ impl CredentialsVerification {
 }
 
 impl CredentialsVerification {
-    /// Verify a [`Credential`]
-    // TODO: Move to CredentialsVerification
+    /// Verify a [`Credential`], issued by one of the authorities or by the last delegate of
+    /// a chain of delegations starting at one of the authorities
     pub async fn verify_credential(
         &self,
         expected_subject: Option<&Identifier>,
         authorities: &[Identifier],
         credential_and_purpose_key: &CredentialAndPurposeKey,
     ) -> Result<CredentialAndPurposeKeyData> {
-        let purpose_key_data = self
-            .purpose_keys_verification
-            .verify_purpose_key_attestation(
-                None,
-                &credential_and_purpose_key.purpose_key_attestation,
-            )
+        let delegation = self
+            .verify_delegations(authorities, credential_and_purpose_key.delegations())
             .await?;
+        let issuers = match &delegation {
+            Some(delegation) => vec![delegation.delegate.clone()],
+            None => authorities.to_vec(),
+        };
 
-        if !authorities.contains(&purpose_key_data.subject) {
-            return Err(IdentityError::UnknownAuthority.into());
+        let (credential_data, purpose_key_data, credential_hash) = self
+            .verify_signed_credential(expected_subject, &issuers, credential_and_purpose_key)
+            .await?;
+
+        if credential_data.subject_attributes.schema == DELEGATION_SCHEMA {
+            // A delegation only allows its subject to issue Credentials
+            return Err(IdentityError::CredentialVerificationFailed.into());
         }
 
-        let public_key = match purpose_key_data.public_key.clone() {
-            PurposePublicKey::SecureChannelStaticKey(_) => {
-                return Err(IdentityError::InvalidKeyType.into())
+        let authority = match delegation {
+            Some(delegation) => {
+                if !delegation.scope.allows(&credential_data)
+                    || credential_data.expires_at > delegation.expires_at
+                {
+                    return Err(IdentityError::DelegationScopeExceeded.into());
+                }
+                delegation.authority
             }
-
-            PurposePublicKey::CredentialSigningKey(public_key) => public_key,
+            None => purpose_key_data.subject.clone(),
         };
 
-        let public_key = public_key.into();
+        if self
+            .credential_revocations
+            .is_revoked(&authority, &credential_hash, &credential_data)
+        {
+            return Err(IdentityError::CredentialRevoked.into());
+        }
+
+        // FIXME: Verify if given authority is allowed to issue credentials with given Schema <-- Should be handled somewhere in the TrustContext
+        // FIXME: Verify if Schema aligns with Attributes <-- Should be handled somewhere in the TrustContext
+
+        Ok(CredentialAndPurposeKeyData {
+            credential_data,
+            purpose_key_data,
+            authority,
+        })
+    }
+
+    /// Verify a delegation, along with the delegations it was issued with, and return the
+    /// authority at the start of the chain of delegations and the [`DelegationScope`]
+    pub async fn verify_delegation(
+        &self,
+        authorities: &[Identifier],
+        delegation: &CredentialAndPurposeKey,
+    ) -> Result<(Identifier, DelegationScope)> {
+        match self
+            .verify_delegations(authorities, &delegation.delegation_chain())
+            .await?
+        {
+            Some(verified) => Ok((verified.authority, verified.scope)),
+            None => Err(IdentityError::DelegationVerificationFailed.into()),
+        }
+    }
+
+    /// Verify a chain of delegations and return the last one, with the authority at the start
+    /// of the chain. Each delegation must be issued by the subject of the previous one, and
+    /// must not allow more than the previous one
+    async fn verify_delegations(
+        &self,
+        authorities: &[Identifier],
+        delegations: &[CredentialAndPurposeKey],
+    ) -> Result<Option<VerifiedDelegation>> {
+        let mut verified: Option<VerifiedDelegation> = None;
+        for delegation in delegations {
+            if !delegation.delegations().is_empty() {
+                return Err(IdentityError::DelegationVerificationFailed.into());
+            }
+
+            let issuers = match &verified {
+                Some(previous) => vec![previous.delegate.clone()],
+                None => authorities.to_vec(),
+            };
+            let (credential_data, purpose_key_data, credential_hash) = self
+                .verify_signed_credential(None, &issuers, delegation)
+                .await?;
+            let scope = DelegationScope::from_attributes(&credential_data.subject_attributes)?;
+            let delegate = credential_data
+                .subject
+                .clone()
+                .ok_or(IdentityError::DelegationVerificationFailed)?;
+
+            if let Some(previous) = &verified {
+                if !previous.scope.can_delegate
+                    || !previous.scope.contains(&scope)
+                    || credential_data.expires_at > previous.expires_at
+                    || credential_data
+                        .expires_at
+                        .saturating_sub(*credential_data.created_at)
+                        > previous.scope.max_ttl
+                {
+                    return Err(IdentityError::DelegationScopeExceeded.into());
+                }
+            }
+
+            let authority = match &verified {
+                Some(previous) => previous.authority.clone(),
+                None => purpose_key_data.subject,
+            };
+            if self.credential_revocations.is_revoked(
+                &authority,
+                &credential_hash,
+                &credential_data,
+            ) {
+                return Err(IdentityError::CredentialRevoked.into());
+            }
+
+            // the next delegation, or the credential, is issued by the delegate
+            if let Some(change_history) = &delegation.subject_change_history {
+                self.import_delegate(&delegate, change_history).await?;
+            }
+
+            debug!(%authority, %delegate, "verified a delegation");
+            verified = Some(VerifiedDelegation {
+                authority,
+                delegate,
+                scope,
+                expires_at: credential_data.expires_at,
+            });
+        }
+        Ok(verified)
+    }
+
+    /// Verify the [`ChangeHistory`] of the subject of a delegation and store it, unless a newer
+    /// version of that Identity is already known
+    async fn import_delegate(
+        &self,
+        delegate: &Identifier,
+        change_history: &ChangeHistory,
+    ) -> Result<()> {
+        let identity = Identity::import_from_change_history(
+            Some(delegate),
+            change_history.clone(),
+            self.verifying_vault.clone(),
+        )
+        .await
+        .map_err(|_| IdentityError::DelegationVerificationFailed)?;
+
+        if let Some(known_change_history) = self
+            .identities_repository
+            .retrieve_identity(delegate)
+            .await?
+        {
+            let known_identity = Identity::import_from_change_history(
+                Some(delegate),
+                known_change_history,
+                self.verifying_vault.clone(),
+            )
+            .await?;
+            match identity.compare(&known_identity) {
+                IdentityHistoryComparison::Conflict => {
+                    return Err(IdentityError::ConsistencyError.into())
+                }
+                IdentityHistoryComparison::Older | IdentityHistoryComparison::Equal => {
+                    return Ok(())
+                }
+                IdentityHistoryComparison::Newer => {}
+            }
+        }
+
+        self.identities_repository
+            .update_identity(delegate, identity.change_history())
+            .await
+    }
+
+    /// Verify the signature and the validity of a [`Credential`] issued by one of the issuers
+    async fn verify_signed_credential(
+        &self,
+        expected_subject: Option<&Identifier>,
+        issuers: &[Identifier],
+        credential_and_purpose_key: &CredentialAndPurposeKey,
+    ) -> Result<(CredentialData, PurposeKeyAttestationData, CredentialHash)> {
+        let (purpose_key_data, public_key) = self
+            .verify_authority_purpose_key(
+                issuers,
+                &credential_and_purpose_key.purpose_key_attestation,
+            )
+            .await?;
 
         let versioned_data_hash = self
             .verifying_vault
@@ -154,13 +337,11 @@ impl CredentialsVerification {
             //     In such cases some limited tolerance may be introduced.
         }
 
-        // FIXME: Verify if given authority is allowed to issue credentials with given Schema <-- Should be handled somewhere in the TrustContext
-        // FIXME: Verify if Schema aligns with Attributes <-- Should be handled somewhere in the TrustContext
-
-        Ok(CredentialAndPurposeKeyData {
+        Ok((
             credential_data,
             purpose_key_data,
-        })
+            CredentialHash(versioned_data_hash),
+        ))
     }
 
     /// Receive someone's [`Credential`]: verify and put attributes from it to the storage
@@ -178,6 +359,16 @@ impl CredentialsVerification {
             )
             .await?;
 
+        // remember the credential in order to remove its attributes if it gets revoked
+        self.credential_revocations.accept(
+            self.credential_hash(&credential_and_purpose_key_attestation.credential)
+                .await?,
+            subject,
+            &credential_data.authority,
+            credential_data.credential_data.expires_at,
+            now()?,
+        );
+
         let map = credential_data.credential_data.subject_attributes.map;
         let map: BTreeMap<_, _> = map
             .into_iter()
@@ -191,11 +382,138 @@ impl CredentialsVerification {
                     map,
                     now()?,
                     Some(credential_data.credential_data.expires_at),
-                    Some(credential_data.purpose_key_data.subject),
+                    Some(credential_data.authority),
                 ),
             )
             .await?;
 
         Ok(())
     }
+
+    /// Return the [`CredentialHash`] of a [`Credential`], used to revoke it
+    pub async fn credential_hash(&self, credential: &Credential) -> Result<CredentialHash> {
+        Ok(CredentialHash(
+            self.verifying_vault.sha256(&credential.data).await?,
+        ))
+    }
+
+    /// Verify a [`crate::models::RevocationList`]
+    pub async fn verify_revocation_list(
+        &self,
+        authorities: &[Identifier],
+        revocation_list_and_purpose_key: &RevocationListAndPurposeKey,
+    ) -> Result<(Identifier, RevocationListData)> {
+        let (purpose_key_data, public_key) = self
+            .verify_authority_purpose_key(
+                authorities,
+                &revocation_list_and_purpose_key.purpose_key_attestation,
+            )
+            .await?;
+
+        let revocation_list = &revocation_list_and_purpose_key.revocation_list;
+        let signed_data_hash = self
+            .verifying_vault
+            .sha256(&RevocationList::signed_data(&revocation_list.data))
+            .await?;
+        let signature = revocation_list.signature.clone().into();
+
+        if !self
+            .verifying_vault
+            .verify(&public_key, &signed_data_hash, &signature)
+            .await?
+        {
+            return Err(IdentityError::RevocationListVerificationFailed.into());
+        }
+
+        let versioned_data = revocation_list.get_versioned_data()?;
+        if versioned_data.version != 1 {
+            return Err(IdentityError::UnknownCredentialVersion.into());
+        }
+
+        let revocation_list_data = RevocationListData::get_data(&versioned_data)?;
+
+        if revocation_list_data.created_at < purpose_key_data.created_at
+            || revocation_list_data.expires_at > purpose_key_data.expires_at
+        {
+            // Revocation list validity time range should be inside the purpose key validity time range
+            return Err(IdentityError::RevocationListVerificationFailed.into());
+        }
+
+        let now = now()?;
+
+        if revocation_list_data.created_at > now
+            && revocation_list_data.created_at - now > MAX_ALLOWED_TIME_DRIFT
+        {
+            // Revocation list can't be created in the future
+            return Err(IdentityError::RevocationListVerificationFailed.into());
+        }
+
+        if revocation_list_data.expires_at < now {
+            // Revocation list expired
+            return Err(IdentityError::RevocationListVerificationFailed.into());
+        }
+
+        Ok((purpose_key_data.subject, revocation_list_data))
+    }
+
+    /// Receive a [`crate::models::RevocationList`] from an authority: verify it, use it to
+    /// reject revoked [`Credential`]s, and remove the attributes of the identities which
+    /// presented a revoked [`Credential`]
+    pub async fn receive_revocation_list(
+        &self,
+        authorities: &[Identifier],
+        revocation_list_and_purpose_key: &RevocationListAndPurposeKey,
+    ) -> Result<()> {
+        let (authority, revocation_list_data) = self
+            .verify_revocation_list(authorities, revocation_list_and_purpose_key)
+            .await?;
+        let sequence = revocation_list_data.sequence;
+
+        let revoked_subjects = self
+            .credential_revocations
+            .update(&authority, revocation_list_data)?;
+
+        for subject in revoked_subjects {
+            let attested_by_authority = self
+                .identities_repository
+                .get_attributes(&subject)
+                .await?
+                .map(|entry| entry.attested_by() == Some(authority.clone()))
+                .unwrap_or(false);
+            if attested_by_authority {
+                info!(%subject, %authority, "removing the attributes of a revoked credential");
+                self.identities_repository.delete(&subject).await?;
+            }
+        }
+
+        debug!(%authority, sequence, "received a revocation list");
+        Ok(())
+    }
+
+    /// Verify that a purpose key attestation was issued by one of the authorities and
+    /// return its credential signing key
+    async fn verify_authority_purpose_key(
+        &self,
+        authorities: &[Identifier],
+        purpose_key_attestation: &PurposeKeyAttestation,
+    ) -> Result<(PurposeKeyAttestationData, PublicKey)> {
+        let purpose_key_data = self
+            .purpose_keys_verification
+            .verify_purpose_key_attestation(None, purpose_key_attestation)
+            .await?;
+
+        if !authorities.contains(&purpose_key_data.subject) {
+            return Err(IdentityError::UnknownAuthority.into());
+        }
+
+        let public_key = match purpose_key_data.public_key.clone() {
+            PurposePublicKey::SecureChannelStaticKey(_) => {
+                return Err(IdentityError::InvalidKeyType.into())
+            }
+
+            PurposePublicKey::CredentialSigningKey(public_key) => public_key,
+        };
+
+        Ok((purpose_key_data, public_key.into()))
+    }
 }
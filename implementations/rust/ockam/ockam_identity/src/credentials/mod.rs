@@ -1,4 +1,5 @@
 mod authority_service;
+mod credential_revocations;
 #[allow(clippy::module_inception)]
 mod credentials;
 mod credentials_creation;
@@ -8,9 +9,12 @@ mod credentials_server;
 mod credentials_server_worker;
 mod credentials_verification;
 mod one_time_code;
+mod revocation_list_issuer;
+mod revocation_list_refresher;
 mod trust_context;
 
 pub use authority_service::*;
+pub use credential_revocations::*;
 pub use credentials::*;
 pub use credentials_creation::*;
 pub use credentials_issuer::*;
@@ -18,4 +22,6 @@ pub use credentials_retriever::*;
 pub use credentials_server::*;
 pub use credentials_verification::*;
 pub use one_time_code::*;
+pub use revocation_list_issuer::*;
+pub use revocation_list_refresher::*;
 pub use trust_context::*;
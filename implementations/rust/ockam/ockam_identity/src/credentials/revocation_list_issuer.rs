@@ -0,0 +1,251 @@
+use crate::models::{
+    CredentialHash, Identifier, RevocationListAndPurposeKey, RevokedCredential, RevokedSubject,
+};
+use crate::storage::{InMemoryStorage, Storage};
+use crate::utils::now;
+use crate::{secure_channel_required, Credentials, IdentitySecureChannelLocalInfo};
+use crate::{TimestampInSeconds, MAX_CREDENTIAL_VALIDITY};
+
+use core::time::Duration;
+use minicbor::{Decode, Decoder, Encode};
+use ockam_core::api::{Method, Request, Response};
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::ToString;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{api, Result, Route, Routed, Worker};
+use ockam_node::{Context, RpcClient};
+use tracing::{info, trace};
+
+/// Maximum duration for a valid revocation list in seconds (1 day).
+/// Clients are expected to fetch the revocation list much more frequently
+pub const MAX_REVOCATION_LIST_VALIDITY: Duration = Duration::from_secs(24 * 3600);
+
+const REVOCATIONS_ID: &str = "revocation_list";
+const REVOCATIONS_KEY: &str = "revocations";
+
+/// Subjects and credentials revoked by an authority, persisted in a [`Storage`]
+///
+/// WARNING: updates are not atomic, the revocations are expected to be modified
+/// by a single worker
+pub struct RevocationsStorage {
+    storage: Arc<dyn Storage>,
+}
+
+#[derive(Debug, Default, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct Revocations {
+    #[n(1)] sequence: u64,
+    #[n(2)] revoked_subjects: Vec<RevokedSubject>,
+    #[n(3)] revoked_credentials: Vec<RevokedCredential>,
+}
+
+impl RevocationsStorage {
+    /// Create revocations persisted in a storage
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Create revocations in memory
+    pub fn create() -> Arc<Self> {
+        Arc::new(Self::new(InMemoryStorage::create()))
+    }
+
+    /// Revoke all the credentials issued until now to a subject
+    pub async fn revoke_subject(&self, subject: &Identifier) -> Result<()> {
+        let mut revocations = self.load().await?;
+        revocations
+            .revoked_subjects
+            .retain(|r| &r.subject != subject);
+        revocations.revoked_subjects.push(RevokedSubject {
+            subject: subject.clone(),
+            revoked_at: now()?,
+        });
+        info!(%subject, "revoked the credentials of a subject");
+        self.store(revocations).await
+    }
+
+    /// Revoke a single credential
+    pub async fn revoke_credential(&self, credential_hash: &CredentialHash) -> Result<()> {
+        let mut revocations = self.load().await?;
+        if revocations
+            .revoked_credentials
+            .iter()
+            .any(|r| &r.credential_hash == credential_hash)
+        {
+            return Ok(());
+        }
+        revocations.revoked_credentials.push(RevokedCredential {
+            credential_hash: credential_hash.clone(),
+            revoked_at: now()?,
+        });
+        info!(%credential_hash, "revoked a credential");
+        self.store(revocations).await
+    }
+
+    /// Return the sequence number of the revocations, the revoked subjects and the revoked
+    /// credentials. Revocations older than the maximum credential validity are not returned
+    /// since all the credentials they apply to are expired
+    pub async fn revocations(&self) -> Result<(u64, Vec<RevokedSubject>, Vec<RevokedCredential>)> {
+        let revocations = self.load().await?;
+        let now = now()?;
+        let is_active = |revoked_at: &TimestampInSeconds| {
+            now.saturating_sub(**revoked_at) <= MAX_CREDENTIAL_VALIDITY.as_secs()
+        };
+
+        Ok((
+            revocations.sequence,
+            revocations
+                .revoked_subjects
+                .into_iter()
+                .filter(|r| is_active(&r.revoked_at))
+                .collect(),
+            revocations
+                .revoked_credentials
+                .into_iter()
+                .filter(|r| is_active(&r.revoked_at))
+                .collect(),
+        ))
+    }
+
+    async fn load(&self) -> Result<Revocations> {
+        match self.storage.get(REVOCATIONS_ID, REVOCATIONS_KEY).await? {
+            Some(data) => Ok(minicbor::decode(&data)?),
+            None => Ok(Revocations::default()),
+        }
+    }
+
+    async fn store(&self, mut revocations: Revocations) -> Result<()> {
+        revocations.sequence += 1;
+        self.storage
+            .set(
+                REVOCATIONS_ID,
+                REVOCATIONS_KEY.to_string(),
+                minicbor::to_vec(&revocations)?,
+            )
+            .await
+    }
+}
+
+/// This struct runs as a Worker to publish the revocation list of an authority
+pub struct RevocationListIssuer {
+    credentials: Arc<Credentials>,
+    issuer: Identifier,
+    revocations: Arc<RevocationsStorage>,
+}
+
+impl RevocationListIssuer {
+    /// Create a new revocation list issuer
+    pub fn new(
+        credentials: Arc<Credentials>,
+        issuer: &Identifier,
+        revocations: Arc<RevocationsStorage>,
+    ) -> Self {
+        Self {
+            credentials,
+            issuer: issuer.clone(),
+            revocations,
+        }
+    }
+
+    async fn issue_revocation_list(&self) -> Result<RevocationListAndPurposeKey> {
+        let (sequence, revoked_subjects, revoked_credentials) =
+            self.revocations.revocations().await?;
+
+        self.credentials
+            .credentials_creation()
+            .issue_revocation_list(
+                &self.issuer,
+                sequence,
+                revoked_subjects,
+                revoked_credentials,
+                MAX_REVOCATION_LIST_VALIDITY,
+            )
+            .await
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for RevocationListIssuer {
+    type Context = Context;
+    type Message = Vec<u8>;
+
+    async fn handle_message(&mut self, c: &mut Context, m: Routed<Self::Message>) -> Result<()> {
+        if let Ok(i) = IdentitySecureChannelLocalInfo::find_info(m.local_message()) {
+            let from = i.their_identity_id();
+            let mut dec = Decoder::new(m.as_body());
+            let req: Request = dec.decode()?;
+            trace! {
+                target: "ockam_identity::credentials::revocation_list_issuer",
+                from   = %from,
+                id     = %req.id(),
+                method = ?req.method(),
+                path   = %req.path(),
+                body   = %req.has_body(),
+                "request"
+            }
+            let res = match (req.method(), req.path()) {
+                (Some(Method::Get), "/") | (Some(Method::Get), "/revocation_list") => {
+                    match self.issue_revocation_list().await {
+                        Ok(list) => Response::ok(req.id()).body(list).to_vec()?,
+                        Err(error) => api::internal_error(&req, &error.to_string()).to_vec()?,
+                    }
+                }
+                _ => api::unknown_path(&req).to_vec()?,
+            };
+            c.send(m.return_route(), res).await
+        } else {
+            secure_channel_required(c, m).await
+        }
+    }
+}
+
+/// Client for a revocation list issuer
+pub struct RevocationListIssuerClient {
+    client: RpcClient,
+}
+
+impl RevocationListIssuerClient {
+    /// Create a new revocation list issuer client
+    /// The route needs to be a secure channel
+    pub async fn new(route: Route, ctx: &Context) -> Result<Self> {
+        Ok(RevocationListIssuerClient {
+            client: RpcClient::new(route, ctx).await?,
+        })
+    }
+
+    /// Return the current revocation list of the issuer
+    pub async fn revocation_list(&self) -> Result<RevocationListAndPurposeKey> {
+        self.client.request(&Request::get("/")).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_revocations_storage() -> Result<()> {
+        let revocations = RevocationsStorage::create();
+        assert_eq!(revocations.revocations().await?.0, 0);
+
+        let subject = Identifier([1; 20]);
+        revocations.revoke_subject(&subject).await?;
+        revocations
+            .revoke_credential(&CredentialHash([2; 32]))
+            .await?;
+        // revoking the same credential twice doesn't change the list
+        revocations
+            .revoke_credential(&CredentialHash([2; 32]))
+            .await?;
+        // revoking a subject again only updates its revocation time
+        revocations.revoke_subject(&subject).await?;
+
+        let (sequence, revoked_subjects, revoked_credentials) = revocations.revocations().await?;
+        assert_eq!(sequence, 3);
+        assert_eq!(revoked_subjects.len(), 1);
+        assert_eq!(revoked_credentials.len(), 1);
+        Ok(())
+    }
+}
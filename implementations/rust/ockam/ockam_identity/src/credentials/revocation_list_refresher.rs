@@ -0,0 +1,115 @@
+use crate::models::Identifier;
+use crate::{
+    Credentials, RemoteCredentialsRetrieverInfo, RevocationListIssuerClient, SecureChannelOptions,
+    SecureChannels, TrustMultiIdentifiersPolicy,
+};
+
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{route, Address, AllowSourceAddress, Result, Routed, Worker};
+use ockam_node::{Context, DelayedEvent, WorkerBuilder};
+use tracing::{debug, warn};
+
+/// Default interval between two retrievals of the revocation list of an authority
+pub const DEFAULT_REVOCATION_LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// This struct runs as a Worker to periodically retrieve the revocation list of an authority.
+/// Every new revocation list is used to reject the revoked credentials and to remove the
+/// attributes of the identities which already presented a revoked credential
+pub struct RevocationListRefresher {
+    secure_channels: Arc<SecureChannels>,
+    credentials: Arc<Credentials>,
+    identifier: Identifier,
+    authority: RemoteCredentialsRetrieverInfo,
+    refresh_interval: Duration,
+    refresh: DelayedEvent<Vec<u8>>,
+}
+
+impl RevocationListRefresher {
+    /// Start a worker retrieving the revocation list of an authority every `refresh_interval`,
+    /// using a secure channel created with the given identity.
+    /// The first retrieval happens right away
+    pub async fn start(
+        ctx: &Context,
+        secure_channels: Arc<SecureChannels>,
+        identifier: &Identifier,
+        authority: RemoteCredentialsRetrieverInfo,
+        refresh_interval: Duration,
+    ) -> Result<Address> {
+        let address = Address::random_tagged("RevocationListRefresher");
+        let refresh = DelayedEvent::create(ctx, address.clone(), vec![]).await?;
+        let refresh_source_address = refresh.address();
+
+        let refresher = Self {
+            credentials: secure_channels.identities().credentials(),
+            secure_channels,
+            identifier: identifier.clone(),
+            authority,
+            refresh_interval,
+            refresh,
+        };
+
+        WorkerBuilder::new(refresher)
+            .with_address(address.clone())
+            .with_incoming_access_control(AllowSourceAddress(refresh_source_address))
+            .start(ctx)
+            .await?;
+
+        Ok(address)
+    }
+
+    async fn refresh_revocation_list(&self, ctx: &Context) -> Result<()> {
+        let resolved_route = ctx
+            .resolve_transport_route(self.authority.route.clone())
+            .await?;
+
+        let options =
+            SecureChannelOptions::new().with_trust_policy(TrustMultiIdentifiersPolicy::new(vec![
+                self.authority.identifier.clone(),
+            ]));
+        let sc = self
+            .secure_channels
+            .create_secure_channel(ctx, &self.identifier, resolved_route, options)
+            .await?;
+
+        let result = async {
+            let client = RevocationListIssuerClient::new(
+                route![sc.clone(), self.authority.service_address.clone()],
+                ctx,
+            )
+            .await?;
+            let revocation_list = client.revocation_list().await?;
+            self.credentials
+                .credentials_verification()
+                .receive_revocation_list(&[self.authority.identifier.clone()], &revocation_list)
+                .await
+        }
+        .await;
+
+        self.secure_channels
+            .stop_secure_channel(ctx, sc.encryptor_address())
+            .await?;
+        result
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for RevocationListRefresher {
+    type Context = Context;
+    type Message = Vec<u8>;
+
+    async fn initialize(&mut self, _ctx: &mut Context) -> Result<()> {
+        self.refresh.schedule(Duration::ZERO).await
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, _m: Routed<Self::Message>) -> Result<()> {
+        debug!(authority = %self.authority.identifier, "retrieving the revocation list");
+        if let Err(e) = self.refresh_revocation_list(ctx).await {
+            // the current revocation list is kept until the next retrieval
+            warn!(authority = %self.authority.identifier, "cannot retrieve the revocation list: {e}");
+        }
+        self.refresh.schedule(self.refresh_interval).await
+    }
+}
@@ -66,6 +66,18 @@ pub enum IdentityError {
     ExpectedSecretKeyInsteadOfPublic,
     /// Expected Public Key, got Secret Key
     ExpectedPublicKeyInsteadOfSecret,
+    /// Credential was revoked by its issuer
+    CredentialRevoked,
+    /// Revocation List Verification Failed
+    RevocationListVerificationFailed,
+    /// The Revocation List is older than the one already received
+    StaleRevocationList,
+    /// Delegation Verification Failed
+    DelegationVerificationFailed,
+    /// The Credential is outside of the scope of its delegation
+    DelegationScopeExceeded,
+    /// The delegation allowing to issue a Credential expired
+    DelegationExpired,
 }
 
 impl ockam_core::compat::error::Error for IdentityError {}
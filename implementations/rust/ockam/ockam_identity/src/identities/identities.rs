@@ -1,8 +1,9 @@
 use crate::identities::{IdentitiesKeys, IdentitiesRepository};
 use crate::purpose_keys::storage::{PurposeKeysRepository, PurposeKeysStorage};
 use crate::{
-    Credentials, CredentialsServer, CredentialsServerModule, Identifier, IdentitiesBuilder,
-    IdentitiesCreation, IdentitiesReader, IdentitiesStorage, Identity, PurposeKeys, Vault,
+    CredentialRevocations, Credentials, CredentialsServer, CredentialsServerModule, Identifier,
+    IdentitiesBuilder, IdentitiesCreation, IdentitiesReader, IdentitiesStorage, Identity,
+    PurposeKeys, Vault,
 };
 
 use ockam_core::compat::sync::Arc;
@@ -15,6 +16,7 @@ pub struct Identities {
     vault: Vault,
     identities_repository: Arc<dyn IdentitiesRepository>,
     purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+    credential_revocations: Arc<CredentialRevocations>,
 }
 
 impl Identities {
@@ -33,6 +35,11 @@ impl Identities {
         self.purpose_keys_repository.clone()
     }
 
+    /// Return the credential revocations received from authorities
+    pub fn credential_revocations(&self) -> Arc<CredentialRevocations> {
+        self.credential_revocations.clone()
+    }
+
     /// Get an [`Identity`] from the repository
     pub async fn get_identity(&self, identifier: &Identifier) -> Result<Identity> {
         let change_history = self.identities_repository.get_identity(identifier).await?;
@@ -88,6 +95,7 @@ impl Identities {
             self.vault.verifying_vault.clone(),
             self.purpose_keys(),
             self.identities_repository.clone(),
+            self.credential_revocations.clone(),
         ))
     }
 
@@ -108,6 +116,7 @@ impl Identities {
             vault,
             identities_repository,
             purpose_keys_repository,
+            credential_revocations: CredentialRevocations::create(),
         }
     }
 
@@ -1,5 +1,6 @@
-use crate::models::{Credential, PurposeKeyAttestation};
+use crate::models::{ChangeHistory, Credential, PurposeKeyAttestation};
 use minicbor::{Decode, Encode};
+use ockam_core::compat::vec::Vec;
 
 /// [`Credential`] and the corresponding [`PurposeKeyAttestation`] that was used to issue that
 /// [`Credential`] and will be used to verify it
@@ -12,4 +13,11 @@ pub struct CredentialAndPurposeKey {
     /// Corresponding [`PurposeKeyAttestation`] that was used to issue that
     /// [`Credential`] and will be used to verify it
     #[n(2)] pub purpose_key_attestation: PurposeKeyAttestation,
+    /// Chain of delegations allowing the issuer of that [`Credential`] to issue it, when the
+    /// issuer is not an authority. The first delegation is issued by an authority and each
+    /// following delegation by the subject of the previous one
+    #[n(3)] pub delegations: Option<Vec<CredentialAndPurposeKey>>,
+    /// [`ChangeHistory`] of the subject of a delegation, so that the Credentials issued by
+    /// that subject can be verified by nodes which never received its Identity
+    #[n(4)] pub subject_change_history: Option<ChangeHistory>,
 }
@@ -0,0 +1,25 @@
+use crate::models::SchemaId;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::{collections::BTreeMap, vec::Vec};
+
+/// [`SchemaId`] of the [`super::Credential`]s delegating the issuance of Credentials
+/// to their subject. Their [`super::Attributes`] contain a single [`DELEGATION_SCOPE`] attribute
+pub const DELEGATION_SCHEMA: SchemaId = SchemaId(2);
+
+/// Name of the attribute containing the CBOR serialized [`DelegationScope`] of a delegation
+pub const DELEGATION_SCOPE: &[u8] = b"ockam_delegation_scope";
+
+/// Restrictions on the [`super::Credential`]s that a delegate can issue
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DelegationScope {
+    /// Attributes that the delegate can attest, with their allowed values.
+    /// An attribute without values can have any value
+    #[n(1)] pub attributes: BTreeMap<ByteVec, Vec<ByteVec>>,
+    /// Maximum validity of the Credentials issued by the delegate, in seconds
+    #[n(2)] pub max_ttl: u64,
+    /// True if the delegate can delegate a subset of its scope to another identity
+    #[n(3)] pub can_delegate: bool,
+}
@@ -1,9 +1,11 @@
 mod change_history;
 mod credential;
 mod credential_and_purpose_key;
+mod delegation;
 mod identifiers;
 mod public_keys;
 mod purpose_key_attestation;
+mod revocation_list;
 mod signatures;
 mod timestamp;
 mod utils;
@@ -12,9 +14,11 @@ mod versioned_data;
 pub use change_history::*;
 pub use credential::*;
 pub use credential_and_purpose_key::*;
+pub use delegation::*;
 pub use identifiers::*;
 pub use public_keys::*;
 pub use purpose_key_attestation::*;
+pub use revocation_list::*;
 pub use signatures::*;
 pub use timestamp::*;
 pub use versioned_data::*;
@@ -0,0 +1,83 @@
+use crate::models::{CredentialSignature, Identifier, PurposeKeyAttestation, TimestampInSeconds};
+use minicbor::{Decode, Encode};
+use ockam_core::compat::vec::Vec;
+
+/// CredentialHash length
+pub const CREDENTIAL_HASH_LEN: usize = 32;
+
+/// Unique identifier for a [`super::Credential`]
+/// Computed as the SHA256 of the [`super::Credential`] data, which is the data signed by the issuer
+#[derive(Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct CredentialHash(pub [u8; CREDENTIAL_HASH_LEN]);
+
+/// Domain separation tag prepended to the [`RevocationList`] data before it is hashed and signed.
+/// [`super::Credential`]s are signed with the same Purpose Key, so a signature over one of them
+/// must never be accepted for the other
+pub const REVOCATION_LIST_SIGNATURE_DOMAIN: &[u8] = b"ockam/revocation_list/v1";
+
+/// Revocation list, published by an Authority (issuer) to revoke [`super::Credential`]s before
+/// their expiration
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RevocationList {
+    /// CBOR serialized [`super::VersionedData`]
+    /// where VersionedData::data is CBOR serialized [`RevocationListData`]
+    #[cbor(with = "minicbor::bytes")]
+    #[n(1)] pub data: Vec<u8>,
+    /// Signature over [`REVOCATION_LIST_SIGNATURE_DOMAIN`] followed by the data field, using
+    /// the corresponding Credentials [`super::PurposeKeyAttestation`]
+    #[n(2)] pub signature: CredentialSignature,
+}
+
+/// [`RevocationList`] and the corresponding [`PurposeKeyAttestation`] that was used to sign that
+/// [`RevocationList`] and will be used to verify it
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RevocationListAndPurposeKey {
+    /// [`RevocationList`]
+    #[n(1)] pub revocation_list: RevocationList,
+    /// Corresponding Credentials [`PurposeKeyAttestation`]
+    #[n(2)] pub purpose_key_attestation: PurposeKeyAttestation,
+}
+
+/// Data inside a [`RevocationList`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RevocationListData {
+    /// Number incremented by the Authority (issuer) every time the list changes.
+    /// A list with a lower sequence number than the one already known must be rejected
+    #[n(1)] pub sequence: u64,
+    /// Subjects whose [`super::Credential`]s issued before a given time are revoked
+    #[n(2)] pub revoked_subjects: Vec<RevokedSubject>,
+    /// Individually revoked [`super::Credential`]s
+    #[n(3)] pub revoked_credentials: Vec<RevokedCredential>,
+    /// Creation [`TimestampInSeconds`] (UTC)
+    #[n(4)] pub created_at: TimestampInSeconds,
+    /// Expiration [`TimestampInSeconds`] (UTC)
+    #[n(5)] pub expires_at: TimestampInSeconds,
+}
+
+/// Revocation of all the [`super::Credential`]s issued to a subject up to a given time
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RevokedSubject {
+    /// Subject of the revoked [`super::Credential`]s
+    #[n(1)] pub subject: Identifier,
+    /// Revocation [`TimestampInSeconds`] (UTC). Credentials created after that time are valid
+    #[n(2)] pub revoked_at: TimestampInSeconds,
+}
+
+/// Revocation of a single [`super::Credential`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RevokedCredential {
+    /// [`CredentialHash`] of the revoked [`super::Credential`]
+    #[n(1)] pub credential_hash: CredentialHash,
+    /// Revocation [`TimestampInSeconds`] (UTC)
+    #[n(2)] pub revoked_at: TimestampInSeconds,
+}
@@ -1,9 +1,11 @@
 use crate::models::utils::get_versioned_data;
 use crate::models::{
-    CredentialData, CredentialSignature, Ed25519Signature, P256ECDSASignature, VersionedData,
+    CredentialAndPurposeKey, CredentialData, CredentialSignature, DelegationScope,
+    Ed25519Signature, P256ECDSASignature, VersionedData,
 };
 use crate::{Credential, IdentityError};
 
+use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 use ockam_vault::{SecretType, Signature};
 
@@ -14,6 +16,34 @@ impl Credential {
     }
 }
 
+impl CredentialAndPurposeKey {
+    /// Return the chain of delegations allowing the issuer to issue that [`Credential`],
+    /// which is empty when the issuer is an authority
+    pub fn delegations(&self) -> &[CredentialAndPurposeKey] {
+        self.delegations.as_deref().unwrap_or_default()
+    }
+
+    /// Return the chain of delegations ending with this delegation, which is given to the
+    /// issuer of a delegated [`Credential`]
+    pub fn delegation_chain(&self) -> Vec<CredentialAndPurposeKey> {
+        let mut chain = self.delegations().to_vec();
+        chain.push(CredentialAndPurposeKey {
+            credential: self.credential.clone(),
+            purpose_key_attestation: self.purpose_key_attestation.clone(),
+            delegations: None,
+            subject_change_history: self.subject_change_history.clone(),
+        });
+        chain
+    }
+
+    /// Return the [`DelegationScope`] of this delegation, without verifying it
+    pub fn delegation_scope(&self) -> Result<DelegationScope> {
+        let versioned_data = self.credential.get_versioned_data()?;
+        let credential_data = CredentialData::get_data(&versioned_data)?;
+        DelegationScope::from_attributes(&credential_data.subject_attributes)
+    }
+}
+
 impl CredentialData {
     /// Extract [`CredentialData`] from [`VersionedData`]
     pub fn get_data(versioned_data: &VersionedData) -> Result<Self> {
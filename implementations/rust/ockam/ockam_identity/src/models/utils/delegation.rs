@@ -0,0 +1,144 @@
+use crate::models::{
+    Attributes, CredentialData, DelegationScope, DELEGATION_SCHEMA, DELEGATION_SCOPE,
+};
+use crate::IdentityError;
+
+use core::time::Duration;
+use minicbor::bytes::ByteVec;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+impl DelegationScope {
+    /// Create a scope allowing no attributes, for Credentials valid up to `max_ttl`
+    pub fn new(max_ttl: Duration) -> Self {
+        Self {
+            attributes: BTreeMap::new(),
+            max_ttl: max_ttl.as_secs(),
+            can_delegate: false,
+        }
+    }
+
+    /// Allow an attribute with the given values, or with any value if `values` is empty
+    pub fn with_attribute(mut self, key: Vec<u8>, values: Vec<Vec<u8>>) -> Self {
+        self.attributes
+            .insert(key.into(), values.into_iter().map(ByteVec::from).collect());
+        self
+    }
+
+    /// Allow the delegate to delegate a subset of this scope
+    pub fn with_can_delegate(mut self, can_delegate: bool) -> Self {
+        self.can_delegate = can_delegate;
+        self
+    }
+
+    /// Return the [`Attributes`] of a delegation [`crate::models::Credential`] with this scope
+    pub fn to_attributes(&self) -> Result<Attributes> {
+        let mut map = BTreeMap::new();
+        map.insert(
+            DELEGATION_SCOPE.to_vec().into(),
+            minicbor::to_vec(self)?.into(),
+        );
+        Ok(Attributes {
+            schema: DELEGATION_SCHEMA,
+            map,
+        })
+    }
+
+    /// Extract the scope from the [`Attributes`] of a delegation [`crate::models::Credential`]
+    pub fn from_attributes(attributes: &Attributes) -> Result<Self> {
+        if attributes.schema != DELEGATION_SCHEMA {
+            return Err(IdentityError::DelegationVerificationFailed.into());
+        }
+        match attributes
+            .map
+            .get(&ByteVec::from(DELEGATION_SCOPE.to_vec()))
+        {
+            Some(scope) => Ok(minicbor::decode(scope)?),
+            None => Err(IdentityError::DelegationVerificationFailed.into()),
+        }
+    }
+
+    /// Return true if a Credential has only allowed attributes and values,
+    /// and is not valid for longer than the maximum ttl
+    pub fn allows(&self, credential_data: &CredentialData) -> bool {
+        let ttl = credential_data
+            .expires_at
+            .saturating_sub(*credential_data.created_at);
+        ttl <= self.max_ttl && self.allows_attributes(&credential_data.subject_attributes)
+    }
+
+    /// Return true if all the attributes and their values are allowed
+    pub fn allows_attributes(&self, attributes: &Attributes) -> bool {
+        attributes
+            .map
+            .iter()
+            .all(|(key, value)| match self.attributes.get(key) {
+                Some(values) => values.is_empty() || values.contains(value),
+                None => false,
+            })
+    }
+
+    /// Return true if another scope does not allow more than this one
+    pub fn contains(&self, other: &DelegationScope) -> bool {
+        other.max_ttl <= self.max_ttl
+            && other
+                .attributes
+                .iter()
+                .all(|(key, other_values)| match self.attributes.get(key) {
+                    Some(values) => {
+                        values.is_empty()
+                            || (!other_values.is_empty()
+                                && other_values.iter().all(|v| values.contains(v)))
+                    }
+                    None => false,
+                })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SchemaId, TimestampInSeconds};
+
+    fn credential_data(attributes: &[(&str, &str)], ttl: u64) -> CredentialData {
+        CredentialData {
+            subject: None,
+            subject_latest_change_hash: None,
+            subject_attributes: Attributes {
+                schema: SchemaId(1),
+                map: attributes
+                    .iter()
+                    .map(|(k, v)| (k.as_bytes().to_vec().into(), v.as_bytes().to_vec().into()))
+                    .collect(),
+            },
+            created_at: TimestampInSeconds(100),
+            expires_at: TimestampInSeconds(100 + ttl),
+        }
+    }
+
+    #[test]
+    fn test_delegation_scope() -> Result<()> {
+        let scope = DelegationScope::new(Duration::from_secs(60))
+            .with_attribute(b"role".to_vec(), vec![b"device".to_vec()])
+            .with_attribute(b"team".to_vec(), vec![]);
+        assert_eq!(
+            DelegationScope::from_attributes(&scope.to_attributes()?)?,
+            scope
+        );
+
+        assert!(scope.allows(&credential_data(&[("role", "device"), ("team", "a")], 60)));
+        assert!(!scope.allows(&credential_data(&[("role", "admin")], 60)));
+        assert!(!scope.allows(&credential_data(&[("project", "p")], 60)));
+        assert!(!scope.allows(&credential_data(&[("role", "device")], 61)));
+
+        let narrower = DelegationScope::new(Duration::from_secs(30))
+            .with_attribute(b"team".to_vec(), vec![b"a".to_vec()]);
+        assert!(scope.contains(&narrower));
+        assert!(!narrower.contains(&scope));
+        let any_role =
+            DelegationScope::new(Duration::from_secs(30)).with_attribute(b"role".to_vec(), vec![]);
+        assert!(!scope.contains(&any_role));
+        Ok(())
+    }
+}
@@ -7,8 +7,10 @@ fn get_versioned_data<'a, T: Decode<'a, ()>>(data: &'a [u8]) -> Result<T> {
 
 mod change_history;
 mod credentials;
+mod delegation;
 mod identifiers;
 mod public_keys;
 mod purpose_key_attestation;
+mod revocation_list;
 mod signatures;
 mod timestamp;
@@ -0,0 +1,86 @@
+use crate::models::utils::get_versioned_data;
+use crate::models::{
+    CredentialHash, RevocationList, RevocationListData, VersionedData, CREDENTIAL_HASH_LEN,
+    REVOCATION_LIST_SIGNATURE_DOMAIN,
+};
+use crate::IdentityError;
+
+use core::fmt::{Display, Formatter};
+use core::ops::Deref;
+use minicbor::bytes::ByteArray;
+use minicbor::encode::Write;
+use minicbor::{Decode, Decoder, Encode, Encoder};
+use ockam_core::compat::string::String;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{Error, Result};
+
+impl RevocationList {
+    /// Extract [`VersionedData`]
+    pub fn get_versioned_data(&self) -> Result<VersionedData> {
+        get_versioned_data(&self.data)
+    }
+
+    /// Bytes signed by the issuer for the given data: the revocation list domain separation tag,
+    /// followed by the data
+    pub fn signed_data(data: &[u8]) -> Vec<u8> {
+        let mut signed_data = REVOCATION_LIST_SIGNATURE_DOMAIN.to_vec();
+        signed_data.extend_from_slice(data);
+        signed_data
+    }
+}
+
+impl RevocationListData {
+    /// Extract [`RevocationListData`] from [`VersionedData`]
+    pub fn get_data(versioned_data: &VersionedData) -> Result<Self> {
+        Ok(minicbor::decode(&versioned_data.data)?)
+    }
+}
+
+impl<C> Encode<C> for CredentialHash {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        ByteArray::from(self.0).encode(e, ctx)
+    }
+}
+
+impl<'b, C> Decode<'b, C> for CredentialHash {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let data = ByteArray::<CREDENTIAL_HASH_LEN>::decode(d, ctx)?;
+
+        Ok(Self(*data.deref()))
+    }
+}
+
+impl Display for CredentialHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&hex::encode(self.0.as_ref()))
+    }
+}
+
+impl From<[u8; CREDENTIAL_HASH_LEN]> for CredentialHash {
+    fn from(value: [u8; CREDENTIAL_HASH_LEN]) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<&str> for CredentialHash {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        let data = hex::decode(value.trim()).map_err(|_| IdentityError::InvalidHex)?;
+        <[u8; CREDENTIAL_HASH_LEN]>::try_from(data.as_slice())
+            .map(Self)
+            .map_err(|_| IdentityError::InvalidHex.into())
+    }
+}
+
+impl TryFrom<String> for CredentialHash {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::try_from(value.as_str())
+    }
+}
@@ -0,0 +1,105 @@
+use core::fmt;
+use core::fmt::Formatter;
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+use ockam_vault::constants::CHACHA20_POLY1305_SECRET_LENGTH_U32;
+use ockam_vault::{Buffer, KeyId, SecretAttributes, SecureChannelVault};
+
+/// Authenticated encryption used for the handshake messages and for the messages
+/// exchanged over an established secure channel
+///
+/// The cipher is negotiated during the handshake: the initiator sends the list of ciphers it
+/// supports, in order of preference, and the responder selects the first one that it supports.
+/// AES-GCM is used with peers which don't support this negotiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecureChannelCipher {
+    /// AES-256-GCM, best suited to platforms with AES hardware acceleration
+    #[default]
+    Aes256Gcm,
+    /// ChaCha20-Poly1305, best suited to platforms without AES hardware acceleration
+    ChaCha20Poly1305,
+}
+
+impl SecureChannelCipher {
+    /// Identifier of the cipher, as sent during the handshake
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            SecureChannelCipher::Aes256Gcm => 1,
+            SecureChannelCipher::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    /// Return the cipher corresponding to an identifier sent during the handshake, if it is known
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(SecureChannelCipher::Aes256Gcm),
+            2 => Some(SecureChannelCipher::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// Secret attributes for the keys used with this cipher
+    pub(crate) fn key_attributes(&self) -> SecretAttributes {
+        match self {
+            SecureChannelCipher::Aes256Gcm => SecretAttributes::Aes256,
+            SecureChannelCipher::ChaCha20Poly1305 => {
+                SecretAttributes::Buffer(CHACHA20_POLY1305_SECRET_LENGTH_U32)
+            }
+        }
+    }
+
+    /// Encrypt a plaintext with a key stored in the vault
+    pub(crate) async fn encrypt(
+        &self,
+        vault: &Arc<dyn SecureChannelVault>,
+        key_id: &KeyId,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        match self {
+            SecureChannelCipher::Aes256Gcm => {
+                vault
+                    .aead_aes_gcm_encrypt(key_id, plaintext, nonce, aad)
+                    .await
+            }
+            SecureChannelCipher::ChaCha20Poly1305 => {
+                vault
+                    .aead_chacha20_poly1305_encrypt(key_id, plaintext, nonce, aad)
+                    .await
+            }
+        }
+    }
+
+    /// Decrypt a ciphertext with a key stored in the vault
+    pub(crate) async fn decrypt(
+        &self,
+        vault: &Arc<dyn SecureChannelVault>,
+        key_id: &KeyId,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        match self {
+            SecureChannelCipher::Aes256Gcm => {
+                vault
+                    .aead_aes_gcm_decrypt(key_id, cipher_text, nonce, aad)
+                    .await
+            }
+            SecureChannelCipher::ChaCha20Poly1305 => {
+                vault
+                    .aead_chacha20_poly1305_decrypt(key_id, cipher_text, nonce, aad)
+                    .await
+            }
+        }
+    }
+}
+
+impl fmt::Display for SecureChannelCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SecureChannelCipher::Aes256Gcm => write!(f, "aes-256-gcm"),
+            SecureChannelCipher::ChaCha20Poly1305 => write!(f, "chacha20-poly1305"),
+        }
+    }
+}
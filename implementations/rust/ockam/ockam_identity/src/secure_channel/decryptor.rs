@@ -8,11 +8,15 @@ use ockam_vault::{KeyId, SecureChannelVault};
 use crate::models::Identifier;
 use crate::secure_channel::encryptor::{Encryptor, KEY_RENEWAL_INTERVAL};
 use crate::secure_channel::key_tracker::KeyTracker;
+use crate::secure_channel::message::{RefreshCredentials, SecureChannelMessage};
 use crate::secure_channel::nonce_tracker::NonceTracker;
 use crate::secure_channel::Addresses;
-use crate::{DecryptionRequest, DecryptionResponse, IdentityError, IdentitySecureChannelLocalInfo};
+use crate::{
+    DecryptionRequest, DecryptionResponse, Identities, IdentityError,
+    IdentitySecureChannelLocalInfo, SecureChannelCipher, TrustContext,
+};
 
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 pub(crate) struct DecryptorHandler {
     //for debug purposes only
@@ -20,21 +24,33 @@ pub(crate) struct DecryptorHandler {
     pub(crate) addresses: Addresses,
     pub(crate) their_identity_id: Identifier,
     pub(crate) decryptor: Decryptor,
+    identities: Arc<Identities>,
+    trust_context: Option<TrustContext>,
+    /// true if the other party sends framed secure channel messages
+    framed: bool,
 }
 
 impl DecryptorHandler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         role: &'static str,
         addresses: Addresses,
         key: KeyId,
-        vault: Arc<dyn SecureChannelVault>,
+        cipher: SecureChannelCipher,
+        identities: Arc<Identities>,
+        trust_context: Option<TrustContext>,
         their_identity_id: Identifier,
+        framed: bool,
     ) -> Self {
+        let vault = identities.vault().secure_channel_vault;
         Self {
             role,
             addresses,
             their_identity_id,
-            decryptor: Decryptor::new(key, vault),
+            decryptor: Decryptor::new(key, vault, cipher),
+            identities,
+            trust_context,
+            framed,
         }
     }
 
@@ -84,8 +100,17 @@ impl DecryptorHandler {
         // Decrypt the binary
         let decrypted_payload = self.decryptor.decrypt(&payload).await?;
 
+        match SecureChannelMessage::decode(decrypted_payload, self.framed)? {
+            SecureChannelMessage::Payload(payload) => self.handle_payload(ctx, payload).await,
+            SecureChannelMessage::RefreshCredentials(refresh) => {
+                self.handle_refresh_credentials(refresh).await
+            }
+        }
+    }
+
+    async fn handle_payload(&mut self, ctx: &mut Context, payload: Vec<u8>) -> Result<()> {
         // Encrypted data should be a TransportMessage
-        let mut transport_message = TransportMessage::decode(&decrypted_payload)?;
+        let mut transport_message = TransportMessage::decode(&payload)?;
 
         // Add encryptor hop in the return_route (instead of our address)
         transport_message
@@ -115,6 +140,44 @@ impl DecryptorHandler {
         }
     }
 
+    /// Verify the credentials presented again by the other party and update its attributes.
+    /// Invalid credentials are ignored, the previous attributes are kept until they expire
+    async fn handle_refresh_credentials(&mut self, refresh: RefreshCredentials) -> Result<()> {
+        info!(
+            "SecureChannel {} received fresh credentials {}",
+            self.role, &self.addresses.decryptor_remote
+        );
+
+        let trust_context = match &self.trust_context {
+            Some(trust_context) => trust_context,
+            None => {
+                warn!(
+                    "credentials presented by {} cannot be verified without a trust context",
+                    self.their_identity_id
+                );
+                return Ok(());
+            }
+        };
+        let authorities = trust_context.authorities().await?;
+
+        for credential in refresh.credentials {
+            if let Err(e) = self
+                .identities
+                .credentials()
+                .credentials_verification()
+                .receive_presented_credential(&self.their_identity_id, &authorities, &credential)
+                .await
+            {
+                warn!(
+                    "invalid credential presented by {}: {e}",
+                    self.their_identity_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     /// Remove the channel keys on shutdown
     pub(crate) async fn shutdown(&self) -> Result<()> {
         self.decryptor.shutdown().await
@@ -123,20 +186,26 @@ impl DecryptorHandler {
 
 pub(crate) struct Decryptor {
     vault: Arc<dyn SecureChannelVault>,
+    cipher: SecureChannelCipher,
     key_tracker: KeyTracker,
     nonce_tracker: NonceTracker,
 }
 
 impl Decryptor {
-    pub fn new(key_id: KeyId, vault: Arc<dyn SecureChannelVault>) -> Self {
+    pub fn new(
+        key_id: KeyId,
+        vault: Arc<dyn SecureChannelVault>,
+        cipher: SecureChannelCipher,
+    ) -> Self {
         Self {
             vault,
+            cipher,
             key_tracker: KeyTracker::new(key_id, KEY_RENEWAL_INTERVAL),
             nonce_tracker: NonceTracker::new(),
         }
     }
 
-    /// Restore 12-byte nonce needed for AES GCM from 8 byte that we use for noise
+    /// Restore 12-byte nonce needed for AES GCM and ChaCha20-Poly1305 from 8 byte that we use for noise
     fn convert_nonce_from_small(b: &[u8]) -> Result<(u64, [u8; 12])> {
         let bytes: [u8; 8] = b.try_into().map_err(|_| IdentityError::InvalidNonce)?;
 
@@ -158,14 +227,14 @@ impl Decryptor {
         let key = if let Some(key) = self.key_tracker.get_key(nonce)? {
             key
         } else {
-            Encryptor::rekey(&self.vault, &self.key_tracker.current_key).await?
+            Encryptor::rekey(&self.vault, self.cipher, &self.key_tracker.current_key).await?
         };
 
         // to improve protection against connection disruption attacks, we want to validate the
         // message with a decryption _before_ committing to the new state
         let result = self
-            .vault
-            .aead_aes_gcm_decrypt(&key, &payload[8..], &nonce_buffer, &[])
+            .cipher
+            .decrypt(&self.vault, &key, &payload[8..], &nonce_buffer, &[])
             .await;
 
         if result.is_ok() {
@@ -4,12 +4,13 @@ use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
 use ockam_vault::{KeyId, Secret, SecureChannelVault};
 
-use crate::IdentityError;
+use crate::{IdentityError, SecureChannelCipher};
 
 pub(crate) struct Encryptor {
     key: KeyId,
     nonce: u64,
     vault: Arc<dyn SecureChannelVault>,
+    cipher: SecureChannelCipher,
 }
 
 // To simplify the implementation we use the same constant for the size of the message
@@ -20,7 +21,7 @@ pub(crate) const KEY_RENEWAL_INTERVAL: u64 = 32;
 impl Encryptor {
     /// We use u64 nonce since it's convenient to work with it (e.g. increment)
     /// But we use 8-byte be format to send it over to the other side (according to noise spec)
-    /// And we use 12-byte be format for encryption, since AES-GCM wants 12 bytes
+    /// And we use 12-byte be format for encryption, since AES-GCM and ChaCha20-Poly1305 want 12 bytes
     pub(crate) fn convert_nonce_from_u64(nonce: u64) -> ([u8; 8], [u8; 12]) {
         let mut n: [u8; 12] = [0; 12];
         let b: [u8; 8] = nonce.to_be_bytes();
@@ -30,12 +31,16 @@ impl Encryptor {
         (b, n)
     }
 
-    pub async fn rekey(vault: &Arc<dyn SecureChannelVault>, key: &KeyId) -> Result<KeyId> {
+    pub async fn rekey(
+        vault: &Arc<dyn SecureChannelVault>,
+        cipher: SecureChannelCipher,
+        key: &KeyId,
+    ) -> Result<KeyId> {
         let nonce_buffer = Self::convert_nonce_from_u64(u64::MAX).1;
         let zeroes = [0u8; 32];
 
-        let new_key_buffer = vault
-            .aead_aes_gcm_encrypt(key, &zeroes, &nonce_buffer, &[])
+        let new_key_buffer = cipher
+            .encrypt(vault, key, &zeroes, &nonce_buffer, &[])
             .await?;
 
         let attributes = vault.get_secret_attributes(key).await?;
@@ -54,7 +59,7 @@ impl Encryptor {
         self.nonce += 1;
 
         if current_nonce > 0 && current_nonce % KEY_RENEWAL_INTERVAL == 0 {
-            let new_key = Self::rekey(&self.vault, &self.key).await?;
+            let new_key = Self::rekey(&self.vault, self.cipher, &self.key).await?;
             let old_key = core::mem::replace(&mut self.key, new_key);
             self.vault.delete_secret(old_key).await?;
         }
@@ -62,8 +67,8 @@ impl Encryptor {
         let (small_nonce, nonce) = Self::convert_nonce_from_u64(current_nonce);
 
         let mut cipher_text = self
-            .vault
-            .aead_aes_gcm_encrypt(&self.key, payload, &nonce, &[])
+            .cipher
+            .encrypt(&self.vault, &self.key, payload, &nonce, &[])
             .await?;
 
         let mut res = Vec::new();
@@ -73,8 +78,18 @@ impl Encryptor {
         Ok(res)
     }
 
-    pub fn new(key: KeyId, nonce: u64, vault: Arc<dyn SecureChannelVault>) -> Self {
-        Self { key, nonce, vault }
+    pub fn new(
+        key: KeyId,
+        nonce: u64,
+        vault: Arc<dyn SecureChannelVault>,
+        cipher: SecureChannelCipher,
+    ) -> Self {
+        Self {
+            key,
+            nonce,
+            vault,
+            cipher,
+        }
     }
 
     pub(crate) async fn shutdown(&self) -> Result<()> {
@@ -1,13 +1,25 @@
+use core::time::Duration;
 use ockam_core::compat::boxed::Box;
-use ockam_core::{async_trait, Decodable, Encodable, Route};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Address, Decodable, Encodable, Route};
 use ockam_core::{Any, Result, Routed, TransportMessage, Worker};
-use ockam_node::Context;
-use tracing::debug;
+use ockam_node::{Context, DelayedEvent};
+use tracing::{debug, info, warn};
 
+use crate::models::{CredentialAndPurposeKey, CredentialData, Identifier};
 use crate::secure_channel::addresses::Addresses;
 use crate::secure_channel::api::{EncryptionRequest, EncryptionResponse};
 use crate::secure_channel::encryptor::Encryptor;
-use crate::IdentityError;
+use crate::secure_channel::message::{RefreshCredentials, SecureChannelMessage};
+use crate::utils::now;
+use crate::{CredentialsRetriever, IdentityError, TimestampInSeconds};
+
+/// Minimum delay between two refreshes of the presented credentials
+const MIN_CREDENTIAL_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay before retrying when no fresh credential could be retrieved
+const CREDENTIAL_RETRY_INTERVAL: Duration = Duration::from_secs(10);
 
 pub(crate) struct EncryptorWorker {
     //for debug purposes only
@@ -15,6 +27,111 @@ pub(crate) struct EncryptorWorker {
     addresses: Addresses,
     remote_route: Route,
     encryptor: Encryptor,
+    credential_refresher: Option<CredentialRefresher>,
+    /// true if the other party accepts framed secure channel messages
+    framed: bool,
+}
+
+/// Retrieve fresh credentials and present them to the other party
+/// before the previously presented ones expire
+pub(crate) struct CredentialRefresher {
+    identifier: Identifier,
+    retriever: Arc<dyn CredentialsRetriever>,
+    refresh_time_gap: Duration,
+    expires_at: Option<TimestampInSeconds>,
+    refresh: DelayedEvent<Vec<u8>>,
+}
+
+impl CredentialRefresher {
+    /// Create a refresher for credentials presented during the handshake
+    pub(crate) fn new(
+        identifier: Identifier,
+        retriever: Arc<dyn CredentialsRetriever>,
+        refresh_time_gap: Duration,
+        presented_credentials: &[CredentialAndPurposeKey],
+        refresh: DelayedEvent<Vec<u8>>,
+    ) -> Result<Self> {
+        let mut expires_at = None;
+        for credential in presented_credentials {
+            let credential_expires_at = credential_expiration(credential)?;
+            expires_at = Some(match expires_at {
+                Some(e) if e < credential_expires_at => e,
+                _ => credential_expires_at,
+            });
+        }
+
+        Ok(Self {
+            identifier,
+            retriever,
+            refresh_time_gap,
+            expires_at,
+            refresh,
+        })
+    }
+
+    /// Address sending the scheduled refresh events
+    pub(crate) fn address(&self) -> Address {
+        self.refresh.address()
+    }
+
+    /// Schedule the next refresh some time before the expiration of the current credential
+    async fn schedule(&mut self) -> Result<()> {
+        let delay = match self.expires_at {
+            Some(expires_at) => refresh_delay(now()?, expires_at, self.refresh_time_gap),
+            None => Duration::ZERO,
+        };
+        self.refresh.schedule(delay).await
+    }
+
+    /// Retrieve a credential expiring after the current one.
+    /// Return None if no such credential is available yet
+    async fn retrieve(&mut self, ctx: &Context) -> Option<CredentialAndPurposeKey> {
+        let credential = match self.retriever.retrieve(ctx, &self.identifier).await {
+            Ok(credential) => credential,
+            Err(e) => {
+                warn!(identifier = %self.identifier, "cannot retrieve a fresh credential: {e}");
+                return None;
+            }
+        };
+
+        let expires_at = match credential_expiration(&credential) {
+            Ok(expires_at) => expires_at,
+            Err(e) => {
+                warn!(identifier = %self.identifier, "invalid credential: {e}");
+                return None;
+            }
+        };
+
+        // the retriever can return a cached credential until it is about to expire
+        if self
+            .expires_at
+            .map_or(false, |current| expires_at <= current)
+        {
+            debug!(identifier = %self.identifier, "the retrieved credential is not fresher");
+            return None;
+        }
+
+        self.expires_at = Some(expires_at);
+        Some(credential)
+    }
+}
+
+/// Return the expiration time of a credential
+fn credential_expiration(credential: &CredentialAndPurposeKey) -> Result<TimestampInSeconds> {
+    let versioned_data = credential.credential.get_versioned_data()?;
+    Ok(CredentialData::get_data(&versioned_data)?.expires_at)
+}
+
+/// Return the delay before refreshing a credential expiring at `expires_at`
+pub(crate) fn refresh_delay(
+    now: TimestampInSeconds,
+    expires_at: TimestampInSeconds,
+    refresh_time_gap: Duration,
+) -> Duration {
+    let delay = expires_at
+        .saturating_sub(*now)
+        .saturating_sub(refresh_time_gap.as_secs());
+    Duration::from_secs(delay).max(MIN_CREDENTIAL_REFRESH_INTERVAL)
 }
 
 impl EncryptorWorker {
@@ -23,12 +140,16 @@ impl EncryptorWorker {
         addresses: Addresses,
         remote_route: Route,
         encryptor: Encryptor,
+        credential_refresher: Option<CredentialRefresher>,
+        framed: bool,
     ) -> Self {
         Self {
             role,
             addresses,
             remote_route,
             encryptor,
+            credential_refresher,
+            framed,
         }
     }
 
@@ -84,8 +205,50 @@ impl EncryptorWorker {
             msg.into_transport_message().payload,
         );
 
+        self.send_message(ctx, SecureChannelMessage::Payload(msg.encode()?))
+            .await
+    }
+
+    /// Present fresh credentials to the other party
+    async fn handle_refresh_credentials(
+        &mut self,
+        ctx: &mut <Self as Worker>::Context,
+    ) -> Result<()> {
+        let refresher = match self.credential_refresher.as_mut() {
+            Some(refresher) => refresher,
+            None => return Ok(()),
+        };
+
+        let credential = match refresher.retrieve(ctx).await {
+            Some(credential) => credential,
+            None => return refresher.refresh.schedule(CREDENTIAL_RETRY_INTERVAL).await,
+        };
+        refresher.schedule().await?;
+
+        info!(
+            "SecureChannel {} presenting a fresh credential {}",
+            self.role, &self.addresses.encryptor
+        );
+        self.send_message(
+            ctx,
+            SecureChannelMessage::RefreshCredentials(RefreshCredentials {
+                credentials: vec![credential],
+            }),
+        )
+        .await
+    }
+
+    /// Encrypt a message and send it to the decryptor on the other side
+    async fn send_message(
+        &mut self,
+        ctx: &mut <Self as Worker>::Context,
+        message: SecureChannelMessage,
+    ) -> Result<()> {
         // Encrypt the message
-        let encrypted_payload = self.encryptor.encrypt(&msg.encode()?).await?;
+        let encrypted_payload = self
+            .encryptor
+            .encrypt(&message.encode(self.framed)?)
+            .await?;
 
         // Send the message to the decryptor on the other side
         ctx.send_from_address(
@@ -104,6 +267,13 @@ impl Worker for EncryptorWorker {
     type Message = Any;
     type Context = Context;
 
+    async fn initialize(&mut self, _context: &mut Self::Context) -> Result<()> {
+        if let Some(refresher) = self.credential_refresher.as_mut() {
+            refresher.schedule().await?;
+        }
+        Ok(())
+    }
+
     async fn handle_message(
         &mut self,
         ctx: &mut Self::Context,
@@ -115,6 +285,8 @@ impl Worker for EncryptorWorker {
             self.handle_encrypt(ctx, msg).await?;
         } else if msg_addr == self.addresses.encryptor_api {
             self.handle_encrypt_api(ctx, msg).await?;
+        } else if msg_addr == self.addresses.encryptor_internal {
+            self.handle_refresh_credentials(ctx).await?;
         } else {
             return Err(IdentityError::UnknownChannelMsgDestination.into());
         }
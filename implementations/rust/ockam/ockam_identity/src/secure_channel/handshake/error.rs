@@ -14,6 +14,12 @@ pub enum XXError {
     MessageLenMismatch,
     /// Invalid internal state.
     InvalidInternalState,
+    /// The other party did not use a hybrid key exchange.
+    HybridKeyExchangeRequired,
+    /// The parties don't support a common cipher.
+    NoCommonCipher,
+    /// The responder sent an unknown session resumption mode.
+    InvalidResumptionMode,
 }
 
 impl StdError for XXError {}
@@ -24,6 +30,9 @@ impl fmt::Display for XXError {
             Self::InternalVaultError => write!(f, "internal vault error"),
             Self::MessageLenMismatch => write!(f, "message length mismatch"),
             Self::InvalidInternalState => write!(f, "invalid internal state"),
+            Self::HybridKeyExchangeRequired => write!(f, "a hybrid key exchange is required"),
+            Self::NoCommonCipher => write!(f, "no common cipher"),
+            Self::InvalidResumptionMode => write!(f, "invalid session resumption mode"),
         }
     }
 }
@@ -35,6 +44,9 @@ impl From<XXError> for Error {
             XXError::InternalVaultError => Kind::Internal,
             XXError::MessageLenMismatch => Kind::Misuse,
             XXError::InvalidInternalState => Kind::Internal,
+            XXError::HybridKeyExchangeRequired => Kind::Unsupported,
+            XXError::NoCommonCipher => Kind::Unsupported,
+            XXError::InvalidResumptionMode => Kind::Invalid,
         };
 
         Error::new(Origin::KeyExchange, kind, err)
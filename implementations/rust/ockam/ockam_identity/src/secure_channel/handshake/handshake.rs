@@ -12,6 +12,7 @@ use Status::*;
 use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake_state_machine::{HandshakeKeys, Status};
 use crate::secure_channel::Role;
+use crate::SecureChannelCipher;
 
 /// The number of bytes in a SHA256 digest
 pub const SHA256_SIZE_U32: u32 = 32;
@@ -188,6 +189,90 @@ impl Handshake {
         Ok(payload)
     }
 
+    /// Encode the second message of a resumed session, from the responder to the initiator.
+    /// The resumption secret has already been mixed, so that message only contains the responder
+    /// ephemeral public key + an encrypted payload
+    pub(super) async fn encode_resumption_message2(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut state = self.state.clone();
+        // output e.pubKey
+        let e_pub_key = self.get_public_key(state.e()?).await?;
+        state.mix_hash(e_pub_key.data());
+        let mut message2 = e_pub_key.data().to_vec();
+
+        // ck, k = HKDF(ck, DH(e, re), 2)
+        let dh = self.dh(state.e()?, state.re()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // encrypt and output payload
+        let c = self.encrypt_and_hash(&mut state, payload).await?;
+        message2.extend(c);
+        self.state = state;
+        Ok(message2)
+    }
+
+    /// Decode the second message of a resumed session, sent by the responder
+    pub(super) async fn decode_resumption_message2(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        let mut state = self.state.clone();
+        // decode re.pubKey
+        let re_pub_key = Self::read_key(message)?;
+        state.re = Some(PublicKey::new(re_pub_key.to_vec(), X25519));
+        state.mix_hash(re_pub_key);
+
+        // ck, k = HKDF(ck, DH(e, re), 2)
+        let dh = self.dh(state.e()?, state.re()?).await?;
+        self.hkdf(&mut state, dh).await?;
+
+        // decrypt payload
+        let c = Self::read_resumption_message2_payload(message)?;
+        let payload = self.hash_and_decrypt(&mut state, c).await?;
+
+        self.state = state;
+        Ok(payload)
+    }
+
+    /// Derive the secret of a resumption ticket from the chaining key, before the final keys
+    /// are computed. Both parties of the handshake derive the same secret
+    pub(super) async fn derive_resumption_secret(&self) -> Result<KeyId> {
+        let hkdf_output = self
+            .vault
+            .hkdf_sha256(
+                self.state.ck()?,
+                b"resumption",
+                None,
+                vec![Self::ck_attributes()],
+            )
+            .await?;
+
+        let [secret]: [KeyId; 1] = hkdf_output
+            .try_into()
+            .map_err(|_| XXError::InternalVaultError)?;
+        Ok(secret)
+    }
+
+    /// Mix the cleartext prefix of message 2, with the selected cipher and the resumption mode,
+    /// into the handshake hash so that the encrypted payload of message 2 authenticates it.
+    /// Nothing is mixed when there is no prefix, as with parties which don't negotiate anything
+    pub(super) fn mix_message2_prefix(&mut self, prefix: &[u8]) {
+        if !prefix.is_empty() {
+            self.state.mix_hash(prefix);
+        }
+    }
+
+    /// Set the cipher used to encrypt the handshake messages once the ephemeral keys are exchanged,
+    /// and the messages of the secure channel
+    pub(super) fn set_cipher(&mut self, cipher: SecureChannelCipher) {
+        self.state.cipher = cipher;
+    }
+
+    /// Mix a shared secret obtained outside of the Diffie-Hellman exchanges, for example with a KEM
+    /// ck, k = HKDF(ck, secret, 2)
+    pub(super) async fn mix_key(&mut self, secret: KeyId) -> Result<()> {
+        let mut state = self.state.clone();
+        self.hkdf(&mut state, secret).await?;
+        self.state = state;
+        Ok(())
+    }
+
     /// Set the final state of the state machine by creating the encryption / decryption keys
     /// and return the other party identity
     pub(super) async fn set_final_state(&mut self, role: Role) -> Result<()> {
@@ -202,6 +287,7 @@ impl Handshake {
         state.status = Ready(HandshakeKeys {
             encryption_key,
             decryption_key,
+            cipher: state.cipher,
         });
         // now remove the ephemeral keys which are not useful anymore
         self.state = state;
@@ -238,7 +324,7 @@ impl Handshake {
     /// Import the k secret
     async fn import_k_secret(&self, content: Vec<u8>) -> Result<KeyId> {
         self.vault
-            .import_ephemeral_secret(Secret::new(content), Self::k_attributes())
+            .import_ephemeral_secret(Secret::new(content), self.state.cipher.key_attributes())
             .await
     }
 
@@ -267,7 +353,7 @@ impl Handshake {
                 state.ck()?,
                 b"",
                 Some(&dh),
-                vec![Self::ck_attributes(), Self::k_attributes()],
+                vec![Self::ck_attributes(), state.cipher.key_attributes()],
             )
             .await?;
 
@@ -301,7 +387,7 @@ impl Handshake {
                 state.ck()?,
                 b"",
                 None,
-                vec![Self::k_attributes(), Self::k_attributes()],
+                vec![state.cipher.key_attributes(), state.cipher.key_attributes()],
             )
             .await?;
 
@@ -319,9 +405,9 @@ impl Handshake {
     async fn hash_and_decrypt(&self, state: &mut HandshakeState, c: &[u8]) -> Result<Vec<u8>> {
         let mut nonce = [0u8; 12];
         nonce[4..].copy_from_slice(&state.n.to_be_bytes());
-        let result = self
-            .vault
-            .aead_aes_gcm_decrypt(state.k()?, c, nonce.as_ref(), &state.h)
+        let result = state
+            .cipher
+            .decrypt(&self.vault, state.k()?, c, nonce.as_ref(), &state.h)
             .await
             .map(|b| b.to_vec())?;
         state.mix_hash(c);
@@ -334,9 +420,9 @@ impl Handshake {
         let mut nonce = [0u8; 12];
         nonce[4..].copy_from_slice(&state.n.to_be_bytes());
 
-        let result = self
-            .vault
-            .aead_aes_gcm_encrypt(state.k()?, p, nonce.as_ref(), &state.h)
+        let result = state
+            .cipher
+            .encrypt(&self.vault, state.k()?, p, nonce.as_ref(), &state.h)
             .await?
             .to_vec();
         state.mix_hash(result.as_slice());
@@ -370,11 +456,6 @@ impl Handshake {
         SecretAttributes::Buffer(SHA256_SIZE_U32)
     }
 
-    /// Secret attributes for the k key
-    fn k_attributes() -> SecretAttributes {
-        SecretAttributes::Aes256
-    }
-
     /// Read the message 1 payload which is present after the public key
     fn read_message1_payload(message: &[u8]) -> Result<&[u8]> {
         Self::read_end(message, Self::key_size())
@@ -390,6 +471,11 @@ impl Handshake {
         Self::read_end(message, Self::key_size() + Self::encrypted_key_size())
     }
 
+    /// Read the encrypted payload of a resumed session message 2, which is present after the public key
+    fn read_resumption_message2_payload(message: &[u8]) -> Result<&[u8]> {
+        Self::read_end(message, Self::key_size())
+    }
+
     /// Read the message 3 encrypted key at the beginning of the message
     fn read_message3_encrypted_key(message: &[u8]) -> Result<&[u8]> {
         Self::read_start(message, Self::encrypted_key_size())
@@ -436,6 +522,7 @@ impl Handshake {
 
     /// Size of an encrypted key
     fn encrypted_key_size() -> usize {
+        // ChaCha20-Poly1305 has the same tag size as AES-GCM
         Self::key_size() + AES_GCM_TAGSIZE_USIZE
     }
 }
@@ -451,6 +538,7 @@ pub(super) struct HandshakeState {
     n: u64,
     h: [u8; SHA256_SIZE_USIZE],
     ck: Option<KeyId>,
+    pub(super) cipher: SecureChannelCipher,
     pub(super) status: Status,
 }
 
@@ -469,6 +557,7 @@ impl HandshakeState {
             n: 0,
             h: [0u8; SHA256_SIZE_USIZE],
             ck: None,
+            cipher: SecureChannelCipher::default(),
             status: Initial,
         }
     }
@@ -653,6 +742,120 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_full_handshake_chacha20_poly1305() -> Result<()> {
+        let vault = identities().vault().secure_channel_vault;
+        let initiator_static_key = vault
+            .generate_static_secret(SecretAttributes::X25519)
+            .await?;
+        let responder_static_key = vault
+            .generate_static_secret(SecretAttributes::X25519)
+            .await?;
+        let mut initiator = Handshake::new(vault.clone(), initiator_static_key).await?;
+        let mut responder = Handshake::new(vault.clone(), responder_static_key).await?;
+        initiator.initialize().await?;
+        responder.initialize().await?;
+
+        let message1 = initiator.encode_message1(b"").await?;
+        responder.decode_message1(&message1).await?;
+
+        initiator.set_cipher(SecureChannelCipher::ChaCha20Poly1305);
+        responder.set_cipher(SecureChannelCipher::ChaCha20Poly1305);
+
+        let message2 = responder.encode_message2(b"message 2").await?;
+        assert_eq!(initiator.decode_message2(&message2).await?, b"message 2");
+
+        let message3 = initiator.encode_message3(b"message 3").await?;
+        assert_eq!(responder.decode_message3(&message3).await?, b"message 3");
+
+        initiator.set_final_state(Role::Initiator).await?;
+        responder.set_final_state(Role::Responder).await?;
+
+        let initiator_keys = initiator.get_handshake_keys().unwrap();
+        let responder_keys = responder.get_handshake_keys().unwrap();
+        assert_eq!(initiator_keys.cipher, SecureChannelCipher::ChaCha20Poly1305);
+        assert_eq!(
+            vault
+                .get_secret_attributes(&initiator_keys.encryption_key)
+                .await?,
+            SecureChannelCipher::ChaCha20Poly1305.key_attributes()
+        );
+
+        let cipher_text = initiator_keys
+            .cipher
+            .encrypt(
+                &vault,
+                &initiator_keys.encryption_key,
+                b"hello",
+                &[0u8; 12],
+                &[],
+            )
+            .await?;
+        let plain_text = responder_keys
+            .cipher
+            .decrypt(
+                &vault,
+                &responder_keys.decryption_key,
+                &cipher_text,
+                &[0u8; 12],
+                &[],
+            )
+            .await?;
+        assert_eq!(plain_text, b"hello");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_full_handshake_cipher_mismatch() -> Result<()> {
+        let vault = identities().vault().secure_channel_vault;
+        let initiator_static_key = vault
+            .generate_static_secret(SecretAttributes::X25519)
+            .await?;
+        let responder_static_key = vault
+            .generate_static_secret(SecretAttributes::X25519)
+            .await?;
+        let mut initiator = Handshake::new(vault.clone(), initiator_static_key).await?;
+        let mut responder = Handshake::new(vault.clone(), responder_static_key).await?;
+        initiator.initialize().await?;
+        responder.initialize().await?;
+
+        let message1 = initiator.encode_message1(b"").await?;
+        responder.decode_message1(&message1).await?;
+
+        responder.set_cipher(SecureChannelCipher::ChaCha20Poly1305);
+        let message2 = responder.encode_message2(b"message 2").await?;
+        assert!(initiator.decode_message2(&message2).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_message2_prefix_is_authenticated() -> Result<()> {
+        let vault = identities().vault().secure_channel_vault;
+        for (responder_prefix, initiator_prefix) in [([1u8, 1u8], [1u8, 1u8]), ([1, 1], [1, 0])] {
+            let initiator_static_key = vault
+                .generate_static_secret(SecretAttributes::X25519)
+                .await?;
+            let responder_static_key = vault
+                .generate_static_secret(SecretAttributes::X25519)
+                .await?;
+            let mut initiator = Handshake::new(vault.clone(), initiator_static_key).await?;
+            let mut responder = Handshake::new(vault.clone(), responder_static_key).await?;
+            initiator.initialize().await?;
+            responder.initialize().await?;
+
+            let message1 = initiator.encode_message1(b"").await?;
+            responder.decode_message1(&message1).await?;
+
+            // the prefix received by the initiator must be the one sent by the responder
+            responder.mix_message2_prefix(&responder_prefix);
+            let message2 = responder.encode_message2(b"message 2").await?;
+            initiator.mix_message2_prefix(&initiator_prefix);
+            let decoded = initiator.decode_message2(&message2).await;
+            assert_eq!(decoded.is_ok(), responder_prefix == initiator_prefix);
+        }
+        Ok(())
+    }
+
     // --------------------
     // TESTS IMPLEMENTATION
     // --------------------
@@ -1,3 +1,4 @@
+use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
@@ -6,10 +7,15 @@ use ockam_vault::{KeyId, PublicKey, SecretType};
 use tracing::debug;
 
 use crate::models::{
-    ChangeHistory, CredentialAndPurposeKey, Identifier, PurposeKeyAttestation, PurposePublicKey,
+    ChangeHistory, CredentialAndPurposeKey, CredentialData, Identifier, PurposeKeyAttestation,
+    PurposePublicKey,
 };
+use crate::secure_channel::handshake::error::XXError;
+use crate::secure_channel::handshake::hybrid_key_exchange::HybridKeyExchangeOffer;
+use crate::secure_channel::{ResumptionTicket, StoredResumptionTicket};
 use crate::{
-    Identities, Identity, IdentityError, SecureChannelTrustInfo, TrustContext, TrustPolicy,
+    Identities, Identity, IdentityError, SecureChannelCipher, SecureChannelTrustInfo,
+    TimestampInSeconds, TrustContext, TrustPolicy,
 };
 
 /// Interface for a state machine in a key exchange protocol
@@ -34,6 +40,13 @@ pub(super) enum Action {
     SendMessage(Vec<u8>),
 }
 
+/// Sent by the responder at the beginning of message 2 when a session resumption was offered
+/// and a full handshake is performed instead
+pub(super) const FULL_HANDSHAKE: u8 = 0;
+
+/// Sent by the responder at the beginning of message 2 when a session is resumed
+pub(super) const RESUMED_SESSION: u8 = 1;
+
 /// List of possible states for the initiator or responder sides of the exchange
 #[derive(Debug, Clone)]
 pub(super) enum Status {
@@ -49,6 +62,7 @@ pub(super) enum Status {
 pub(super) struct HandshakeKeys {
     pub(super) encryption_key: KeyId,
     pub(super) decryption_key: KeyId,
+    pub(super) cipher: SecureChannelCipher,
 }
 
 /// The end result of a handshake with identity/credentials exchange is
@@ -57,6 +71,11 @@ pub(super) struct HandshakeKeys {
 pub(super) struct HandshakeResults {
     pub(super) handshake_keys: HandshakeKeys,
     pub(super) their_identifier: Identifier,
+    /// ticket which can be used to resume this session, if the responder issued one
+    pub(super) resumption_ticket: Option<StoredResumptionTicket>,
+    /// true if the other party exchanges framed secure channel messages and accepts refreshed
+    /// credentials. Otherwise only unframed transport messages can be sent
+    pub(super) their_credential_refresh: bool,
 }
 
 /// This struct implements functions common to both initiator and the responder state machines
@@ -67,7 +86,14 @@ pub(super) struct CommonStateMachine {
     pub(super) credentials: Vec<CredentialAndPurposeKey>,
     pub(super) trust_policy: Arc<dyn TrustPolicy>,
     pub(super) trust_context: Option<TrustContext>,
+    /// resumption ticket issued for this session, with its secret
+    pub(super) resumption_ticket: Option<(ResumptionTicket, KeyId)>,
+    /// true if the session keys derive from a hybrid key exchange, possibly in a resumed session
+    pub(super) hybrid_key_exchange: bool,
     their_identifier: Option<Identifier>,
+    /// credentials presented by the other party, verified again when a session is resumed
+    their_credentials: Vec<CredentialAndPurposeKey>,
+    their_credential_refresh: bool,
 }
 
 impl CommonStateMachine {
@@ -86,18 +112,23 @@ impl CommonStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            resumption_ticket: None,
+            hybrid_key_exchange: false,
             their_identifier: None,
+            their_credentials: vec![],
+            their_credential_refresh: false,
         }
     }
 
-    /// Prepare a payload containing the identity of the current party and serialize it.
+    /// Prepare a payload containing the identity of the current party.
     /// That payload contains:
     ///
     ///  - the current Identity Change History
     ///  - the current Secure Channel Purpose Key Attestation
     ///  - the Identity Credentials and corresponding Credentials Purpose Key Attestations
+    ///  - the support of credentials refreshes after the handshake
     ///
-    pub(super) async fn make_identity_payload(&self) -> Result<Vec<u8>> {
+    pub(super) async fn make_identity_payload(&self) -> Result<IdentityAndCredentials> {
         // prepare the payload that will be sent either in message 2 or message 3
         let change_history = self
             .identities
@@ -108,8 +139,11 @@ impl CommonStateMachine {
             change_history,
             purpose_key_attestation: self.purpose_key_attestation.clone(),
             credentials: self.credentials.clone(),
+            kem_ciphertext: None,
+            resumption_ticket: None,
+            credential_refresh: Some(true),
         };
-        Ok(minicbor::to_vec(payload)?)
+        Ok(payload)
     }
 
     /// Verify the identity sent by the other party: the Purpose Key and the credentials must be valid
@@ -157,20 +191,35 @@ impl CommonStateMachine {
             }
         }
 
-        self.verify_credentials(identity.identifier(), peer.credentials)
+        self.verify_credentials(identity.identifier(), peer.credentials.clone())
             .await?;
         self.their_identifier = Some(identity.identifier().clone());
+        self.their_credentials = peer.credentials;
+        // parties which don't advertise it expect the framing used before credentials refreshes
+        self.their_credential_refresh = peer.credential_refresh == Some(true);
         Ok(())
     }
 
-    /// Verify that the credentials sent by the other party are valid using a trust context
-    /// and store them
-    async fn verify_credentials(
-        &self,
-        their_identifier: &Identifier,
-        credentials: Vec<CredentialAndPurposeKey>,
+    /// Verify the identity of the other party when a session is resumed. That identity has been
+    /// verified when the resumption ticket was issued, but the trust policy might have changed since
+    /// and the credentials it presented might have expired or been revoked
+    pub(super) async fn verify_resumed_identity(
+        &mut self,
+        resumption: &StoredResumptionTicket,
     ) -> Result<()> {
-        // check our TrustPolicy
+        let their_identifier = resumption.their_identifier.clone();
+        let their_credentials = resumption.their_credentials.clone();
+        self.verify_credentials(&their_identifier, their_credentials.clone())
+            .await?;
+        self.their_identifier = Some(their_identifier);
+        self.their_credentials = their_credentials;
+        // sessions can only be resumed by parties which also support credentials refreshes
+        self.their_credential_refresh = true;
+        Ok(())
+    }
+
+    /// Check our TrustPolicy for the other party
+    async fn check_trust_policy(&self, their_identifier: &Identifier) -> Result<()> {
         let trust_info = SecureChannelTrustInfo::new(their_identifier.clone());
         let trusted = self.trust_policy.check(&trust_info).await?;
         if !trusted {
@@ -181,6 +230,17 @@ impl CommonStateMachine {
             "Initiator checked trust policy for SecureChannel from: {}",
             their_identifier
         );
+        Ok(())
+    }
+
+    /// Verify that the credentials sent by the other party are valid using a trust context
+    /// and store them
+    async fn verify_credentials(
+        &self,
+        their_identifier: &Identifier,
+        credentials: Vec<CredentialAndPurposeKey>,
+    ) -> Result<()> {
+        self.check_trust_policy(their_identifier).await?;
 
         if let Some(trust_context) = &self.trust_context {
             for credential in credentials {
@@ -208,18 +268,53 @@ impl CommonStateMachine {
         Ok(())
     }
 
+    /// Return the earliest expiration time of the credentials presented by the other party.
+    /// A session can't be resumed with credentials which have expired
+    fn their_credentials_expires_at(&self) -> Option<TimestampInSeconds> {
+        self.their_credentials
+            .iter()
+            .filter_map(|credential| {
+                let versioned_data = credential.credential.get_versioned_data().ok()?;
+                CredentialData::get_data(&versioned_data)
+                    .ok()
+                    .map(|data| data.expires_at)
+            })
+            .min()
+    }
+
     /// Return the results of the full handshake
     ///  - the other party identity
     ///  - the encryption and decryption keys to use on the next messages to exchange
+    ///  - a ticket to resume the session, if one was issued. The ticket expires with the
+    ///    credentials presented by the other party
     pub(super) fn make_handshake_results(
         &self,
         handshake_keys: Option<HandshakeKeys>,
     ) -> Option<HandshakeResults> {
         match (self.their_identifier.clone(), handshake_keys) {
-            (Some(their_identifier), Some(handshake_keys)) => Some(HandshakeResults {
-                their_identifier,
-                handshake_keys,
-            }),
+            (Some(their_identifier), Some(handshake_keys)) => {
+                let credentials_expires_at = self.their_credentials_expires_at();
+                let resumption_ticket =
+                    self.resumption_ticket.clone().map(|(mut ticket, secret)| {
+                        if let Some(expires_at) = credentials_expires_at {
+                            ticket.expires_at = ticket.expires_at.min(expires_at);
+                        }
+                        StoredResumptionTicket {
+                            our_identifier: self.identifier.clone(),
+                            their_identifier: their_identifier.clone(),
+                            their_credentials: self.their_credentials.clone(),
+                            ticket,
+                            secret,
+                            hybrid_key_exchange: self.hybrid_key_exchange,
+                        }
+                    });
+                Some(HandshakeResults {
+                    their_identifier,
+                    handshake_keys,
+                    resumption_ticket,
+                    their_credential_refresh: self.their_credential_refresh,
+                })
+            }
             _ => None,
         }
     }
@@ -239,4 +334,157 @@ pub(super) struct IdentityAndCredentials {
     /// Credentials associated to the identity along with corresponding Credentials Purpose Keys
     /// to verify those Credentials
     #[n(3)] pub(super) credentials: Vec<CredentialAndPurposeKey>,
+    /// KEM ciphertext sent by the responder when it accepts a hybrid key exchange
+    #[n(4)] pub(super) kem_ciphertext: Option<ByteVec>,
+    /// Session resumption ticket issued by the responder
+    #[n(5)] pub(super) resumption_ticket: Option<ResumptionTicket>,
+    /// Set when the party accepts framed secure channel messages after the handshake, including
+    /// refreshed credentials. Older parties don't send it and only exchange transport messages
+    #[n(6)] pub(super) credential_refresh: Option<bool>,
+}
+
+/// This internal structure is used as the payload of message 2 when a session is resumed
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(super) struct ResumedSession {
+    /// New session resumption ticket issued by the responder, replacing the one which was used
+    #[n(1)] pub(super) resumption_ticket: Option<ResumptionTicket>,
+}
+
+/// This internal structure is used as the payload of message 1 in the XX protocol, to negotiate
+/// the parameters of the handshake.
+/// It is empty when the default parameters are used, which is what older responders expect
+#[derive(Debug, Clone, Default, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(super) struct HandshakeOffer {
+    /// Hybrid key exchange proposed by the initiator
+    #[n(1)] pub(super) key_exchange: Option<HybridKeyExchangeOffer>,
+    /// Identifiers of the ciphers supported by the initiator, in order of preference
+    #[n(2)] pub(super) ciphers: Option<Vec<u8>>,
+    /// Identifier of a resumption ticket, when the initiator wants to resume a previous session
+    #[n(3)] pub(super) resumption_ticket: Option<ByteVec>,
+}
+
+impl HandshakeOffer {
+    /// Create an offer for a hybrid key exchange, a list of ciphers and a session resumption
+    pub(super) fn new(
+        key_exchange: Option<HybridKeyExchangeOffer>,
+        ciphers: &[SecureChannelCipher],
+        resumption_ticket: Option<&ResumptionTicket>,
+    ) -> Self {
+        // the list of ciphers is only sent when it differs from the default one
+        let ciphers = if ciphers == [SecureChannelCipher::default()] {
+            None
+        } else {
+            Some(ciphers.iter().map(|c| c.id()).collect())
+        };
+        Self {
+            key_exchange,
+            ciphers,
+            resumption_ticket: resumption_ticket.map(|ticket| ticket.id.clone()),
+        }
+    }
+
+    /// Serialize the offer, as an empty payload if there is nothing to negotiate
+    pub(super) fn encode(&self) -> Result<Vec<u8>> {
+        if self.key_exchange.is_none() && self.ciphers.is_none() && self.resumption_ticket.is_none()
+        {
+            Ok(vec![])
+        } else {
+            Ok(minicbor::to_vec(self)?)
+        }
+    }
+
+    /// Deserialize the payload of message 1
+    pub(super) fn decode(payload: &[u8]) -> Result<Self> {
+        if payload.is_empty() {
+            Ok(Self::default())
+        } else {
+            Ok(minicbor::decode(payload)?)
+        }
+    }
+
+    /// Responder: select the first cipher proposed by the initiator which is supported.
+    /// Initiators which don't send a list of ciphers only support the default cipher
+    pub(super) fn select_cipher(
+        &self,
+        supported: &[SecureChannelCipher],
+    ) -> Result<SecureChannelCipher> {
+        let proposed = match &self.ciphers {
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| SecureChannelCipher::from_id(*id))
+                .collect(),
+            None => vec![SecureChannelCipher::default()],
+        };
+        proposed
+            .into_iter()
+            .find(|cipher| supported.contains(cipher))
+            .ok_or_else(|| XXError::NoCommonCipher.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{identities, Purpose, TrustEveryonePolicy};
+
+    /// Identity payload sent by parties which don't support credentials refreshes
+    #[derive(Debug, Clone, Encode, Decode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct OldIdentityAndCredentials {
+        #[n(1)] change_history: ChangeHistory,
+        #[n(2)] purpose_key_attestation: PurposeKeyAttestation,
+        #[n(3)] credentials: Vec<CredentialAndPurposeKey>,
+        #[n(4)] kem_ciphertext: Option<ByteVec>,
+        #[n(5)] resumption_ticket: Option<ResumptionTicket>,
+    }
+
+    #[tokio::test]
+    async fn test_credential_refresh_negotiation_with_old_parties() -> Result<()> {
+        let identities = identities();
+        let (their_state_machine, their_public_key) = state_machine(identities.clone()).await?;
+        let (mut our_state_machine, _) = state_machine(identities).await?;
+
+        let payload = their_state_machine.make_identity_payload().await?;
+        assert_eq!(payload.credential_refresh, Some(true));
+        our_state_machine
+            .verify_identity(payload.clone(), &their_public_key)
+            .await?;
+        assert!(our_state_machine.their_credential_refresh);
+
+        // old parties ignore the new field, and don't send it
+        let old_payload: OldIdentityAndCredentials =
+            minicbor::decode(&minicbor::to_vec(&payload)?)?;
+        let old_payload: IdentityAndCredentials =
+            minicbor::decode(&minicbor::to_vec(&old_payload)?)?;
+        assert_eq!(old_payload.credential_refresh, None);
+        our_state_machine
+            .verify_identity(old_payload, &their_public_key)
+            .await?;
+        assert!(!our_state_machine.their_credential_refresh);
+        Ok(())
+    }
+
+    /// Create a state machine for a new identity, and return the public key of its purpose key
+    async fn state_machine(identities: Arc<Identities>) -> Result<(CommonStateMachine, PublicKey)> {
+        let identity = identities.identities_creation().create_identity().await?;
+        let purpose_key = identities
+            .purpose_keys()
+            .purpose_keys_creation()
+            .get_or_create_purpose_key(identity.identifier(), Purpose::SecureChannel)
+            .await?;
+        let state_machine = CommonStateMachine::new(
+            identities,
+            identity.identifier().clone(),
+            purpose_key.attestation().clone(),
+            vec![],
+            Arc::new(TrustEveryonePolicy),
+            None,
+        );
+        Ok((state_machine, purpose_key.public_key().clone()))
+    }
 }
@@ -6,15 +6,15 @@ use ockam_core::{
     AllowAll, Any, Decodable, DenyAll, Error, Mailbox, Mailboxes, OutgoingAccessControl, Route,
     Routed,
 };
-use ockam_core::{AllowOnwardAddress, Result, Worker};
+use ockam_core::{AllowOnwardAddress, AllowSourceAddress, Result, Worker};
 use ockam_node::callback::CallbackSender;
-use ockam_node::{Context, WorkerBuilder};
-use tracing::{debug, info};
+use ockam_node::{Context, DelayedEvent, WorkerBuilder};
+use tracing::{debug, info, warn};
 
 use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::decryptor::DecryptorHandler;
 use crate::secure_channel::encryptor::Encryptor;
-use crate::secure_channel::encryptor_worker::EncryptorWorker;
+use crate::secure_channel::encryptor_worker::{CredentialRefresher, EncryptorWorker};
 use crate::secure_channel::handshake::handshake_state_machine::Action::SendMessage;
 use crate::secure_channel::handshake::handshake_state_machine::Event::{
     Initialize, ReceivedMessage,
@@ -26,8 +26,8 @@ use crate::secure_channel::handshake::initiator_state_machine::InitiatorStateMac
 use crate::secure_channel::handshake::responder_state_machine::ResponderStateMachine;
 use crate::secure_channel::{Addresses, Role};
 use crate::{
-    IdentityError, PurposeKey, SecureChannelRegistryEntry, SecureChannels, TrustContext,
-    TrustPolicy,
+    CredentialsRetriever, IdentityError, KeyExchangeMode, PurposeKey, SecureChannelCipher,
+    SecureChannelRegistryEntry, SecureChannels, TrustContext, TrustPolicy,
 };
 
 /// This struct implements a Worker receiving and sending messages
@@ -41,6 +41,10 @@ pub(crate) struct HandshakeWorker {
     role: Role,
     remote_route: Option<Route>,
     decryptor_handler: Option<DecryptorHandler>,
+    trust_context: Option<TrustContext>,
+    presented_credentials: Vec<CredentialAndPurposeKey>,
+    credentials_retriever: Option<Arc<dyn CredentialsRetriever>>,
+    credential_refresh_time_gap: Duration,
 }
 
 #[ockam_core::worker]
@@ -98,19 +102,21 @@ impl Worker for HandshakeWorker {
         };
 
         let transport_message = message.into_transport_message();
-        if let SendMessage(message) = self
+        let action = self
             .state_machine
             .on_event(ReceivedMessage(Vec::<u8>::decode(
                 &transport_message.payload,
             )?))
-            .await?
-        {
-            // set the remote route by taking the most up to date message return route
-            // In the case of the initiator the first return route mentions the secure channel listener
-            // address so we need to wait for the return route corresponding to the remote handshake worker
-            // when it has been spawned
-            self.remote_route = Some(transport_message.return_route);
+            .await?;
 
+        // set the remote route by taking the most up to date message return route
+        // In the case of the initiator the first return route mentions the secure channel listener
+        // address so we need to wait for the return route corresponding to the remote handshake worker
+        // when it has been spawned. When a session is resumed, that return route comes with
+        // the last handshake message
+        self.remote_route = Some(transport_message.return_route);
+
+        if let SendMessage(message) = action {
             context
                 .send_from_address(
                     self.remote_route()?,
@@ -158,6 +164,12 @@ impl HandshakeWorker {
         trust_policy: Arc<dyn TrustPolicy>,
         decryptor_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
         credentials: Vec<CredentialAndPurposeKey>,
+        credentials_retriever: Option<Arc<dyn CredentialsRetriever>>,
+        credential_refresh_time_gap: Duration,
+        key_exchange_mode: KeyExchangeMode,
+        ciphers: Vec<SecureChannelCipher>,
+        resumption_identifier: Option<Identifier>,
+        resumption_ticket_lifetime: Option<Duration>,
         trust_context: Option<TrustContext>,
         remote_route: Option<Route>,
         timeout: Option<Duration>,
@@ -165,7 +177,18 @@ impl HandshakeWorker {
     ) -> Result<()> {
         let vault = secure_channels.identities.vault().secure_channel_vault;
         let identities = secure_channels.identities();
+        let presented_credentials = credentials.clone();
+        let resumption_tickets = secure_channels.resumption_tickets.clone();
         let state_machine: Box<dyn StateMachine> = if role.is_initiator() {
+            // the last ticket received from the responder can only be used once
+            let resumption = match &resumption_identifier {
+                Some(their_identifier) => {
+                    resumption_tickets
+                        .take_received(&identifier, their_identifier)
+                        .await?
+                }
+                None => None,
+            };
             Box::new(
                 InitiatorStateMachine::new(
                     vault,
@@ -174,7 +197,11 @@ impl HandshakeWorker {
                     purpose_key,
                     credentials,
                     trust_policy,
-                    trust_context,
+                    trust_context.clone(),
+                    key_exchange_mode,
+                    ciphers,
+                    resumption,
+                    resumption_tickets,
                 )
                 .await?,
             )
@@ -187,7 +214,11 @@ impl HandshakeWorker {
                     purpose_key,
                     credentials,
                     trust_policy,
-                    trust_context,
+                    trust_context.clone(),
+                    key_exchange_mode,
+                    ciphers,
+                    resumption_ticket_lifetime,
+                    resumption_tickets,
                 )
                 .await?,
             )
@@ -209,6 +240,10 @@ impl HandshakeWorker {
             remote_route: remote_route.clone(),
             addresses: addresses.clone(),
             decryptor_handler: None,
+            trust_context,
+            presented_credentials,
+            credentials_retriever,
+            credential_refresh_time_gap,
         };
 
         WorkerBuilder::new(worker)
@@ -293,12 +328,48 @@ impl HandshakeWorker {
             self.role.str(),
             self.addresses.clone(),
             handshake_results.handshake_keys.decryption_key,
-            self.secure_channels.identities.vault().secure_channel_vault,
+            handshake_results.handshake_keys.cipher,
+            self.secure_channels.identities(),
+            self.trust_context.clone(),
             handshake_results.their_identifier.clone(),
+            handshake_results.their_credential_refresh,
         );
 
         // create a separate encryptor worker which will be started independently
         {
+            // the credentials are presented again before they expire if they can be retrieved,
+            // and if the other party accepts them
+            let credential_refresher = match self.credentials_retriever.clone() {
+                Some(_) if !handshake_results.their_credential_refresh => {
+                    warn!(
+                        "SecureChannel {} at {}: the other party doesn't accept refreshed credentials",
+                        self.role.str(),
+                        &self.addresses.encryptor
+                    );
+                    None
+                }
+                Some(retriever) => Some(CredentialRefresher::new(
+                    self.identifier.clone(),
+                    retriever,
+                    self.credential_refresh_time_gap,
+                    &self.presented_credentials,
+                    DelayedEvent::create(
+                        context,
+                        self.addresses.encryptor_internal.clone(),
+                        vec![],
+                    )
+                    .await?,
+                )?),
+                None => None,
+            };
+            let internal_mailbox = credential_refresher.as_ref().map(|refresher| {
+                Mailbox::new(
+                    self.addresses.encryptor_internal.clone(),
+                    Arc::new(AllowSourceAddress(refresher.address())),
+                    Arc::new(DenyAll),
+                )
+            });
+
             let encryptor = EncryptorWorker::new(
                 self.role.str(),
                 self.addresses.clone(),
@@ -307,7 +378,10 @@ impl HandshakeWorker {
                     handshake_results.handshake_keys.encryption_key,
                     0,
                     self.secure_channels.identities.vault().secure_channel_vault,
+                    handshake_results.handshake_keys.cipher,
                 ),
+                credential_refresher,
+                handshake_results.their_credential_refresh,
             );
 
             let next_hop = self.remote_route()?.next()?.clone();
@@ -322,8 +396,11 @@ impl HandshakeWorker {
                 Arc::new(AllowAll),
             );
 
+            let mut mailboxes = vec![api_mailbox];
+            mailboxes.extend(internal_mailbox);
+
             WorkerBuilder::new(encryptor)
-                .with_mailboxes(Mailboxes::new(main_mailbox, vec![api_mailbox]))
+                .with_mailboxes(Mailboxes::new(main_mailbox, mailboxes))
                 .start(context)
                 .await?;
         }
@@ -357,6 +434,16 @@ impl HandshakeWorker {
             .secure_channel_registry()
             .register_channel(info)?;
 
+        // keep the ticket which can be used to resume this session later
+        if let Some(ticket) = handshake_results.resumption_ticket {
+            let resumption_tickets = &self.secure_channels.resumption_tickets;
+            if self.role.is_initiator() {
+                resumption_tickets.add_received(ticket).await?;
+            } else {
+                resumption_tickets.add_issued(ticket).await?;
+            }
+        }
+
         Ok(decryptor)
     }
 }
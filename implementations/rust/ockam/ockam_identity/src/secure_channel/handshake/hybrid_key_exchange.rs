@@ -0,0 +1,140 @@
+use minicbor::{Decode, Encode};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_vault::{KeyId, SecureChannelVault};
+use tracing::{debug, warn};
+
+use crate::secure_channel::handshake::error::XXError;
+use crate::KeyExchangeMode;
+
+/// KEM used in a hybrid key exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub(super) enum Kem {
+    #[n(1)] Kyber768,
+}
+
+/// Proposal for a hybrid key exchange, sent by the initiator as the payload of message 1
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(super) struct HybridKeyExchangeOffer {
+    #[n(1)] kem: Kem,
+    #[cbor(with = "minicbor::bytes")]
+    #[n(2)] encapsulation_key: Vec<u8>,
+}
+
+/// This struct negotiates the KEM part of a hybrid key exchange.
+///
+/// The initiator sends an ephemeral encapsulation key in message 1. When the responder accepts
+/// the hybrid key exchange, it encapsulates a shared secret with that key and returns the
+/// ciphertext in the encrypted payload of message 2. Both parties then mix the shared secret
+/// in the handshake keys, before message 3 and the final keys are derived.
+///
+/// Older responders ignore the message 1 payload, which results in a classic handshake.
+/// Since that payload is part of the handshake hash, it cannot be removed by an attacker
+/// to force a classic handshake.
+pub(super) struct HybridKeyExchange {
+    vault: Arc<dyn SecureChannelVault>,
+    mode: KeyExchangeMode,
+    decapsulation_key: Option<KeyId>,
+}
+
+impl HybridKeyExchange {
+    /// Create a new hybrid key exchange
+    pub(super) fn new(vault: Arc<dyn SecureChannelVault>, mode: KeyExchangeMode) -> Self {
+        Self {
+            vault,
+            mode,
+            decapsulation_key: None,
+        }
+    }
+
+    /// Return true if a classic handshake must be rejected
+    pub(super) fn requires_hybrid(&self) -> bool {
+        self.mode.requires_hybrid()
+    }
+
+    /// Initiator: return the offer to send in message 1, if any
+    pub(super) async fn offer(&mut self) -> Result<Option<HybridKeyExchangeOffer>> {
+        if !self.mode.is_hybrid() {
+            return Ok(None);
+        }
+
+        let (decapsulation_key, encapsulation_key) =
+            self.vault.generate_ephemeral_kem_secret().await?;
+        self.decapsulation_key = Some(decapsulation_key);
+
+        Ok(Some(HybridKeyExchangeOffer {
+            kem: Kem::Kyber768,
+            encapsulation_key,
+        }))
+    }
+
+    /// Responder: accept or ignore the offer sent in message 1.
+    /// Return the shared secret to mix and the ciphertext to send back when the offer is accepted
+    pub(super) async fn accept(
+        &self,
+        offer: Option<&HybridKeyExchangeOffer>,
+    ) -> Result<Option<(KeyId, Vec<u8>)>> {
+        let offer = match offer {
+            Some(offer) => offer,
+            None if self.mode.requires_hybrid() => {
+                return Err(XXError::HybridKeyExchangeRequired.into())
+            }
+            None => return Ok(None),
+        };
+
+        if !self.mode.is_hybrid() {
+            debug!("ignoring the hybrid key exchange proposed by the initiator");
+            return Ok(None);
+        }
+
+        let (shared_secret, ciphertext) = match offer.kem {
+            Kem::Kyber768 => self.vault.kem_encapsulate(&offer.encapsulation_key).await?,
+        };
+
+        Ok(Some((shared_secret, ciphertext)))
+    }
+
+    /// Initiator: delete the decapsulation key when the responder resumed a previous session
+    /// instead of performing a new key exchange
+    pub(super) async fn discard(&mut self) -> Result<()> {
+        if let Some(decapsulation_key) = self.decapsulation_key.take() {
+            self.vault.delete_secret(decapsulation_key).await?;
+        }
+        Ok(())
+    }
+
+    /// Initiator: return the shared secret to mix, using the ciphertext sent in message 2.
+    /// The responder did not accept the hybrid key exchange if there is no ciphertext
+    pub(super) async fn complete(&mut self, ciphertext: Option<&[u8]>) -> Result<Option<KeyId>> {
+        let decapsulation_key = match self.decapsulation_key.take() {
+            Some(decapsulation_key) => decapsulation_key,
+            None if ciphertext.is_some() => return Err(XXError::InvalidInternalState.into()),
+            None => return Ok(None),
+        };
+
+        let shared_secret = match ciphertext {
+            Some(ciphertext) => Some(
+                self.vault
+                    .kem_decapsulate(&decapsulation_key, ciphertext)
+                    .await,
+            ),
+            None => None,
+        };
+        // the decapsulation key is only used once
+        self.vault.delete_secret(decapsulation_key).await?;
+
+        match shared_secret {
+            Some(shared_secret) => Ok(Some(shared_secret?)),
+            None if self.mode.requires_hybrid() => Err(XXError::HybridKeyExchangeRequired.into()),
+            None => {
+                warn!("the responder does not support hybrid key exchanges, falling back to a classic handshake");
+                Ok(None)
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
-use ockam_vault::{PublicKey, SecureChannelVault};
+use ockam_vault::{KeyId, PublicKey, SecureChannelVault};
 use Action::*;
 use Event::*;
 use Role::*;
@@ -14,10 +14,14 @@ use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake::Handshake;
 use crate::secure_channel::handshake::handshake_state_machine::{
-    Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
-    StateMachine, Status,
+    Action, CommonStateMachine, Event, HandshakeKeys, HandshakeOffer, HandshakeResults,
+    IdentityAndCredentials, ResumedSession, StateMachine, Status, FULL_HANDSHAKE, RESUMED_SESSION,
+};
+use crate::secure_channel::handshake::hybrid_key_exchange::HybridKeyExchange;
+use crate::secure_channel::{ResumptionTickets, StoredResumptionTicket};
+use crate::{
+    Identities, KeyExchangeMode, PurposeKey, Role, SecureChannelCipher, TrustContext, TrustPolicy,
 };
-use crate::{Identities, PurposeKey, Role, TrustContext, TrustPolicy};
 
 /// Implementation of a state machine for the key exchange on the initiator side
 #[async_trait]
@@ -28,24 +32,56 @@ impl StateMachine for InitiatorStateMachine {
             // Initialize the handshake and send message 1
             (Initial, Initialize) => {
                 self.initialize_handshake().await?;
-                let message1 = self.encode_message1(&[]).await?;
+                let offer = HandshakeOffer::new(
+                    self.key_exchange.offer().await?,
+                    &self.ciphers,
+                    self.resumption
+                        .as_ref()
+                        .map(|resumption| &resumption.ticket),
+                );
+                self.ciphers_offered = offer.ciphers.is_some();
+                let message1 = self.encode_message1(&offer.encode()?).await?;
 
                 // Send message 1 and wait for message 2
                 self.handshake.state.status = WaitingForMessage2;
                 Ok(SendMessage(message1))
             }
             // Process message 2 and send message 3
-            (WaitingForMessage2, ReceivedMessage(message)) => {
-                let message2_payload = self.decode_message2(&message).await?;
-                let their_identity_payload: IdentityAndCredentials =
+            (WaitingForMessage2, ReceivedMessage(full_message)) => {
+                let message = self.read_selected_cipher(&full_message)?;
+                let (resumed, message) = self.read_resumption_mode(message).await?;
+                // the cleartext prefix is authenticated by the encrypted payload of message 2
+                self.mix_message2_prefix(&full_message[..full_message.len() - message.len()]);
+                if resumed {
+                    return self.resume_session(message).await;
+                }
+                let message2_payload = self.decode_message2(message).await?;
+                let mut their_identity_payload: IdentityAndCredentials =
                     minicbor::decode(&message2_payload)?;
+                let kem_ciphertext = their_identity_payload.kem_ciphertext.take();
+                let resumption_ticket = their_identity_payload.resumption_ticket.take();
                 self.verify_identity(their_identity_payload, &self.handshake.state.rs()?.clone())
                     .await?;
+                // mix the KEM shared secret before deriving the keys of message 3
+                if let Some(shared_secret) = self
+                    .key_exchange
+                    .complete(kem_ciphertext.as_deref().map(|c| c.as_slice()))
+                    .await?
+                {
+                    self.mix_key(shared_secret).await?;
+                    self.common.hybrid_key_exchange = true;
+                }
                 let identity_payload = self
                     .identity_payload
                     .take()
                     .ok_or(XXError::InvalidInternalState)?;
-                let message3 = self.encode_message3(&identity_payload).await?;
+                let message3 = self
+                    .encode_message3(&minicbor::to_vec(identity_payload)?)
+                    .await?;
+                if let Some(ticket) = resumption_ticket {
+                    let secret = self.derive_resumption_secret().await?;
+                    self.common.resumption_ticket = Some((ticket, secret));
+                }
                 self.set_final_state(Initiator).await?;
                 Ok(SendMessage(message3))
             }
@@ -70,14 +106,23 @@ impl StateMachine for InitiatorStateMachine {
 pub(super) struct InitiatorStateMachine {
     pub(super) common: CommonStateMachine,
     pub(super) handshake: Handshake,
-    /// this serialized payload contains an identity, its credentials and a signature of its static key
-    pub(super) identity_payload: Option<Vec<u8>>,
+    /// this payload contains an identity, its credentials and a signature of its static key
+    pub(super) identity_payload: Option<IdentityAndCredentials>,
+    pub(super) key_exchange: HybridKeyExchange,
+    /// ciphers supported by the initiator, in order of preference
+    pub(super) ciphers: Vec<SecureChannelCipher>,
+    /// true if the list of ciphers was sent to the responder in message 1
+    pub(super) ciphers_offered: bool,
+    /// ticket sent to the responder in message 1 to resume a previous session
+    pub(super) resumption: Option<StoredResumptionTicket>,
+    pub(super) resumption_tickets: ResumptionTickets,
 }
 
 impl InitiatorStateMachine {
     delegate! {
         to self.common {
             async fn verify_identity(&mut self, peer: IdentityAndCredentials, peer_public_key: &PublicKey) -> Result<()>;
+            async fn verify_resumed_identity(&mut self, resumption: &StoredResumptionTicket) -> Result<()>;
             fn make_handshake_results(&self, handshake_keys: Option<HandshakeKeys>) -> Option<HandshakeResults>;
         }
     }
@@ -87,7 +132,12 @@ impl InitiatorStateMachine {
             async fn initialize_handshake(&mut self) -> Result<()>;
             async fn encode_message1(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
             async fn decode_message2(&mut self, message: &[u8]) -> Result<Vec<u8>>;
+            async fn decode_resumption_message2(&mut self, message: &[u8]) -> Result<Vec<u8>>;
+            async fn derive_resumption_secret(&self) -> Result<KeyId>;
             async fn encode_message3(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
+            fn set_cipher(&mut self, cipher: SecureChannelCipher);
+            async fn mix_key(&mut self, secret: KeyId) -> Result<()>;
+            fn mix_message2_prefix(&mut self, prefix: &[u8]);
             async fn set_final_state(&mut self, role: Role) -> Result<()>;
             fn get_handshake_keys(&self) -> Option<HandshakeKeys>;
         }
@@ -95,6 +145,7 @@ impl InitiatorStateMachine {
 }
 
 impl InitiatorStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn SecureChannelVault>,
         identities: Arc<Identities>,
@@ -103,6 +154,10 @@ impl InitiatorStateMachine {
         credentials: Vec<CredentialAndPurposeKey>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        key_exchange_mode: KeyExchangeMode,
+        ciphers: Vec<SecureChannelCipher>,
+        resumption: Option<StoredResumptionTicket>,
+        resumption_tickets: ResumptionTickets,
     ) -> Result<InitiatorStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -114,10 +169,91 @@ impl InitiatorStateMachine {
         );
         let identity_payload = common.make_identity_payload().await?;
 
+        // a session established with a classic key exchange is not resumed
+        // when a hybrid key exchange is required
+        let resumption = match resumption {
+            Some(resumption)
+                if key_exchange_mode.requires_hybrid() && !resumption.hybrid_key_exchange =>
+            {
+                resumption_tickets.discard(resumption).await?;
+                None
+            }
+            resumption => resumption,
+        };
+
         Ok(InitiatorStateMachine {
             common,
-            handshake: Handshake::new(vault, purpose_key.key_id().clone()).await?,
+            handshake: Handshake::new(vault.clone(), purpose_key.key_id().clone()).await?,
             identity_payload: Some(identity_payload),
+            key_exchange: HybridKeyExchange::new(vault, key_exchange_mode),
+            ciphers,
+            ciphers_offered: false,
+            resumption,
+            resumption_tickets,
         })
     }
+
+    /// When a list of ciphers has been offered, the responder sends the selected cipher
+    /// at the beginning of message 2. Set that cipher and return the rest of the message
+    fn read_selected_cipher<'a>(&mut self, message: &'a [u8]) -> Result<&'a [u8]> {
+        if !self.ciphers_offered {
+            return Ok(message);
+        }
+
+        let (id, message) = message.split_first().ok_or(XXError::MessageLenMismatch)?;
+        let cipher = SecureChannelCipher::from_id(*id)
+            .filter(|cipher| self.ciphers.contains(cipher))
+            .ok_or(XXError::NoCommonCipher)?;
+        self.set_cipher(cipher);
+        Ok(message)
+    }
+
+    /// When a session resumption has been offered, the responder sends whether the session is
+    /// resumed at the beginning of message 2, after the selected cipher.
+    /// Return that flag and the rest of the message.
+    /// The resumption secret is deleted if the responder did not accept the ticket
+    async fn read_resumption_mode<'a>(&mut self, message: &'a [u8]) -> Result<(bool, &'a [u8])> {
+        let resumption = match self.resumption.take() {
+            Some(resumption) => resumption,
+            None => return Ok((false, message)),
+        };
+
+        let (mode, message) = message.split_first().ok_or(XXError::MessageLenMismatch)?;
+        match *mode {
+            RESUMED_SESSION => {
+                self.resumption = Some(resumption);
+                Ok((true, message))
+            }
+            FULL_HANDSHAKE => {
+                self.resumption_tickets.discard(resumption).await?;
+                Ok((false, message))
+            }
+            _ => {
+                self.resumption_tickets.discard(resumption).await?;
+                Err(XXError::InvalidResumptionMode.into())
+            }
+        }
+    }
+
+    /// Process the message 2 of a resumed session: mix the resumption secret, decrypt the payload
+    /// and keep the new resumption ticket, if any. There is no message 3 in that case
+    async fn resume_session(&mut self, message: &[u8]) -> Result<Action> {
+        let resumption = self
+            .resumption
+            .take()
+            .ok_or(XXError::InvalidInternalState)?;
+        // the session secrets are derived from the previous session instead of a key exchange
+        self.key_exchange.discard().await?;
+        self.mix_key(resumption.secret.clone()).await?;
+        self.common.hybrid_key_exchange = resumption.hybrid_key_exchange;
+        let payload = self.decode_resumption_message2(message).await?;
+        let resumed_session: ResumedSession = minicbor::decode(&payload)?;
+        self.verify_resumed_identity(&resumption).await?;
+        if let Some(ticket) = resumed_session.resumption_ticket {
+            let secret = self.derive_resumption_secret().await?;
+            self.common.resumption_ticket = Some((ticket, secret));
+        }
+        self.set_final_state(Initiator).await?;
+        Ok(NoAction)
+    }
 }
@@ -8,5 +8,6 @@ compile_error!("Key Exchange is only supported on little-endian machines");
 mod handshake;
 mod handshake_state_machine;
 pub(crate) mod handshake_worker;
+mod hybrid_key_exchange;
 mod initiator_state_machine;
 mod responder_state_machine;
@@ -1,10 +1,11 @@
 use async_trait::async_trait;
+use core::time::Duration;
 use delegate::delegate;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
-use ockam_vault::{PublicKey, SecureChannelVault};
+use ockam_vault::{KeyId, PublicKey, SecureChannelVault};
 use Action::*;
 use Event::*;
 use Role::*;
@@ -14,10 +15,16 @@ use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake::Handshake;
 use crate::secure_channel::handshake::handshake_state_machine::{
-    Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
-    StateMachine, Status,
+    Action, CommonStateMachine, Event, HandshakeKeys, HandshakeOffer, HandshakeResults,
+    IdentityAndCredentials, ResumedSession, StateMachine, Status, FULL_HANDSHAKE, RESUMED_SESSION,
+};
+use crate::secure_channel::handshake::hybrid_key_exchange::HybridKeyExchange;
+use crate::secure_channel::{ResumptionTicket, ResumptionTickets, StoredResumptionTicket};
+use crate::utils::now;
+use crate::{
+    Identities, KeyExchangeMode, PurposeKey, Role, SecureChannelCipher, TimestampInSeconds,
+    TrustContext, TrustPolicy,
 };
-use crate::{Identities, PurposeKey, Role, TrustContext, TrustPolicy};
 
 /// Implementation of a state machine for the key exchange on the responder side
 #[async_trait]
@@ -33,15 +40,50 @@ impl StateMachine for ResponderStateMachine {
             }
             // Process message 1 and send message 2
             (WaitingForMessage1, ReceivedMessage(message)) => {
-                self.decode_message1(&message).await?;
-                let identity_payload = self
+                let message1_payload = self.decode_message1(&message).await?;
+                let offer = HandshakeOffer::decode(&message1_payload)?;
+                let cipher = offer.select_cipher(&self.ciphers)?;
+                self.set_cipher(cipher);
+                // initiators which offered a list of ciphers expect the selected one first,
+                // then whether the session is resumed if they offered a resumption ticket
+                let mut prefix = vec![];
+                if offer.ciphers.is_some() {
+                    prefix.push(cipher.id());
+                }
+                if let Some(ticket_id) = &offer.resumption_ticket {
+                    if let Some(message2) = self.resume_session(ticket_id, &prefix).await? {
+                        return Ok(SendMessage(message2));
+                    }
+                    prefix.push(FULL_HANDSHAKE);
+                }
+                // the cleartext prefix is authenticated by the encrypted payload of message 2
+                self.mix_message2_prefix(&prefix);
+                let key_exchange = self
+                    .key_exchange
+                    .accept(offer.key_exchange.as_ref())
+                    .await?;
+                let mut identity_payload = self
                     .identity_payload
                     .take()
                     .ok_or(XXError::InvalidInternalState)?;
-                let message2 = self.encode_message2(&identity_payload).await?;
+                let shared_secret = key_exchange.map(|(shared_secret, ciphertext)| {
+                    identity_payload.kem_ciphertext = Some(ciphertext.into());
+                    shared_secret
+                });
+                self.issued_ticket = self.issue_resumption_ticket()?;
+                identity_payload.resumption_ticket = self.issued_ticket.clone();
+                let message2 = self
+                    .encode_message2(&minicbor::to_vec(identity_payload)?)
+                    .await?;
+                prefix.extend(message2);
+                // mix the KEM shared secret before deriving the keys of message 3
+                if let Some(shared_secret) = shared_secret {
+                    self.mix_key(shared_secret).await?;
+                    self.common.hybrid_key_exchange = true;
+                }
 
                 self.handshake.state.status = WaitingForMessage3;
-                Ok(SendMessage(message2))
+                Ok(SendMessage(prefix))
             }
             // Process message 3
             (WaitingForMessage3, ReceivedMessage(message)) => {
@@ -50,6 +92,10 @@ impl StateMachine for ResponderStateMachine {
                     minicbor::decode(&message3_payload)?;
                 self.verify_identity(their_identity_payload, &self.handshake.state.rs()?.clone())
                     .await?;
+                if let Some(ticket) = self.issued_ticket.take() {
+                    let secret = self.derive_resumption_secret().await?;
+                    self.common.resumption_ticket = Some((ticket, secret));
+                }
                 self.set_final_state(Responder).await?;
                 Ok(NoAction)
             }
@@ -73,14 +119,23 @@ impl StateMachine for ResponderStateMachine {
 pub struct ResponderStateMachine {
     common: CommonStateMachine,
     handshake: Handshake,
-    /// this serialized payload contains an identity, its credentials and a signature of its static key
-    identity_payload: Option<Vec<u8>>,
+    /// this payload contains an identity, its credentials and a signature of its static key
+    identity_payload: Option<IdentityAndCredentials>,
+    key_exchange: HybridKeyExchange,
+    /// ciphers supported by the responder
+    ciphers: Vec<SecureChannelCipher>,
+    /// lifetime of the resumption tickets, if they are issued
+    resumption_ticket_lifetime: Option<Duration>,
+    resumption_tickets: ResumptionTickets,
+    /// resumption ticket sent in message 2
+    issued_ticket: Option<ResumptionTicket>,
 }
 
 impl ResponderStateMachine {
     delegate! {
         to self.common {
             async fn verify_identity(&mut self, peer: IdentityAndCredentials, peer_public_key: &PublicKey) -> Result<()>;
+            async fn verify_resumed_identity(&mut self, resumption: &StoredResumptionTicket) -> Result<()>;
             fn make_handshake_results(&self, handshake_keys: Option<HandshakeKeys>) -> Option<HandshakeResults>;
         }
     }
@@ -90,7 +145,12 @@ impl ResponderStateMachine {
             async fn initialize_handshake(&mut self) -> Result<()>;
             async fn decode_message1(&mut self, message: &[u8]) -> Result<Vec<u8>>;
             async fn encode_message2(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
+            async fn encode_resumption_message2(&mut self, payload: &[u8]) -> Result<Vec<u8>>;
+            async fn derive_resumption_secret(&self) -> Result<KeyId>;
             async fn decode_message3(&mut self, message: &[u8]) -> Result<Vec<u8>>;
+            fn set_cipher(&mut self, cipher: SecureChannelCipher);
+            async fn mix_key(&mut self, secret: KeyId) -> Result<()>;
+            fn mix_message2_prefix(&mut self, prefix: &[u8]);
             async fn set_final_state(&mut self, role: Role) -> Result<()>;
             fn get_handshake_keys(&self) -> Option<HandshakeKeys>;
         }
@@ -98,7 +158,8 @@ impl ResponderStateMachine {
 }
 
 impl ResponderStateMachine {
-    pub async fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn new(
         vault: Arc<dyn SecureChannelVault>,
         identities: Arc<Identities>,
         identifier: Identifier,
@@ -106,6 +167,10 @@ impl ResponderStateMachine {
         credentials: Vec<CredentialAndPurposeKey>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        key_exchange_mode: KeyExchangeMode,
+        ciphers: Vec<SecureChannelCipher>,
+        resumption_ticket_lifetime: Option<Duration>,
+        resumption_tickets: ResumptionTickets,
     ) -> Result<ResponderStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -119,8 +184,66 @@ impl ResponderStateMachine {
 
         Ok(ResponderStateMachine {
             common,
-            handshake: Handshake::new(vault, purpose_key.key_id().clone()).await?,
+            handshake: Handshake::new(vault.clone(), purpose_key.key_id().clone()).await?,
             identity_payload: Some(identity_payload),
+            key_exchange: HybridKeyExchange::new(vault, key_exchange_mode),
+            ciphers,
+            resumption_ticket_lifetime,
+            resumption_tickets,
+            issued_ticket: None,
         })
     }
+
+    /// Create a new resumption ticket if tickets are issued by this responder
+    fn issue_resumption_ticket(&self) -> Result<Option<ResumptionTicket>> {
+        match self.resumption_ticket_lifetime {
+            Some(lifetime) => {
+                let expires_at = TimestampInSeconds(now()?.0 + lifetime.as_secs());
+                Ok(Some(ResumptionTicket::new(expires_at)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Resume a previous session if the ticket sent by the initiator is valid, and return
+    /// the message 2 to send, starting with the given prefix and the resumption mode.
+    /// Return None if a full handshake must be performed instead, which is also the case when
+    /// a hybrid key exchange is required and the previous session used a classic one
+    async fn resume_session(&mut self, ticket_id: &[u8], prefix: &[u8]) -> Result<Option<Vec<u8>>> {
+        if self.resumption_ticket_lifetime.is_none() {
+            return Ok(None);
+        }
+        let resumption = match self
+            .resumption_tickets
+            .take_issued(ticket_id, &self.common.identifier)
+            .await?
+        {
+            Some(resumption) => resumption,
+            None => return Ok(None),
+        };
+        if self.key_exchange.requires_hybrid() && !resumption.hybrid_key_exchange {
+            self.resumption_tickets.discard(resumption).await?;
+            return Ok(None);
+        }
+        self.common.hybrid_key_exchange = resumption.hybrid_key_exchange;
+
+        let mut prefix = prefix.to_vec();
+        prefix.push(RESUMED_SESSION);
+        self.mix_message2_prefix(&prefix);
+        self.mix_key(resumption.secret.clone()).await?;
+        self.verify_resumed_identity(&resumption).await?;
+        let resumed_session = ResumedSession {
+            resumption_ticket: self.issue_resumption_ticket()?,
+        };
+        prefix.extend(
+            self.encode_resumption_message2(&minicbor::to_vec(&resumed_session)?)
+                .await?,
+        );
+        if let Some(ticket) = resumed_session.resumption_ticket {
+            let secret = self.derive_resumption_secret().await?;
+            self.common.resumption_ticket = Some((ticket, secret));
+        }
+        self.set_final_state(Responder).await?;
+        Ok(Some(prefix))
+    }
 }
@@ -0,0 +1,49 @@
+use core::fmt;
+use core::fmt::Formatter;
+
+/// Key exchange used to establish a secure channel
+///
+/// The classic key exchange is a Noise XX handshake over X25519.
+/// The hybrid key exchange additionally mixes a Kyber768 shared secret into the handshake keys,
+/// so that the channel stays confidential even if X25519 gets broken by a quantum computer.
+/// The KEM keys are ephemeral and negotiated during the handshake.
+///
+/// Kyber768 is provided by the `pqc_kyber` 0.7 crate, which implements the round 3 submission
+/// of Kyber to the NIST post-quantum competition, not the standardized ML-KEM-768 (FIPS 203).
+/// The two are not interoperable.
+///
+/// A responder which requires a hybrid key exchange only resumes the sessions which were
+/// established with a hybrid key exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyExchangeMode {
+    /// Classic Noise XX handshake. Hybrid key exchanges proposed by an initiator are ignored
+    #[default]
+    Classic,
+    /// Propose or accept a hybrid key exchange and fall back to a classic handshake
+    /// if the other party does not support it
+    Hybrid,
+    /// Only establish secure channels with a hybrid key exchange
+    HybridOnly,
+}
+
+impl KeyExchangeMode {
+    /// Return true if a hybrid key exchange can be used
+    pub fn is_hybrid(&self) -> bool {
+        !matches!(self, KeyExchangeMode::Classic)
+    }
+
+    /// Return true if a classic handshake must be rejected
+    pub fn requires_hybrid(&self) -> bool {
+        matches!(self, KeyExchangeMode::HybridOnly)
+    }
+}
+
+impl fmt::Display for KeyExchangeMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyExchangeMode::Classic => write!(f, "classic"),
+            KeyExchangeMode::Hybrid => write!(f, "hybrid"),
+            KeyExchangeMode::HybridOnly => write!(f, "hybrid-only"),
+        }
+    }
+}
@@ -48,10 +48,12 @@ impl IdentityChannelListener {
     }
 
     /// If credentials are not provided via list in options
-    /// get them from the trust context
+    /// get them from the credentials retriever or from the trust context
     async fn get_credentials(&self, ctx: &mut Context) -> Result<Vec<CredentialAndPurposeKey>> {
         let credentials = if self.options.credentials.is_empty() {
-            if let Some(trust_context) = &self.options.trust_context {
+            if let Some(retriever) = &self.options.credentials_retriever {
+                vec![retriever.retrieve(ctx, &self.identifier).await?]
+            } else if let Some(trust_context) = &self.options.trust_context {
                 vec![
                     trust_context
                         .authority()?
@@ -108,6 +110,12 @@ impl Worker for IdentityChannelListener {
             self.options.trust_policy.clone(),
             access_control.decryptor_outgoing_access_control,
             credentials,
+            self.options.credentials_retriever.clone(),
+            self.options.credential_refresh_time_gap,
+            self.options.key_exchange_mode,
+            self.options.ciphers.clone(),
+            None,
+            self.options.resumption_ticket_lifetime,
             self.options.trust_context.clone(),
             None,
             None,
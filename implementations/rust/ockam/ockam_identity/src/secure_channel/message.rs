@@ -0,0 +1,106 @@
+use minicbor::{Decode, Encode};
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+
+use crate::models::CredentialAndPurposeKey;
+
+/// Message sent over an established secure channel, once encrypted
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+pub(crate) enum SecureChannelMessage {
+    /// Encoded [`ockam_core::TransportMessage`] to forward to its onward route
+    #[n(0)] Payload(#[n(0)] #[cbor(with = "minicbor::bytes")] Vec<u8>),
+    /// Fresh credentials replacing the credentials presented during the handshake
+    #[n(1)] RefreshCredentials(#[n(0)] RefreshCredentials),
+}
+
+/// Credentials presented again by one party before its previous credentials expire
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(crate) struct RefreshCredentials {
+    #[n(1)] pub(crate) credentials: Vec<CredentialAndPurposeKey>,
+}
+
+impl SecureChannelMessage {
+    /// Serialize the message before its encryption.
+    /// When the other party doesn't support framed messages, as negotiated during the handshake,
+    /// only payloads can be sent and they are sent as they are
+    pub(crate) fn encode(self, framed: bool) -> Result<Vec<u8>> {
+        if framed {
+            return Ok(minicbor::to_vec(self)?);
+        }
+        match self {
+            SecureChannelMessage::Payload(payload) => Ok(payload),
+            SecureChannelMessage::RefreshCredentials(_) => Err(Error::new(
+                Origin::Channel,
+                Kind::Unsupported,
+                "the other party doesn't accept refreshed credentials",
+            )),
+        }
+    }
+
+    /// Deserialize a decrypted message.
+    /// When the other party doesn't support framed messages, it only sends payloads
+    pub(crate) fn decode(data: Vec<u8>, framed: bool) -> Result<Self> {
+        if framed {
+            Ok(minicbor::decode(&data)?)
+        } else {
+            Ok(SecureChannelMessage::Payload(data))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::{route, Decodable, Encodable, TransportMessage};
+
+    #[test]
+    fn test_unframed_payloads_are_transport_messages() -> Result<()> {
+        // this is the plaintext exchanged by parties which don't support framed messages
+        let transport_message = TransportMessage::v1(route!["a"], route!["b"], b"hello".to_vec());
+        let old_plaintext = transport_message.encode()?;
+
+        let plaintext = SecureChannelMessage::Payload(old_plaintext.clone()).encode(false)?;
+        assert_eq!(plaintext, old_plaintext);
+
+        match SecureChannelMessage::decode(old_plaintext, false)? {
+            SecureChannelMessage::Payload(payload) => {
+                assert_eq!(TransportMessage::decode(&payload)?, transport_message)
+            }
+            _ => panic!("an unframed message must be a payload"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_refreshed_credentials_are_not_sent_unframed() {
+        let refresh = SecureChannelMessage::RefreshCredentials(RefreshCredentials {
+            credentials: vec![],
+        });
+        assert!(refresh.encode(false).is_err());
+    }
+
+    #[test]
+    fn test_framed_messages() -> Result<()> {
+        let plaintext = SecureChannelMessage::Payload(b"payload".to_vec()).encode(true)?;
+        assert_ne!(plaintext, b"payload".to_vec());
+        match SecureChannelMessage::decode(plaintext, true)? {
+            SecureChannelMessage::Payload(payload) => assert_eq!(payload, b"payload".to_vec()),
+            _ => panic!("a payload was sent"),
+        }
+
+        let refresh = SecureChannelMessage::RefreshCredentials(RefreshCredentials {
+            credentials: vec![],
+        });
+        match SecureChannelMessage::decode(refresh.encode(true)?, true)? {
+            SecureChannelMessage::RefreshCredentials(refresh) => {
+                assert!(refresh.credentials.is_empty())
+            }
+            _ => panic!("credentials were sent"),
+        }
+        Ok(())
+    }
+}
@@ -2,16 +2,20 @@
 pub mod access_control;
 mod addresses;
 mod api;
+mod cipher;
 mod decryptor;
 mod encryptor;
 mod encryptor_worker;
 mod handshake;
+mod key_exchange;
 mod key_tracker;
 mod listener;
 mod local_info;
+mod message;
 mod nonce_tracker;
 mod options;
 mod registry;
+mod resumption;
 mod role;
 /// List of trust policies to setup ABAC controls
 pub mod trust_policy;
@@ -19,27 +23,54 @@ pub mod trust_policy;
 pub use access_control::*;
 pub(crate) use addresses::*;
 pub use api::*;
+pub use cipher::*;
 pub(crate) use handshake::*;
+pub use key_exchange::*;
 pub(crate) use listener::*;
 pub use local_info::*;
 pub use options::*;
 pub use registry::*;
+pub(crate) use resumption::*;
 pub(crate) use role::*;
 pub use trust_policy::*;
 
 #[cfg(test)]
 mod tests {
+    use crate::secure_channel::encryptor_worker::refresh_delay;
     use crate::secure_channel::{decryptor::Decryptor, encryptor::Encryptor};
-    use crate::Vault;
+    use crate::{SecureChannelCipher, TimestampInSeconds, Vault};
+    use core::time::Duration;
     use ockam_core::compat::rand::RngCore;
     use ockam_core::Result;
-    use ockam_vault::{Secret, SecretAttributes};
+    use ockam_vault::Secret;
     use rand::seq::SliceRandom;
     use rand::thread_rng;
 
     #[tokio::test]
     async fn test_encrypt_decrypt_normal_flow() {
-        let (mut encryptor, mut decryptor) = create_encryptor_decryptor().await.unwrap();
+        let (mut encryptor, mut decryptor) =
+            create_encryptor_decryptor(SecureChannelCipher::Aes256Gcm)
+                .await
+                .unwrap();
+
+        for n in 0..100 {
+            let msg = vec![n];
+            assert_eq!(
+                msg,
+                decryptor
+                    .decrypt(&encryptor.encrypt(&msg).await.unwrap())
+                    .await
+                    .unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_decrypt_chacha20_poly1305() {
+        let (mut encryptor, mut decryptor) =
+            create_encryptor_decryptor(SecureChannelCipher::ChaCha20Poly1305)
+                .await
+                .unwrap();
 
         for n in 0..100 {
             let msg = vec![n];
@@ -55,7 +86,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_encrypt_decrypt_with_message_lost() {
-        let (mut encryptor, mut decryptor) = create_encryptor_decryptor().await.unwrap();
+        let (mut encryptor, mut decryptor) =
+            create_encryptor_decryptor(SecureChannelCipher::Aes256Gcm)
+                .await
+                .unwrap();
 
         for n in 0..100 {
             let msg = vec![n];
@@ -70,7 +104,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_encrypt_decrypt_out_of_order() {
-        let (mut encryptor, mut decryptor) = create_encryptor_decryptor().await.unwrap();
+        let (mut encryptor, mut decryptor) =
+            create_encryptor_decryptor(SecureChannelCipher::Aes256Gcm)
+                .await
+                .unwrap();
 
         // Vec<(plaintext, ciphertext)>
         let mut all_msgs: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
@@ -108,7 +145,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_attack_nonce() {
-        let (mut encryptor, mut decryptor) = create_encryptor_decryptor().await.unwrap();
+        let (mut encryptor, mut decryptor) =
+            create_encryptor_decryptor(SecureChannelCipher::Aes256Gcm)
+                .await
+                .unwrap();
         for n in 0..100 {
             let msg = vec![n];
             let ciphertext = encryptor.encrypt(&msg).await.unwrap();
@@ -132,7 +172,27 @@ mod tests {
         }
     }
 
-    async fn create_encryptor_decryptor() -> Result<(Encryptor, Decryptor)> {
+    #[test]
+    fn test_credential_refresh_delay() {
+        let gap = Duration::from_secs(60);
+        assert_eq!(
+            refresh_delay(TimestampInSeconds(100), TimestampInSeconds(1000), gap),
+            Duration::from_secs(840)
+        );
+        // expired or almost expired credentials are refreshed after a minimum delay
+        assert_eq!(
+            refresh_delay(TimestampInSeconds(100), TimestampInSeconds(120), gap),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            refresh_delay(TimestampInSeconds(100), TimestampInSeconds(50), gap),
+            Duration::from_secs(1)
+        );
+    }
+
+    async fn create_encryptor_decryptor(
+        cipher: SecureChannelCipher,
+    ) -> Result<(Encryptor, Decryptor)> {
         let vault1 = Vault::create_secure_channel_vault();
         let vault2 = Vault::create_secure_channel_vault();
 
@@ -140,7 +200,7 @@ mod tests {
         let mut key = [0u8; 32];
         rng.fill_bytes(&mut key);
 
-        let secret_attrs = SecretAttributes::Aes256;
+        let secret_attrs = cipher.key_attributes();
         let key_on_v1 = vault1
             .import_ephemeral_secret(Secret::new(key.to_vec()), secret_attrs)
             .await
@@ -152,8 +212,8 @@ mod tests {
             .unwrap();
 
         Ok((
-            Encryptor::new(key_on_v1, 0, vault1),
-            Decryptor::new(key_on_v2, vault2),
+            Encryptor::new(key_on_v1, 0, vault1, cipher),
+            Decryptor::new(key_on_v2, vault2, cipher),
         ))
     }
 }
@@ -3,9 +3,12 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
 use ockam_core::{Address, OutgoingAccessControl, Result};
 
-use crate::models::CredentialAndPurposeKey;
+use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::Addresses;
-use crate::{TrustContext, TrustEveryonePolicy, TrustPolicy};
+use crate::{
+    CredentialsRetriever, KeyExchangeMode, SecureChannelCipher, TrustContext, TrustEveryonePolicy,
+    TrustPolicy,
+};
 
 use core::fmt;
 use core::fmt::Formatter;
@@ -13,12 +16,23 @@ use core::time::Duration;
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
 
+/// Time before the expiration of the presented credentials when fresh credentials are presented
+pub(crate) const DEFAULT_CREDENTIAL_REFRESH_TIME_GAP: Duration = Duration::from_secs(5 * 60);
+
+/// Default lifetime of the session resumption tickets issued by a listener
+pub const DEFAULT_RESUMPTION_TICKET_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
 /// Trust options for a Secure Channel
 pub struct SecureChannelOptions {
     pub(crate) flow_control_id: FlowControlId,
     pub(crate) trust_policy: Arc<dyn TrustPolicy>,
     pub(crate) trust_context: Option<TrustContext>,
     pub(crate) credentials: Vec<CredentialAndPurposeKey>,
+    pub(crate) credentials_retriever: Option<Arc<dyn CredentialsRetriever>>,
+    pub(crate) credential_refresh_time_gap: Duration,
+    pub(crate) key_exchange_mode: KeyExchangeMode,
+    pub(crate) ciphers: Vec<SecureChannelCipher>,
+    pub(crate) resumption_identifier: Option<Identifier>,
     pub(crate) timeout: Duration,
 }
 
@@ -41,6 +55,11 @@ impl SecureChannelOptions {
             trust_policy: Arc::new(TrustEveryonePolicy),
             trust_context: None,
             credentials: vec![],
+            credentials_retriever: None,
+            credential_refresh_time_gap: DEFAULT_CREDENTIAL_REFRESH_TIME_GAP,
+            key_exchange_mode: KeyExchangeMode::default(),
+            ciphers: vec![SecureChannelCipher::Aes256Gcm],
+            resumption_identifier: None,
             timeout: DEFAULT_TIMEOUT,
         }
     }
@@ -63,6 +82,46 @@ impl SecureChannelOptions {
         self
     }
 
+    /// Retrieve the credential to present with a [`CredentialsRetriever`].
+    /// A fresh credential is retrieved and presented again over the established channel
+    /// before the previous one expires
+    pub fn with_credentials_retriever(
+        mut self,
+        credentials_retriever: Arc<dyn CredentialsRetriever>,
+    ) -> Self {
+        self.credentials_retriever = Some(credentials_retriever);
+        self
+    }
+
+    /// Sets how long before its expiration a credential is refreshed, different from the default
+    /// one [`DEFAULT_CREDENTIAL_REFRESH_TIME_GAP`]
+    pub fn with_credential_refresh_time_gap(mut self, time_gap: Duration) -> Self {
+        self.credential_refresh_time_gap = time_gap;
+        self
+    }
+
+    /// Sets the key exchange used during the handshake, [`KeyExchangeMode::Classic`] by default
+    pub fn with_key_exchange_mode(mut self, key_exchange_mode: KeyExchangeMode) -> Self {
+        self.key_exchange_mode = key_exchange_mode;
+        self
+    }
+
+    /// Sets the ciphers proposed to the responder, in order of preference.
+    /// [`SecureChannelCipher::Aes256Gcm`] is used by default, and must be part of the list
+    /// to establish a secure channel with a responder which does not support the negotiation
+    pub fn with_ciphers(mut self, ciphers: Vec<SecureChannelCipher>) -> Self {
+        self.ciphers = ciphers;
+        self
+    }
+
+    /// Resume the last session established with the given identity, in one round trip, if
+    /// a resumption ticket was received at the end of that session and has not expired.
+    /// A full handshake is performed otherwise, or if the responder does not accept the ticket
+    pub fn with_session_resumption(mut self, their_identifier: Identifier) -> Self {
+        self.resumption_identifier = Some(their_identifier);
+        self
+    }
+
     /// Sets trust context
     pub fn with_trust_context(mut self, trust_context: TrustContext) -> Self {
         self.trust_context = Some(trust_context);
@@ -129,6 +188,11 @@ pub struct SecureChannelListenerOptions {
     pub(crate) trust_policy: Arc<dyn TrustPolicy>,
     pub(crate) trust_context: Option<TrustContext>,
     pub(crate) credentials: Vec<CredentialAndPurposeKey>,
+    pub(crate) credentials_retriever: Option<Arc<dyn CredentialsRetriever>>,
+    pub(crate) credential_refresh_time_gap: Duration,
+    pub(crate) key_exchange_mode: KeyExchangeMode,
+    pub(crate) ciphers: Vec<SecureChannelCipher>,
+    pub(crate) resumption_ticket_lifetime: Option<Duration>,
 }
 
 impl fmt::Debug for SecureChannelListenerOptions {
@@ -149,6 +213,14 @@ impl SecureChannelListenerOptions {
             trust_policy: Arc::new(TrustEveryonePolicy),
             trust_context: None,
             credentials: vec![],
+            credentials_retriever: None,
+            credential_refresh_time_gap: DEFAULT_CREDENTIAL_REFRESH_TIME_GAP,
+            key_exchange_mode: KeyExchangeMode::default(),
+            ciphers: vec![
+                SecureChannelCipher::Aes256Gcm,
+                SecureChannelCipher::ChaCha20Poly1305,
+            ],
+            resumption_ticket_lifetime: None,
         }
     }
 
@@ -173,6 +245,45 @@ impl SecureChannelListenerOptions {
         self
     }
 
+    /// Retrieve the credential to present with a [`CredentialsRetriever`].
+    /// A fresh credential is retrieved and presented again over the established channel
+    /// before the previous one expires
+    pub fn with_credentials_retriever(
+        mut self,
+        credentials_retriever: Arc<dyn CredentialsRetriever>,
+    ) -> Self {
+        self.credentials_retriever = Some(credentials_retriever);
+        self
+    }
+
+    /// Sets how long before its expiration a credential is refreshed, different from the default
+    /// one [`DEFAULT_CREDENTIAL_REFRESH_TIME_GAP`]
+    pub fn with_credential_refresh_time_gap(mut self, time_gap: Duration) -> Self {
+        self.credential_refresh_time_gap = time_gap;
+        self
+    }
+
+    /// Sets the key exchange used during the handshake, [`KeyExchangeMode::Classic`] by default
+    pub fn with_key_exchange_mode(mut self, key_exchange_mode: KeyExchangeMode) -> Self {
+        self.key_exchange_mode = key_exchange_mode;
+        self
+    }
+
+    /// Sets the ciphers which can be selected when an initiator proposes a list of ciphers.
+    /// All the ciphers are supported by default
+    pub fn with_ciphers(mut self, ciphers: Vec<SecureChannelCipher>) -> Self {
+        self.ciphers = ciphers;
+        self
+    }
+
+    /// Issue a session resumption ticket, valid for the given duration, at the end of each
+    /// handshake. Initiators can use it once to re-establish a secure channel in one round trip.
+    /// No tickets are issued by default
+    pub fn with_resumption_tickets(mut self, lifetime: Duration) -> Self {
+        self.resumption_ticket_lifetime = Some(lifetime);
+        self
+    }
+
     /// Sets trust context
     pub fn with_trust_context(mut self, trust_context: TrustContext) -> Self {
         self.trust_context = Some(trust_context);
@@ -0,0 +1,241 @@
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::rand::{self, RngCore};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_vault::{KeyId, SecureChannelVault};
+
+use crate::models::{CredentialAndPurposeKey, Identifier};
+use crate::utils::now;
+use crate::TimestampInSeconds;
+
+/// Size of a resumption ticket identifier
+const RESUMPTION_TICKET_ID_LENGTH: usize = 16;
+
+/// Ticket issued by a responder at the end of a handshake. The initiator can send it back
+/// in the first message of a later handshake to re-establish a secure channel in one round trip,
+/// using a secret derived from the previous session instead of exchanging identities again
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub(crate) struct ResumptionTicket {
+    /// Random identifier of the ticket, used by the responder to find the resumption secret
+    #[n(1)] pub(crate) id: ByteVec,
+    /// The ticket can not be used after that time
+    #[n(2)] pub(crate) expires_at: TimestampInSeconds,
+}
+
+impl ResumptionTicket {
+    /// Create a new ticket with a random identifier
+    pub(crate) fn new(expires_at: TimestampInSeconds) -> Self {
+        let mut id = vec![0; RESUMPTION_TICKET_ID_LENGTH];
+        rand::thread_rng().fill_bytes(&mut id);
+        Self {
+            id: id.into(),
+            expires_at,
+        }
+    }
+}
+
+/// A resumption ticket with its secret, bound to the identities of both parties.
+/// The credentials presented by the other party are verified again when the session is resumed
+#[derive(Debug, Clone)]
+pub(crate) struct StoredResumptionTicket {
+    pub(crate) our_identifier: Identifier,
+    pub(crate) their_identifier: Identifier,
+    pub(crate) their_credentials: Vec<CredentialAndPurposeKey>,
+    pub(crate) ticket: ResumptionTicket,
+    pub(crate) secret: KeyId,
+    /// true if the session of this ticket, or the session it was resumed from,
+    /// was established with a hybrid key exchange
+    pub(crate) hybrid_key_exchange: bool,
+}
+
+impl StoredResumptionTicket {
+    fn is_expired(&self, now: TimestampInSeconds) -> bool {
+        self.ticket.expires_at <= now
+    }
+}
+
+/// Resumption tickets issued by our responders and received by our initiators.
+///
+/// Each ticket can only be used once, and only the last ticket exchanged between two identities
+/// is kept. The secrets of the tickets which are replaced, used or expired are deleted from the vault
+#[derive(Clone)]
+pub(crate) struct ResumptionTickets {
+    vault: Arc<dyn SecureChannelVault>,
+    // the ticket id is used as a key
+    issued: Arc<RwLock<BTreeMap<Vec<u8>, StoredResumptionTicket>>>,
+    // our identifier and their identifier are used as a key
+    received: Arc<RwLock<BTreeMap<(Identifier, Identifier), StoredResumptionTicket>>>,
+}
+
+impl ResumptionTickets {
+    /// Create an empty set of tickets
+    pub(crate) fn new(vault: Arc<dyn SecureChannelVault>) -> Self {
+        Self {
+            vault,
+            issued: Default::default(),
+            received: Default::default(),
+        }
+    }
+
+    /// Responder: store a ticket issued to an initiator, replacing the tickets previously
+    /// issued to the same initiator
+    pub(crate) async fn add_issued(&self, ticket: StoredResumptionTicket) -> Result<()> {
+        let now = now()?;
+        let removed = {
+            let mut issued = self.issued.write().unwrap();
+            let removed_ids: Vec<Vec<u8>> = issued
+                .iter()
+                .filter(|(_, t)| {
+                    t.is_expired(now)
+                        || (t.our_identifier == ticket.our_identifier
+                            && t.their_identifier == ticket.their_identifier)
+                })
+                .map(|(id, _)| id.clone())
+                .collect();
+            let removed: Vec<StoredResumptionTicket> = removed_ids
+                .iter()
+                .filter_map(|id| issued.remove(id))
+                .collect();
+            issued.insert(ticket.ticket.id.to_vec(), ticket);
+            removed
+        };
+        self.delete_secrets(removed).await
+    }
+
+    /// Responder: take the ticket sent by an initiator if it is valid for our identifier
+    pub(crate) async fn take_issued(
+        &self,
+        id: &[u8],
+        our_identifier: &Identifier,
+    ) -> Result<Option<StoredResumptionTicket>> {
+        let ticket = self.issued.write().unwrap().remove(id);
+        self.validate(ticket, our_identifier).await
+    }
+
+    /// Initiator: store a ticket received from a responder, replacing the previous one
+    pub(crate) async fn add_received(&self, ticket: StoredResumptionTicket) -> Result<()> {
+        let key = (
+            ticket.our_identifier.clone(),
+            ticket.their_identifier.clone(),
+        );
+        let replaced = self.received.write().unwrap().insert(key, ticket);
+        self.delete_secrets(replaced).await
+    }
+
+    /// Initiator: take the ticket received from a given responder, if it has not expired
+    pub(crate) async fn take_received(
+        &self,
+        our_identifier: &Identifier,
+        their_identifier: &Identifier,
+    ) -> Result<Option<StoredResumptionTicket>> {
+        let ticket = self
+            .received
+            .write()
+            .unwrap()
+            .remove(&(our_identifier.clone(), their_identifier.clone()));
+        self.validate(ticket, our_identifier).await
+    }
+
+    /// Delete the secret of a ticket which is not going to be used
+    pub(crate) async fn discard(&self, ticket: StoredResumptionTicket) -> Result<()> {
+        self.delete_secrets(Some(ticket)).await
+    }
+
+    /// Return the ticket if it has not expired and was exchanged by our identity.
+    /// Otherwise delete its secret
+    async fn validate(
+        &self,
+        ticket: Option<StoredResumptionTicket>,
+        our_identifier: &Identifier,
+    ) -> Result<Option<StoredResumptionTicket>> {
+        let ticket = match ticket {
+            Some(ticket) => ticket,
+            None => return Ok(None),
+        };
+        if ticket.is_expired(now()?) || &ticket.our_identifier != our_identifier {
+            self.discard(ticket).await?;
+            return Ok(None);
+        }
+        Ok(Some(ticket))
+    }
+
+    async fn delete_secrets(
+        &self,
+        tickets: impl IntoIterator<Item = StoredResumptionTicket>,
+    ) -> Result<()> {
+        for ticket in tickets {
+            self.vault.delete_secret(ticket.secret).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_vault::{Secret, SecretAttributes, SoftwareSecureChannelVault};
+
+    #[tokio::test]
+    async fn test_resumption_tickets() -> Result<()> {
+        let vault = SoftwareSecureChannelVault::create();
+        let tickets = ResumptionTickets::new(vault.clone());
+        let alice = Identifier([1; 20]);
+        let bob = Identifier([2; 20]);
+        let expires_at = TimestampInSeconds(now()?.0 + 60);
+
+        // a new ticket replaces the previous one issued to the same identity
+        let first = stored_ticket(&vault, &bob, &alice, expires_at).await?;
+        let second = stored_ticket(&vault, &bob, &alice, expires_at).await?;
+        tickets.add_issued(first.clone()).await?;
+        tickets.add_issued(second.clone()).await?;
+        assert_eq!(vault.number_of_ephemeral_secrets(), 1);
+        assert!(tickets.take_issued(&first.ticket.id, &bob).await?.is_none());
+
+        // a ticket can only be used by the identity it was issued by, and only once
+        assert!(tickets
+            .take_issued(&second.ticket.id, &alice)
+            .await?
+            .is_none());
+        assert_eq!(vault.number_of_ephemeral_secrets(), 0);
+        let third = stored_ticket(&vault, &bob, &alice, expires_at).await?;
+        tickets.add_issued(third.clone()).await?;
+        assert!(tickets.take_issued(&third.ticket.id, &bob).await?.is_some());
+        assert!(tickets.take_issued(&third.ticket.id, &bob).await?.is_none());
+
+        // expired tickets are discarded
+        let received = stored_ticket(&vault, &alice, &bob, TimestampInSeconds(0)).await?;
+        tickets.add_received(received).await?;
+        assert!(tickets.take_received(&alice, &bob).await?.is_none());
+
+        let received = stored_ticket(&vault, &alice, &bob, expires_at).await?;
+        tickets.add_received(received.clone()).await?;
+        let taken = tickets.take_received(&alice, &bob).await?;
+        assert_eq!(taken.map(|t| t.ticket), Some(received.ticket));
+        assert!(tickets.take_received(&alice, &bob).await?.is_none());
+        Ok(())
+    }
+
+    async fn stored_ticket(
+        vault: &Arc<SoftwareSecureChannelVault>,
+        our_identifier: &Identifier,
+        their_identifier: &Identifier,
+        expires_at: TimestampInSeconds,
+    ) -> Result<StoredResumptionTicket> {
+        let secret = vault
+            .import_ephemeral_secret(Secret::new(vec![1; 32]), SecretAttributes::Buffer(32))
+            .await?;
+        Ok(StoredResumptionTicket {
+            our_identifier: our_identifier.clone(),
+            their_identifier: their_identifier.clone(),
+            their_credentials: vec![],
+            ticket: ResumptionTicket::new(expires_at),
+            secret,
+            hybrid_key_exchange: false,
+        })
+    }
+}
@@ -7,8 +7,8 @@ use crate::identities::Identities;
 use crate::models::Identifier;
 use crate::secure_channel::handshake_worker::HandshakeWorker;
 use crate::secure_channel::{
-    Addresses, IdentityChannelListener, Role, SecureChannelListenerOptions, SecureChannelOptions,
-    SecureChannelRegistry,
+    Addresses, IdentityChannelListener, ResumptionTickets, Role, SecureChannelListenerOptions,
+    SecureChannelOptions, SecureChannelRegistry,
 };
 use crate::{Purpose, SecureChannel, SecureChannelListener, SecureChannelsBuilder, Vault};
 
@@ -17,6 +17,7 @@ use crate::{Purpose, SecureChannel, SecureChannelListener, SecureChannelsBuilder
 pub struct SecureChannels {
     pub(crate) identities: Arc<Identities>,
     pub(crate) secure_channel_registry: SecureChannelRegistry,
+    pub(crate) resumption_tickets: ResumptionTickets,
 }
 
 impl SecureChannels {
@@ -25,9 +26,11 @@ impl SecureChannels {
         identities: Arc<Identities>,
         secure_channel_registry: SecureChannelRegistry,
     ) -> Self {
+        let resumption_tickets = ResumptionTickets::new(identities.vault().secure_channel_vault);
         Self {
             identities,
             secure_channel_registry,
+            resumption_tickets,
         }
     }
 
@@ -105,6 +108,14 @@ impl SecureChannels {
             .get_or_create_purpose_key(identifier, Purpose::SecureChannel)
             .await?;
 
+        // when credentials are refreshed, the first one is retrieved before the handshake
+        let credentials = match &options.credentials_retriever {
+            Some(retriever) if options.credentials.is_empty() => {
+                vec![retriever.retrieve(ctx, identifier).await?]
+            }
+            _ => options.credentials,
+        };
+
         HandshakeWorker::create(
             ctx,
             Arc::new(self.clone()),
@@ -113,7 +124,13 @@ impl SecureChannels {
             purpose_key,
             options.trust_policy,
             access_control.decryptor_outgoing_access_control,
-            options.credentials,
+            credentials,
+            options.credentials_retriever,
+            options.credential_refresh_time_gap,
+            options.key_exchange_mode,
+            options.ciphers,
+            options.resumption_identifier,
+            None,
             options.trust_context,
             Some(route),
             Some(options.timeout),
@@ -92,6 +92,26 @@ impl LmdbStorage {
         };
         task::spawn_blocking(t).await.map_err(map_join_err)?
     }
+
+    /// Return all the `(id, key, value)` entries of the database,
+    /// for example to copy them to another [`Storage`]
+    pub async fn entries(&self) -> Result<Vec<(String, String, Vec<u8>)>> {
+        let d = self.clone();
+        let t = move || {
+            let r = d.env.begin_ro_txn().map_err(map_lmdb_err)?;
+            let mut cursor = r.open_ro_cursor(d.map).map_err(map_lmdb_err)?;
+            let mut entries = Vec::new();
+            for entry in cursor.iter() {
+                let (k, v) = entry.map_err(map_lmdb_err)?;
+                let k = str::from_utf8(k).map_err(|e| Error::new(Origin::Node, Kind::Io, e))?;
+                if let Some((id, key)) = k.rsplit_once(':') {
+                    entries.push((id.to_string(), key.to_string(), v.to_vec()));
+                }
+            }
+            Ok(entries)
+        };
+        task::spawn_blocking(t).await.map_err(map_join_err)?
+    }
 }
 
 #[async_trait]
@@ -5,6 +5,7 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{Error, Result};
 use ockam_node::tokio::task::{self, JoinError};
+use ockam_node::SqliteDatabase;
 use rusqlite::{params, Connection};
 use std::fmt;
 use std::path::Path;
@@ -12,7 +13,7 @@ use tokio_retry::strategy::{jitter, FixedInterval};
 use tokio_retry::Retry;
 use tracing::debug;
 
-use Storage;
+use crate::storage::Storage;
 
 /// Storage using the Sqlite database
 #[derive(Clone)]
@@ -59,25 +60,40 @@ impl SqliteStorage {
         Retry::spawn(retry_strategy, || async { Self::make(path).await }).await
     }
 
+    /// Create the identity and policy tables in a database shared with other storages,
+    /// for example the storage of a vault
+    pub async fn from_database(database: &SqliteDatabase) -> Result<Self> {
+        let conn = database.conn();
+        let t = move || {
+            Self::create_tables(&conn.lock().unwrap())?;
+            Ok(SqliteStorage { conn })
+        };
+        task::spawn_blocking(t).await.map_err(map_join_err)?
+    }
+
     async fn make(p: &Path) -> Result<Self> {
         debug!("create the Sqlite database");
         let p = p.to_path_buf();
         // Creates database file if it doesn't exist
         let conn = Connection::open(p).map_err(map_sqlite_err)?;
-        let _ = conn
-            .execute_batch(
-                &("PRAGMA encoding = 'UTF-8';".to_owned()
-                    + SqliteStorage::CREATE_IDENTITY_TABLE_SQL
-                    + SqliteStorage::CREATE_IDENTITY_INDEX_SQL
-                    + SqliteStorage::CREATE_POLICY_TABLE_SQL
-                    + SqliteStorage::CREATE_POLICY_INDEX_SQL),
-            )
+        conn.execute_batch("PRAGMA encoding = 'UTF-8';")
             .map_err(map_sqlite_err)?;
+        Self::create_tables(&conn)?;
         Ok(SqliteStorage {
             conn: Arc::new(Mutex::new(conn)),
         })
     }
 
+    fn create_tables(conn: &Connection) -> Result<()> {
+        conn.execute_batch(
+            &(SqliteStorage::CREATE_IDENTITY_TABLE_SQL.to_owned()
+                + SqliteStorage::CREATE_IDENTITY_INDEX_SQL
+                + SqliteStorage::CREATE_POLICY_TABLE_SQL
+                + SqliteStorage::CREATE_POLICY_INDEX_SQL),
+        )
+        .map_err(map_sqlite_err)
+    }
+
     /// Getter for Sqlite Connection
     pub fn conn(&self) -> Arc<Mutex<Connection>> {
         Arc::clone(&self.conn)
@@ -189,4 +205,20 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_shared_database() -> Result<()> {
+        let temp_path = NamedTempFile::new().unwrap().into_temp_path();
+        let database = SqliteDatabase::create(&temp_path).await?;
+        let db = SqliteStorage::from_database(&database).await?;
+
+        db.set("1", String::from("2"), vec![1, 2, 3, 4]).await?;
+        assert_eq!(db.get("1", "2").await?, Some(vec![1, 2, 3, 4]));
+
+        // the identities are visible when opening the same file directly
+        let other = SqliteStorage::new(temp_path.to_path_buf()).await?;
+        assert_eq!(other.get("1", "2").await?, Some(vec![1, 2, 3, 4]));
+
+        Ok(())
+    }
 }
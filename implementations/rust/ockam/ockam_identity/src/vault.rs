@@ -80,6 +80,17 @@ impl Vault {
         Ok(Self::create_with_persistent_storage(storage))
     }
 
+    /// Create Software Vaults with a [`PersistentStorage`] encrypting the secrets at rest
+    #[cfg(feature = "std")]
+    pub async fn create_with_encrypted_persistent_storage_path(
+        path: &std::path::Path,
+        encryption: ockam_vault::storage::StorageEncryption,
+    ) -> ockam_core::Result<Vault> {
+        let storage =
+            ockam_vault::storage::PersistentStorage::create_encrypted(path, encryption).await?;
+        Ok(Self::create_with_persistent_storage(storage))
+    }
+
     /// Create Software Vaults with a given [`VaultStorage`]r
     pub fn create_with_persistent_storage(storage: VaultStorage) -> Vault {
         Self::new(
@@ -1,14 +1,14 @@
 use core::time::Duration;
 use ockam_core::compat::sync::Arc;
 use ockam_core::{route, Address, AllowAll, Any, DenyAll, Mailboxes, Result, Routed, Worker};
-use ockam_identity::models::{Identifier, SchemaId};
+use ockam_identity::models::{CredentialAndPurposeKey, Identifier, RevokedCredential, SchemaId};
 use ockam_identity::secure_channels::secure_channels;
-use ockam_identity::utils::AttributesBuilder;
+use ockam_identity::utils::{now, AttributesBuilder};
 use ockam_identity::{
     AuthorityService, DecryptionResponse, EncryptionRequest, EncryptionResponse,
-    IdentityAccessControlBuilder, IdentitySecureChannelLocalInfo, Purpose,
-    SecureChannelListenerOptions, SecureChannelOptions, SecureChannels, TrustContext,
-    TrustEveryonePolicy, TrustIdentifierPolicy, Vault,
+    IdentityAccessControlBuilder, IdentitySecureChannelLocalInfo, KeyExchangeMode, Purpose,
+    SecureChannelCipher, SecureChannelListenerOptions, SecureChannelOptions, SecureChannels,
+    TrustContext, TrustEveryonePolicy, TrustIdentifierPolicy, Vault,
 };
 use ockam_node::{Context, MessageReceiveOptions, WorkerBuilder};
 use ockam_vault::{
@@ -282,6 +282,502 @@ async fn test_channel_rejected_trust_policy(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+async fn check_channel_options(
+    ctx: &mut Context,
+    listener_options: SecureChannelListenerOptions,
+    initiator_options: SecureChannelOptions,
+) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let listener_address = Address::random_local();
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            listener_address.clone(),
+            listener_options,
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route![listener_address],
+            initiator_options.with_timeout(Duration::from_millis(500)),
+        )
+        .await?;
+
+    let child_address = Address::random_local();
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            child_address.clone(),
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer(child_address, bob_listener.flow_control_id());
+
+    // send enough messages to renew the channel keys
+    for i in 0..40 {
+        child_ctx
+            .send(
+                route![alice_channel.clone(), child_ctx.address()],
+                format!("Hello, Bob! {i}"),
+            )
+            .await?;
+
+        let msg = child_ctx.receive::<String>().await?;
+        assert_eq!(format!("Hello, Bob! {i}"), msg.body());
+    }
+
+    Ok(())
+}
+
+async fn check_key_exchange_modes(
+    ctx: &mut Context,
+    listener_mode: KeyExchangeMode,
+    initiator_mode: KeyExchangeMode,
+) -> Result<()> {
+    check_channel_options(
+        ctx,
+        SecureChannelListenerOptions::new().with_key_exchange_mode(listener_mode),
+        SecureChannelOptions::new().with_key_exchange_mode(initiator_mode),
+    )
+    .await
+}
+
+async fn check_ciphers(
+    ctx: &mut Context,
+    listener_ciphers: Vec<SecureChannelCipher>,
+    initiator_ciphers: Vec<SecureChannelCipher>,
+) -> Result<()> {
+    check_channel_options(
+        ctx,
+        SecureChannelListenerOptions::new().with_ciphers(listener_ciphers),
+        SecureChannelOptions::new().with_ciphers(initiator_ciphers),
+    )
+    .await
+}
+
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange(ctx: &mut Context) -> Result<()> {
+    check_key_exchange_modes(ctx, KeyExchangeMode::Hybrid, KeyExchangeMode::HybridOnly).await?;
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange_fallback(ctx: &mut Context) -> Result<()> {
+    check_key_exchange_modes(ctx, KeyExchangeMode::Classic, KeyExchangeMode::Hybrid).await?;
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_hybrid_key_exchange_required(ctx: &mut Context) -> Result<()> {
+    let result =
+        check_key_exchange_modes(ctx, KeyExchangeMode::Classic, KeyExchangeMode::HybridOnly).await;
+    assert!(result.is_err());
+
+    let result =
+        check_key_exchange_modes(ctx, KeyExchangeMode::HybridOnly, KeyExchangeMode::Classic).await;
+    assert!(result.is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_chacha20_poly1305(ctx: &mut Context) -> Result<()> {
+    use SecureChannelCipher::*;
+
+    // both parties only support ChaCha20-Poly1305
+    check_ciphers(ctx, vec![ChaCha20Poly1305], vec![ChaCha20Poly1305]).await?;
+    // the responder selects the preferred cipher of the initiator
+    check_ciphers(
+        ctx,
+        vec![Aes256Gcm, ChaCha20Poly1305],
+        vec![ChaCha20Poly1305, Aes256Gcm],
+    )
+    .await?;
+    // ChaCha20-Poly1305 is supported by default by the responder
+    check_channel_options(
+        ctx,
+        SecureChannelListenerOptions::new(),
+        SecureChannelOptions::new().with_ciphers(vec![ChaCha20Poly1305]),
+    )
+    .await?;
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_ciphers_interoperability(ctx: &mut Context) -> Result<()> {
+    use SecureChannelCipher::*;
+
+    // an initiator preferring ChaCha20-Poly1305 falls back to AES-GCM
+    check_ciphers(ctx, vec![Aes256Gcm], vec![ChaCha20Poly1305, Aes256Gcm]).await?;
+    // an initiator which does not negotiate the cipher uses AES-GCM
+    check_ciphers(ctx, vec![ChaCha20Poly1305, Aes256Gcm], vec![Aes256Gcm]).await?;
+
+    // there must be a common cipher
+    let result = check_ciphers(ctx, vec![Aes256Gcm], vec![ChaCha20Poly1305]).await;
+    assert!(result.is_err());
+    let result = check_ciphers(ctx, vec![ChaCha20Poly1305], vec![Aes256Gcm]).await;
+    assert!(result.is_err());
+
+    ctx.stop().await
+}
+
+/// Forward messages to the next hop of their onward route and count them
+struct CountingHop {
+    count: Arc<AtomicU8>,
+}
+
+#[ockam_core::async_trait]
+impl Worker for CountingHop {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        self.count.fetch_add(1, Ordering::Relaxed);
+
+        let mut message = msg.into_local_message();
+        let transport_message = message.transport_mut();
+        transport_message.onward_route.step()?;
+        transport_message
+            .return_route
+            .modify()
+            .prepend(ctx.address());
+        ctx.forward(message).await
+    }
+}
+
+/// Create successive secure channels to the same listener, trying to resume the previous session
+/// each time, and check the number of handshake messages exchanged for each channel
+async fn check_session_resumption(
+    ctx: &mut Context,
+    listener_options: SecureChannelListenerOptions,
+    expected_handshake_messages: &[u8],
+) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let count = Arc::new(AtomicU8::new(0));
+    let hop_address = Address::random_local();
+    ctx.start_worker(
+        hop_address.clone(),
+        CountingHop {
+            count: count.clone(),
+        },
+    )
+    .await?;
+
+    let listener_address = Address::random_local();
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            listener_address.clone(),
+            listener_options
+                .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone())),
+        )
+        .await?;
+
+    for expected in expected_handshake_messages {
+        count.store(0, Ordering::Relaxed);
+        let alice_options = SecureChannelOptions::new()
+            .with_trust_policy(TrustIdentifierPolicy::new(bob.identifier().clone()))
+            .with_session_resumption(bob.identifier().clone());
+        let alice_channel = secure_channels
+            .create_secure_channel(
+                ctx,
+                alice.identifier(),
+                route![hop_address.clone(), listener_address.clone()],
+                alice_options,
+            )
+            .await?;
+        ctx.sleep(Duration::from_millis(100)).await;
+        assert_eq!(count.load(Ordering::Relaxed), *expected);
+
+        let child_address = Address::random_local();
+        let mut child_ctx = ctx
+            .new_detached_with_mailboxes(Mailboxes::main(
+                child_address.clone(),
+                Arc::new(AllowAll),
+                Arc::new(AllowAll),
+            ))
+            .await?;
+        ctx.flow_controls()
+            .add_consumer(child_address, bob_listener.flow_control_id());
+
+        child_ctx
+            .send(
+                route![alice_channel.clone(), child_ctx.address()],
+                "Hello, Bob!".to_string(),
+            )
+            .await?;
+        let msg = child_ctx.receive::<String>().await?;
+        let local_info = IdentitySecureChannelLocalInfo::find_info(msg.local_message())?;
+        assert_eq!(&local_info.their_identity_id(), alice.identifier());
+        assert_eq!("Hello, Bob!", msg.body());
+    }
+
+    Ok(())
+}
+
+#[ockam_macros::test]
+async fn test_channel_session_resumption(ctx: &mut Context) -> Result<()> {
+    // the first channel needs a full handshake, the next ones resume the previous session
+    // with a new ticket each time
+    check_session_resumption(
+        ctx,
+        SecureChannelListenerOptions::new().with_resumption_tickets(Duration::from_secs(60)),
+        &[3, 2, 2],
+    )
+    .await?;
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_session_resumption_fallback(ctx: &mut Context) -> Result<()> {
+    // the listener does not issue tickets
+    check_session_resumption(ctx, SecureChannelListenerOptions::new(), &[3, 3]).await?;
+    // the tickets expire immediately
+    check_session_resumption(
+        ctx,
+        SecureChannelListenerOptions::new().with_resumption_tickets(Duration::ZERO),
+        &[3, 3],
+    )
+    .await?;
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_session_resumption_hybrid_key_exchange_required(
+    ctx: &mut Context,
+) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let count = Arc::new(AtomicU8::new(0));
+    let hop_address = Address::random_local();
+    ctx.start_worker(
+        hop_address.clone(),
+        CountingHop {
+            count: count.clone(),
+        },
+    )
+    .await?;
+
+    // both listeners of bob share the same resumption tickets
+    let classic_listener = Address::random_local();
+    let hybrid_listener = Address::random_local();
+    for (listener_address, mode) in [
+        (&classic_listener, KeyExchangeMode::Classic),
+        (&hybrid_listener, KeyExchangeMode::HybridOnly),
+    ] {
+        secure_channels
+            .create_secure_channel_listener(
+                ctx,
+                bob.identifier(),
+                listener_address.clone(),
+                SecureChannelListenerOptions::new()
+                    .with_key_exchange_mode(mode)
+                    .with_resumption_tickets(Duration::from_secs(60))
+                    .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone())),
+            )
+            .await?;
+    }
+
+    let mut handshake_messages = vec![];
+    for listener_address in [&classic_listener, &hybrid_listener, &hybrid_listener] {
+        count.store(0, Ordering::Relaxed);
+        let alice_options = SecureChannelOptions::new()
+            .with_key_exchange_mode(KeyExchangeMode::Hybrid)
+            .with_trust_policy(TrustIdentifierPolicy::new(bob.identifier().clone()))
+            .with_session_resumption(bob.identifier().clone());
+        secure_channels
+            .create_secure_channel(
+                ctx,
+                alice.identifier(),
+                route![hop_address.clone(), listener_address.clone()],
+                alice_options,
+            )
+            .await?;
+        ctx.sleep(Duration::from_millis(100)).await;
+        handshake_messages.push(count.load(Ordering::Relaxed));
+    }
+
+    // the session established with a classic key exchange is not resumed by the listener
+    // requiring a hybrid key exchange, the session established with a hybrid key exchange is
+    assert_eq!(handshake_messages, vec![3, 3, 2]);
+    ctx.stop().await
+}
+
+/// Create a secure channel from alice to bob, presenting a credential and trying to resume
+/// the previous session, and return the number of handshake messages exchanged
+#[allow(clippy::too_many_arguments)]
+async fn create_resumed_channel_with_credential(
+    ctx: &mut Context,
+    secure_channels: &Arc<SecureChannels>,
+    alice: &Identifier,
+    bob: &Identifier,
+    hop_address: &Address,
+    count: &Arc<AtomicU8>,
+    listener_address: &Address,
+    trust_context: &TrustContext,
+    credential: CredentialAndPurposeKey,
+) -> Result<u8> {
+    count.store(0, Ordering::Relaxed);
+    let alice_options = SecureChannelOptions::new()
+        .with_trust_policy(TrustIdentifierPolicy::new(bob.clone()))
+        .with_session_resumption(bob.clone())
+        .with_trust_context(trust_context.clone())
+        .with_credential(credential)
+        .with_timeout(Duration::from_secs(1));
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            alice,
+            route![hop_address.clone(), listener_address.clone()],
+            alice_options,
+        )
+        .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    Ok(count.load(Ordering::Relaxed))
+}
+
+#[ockam_macros::test]
+async fn test_channel_session_resumption_credentials(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+    let credentials = secure_channels.identities().credentials();
+
+    let authority = identities_creation.create_identity().await?;
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+    let issue_credential = |subject: Identifier, ttl: u64| {
+        let credentials_creation = credentials.credentials_creation();
+        let authority = authority.identifier().clone();
+        async move {
+            credentials_creation
+                .issue_credential(
+                    &authority,
+                    &subject,
+                    AttributesBuilder::with_schema(SchemaId(1))
+                        .with_attribute("role", "device")
+                        .build(),
+                    Duration::from_secs(ttl),
+                )
+                .await
+        }
+    };
+
+    let count = Arc::new(AtomicU8::new(0));
+    let hop_address = Address::random_local();
+    ctx.start_worker(
+        hop_address.clone(),
+        CountingHop {
+            count: count.clone(),
+        },
+    )
+    .await?;
+
+    let trust_context = TrustContext::new(
+        "test_trust_context_id".to_string(),
+        Some(AuthorityService::new(
+            credentials.clone(),
+            authority.identifier().clone(),
+            None,
+        )),
+    );
+    let listener_address = Address::random_local();
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            listener_address.clone(),
+            SecureChannelListenerOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone()))
+                .with_trust_context(trust_context.clone())
+                .with_credential(issue_credential(bob.identifier().clone(), 60).await?)
+                .with_resumption_tickets(Duration::from_secs(60)),
+        )
+        .await?;
+
+    // the resumption ticket expires with the credential presented during the handshake
+    let mut presented = vec![];
+    for (ttl, expected) in [(2, 3), (60, 3), (60, 2)] {
+        let credential = issue_credential(alice.identifier().clone(), ttl).await?;
+        let handshake_messages = create_resumed_channel_with_credential(
+            ctx,
+            &secure_channels,
+            alice.identifier(),
+            bob.identifier(),
+            &hop_address,
+            &count,
+            &listener_address,
+            &trust_context,
+            credential.clone(),
+        )
+        .await?;
+        assert_eq!(handshake_messages, expected);
+        presented.push(credential);
+        if ttl == 2 {
+            ctx.sleep(Duration::from_secs(3)).await;
+        }
+    }
+
+    // the credential presented during the last full handshake is verified again
+    // when the session is resumed
+    let credential = presented[1].clone();
+    let revocation_list = credentials
+        .credentials_creation()
+        .issue_revocation_list(
+            authority.identifier(),
+            1,
+            vec![],
+            vec![RevokedCredential {
+                credential_hash: credentials
+                    .credentials_verification()
+                    .credential_hash(&credential.credential)
+                    .await?,
+                revoked_at: now()?,
+            }],
+            Duration::from_secs(60),
+        )
+        .await?;
+    credentials
+        .credentials_verification()
+        .receive_revocation_list(&[authority.identifier().clone()], &revocation_list)
+        .await?;
+    assert!(create_resumed_channel_with_credential(
+        ctx,
+        &secure_channels,
+        alice.identifier(),
+        bob.identifier(),
+        &hop_address,
+        &count,
+        &listener_address,
+        &trust_context,
+        credential,
+    )
+    .await
+    .is_err());
+
+    ctx.stop().await
+}
+
 #[ockam_macros::test]
 async fn test_channel_send_multiple_messages_both_directions(ctx: &mut Context) -> Result<()> {
     let secure_channels = secure_channels();
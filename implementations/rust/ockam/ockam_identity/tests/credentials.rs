@@ -1,15 +1,19 @@
-use std::sync::atomic::{AtomicI8, Ordering};
+use std::sync::atomic::{AtomicI8, AtomicUsize, Ordering};
 use std::time::Duration;
 
 use ockam_core::compat::sync::Arc;
 use ockam_core::{async_trait, Any, DenyAll};
 use ockam_core::{route, Result, Routed, Worker};
-use ockam_identity::models::SchemaId;
+use ockam_identity::models::{
+    Credential, CredentialAndPurposeKey, DelegationScope, Identifier, RevocationList,
+    RevocationListAndPurposeKey, SchemaId,
+};
 use ockam_identity::secure_channels::secure_channels;
 use ockam_identity::utils::AttributesBuilder;
 use ockam_identity::{
-    AuthorityService, CredentialAccessControl, CredentialsMemoryRetriever,
-    SecureChannelListenerOptions, SecureChannelOptions, TrustContext, TrustIdentifierPolicy,
+    AuthorityService, CredentialAccessControl, Credentials, CredentialsMemoryRetriever,
+    CredentialsRetriever, SecureChannelListenerOptions, SecureChannelOptions, TrustContext,
+    TrustIdentifierPolicy, MAX_CREDENTIAL_VALIDITY,
 };
 use ockam_node::{Context, WorkerBuilder};
 
@@ -310,6 +314,133 @@ async fn access_control(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+#[ockam_macros::test]
+async fn credentials_refresh(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let identities_repository = identities.repository();
+    let credentials = identities.credentials();
+
+    let authority = identities_creation.create_identity().await?;
+    let server = identities_creation.create_identity().await?;
+    let client = identities_creation.create_identity().await?;
+
+    let trust_context = TrustContext::new(
+        "test_trust_context_id".to_string(),
+        Some(AuthorityService::new(
+            credentials.clone(),
+            authority.identifier().clone(),
+            None,
+        )),
+    );
+
+    // The server presents its own credential, since it has a trust context
+    let server_credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            authority.identifier(),
+            server.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("is_server", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            server.identifier(),
+            "listener",
+            SecureChannelListenerOptions::new()
+                .with_trust_context(trust_context.clone())
+                .with_credential(server_credential),
+        )
+        .await?;
+
+    let retriever = Arc::new(ShortLivedCredentialsRetriever {
+        credentials: credentials.clone(),
+        authority: authority.identifier().clone(),
+        retrievals: Default::default(),
+    });
+
+    secure_channels
+        .create_secure_channel(
+            ctx,
+            client.identifier(),
+            route!["listener"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(server.identifier().clone()))
+                .with_trust_context(trust_context)
+                .with_credentials_retriever(retriever.clone())
+                .with_credential_refresh_time_gap(Duration::from_secs(2)),
+        )
+        .await?;
+
+    // the listener stores the attributes of the client once it received its credential
+    let mut attrs = None;
+    for _ in 0..50 {
+        attrs = identities_repository
+            .get_attributes(client.identifier())
+            .await?;
+        if attrs.is_some() {
+            break;
+        }
+        ctx.sleep(Duration::from_millis(100)).await;
+    }
+    let expires1 = attrs.unwrap().expires().unwrap();
+    assert_eq!(retriever.retrievals.load(Ordering::Relaxed), 1);
+
+    // the credential expires after 4 seconds and is refreshed 2 seconds before
+    ctx.sleep(Duration::from_secs(3)).await;
+
+    let attrs = identities_repository
+        .get_attributes(client.identifier())
+        .await?
+        .unwrap();
+    assert!(attrs.expires().unwrap() > expires1);
+    assert_eq!(
+        attrs
+            .attrs()
+            .get("is_superuser".as_bytes())
+            .unwrap()
+            .as_slice(),
+        b"true"
+    );
+    assert!(retriever.retrievals.load(Ordering::Relaxed) >= 2);
+
+    ctx.stop().await
+}
+
+struct ShortLivedCredentialsRetriever {
+    credentials: Arc<Credentials>,
+    authority: Identifier,
+    retrievals: AtomicUsize,
+}
+
+#[async_trait]
+impl CredentialsRetriever for ShortLivedCredentialsRetriever {
+    async fn retrieve(
+        &self,
+        _ctx: &Context,
+        for_identity: &Identifier,
+    ) -> Result<CredentialAndPurposeKey> {
+        self.retrievals.fetch_add(1, Ordering::Relaxed);
+        self.credentials
+            .credentials_creation()
+            .issue_credential(
+                &self.authority,
+                for_identity,
+                AttributesBuilder::with_schema(SchemaId(0))
+                    .with_attribute("is_superuser", "true")
+                    .build(),
+                Duration::from_secs(4),
+            )
+            .await
+    }
+}
+
 struct CountingWorker {
     msgs_count: Arc<AtomicI8>,
 }
@@ -329,3 +460,404 @@ impl Worker for CountingWorker {
         Ok(())
     }
 }
+
+#[ockam_macros::test]
+async fn delegated_credentials(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+    let credentials_creation = credentials.credentials_creation();
+    let credentials_verification = credentials.credentials_verification();
+
+    let authority = identities_creation.create_identity().await?;
+    let lead = identities_creation.create_identity().await?;
+    let sub_lead = identities_creation.create_identity().await?;
+    let device = identities_creation.create_identity().await?;
+    let authorities = [authority.identifier().clone()];
+
+    let scope = DelegationScope::new(Duration::from_secs(60))
+        .with_attribute(b"role".to_vec(), vec![b"device".to_vec()])
+        .with_attribute(b"team".to_vec(), vec![]);
+    let delegation = credentials_creation
+        .issue_delegation(
+            authority.identifier(),
+            lead.identifier(),
+            &scope,
+            Duration::from_secs(120),
+            None,
+        )
+        .await?;
+    let (delegation_authority, delegation_scope) = credentials_verification
+        .verify_delegation(&authorities, &delegation)
+        .await?;
+    assert_eq!(&delegation_authority, authority.identifier());
+    assert_eq!(delegation_scope, scope);
+
+    // The delegate issues a credential within the scope of its delegation
+    let credential = credentials_creation
+        .issue_delegated_credential(
+            lead.identifier(),
+            device.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("role", "device")
+                .with_attribute("team", "blue")
+                .build(),
+            Duration::from_secs(60),
+            &delegation,
+        )
+        .await?;
+    let credential_data = credentials_verification
+        .verify_credential(Some(device.identifier()), &authorities, &credential)
+        .await?;
+    assert_eq!(&credential_data.authority, authority.identifier());
+    assert_eq!(&credential_data.purpose_key_data.subject, lead.identifier());
+
+    // The chain must start at one of the authorities
+    assert!(credentials_verification
+        .verify_credential(
+            Some(device.identifier()),
+            &[lead.identifier().clone()],
+            &credential
+        )
+        .await
+        .is_err());
+
+    // A delegation is not a credential
+    assert!(credentials_verification
+        .verify_credential(Some(lead.identifier()), &authorities, &delegation)
+        .await
+        .is_err());
+
+    // The delegate can't issue credentials outside of its scope
+    for (attribute, ttl) in [("admin", 60), ("device", 61)] {
+        assert!(credentials_creation
+            .issue_delegated_credential(
+                lead.identifier(),
+                device.identifier(),
+                AttributesBuilder::with_schema(SchemaId(0))
+                    .with_attribute("role", attribute)
+                    .build(),
+                Duration::from_secs(ttl),
+                &delegation,
+            )
+            .await
+            .is_err());
+    }
+
+    // A credential issued outside of the scope is rejected when verified
+    let mut forged = credentials_creation
+        .issue_credential(
+            lead.identifier(),
+            device.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("role", "admin")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    forged.delegations = Some(delegation.delegation_chain());
+    assert!(credentials_verification
+        .verify_credential(Some(device.identifier()), &authorities, &forged)
+        .await
+        .is_err());
+
+    // The delegation doesn't allow to delegate further
+    let sub_scope = DelegationScope::new(Duration::from_secs(30))
+        .with_attribute(b"team".to_vec(), vec![b"blue".to_vec()]);
+    assert!(credentials_creation
+        .issue_delegation(
+            lead.identifier(),
+            sub_lead.identifier(),
+            &sub_scope,
+            Duration::from_secs(60),
+            Some(&delegation),
+        )
+        .await
+        .is_err());
+    let mut forged = credentials_creation
+        .issue_delegation(
+            lead.identifier(),
+            sub_lead.identifier(),
+            &sub_scope,
+            Duration::from_secs(60),
+            None,
+        )
+        .await?;
+    forged.delegations = Some(delegation.delegation_chain());
+    assert!(credentials_verification
+        .verify_delegation(&authorities, &forged)
+        .await
+        .is_err());
+
+    // A delegation allowing to delegate further
+    let delegation = credentials_creation
+        .issue_delegation(
+            authority.identifier(),
+            lead.identifier(),
+            &scope.clone().with_can_delegate(true),
+            Duration::from_secs(120),
+            None,
+        )
+        .await?;
+    let sub_delegation = credentials_creation
+        .issue_delegation(
+            lead.identifier(),
+            sub_lead.identifier(),
+            &sub_scope,
+            Duration::from_secs(60),
+            Some(&delegation),
+        )
+        .await?;
+    let credential = credentials_creation
+        .issue_delegated_credential(
+            sub_lead.identifier(),
+            device.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("team", "blue")
+                .build(),
+            Duration::from_secs(30),
+            &sub_delegation,
+        )
+        .await?;
+    let credential_data = credentials_verification
+        .verify_credential(Some(device.identifier()), &authorities, &credential)
+        .await?;
+    assert_eq!(&credential_data.authority, authority.identifier());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn delegated_credentials_with_default_ttls(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+    let credentials_creation = credentials.credentials_creation();
+    let credentials_verification = credentials.credentials_verification();
+
+    let authority = identities_creation.create_identity().await?;
+    let lead = identities_creation.create_identity().await?;
+    let sub_lead = identities_creation.create_identity().await?;
+    let device = identities_creation.create_identity().await?;
+    let authorities = [authority.identifier().clone()];
+
+    let scope = DelegationScope::new(MAX_CREDENTIAL_VALIDITY)
+        .with_attribute(b"role".to_vec(), vec![])
+        .with_can_delegate(true);
+    let delegation = credentials_creation
+        .issue_delegation(
+            authority.identifier(),
+            lead.identifier(),
+            &scope,
+            MAX_CREDENTIAL_VALIDITY,
+            None,
+        )
+        .await?;
+
+    // The sub-delegation and the credential are issued later with the same ttl,
+    // they must not outlive the delegations allowing to issue them
+    ctx.sleep(Duration::from_millis(1100)).await;
+    let sub_delegation = credentials_creation
+        .issue_delegation(
+            lead.identifier(),
+            sub_lead.identifier(),
+            &scope.with_can_delegate(false),
+            MAX_CREDENTIAL_VALIDITY,
+            Some(&delegation),
+        )
+        .await?;
+    credentials_verification
+        .verify_delegation(&authorities, &sub_delegation)
+        .await?;
+
+    ctx.sleep(Duration::from_millis(1100)).await;
+    let credential = credentials_creation
+        .issue_delegated_credential(
+            sub_lead.identifier(),
+            device.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("role", "device")
+                .build(),
+            MAX_CREDENTIAL_VALIDITY,
+            &sub_delegation,
+        )
+        .await?;
+    credentials_verification
+        .verify_credential(Some(device.identifier()), &authorities, &credential)
+        .await?;
+
+    // No credential can be issued once the delegation expired
+    let short_delegation = credentials_creation
+        .issue_delegation(
+            authority.identifier(),
+            lead.identifier(),
+            &DelegationScope::new(MAX_CREDENTIAL_VALIDITY).with_attribute(b"role".to_vec(), vec![]),
+            Duration::from_secs(1),
+            None,
+        )
+        .await?;
+    ctx.sleep(Duration::from_millis(2100)).await;
+    assert!(credentials_creation
+        .issue_delegated_credential(
+            lead.identifier(),
+            device.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("role", "device")
+                .build(),
+            MAX_CREDENTIAL_VALIDITY,
+            &short_delegation,
+        )
+        .await
+        .is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn delegated_credentials_verified_with_the_authority_only(ctx: &mut Context) -> Result<()> {
+    let issuer = secure_channels();
+    let identities = issuer.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials_creation = identities.credentials().credentials_creation();
+
+    let authority = identities_creation.create_identity().await?;
+    let lead = identities_creation.create_identity().await?;
+    let sub_lead = identities_creation.create_identity().await?;
+    let device = identities_creation.create_identity().await?;
+    let authorities = [authority.identifier().clone()];
+
+    let scope = DelegationScope::new(Duration::from_secs(60))
+        .with_attribute(b"role".to_vec(), vec![])
+        .with_can_delegate(true);
+    let delegation = credentials_creation
+        .issue_delegation(
+            authority.identifier(),
+            lead.identifier(),
+            &scope,
+            Duration::from_secs(60),
+            None,
+        )
+        .await?;
+    let sub_delegation = credentials_creation
+        .issue_delegation(
+            lead.identifier(),
+            sub_lead.identifier(),
+            &scope.with_can_delegate(false),
+            Duration::from_secs(60),
+            Some(&delegation),
+        )
+        .await?;
+    let credential = credentials_creation
+        .issue_delegated_credential(
+            sub_lead.identifier(),
+            device.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("role", "device")
+                .build(),
+            Duration::from_secs(60),
+            &sub_delegation,
+        )
+        .await?;
+
+    // The verifier only knows the authority, the delegates are imported from the delegations
+    let verifier = secure_channels();
+    let verifier_identities = verifier.identities();
+    verifier_identities
+        .identities_creation()
+        .import(Some(authority.identifier()), &authority.export()?)
+        .await?;
+    let credentials_verification = verifier_identities.credentials().credentials_verification();
+
+    // The change history of a delegate must be the one of the subject of its delegation
+    let mut forged = credential.clone();
+    if let Some(delegations) = forged.delegations.as_mut() {
+        delegations[0].subject_change_history = Some(device.change_history().clone());
+    }
+    assert!(credentials_verification
+        .verify_credential(Some(device.identifier()), &authorities, &forged)
+        .await
+        .is_err());
+
+    let credential_data = credentials_verification
+        .verify_credential(Some(device.identifier()), &authorities, &credential)
+        .await?;
+    assert_eq!(&credential_data.authority, authority.identifier());
+    assert_eq!(
+        &credential_data.purpose_key_data.subject,
+        sub_lead.identifier()
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn revocation_lists_are_not_credentials(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+    let credentials_creation = credentials.credentials_creation();
+    let credentials_verification = credentials.credentials_verification();
+
+    let authority = identities_creation.create_identity().await?;
+    let subject = identities_creation.create_identity().await?;
+    let authorities = [authority.identifier().clone()];
+
+    let credential = credentials_creation
+        .issue_credential(
+            authority.identifier(),
+            subject.identifier(),
+            AttributesBuilder::with_schema(SchemaId(0))
+                .with_attribute("role", "device")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    let revocation_list = credentials_creation
+        .issue_revocation_list(
+            authority.identifier(),
+            1,
+            vec![],
+            vec![],
+            Duration::from_secs(60),
+        )
+        .await?;
+    let (issuer, data) = credentials_verification
+        .verify_revocation_list(&authorities, &revocation_list)
+        .await?;
+    assert_eq!(&issuer, authority.identifier());
+    assert_eq!(data.sequence, 1);
+
+    // Both are signed with the same Purpose Key, but a signature over a revocation list
+    // can't be used as a signature over a credential
+    let credential_from_revocation_list = CredentialAndPurposeKey {
+        credential: Credential {
+            data: revocation_list.revocation_list.data.clone(),
+            signature: revocation_list.revocation_list.signature.clone(),
+        },
+        purpose_key_attestation: revocation_list.purpose_key_attestation.clone(),
+        delegations: None,
+        subject_change_history: None,
+    };
+    assert!(credentials_verification
+        .verify_credential(None, &authorities, &credential_from_revocation_list)
+        .await
+        .is_err());
+
+    // and the other way around
+    let revocation_list_from_credential = RevocationListAndPurposeKey {
+        revocation_list: RevocationList {
+            data: credential.credential.data.clone(),
+            signature: credential.credential.signature.clone(),
+        },
+        purpose_key_attestation: credential.purpose_key_attestation.clone(),
+    };
+    assert!(credentials_verification
+        .verify_revocation_list(&authorities, &revocation_list_from_credential)
+        .await
+        .is_err());
+
+    ctx.stop().await
+}
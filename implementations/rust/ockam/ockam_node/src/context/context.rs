@@ -1,4 +1,5 @@
 use crate::channel_types::{SmallReceiver, SmallSender};
+use crate::metrics::MetricsSink;
 use crate::tokio::runtime::Handle;
 use crate::{error::*, AsyncDropSender, NodeMessage};
 use core::sync::atomic::AtomicUsize;
@@ -26,6 +27,7 @@ pub struct Context {
     /// List of transports used to resolve external addresses to local workers in routes
     pub(super) transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
     pub(super) flow_controls: FlowControls,
+    pub(super) metrics: Arc<dyn MetricsSink>,
 }
 
 /// This trait can be used to integrate transports into a node
@@ -81,6 +83,11 @@ impl Context {
     pub fn flow_controls(&self) -> &FlowControls {
         &self.flow_controls
     }
+
+    /// Shared [`MetricsSink`] of the node
+    pub fn metrics(&self) -> &Arc<dyn MetricsSink> {
+        &self.metrics
+    }
 }
 
 impl Context {
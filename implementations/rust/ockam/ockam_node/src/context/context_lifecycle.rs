@@ -12,6 +12,7 @@ use ockam_transport_core::Transport;
 
 use crate::async_drop::AsyncDrop;
 use crate::channel_types::{message_channel, small_channel, SmallReceiver, SmallSender};
+use crate::metrics::MetricsSink;
 use crate::tokio::{self, runtime::Handle};
 use crate::{debugger, Context};
 use crate::{error::*, relay::CtrlSignal, router::SenderPair, NodeMessage};
@@ -25,6 +26,7 @@ pub type AsyncDropSender = crate::tokio::sync::oneshot::Sender<Address>;
 
 impl Drop for Context {
     fn drop(&mut self) {
+        self.metrics.worker_stopped(&self.address());
         if let Some(sender) = self.async_drop_sender.take() {
             trace!("De-allocated detached context {}", self.address());
             if let Err(e) = sender.send(self.address()) {
@@ -62,9 +64,11 @@ impl Context {
         async_drop_sender: Option<AsyncDropSender>,
         transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
         flow_controls: &FlowControls,
+        metrics: Arc<dyn MetricsSink>,
     ) -> (Self, SenderPair, SmallReceiver<CtrlSignal>) {
         let (mailbox_tx, receiver) = message_channel();
         let (ctrl_tx, ctrl_rx) = small_channel();
+        metrics.worker_started(&mailboxes.main_address());
         (
             Self {
                 rt,
@@ -75,6 +79,7 @@ impl Context {
                 mailbox_count: Arc::new(0.into()),
                 transports,
                 flow_controls: flow_controls.clone(),
+                metrics,
             },
             SenderPair {
                 msgs: mailbox_tx,
@@ -95,6 +100,7 @@ impl Context {
             None,
             self.transports.clone(),
             &self.flow_controls,
+            self.metrics.clone(),
         )
     }
 
@@ -110,6 +116,7 @@ impl Context {
             Some(drop_sender),
             self.transports.clone(),
             &self.flow_controls,
+            self.metrics.clone(),
         )
     }
 
@@ -168,8 +175,7 @@ impl Context {
         let (ctx, sender, _) = self.copy_with_mailboxes_detached(mailboxes, drop_sender);
 
         // Create a "detached relay" and register it with the router
-        let (msg, mut rx) =
-            NodeMessage::start_worker(addresses, sender, true, Arc::clone(&self.mailbox_count));
+        let (msg, mut rx) = NodeMessage::start_worker(addresses, sender, true, ctx.mailbox_count());
         self.sender
             .send(msg)
             .await
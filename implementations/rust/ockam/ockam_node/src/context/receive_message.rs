@@ -4,6 +4,7 @@ use core::time::Duration;
 use ockam_core::{Message, RelayMessage, Result, Routed};
 
 use crate::debugger;
+use crate::metrics::AccessControlDirection;
 use crate::tokio::time::timeout;
 use crate::{error::*, parser};
 use crate::{Context, DEFAULT_TIMEOUT};
@@ -64,7 +65,9 @@ impl Context {
                 trace!("{}: received new message!", self.address());
 
                 // First we update the mailbox fill metrics
-                self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+                let depth = self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+                self.metrics
+                    .mailbox_depth(&self.address(), depth.saturating_sub(1));
 
                 msg
             }) {
@@ -82,6 +85,8 @@ impl Context {
                     relay_msg.return_route(),
                     relay_msg.destination()
                 );
+                self.metrics
+                    .access_denied(relay_msg.destination(), AccessControlDirection::Incoming);
                 continue;
             }
 
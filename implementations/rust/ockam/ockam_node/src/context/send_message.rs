@@ -1,5 +1,6 @@
 use crate::channel_types::small_channel;
 use crate::context::MessageWait;
+use crate::metrics::AccessControlDirection;
 use crate::{debugger, Context, MessageReceiveOptions, DEFAULT_TIMEOUT};
 use crate::{error::*, NodeMessage};
 use core::time::Duration;
@@ -268,6 +269,8 @@ impl Context {
                 relay_msg.source(),
                 relay_msg.destination()
             );
+            self.metrics
+                .access_denied(relay_msg.source(), AccessControlDirection::Outgoing);
             return Ok(());
         }
 
@@ -353,6 +356,8 @@ impl Context {
                 relay_msg.source(),
                 relay_msg.destination(),
             );
+            self.metrics
+                .access_denied(relay_msg.source(), AccessControlDirection::Outgoing);
             return Ok(());
         }
 
@@ -1,12 +1,14 @@
 // use crate::message::BaseMessage;
 
 use crate::channel_types::SmallSender;
+use crate::metrics::MetricsSink;
 use crate::{
     router::{Router, SenderPair},
     tokio::runtime::{Handle, Runtime},
     NodeMessage,
 };
 use core::future::Future;
+use ockam_core::compat::sync::Arc;
 use ockam_core::{Address, Result};
 
 #[cfg(feature = "metrics")]
@@ -41,9 +43,9 @@ pub struct Executor {
 
 impl Executor {
     /// Create a new Ockam node [`Executor`] instance
-    pub fn new(flow_controls: &FlowControls) -> Self {
+    pub fn new(flow_controls: &FlowControls, metrics_sink: Arc<dyn MetricsSink>) -> Self {
         let rt = Runtime::new().unwrap();
-        let router = Router::new(flow_controls);
+        let router = Router::new(flow_controls, metrics_sink);
         #[cfg(feature = "metrics")]
         let metrics = Metrics::new(&rt, router.get_metrics_readout());
         Self {
@@ -0,0 +1,22 @@
+/// OpenMetrics text rendering of a [`MetricsRegistry`]
+#[cfg(feature = "std")]
+mod open_metrics;
+
+/// In-memory aggregation of the node metrics
+#[cfg(feature = "std")]
+mod registry;
+
+/// Periodic collection of the async runtime metrics
+#[cfg(feature = "metrics")]
+mod runtime;
+
+/// Trait receiving the metrics events of a node
+mod sink;
+
+#[cfg(feature = "std")]
+pub use open_metrics::*;
+#[cfg(feature = "std")]
+pub use registry::*;
+#[cfg(feature = "metrics")]
+pub(crate) use runtime::Metrics;
+pub use sink::*;
@@ -0,0 +1,195 @@
+use crate::metrics::{AccessControlDirection, MetricsRegistry, LATENCY_BUCKETS};
+use core::fmt::Write;
+use ockam_core::compat::string::String;
+
+/// Content type of the OpenMetrics text exposition format
+pub const OPEN_METRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+impl MetricsRegistry {
+    /// Render all the metrics of this registry in the OpenMetrics text format
+    pub fn render_open_metrics(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail
+        let _ = self.write_open_metrics(&mut out);
+        out
+    }
+
+    fn write_open_metrics(&self, out: &mut String) -> core::fmt::Result {
+        let workers = self.workers();
+
+        writeln!(out, "# TYPE ockam_node_messages_handled counter")?;
+        writeln!(
+            out,
+            "# HELP ockam_node_messages_handled Messages handled by all the workers of the node."
+        )?;
+        writeln!(
+            out,
+            "ockam_node_messages_handled_total {}",
+            self.messages_handled()
+        )?;
+
+        writeln!(out, "# TYPE ockam_router_resolution_failures counter")?;
+        writeln!(
+            out,
+            "# HELP ockam_router_resolution_failures Addresses which could not be resolved to a running worker."
+        )?;
+        writeln!(
+            out,
+            "ockam_router_resolution_failures_total {}",
+            self.resolution_failures()
+        )?;
+
+        writeln!(out, "# TYPE ockam_node_access_control_denials counter")?;
+        writeln!(
+            out,
+            "# HELP ockam_node_access_control_denials Messages rejected by an access control."
+        )?;
+        for direction in [
+            AccessControlDirection::Incoming,
+            AccessControlDirection::Outgoing,
+        ] {
+            writeln!(
+                out,
+                "ockam_node_access_control_denials_total{{direction=\"{}\"}} {}",
+                direction,
+                self.access_denials(direction)
+            )?;
+        }
+
+        writeln!(out, "# TYPE ockam_worker_messages_handled counter")?;
+        writeln!(
+            out,
+            "# HELP ockam_worker_messages_handled Messages handled by a running worker."
+        )?;
+        for (address, metrics) in workers.iter() {
+            writeln!(
+                out,
+                "ockam_worker_messages_handled_total{{address=\"{}\"}} {}",
+                escape(address),
+                metrics.messages_handled
+            )?;
+        }
+
+        writeln!(out, "# TYPE ockam_worker_mailbox_depth gauge")?;
+        writeln!(
+            out,
+            "# HELP ockam_worker_mailbox_depth Messages waiting in the mailbox of a running worker."
+        )?;
+        for (address, metrics) in workers.iter() {
+            writeln!(
+                out,
+                "ockam_worker_mailbox_depth{{address=\"{}\"}} {}",
+                escape(address),
+                metrics.mailbox_depth
+            )?;
+        }
+
+        writeln!(out, "# TYPE ockam_worker_handler_latency_seconds histogram")?;
+        writeln!(
+            out,
+            "# HELP ockam_worker_handler_latency_seconds Time spent by a running worker to handle a message."
+        )?;
+        for (address, metrics) in workers.iter() {
+            let address = escape(address);
+            let histogram = &metrics.handler_latency;
+            let buckets = histogram.cumulative_buckets();
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(buckets.iter()) {
+                writeln!(
+                    out,
+                    "ockam_worker_handler_latency_seconds_bucket{{address=\"{}\",le=\"{}\"}} {}",
+                    address, bound, count
+                )?;
+            }
+            writeln!(
+                out,
+                "ockam_worker_handler_latency_seconds_bucket{{address=\"{}\",le=\"+Inf\"}} {}",
+                address,
+                histogram.count()
+            )?;
+            writeln!(
+                out,
+                "ockam_worker_handler_latency_seconds_sum{{address=\"{}\"}} {}",
+                address,
+                histogram.sum().as_secs_f64()
+            )?;
+            writeln!(
+                out,
+                "ockam_worker_handler_latency_seconds_count{{address=\"{}\"}} {}",
+                address,
+                histogram.count()
+            )?;
+        }
+
+        writeln!(out, "# TYPE ockam_worker_access_control_denials counter")?;
+        writeln!(
+            out,
+            "# HELP ockam_worker_access_control_denials Messages rejected by an access control of a running worker."
+        )?;
+        for (address, metrics) in workers.iter() {
+            let address = escape(address);
+            writeln!(
+                out,
+                "ockam_worker_access_control_denials_total{{address=\"{}\",direction=\"incoming\"}} {}",
+                address, metrics.incoming_denials
+            )?;
+            writeln!(
+                out,
+                "ockam_worker_access_control_denials_total{{address=\"{}\",direction=\"outgoing\"}} {}",
+                address, metrics.outgoing_denials
+            )?;
+        }
+
+        writeln!(out, "# EOF")
+    }
+}
+
+/// Escape a label value as required by the OpenMetrics text format
+fn escape(value: impl core::fmt::Display) -> String {
+    let mut escaped = String::new();
+    for c in format!("{}", value).chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::MetricsSink;
+    use core::time::Duration;
+    use ockam_core::Address;
+
+    #[test]
+    fn test_render_open_metrics() {
+        let registry = MetricsRegistry::new();
+        let address: Address = "api".into();
+        registry.worker_started(&address);
+        registry.message_handled(&address, Some(Duration::from_millis(2)));
+        registry.mailbox_depth(&address, 3);
+        registry.resolution_failed(&"unknown".into());
+
+        let text = registry.render_open_metrics();
+        assert!(text.contains("ockam_node_messages_handled_total 1\n"));
+        assert!(text.contains("ockam_router_resolution_failures_total 1\n"));
+        assert!(text.contains("ockam_worker_messages_handled_total{address=\"0#api\"} 1\n"));
+        assert!(text.contains("ockam_worker_mailbox_depth{address=\"0#api\"} 3\n"));
+        assert!(text.contains(
+            "ockam_worker_handler_latency_seconds_bucket{address=\"0#api\",le=\"0.001\"} 0\n"
+        ));
+        assert!(text.contains(
+            "ockam_worker_handler_latency_seconds_bucket{address=\"0#api\",le=\"0.0025\"} 1\n"
+        ));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,313 @@
+use crate::metrics::{AccessControlDirection, MetricsSink};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Address;
+
+/// Upper bounds, in seconds, of the buckets of the handler latency histograms
+pub const LATENCY_BUCKETS: [f64; 12] = [
+    0.000_05, 0.000_1, 0.000_25, 0.000_5, 0.001, 0.002_5, 0.005, 0.01, 0.025, 0.1, 0.5, 1.0,
+];
+
+/// Index of the [`LATENCY_BUCKETS`] bucket of an observation, or
+/// `LATENCY_BUCKETS.len()` if it is above the largest bound
+fn latency_bucket(latency: Duration) -> usize {
+    let seconds = latency.as_secs_f64();
+    LATENCY_BUCKETS
+        .iter()
+        .position(|bound| seconds <= *bound)
+        .unwrap_or(LATENCY_BUCKETS.len())
+}
+
+/// Histogram with the fixed [`LATENCY_BUCKETS`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Number of observations per bucket (not cumulative). The last entry
+    /// counts observations above the largest bound
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: Duration,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    /// Record a new observation
+    pub fn observe(&mut self, latency: Duration) {
+        self.buckets[latency_bucket(latency)] += 1;
+        self.sum += latency;
+        self.count += 1;
+    }
+
+    /// Cumulative number of observations for each bound of [`LATENCY_BUCKETS`]
+    /// followed by the total for the `+Inf` bound
+    pub fn cumulative_buckets(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .scan(0, |total, count| {
+                *total += count;
+                Some(*total)
+            })
+            .collect()
+    }
+
+    /// Sum of all the observations
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// Number of observations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Metrics collected for a single worker address
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkerMetrics {
+    /// Number of messages handled by the worker
+    pub messages_handled: u64,
+    /// Number of messages left in the mailbox when the last message was received
+    pub mailbox_depth: usize,
+    /// Time spent in the `handle_message` function of the worker
+    pub handler_latency: LatencyHistogram,
+    /// Number of messages rejected by the incoming access control
+    pub incoming_denials: u64,
+    /// Number of messages rejected by the outgoing access control
+    pub outgoing_denials: u64,
+}
+
+/// Atomic counters updated while a worker handles its messages
+#[derive(Debug, Default)]
+struct WorkerCounters {
+    messages_handled: AtomicU64,
+    mailbox_depth: AtomicUsize,
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_sum_nanos: AtomicU64,
+    latency_count: AtomicU64,
+    incoming_denials: AtomicU64,
+    outgoing_denials: AtomicU64,
+}
+
+impl WorkerCounters {
+    fn observe_latency(&self, latency: Duration) {
+        self.latency_buckets[latency_bucket(latency)].fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.latency_sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> WorkerMetrics {
+        let mut buckets = [0; LATENCY_BUCKETS.len() + 1];
+        for (bucket, counter) in buckets.iter_mut().zip(self.latency_buckets.iter()) {
+            *bucket = counter.load(Ordering::Relaxed);
+        }
+        WorkerMetrics {
+            messages_handled: self.messages_handled.load(Ordering::Relaxed),
+            mailbox_depth: self.mailbox_depth.load(Ordering::Relaxed),
+            handler_latency: LatencyHistogram {
+                buckets,
+                sum: Duration::from_nanos(self.latency_sum_nanos.load(Ordering::Relaxed)),
+                count: self.latency_count.load(Ordering::Relaxed),
+            },
+            incoming_denials: self.incoming_denials.load(Ordering::Relaxed),
+            outgoing_denials: self.outgoing_denials.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// In-memory [`MetricsSink`] aggregating the metrics of a node
+///
+/// The counters of a worker are registered when the worker starts, so that
+/// handling a message only updates atomic counters under a shared lock.
+///
+/// Per-worker metrics are discarded when the worker stops, since most
+/// workers (secure channels, portals, ...) use random addresses.
+/// Node-wide totals are kept for the lifetime of the registry.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    workers: RwLock<BTreeMap<Address, Arc<WorkerCounters>>>,
+    messages_handled: AtomicU64,
+    resolution_failures: AtomicU64,
+    incoming_denials: AtomicU64,
+    outgoing_denials: AtomicU64,
+}
+
+impl MetricsRegistry {
+    /// Create a new, empty, registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new, empty, registry
+    pub fn create() -> Arc<MetricsRegistry> {
+        Arc::new(Self::new())
+    }
+
+    /// Return a copy of the metrics of all the running workers
+    pub fn workers(&self) -> BTreeMap<Address, WorkerMetrics> {
+        self.workers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(address, counters)| (address.clone(), counters.snapshot()))
+            .collect()
+    }
+
+    /// Return a copy of the metrics of a given worker
+    pub fn worker(&self, address: &Address) -> Option<WorkerMetrics> {
+        self.workers
+            .read()
+            .unwrap()
+            .get(address)
+            .map(|counters| counters.snapshot())
+    }
+
+    /// Total number of messages handled by all the workers of the node
+    pub fn messages_handled(&self) -> u64 {
+        self.messages_handled.load(Ordering::Relaxed)
+    }
+
+    /// Total number of addresses which the router could not resolve
+    pub fn resolution_failures(&self) -> u64 {
+        self.resolution_failures.load(Ordering::Relaxed)
+    }
+
+    /// Total number of messages rejected by an access control
+    pub fn access_denials(&self, direction: AccessControlDirection) -> u64 {
+        match direction {
+            AccessControlDirection::Incoming => self.incoming_denials.load(Ordering::Relaxed),
+            AccessControlDirection::Outgoing => self.outgoing_denials.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Return the counters of a running worker. Reports for a worker which
+    /// already stopped are ignored, so that they don't register it again
+    fn counters(&self, address: &Address) -> Option<Arc<WorkerCounters>> {
+        self.workers.read().unwrap().get(address).cloned()
+    }
+}
+
+impl MetricsSink for MetricsRegistry {
+    fn message_handled(&self, address: &Address, latency: Option<Duration>) {
+        self.messages_handled.fetch_add(1, Ordering::Relaxed);
+        if let Some(counters) = self.counters(address) {
+            counters.messages_handled.fetch_add(1, Ordering::Relaxed);
+            if let Some(latency) = latency {
+                counters.observe_latency(latency);
+            }
+        }
+    }
+
+    fn mailbox_depth(&self, address: &Address, depth: usize) {
+        if let Some(counters) = self.counters(address) {
+            counters.mailbox_depth.store(depth, Ordering::Relaxed)
+        }
+    }
+
+    fn resolution_failed(&self, _address: &Address) {
+        self.resolution_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn access_denied(&self, address: &Address, direction: AccessControlDirection) {
+        match direction {
+            AccessControlDirection::Incoming => {
+                self.incoming_denials.fetch_add(1, Ordering::Relaxed);
+                if let Some(counters) = self.counters(address) {
+                    counters.incoming_denials.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            AccessControlDirection::Outgoing => {
+                self.outgoing_denials.fetch_add(1, Ordering::Relaxed);
+                if let Some(counters) = self.counters(address) {
+                    counters.outgoing_denials.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    fn worker_started(&self, address: &Address) {
+        self.workers
+            .write()
+            .unwrap()
+            .insert(address.clone(), Default::default());
+    }
+
+    fn worker_stopped(&self, address: &Address) {
+        self.workers.write().unwrap().remove(address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.observe(Duration::from_micros(10));
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_secs(2));
+
+        let buckets = histogram.cumulative_buckets();
+        assert_eq!(buckets.len(), LATENCY_BUCKETS.len() + 1);
+        assert_eq!(buckets[0], 1);
+        assert_eq!(buckets[6], 2);
+        assert_eq!(buckets[LATENCY_BUCKETS.len() - 1], 2);
+        assert_eq!(buckets[LATENCY_BUCKETS.len()], 3);
+        assert_eq!(histogram.count(), 3);
+    }
+
+    #[test]
+    fn test_registry_drops_stopped_workers() {
+        let registry = MetricsRegistry::new();
+        let address: Address = "worker".into();
+
+        registry.worker_started(&address);
+        registry.message_handled(&address, Some(Duration::from_millis(1)));
+        registry.mailbox_depth(&address, 4);
+        registry.access_denied(&address, AccessControlDirection::Incoming);
+        registry.resolution_failed(&"unknown".into());
+
+        let worker = registry.worker(&address).unwrap();
+        assert_eq!(worker.messages_handled, 1);
+        assert_eq!(worker.mailbox_depth, 4);
+        assert_eq!(worker.incoming_denials, 1);
+        assert_eq!(worker.outgoing_denials, 0);
+        assert_eq!(registry.resolution_failures(), 1);
+
+        registry.worker_stopped(&address);
+        assert!(registry.worker(&address).is_none());
+
+        // late reports for a stopped worker only update the node-wide totals
+        registry.message_handled(&address, None);
+        registry.access_denied(&address, AccessControlDirection::Incoming);
+        assert!(registry.workers().is_empty());
+        assert_eq!(registry.messages_handled(), 2);
+        assert_eq!(registry.access_denials(AccessControlDirection::Incoming), 2);
+    }
+
+    #[test]
+    fn test_registry_records_started_workers() {
+        let registry = MetricsRegistry::new();
+        let address: Address = "worker".into();
+
+        registry.worker_started(&address);
+        assert_eq!(registry.worker(&address), Some(WorkerMetrics::default()));
+
+        registry.message_handled(&address, Some(Duration::from_micros(10)));
+        registry.message_handled(&address, Some(Duration::from_secs(2)));
+        registry.access_denied(&address, AccessControlDirection::Outgoing);
+
+        let worker = registry.worker(&address).unwrap();
+        assert_eq!(worker.messages_handled, 2);
+        assert_eq!(worker.outgoing_denials, 1);
+        assert_eq!(worker.handler_latency.count(), 2);
+        assert_eq!(
+            worker.handler_latency.sum(),
+            Duration::from_secs(2) + Duration::from_micros(10)
+        );
+        assert_eq!(worker.handler_latency.cumulative_buckets()[0], 1);
+        assert_eq!(registry.workers().len(), 1);
+    }
+}
@@ -0,0 +1,57 @@
+use core::fmt;
+use core::time::Duration;
+use ockam_core::Address;
+
+/// Direction of a message that was checked by an access control
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccessControlDirection {
+    /// The message was checked by the incoming access control of its destination
+    Incoming,
+    /// The message was checked by the outgoing access control of its sender
+    Outgoing,
+}
+
+impl fmt::Display for AccessControlDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessControlDirection::Incoming => write!(f, "incoming"),
+            AccessControlDirection::Outgoing => write!(f, "outgoing"),
+        }
+    }
+}
+
+/// Destination for the metrics produced by the router and the workers of a node
+///
+/// All the functions have a default implementation doing nothing, so that
+/// a sink only needs to implement the events it is interested in.
+///
+/// The functions of a sink are called while messages are being routed and
+/// handled, so they must return quickly and never block.
+pub trait MetricsSink: Send + Sync + 'static {
+    /// A worker finished handling a message
+    ///
+    /// `latency` is the time spent in the worker `handle_message` function.
+    /// It is `None` on platforms without a monotonic clock.
+    fn message_handled(&self, _address: &Address, _latency: Option<Duration>) {}
+
+    /// A message was taken out of the mailbox of a worker, leaving `depth` messages queued
+    fn mailbox_depth(&self, _address: &Address, _depth: usize) {}
+
+    /// The router could not resolve `address` to a running worker
+    fn resolution_failed(&self, _address: &Address) {}
+
+    /// A message was dropped because it did not pass an access control of `address`
+    fn access_denied(&self, _address: &Address, _direction: AccessControlDirection) {}
+
+    /// A worker, processor or detached context was started with this address
+    fn worker_started(&self, _address: &Address) {}
+
+    /// The worker, processor or detached context with this address was stopped
+    fn worker_stopped(&self, _address: &Address) {}
+}
+
+/// A [`MetricsSink`] discarding all the events. This is the default sink of a node
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {}
@@ -2,6 +2,7 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::FlowControls;
 use ockam_core::{Address, AllowAll, Mailbox, Mailboxes};
 
+use crate::metrics::{MetricsSink, NoopMetricsSink};
 use crate::{debugger, Context, Executor};
 
 /// A minimal worker implementation that does nothing
@@ -19,6 +20,7 @@ impl ockam_core::Worker for NullWorker {
 /// builder API to customise the underlying node that is created.
 pub struct NodeBuilder {
     logging: bool,
+    metrics_sink: Arc<dyn MetricsSink>,
 }
 
 impl Default for NodeBuilder {
@@ -30,12 +32,26 @@ impl Default for NodeBuilder {
 impl NodeBuilder {
     /// Create a node
     pub fn new() -> Self {
-        Self { logging: true }
+        Self {
+            logging: true,
+            metrics_sink: Arc::new(NoopMetricsSink),
+        }
     }
 
     /// Disable logging on this node
     pub fn no_logging(self) -> Self {
-        Self { logging: false }
+        Self {
+            logging: false,
+            ..self
+        }
+    }
+
+    /// Send the router and workers metrics of this node to the given sink
+    pub fn with_metrics_sink(self, metrics_sink: Arc<dyn MetricsSink>) -> Self {
+        Self {
+            metrics_sink,
+            ..self
+        }
     }
 
     /// Consume this builder and yield a new Ockam Node
@@ -50,7 +66,7 @@ impl NodeBuilder {
         // Shared instance of FlowControls
         let flow_controls = FlowControls::new();
 
-        let mut exe = Executor::new(&flow_controls);
+        let mut exe = Executor::new(&flow_controls, self.metrics_sink.clone());
         let addr: Address = "app".into();
 
         // The root application worker needs a mailbox and relay to accept
@@ -65,6 +81,7 @@ impl NodeBuilder {
             None,
             Default::default(),
             &flow_controls,
+            self.metrics_sink,
         );
 
         debugger::log_inherit_context("NODE", &ctx, &ctx);
@@ -71,7 +71,18 @@ where
 
         // Call the worker handle function - pass errors up
         let routed = Self::wrap_direct_message(relay_msg)?;
-        self.worker.handle_message(&mut self.ctx, routed).await?;
+        #[cfg(feature = "std")]
+        let started = std::time::Instant::now();
+        let result = self.worker.handle_message(&mut self.ctx, routed).await;
+
+        #[cfg(feature = "std")]
+        let latency = Some(started.elapsed());
+        #[cfg(not(feature = "std"))]
+        let latency = None;
+        self.ctx
+            .metrics()
+            .message_handled(&self.ctx.address(), latency);
+        result?;
 
         // Signal to the outer loop that we would like to run again
         Ok(true)
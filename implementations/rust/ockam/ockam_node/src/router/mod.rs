@@ -14,6 +14,7 @@ use record::{AddressMeta, AddressRecord, InternalMap};
 use state::{NodeState, RouterState};
 
 use crate::channel_types::{router_channel, MessageSender, RouterReceiver, SmallSender};
+use crate::metrics::MetricsSink;
 use crate::{
     error::{NodeError, NodeReason},
     relay::CtrlSignal,
@@ -48,6 +49,8 @@ pub struct Router {
     external: BTreeMap<TransportType, Address>,
     /// Receiver for messages from node
     receiver: Option<RouterReceiver<NodeMessage>>,
+    /// Sink for the routing metrics
+    metrics: Arc<dyn MetricsSink>,
 }
 
 enum RouteType {
@@ -64,13 +67,14 @@ fn determine_type(next: &Address) -> RouteType {
 }
 
 impl Router {
-    pub fn new(flow_controls: &FlowControls) -> Self {
+    pub fn new(flow_controls: &FlowControls, metrics: Arc<dyn MetricsSink>) -> Self {
         let (sender, receiver) = router_channel();
         Self {
             state: RouterState::new(sender),
             map: InternalMap::new(flow_controls),
             external: BTreeMap::new(),
             receiver: Some(receiver),
+            metrics,
         }
     }
 
@@ -22,6 +22,7 @@ pub(super) async fn resolve(
         p.clone()
     } else {
         trace!("{} FAILED; no such worker", base);
+        router.metrics.resolution_failed(addr);
         reply
             .send(RouterReply::no_such_address(addr.clone()))
             .await
@@ -42,6 +43,7 @@ pub(super) async fn resolve(
         }
         None => {
             trace!("{} FAILED; no such worker", base);
+            router.metrics.resolution_failed(addr);
             reply.send(RouterReply::no_such_address(addr.clone()))
         }
     }
@@ -15,6 +15,18 @@ mod in_memory_value_storage;
 /// Trait defining the functions for a key value storage
 mod key_value_storage;
 
+/// Sqlite database shared by the Sqlite storages
+#[cfg(feature = "sqlite")]
+mod sqlite_database;
+
+/// Sqlite implementation of a key value storage
+#[cfg(feature = "sqlite")]
+mod sqlite_key_value_storage;
+
+/// Sqlite implementation of a value storage
+#[cfg(feature = "sqlite")]
+mod sqlite_value_storage;
+
 /// This trait defines types which can be used as keys in JSON maps
 mod to_string_key;
 
@@ -28,5 +40,11 @@ pub use file_value_storage::*;
 pub use in_memory_key_value_storage::*;
 pub use in_memory_value_storage::*;
 pub use key_value_storage::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite_database::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite_key_value_storage::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite_value_storage::*;
 pub use to_string_key::*;
 pub use value_storage::*;
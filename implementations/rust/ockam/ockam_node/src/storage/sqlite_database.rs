@@ -0,0 +1,151 @@
+use crate::storage::{SqliteKeyValueStorage, SqliteValueStorage, ToStringKey};
+use crate::tokio::task::{self, JoinError};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use rusqlite::{Connection, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long a connection waits for a lock held by another connection or process
+/// before failing with a "database is locked" error
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sqlite database shared by several key / value and value storages
+///
+/// Each [`SqliteKeyValueStorage`] uses its own namespace and each [`SqliteValueStorage`]
+/// its own name so that the vault, the identities and the policies of a node can all be
+/// stored in the same database file.
+///
+/// Every operation runs in its own transaction and nothing is cached in memory, so the same
+/// database file can safely be used by several storages and several processes at once.
+/// Read-modify-write operations take the database write lock before reading.
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl fmt::Debug for SqliteDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SqliteDatabase")
+    }
+}
+
+impl SqliteDatabase {
+    const CREATE_KEY_VALUE_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS key_value (
+        namespace TEXT NOT NULL,
+        key TEXT NOT NULL,
+        key_data BLOB NOT NULL,
+        value BLOB NOT NULL,
+        PRIMARY KEY (namespace, key)
+    );";
+
+    const CREATE_VALUE_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS value (
+        name TEXT NOT NULL PRIMARY KEY,
+        value BLOB NOT NULL
+    );";
+
+    /// Open the database at the given path, creating it if it doesn't exist
+    pub async fn create(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
+        let t = move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| Error::new(Origin::Node, Kind::Io, e))?;
+            }
+            let conn = Connection::open(&path).map_err(map_sqlite_err)?;
+            Self::init(conn)
+        };
+        task::spawn_blocking(t).await.map_err(map_join_err)?
+    }
+
+    /// Create a database which only lives in memory. This is mostly useful for tests
+    pub fn create_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().map_err(map_sqlite_err)?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(map_sqlite_err)?;
+        // WAL lets readers proceed while another process is writing
+        conn.execute_batch(
+            &("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;".to_owned()
+                + Self::CREATE_KEY_VALUE_TABLE_SQL
+                + Self::CREATE_VALUE_TABLE_SQL),
+        )
+        .map_err(map_sqlite_err)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Getter for the Sqlite connection, which can be used to create additional tables
+    pub fn conn(&self) -> Arc<Mutex<Connection>> {
+        self.conn.clone()
+    }
+
+    /// Return a key / value storage using the given namespace in this database
+    pub fn key_value_storage<K, V>(&self, namespace: &str) -> SqliteKeyValueStorage<K, V>
+    where
+        K: Serialize + for<'de> Deserialize<'de> + ToStringKey + Send + Sync + 'static,
+        V: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+    {
+        SqliteKeyValueStorage::new(self.clone(), namespace)
+    }
+
+    /// Return a value storage using the given name in this database
+    pub fn value_storage<V>(&self, name: &str) -> SqliteValueStorage<V>
+    where
+        V: Default + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+    {
+        SqliteValueStorage::new(self.clone(), name)
+    }
+
+    /// Run a function with the connection on a blocking thread
+    pub(crate) async fn with_connection<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<R> + Send + 'static,
+    ) -> Result<R> {
+        let conn = self.conn();
+        let t = move || {
+            let conn = conn.lock().unwrap();
+            f(&conn)
+        };
+        task::spawn_blocking(t).await.map_err(map_join_err)?
+    }
+
+    /// Run a function inside a write transaction on a blocking thread.
+    /// The transaction is only committed if the function is successful
+    pub(crate) async fn with_write_transaction<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<R> + Send + 'static,
+    ) -> Result<R> {
+        let conn = self.conn();
+        let t = move || {
+            let mut conn = conn.lock().unwrap();
+            // take the write lock right away to avoid a deadlock when another process
+            // is also trying to upgrade a read lock
+            let transaction = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(map_sqlite_err)?;
+            let result = f(&transaction)?;
+            transaction.commit().map_err(map_sqlite_err)?;
+            Ok(result)
+        };
+        task::spawn_blocking(t).await.map_err(map_join_err)?
+    }
+}
+
+pub(crate) fn map_join_err(err: JoinError) -> Error {
+    Error::new(Origin::Application, Kind::Io, err)
+}
+
+pub(crate) fn map_sqlite_err(err: rusqlite::Error) -> Error {
+    Error::new(Origin::Application, Kind::Io, err)
+}
+
+pub(crate) fn map_serde_err(err: serde_json::Error) -> Error {
+    Error::new(Origin::Application, Kind::Serialization, err)
+}
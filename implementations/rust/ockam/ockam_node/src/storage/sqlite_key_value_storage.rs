@@ -0,0 +1,215 @@
+use crate::storage::sqlite_database::{map_serde_err, map_sqlite_err};
+use crate::{KeyValueStorage, SqliteDatabase, ToStringKey};
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// Key value storage backed by a Sqlite database
+///
+/// Values are serialized as JSON and stored in the `key_value` table under a namespace.
+/// Keys are stored both as their [`ToStringKey`] representation, which is used for lookups,
+/// and as JSON so that they can be returned by the `keys` function.
+///
+/// Contrary to the `FileKeyValueStorage` there is no in-memory cache: all the storages and
+/// processes using the same database file always see the latest committed values.
+pub struct SqliteKeyValueStorage<K, V> {
+    database: SqliteDatabase,
+    namespace: String,
+    _phantom_data: PhantomData<(K, V)>,
+}
+
+impl<K, V> Clone for SqliteKeyValueStorage<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            database: self.database.clone(),
+            namespace: self.namespace.clone(),
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<
+        K: Serialize + for<'de> Deserialize<'de> + ToStringKey + Send + Sync + 'static,
+        V: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+    > SqliteKeyValueStorage<K, V>
+{
+    /// Create a key / value storage for a namespace of the given database
+    pub fn new(database: SqliteDatabase, namespace: &str) -> Self {
+        Self {
+            database,
+            namespace: namespace.to_string(),
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<
+        K: Serialize + for<'de> Deserialize<'de> + ToStringKey + Send + Sync + 'static,
+        V: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+    > KeyValueStorage<K, V> for SqliteKeyValueStorage<K, V>
+{
+    async fn put(&self, key: K, value: V) -> Result<()> {
+        let namespace = self.namespace.clone();
+        let key_string = key.to_string_key();
+        let key_data = serde_json::to_vec(&key).map_err(map_serde_err)?;
+        let value = serde_json::to_vec(&value).map_err(map_serde_err)?;
+        self.database
+            .with_connection(move |conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO key_value (namespace, key, key_data, value) VALUES (?1, ?2, ?3, ?4)",
+                    params![namespace, key_string, key_data, value],
+                )
+                .map_err(map_sqlite_err)?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get(&self, key: &K) -> Result<Option<V>> {
+        let namespace = self.namespace.clone();
+        let key = key.to_string_key();
+        let value = self
+            .database
+            .with_connection(move |conn| {
+                conn.query_row(
+                    "SELECT value FROM key_value WHERE namespace = ?1 AND key = ?2",
+                    params![namespace, key],
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()
+                .map_err(map_sqlite_err)
+            })
+            .await?;
+        value
+            .map(|v| serde_json::from_slice(&v).map_err(map_serde_err))
+            .transpose()
+    }
+
+    async fn delete(&self, key: &K) -> Result<Option<V>> {
+        let namespace = self.namespace.clone();
+        let key = key.to_string_key();
+        let value = self
+            .database
+            .with_write_transaction(move |conn| {
+                let value = conn
+                    .query_row(
+                        "SELECT value FROM key_value WHERE namespace = ?1 AND key = ?2",
+                        params![namespace, key],
+                        |row| row.get::<_, Vec<u8>>(0),
+                    )
+                    .optional()
+                    .map_err(map_sqlite_err)?;
+                conn.execute(
+                    "DELETE FROM key_value WHERE namespace = ?1 AND key = ?2",
+                    params![namespace, key],
+                )
+                .map_err(map_sqlite_err)?;
+                Ok(value)
+            })
+            .await?;
+        value
+            .map(|v| serde_json::from_slice(&v).map_err(map_serde_err))
+            .transpose()
+    }
+
+    async fn keys(&self) -> Result<Vec<K>> {
+        let namespace = self.namespace.clone();
+        let keys = self
+            .database
+            .with_connection(move |conn| {
+                let mut stmt = conn
+                    .prepare("SELECT key_data FROM key_value WHERE namespace = ?1 ORDER BY key")
+                    .map_err(map_sqlite_err)?;
+                let result: Result<Vec<Vec<u8>>> = stmt
+                    .query_map(params![namespace], |row| row.get(0))
+                    .map_err(map_sqlite_err)?
+                    .map(|value| value.map_err(map_sqlite_err))
+                    .collect();
+                result
+            })
+            .await?;
+        keys.iter()
+            .map(|k| serde_json::from_slice(k).map_err(map_serde_err))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_sqlite_key_value_storage() -> Result<()> {
+        let file = NamedTempFile::new().unwrap();
+        let database = SqliteDatabase::create(file.path()).await?;
+        let storage = database.key_value_storage::<Key, Value>("values");
+
+        // persist new values
+        storage.put(Key::new(1, 2), Value(10)).await?;
+        storage.put(Key::new(3, 4), Value(20)).await?;
+
+        // retrieve the values
+        assert_eq!(storage.get(&Key::new(0, 0)).await?, None);
+        assert_eq!(storage.get(&Key::new(1, 2)).await?, Some(Value(10)));
+        assert_eq!(storage.keys().await?, vec![Key::new(1, 2), Key::new(3, 4)]);
+
+        // a value can be replaced
+        storage.put(Key::new(1, 2), Value(30)).await?;
+        assert_eq!(storage.get(&Key::new(1, 2)).await?, Some(Value(30)));
+
+        // a value can be deleted
+        assert_eq!(storage.delete(&Key::new(1, 2)).await?, Some(Value(30)));
+        assert_eq!(storage.get(&Key::new(1, 2)).await?, None);
+        assert_eq!(storage.delete(&Key::new(1, 2)).await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_key_value_storage_namespaces() -> Result<()> {
+        let file = NamedTempFile::new().unwrap();
+        let database = SqliteDatabase::create(file.path()).await?;
+        let storage1 = database.key_value_storage::<Key, Value>("namespace1");
+        let storage2 = database.key_value_storage::<Key, Value>("namespace2");
+
+        storage1.put(Key::new(1, 2), Value(10)).await?;
+        assert_eq!(storage2.get(&Key::new(1, 2)).await?, None);
+        assert!(storage2.keys().await?.is_empty());
+
+        // another connection to the same file sees the same values
+        let other = SqliteDatabase::create(file.path()).await?;
+        let storage3 = other.key_value_storage::<Key, Value>("namespace1");
+        assert_eq!(storage3.get(&Key::new(1, 2)).await?, Some(Value(10)));
+        storage3.delete(&Key::new(1, 2)).await?;
+        assert_eq!(storage1.get(&Key::new(1, 2)).await?, None);
+
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize, Default, PartialEq, Eq, Clone, Debug)]
+    struct Value(u8);
+
+    #[derive(Serialize, Deserialize, Default, PartialEq, Eq, Clone, Debug)]
+    struct Key {
+        key1: u8,
+        key2: u8,
+    }
+
+    impl ToStringKey for Key {
+        fn to_string_key(&self) -> String {
+            format!("{}_{}", self.key1, self.key2)
+        }
+    }
+
+    impl Key {
+        fn new(key1: u8, key2: u8) -> Self {
+            Self { key1, key2 }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::storage::sqlite_database::{map_serde_err, map_sqlite_err};
+use crate::{SqliteDatabase, ValueStorage};
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// Value storage backed by a Sqlite database
+///
+/// The value is serialized as JSON and stored in the `value` table under a name.
+/// If no value has been stored yet, the default value of `V` is used.
+///
+/// Updates are executed in a transaction holding the database write lock, so concurrent
+/// modifications from several storages or processes are serialized and an update
+/// failing in the middle leaves the stored value untouched.
+pub struct SqliteValueStorage<V> {
+    database: SqliteDatabase,
+    name: String,
+    _phantom_data: PhantomData<V>,
+}
+
+impl<V> Clone for SqliteValueStorage<V> {
+    fn clone(&self) -> Self {
+        Self {
+            database: self.database.clone(),
+            name: self.name.clone(),
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<V: Default + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static>
+    SqliteValueStorage<V>
+{
+    /// Create a value storage for a name of the given database
+    pub fn new(database: SqliteDatabase, name: &str) -> Self {
+        Self {
+            database,
+            name: name.to_string(),
+            _phantom_data: PhantomData,
+        }
+    }
+
+    fn load(conn: &Connection, name: &str) -> Result<V> {
+        let value = conn
+            .query_row(
+                "SELECT value FROM value WHERE name = ?1",
+                params![name],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()
+            .map_err(map_sqlite_err)?;
+        match value {
+            Some(value) => serde_json::from_slice(&value).map_err(map_serde_err),
+            None => Ok(V::default()),
+        }
+    }
+
+    fn store(conn: &Connection, name: &str, value: &V) -> Result<()> {
+        let value = serde_json::to_vec(value).map_err(map_serde_err)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO value (name, value) VALUES (?1, ?2)",
+            params![name, value],
+        )
+        .map_err(map_sqlite_err)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<V: Default + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static> ValueStorage<V>
+    for SqliteValueStorage<V>
+{
+    async fn update_value(&self, f: impl Fn(V) -> Result<V> + Send + Sync + 'static) -> Result<()> {
+        let f = move |v: V| Ok((f(v)?, ()));
+        self.modify_value(f).await
+    }
+
+    async fn modify_value<R: Send + Sync + 'static>(
+        &self,
+        f: impl Fn(V) -> Result<(V, R)> + Send + Sync + 'static,
+    ) -> Result<R> {
+        let name = self.name.clone();
+        self.database
+            .with_write_transaction(move |conn| {
+                let existing_value = Self::load(conn, &name)?;
+                let (updated_value, result) = f(existing_value)?;
+                Self::store(conn, &name, &updated_value)?;
+                Ok(result)
+            })
+            .await
+    }
+
+    async fn read_value<R: Send + Sync + 'static>(
+        &self,
+        f: impl Fn(V) -> Result<R> + Send + Sync + 'static,
+    ) -> Result<R> {
+        let name = self.name.clone();
+        self.database
+            .with_connection(move |conn| f(Self::load(conn, &name)?))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::errcode::{Kind, Origin};
+    use ockam_core::Error;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_sqlite_value_storage() -> Result<()> {
+        let file = NamedTempFile::new().unwrap();
+        let database = SqliteDatabase::create(file.path()).await?;
+        let storage = database.value_storage::<Value>("value");
+
+        // the initial value is the default value
+        assert_eq!(storage.read_value(Ok).await?, Value::default());
+
+        // the value can be updated
+        storage.update_value(|_: Value| Ok(Value(10))).await?;
+        assert_eq!(storage.read_value(Ok).await?, Value(10));
+
+        // a value can be modified and a result returned
+        let previous = storage
+            .modify_value(|v: Value| Ok((Value(v.0 + 1), v.0)))
+            .await?;
+        assert_eq!(previous, 10);
+        assert_eq!(storage.read_value(Ok).await?, Value(11));
+
+        // a failed update leaves the value untouched
+        let result = storage
+            .update_value(|_: Value| Err(Error::new(Origin::Node, Kind::Invalid, "failed")))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(storage.read_value(Ok).await?, Value(11));
+
+        // another connection to the same file sees the same value
+        let other = SqliteDatabase::create(file.path()).await?;
+        let other_storage = other.value_storage::<Value>("value");
+        assert_eq!(other_storage.read_value(Ok).await?, Value(11));
+
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+    struct Value(u8);
+}
@@ -8,6 +8,7 @@ use ockam_core::compat::{
 use ockam_core::{async_trait, Address, AllowAll, Any, Decodable, DenyAll, Message, LOCAL};
 use ockam_core::{route, Processor, Result, Routed, Worker};
 use ockam_node::compat::futures::FutureExt;
+use ockam_node::metrics::MetricsRegistry;
 use ockam_node::{Context, MessageReceiveOptions, NodeBuilder};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicI8, AtomicU32};
@@ -560,3 +561,40 @@ async fn starting_worker_with_dup_address_should_fail(ctx: &mut Context) -> Resu
         .is_err());
     ctx.stop().await
 }
+
+#[allow(non_snake_case)]
+#[test]
+fn metrics_registry__worker_handles_messages__should_record_metrics() {
+    let registry = MetricsRegistry::create();
+    let (mut ctx, mut executor) = NodeBuilder::new()
+        .with_metrics_sink(registry.clone())
+        .build();
+    executor
+        .execute(async move {
+            let res = std::panic::AssertUnwindSafe(async {
+                ctx.start_worker("echo", DummyWorker).await?;
+                let reply: String = ctx
+                    .send_and_receive(route!["echo"], "Hello".to_string())
+                    .await?;
+                assert_eq!(reply, "Hello");
+
+                // the router can't resolve a missing worker
+                assert!(ctx
+                    .send(route!["missing"], "Hello".to_string())
+                    .await
+                    .is_err());
+                Result::<()>::Ok(())
+            })
+            .catch_unwind()
+            .await;
+
+            ctx.stop().await?;
+
+            res.unwrap()
+        })
+        .unwrap()
+        .unwrap();
+
+    assert!(registry.messages_handled() >= 1);
+    assert!(registry.resolution_failures() >= 1);
+}
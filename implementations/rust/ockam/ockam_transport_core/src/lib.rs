@@ -9,6 +9,7 @@
 //! * `ockam_transport_ble` - Bluetooth Low Energy Transport
 //! * `ockam_transport_websocket` - WebSocket Transport
 //! * `ockam_transport_uds` - Unix Domain Socket Transport
+//! * `ockam_transport_quic` - QUIC Transport
 //!
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -0,0 +1,35 @@
+//! This crate provides a QUIC Transport for Ockam's Routing Protocol.
+//!
+//! QUIC connections are not bound to the network address of the client, which keeps them
+//! alive when a mobile device moves from one network to another.
+//!
+//! This crate requires the rust standard library `"std"`
+#![deny(unsafe_code)]
+#![warn(
+    missing_docs,
+    dead_code,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+mod options;
+mod tls;
+mod transport;
+
+use ockam_core::TransportType;
+pub use options::{QuicConnectionOptions, QuicListenerOptions};
+pub use transport::*;
+
+mod workers;
+pub(crate) use workers::*;
+
+pub(crate) const CLUSTER_NAME: &str = "_internals.transport.quic";
+
+/// Transport type for QUIC addresses
+pub const QUIC: TransportType = TransportType::new(6);
+
+/// Maximum size of an encoded [`TransportMessage`](ockam_core::TransportMessage)
+/// sent over a QUIC connection
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
@@ -0,0 +1,213 @@
+use crate::workers::Addresses;
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
+use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
+
+/// Default interval between the keep-alive packets of a QUIC connection
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default duration after which a QUIC connection without traffic is closed
+pub const DEFAULT_MAX_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default name of the server checked against its certificate
+pub const DEFAULT_SERVER_NAME: &str = "localhost";
+
+pub(crate) struct QuicConnectionAccessControl {
+    pub sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub receiver_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+}
+
+/// Trust Options for a QUIC connection
+///
+/// By default the certificate of the server is verified with the native root certificates.
+/// Use [`QuicConnectionOptions::with_root_certificate`] to verify it with other root certificates,
+/// or [`QuicConnectionOptions::without_server_verification`] when the messages exchanged over
+/// the connection are authenticated by an Ockam secure channel
+#[derive(Debug)]
+pub struct QuicConnectionOptions {
+    pub(super) consumer: Vec<FlowControlId>,
+    pub(crate) flow_control_id: FlowControlId,
+    pub(crate) server_name: String,
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    pub(crate) verify_server: bool,
+    pub(crate) keep_alive_interval: Duration,
+    pub(crate) max_idle_timeout: Duration,
+}
+
+impl QuicConnectionOptions {
+    #[allow(clippy::new_without_default)]
+    /// Mark this Quic Receiver as a Producer with a random [`FlowControlId`]
+    pub fn new() -> Self {
+        Self {
+            consumer: vec![],
+            flow_control_id: FlowControls::generate_flow_control_id(),
+            server_name: DEFAULT_SERVER_NAME.to_string(),
+            root_certificates: vec![],
+            verify_server: true,
+            keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            max_idle_timeout: DEFAULT_MAX_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Mark that this Connection is a Consumer for to the given [`FlowControlId`]
+    pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
+        self.consumer.push(id.clone());
+
+        self
+    }
+
+    /// Set the name of the server checked against its certificate
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = server_name.into();
+        self
+    }
+
+    /// Verify the certificate of the server with this DER encoded root certificate,
+    /// instead of the native root certificates
+    pub fn with_root_certificate(mut self, certificate: Vec<u8>) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Accept any certificate presented by the server.
+    /// The peer must then be authenticated otherwise, for example with an Ockam secure channel
+    pub fn without_server_verification(mut self) -> Self {
+        self.verify_server = false;
+        self
+    }
+
+    /// Set the interval between the keep-alive packets
+    pub fn with_keep_alive_interval(mut self, keep_alive_interval: Duration) -> Self {
+        self.keep_alive_interval = keep_alive_interval;
+        self
+    }
+
+    /// Set the duration after which a connection without traffic is closed
+    pub fn with_max_idle_timeout(mut self, max_idle_timeout: Duration) -> Self {
+        self.max_idle_timeout = max_idle_timeout;
+        self
+    }
+
+    /// Getter for freshly generated [`FlowControlId`]
+    pub fn flow_control_id(&self) -> FlowControlId {
+        self.flow_control_id.clone()
+    }
+}
+
+impl QuicConnectionOptions {
+    pub(crate) fn setup_flow_control(&self, flow_controls: &FlowControls, addresses: &Addresses) {
+        flow_controls.add_producer(
+            addresses.receiver_address().clone(),
+            &self.flow_control_id,
+            None,
+            vec![addresses.sender_address().clone()],
+        );
+
+        for id in &self.consumer {
+            flow_controls.add_consumer(addresses.sender_address().clone(), id);
+        }
+    }
+
+    pub(crate) fn create_access_control(
+        &self,
+        flow_controls: &FlowControls,
+    ) -> QuicConnectionAccessControl {
+        QuicConnectionAccessControl {
+            sender_incoming_access_control: Arc::new(AllowAll),
+            receiver_outgoing_access_control: Arc::new(FlowControlOutgoingAccessControl::new(
+                flow_controls,
+                self.flow_control_id.clone(),
+                None,
+            )),
+        }
+    }
+}
+
+/// Trust Options for a QUIC listener
+///
+/// By default the listener presents a freshly generated self-signed certificate,
+/// use [`QuicListenerOptions::with_certificate`] to present another one
+#[derive(Debug)]
+pub struct QuicListenerOptions {
+    pub(crate) flow_control_id: FlowControlId,
+    pub(crate) certificate: Option<(Vec<Vec<u8>>, Vec<u8>)>,
+    pub(crate) max_idle_timeout: Duration,
+}
+
+impl QuicListenerOptions {
+    /// Mark this Quic Listener as a Spawner with given [`FlowControlId`].
+    /// NOTE: Spawned connections get fresh random [`FlowControlId`], however they are still marked
+    /// with Spawner's [`FlowControlId`]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            flow_control_id: FlowControls::generate_flow_control_id(),
+            certificate: None,
+            max_idle_timeout: DEFAULT_MAX_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Present this DER encoded certificate chain, with its DER encoded private key
+    pub fn with_certificate(
+        mut self,
+        certificate_chain: Vec<Vec<u8>>,
+        private_key: Vec<u8>,
+    ) -> Self {
+        self.certificate = Some((certificate_chain, private_key));
+        self
+    }
+
+    /// Set the duration after which a connection without traffic is closed
+    pub fn with_max_idle_timeout(mut self, max_idle_timeout: Duration) -> Self {
+        self.max_idle_timeout = max_idle_timeout;
+        self
+    }
+
+    /// Getter for freshly generated [`FlowControlId`]
+    pub fn spawner_flow_control_id(&self) -> FlowControlId {
+        self.flow_control_id.clone()
+    }
+}
+
+impl QuicListenerOptions {
+    pub(crate) fn setup_flow_control_for_listener(
+        &self,
+        flow_controls: &FlowControls,
+        address: &Address,
+    ) {
+        flow_controls.add_spawner(address.clone(), &self.flow_control_id);
+    }
+
+    pub(crate) fn setup_flow_control_for_connection(
+        &self,
+        flow_controls: &FlowControls,
+        addresses: &Addresses,
+    ) -> FlowControlId {
+        let flow_control_id = FlowControls::generate_flow_control_id();
+
+        flow_controls.add_producer(
+            addresses.receiver_address().clone(),
+            &flow_control_id,
+            Some(&self.flow_control_id),
+            vec![addresses.sender_address().clone()],
+        );
+
+        flow_control_id
+    }
+
+    pub(crate) fn create_access_control(
+        &self,
+        flow_controls: &FlowControls,
+        flow_control_id: FlowControlId,
+    ) -> QuicConnectionAccessControl {
+        QuicConnectionAccessControl {
+            sender_incoming_access_control: Arc::new(AllowAll),
+            receiver_outgoing_access_control: Arc::new(FlowControlOutgoingAccessControl::new(
+                flow_controls,
+                flow_control_id,
+                Some(self.flow_control_id.clone()),
+            )),
+        }
+    }
+}
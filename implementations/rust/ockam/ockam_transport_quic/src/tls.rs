@@ -0,0 +1,127 @@
+use crate::{QuicConnectionOptions, QuicListenerOptions};
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use quinn::{ClientConfig, IdleTimeout, ServerConfig, TransportConfig};
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerName};
+use std::time::SystemTime;
+
+/// Protocol negotiated by the QUIC connections of Ockam nodes
+const ALPN_PROTOCOL: &[u8] = b"ockam";
+
+/// Create the configuration of an outgoing connection
+pub(crate) fn client_config(options: &QuicConnectionOptions) -> Result<ClientConfig> {
+    let builder = rustls::ClientConfig::builder().with_safe_defaults();
+    let mut crypto = if !options.verify_server {
+        builder
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        if options.root_certificates.is_empty() {
+            let native_certificates = rustls_native_certs::load_native_certs().map_err(|e| {
+                tls_config_error(format!("cannot load the native certificates: {e}"))
+            })?;
+            for certificate in native_certificates {
+                // Some native certificates might not be supported, they are skipped
+                let _ = roots.add(&Certificate(certificate.0));
+            }
+        }
+        for certificate in &options.root_certificates {
+            roots
+                .add(&Certificate(certificate.clone()))
+                .map_err(tls_config_error)?;
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+
+    let mut transport = transport_config(options.max_idle_timeout)?;
+    transport.keep_alive_interval(Some(options.keep_alive_interval));
+
+    let mut config = ClientConfig::new(Arc::new(crypto));
+    config.transport_config(Arc::new(transport));
+    Ok(config)
+}
+
+/// Create the configuration of a listener
+pub(crate) fn server_config(options: &QuicListenerOptions) -> Result<ServerConfig> {
+    let (certificate_chain, private_key) = match &options.certificate {
+        Some(certificate) => certificate.clone(),
+        None => self_signed_certificate()?,
+    };
+
+    let mut crypto = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            certificate_chain.into_iter().map(Certificate).collect(),
+            PrivateKey(private_key),
+        )
+        .map_err(tls_config_error)?;
+    crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+
+    let mut config = ServerConfig::with_crypto(Arc::new(crypto));
+    config.transport_config(Arc::new(transport_config(options.max_idle_timeout)?));
+    Ok(config)
+}
+
+fn transport_config(max_idle_timeout: Duration) -> Result<TransportConfig> {
+    let mut transport = TransportConfig::default();
+    transport.max_idle_timeout(Some(
+        IdleTimeout::try_from(max_idle_timeout).map_err(tls_config_error)?,
+    ));
+    Ok(transport)
+}
+
+/// Generate a self-signed certificate chain for `localhost`, and its private key
+fn self_signed_certificate() -> Result<(Vec<Vec<u8>>, Vec<u8>)> {
+    let certificate =
+        rcgen::generate_simple_self_signed(vec![crate::options::DEFAULT_SERVER_NAME.to_string()])
+            .map_err(tls_config_error)?;
+    let der = certificate.serialize_der().map_err(tls_config_error)?;
+    Ok((vec![der], certificate.serialize_private_key_der()))
+}
+
+/// Accept any server certificate, when the peer is authenticated otherwise
+struct SkipServerVerification;
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> core::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+fn tls_config_error(e: impl core::fmt::Display) -> Error {
+    Error::new(
+        Origin::Transport,
+        Kind::Invalid,
+        format!("invalid QUIC TLS configuration: {e}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_root_certificate_is_rejected() {
+        let options = QuicConnectionOptions::new().with_root_certificate(vec![1, 2, 3]);
+        assert!(client_config(&options).is_err());
+    }
+
+    #[test]
+    fn test_listener_generates_a_certificate() {
+        assert!(server_config(&QuicListenerOptions::new()).is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+use core::fmt;
+use core::fmt::Formatter;
+use ockam_core::compat::net::{SocketAddr, ToSocketAddrs};
+use ockam_core::flow_control::FlowControlId;
+use ockam_core::{Address, Result};
+use ockam_transport_core::TransportError;
+
+/// Quic connection mode
+#[derive(Copy, Debug, Clone)]
+pub enum QuicConnectionMode {
+    /// Connection was initiated from our node
+    Outgoing,
+    /// Connection was accepted from a QUIC listener
+    Incoming,
+}
+
+impl fmt::Display for QuicConnectionMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            QuicConnectionMode::Outgoing => write!(f, "outgoing"),
+            QuicConnectionMode::Incoming => write!(f, "incoming"),
+        }
+    }
+}
+
+/// Result of [`QuicTransport::connect`](crate::QuicTransport::connect) call.
+#[derive(Clone, Debug)]
+pub struct QuicConnection {
+    sender_address: Address,
+    receiver_address: Address,
+    socket_address: SocketAddr,
+    mode: QuicConnectionMode,
+    flow_control_id: FlowControlId,
+}
+
+impl fmt::Display for QuicConnection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Socket: {}, Worker: {}, Processor: {}, FlowId: {}",
+            self.socket_address, self.sender_address, self.receiver_address, self.flow_control_id
+        )
+    }
+}
+
+impl From<QuicConnection> for Address {
+    fn from(value: QuicConnection) -> Self {
+        value.sender_address
+    }
+}
+
+impl QuicConnection {
+    /// Constructor
+    pub fn new(
+        sender_address: Address,
+        receiver_address: Address,
+        socket_address: SocketAddr,
+        mode: QuicConnectionMode,
+        flow_control_id: FlowControlId,
+    ) -> Self {
+        Self {
+            sender_address,
+            receiver_address,
+            socket_address,
+            mode,
+            flow_control_id,
+        }
+    }
+    /// Corresponding Sender Worker [`Address`] that can be used
+    /// in a route to send messages to the other side of the QUIC connection
+    pub fn sender_address(&self) -> &Address {
+        &self.sender_address
+    }
+    /// Corresponding Receiver Processor [`Address`]
+    pub fn receiver_address(&self) -> &Address {
+        &self.receiver_address
+    }
+    /// [`SocketAddr`] of the peer when the connection was established.
+    /// The peer can migrate the connection to another address afterwards
+    pub fn socket_address(&self) -> &SocketAddr {
+        &self.socket_address
+    }
+    /// Generated fresh random [`FlowControlId`]
+    pub fn flow_control_id(&self) -> &FlowControlId {
+        &self.flow_control_id
+    }
+    /// Corresponding [`QuicConnectionMode`]
+    pub fn mode(&self) -> QuicConnectionMode {
+        self.mode
+    }
+}
+
+/// Result of [`QuicTransport::listen`](crate::QuicTransport::listen) call.
+#[derive(Clone, Debug)]
+pub struct QuicListener {
+    processor_address: Address,
+    socket_address: SocketAddr,
+    flow_control_id: FlowControlId,
+}
+
+impl fmt::Display for QuicListener {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Socket: {}, Processor: {}, FlowId: {}",
+            self.socket_address, self.processor_address, self.flow_control_id
+        )
+    }
+}
+
+impl QuicListener {
+    /// Constructor
+    pub fn new(
+        processor_address: Address,
+        socket_address: SocketAddr,
+        flow_control_id: FlowControlId,
+    ) -> Self {
+        Self {
+            processor_address,
+            socket_address,
+            flow_control_id,
+        }
+    }
+    /// Corresponding Processor [`Address`] that can be used to stop the Listener
+    pub fn processor_address(&self) -> &Address {
+        &self.processor_address
+    }
+    /// Corresponding [`SocketAddr`]
+    pub fn socket_address(&self) -> &SocketAddr {
+        &self.socket_address
+    }
+    /// Corresponding [`SocketAddr`] in String format
+    pub fn socket_string(&self) -> String {
+        self.socket_address.to_string()
+    }
+    /// Generated fresh random [`FlowControlId`]
+    pub fn flow_control_id(&self) -> &FlowControlId {
+        &self.flow_control_id
+    }
+}
+
+/// Resolve the given peer to a [`SocketAddr`](std::net::SocketAddr)
+pub fn resolve_peer(peer: String) -> Result<SocketAddr> {
+    // Try to parse as SocketAddr
+    if let Ok(p) = parse_socket_addr(&peer) {
+        return Ok(p);
+    }
+
+    // Try to resolve hostname
+    if let Ok(mut iter) = peer.to_socket_addrs() {
+        // Prefer ip4
+        if let Some(p) = iter.find(|x| x.is_ipv4()) {
+            return Ok(p);
+        }
+        if let Some(p) = iter.find(|x| x.is_ipv6()) {
+            return Ok(p);
+        }
+    }
+
+    // Nothing worked, return an error
+    Err(TransportError::InvalidAddress.into())
+}
+
+pub(crate) fn parse_socket_addr(s: &str) -> Result<SocketAddr> {
+    Ok(s.parse().map_err(|_| TransportError::InvalidAddress)?)
+}
@@ -0,0 +1,67 @@
+use crate::transport::common::{resolve_peer, QuicConnection};
+use crate::workers::{Addresses, QuicRecvProcessor, QuicSendWorker};
+use crate::{QuicConnectionMode, QuicConnectionOptions, QuicTransport};
+use ockam_core::{Address, Result};
+
+impl QuicTransport {
+    /// Establish an outgoing QUIC connection.
+    ///
+    /// ```rust
+    /// use ockam_transport_quic::{QuicConnectionOptions, QuicListenerOptions, QuicTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let quic = QuicTransport::create(&ctx).await?;
+    /// quic.listen("127.0.0.1:8000", QuicListenerOptions::new()).await?; // Listen on port 8000
+    /// let connection = quic.connect("127.0.0.1:5000", QuicConnectionOptions::new()).await?; // and connect to port 5000
+    /// # Ok(()) }
+    /// ```
+    pub async fn connect(
+        &self,
+        peer: impl Into<String>,
+        options: QuicConnectionOptions,
+    ) -> Result<QuicConnection> {
+        // Resolve peer address
+        let socket = resolve_peer(peer.into())?;
+
+        let connection = QuicSendWorker::connect(socket, &options).await?;
+
+        let mode = QuicConnectionMode::Outgoing;
+        let addresses = Addresses::generate(mode);
+
+        options.setup_flow_control(self.ctx.flow_controls(), &addresses);
+        let flow_control_id = options.flow_control_id.clone();
+        let access_control = options.create_access_control(self.ctx.flow_controls());
+
+        QuicSendWorker::start(
+            &self.ctx,
+            connection.clone(),
+            &addresses,
+            socket,
+            access_control.sender_incoming_access_control,
+        )
+        .await?;
+
+        QuicRecvProcessor::start(
+            &self.ctx,
+            connection,
+            &addresses,
+            socket,
+            access_control.receiver_outgoing_access_control,
+        )
+        .await?;
+
+        Ok(QuicConnection::new(
+            addresses.sender_address().clone(),
+            addresses.receiver_address().clone(),
+            socket,
+            mode,
+            flow_control_id,
+        ))
+    }
+
+    /// Interrupt an active QUIC connection given its Sender `Address`
+    pub async fn disconnect(&self, address: impl Into<Address>) -> Result<()> {
+        self.ctx.stop_worker(address.into()).await
+    }
+}
@@ -0,0 +1,146 @@
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Address, AsyncTryClone, Error, Result, TransportType};
+use ockam_node::Context;
+use ockam_transport_core::Transport;
+use std::sync::Arc;
+
+use crate::{QuicConnectionOptions, QuicTransport, QUIC};
+
+impl QuicTransport {
+    /// Create a QUIC transport
+    ///
+    /// ```rust
+    /// use ockam_transport_quic::QuicTransport;
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let quic = QuicTransport::create(&ctx).await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create(ctx: &Context) -> Result<Self> {
+        Self::create_with_resolution_options(ctx, QuicConnectionOptions::new).await
+    }
+
+    /// Create a QUIC transport which resolves the QUIC addresses of a route with connections
+    /// created with the given options, for example to verify the servers with other root
+    /// certificates than the native ones
+    ///
+    /// ```rust
+    /// use ockam_transport_quic::{QuicConnectionOptions, QuicTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context, root_certificate: Vec<u8>) -> Result<()> {
+    /// let quic = QuicTransport::create_with_resolution_options(&ctx, move || {
+    ///     QuicConnectionOptions::new().with_root_certificate(root_certificate.clone())
+    /// })
+    /// .await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_with_resolution_options(
+        ctx: &Context,
+        resolution_options: impl Fn() -> QuicConnectionOptions + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let quic = Self {
+            ctx: ctx.async_try_clone().await?,
+            resolution_options: Arc::new(resolution_options),
+        };
+        // make the QUIC transport available in the list of supported transports for
+        // later address resolution when socket addresses will need to be instantiated as QUIC
+        // worker addresses
+        ctx.register_transport(Arc::new(quic.async_try_clone().await?));
+        Ok(quic)
+    }
+}
+
+impl QuicTransport {
+    /// Getter
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+}
+
+#[async_trait]
+impl Transport for QuicTransport {
+    fn transport_type(&self) -> TransportType {
+        QUIC
+    }
+
+    async fn resolve_address(&self, address: Address) -> Result<Address> {
+        if address.transport_type() == QUIC {
+            Ok(self
+                .connect(address.address().to_string(), (self.resolution_options)())
+                .await?
+                .into())
+        } else {
+            Err(Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                format!(
+                    "this address can not be resolved by a QUIC transport {}",
+                    address
+                ),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::DEFAULT_SERVER_NAME;
+    use crate::QuicListenerOptions;
+    use ockam_transport_core::TransportError;
+
+    #[ockam_macros::test]
+    async fn test_resolve_address(ctx: &mut Context) -> Result<()> {
+        // An address is resolved with the resolution options of the transport, which
+        // verify the certificate of the listener
+        let certificate =
+            rcgen::generate_simple_self_signed(vec![DEFAULT_SERVER_NAME.to_string()]).unwrap();
+        let certificate_der = certificate.serialize_der().unwrap();
+
+        let root_certificate = certificate_der.clone();
+        let quic = QuicTransport::create_with_resolution_options(ctx, move || {
+            QuicConnectionOptions::new().with_root_certificate(root_certificate.clone())
+        })
+        .await?;
+        let listener = quic
+            .listen(
+                "127.0.0.1:0",
+                QuicListenerOptions::new().with_certificate(
+                    vec![certificate_der],
+                    certificate.serialize_private_key_der(),
+                ),
+            )
+            .await?;
+        let initial_workers = ctx.list_workers().await?;
+
+        let resolved = quic
+            .resolve_address(Address::new(QUIC, listener.socket_string()))
+            .await?;
+
+        // the QUIC address is replaced with the QUIC sender worker address
+        let mut additional_workers = ctx.list_workers().await?;
+        additional_workers.retain(|w| !initial_workers.contains(w));
+        assert!(additional_workers.contains(&resolved));
+
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_resolve_other_transport_address(ctx: &mut Context) -> Result<()> {
+        let quic = QuicTransport::create(ctx).await?;
+        let result = quic
+            .resolve_address(Address::new(TransportType::new(1), "127.0.0.1:4000"))
+            .await;
+        assert!(result.is_err());
+
+        let result = quic
+            .resolve_address(Address::new(QUIC, "not a socket address"))
+            .await;
+        let invalid_address: Error = TransportError::InvalidAddress.into();
+        assert_eq!(result.unwrap_err().code(), invalid_address.code());
+
+        ctx.stop().await
+    }
+}
@@ -0,0 +1,40 @@
+use crate::transport::common::{parse_socket_addr, QuicListener};
+use crate::workers::QuicListenProcessor;
+use crate::{QuicListenerOptions, QuicTransport};
+use ockam_core::{Address, Result};
+
+impl QuicTransport {
+    /// Start listening to incoming connections on an existing transport
+    ///
+    /// Returns the local address that this transport is bound to.
+    ///
+    /// This can be useful, for example, when binding to port 0 to figure out
+    /// which port was actually bound.
+    ///
+    /// ```rust
+    /// use ockam_transport_quic::{QuicListenerOptions, QuicTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let quic = QuicTransport::create(&ctx).await?;
+    /// quic.listen("127.0.0.1:8000", QuicListenerOptions::new()).await?;
+    /// # Ok(()) }
+    pub async fn listen(
+        &self,
+        bind_addr: impl AsRef<str>,
+        options: QuicListenerOptions,
+    ) -> Result<QuicListener> {
+        let flow_control_id = options.flow_control_id.clone();
+        let bind_addr = parse_socket_addr(bind_addr.as_ref())?;
+        // Could be different from the bind_addr, e.g., if binding to port 0
+        let (socket_addr, address) =
+            QuicListenProcessor::start(&self.ctx, bind_addr, options).await?;
+
+        Ok(QuicListener::new(address, socket_addr, flow_control_id))
+    }
+
+    /// Interrupt an active QUIC listener given its `Address`
+    pub async fn stop_listener(&self, address: &Address) -> Result<()> {
+        self.ctx.stop_processor(address.clone()).await
+    }
+}
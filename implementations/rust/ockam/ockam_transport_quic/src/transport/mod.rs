@@ -0,0 +1,53 @@
+pub(crate) mod common;
+mod connection;
+mod lifecycle;
+mod listener;
+
+pub use common::*;
+
+use crate::QuicConnectionOptions;
+use ockam_core::{async_trait, AsyncTryClone, Result};
+use ockam_node::{Context, HasContext};
+use std::sync::Arc;
+
+/// High level management interface for QUIC transports
+///
+/// Be aware that only one `QuicTransport` can exist per node, as it
+/// registers itself as a router for the `QUIC` address type.
+///
+/// To listen for incoming connections use
+/// [`quic.listen()`](crate::QuicTransport::listen).
+///
+/// To establish an outgoing connection use
+/// [`quic.connect()`](crate::QuicTransport::connect).
+/// QUIC addresses present in a route are also connected lazily when the route is resolved.
+///
+/// ```rust
+/// use ockam_transport_quic::{QuicConnectionOptions, QuicListenerOptions, QuicTransport};
+/// # use ockam_node::Context;
+/// # use ockam_core::Result;
+/// # async fn test(ctx: Context) -> Result<()> {
+/// let quic = QuicTransport::create(&ctx).await?;
+/// quic.listen("127.0.0.1:8000", QuicListenerOptions::new()).await?; // Listen on port 8000
+/// quic.connect("127.0.0.1:5000", QuicConnectionOptions::new()).await?; // And connect to port 5000
+/// # Ok(()) }
+/// ```
+#[derive(AsyncTryClone)]
+#[async_try_clone(crate = "ockam_core")]
+pub struct QuicTransport {
+    ctx: Context,
+    /// options of the connections created to resolve the QUIC addresses of a route
+    resolution_options: Arc<dyn Fn() -> QuicConnectionOptions + Send + Sync>,
+}
+
+/// This trait adds a `create_quic_transport` method to any struct returning a Context.
+/// This is the case for an ockam::Node, so you can write `node.create_quic_transport()`
+#[async_trait]
+pub trait QuicTransportExtension: HasContext {
+    /// Create a QUIC transport
+    async fn create_quic_transport(&self) -> Result<QuicTransport> {
+        QuicTransport::create(self.get_context()).await
+    }
+}
+
+impl<A: HasContext> QuicTransportExtension for A {}
@@ -0,0 +1,108 @@
+use crate::workers::{Addresses, QuicRecvProcessor};
+use crate::{tls, QuicConnectionMode, QuicListenerOptions, QuicSendWorker};
+use ockam_core::{async_trait, compat::net::SocketAddr};
+use ockam_core::{Address, Processor, Result};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use quinn::Endpoint;
+use tracing::{debug, warn};
+
+/// A QUIC Listen processor
+///
+/// QUIC listen processors are created by `QuicTransport`
+/// after a call is made to
+/// [`QuicTransport::listen`](crate::QuicTransport::listen).
+pub(crate) struct QuicListenProcessor {
+    endpoint: Endpoint,
+    options: QuicListenerOptions,
+}
+
+impl QuicListenProcessor {
+    pub(crate) async fn start(
+        ctx: &Context,
+        addr: SocketAddr,
+        options: QuicListenerOptions,
+    ) -> Result<(SocketAddr, Address)> {
+        debug!("Binding QuicListener to {}", addr);
+        let endpoint =
+            Endpoint::server(tls::server_config(&options)?, addr).map_err(TransportError::from)?;
+        let saddr = endpoint.local_addr().map_err(TransportError::from)?;
+
+        let address = Address::random_tagged("QuicListenProcessor");
+        options.setup_flow_control_for_listener(ctx.flow_controls(), &address);
+
+        let processor = Self { endpoint, options };
+
+        ctx.start_processor(address.clone(), processor).await?;
+
+        Ok((saddr, address))
+    }
+}
+
+#[async_trait]
+impl Processor for QuicListenProcessor {
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.set_cluster(crate::CLUSTER_NAME).await
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        // Stop accepting connections, the accepted ones are closed by their sender workers
+        self.endpoint.set_server_config(None);
+
+        Ok(())
+    }
+
+    async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
+        debug!("Waiting for incoming QUIC connection...");
+
+        // Wait for an incoming connection
+        let connecting = match self.endpoint.accept().await {
+            Some(connecting) => connecting,
+            // The endpoint was closed
+            None => return Ok(false),
+        };
+        let peer = connecting.remote_address();
+        let connection = match connecting.await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("QUIC handshake with {} failed: {}", peer, e);
+                return Ok(true);
+            }
+        };
+        debug!("QUIC connection accepted");
+
+        let mode = QuicConnectionMode::Incoming;
+        let addresses = Addresses::generate(mode);
+
+        let receiver_flow_control_id = self
+            .options
+            .setup_flow_control_for_connection(ctx.flow_controls(), &addresses);
+        let access_control = self
+            .options
+            .create_access_control(ctx.flow_controls(), receiver_flow_control_id);
+
+        // Worker to receive messages from the Node and send them over the wire
+        QuicSendWorker::start(
+            ctx,
+            connection.clone(),
+            &addresses,
+            peer,
+            access_control.sender_incoming_access_control,
+        )
+        .await?;
+
+        // Processor to receive messages over the wire and forward them to the node
+        QuicRecvProcessor::start(
+            ctx,
+            connection,
+            &addresses,
+            peer,
+            access_control.receiver_outgoing_access_control,
+        )
+        .await?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::workers::Addresses;
+use crate::{QuicSendWorkerMsg, MAX_MESSAGE_SIZE};
+use ockam_core::compat::net::SocketAddr;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    async_trait, AllowOnwardAddress, DenyAll, Mailbox, Mailboxes, OutgoingAccessControl,
+};
+use ockam_core::{Decodable, LocalMessage, Processor, Result, TransportMessage};
+use ockam_node::{Context, ProcessorBuilder};
+use ockam_transport_core::TransportError;
+use quinn::{Connection, RecvStream};
+use tracing::{error, info, trace};
+
+/// A QUIC receiving message processor
+///
+/// This half of the worker is created when spawning a new connection
+/// worker pair, and listens for incoming QUIC packets, to relay into
+/// the node message system.
+///
+/// The messages are read from the unidirectional stream opened by the peer
+pub(crate) struct QuicRecvProcessor {
+    connection: Connection,
+    stream: Option<RecvStream>,
+    socket_address: SocketAddr,
+    addresses: Addresses,
+}
+
+impl QuicRecvProcessor {
+    pub async fn start(
+        ctx: &Context,
+        connection: Connection,
+        addresses: &Addresses,
+        socket_address: SocketAddr,
+        receiver_outgoing_access_control: Arc<dyn OutgoingAccessControl>,
+    ) -> Result<()> {
+        let receiver = QuicRecvProcessor {
+            connection,
+            stream: None,
+            socket_address,
+            addresses: addresses.clone(),
+        };
+
+        let mailbox = Mailbox::new(
+            addresses.receiver_address().clone(),
+            Arc::new(DenyAll),
+            receiver_outgoing_access_control,
+        );
+        let internal = Mailbox::new(
+            addresses.receiver_internal_address().clone(),
+            Arc::new(DenyAll),
+            Arc::new(AllowOnwardAddress(
+                addresses.sender_internal_address().clone(),
+            )),
+        );
+        ProcessorBuilder::new(receiver)
+            .with_mailboxes(Mailboxes::new(mailbox, vec![internal]))
+            .start(ctx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Read the next length-prefixed message, the stream is accepted on the first message
+    async fn read_message(&mut self) -> Result<Vec<u8>> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                let stream = self
+                    .connection
+                    .accept_uni()
+                    .await
+                    .map_err(|_| TransportError::ConnectionDrop)?;
+                self.stream.insert(stream)
+            }
+        };
+
+        let mut len = [0; 4];
+        stream
+            .read_exact(&mut len)
+            .await
+            .map_err(|_| TransportError::ConnectionDrop)?;
+        let len = u32::from_be_bytes(len) as usize;
+        trace!("Received message header for {} bytes", len);
+        if len > MAX_MESSAGE_SIZE {
+            error!(
+                "Message of length {} from peer '{}' is too large",
+                len, self.socket_address
+            );
+            return Err(TransportError::AttackAttmept.into());
+        }
+
+        let mut buf = vec![0; len];
+        stream
+            .read_exact(&mut buf)
+            .await
+            .map_err(|_| TransportError::ConnectionDrop)?;
+        Ok(buf)
+    }
+}
+
+#[async_trait]
+impl Processor for QuicRecvProcessor {
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.set_cluster(crate::CLUSTER_NAME).await
+    }
+
+    /// Get the next message from the connection if there are any
+    /// available and forward it to the next hop in the route.
+    ///
+    /// When the connection is closed, or the peer sends an invalid message,
+    /// the sender is notified and this processor stops.
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let msg = self.read_message().await.and_then(|buf| {
+            TransportMessage::decode(&buf).map_err(|_| TransportError::RecvBadMessage.into())
+        });
+        let mut msg = match msg {
+            Ok(msg) => msg,
+            Err(e) => {
+                info!(
+                    "Connection to peer '{}' was closed; dropping stream: {}",
+                    self.socket_address, e
+                );
+
+                // Notify sender tx is closed
+                ctx.send_from_address(
+                    self.addresses.sender_internal_address().clone(),
+                    QuicSendWorkerMsg::ConnectionClosed,
+                    self.addresses.receiver_internal_address().clone(),
+                )
+                .await?;
+
+                return Ok(false);
+            }
+        };
+
+        // Heartbeat message
+        if msg.onward_route.next().is_err() {
+            trace!("Got heartbeat message from: {}", self.socket_address);
+            return Ok(true);
+        }
+
+        // Insert the peer address into the return route so that
+        // reply routing can be properly resolved
+        msg.return_route
+            .modify()
+            .prepend(self.addresses.sender_address().clone());
+
+        trace!("Message onward route: {}", msg.onward_route);
+        trace!("Message return route: {}", msg.return_route);
+
+        // Forward the message to the next hop in the route
+        ctx.forward_from_address(
+            LocalMessage::new(msg, vec![]),
+            self.addresses.receiver_address().clone(),
+        )
+        .await?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,223 @@
+use crate::workers::Addresses;
+use crate::{tls, QuicConnectionOptions, MAX_MESSAGE_SIZE};
+use ockam_core::{
+    async_trait,
+    compat::{net::SocketAddr, sync::Arc},
+    AllowSourceAddress, DenyAll, IncomingAccessControl,
+};
+use ockam_core::{
+    Any, Decodable, Encodable, Mailbox, Mailboxes, Message, Result, Routed, TransportMessage,
+    Worker,
+};
+use ockam_node::{Context, WorkerBuilder};
+use ockam_transport_core::TransportError;
+use quinn::{Connection, Endpoint, SendStream, VarInt};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use tracing::{debug, info, trace, warn};
+
+#[derive(Serialize, Deserialize, Message, Clone)]
+pub(crate) enum QuicSendWorkerMsg {
+    ConnectionClosed,
+}
+
+/// A QUIC sending message worker
+///
+/// This half of the worker is created when spawning a new connection
+/// worker pair, and listens for messages from the node message system
+/// to dispatch to a remote peer.
+///
+/// The messages are written to a single unidirectional stream, which keeps them in order
+pub(crate) struct QuicSendWorker {
+    connection: Connection,
+    stream: Option<SendStream>,
+    socket_address: SocketAddr,
+    addresses: Addresses,
+    rx_should_be_stopped: bool,
+}
+
+impl QuicSendWorker {
+    /// Create a `QuicSendWorker`, the receiving half of the connection is
+    /// started with [`QuicRecvProcessor::start`](crate::QuicRecvProcessor::start)
+    pub(crate) async fn start(
+        ctx: &Context,
+        connection: Connection,
+        addresses: &Addresses,
+        socket_address: SocketAddr,
+        sender_incoming_access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        trace!("Creating new QUIC worker pair");
+        let sender_worker = Self {
+            connection,
+            stream: None,
+            socket_address,
+            addresses: addresses.clone(),
+            rx_should_be_stopped: true,
+        };
+
+        let main_mailbox = Mailbox::new(
+            addresses.sender_address().clone(),
+            sender_incoming_access_control,
+            Arc::new(DenyAll),
+        );
+
+        let internal_mailbox = Mailbox::new(
+            addresses.sender_internal_address().clone(),
+            Arc::new(AllowSourceAddress(
+                addresses.receiver_internal_address().clone(),
+            )),
+            Arc::new(DenyAll),
+        );
+
+        WorkerBuilder::new(sender_worker)
+            .with_mailboxes(Mailboxes::new(main_mailbox, vec![internal_mailbox]))
+            .start(ctx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn stop(&self, ctx: &Context) -> Result<()> {
+        ctx.stop_worker(self.addresses.sender_address().clone())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Open a QUIC connection to the given peer, from a fresh local UDP socket
+    pub(crate) async fn connect(
+        socket_address: SocketAddr,
+        options: &QuicConnectionOptions,
+    ) -> Result<Connection> {
+        debug!(addr = %socket_address, "Connecting");
+        let bind_address: SocketAddr = if socket_address.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let mut endpoint = Endpoint::client(bind_address).map_err(TransportError::from)?;
+        endpoint.set_default_client_config(tls::client_config(options)?);
+
+        let connecting = endpoint
+            .connect(socket_address, &options.server_name)
+            .map_err(|e| {
+                debug!(addr = %socket_address, err = %e, "Failed to connect");
+                TransportError::InvalidAddress
+            })?;
+        match connecting.await {
+            Ok(connection) => {
+                debug!(addr = %socket_address, "Connected");
+                Ok(connection)
+            }
+            Err(e) => {
+                debug!(addr = %socket_address, err = %e, "Failed to connect");
+                Err(TransportError::PeerNotFound.into())
+            }
+        }
+    }
+
+    /// Write a message to the stream, which is opened on the first message
+    async fn send(&mut self, msg: &[u8]) -> Result<()> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                let stream = self
+                    .connection
+                    .open_uni()
+                    .await
+                    .map_err(|_| TransportError::ConnectionDrop)?;
+                self.stream.insert(stream)
+            }
+        };
+        stream
+            .write_all(msg)
+            .await
+            .map_err(|_| TransportError::ConnectionDrop)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Worker for QuicSendWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        ctx.set_cluster(crate::CLUSTER_NAME).await?;
+
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        self.connection.close(VarInt::from_u32(0), b"closed");
+
+        if self.rx_should_be_stopped {
+            let _ = ctx
+                .stop_processor(self.addresses.receiver_address().clone())
+                .await;
+        }
+
+        Ok(())
+    }
+
+    // QuicSendWorker will receive messages from the node to send
+    // across the QUIC connection to our friend
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let recipient = msg.msg_addr();
+        if &recipient == self.addresses.sender_internal_address() {
+            let msg = QuicSendWorkerMsg::decode(msg.payload())?;
+
+            match msg {
+                QuicSendWorkerMsg::ConnectionClosed => {
+                    info!(
+                        "Stopping sender due to closed connection {}",
+                        self.socket_address
+                    );
+                    // No need to stop Receiver as it notified us about connection drop and will
+                    // stop itself
+                    self.rx_should_be_stopped = false;
+                    self.stop(ctx).await?;
+
+                    return Ok(());
+                }
+            }
+        } else {
+            let mut msg = msg.into_transport_message();
+            // Remove our own address from the route so the other end
+            // knows what to do with the incoming message
+            msg.onward_route.step()?;
+            // Create a message buffer with prepended length
+            let msg = prepare_message(msg)?;
+
+            if self.send(&msg).await.is_err() {
+                warn!("Failed to send message to peer {}", self.socket_address);
+                self.stop(ctx).await?;
+
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Helper that creates a length-prefixed buffer containing the given
+/// `TransportMessage`'s payload
+///
+/// The length-prefix is encoded as a big-endian 32-bit unsigned
+/// integer.
+fn prepare_message(msg: TransportMessage) -> Result<Vec<u8>> {
+    let msg = msg.encode().map_err(|_| TransportError::SendBadMessage)?;
+    if msg.len() > MAX_MESSAGE_SIZE {
+        return Err(TransportError::Capacity.into());
+    }
+
+    let mut buf = Vec::with_capacity(4 + msg.len());
+    buf.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&msg);
+    Ok(buf)
+}
@@ -0,0 +1,208 @@
+use core::time::Duration;
+use ockam_core::compat::rand::{self, Rng};
+use ockam_core::{route, AllowAll, Result, Routed, Worker};
+use ockam_node::{Context, MessageReceiveOptions};
+use ockam_transport_quic::{QuicConnectionOptions, QuicListenerOptions, QuicTransport};
+
+pub struct Echoer;
+
+#[ockam_core::worker]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+fn random_message(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn quic_lifecycle__two_connections__should_both_work(ctx: &mut Context) -> Result<()> {
+    let options = QuicListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = QuicTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let msg1 = random_message(256);
+    // larger than a UDP datagram
+    let msg2 = random_message(100_000);
+
+    let tx_address1 = transport
+        .connect(
+            listener.socket_string(),
+            QuicConnectionOptions::new().without_server_verification(),
+        )
+        .await?;
+
+    let reply1: String = ctx
+        .send_and_receive(route![tx_address1.clone(), "echoer"], msg1.clone())
+        .await?;
+    assert_eq!(reply1, msg1, "Should receive the same message");
+
+    let tx_address2 = transport
+        .connect(
+            listener.socket_string(),
+            QuicConnectionOptions::new().without_server_verification(),
+        )
+        .await?;
+    let reply2: String = ctx
+        .send_and_receive(route![tx_address2.clone(), "echoer"], msg2.clone())
+        .await?;
+    assert_eq!(reply2, msg2, "Should receive the same message");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn quic_lifecycle__disconnect__should_stop_worker(ctx: &mut Context) -> Result<()> {
+    let options = QuicListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = QuicTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let connection = transport
+        .connect(
+            listener.socket_string(),
+            QuicConnectionOptions::new().without_server_verification(),
+        )
+        .await?;
+    let msg = random_message(256);
+    let reply: String = ctx
+        .send_and_receive(
+            route![connection.sender_address().clone(), "echoer"],
+            msg.clone(),
+        )
+        .await?;
+    assert_eq!(reply, msg, "Should receive the same message");
+
+    transport
+        .disconnect(connection.sender_address().clone())
+        .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    let res = ctx
+        .send(route![connection.sender_address().clone(), "echoer"], msg)
+        .await;
+    assert!(res.is_err(), "Should not send messages after disconnection");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn quic_lifecycle__consumer_not_allowed__should_not_receive(ctx: &mut Context) -> Result<()> {
+    // The echoer is not a consumer of the listener flow control
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = QuicTransport::create(ctx).await?;
+    let listener = transport
+        .listen("127.0.0.1:0", QuicListenerOptions::new())
+        .await?;
+    let connection = transport
+        .connect(
+            listener.socket_string(),
+            QuicConnectionOptions::new().without_server_verification(),
+        )
+        .await?;
+
+    let mut child = ctx.new_detached("child", AllowAll, AllowAll).await?;
+    child
+        .send(
+            route![connection.sender_address().clone(), "echoer"],
+            random_message(16),
+        )
+        .await?;
+    let res = child
+        .receive_extended::<String>(
+            MessageReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err(), "The message should be rejected");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn quic_lifecycle__unknown_certificate__should_be_rejected(ctx: &mut Context) -> Result<()> {
+    let transport = QuicTransport::create(ctx).await?;
+    // The listener presents a self-signed certificate
+    let listener = transport
+        .listen("127.0.0.1:0", QuicListenerOptions::new())
+        .await?;
+
+    let res = transport
+        .connect(listener.socket_string(), QuicConnectionOptions::new())
+        .await;
+    assert!(res.is_err(), "The certificate should not be trusted");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn quic_lifecycle__root_certificate__should_verify_listener(ctx: &mut Context) -> Result<()> {
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let der = certificate.serialize_der().unwrap();
+    let options = QuicListenerOptions::new()
+        .with_certificate(vec![der.clone()], certificate.serialize_private_key_der());
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = QuicTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+    let connection = transport
+        .connect(
+            listener.socket_string(),
+            QuicConnectionOptions::new().with_root_certificate(der),
+        )
+        .await?;
+
+    let msg = random_message(256);
+    let reply: String = ctx
+        .send_and_receive(
+            route![connection.sender_address().clone(), "echoer"],
+            msg.clone(),
+        )
+        .await?;
+    assert_eq!(reply, msg, "Should receive the same message");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
@@ -25,7 +25,12 @@ mod transport;
 
 use ockam_core::TransportType;
 pub use options::{TcpConnectionOptions, TcpListenerOptions};
-pub use portal::{PortalInternalMessage, PortalMessage, MAX_PAYLOAD_SIZE};
+pub use portal::{
+    PortalCapabilities, PortalInternalMessage, PortalMessage, TcpInletTls, TcpOutletLoadBalancing,
+    TcpOutletTargetStatus, TcpOutletTargets, TcpOutletTls, TcpPortalLimiter, TcpPortalLimits,
+    TcpPortalLimitsStatus, DEFAULT_DNS_REFRESH_INTERVAL, DEFAULT_PORTAL_CREDIT_WINDOW,
+    DEFAULT_UNHEALTHY_TARGET_BACKOFF, MAX_PAYLOAD_SIZE,
+};
 pub use registry::*;
 pub use transport::common::*;
 pub use transport::*;
@@ -0,0 +1,157 @@
+use crate::MAX_PAYLOAD_SIZE;
+use ockam_core::compat::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Default number of bytes a portal can send to the other side of the portal
+/// before they are acknowledged
+pub const DEFAULT_PORTAL_CREDIT_WINDOW: u64 = 16 * MAX_PAYLOAD_SIZE as u64;
+
+/// Minimum credit window. The other side acknowledges the data after writing
+/// [`CREDIT_ACKNOWLEDGEMENT_THRESHOLD`] bytes, so a smaller window could stall the portal
+pub(crate) const MIN_PORTAL_CREDIT_WINDOW: u64 = 2 * MAX_PAYLOAD_SIZE as u64;
+
+/// Number of bytes written to the TCP connection before they are acknowledged
+/// to the other side of the portal
+pub(crate) const CREDIT_ACKNOWLEDGEMENT_THRESHOLD: u64 = MAX_PAYLOAD_SIZE as u64;
+
+/// Credits of the data sent to the other side of a portal.
+///
+/// The receiver processor stops reading the TCP connection when the other side has not
+/// acknowledged `window` bytes, so that the data waiting in the mailbox of the other side
+/// stays bounded. Credits are only enforced once the other side has sent an acknowledgement,
+/// since older portals and some intermediate workers never acknowledge anything
+#[derive(Clone, Debug)]
+pub(crate) struct PortalCredits {
+    window: u64,
+    state: Arc<Mutex<CreditsState>>,
+    notify: Arc<Notify>,
+}
+
+#[derive(Debug, Default)]
+struct CreditsState {
+    enabled: bool,
+    sent: u64,
+    acknowledged: u64,
+}
+
+impl CreditsState {
+    fn has_credits(&self, window: u64) -> bool {
+        !self.enabled || self.sent.saturating_sub(self.acknowledged) < window
+    }
+}
+
+impl PortalCredits {
+    pub(crate) fn new(window: u64) -> Self {
+        Self {
+            window: window.max(MIN_PORTAL_CREDIT_WINDOW),
+            state: Default::default(),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Record that some bytes were sent to the other side
+    pub(crate) fn sent(&self, len: usize) {
+        self.state.lock().unwrap().sent += len as u64;
+    }
+
+    /// Record the total number of bytes acknowledged by the other side
+    pub(crate) fn acknowledge(&self, acknowledged: u64) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.enabled = true;
+            state.acknowledged = state.acknowledged.max(acknowledged);
+        }
+        self.notify.notify_one();
+    }
+
+    /// Return true if more data can be sent to the other side
+    pub(crate) fn has_credits(&self) -> bool {
+        self.state.lock().unwrap().has_credits(self.window)
+    }
+
+    /// Wait until more data can be sent to the other side
+    pub(crate) async fn wait(&self) {
+        loop {
+            // Register before checking the state, so that no acknowledgement is missed
+            let notified = self.notify.notified();
+            if self.has_credits() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Acknowledgements of the data received from the other side of a portal, once written
+/// to the TCP connection
+#[derive(Debug, Default)]
+pub(crate) struct PortalAcknowledgements {
+    enabled: bool,
+    written: u64,
+    acknowledged: u64,
+}
+
+impl PortalAcknowledgements {
+    /// Acknowledgements are only sent to a portal which acknowledges our data
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Record that some bytes were written and return the total number of bytes
+    /// to acknowledge, if an acknowledgement must be sent
+    pub(crate) fn written(&mut self, len: usize) -> Option<u64> {
+        self.written += len as u64;
+        if self.enabled && self.written - self.acknowledged >= CREDIT_ACKNOWLEDGEMENT_THRESHOLD {
+            self.acknowledged = self.written;
+            Some(self.written)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    #[tokio::test]
+    async fn test_credits_are_enforced_once_acknowledged() {
+        let credits = PortalCredits::new(MIN_PORTAL_CREDIT_WINDOW);
+
+        // nothing was ever acknowledged by the other side
+        credits.sent(10 * MIN_PORTAL_CREDIT_WINDOW as usize);
+        assert!(credits.has_credits());
+
+        credits.acknowledge(9 * MIN_PORTAL_CREDIT_WINDOW);
+        assert!(!credits.has_credits());
+
+        let waiting = {
+            let credits = credits.clone();
+            tokio::spawn(async move { credits.wait().await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        credits.acknowledge(9 * MIN_PORTAL_CREDIT_WINDOW + 1);
+        waiting.await.unwrap();
+        assert!(credits.has_credits());
+    }
+
+    #[test]
+    fn test_acknowledgements() {
+        let mut acknowledgements = PortalAcknowledgements::default();
+        assert_eq!(acknowledgements.written(MAX_PAYLOAD_SIZE), None);
+
+        acknowledgements.enable();
+        assert_eq!(
+            acknowledgements.written(1),
+            Some(MAX_PAYLOAD_SIZE as u64 + 1)
+        );
+        assert_eq!(acknowledgements.written(MAX_PAYLOAD_SIZE - 1), None);
+        assert_eq!(
+            acknowledgements.written(1),
+            Some(2 * MAX_PAYLOAD_SIZE as u64 + 1)
+        );
+    }
+}
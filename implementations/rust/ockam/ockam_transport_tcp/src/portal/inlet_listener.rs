@@ -6,7 +6,7 @@ use ockam_core::{Address, Processor, Result, Route};
 use ockam_node::Context;
 use ockam_transport_core::TransportError;
 use tokio::net::TcpListener;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 /// A TCP Portal Inlet listen processor
 ///
@@ -91,6 +91,18 @@ impl Processor for TcpInletListenProcessor {
         );
 
         let (stream, peer) = self.inner.accept().await.map_err(TransportError::from)?;
+
+        let connection_permit = match &self.options.limiter {
+            Some(limiter) => match limiter.acquire_connection() {
+                Some(permit) => Some(permit),
+                None => {
+                    warn!(%peer, "too many connections, the connection is closed");
+                    return Ok(true);
+                }
+            },
+            None => None,
+        };
+
         TcpPortalWorker::start_new_inlet(
             ctx,
             self.registry.clone(),
@@ -99,6 +111,10 @@ impl Processor for TcpInletListenProcessor {
             outlet_listener_route,
             addresses,
             self.options.incoming_access_control.clone(),
+            self.options.tls.clone(),
+            self.options.limiter.clone(),
+            connection_permit,
+            self.options.credit_window,
         )
         .await?;
 
@@ -0,0 +1,348 @@
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+use ockam_core::compat::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Limits enforced on the connections of an Inlet or of an Outlet
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TcpPortalLimits {
+    max_connections: Option<usize>,
+    connection_bytes_per_second: Option<u64>,
+    aggregate_bytes_per_second: Option<u64>,
+    idle_timeout: Option<Duration>,
+}
+
+impl TcpPortalLimits {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of connections open at the same time.
+    /// Additional connections are closed right away
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Set the maximum number of bytes per second transferred by each connection,
+    /// in each direction
+    pub fn with_connection_bytes_per_second(mut self, bytes_per_second: u64) -> Self {
+        self.connection_bytes_per_second = Some(bytes_per_second);
+        self
+    }
+
+    /// Set the maximum number of bytes per second transferred by all the connections together,
+    /// in each direction
+    pub fn with_aggregate_bytes_per_second(mut self, bytes_per_second: u64) -> Self {
+        self.aggregate_bytes_per_second = Some(bytes_per_second);
+        self
+    }
+
+    /// Set the duration after which a connection without any data transferred is closed
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Maximum number of connections open at the same time
+    pub fn max_connections(&self) -> Option<usize> {
+        self.max_connections
+    }
+
+    /// Maximum number of bytes per second transferred by each connection
+    pub fn connection_bytes_per_second(&self) -> Option<u64> {
+        self.connection_bytes_per_second
+    }
+
+    /// Maximum number of bytes per second transferred by all the connections
+    pub fn aggregate_bytes_per_second(&self) -> Option<u64> {
+        self.aggregate_bytes_per_second
+    }
+
+    /// Duration after which an idle connection is closed
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+}
+
+/// Current state of the limits of an Inlet or of an Outlet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TcpPortalLimitsStatus {
+    /// Configured limits
+    pub limits: TcpPortalLimits,
+    /// Number of connections currently open
+    pub active_connections: usize,
+    /// Number of connections closed because too many connections were open
+    pub rejected_connections: u64,
+    /// Number of connections closed because they were idle
+    pub idle_disconnections: u64,
+    /// Number of times a connection had to wait before transferring data
+    pub throttled_transfers: u64,
+}
+
+/// Handle enforcing [`TcpPortalLimits`] on all the connections of an Inlet or of an Outlet.
+/// It is shared by the connections, and can be kept to get the [`TcpPortalLimitsStatus`]
+#[derive(Clone, Debug)]
+pub struct TcpPortalLimiter {
+    state: Arc<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    limits: TcpPortalLimits,
+    active_connections: AtomicUsize,
+    rejected_connections: AtomicU64,
+    idle_disconnections: AtomicU64,
+    throttled_transfers: AtomicU64,
+    // aggregate buckets for the data received from the TCP connections, and sent to them
+    aggregate_received: Option<Arc<Mutex<TokenBucket>>>,
+    aggregate_sent: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl TcpPortalLimiter {
+    /// Create a limiter enforcing the given limits
+    pub fn new(limits: TcpPortalLimits) -> Self {
+        let aggregate = || {
+            limits
+                .aggregate_bytes_per_second
+                .map(|rate| Arc::new(Mutex::new(TokenBucket::new(rate, Instant::now()))))
+        };
+        Self {
+            state: Arc::new(LimiterState {
+                aggregate_received: aggregate(),
+                aggregate_sent: aggregate(),
+                limits,
+                active_connections: AtomicUsize::new(0),
+                rejected_connections: AtomicU64::new(0),
+                idle_disconnections: AtomicU64::new(0),
+                throttled_transfers: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Configured limits
+    pub fn limits(&self) -> &TcpPortalLimits {
+        &self.state.limits
+    }
+
+    /// Current state of the limits
+    pub fn status(&self) -> TcpPortalLimitsStatus {
+        TcpPortalLimitsStatus {
+            limits: self.state.limits.clone(),
+            active_connections: self.state.active_connections.load(Ordering::Relaxed),
+            rejected_connections: self.state.rejected_connections.load(Ordering::Relaxed),
+            idle_disconnections: self.state.idle_disconnections.load(Ordering::Relaxed),
+            throttled_transfers: self.state.throttled_transfers.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Return a permit for a new connection, or None if too many connections are already open.
+    /// The connection is counted as open until the permit is dropped
+    pub(crate) fn acquire_connection(&self) -> Option<ConnectionPermit> {
+        let max = self.state.limits.max_connections.unwrap_or(usize::MAX);
+        let acquired = self.state.active_connections.fetch_update(
+            Ordering::AcqRel,
+            Ordering::Acquire,
+            |active| if active < max { Some(active + 1) } else { None },
+        );
+        match acquired {
+            Ok(_) => Some(ConnectionPermit {
+                limiter: self.clone(),
+            }),
+            Err(_) => {
+                self.state
+                    .rejected_connections
+                    .fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Bandwidth of a new connection, for the data received from the TCP connection
+    pub(crate) fn received_bandwidth(&self) -> ConnectionBandwidth {
+        self.bandwidth(self.state.aggregate_received.clone())
+    }
+
+    /// Bandwidth of a new connection, for the data sent to the TCP connection
+    pub(crate) fn sent_bandwidth(&self) -> ConnectionBandwidth {
+        self.bandwidth(self.state.aggregate_sent.clone())
+    }
+
+    fn bandwidth(&self, aggregate: Option<Arc<Mutex<TokenBucket>>>) -> ConnectionBandwidth {
+        ConnectionBandwidth {
+            limiter: self.clone(),
+            connection: self
+                .state
+                .limits
+                .connection_bytes_per_second
+                .map(|rate| TokenBucket::new(rate, Instant::now())),
+            aggregate,
+        }
+    }
+
+    /// Record that a connection was closed because it was idle
+    pub(crate) fn idle_disconnection(&self) {
+        self.state
+            .idle_disconnections
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// An open connection, counted until it is dropped
+#[derive(Debug)]
+pub(crate) struct ConnectionPermit {
+    limiter: TcpPortalLimiter,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.limiter
+            .state
+            .active_connections
+            .fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Bandwidth available to one direction of a connection
+pub(crate) struct ConnectionBandwidth {
+    limiter: TcpPortalLimiter,
+    connection: Option<TokenBucket>,
+    aggregate: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl ConnectionBandwidth {
+    /// Wait until `len` bytes can be transferred
+    pub(crate) async fn consume(&mut self, len: usize) {
+        let now = Instant::now();
+        let mut wait = Duration::ZERO;
+        if let Some(bucket) = &mut self.connection {
+            wait = wait.max(bucket.take(len as u64, now));
+        }
+        if let Some(bucket) = &self.aggregate {
+            wait = wait.max(bucket.lock().unwrap().take(len as u64, now));
+        }
+        if !wait.is_zero() {
+            self.limiter
+                .state
+                .throttled_transfers
+                .fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Token bucket refilled with `rate` tokens per second, holding at most one second of tokens.
+/// Taking more tokens than available is allowed, the bucket is then in debt and the caller
+/// must wait until the debt is repaid
+#[derive(Debug)]
+struct TokenBucket {
+    rate: u64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate as f64,
+            last_refill: now,
+        }
+    }
+
+    /// Take some tokens and return the duration to wait before using them
+    fn take(&mut self, tokens: u64, now: Instant) -> Duration {
+        if self.rate == 0 {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.last_refill = now;
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.rate as f64).min(self.rate as f64);
+        self.tokens -= tokens as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate as f64)
+        }
+    }
+}
+
+/// Last time data was transferred by a connection, shared by both directions
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectionActivity {
+    last_activity: Arc<Mutex<Instant>>,
+}
+
+impl ConnectionActivity {
+    pub(crate) fn new() -> Self {
+        Self {
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub(crate) fn record(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    pub(crate) fn idle_duration(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(100, start);
+
+        // a full second of tokens is available
+        assert_eq!(bucket.take(100, start), Duration::ZERO);
+        // then the bucket is in debt
+        assert_eq!(bucket.take(50, start), Duration::from_millis(500));
+        // the debt is repaid over time
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(0, later), Duration::ZERO);
+        // the bucket never holds more than one second of tokens
+        let much_later = later + Duration::from_secs(10);
+        assert_eq!(bucket.take(100, much_later), Duration::ZERO);
+        assert!(bucket.take(1, much_later) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let limiter = TcpPortalLimiter::new(TcpPortalLimits::new().with_max_connections(2));
+        let first = limiter.acquire_connection();
+        let second = limiter.acquire_connection();
+        assert!(first.is_some());
+        assert!(second.is_some());
+        assert!(limiter.acquire_connection().is_none());
+
+        drop(first);
+        assert!(limiter.acquire_connection().is_some());
+
+        let status = limiter.status();
+        assert_eq!(status.active_connections, 1);
+        assert_eq!(status.rejected_connections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_bandwidth_is_shared_by_connections() {
+        let limiter =
+            TcpPortalLimiter::new(TcpPortalLimits::new().with_aggregate_bytes_per_second(1000));
+        let mut first = limiter.received_bandwidth();
+        let mut second = limiter.received_bandwidth();
+        let mut sent = limiter.sent_bandwidth();
+
+        first.consume(1000).await;
+        sent.consume(1000).await;
+        assert_eq!(limiter.status().throttled_transfers, 0);
+
+        second.consume(10).await;
+        assert_eq!(limiter.status().throttled_transfers, 1);
+    }
+}
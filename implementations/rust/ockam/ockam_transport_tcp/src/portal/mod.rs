@@ -1,13 +1,21 @@
 mod addresses;
+mod credits;
 mod inlet_listener;
+mod limits;
 pub mod options;
 mod outlet_listener;
+mod outlet_targets;
 mod portal_message;
 mod portal_receiver;
 mod portal_worker;
+mod tls;
 
+pub use credits::DEFAULT_PORTAL_CREDIT_WINDOW;
 pub(crate) use inlet_listener::*;
+pub use limits::{TcpPortalLimiter, TcpPortalLimits, TcpPortalLimitsStatus};
 pub(crate) use outlet_listener::*;
+pub use outlet_targets::*;
 pub use portal_message::*;
 pub(crate) use portal_receiver::*;
 pub(crate) use portal_worker::*;
+pub use tls::{TcpInletTls, TcpOutletTls};
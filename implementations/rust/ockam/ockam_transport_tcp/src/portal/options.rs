@@ -1,4 +1,11 @@
 use crate::portal::addresses::Addresses;
+use crate::portal::credits::DEFAULT_PORTAL_CREDIT_WINDOW;
+use crate::portal::limits::TcpPortalLimiter;
+use crate::portal::outlet_targets::{
+    TcpOutletLoadBalancing, DEFAULT_DNS_REFRESH_INTERVAL, DEFAULT_UNHEALTHY_TARGET_BACKOFF,
+};
+use crate::portal::tls::{TcpInletTls, TcpOutletTls};
+use core::time::Duration;
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControls};
 use ockam_core::{Address, AllowAll, IncomingAccessControl};
@@ -7,6 +14,9 @@ use ockam_core::{Address, AllowAll, IncomingAccessControl};
 #[derive(Debug)]
 pub struct TcpInletOptions {
     pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) tls: Option<TcpInletTls>,
+    pub(super) limiter: Option<TcpPortalLimiter>,
+    pub(super) credit_window: u64,
 }
 
 impl TcpInletOptions {
@@ -14,6 +24,9 @@ impl TcpInletOptions {
     pub fn new() -> Self {
         Self {
             incoming_access_control: Arc::new(AllowAll),
+            tls: None,
+            limiter: None,
+            credit_window: DEFAULT_PORTAL_CREDIT_WINDOW,
         }
     }
 
@@ -35,6 +48,24 @@ impl TcpInletOptions {
         self
     }
 
+    /// Accept the connections of the Inlet clients with TLS
+    pub fn with_tls(mut self, tls: TcpInletTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Enforce the limits of a [`TcpPortalLimiter`] on the connections of the Inlet clients
+    pub fn with_limiter(mut self, limiter: TcpPortalLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Set the number of bytes sent to the Outlet before they must be acknowledged
+    pub fn with_credit_window(mut self, window: u64) -> Self {
+        self.credit_window = window;
+        self
+    }
+
     pub(super) fn setup_flow_control(
         &self,
         flow_controls: &FlowControls,
@@ -62,6 +93,12 @@ impl Default for TcpInletOptions {
 pub struct TcpOutletOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(crate) load_balancing: TcpOutletLoadBalancing,
+    pub(crate) dns_refresh_interval: Duration,
+    pub(crate) unhealthy_target_backoff: Duration,
+    pub(super) tls: Option<TcpOutletTls>,
+    pub(super) limiter: Option<TcpPortalLimiter>,
+    pub(super) credit_window: u64,
 }
 
 impl TcpOutletOptions {
@@ -70,6 +107,12 @@ impl TcpOutletOptions {
         Self {
             consumer: vec![],
             incoming_access_control: Arc::new(AllowAll),
+            load_balancing: TcpOutletLoadBalancing::default(),
+            dns_refresh_interval: DEFAULT_DNS_REFRESH_INTERVAL,
+            unhealthy_target_backoff: DEFAULT_UNHEALTHY_TARGET_BACKOFF,
+            tls: None,
+            limiter: None,
+            credit_window: DEFAULT_PORTAL_CREDIT_WINDOW,
         }
     }
 
@@ -100,6 +143,42 @@ impl TcpOutletOptions {
         self
     }
 
+    /// Set the strategy used to select the target of each new connection
+    pub fn with_load_balancing(mut self, load_balancing: TcpOutletLoadBalancing) -> Self {
+        self.load_balancing = load_balancing;
+        self
+    }
+
+    /// Set the interval after which the targets given as DNS names are resolved again
+    pub fn with_dns_refresh_interval(mut self, interval: Duration) -> Self {
+        self.dns_refresh_interval = interval;
+        self
+    }
+
+    /// Set the duration during which a target is not selected after a failed connection
+    pub fn with_unhealthy_target_backoff(mut self, backoff: Duration) -> Self {
+        self.unhealthy_target_backoff = backoff;
+        self
+    }
+
+    /// Connect to the targets with TLS
+    pub fn with_tls(mut self, tls: TcpOutletTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Enforce the limits of a [`TcpPortalLimiter`] on the connections to the targets
+    pub fn with_limiter(mut self, limiter: TcpPortalLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Set the number of bytes sent to the Inlet before they must be acknowledged
+    pub fn with_credit_window(mut self, window: u64) -> Self {
+        self.credit_window = window;
+        self
+    }
+
     pub(super) fn setup_flow_control_for_outlet_listener(
         &self,
         flow_controls: &FlowControls,
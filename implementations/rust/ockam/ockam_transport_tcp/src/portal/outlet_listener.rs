@@ -1,9 +1,10 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalWorker, PortalMessage, TcpOutletOptions, TcpRegistry};
+use crate::{
+    portal::TcpPortalWorker, PortalMessage, TcpOutletOptions, TcpOutletTargets, TcpRegistry,
+};
 use ockam_core::{async_trait, Address, DenyAll, Result, Routed, Worker};
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::TransportError;
-use std::net::SocketAddr;
 use tracing::debug;
 
 /// A TCP Portal Outlet listen worker
@@ -13,16 +14,16 @@ use tracing::debug;
 /// [`TcpTransport::create_outlet`](crate::TcpTransport::create_outlet).
 pub(crate) struct TcpOutletListenWorker {
     registry: TcpRegistry,
-    peer: SocketAddr,
+    targets: TcpOutletTargets,
     options: TcpOutletOptions,
 }
 
 impl TcpOutletListenWorker {
     /// Create a new `TcpOutletListenWorker`
-    fn new(registry: TcpRegistry, peer: SocketAddr, options: TcpOutletOptions) -> Self {
+    fn new(registry: TcpRegistry, targets: TcpOutletTargets, options: TcpOutletOptions) -> Self {
         Self {
             registry,
-            peer,
+            targets,
             options,
         }
     }
@@ -31,14 +32,14 @@ impl TcpOutletListenWorker {
         ctx: &Context,
         registry: TcpRegistry,
         address: Address,
-        peer: SocketAddr,
+        targets: TcpOutletTargets,
         options: TcpOutletOptions,
     ) -> Result<()> {
         let access_control = options.incoming_access_control.clone();
 
         options.setup_flow_control_for_outlet_listener(ctx.flow_controls(), &address);
 
-        let worker = Self::new(registry, peer, options);
+        let worker = Self::new(registry, targets, options);
         WorkerBuilder::new(worker)
             .with_address(address)
             .with_incoming_access_control_arc(access_control)
@@ -75,10 +76,11 @@ impl Worker for TcpOutletListenWorker {
         let return_route = msg.return_route();
         let src_addr = msg.src_addr();
 
-        if let PortalMessage::Ping = msg.body() {
-        } else {
-            return Err(TransportError::Protocol.into());
-        }
+        // the capabilities of the Inlet are sent after its ping
+        let their_capabilities = match PortalMessage::decode_with_capabilities(msg.payload())? {
+            (PortalMessage::Ping, capabilities) => capabilities,
+            _ => return Err(TransportError::Protocol.into()),
+        };
 
         let addresses = Addresses::generate(PortalType::Outlet);
 
@@ -88,10 +90,14 @@ impl Worker for TcpOutletListenWorker {
         TcpPortalWorker::start_new_outlet(
             ctx,
             self.registry.clone(),
-            self.peer,
+            self.targets.clone(),
             return_route.clone(),
             addresses.clone(),
             self.options.incoming_access_control.clone(),
+            self.options.tls.clone(),
+            self.options.limiter.clone(),
+            self.options.credit_window,
+            their_capabilities,
         )
         .await?;
 
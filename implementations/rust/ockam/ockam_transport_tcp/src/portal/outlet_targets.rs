@@ -0,0 +1,396 @@
+use crate::transport::common::{parse_socket_addr, resolve_peer};
+use core::time::Duration;
+use ockam_core::compat::net::SocketAddr;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::Result;
+use ockam_transport_core::TransportError;
+use std::time::Instant;
+use tokio::net::lookup_host;
+use tracing::{debug, warn};
+
+/// Default interval after which the targets given as DNS names are resolved again
+pub const DEFAULT_DNS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default duration during which a target is not selected after a failed connection
+pub const DEFAULT_UNHEALTHY_TARGET_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Strategy used by an Outlet to select the target of each new connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TcpOutletLoadBalancing {
+    /// Select the targets one after the other
+    #[default]
+    RoundRobin,
+    /// Select the target with the smallest number of active connections
+    LeastConnections,
+}
+
+/// Status of one of the targets of an Outlet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpOutletTargetStatus {
+    /// Target, as given when the Outlet was created
+    pub target: String,
+    /// Last resolved address of the target
+    pub address: Option<SocketAddr>,
+    /// False if the last connection to the target failed recently
+    pub healthy: bool,
+    /// Number of connections currently open to the target
+    pub active_connections: usize,
+    /// Number of consecutive failed connections to the target
+    pub failed_connections: u32,
+}
+
+struct TargetState {
+    target: String,
+    // false if the target is a socket address which doesn't need to be resolved
+    needs_resolution: bool,
+    address: Option<SocketAddr>,
+    resolved_at: Option<Instant>,
+    active_connections: usize,
+    failed_connections: u32,
+    unhealthy_until: Option<Instant>,
+}
+
+impl TargetState {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.map(|t| t <= now).unwrap_or(true)
+    }
+
+    fn needs_refresh(&self, now: Instant, interval: Duration) -> bool {
+        self.needs_resolution
+            && self
+                .resolved_at
+                .map(|t| now.duration_since(t) >= interval)
+                .unwrap_or(true)
+    }
+}
+
+struct TargetsState {
+    targets: Vec<TargetState>,
+    // index of the next target for the round-robin selection
+    next: usize,
+    load_balancing: TcpOutletLoadBalancing,
+    dns_refresh_interval: Duration,
+    unhealthy_target_backoff: Duration,
+}
+
+/// Targets of an Outlet, shared by the Outlet listener and the portal workers that it creates.
+///
+/// Each new connection is made to a target selected with the configured [`TcpOutletLoadBalancing`].
+/// A target is considered unhealthy for some time after a failed connection, and is skipped
+/// unless all the other targets are unhealthy too. Targets given as DNS names are resolved
+/// again periodically, and after each failed connection.
+#[derive(Clone)]
+pub struct TcpOutletTargets {
+    state: Arc<Mutex<TargetsState>>,
+}
+
+impl TcpOutletTargets {
+    /// Create the targets of an Outlet. Each target is either a socket address or a `host:port` name.
+    /// At least one of the targets must be resolved successfully
+    pub(crate) fn new(
+        targets: Vec<String>,
+        load_balancing: TcpOutletLoadBalancing,
+        dns_refresh_interval: Duration,
+        unhealthy_target_backoff: Duration,
+    ) -> Result<Self> {
+        let now = Instant::now();
+        let mut states = vec![];
+        for target in targets {
+            let state = if let Ok(address) = parse_socket_addr(&target) {
+                TargetState {
+                    target,
+                    needs_resolution: false,
+                    address: Some(address),
+                    resolved_at: Some(now),
+                    active_connections: 0,
+                    failed_connections: 0,
+                    unhealthy_until: None,
+                }
+            } else {
+                if !has_port(&target) {
+                    return Err(TransportError::InvalidAddress.into());
+                }
+                let address = resolve_peer(target.clone()).ok();
+                TargetState {
+                    target,
+                    needs_resolution: true,
+                    resolved_at: address.map(|_| now),
+                    address,
+                    active_connections: 0,
+                    failed_connections: 0,
+                    unhealthy_until: None,
+                }
+            };
+            states.push(state);
+        }
+
+        if states.iter().all(|s| s.address.is_none()) {
+            return Err(TransportError::InvalidAddress.into());
+        }
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(TargetsState {
+                targets: states,
+                next: 0,
+                load_balancing,
+                dns_refresh_interval,
+                unhealthy_target_backoff,
+            })),
+        })
+    }
+
+    /// Return the status of each target
+    pub fn status(&self) -> Vec<TcpOutletTargetStatus> {
+        let now = Instant::now();
+        let state = self.state.lock().unwrap();
+        state
+            .targets
+            .iter()
+            .map(|t| TcpOutletTargetStatus {
+                target: t.target.clone(),
+                address: t.address,
+                healthy: t.is_healthy(now),
+                active_connections: t.active_connections,
+                failed_connections: t.failed_connections,
+            })
+            .collect()
+    }
+
+    /// Resolve again the targets given as DNS names when their last resolution is too old.
+    /// If a name can't be resolved, its previous address is kept
+    pub(crate) async fn refresh(&self) {
+        let to_resolve: Vec<(usize, String)> = {
+            let now = Instant::now();
+            let state = self.state.lock().unwrap();
+            state
+                .targets
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.needs_refresh(now, state.dns_refresh_interval))
+                .map(|(i, t)| (i, t.target.clone()))
+                .collect()
+        };
+
+        for (index, target) in to_resolve {
+            let address = match lookup_host(target.as_str()).await {
+                Ok(addresses) => {
+                    let addresses: Vec<SocketAddr> = addresses.collect();
+                    // Prefer ip4, like resolve_peer
+                    addresses
+                        .iter()
+                        .find(|a| a.is_ipv4())
+                        .or_else(|| addresses.first())
+                        .copied()
+                }
+                Err(err) => {
+                    warn!("Failed to resolve the outlet target {}: {}", target, err);
+                    None
+                }
+            };
+
+            let mut state = self.state.lock().unwrap();
+            if let Some(t) = state.targets.get_mut(index) {
+                if let Some(address) = address {
+                    if t.address != Some(address) {
+                        debug!("Outlet target {} resolved to {}", target, address);
+                    }
+                    t.address = Some(address);
+                    t.resolved_at = Some(Instant::now());
+                }
+            }
+        }
+    }
+
+    /// Select a target for a new connection, among the targets which have an address and which
+    /// are not excluded. Healthy targets are preferred
+    pub(crate) fn select(&self, excluded: &[usize]) -> Option<SelectedTarget> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let count = state.targets.len();
+
+        // candidates are listed in round-robin order, starting with the next target
+        let candidates: Vec<usize> = (0..count)
+            .map(|i| (state.next + i) % count)
+            .filter(|i| !excluded.contains(i) && state.targets[*i].address.is_some())
+            .collect();
+        let healthy: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|i| state.targets[*i].is_healthy(now))
+            .collect();
+        let candidates = if healthy.is_empty() {
+            candidates
+        } else {
+            healthy
+        };
+
+        let index = match state.load_balancing {
+            TcpOutletLoadBalancing::RoundRobin => candidates.first().copied(),
+            TcpOutletLoadBalancing::LeastConnections => candidates
+                .iter()
+                .copied()
+                .min_by_key(|i| state.targets[*i].active_connections),
+        }?;
+
+        state.next = (index + 1) % count;
+        let target = &mut state.targets[index];
+        let address = target.address?;
+        target.active_connections += 1;
+
+        Some(SelectedTarget {
+            targets: self.clone(),
+            index,
+            address,
+        })
+    }
+}
+
+/// A target selected for a connection. The connection is counted as active until this value is dropped
+pub(crate) struct SelectedTarget {
+    targets: TcpOutletTargets,
+    index: usize,
+    address: SocketAddr,
+}
+
+impl SelectedTarget {
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(crate) fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Target, as given when the Outlet was created
+    pub(crate) fn target(&self) -> String {
+        let state = self.targets.state.lock().unwrap();
+        state.targets[self.index].target.clone()
+    }
+
+    /// Mark the target as healthy after a successful connection
+    pub(crate) fn connected(&self) {
+        let mut state = self.targets.state.lock().unwrap();
+        let target = &mut state.targets[self.index];
+        target.failed_connections = 0;
+        target.unhealthy_until = None;
+    }
+
+    /// Mark the target as unhealthy after a failed connection, and resolve it again before
+    /// its next selection
+    pub(crate) fn failed(self) {
+        let mut state = self.targets.state.lock().unwrap();
+        let backoff = state.unhealthy_target_backoff;
+        let target = &mut state.targets[self.index];
+        target.failed_connections += 1;
+        target.unhealthy_until = Some(Instant::now() + backoff);
+        if target.needs_resolution {
+            target.resolved_at = None;
+        }
+    }
+}
+
+impl Drop for SelectedTarget {
+    fn drop(&mut self) {
+        let mut state = self.targets.state.lock().unwrap();
+        let target = &mut state.targets[self.index];
+        target.active_connections = target.active_connections.saturating_sub(1);
+    }
+}
+
+/// Return true if a target name ends with a port number
+fn has_port(target: &str) -> bool {
+    target
+        .rsplit_once(':')
+        .map(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(load_balancing: TcpOutletLoadBalancing) -> TcpOutletTargets {
+        TcpOutletTargets::new(
+            vec![
+                "127.0.0.1:5000".to_string(),
+                "127.0.0.1:5001".to_string(),
+                "127.0.0.1:5002".to_string(),
+            ],
+            load_balancing,
+            DEFAULT_DNS_REFRESH_INTERVAL,
+            DEFAULT_UNHEALTHY_TARGET_BACKOFF,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn round_robin_selection() {
+        let targets = targets(TcpOutletLoadBalancing::RoundRobin);
+        let selected: Vec<usize> = (0..4)
+            .map(|_| targets.select(&[]).unwrap().index())
+            .collect();
+        assert_eq!(selected, vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn least_connections_selection() {
+        let targets = targets(TcpOutletLoadBalancing::LeastConnections);
+        let first = targets.select(&[]).unwrap();
+        let second = targets.select(&[]).unwrap();
+        assert_eq!((first.index(), second.index()), (0, 1));
+
+        // the first connection is closed, its target has the least connections
+        drop(first);
+        let third = targets.select(&[]).unwrap();
+        assert_eq!(third.index(), 2);
+        let fourth = targets.select(&[]).unwrap();
+        assert_eq!(fourth.index(), 0);
+
+        let status = targets.status();
+        assert_eq!(
+            status
+                .iter()
+                .map(|s| s.active_connections)
+                .collect::<Vec<_>>(),
+            vec![1, 1, 1]
+        );
+    }
+
+    #[test]
+    fn unhealthy_targets_are_skipped() {
+        let targets = targets(TcpOutletLoadBalancing::RoundRobin);
+        targets.select(&[]).unwrap().failed();
+        assert!(!targets.status()[0].healthy);
+        assert_eq!(targets.status()[0].failed_connections, 1);
+
+        let selected: Vec<usize> = (0..3)
+            .map(|_| targets.select(&[]).unwrap().index())
+            .collect();
+        assert_eq!(selected, vec![1, 2, 1]);
+
+        // excluded targets are never selected, unhealthy ones are used as a last resort
+        assert_eq!(targets.select(&[1, 2]).unwrap().index(), 0);
+        assert!(targets.select(&[0, 1, 2]).is_none());
+
+        // a successful connection makes the target healthy again
+        targets.select(&[1, 2]).unwrap().connected();
+        assert!(targets.status()[0].healthy);
+        assert_eq!(targets.status()[0].failed_connections, 0);
+    }
+
+    #[test]
+    fn invalid_targets() {
+        let create = |targets: Vec<&str>| {
+            TcpOutletTargets::new(
+                targets.into_iter().map(|t| t.to_string()).collect(),
+                TcpOutletLoadBalancing::default(),
+                DEFAULT_DNS_REFRESH_INTERVAL,
+                DEFAULT_UNHEALTHY_TARGET_BACKOFF,
+            )
+        };
+        assert!(create(vec![]).is_err());
+        assert!(create(vec!["localhost"]).is_err());
+        assert!(create(vec!["127.0.0.1:5000", "localhost"]).is_err());
+        assert!(create(vec!["127.0.0.1:5000", "localhost:5000"]).is_ok());
+    }
+}
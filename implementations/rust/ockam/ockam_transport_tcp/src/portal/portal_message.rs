@@ -1,4 +1,5 @@
-use ockam_core::Message;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{Decodable, Encodable, Message, Result};
 use serde::{Deserialize, Serialize};
 
 /// A command message type for a Portal
@@ -13,6 +14,44 @@ pub enum PortalMessage {
     Disconnect,
     /// Message with binary payload
     Payload(Vec<u8>),
+    /// Total number of payload bytes written to the TCP connection on the other side,
+    /// which allows more payloads to be sent
+    Credit(u64),
+}
+
+impl PortalMessage {
+    /// Encode a [`PortalMessage::Ping`] or [`PortalMessage::Pong`] followed by the capabilities
+    /// of the portal sending it. Older portals decode the message and ignore its capabilities
+    pub fn encode_with_capabilities(&self, capabilities: &PortalCapabilities) -> Result<Vec<u8>> {
+        let mut encoded = self.encode()?;
+        encoded.extend(capabilities.encode()?);
+        Ok(encoded)
+    }
+
+    /// Decode a message and the capabilities following it.
+    /// Older portals don't send any capabilities, and don't support any of them
+    pub fn decode_with_capabilities(encoded: &[u8]) -> Result<(Self, PortalCapabilities)> {
+        let message = Self::decode(encoded)?;
+        let capabilities = match encoded.get(message.encode()?.len()..) {
+            Some(rest) if !rest.is_empty() => PortalCapabilities::decode(rest)?,
+            _ => PortalCapabilities::default(),
+        };
+        Ok((message, capabilities))
+    }
+}
+
+/// Capabilities advertised by each side of a portal when the portal is established
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PortalCapabilities {
+    /// The portal acknowledges the data it receives with [`PortalMessage::Credit`] messages
+    pub credits: bool,
+}
+
+impl PortalCapabilities {
+    /// Capabilities supported by this implementation
+    pub fn supported() -> Self {
+        Self { credits: true }
+    }
 }
 
 /// An internal message type for a Portal
@@ -24,3 +63,33 @@ pub enum PortalInternalMessage {
 
 ///Maximum allowed size for a payload
 pub const MAX_PAYLOAD_SIZE: usize = 48 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_are_ignored_by_older_portals() -> Result<()> {
+        let encoded =
+            PortalMessage::Ping.encode_with_capabilities(&PortalCapabilities::supported())?;
+        assert!(matches!(
+            PortalMessage::decode(&encoded)?,
+            PortalMessage::Ping
+        ));
+
+        let (message, capabilities) = PortalMessage::decode_with_capabilities(&encoded)?;
+        assert!(matches!(message, PortalMessage::Ping));
+        assert_eq!(capabilities, PortalCapabilities::supported());
+        Ok(())
+    }
+
+    #[test]
+    fn test_older_portals_have_no_capabilities() -> Result<()> {
+        let encoded = PortalMessage::Pong.encode()?;
+        let (message, capabilities) = PortalMessage::decode_with_capabilities(&encoded)?;
+        assert!(matches!(message, PortalMessage::Pong));
+        assert_eq!(capabilities, PortalCapabilities::default());
+        assert!(!capabilities.credits);
+        Ok(())
+    }
+}
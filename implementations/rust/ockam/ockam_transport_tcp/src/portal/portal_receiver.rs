@@ -1,11 +1,15 @@
+use crate::portal::credits::PortalCredits;
+use crate::portal::limits::{ConnectionActivity, ConnectionBandwidth, TcpPortalLimiter};
 use crate::portal::portal_message::MAX_PAYLOAD_SIZE;
+use crate::portal::tls::PortalReadHalf;
 use crate::{PortalInternalMessage, PortalMessage, TcpRegistry};
 use ockam_core::compat::vec::Vec;
 use ockam_core::{async_trait, Encodable, LocalMessage, Route, TransportMessage};
 use ockam_core::{route, Address, Processor, Result};
 use ockam_node::Context;
-use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
-use tracing::{error, warn};
+use tokio::io::AsyncReadExt;
+use tokio::time::timeout;
+use tracing::{error, info, warn};
 
 /// A TCP Portal receiving message processor
 ///
@@ -15,18 +19,25 @@ use tracing::{error, warn};
 pub(crate) struct TcpPortalRecvProcessor {
     registry: TcpRegistry,
     buf: Vec<u8>,
-    read_half: OwnedReadHalf,
+    read_half: PortalReadHalf,
     sender_address: Address,
     onward_route: Route,
+    limiter: Option<TcpPortalLimiter>,
+    bandwidth: Option<ConnectionBandwidth>,
+    activity: ConnectionActivity,
+    credits: PortalCredits,
 }
 
 impl TcpPortalRecvProcessor {
     /// Create a new `TcpPortalRecvProcessor`
     pub fn new(
         registry: TcpRegistry,
-        read_half: OwnedReadHalf,
+        read_half: PortalReadHalf,
         sender_address: Address,
         onward_route: Route,
+        limiter: Option<TcpPortalLimiter>,
+        activity: ConnectionActivity,
+        credits: PortalCredits,
     ) -> Self {
         Self {
             registry,
@@ -34,8 +45,35 @@ impl TcpPortalRecvProcessor {
             read_half,
             sender_address,
             onward_route,
+            bandwidth: limiter.as_ref().map(|l| l.received_bandwidth()),
+            limiter,
+            activity,
+            credits,
         }
     }
+
+    /// Notify the Sender and the other side of the portal that the connection was closed
+    async fn notify_disconnection(&self, ctx: &Context) -> Result<()> {
+        if let Err(err) = ctx
+            .send(
+                route![self.sender_address.clone()],
+                PortalInternalMessage::Disconnect,
+            )
+            .await
+        {
+            warn!(
+                "Error notifying Tcp Portal Sender about dropped connection {}",
+                err
+            );
+        }
+
+        let msg = TransportMessage::v1(
+            self.onward_route.clone(),
+            self.sender_address.clone(),
+            PortalMessage::Disconnect.encode()?,
+        );
+        ctx.forward(LocalMessage::new(msg, vec![])).await
+    }
 }
 
 #[async_trait]
@@ -58,7 +96,36 @@ impl Processor for TcpPortalRecvProcessor {
     async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
         self.buf.clear();
 
-        let _len = match self.read_half.read_buf(&mut self.buf).await {
+        // Stop reading the connection until the other side has acknowledged enough data
+        self.credits.wait().await;
+
+        let idle_timeout = self
+            .limiter
+            .as_ref()
+            .and_then(|l| l.limits().idle_timeout());
+        let read = self.read_half.read_buf(&mut self.buf);
+        let res = match idle_timeout {
+            Some(idle_timeout) => {
+                let remaining = idle_timeout.saturating_sub(self.activity.idle_duration());
+                let res = timeout(remaining, read).await;
+                match res {
+                    Ok(res) => res,
+                    // Data may have been sent to the connection in the meantime
+                    Err(_) if self.activity.idle_duration() < idle_timeout => return Ok(true),
+                    Err(_) => {
+                        info!("Tcp Portal connection was idle for {idle_timeout:?}, closing it");
+                        if let Some(limiter) = &self.limiter {
+                            limiter.idle_disconnection();
+                        }
+                        self.notify_disconnection(ctx).await?;
+                        return Ok(false);
+                    }
+                }
+            }
+            None => read.await,
+        };
+
+        let len = match res {
             Ok(len) => len,
             Err(err) => {
                 error!("Tcp Portal connection read failed with error: {}", err);
@@ -68,29 +135,16 @@ impl Processor for TcpPortalRecvProcessor {
 
         if self.buf.is_empty() {
             // Notify Sender that connection was closed
-            if let Err(err) = ctx
-                .send(
-                    route![self.sender_address.clone()],
-                    PortalInternalMessage::Disconnect,
-                )
-                .await
-            {
-                warn!(
-                    "Error notifying Tcp Portal Sender about dropped connection {}",
-                    err
-                );
-            }
-
-            let msg = TransportMessage::v1(
-                self.onward_route.clone(),
-                self.sender_address.clone(),
-                PortalMessage::Disconnect.encode()?,
-            );
-            ctx.forward(LocalMessage::new(msg, vec![])).await?;
-
+            self.notify_disconnection(ctx).await?;
             return Ok(false);
         }
 
+        self.activity.record();
+        if let Some(bandwidth) = &mut self.bandwidth {
+            bandwidth.consume(len).await;
+        }
+        self.credits.sent(len);
+
         // Loop just in case buf was extended (should not happen though)
         for chunk in self.buf.chunks(MAX_PAYLOAD_SIZE) {
             let msg = TransportMessage::v1(
@@ -1,16 +1,24 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalRecvProcessor, PortalInternalMessage, PortalMessage, TcpRegistry};
+use crate::portal::credits::{PortalAcknowledgements, PortalCredits};
+use crate::portal::limits::{
+    ConnectionActivity, ConnectionBandwidth, ConnectionPermit, TcpPortalLimiter,
+};
+use crate::portal::outlet_targets::SelectedTarget;
+use crate::portal::tls::{split_tcp_stream, PortalReadHalf, PortalWriteHalf};
+use crate::{
+    portal::TcpPortalRecvProcessor, PortalCapabilities, PortalInternalMessage, PortalMessage,
+    TcpInletTls, TcpOutletTargets, TcpOutletTls, TcpRegistry,
+};
 use core::time::Duration;
 use ockam_core::compat::{boxed::Box, net::SocketAddr, sync::Arc};
 use ockam_core::{
     async_trait, AllowAll, AllowOnwardAddresses, AllowSourceAddress, Decodable, DenyAll,
-    IncomingAccessControl, Mailbox, Mailboxes,
+    IncomingAccessControl, Mailbox, Mailboxes, NeutralMessage,
 };
 use ockam_core::{Any, Result, Route, Routed, Worker};
 use ockam_node::{Context, ProcessorBuilder, WorkerBuilder};
 use ockam_transport_core::TransportError;
 use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
 use tracing::{debug, info, trace, warn};
 
@@ -37,9 +45,26 @@ enum State {
 pub(crate) struct TcpPortalWorker {
     registry: TcpRegistry,
     state: State,
-    write_half: Option<OwnedWriteHalf>,
-    read_half: Option<OwnedReadHalf>,
-    peer: SocketAddr,
+    // connection accepted by an Inlet, split once the TLS handshake is done if needed
+    stream: Option<TcpStream>,
+    write_half: Option<PortalWriteHalf>,
+    read_half: Option<PortalReadHalf>,
+    inlet_tls: Option<TcpInletTls>,
+    outlet_tls: Option<TcpOutletTls>,
+    peer: Option<SocketAddr>,
+    // targets which can be selected by an Outlet, and the target it is connected to
+    outlet_targets: Option<TcpOutletTargets>,
+    outlet_target: Option<SelectedTarget>,
+    // limits shared by all the connections of the portal, and the permit of this connection
+    limiter: Option<TcpPortalLimiter>,
+    connection_permit: Option<ConnectionPermit>,
+    sent_bandwidth: Option<ConnectionBandwidth>,
+    activity: ConnectionActivity,
+    // credits of the data sent to the other side, and acknowledgements of the data received
+    credits: PortalCredits,
+    acknowledgements: PortalAcknowledgements,
+    // capabilities advertised by the other side with its ping or pong
+    their_capabilities: PortalCapabilities,
     addresses: Addresses,
     remote_route: Option<Route>,
     is_disconnecting: bool,
@@ -48,6 +73,7 @@ pub(crate) struct TcpPortalWorker {
 
 impl TcpPortalWorker {
     /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`]
+    #[allow(clippy::too_many_arguments)]
     pub(super) async fn start_new_inlet(
         ctx: &Context,
         registry: TcpRegistry,
@@ -56,13 +82,24 @@ impl TcpPortalWorker {
         ping_route: Route,
         addresses: Addresses,
         access_control: Arc<dyn IncomingAccessControl>,
+        tls: Option<TcpInletTls>,
+        limiter: Option<TcpPortalLimiter>,
+        connection_permit: Option<ConnectionPermit>,
+        credit_window: u64,
     ) -> Result<()> {
         Self::start(
             ctx,
             registry,
-            peer,
+            Some(peer),
+            None,
             State::SendPing { ping_route },
             Some(stream),
+            tls,
+            None,
+            limiter,
+            connection_permit,
+            credit_window,
+            PortalCapabilities::default(),
             addresses,
             PortalType::Inlet,
             access_control,
@@ -71,20 +108,32 @@ impl TcpPortalWorker {
     }
 
     /// Start a new `TcpPortalWorker` of type [`TypeName::Outlet`]
+    #[allow(clippy::too_many_arguments)]
     pub(super) async fn start_new_outlet(
         ctx: &Context,
         registry: TcpRegistry,
-        peer: SocketAddr,
+        targets: TcpOutletTargets,
         pong_route: Route,
         addresses: Addresses,
         access_control: Arc<dyn IncomingAccessControl>,
+        tls: Option<TcpOutletTls>,
+        limiter: Option<TcpPortalLimiter>,
+        credit_window: u64,
+        their_capabilities: PortalCapabilities,
     ) -> Result<()> {
         Self::start(
             ctx,
             registry,
-            peer,
+            None,
+            Some(targets),
             State::SendPong { pong_route },
             None,
+            None,
+            tls,
+            limiter,
+            None,
+            credit_window,
+            their_capabilities,
             addresses,
             PortalType::Outlet,
             access_control,
@@ -97,9 +146,16 @@ impl TcpPortalWorker {
     async fn start(
         ctx: &Context,
         registry: TcpRegistry,
-        peer: SocketAddr,
+        peer: Option<SocketAddr>,
+        outlet_targets: Option<TcpOutletTargets>,
         state: State,
         stream: Option<TcpStream>,
+        inlet_tls: Option<TcpInletTls>,
+        outlet_tls: Option<TcpOutletTls>,
+        limiter: Option<TcpPortalLimiter>,
+        connection_permit: Option<ConnectionPermit>,
+        credit_window: u64,
+        their_capabilities: PortalCapabilities,
         addresses: Addresses,
         portal_type: PortalType,
         access_control: Arc<dyn IncomingAccessControl>,
@@ -111,20 +167,24 @@ impl TcpPortalWorker {
             addresses.remote
         );
 
-        let (rx, tx) = match stream {
-            Some(s) => {
-                let (rx, tx) = s.into_split();
-                (Some(rx), Some(tx))
-            }
-            None => (None, None),
-        };
-
         let worker = Self {
             registry,
             state,
-            write_half: tx,
-            read_half: rx,
+            stream,
+            write_half: None,
+            read_half: None,
+            inlet_tls,
+            outlet_tls,
             peer,
+            outlet_targets,
+            outlet_target: None,
+            sent_bandwidth: limiter.as_ref().map(|l| l.sent_bandwidth()),
+            limiter,
+            connection_permit,
+            activity: ConnectionActivity::new(),
+            credits: PortalCredits::new(credit_window),
+            acknowledgements: PortalAcknowledgements::default(),
+            their_capabilities,
             addresses: addresses.clone(),
             remote_route: None,
             is_disconnecting: false,
@@ -173,6 +233,9 @@ impl TcpPortalWorker {
                 rx,
                 self.addresses.internal.clone(),
                 onward_route,
+                self.limiter.clone(),
+                self.activity.clone(),
+                self.credits.clone(),
             );
 
             ProcessorBuilder::new(receiver)
@@ -272,11 +335,28 @@ impl TcpPortalWorker {
         Ok(())
     }
 
+    /// Send the total number of bytes written to the TCP connection to the other side.
+    /// An initial acknowledgement is sent once the portal is established, so that the other
+    /// side knows that it can use credits. Nothing is sent to portals which don't support credits
+    async fn send_credit(&self, ctx: &Context, route: Route, written: u64) -> Result<()> {
+        if !self.their_capabilities.credits {
+            return Ok(());
+        }
+        ctx.send_from_address(
+            route,
+            PortalMessage::Credit(written),
+            self.addresses.remote.clone(),
+        )
+        .await
+    }
+
     async fn handle_send_ping(&self, ctx: &Context, ping_route: Route) -> Result<State> {
         // Force creation of Outlet on the other side
         ctx.send_from_address(
             ping_route,
-            PortalMessage::Ping,
+            NeutralMessage::from(
+                PortalMessage::Ping.encode_with_capabilities(&PortalCapabilities::supported())?,
+            ),
             self.addresses.remote.clone(),
         )
         .await?;
@@ -286,20 +366,103 @@ impl TcpPortalWorker {
         Ok(State::ReceivePong)
     }
 
+    /// Split the connection accepted by an Inlet, after a TLS handshake if the Inlet terminates TLS
+    async fn accept_client(&mut self) -> Result<()> {
+        let stream = self
+            .stream
+            .take()
+            .ok_or(TransportError::PortalInvalidState)?;
+        let (rx, tx) = match &self.inlet_tls {
+            Some(tls) => tls.accept(stream).await?,
+            None => split_tcp_stream(stream),
+        };
+        self.read_half = Some(rx);
+        self.write_half = Some(tx);
+        Ok(())
+    }
+
+    /// Connect an Outlet to one of its targets. When a connection fails, another target is tried
+    async fn connect_to_target(&mut self) -> Result<(PortalReadHalf, PortalWriteHalf)> {
+        let targets = match &self.outlet_targets {
+            Some(targets) => targets.clone(),
+            None => return Err(TransportError::PortalInvalidState.into()),
+        };
+        targets.refresh().await;
+
+        let mut tried = vec![];
+        let mut last_error: ockam_core::Error = TransportError::InvalidAddress.into();
+        while let Some(target) = targets.select(&tried) {
+            let connection = match TcpStream::connect(target.address()).await {
+                Ok(stream) => match &self.outlet_tls {
+                    Some(tls) => tls.connect(stream, &target.target()).await,
+                    None => Ok(split_tcp_stream(stream)),
+                },
+                Err(err) => Err(TransportError::from(err).into()),
+            };
+
+            match connection {
+                Ok(halves) => {
+                    target.connected();
+                    self.peer = Some(target.address());
+                    self.outlet_target = Some(target);
+                    return Ok(halves);
+                }
+                Err(err) => {
+                    warn!(
+                        "Outlet at: {} failed to connect to {}: {}",
+                        self.addresses.internal,
+                        target.address(),
+                        err
+                    );
+                    tried.push(target.index());
+                    target.failed();
+                    last_error = err;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Return false if the Outlet already has too many connections
+    fn acquire_connection(&mut self) -> bool {
+        match &self.limiter {
+            Some(limiter) => {
+                self.connection_permit = limiter.acquire_connection();
+                self.connection_permit.is_some()
+            }
+            None => true,
+        }
+    }
+
+    /// Make the Inlet close the connection of its client, and stop this Outlet
+    async fn reject_connection(&self, ctx: &Context, pong_route: Route) -> Result<()> {
+        warn!(
+            "Outlet at: {} has too many connections, the connection is rejected",
+            self.addresses.internal
+        );
+        ctx.send_from_address(
+            pong_route,
+            PortalMessage::Disconnect,
+            self.addresses.remote.clone(),
+        )
+        .await?;
+        ctx.stop_worker(self.addresses.internal.clone()).await
+    }
+
     async fn handle_send_pong(&mut self, ctx: &Context, pong_route: Route) -> Result<State> {
         // Respond to Inlet
         ctx.send_from_address(
             pong_route.clone(),
-            PortalMessage::Pong,
+            NeutralMessage::from(
+                PortalMessage::Pong.encode_with_capabilities(&PortalCapabilities::supported())?,
+            ),
             self.addresses.remote.clone(),
         )
         .await?;
 
         if self.write_half.is_none() {
-            let stream = TcpStream::connect(self.peer)
-                .await
-                .map_err(TransportError::from)?;
-            let (rx, tx) = stream.into_split();
+            let (rx, tx) = self.connect_to_target().await?;
             self.write_half = Some(tx);
             self.read_half = Some(rx);
 
@@ -313,6 +476,7 @@ impl TcpPortalWorker {
 
         debug!("Outlet at: {} sent pong", self.addresses.internal);
 
+        self.send_credit(ctx, pong_route.clone(), 0).await?;
         self.remote_route = Some(pong_route);
         Ok(State::Initialized)
     }
@@ -328,9 +492,13 @@ impl Worker for TcpPortalWorker {
 
         match state {
             State::SendPing { ping_route } => {
+                self.accept_client().await?;
                 self.state = self.handle_send_ping(ctx, ping_route.clone()).await?;
             }
             State::SendPong { pong_route } => {
+                if !self.acquire_connection() {
+                    return self.reject_connection(ctx, pong_route).await;
+                }
                 self.state = self.handle_send_pong(ctx, pong_route.clone()).await?;
             }
             State::ReceivePong | State::Initialized { .. } => {
@@ -375,17 +543,30 @@ impl Worker for TcpPortalWorker {
                     return Err(TransportError::PortalInvalidState.into());
                 }
 
-                let msg = PortalMessage::decode(msg.payload())?;
-
-                if let PortalMessage::Pong = msg {
-                } else {
-                    return Err(TransportError::Protocol.into());
+                let (msg, their_capabilities) =
+                    PortalMessage::decode_with_capabilities(msg.payload())?;
+
+                match msg {
+                    PortalMessage::Pong => self.their_capabilities = their_capabilities,
+                    PortalMessage::Disconnect => {
+                        // The Outlet rejected the connection
+                        info!(
+                            "Inlet at: {} was disconnected before receiving pong",
+                            self.addresses.internal
+                        );
+                        return self
+                            .start_disconnection(ctx, DisconnectionReason::Remote)
+                            .await;
+                    }
+                    _ => return Err(TransportError::Protocol.into()),
                 }
 
                 self.start_receiver(ctx, return_route.clone()).await?;
 
                 debug!("Inlet at: {} received pong", self.addresses.internal);
 
+                self.send_credit(ctx, return_route.clone(), 0).await?;
+
                 self.remote_route = Some(return_route);
                 self.state = State::Initialized;
             }
@@ -422,12 +603,30 @@ impl Worker for TcpPortalWorker {
 
                     match msg {
                         PortalMessage::Payload(payload) => {
+                            self.activity.record();
+                            if let Some(bandwidth) = &mut self.sent_bandwidth {
+                                bandwidth.consume(payload.len()).await;
+                            }
                             if let Some(tx) = &mut self.write_half {
-                                match tx.write_all(&payload).await {
-                                    Ok(()) => {}
+                                let res = match tx.write_all(&payload).await {
+                                    Ok(()) => tx.flush().await,
+                                    Err(err) => Err(err),
+                                };
+                                match res {
+                                    Ok(()) => {
+                                        // The payloads come from the internal address of
+                                        // the other side, so acknowledgements are sent to
+                                        // its remote address
+                                        let written = self.acknowledgements.written(payload.len());
+                                        if let (Some(written), Some(remote_route)) =
+                                            (written, self.remote_route.clone())
+                                        {
+                                            self.send_credit(ctx, remote_route, written).await?;
+                                        }
+                                    }
                                     Err(err) => {
                                         warn!(
-                                            "Failed to send message to peer {} with error: {}",
+                                            "Failed to send message to peer {:?} with error: {}",
                                             self.peer, err
                                         );
                                         self.start_disconnection(
@@ -441,6 +640,12 @@ impl Worker for TcpPortalWorker {
                                 return Err(TransportError::PortalInvalidState.into());
                             }
                         }
+                        PortalMessage::Credit(acknowledged) => {
+                            // The other side acknowledges our data, so it expects
+                            // acknowledgements for its own data
+                            self.acknowledgements.enable();
+                            self.credits.acknowledge(acknowledged);
+                        }
                         PortalMessage::Disconnect => {
                             self.start_disconnection(ctx, DisconnectionReason::Remote)
                                 .await?;
@@ -0,0 +1,306 @@
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_transport_core::TransportError;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::{
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Maximum duration of a TLS handshake with an Inlet client or an Outlet target
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reading half of a portal connection, which is encrypted with TLS or not
+pub(crate) type PortalReadHalf = Box<dyn AsyncRead + Send + Sync + Unpin>;
+
+/// Writing half of a portal connection, which is encrypted with TLS or not
+pub(crate) type PortalWriteHalf = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+
+/// Split a plaintext portal connection
+pub(crate) fn split_tcp_stream(stream: TcpStream) -> (PortalReadHalf, PortalWriteHalf) {
+    let (rx, tx) = stream.into_split();
+    (Box::new(rx), Box::new(tx))
+}
+
+fn split_tls_stream<S>(stream: S) -> (PortalReadHalf, PortalWriteHalf)
+where
+    S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+{
+    let (rx, tx) = tokio::io::split(stream);
+    (Box::new(rx), Box::new(tx))
+}
+
+/// TLS termination for an Inlet: the clients of the Inlet connect with TLS, using
+/// a certificate served by the node, so that their data is never sent in plaintext
+/// between the clients and the node
+#[derive(Clone)]
+pub struct TcpInletTls {
+    acceptor: TlsAcceptor,
+}
+
+impl TcpInletTls {
+    /// Create a TLS configuration from a PEM-encoded certificate chain and its private key
+    pub fn from_pem(certificate_chain: &[u8], private_key: &[u8]) -> Result<Self> {
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                parse_certificates(certificate_chain)?,
+                parse_private_key(private_key)?,
+            )
+            .map_err(tls_config_error)?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    /// Create a TLS configuration from PEM files containing a certificate chain and its private key
+    pub fn from_pem_files(
+        certificate_chain_path: impl AsRef<Path>,
+        private_key_path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        Self::from_pem(
+            &read_file(certificate_chain_path)?,
+            &read_file(private_key_path)?,
+        )
+    }
+
+    /// Accept a TLS connection from an Inlet client
+    pub(crate) async fn accept(
+        &self,
+        stream: TcpStream,
+    ) -> Result<(PortalReadHalf, PortalWriteHalf)> {
+        let stream = timeout(TLS_HANDSHAKE_TIMEOUT, self.acceptor.accept(stream))
+            .await
+            .map_err(|_| tls_handshake_timeout())?
+            .map_err(TransportError::from)?;
+        Ok(split_tls_stream(stream))
+    }
+}
+
+/// TLS origination for an Outlet: the Outlet connects to its targets with TLS, so that the data
+/// is never sent in plaintext between the node and the targets.
+///
+/// The targets are verified with the native root certificates, unless a CA bundle is given.
+/// The server name sent with SNI, and verified in the target certificate, is the host of the
+/// target unless another name is given.
+#[derive(Clone)]
+pub struct TcpOutletTls {
+    root_certificates: RootCertStore,
+    client_certificate: Option<(Vec<Certificate>, PrivateKey)>,
+    server_name: Option<String>,
+    connector: TlsConnector,
+}
+
+impl TcpOutletTls {
+    /// Create a TLS configuration verifying the targets with the native root certificates
+    pub fn new() -> Result<Self> {
+        let mut root_certificates = RootCertStore::empty();
+        let native_certificates = rustls_native_certs::load_native_certs()
+            .map_err(|e| tls_config_error(format!("cannot load the native certificates: {e}")))?;
+        for certificate in native_certificates {
+            // Some native certificates might not be supported, they are skipped
+            let _ = root_certificates.add(&Certificate(certificate.0));
+        }
+        Self::build(root_certificates, None, None)
+    }
+
+    /// Verify the targets with the PEM-encoded CA certificates of a bundle, instead of the
+    /// native root certificates
+    pub fn with_ca_certificates(self, ca_certificates: &[u8]) -> Result<Self> {
+        let mut root_certificates = RootCertStore::empty();
+        for certificate in parse_certificates(ca_certificates)? {
+            root_certificates
+                .add(&certificate)
+                .map_err(tls_config_error)?;
+        }
+        Self::build(root_certificates, self.client_certificate, self.server_name)
+    }
+
+    /// Verify the targets with the CA certificates of a PEM file
+    pub fn with_ca_certificates_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        self.with_ca_certificates(&read_file(path)?)
+    }
+
+    /// Authenticate the Outlet to its targets with a PEM-encoded client certificate chain
+    /// and its private key
+    pub fn with_client_certificate(
+        self,
+        certificate_chain: &[u8],
+        private_key: &[u8],
+    ) -> Result<Self> {
+        let client_certificate = (
+            parse_certificates(certificate_chain)?,
+            parse_private_key(private_key)?,
+        );
+        Self::build(
+            self.root_certificates,
+            Some(client_certificate),
+            self.server_name,
+        )
+    }
+
+    /// Authenticate the Outlet to its targets with the client certificate chain and the
+    /// private key of PEM files
+    pub fn with_client_certificate_files(
+        self,
+        certificate_chain_path: impl AsRef<Path>,
+        private_key_path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        self.with_client_certificate(
+            &read_file(certificate_chain_path)?,
+            &read_file(private_key_path)?,
+        )
+    }
+
+    /// Use the same server name for all the targets, instead of their host
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Result<Self> {
+        let server_name = server_name.into();
+        ServerName::try_from(server_name.as_str()).map_err(tls_config_error)?;
+        self.server_name = Some(server_name);
+        Ok(self)
+    }
+
+    fn build(
+        root_certificates: RootCertStore,
+        client_certificate: Option<(Vec<Certificate>, PrivateKey)>,
+        server_name: Option<String>,
+    ) -> Result<Self> {
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_certificates.clone());
+        let config = match client_certificate.clone() {
+            Some((certificates, key)) => builder
+                .with_client_auth_cert(certificates, key)
+                .map_err(tls_config_error)?,
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(Self {
+            root_certificates,
+            client_certificate,
+            server_name,
+            connector: TlsConnector::from(Arc::new(config)),
+        })
+    }
+
+    /// Start a TLS connection to a target, given as a socket address or a `host:port` name
+    pub(crate) async fn connect(
+        &self,
+        stream: TcpStream,
+        target: &str,
+    ) -> Result<(PortalReadHalf, PortalWriteHalf)> {
+        let server_name = match &self.server_name {
+            Some(server_name) => server_name.clone(),
+            None => target_host(target),
+        };
+        let server_name = ServerName::try_from(server_name.as_str())
+            .map_err(|_| TransportError::InvalidAddress)?;
+
+        let stream = timeout(
+            TLS_HANDSHAKE_TIMEOUT,
+            self.connector.connect(server_name, stream),
+        )
+        .await
+        .map_err(|_| tls_handshake_timeout())?
+        .map_err(TransportError::from)?;
+        Ok(split_tls_stream(stream))
+    }
+}
+
+impl core::fmt::Debug for TcpInletTls {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TcpInletTls").finish()
+    }
+}
+
+impl core::fmt::Debug for TcpOutletTls {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TcpOutletTls")
+            .field("server_name", &self.server_name)
+            .field("client_certificate", &self.client_certificate.is_some())
+            .finish()
+    }
+}
+
+/// Return the host of a target given as a socket address or a `host:port` name
+fn target_host(target: &str) -> String {
+    let host = target
+        .rsplit_once(':')
+        .map(|(host, _)| host)
+        .unwrap_or(target);
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string()
+}
+
+fn parse_certificates(pem: &[u8]) -> Result<Vec<Certificate>> {
+    let certificates: Vec<Certificate> = rustls_pemfile::certs(&mut &*pem)
+        .map_err(|_| tls_config_error("invalid PEM certificates"))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certificates.is_empty() {
+        return Err(tls_config_error("no certificate found"));
+    }
+    Ok(certificates)
+}
+
+fn parse_private_key(pem: &[u8]) -> Result<PrivateKey> {
+    let items =
+        rustls_pemfile::read_all(&mut &*pem).map_err(|_| tls_config_error("invalid PEM key"))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| tls_config_error("no private key found"))
+}
+
+fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    std::fs::read(path)
+        .map_err(|e| tls_config_error(format!("cannot read {}: {e}", path.display())))
+}
+
+fn tls_config_error(e: impl core::fmt::Display) -> Error {
+    Error::new(
+        Origin::Transport,
+        Kind::Invalid,
+        format!("invalid TLS configuration: {e}"),
+    )
+}
+
+fn tls_handshake_timeout() -> Error {
+    Error::new(Origin::Transport, Kind::Timeout, "TLS handshake timed out")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_host() {
+        assert_eq!(target_host("example.com:443"), "example.com");
+        assert_eq!(target_host("127.0.0.1:443"), "127.0.0.1");
+        assert_eq!(target_host("[::1]:443"), "::1");
+    }
+
+    #[test]
+    fn test_invalid_pem() {
+        assert!(TcpInletTls::from_pem(b"not a certificate", b"not a key").is_err());
+        assert!(parse_certificates(b"").is_err());
+        assert!(parse_private_key(b"").is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use crate::portal::TcpInletListenProcessor;
-use crate::transport::common::{parse_socket_addr, resolve_peer};
-use crate::{portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpTransport};
+use crate::transport::common::parse_socket_addr;
+use crate::{
+    portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpOutletTargets,
+    TcpTransport,
+};
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::{Address, Result, Route};
 
@@ -82,18 +85,58 @@ impl TcpTransport {
         peer: impl Into<String>,
         options: TcpOutletOptions,
     ) -> Result<()> {
-        // Resolve peer address
-        let peer_addr = resolve_peer(peer.into())?;
+        self.create_outlet_with_targets(address, vec![peer.into()], options)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Create Tcp Outlet Listener at address, that connects to one of the given targets for each
+    /// new connection. A target is either a socket address or a `host:port` name which is resolved
+    /// again periodically. The returned [`TcpOutletTargets`] gives the status of each target.
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpOutletLoadBalancing, TcpOutletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{AllowAll, Result};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    ///
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let options =
+    ///     TcpOutletOptions::new().with_load_balancing(TcpOutletLoadBalancing::LeastConnections);
+    /// let targets = tcp
+    ///     .create_outlet_with_targets(
+    ///         "outlet",
+    ///         vec!["localhost:9000".to_string(), "localhost:9001".to_string()],
+    ///         options,
+    ///     )
+    ///     .await?;
+    /// println!("{:?}", targets.status());
+    /// # tcp.stop_outlet("outlet").await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_outlet_with_targets(
+        &self,
+        address: impl Into<Address>,
+        targets: Vec<String>,
+        options: TcpOutletOptions,
+    ) -> Result<TcpOutletTargets> {
+        let targets = TcpOutletTargets::new(
+            targets,
+            options.load_balancing,
+            options.dns_refresh_interval,
+            options.unhealthy_target_backoff,
+        )?;
         TcpOutletListenWorker::start(
             &self.ctx,
             self.registry.clone(),
             address.into(),
-            peer_addr,
+            targets.clone(),
             options,
         )
         .await?;
 
-        Ok(())
+        Ok(targets)
     }
 
     /// Create Tcp Outlet Listener at address, that connects to peer using Tcp
@@ -103,7 +146,7 @@ impl TcpTransport {
         peer: SocketAddr,
         options: TcpOutletOptions,
     ) -> Result<()> {
-        TcpOutletListenWorker::start(&self.ctx, self.registry.clone(), address, peer, options)
+        self.create_outlet_with_targets(address, vec![peer.to_string()], options)
             .await?;
 
         Ok(())
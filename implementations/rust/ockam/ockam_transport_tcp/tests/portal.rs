@@ -4,10 +4,11 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 
 use ockam_core::compat::rand::random;
-use ockam_core::{route, Result};
+use ockam_core::{route, AllowAll, Decodable, NeutralMessage, Result};
 use ockam_node::Context;
 use ockam_transport_tcp::{
-    TcpConnectionOptions, TcpInletOptions, TcpListenerOptions, TcpOutletOptions, TcpTransport,
+    PortalMessage, TcpConnectionOptions, TcpInletOptions, TcpListenerOptions, TcpOutletOptions,
+    TcpPortalLimiter, TcpPortalLimits, TcpTransport,
 };
 
 const LENGTH: usize = 32;
@@ -238,3 +239,303 @@ async fn portal__tcp_connection_with_invalid_message_flow__should_not_succeed(
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__several_targets__should_skip_unavailable_target(ctx: &mut Context) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+
+    // Nothing listens on that address anymore
+    let unavailable = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let unavailable_address = unavailable.local_addr().unwrap().to_string();
+    drop(unavailable);
+
+    let listener1 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let listener2 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let targets = tcp
+        .create_outlet_with_targets(
+            "outlet",
+            vec![
+                unavailable_address,
+                listener1.local_addr().unwrap().to_string(),
+                listener2.local_addr().unwrap().to_string(),
+            ],
+            TcpOutletOptions::new(),
+        )
+        .await?;
+
+    let (inlet_socket_addr, _) = tcp
+        .create_inlet("127.0.0.1:0", route!["outlet"], TcpInletOptions::new())
+        .await?;
+
+    // Each available target accepts one connection
+    let handle1 = tokio::spawn(async move {
+        let (mut stream, _) = listener1.accept().await.unwrap();
+        write_binary(&mut stream, payload1).await;
+        stream
+    });
+    let handle2 = tokio::spawn(async move {
+        let (mut stream, _) = listener2.accept().await.unwrap();
+        write_binary(&mut stream, payload2).await;
+        stream
+    });
+
+    let mut stream1 = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    read_assert_binary(&mut stream1, payload1).await;
+    let mut stream2 = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    read_assert_binary(&mut stream2, payload2).await;
+
+    let _upstream1 = handle1.await.unwrap();
+    let _upstream2 = handle2.await.unwrap();
+
+    let status = targets.status();
+    assert!(!status[0].healthy);
+    assert_eq!(status[0].failed_connections, 1);
+    assert_eq!(status[0].active_connections, 0);
+    assert!(status[1].healthy);
+    assert_eq!(status[1].active_connections, 1);
+    assert!(status[2].healthy);
+    assert_eq!(status[2].active_connections, 1);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5000)]
+async fn portal__max_connections__should_close_additional_connections(
+    ctx: &mut Context,
+) -> Result<()> {
+    let payload = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        listener.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new(),
+    )
+    .await?;
+
+    let limiter = TcpPortalLimiter::new(TcpPortalLimits::new().with_max_connections(1));
+    let (inlet_socket_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_limiter(limiter.clone()),
+        )
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        write_binary(&mut stream, payload).await;
+        stream
+    });
+
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    read_assert_binary(&mut stream, payload).await;
+    let _upstream = handle.await.unwrap();
+
+    // The second connection is closed by the inlet
+    let mut rejected = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    let mut buf = [0u8; LENGTH];
+    assert_eq!(rejected.read(&mut buf).await.unwrap(), 0);
+
+    let status = limiter.status();
+    assert_eq!(status.active_connections, 1);
+    assert_eq!(status.rejected_connections, 1);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 10000)]
+async fn portal__idle_timeout__should_close_connection(ctx: &mut Context) -> Result<()> {
+    let payload = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        listener.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new(),
+    )
+    .await?;
+
+    let limiter =
+        TcpPortalLimiter::new(TcpPortalLimits::new().with_idle_timeout(Duration::from_millis(500)));
+    let (inlet_socket_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_limiter(limiter.clone()),
+        )
+        .await?;
+
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    let (mut upstream, _) = listener.accept().await.unwrap();
+    write_binary(&mut upstream, payload).await;
+    read_assert_binary(&mut stream, payload).await;
+
+    // Nothing is sent anymore, the connection is closed on both ends
+    let mut buf = [0u8; LENGTH];
+    assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    assert_eq!(upstream.read(&mut buf).await.unwrap(), 0);
+    assert_eq!(limiter.status().idle_disconnections, 1);
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__large_transfer_with_small_credit_window__should_succeed(
+    ctx: &mut Context,
+) -> Result<()> {
+    let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        listener.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new().with_credit_window(0),
+    )
+    .await?;
+    let (inlet_socket_addr, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_credit_window(0),
+        )
+        .await?;
+
+    // The data is sent in both directions at the same time
+    let expected = payload.clone();
+    let handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (mut rx, mut tx) = stream.into_split();
+        let to_send = expected.clone();
+        // the write half is kept open, since closing it closes the portal
+        let writer = tokio::spawn(async move {
+            tx.write_all(&to_send).await.unwrap();
+            tx
+        });
+        let mut received = vec![0u8; expected.len()];
+        rx.read_exact(&mut received).await.unwrap();
+        let _tx = writer.await.unwrap();
+        assert_eq!(received, expected);
+    });
+
+    let stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    let (mut rx, mut tx) = stream.into_split();
+    let to_send = payload.clone();
+    // the write half is kept open, since closing it closes the portal
+    let writer = tokio::spawn(async move {
+        tx.write_all(&to_send).await.unwrap();
+        tx
+    });
+    let mut received = vec![0u8; payload.len()];
+    rx.read_exact(&mut received).await.unwrap();
+    let _tx = writer.await.unwrap();
+    assert_eq!(received, payload);
+    handle.await.unwrap();
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5000)]
+async fn portal__inlet_with_older_outlet__should_not_send_credits(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+    // an outlet which doesn't know about capabilities and credits
+    let mut old_outlet = ctx.new_detached("old_outlet", AllowAll, AllowAll).await?;
+    let (inlet_socket_addr, _) = tcp
+        .create_inlet("127.0.0.1:0", route!["old_outlet"], TcpInletOptions::new())
+        .await?;
+
+    let payload = generate_binary();
+    let mut stream = TcpStream::connect(inlet_socket_addr).await.unwrap();
+    write_binary(&mut stream, payload).await;
+
+    let ping = old_outlet.receive::<NeutralMessage>().await?;
+    assert!(matches!(
+        PortalMessage::decode(ping.payload())?,
+        PortalMessage::Ping
+    ));
+    old_outlet
+        .send(ping.return_route(), PortalMessage::Pong)
+        .await?;
+
+    // the initial credit is not sent to an outlet which doesn't support credits
+    let msg = old_outlet.receive::<NeutralMessage>().await?;
+    match PortalMessage::decode(msg.payload())? {
+        PortalMessage::Payload(received) => assert_eq!(received, payload),
+        _ => panic!("a payload was expected"),
+    }
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5000)]
+async fn portal__outlet_with_older_inlet__should_not_send_credits(ctx: &mut Context) -> Result<()> {
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let payload = generate_binary();
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        write_binary(&mut stream, payload).await;
+        stream
+    });
+
+    // an inlet which doesn't know about capabilities and credits
+    let mut old_inlet = ctx.new_detached("old_inlet", AllowAll, AllowAll).await?;
+    old_inlet
+        .send(route!["outlet"], PortalMessage::Ping)
+        .await?;
+    let pong = old_inlet.receive::<NeutralMessage>().await?;
+    assert!(matches!(
+        PortalMessage::decode(pong.payload())?,
+        PortalMessage::Pong
+    ));
+
+    // the initial credit is not sent to an inlet which doesn't support credits
+    let msg = old_inlet.receive::<NeutralMessage>().await?;
+    match PortalMessage::decode(msg.payload())? {
+        PortalMessage::Payload(received) => assert_eq!(received, payload),
+        _ => panic!("a payload was expected"),
+    }
+    let _stream = handle.await.unwrap();
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
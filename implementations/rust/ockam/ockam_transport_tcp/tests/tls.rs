@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::{
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+use ockam_core::{route, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::{
+    TcpInletOptions, TcpInletTls, TcpOutletOptions, TcpOutletTls, TcpTransport,
+};
+
+struct TestCertificate {
+    certificate_pem: String,
+    private_key_pem: String,
+    certificate: Certificate,
+    private_key: PrivateKey,
+}
+
+fn generate_certificate() -> TestCertificate {
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    TestCertificate {
+        certificate_pem: certificate.serialize_pem().unwrap(),
+        private_key_pem: certificate.serialize_private_key_pem(),
+        certificate: Certificate(certificate.serialize_der().unwrap()),
+        private_key: PrivateKey(certificate.serialize_private_key_der()),
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__tls_on_both_ends__should_succeed(ctx: &mut Context) -> Result<()> {
+    let certificate = generate_certificate();
+    let tcp = TcpTransport::create(ctx).await?;
+
+    // The target only accepts TLS connections
+    let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_port = target.local_addr().unwrap().port();
+    let server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![certificate.certificate.clone()],
+            certificate.private_key.clone(),
+        )
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+    let handle = tokio::spawn(async move {
+        let (stream, _) = target.accept().await.unwrap();
+        let mut stream = acceptor.accept(stream).await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(&buf).await.unwrap();
+        stream.flush().await.unwrap();
+    });
+
+    // The outlet originates TLS to the target and the inlet terminates TLS for its clients
+    let outlet_tls =
+        TcpOutletTls::new()?.with_ca_certificates(certificate.certificate_pem.as_bytes())?;
+    tcp.create_outlet(
+        "outlet",
+        format!("localhost:{target_port}"),
+        TcpOutletOptions::new().with_tls(outlet_tls),
+    )
+    .await?;
+    let inlet_tls = TcpInletTls::from_pem(
+        certificate.certificate_pem.as_bytes(),
+        certificate.private_key_pem.as_bytes(),
+    )?;
+    let (inlet_address, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_tls(inlet_tls),
+        )
+        .await?;
+
+    let mut roots = RootCertStore::empty();
+    roots.add(&certificate.certificate).unwrap();
+    let client_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = TlsConnector::from(Arc::new(client_config));
+    let stream = TcpStream::connect(inlet_address).await.unwrap();
+    let mut stream = connector
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+        .unwrap();
+
+    stream.write_all(b"hello").await.unwrap();
+    stream.flush().await.unwrap();
+    let mut buf = [0u8; 5];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+
+    handle.await.unwrap();
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__plaintext_client_on_tls_inlet__should_not_reach_outlet(
+    ctx: &mut Context,
+) -> Result<()> {
+    let certificate = generate_certificate();
+    let tcp = TcpTransport::create(ctx).await?;
+
+    let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        target.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new(),
+    )
+    .await?;
+    let inlet_tls = TcpInletTls::from_pem(
+        certificate.certificate_pem.as_bytes(),
+        certificate.private_key_pem.as_bytes(),
+    )?;
+    let (inlet_address, _) = tcp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            TcpInletOptions::new().with_tls(inlet_tls),
+        )
+        .await?;
+
+    // A plaintext client fails the TLS handshake, and no connection is made to the target
+    let mut stream = TcpStream::connect(inlet_address).await.unwrap();
+    stream.write_all(b"not a tls client hello").await.unwrap();
+    let accepted = tokio::time::timeout(std::time::Duration::from_secs(1), target.accept()).await;
+    assert!(accepted.is_err());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
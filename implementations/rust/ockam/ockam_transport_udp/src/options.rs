@@ -0,0 +1,95 @@
+use core::time::Duration;
+
+/// Default maximum size of the payload of a datagram sent in reliable mode.
+/// It keeps the datagrams below the usual path MTU
+pub const DEFAULT_UDP_FRAGMENT_SIZE: usize = 1200;
+
+/// Default maximum size of an encoded message sent in reliable mode
+pub const DEFAULT_UDP_MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// Default duration after which an unacknowledged datagram is sent again
+pub const DEFAULT_UDP_RETRANSMISSION_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Default number of times a datagram is sent again before the peer is considered unreachable
+pub const DEFAULT_UDP_MAX_RETRANSMISSIONS: u32 = 10;
+
+/// Default maximum number of unacknowledged datagrams sent to a peer
+pub const DEFAULT_UDP_SEND_WINDOW: usize = 256;
+
+/// Options of a UDP Transport
+///
+/// By default each message is sent as a single datagram, without retransmission nor ordering.
+/// In reliable mode the messages are split into numbered fragments, which are acknowledged
+/// Both sides of the communication must use the same mode: the datagrams of a peer using
+/// the other mode are dropped with an error
+/// Both sides of the communication must use the same mode
+#[derive(Clone, Debug)]
+pub struct UdpTransportOptions {
+    pub(crate) reliable: bool,
+    pub(crate) fragment_size: usize,
+    pub(crate) max_message_size: usize,
+    pub(crate) retransmission_timeout: Duration,
+    pub(crate) max_retransmissions: u32,
+    pub(crate) send_window: usize,
+}
+
+impl UdpTransportOptions {
+    /// Default constructor, without delivery guarantees
+    pub fn new() -> Self {
+        Self {
+            reliable: false,
+            fragment_size: DEFAULT_UDP_FRAGMENT_SIZE,
+            max_message_size: DEFAULT_UDP_MAX_MESSAGE_SIZE,
+            retransmission_timeout: DEFAULT_UDP_RETRANSMISSION_TIMEOUT,
+            max_retransmissions: DEFAULT_UDP_MAX_RETRANSMISSIONS,
+            send_window: DEFAULT_UDP_SEND_WINDOW,
+        }
+    }
+
+    /// Acknowledge, retransmit and order the messages, and fragment the large ones
+    pub fn with_reliable_delivery(mut self) -> Self {
+        self.reliable = true;
+        self
+    }
+
+    /// Set the maximum size of the payload of a datagram, in reliable mode
+    pub fn with_fragment_size(mut self, fragment_size: usize) -> Self {
+        self.fragment_size = fragment_size.max(1);
+        self
+    }
+
+    /// Set the maximum size of an encoded message, in reliable mode
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Set the duration after which an unacknowledged datagram is sent again, in reliable mode
+    pub fn with_retransmission_timeout(mut self, retransmission_timeout: Duration) -> Self {
+        self.retransmission_timeout = retransmission_timeout;
+        self
+    }
+
+    /// Set the number of times a datagram is sent again before giving up, in reliable mode
+    pub fn with_max_retransmissions(mut self, max_retransmissions: u32) -> Self {
+        self.max_retransmissions = max_retransmissions;
+        self
+    }
+
+    /// Set the maximum number of unacknowledged datagrams sent to a peer, in reliable mode
+    pub fn with_send_window(mut self, send_window: usize) -> Self {
+        self.send_window = send_window.max(1);
+        self
+    }
+
+    /// Maximum number of fragments of a message
+    pub(crate) fn max_fragments(&self) -> usize {
+        (self.max_message_size + self.fragment_size - 1) / self.fragment_size
+    }
+}
+
+impl Default for UdpTransportOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,74 @@
+use ockam_core::compat::collections::HashMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::Address;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Time of the last datagram sent or received for a flow
+#[derive(Clone, Debug)]
+pub(crate) struct FlowActivity(Arc<Mutex<Instant>>);
+
+impl FlowActivity {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    /// Record some traffic for the flow
+    pub(crate) fn touch(&self) {
+        *self.0.lock().unwrap() = Instant::now();
+    }
+
+    /// Return the duration since the last datagram of the flow
+    pub(crate) fn idle_time(&self) -> Duration {
+        self.0.lock().unwrap().elapsed()
+    }
+}
+
+/// A flow between a UDP client and an Inlet, served by a dedicated `UdpPortalWorker`
+#[derive(Clone, Debug)]
+pub(crate) struct UdpFlow {
+    pub(crate) internal_address: Address,
+    pub(crate) activity: FlowActivity,
+}
+
+/// Flows of an Inlet, indexed by the socket address of their client
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UdpFlows(Arc<Mutex<HashMap<SocketAddr, UdpFlow>>>);
+
+impl UdpFlows {
+    pub(crate) fn get(&self, peer: &SocketAddr) -> Option<UdpFlow> {
+        self.0.lock().unwrap().get(peer).cloned()
+    }
+
+    pub(crate) fn insert(&self, peer: SocketAddr, flow: UdpFlow) {
+        self.0.lock().unwrap().insert(peer, flow);
+    }
+
+    /// Remove a flow if it is still served by the worker with the given internal address
+    pub(crate) fn remove(&self, peer: &SocketAddr, internal_address: &Address) {
+        let mut flows = self.0.lock().unwrap();
+        if flows
+            .get(peer)
+            .map(|f| &f.internal_address == internal_address)
+            .unwrap_or(false)
+        {
+            flows.remove(peer);
+        }
+    }
+
+    /// Remove the flows idle for longer than the timeout, and return them
+    pub(crate) fn remove_idle(&self, idle_timeout: Duration) -> Vec<UdpFlow> {
+        let mut flows = self.0.lock().unwrap();
+        let idle: Vec<SocketAddr> = flows
+            .iter()
+            .filter(|(_, f)| f.activity.idle_time() >= idle_timeout)
+            .map(|(peer, _)| *peer)
+            .collect();
+        idle.iter().filter_map(|peer| flows.remove(peer)).collect()
+    }
+
+    /// Remove all the flows, and return them
+    pub(crate) fn remove_all(&self) -> Vec<UdpFlow> {
+        self.0.lock().unwrap().drain().map(|(_, f)| f).collect()
+    }
+}
@@ -0,0 +1,182 @@
+use crate::portal::addresses::{Addresses, PortalType};
+use crate::portal::portal_message::RECEIVE_BUFFER_SIZE;
+use crate::portal::{
+    FlowActivity, UdpFlow, UdpFlows, UdpPortalInternalMessage, UdpPortalWorker, MAX_DATAGRAM_SIZE,
+};
+use crate::UdpInletOptions;
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, Address, AllowAll, DenyAll, Processor, Result, Route};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+use tracing::{debug, error, warn};
+
+/// Maximum duration between two checks of the idle flows
+const FLOW_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A UDP Portal Inlet listen processor
+///
+/// UDP Portal Inlet listen processors are created by `UdpTransport`
+/// after a call is made to
+/// [`UdpTransport::create_inlet`](crate::UdpTransport::create_inlet).
+///
+/// Each client sending datagrams to the inlet socket is served by a dedicated
+/// `UdpPortalWorker`, which is stopped once the flow has been idle for too long.
+pub(crate) struct UdpInletListenProcessor {
+    socket: Arc<UdpSocket>,
+    buf: Vec<u8>,
+    outlet_listener_route: Route,
+    flows: UdpFlows,
+    options: UdpInletOptions,
+}
+
+impl UdpInletListenProcessor {
+    /// Start a new `UdpInletListenProcessor`
+    pub(crate) async fn start(
+        ctx: &Context,
+        outlet_listener_route: Route,
+        addr: SocketAddr,
+        options: UdpInletOptions,
+    ) -> Result<(SocketAddr, Address)> {
+        let processor_address = Address::random_tagged("UdpInletListenProcessor");
+
+        debug!("Binding UdpInletListenProcessor to {}", addr);
+        let socket = match UdpSocket::bind(addr).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                error!(%addr, %err, "could not bind to address");
+                return Err(TransportError::from(err).into());
+            }
+        };
+        let socket_addr = socket.local_addr().map_err(TransportError::from)?;
+        let processor = Self {
+            socket: Arc::new(socket),
+            buf: vec![0; RECEIVE_BUFFER_SIZE],
+            outlet_listener_route,
+            flows: UdpFlows::default(),
+            options,
+        };
+
+        // the datagrams are sent to the flow workers, which only accept internal messages
+        // from this processor
+        ctx.start_processor_with_access_control(
+            processor_address.clone(),
+            processor,
+            DenyAll,
+            AllowAll,
+        )
+        .await?;
+
+        Ok((socket_addr, processor_address))
+    }
+
+    /// Start a worker for a new client and return its internal address
+    async fn start_flow(&self, ctx: &Context, peer: SocketAddr) -> Result<Address> {
+        let addresses = Addresses::generate(PortalType::Inlet);
+        let outlet_listener_route = self.outlet_listener_route.clone();
+
+        self.options.setup_flow_control(
+            ctx.flow_controls(),
+            &addresses,
+            outlet_listener_route.next()?,
+        );
+
+        let activity = FlowActivity::new();
+        UdpPortalWorker::start_new_inlet(
+            ctx,
+            self.socket.clone(),
+            peer,
+            outlet_listener_route,
+            addresses.clone(),
+            activity.clone(),
+            self.flows.clone(),
+            self.options.incoming_access_control.clone(),
+        )
+        .await?;
+
+        self.flows.insert(
+            peer,
+            UdpFlow {
+                internal_address: addresses.internal.clone(),
+                activity,
+            },
+        );
+
+        Ok(addresses.internal)
+    }
+
+    /// Close the flows which have been idle for too long
+    async fn close_flows(ctx: &Context, flows: Vec<UdpFlow>) {
+        for flow in flows {
+            debug!("Closing UDP inlet flow at {}", flow.internal_address);
+            if let Err(err) = ctx
+                .send(
+                    flow.internal_address.clone(),
+                    UdpPortalInternalMessage::Disconnect,
+                )
+                .await
+            {
+                debug!(
+                    "cannot close UDP inlet flow at {}: {}",
+                    flow.internal_address, err
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Processor for UdpInletListenProcessor {
+    type Context = Context;
+
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        Self::close_flows(ctx, self.flows.remove_all()).await;
+
+        Ok(())
+    }
+
+    async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
+        Self::close_flows(ctx, self.flows.remove_idle(self.options.idle_timeout)).await;
+
+        let (len, peer) =
+            match timeout(FLOW_CHECK_INTERVAL, self.socket.recv_from(&mut self.buf)).await {
+                Ok(Ok(received)) => received,
+                Ok(Err(err)) => {
+                    warn!("UDP inlet failed to receive a datagram: {}", err);
+                    return Ok(true);
+                }
+                // no datagram, check the idle flows again
+                Err(_) => return Ok(true),
+            };
+
+        if len > MAX_DATAGRAM_SIZE {
+            warn!(
+                "Dropping a datagram of {} bytes from {}, the maximum size is {}",
+                len, peer, MAX_DATAGRAM_SIZE
+            );
+            return Ok(true);
+        }
+
+        let internal_address = match self.flows.get(&peer) {
+            Some(flow) => {
+                flow.activity.touch();
+                flow.internal_address
+            }
+            None => {
+                debug!("New UDP inlet flow from {}", peer);
+                self.start_flow(ctx, peer).await?
+            }
+        };
+
+        ctx.send(
+            internal_address,
+            UdpPortalInternalMessage::Datagram(self.buf[..len].to_vec()),
+        )
+        .await?;
+
+        Ok(true)
+    }
+}
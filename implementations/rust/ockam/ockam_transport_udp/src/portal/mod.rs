@@ -0,0 +1,16 @@
+mod addresses;
+mod flows;
+mod inlet_listener;
+mod options;
+mod outlet_listener;
+mod portal_message;
+mod portal_receiver;
+mod portal_worker;
+
+pub(crate) use flows::*;
+pub(crate) use inlet_listener::*;
+pub use options::*;
+pub(crate) use outlet_listener::*;
+pub use portal_message::*;
+pub(crate) use portal_receiver::*;
+pub(crate) use portal_worker::*;
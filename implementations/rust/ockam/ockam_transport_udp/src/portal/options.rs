@@ -0,0 +1,155 @@
+use crate::portal::addresses::Addresses;
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::flow_control::{FlowControlId, FlowControls};
+use ockam_core::{Address, AllowAll, IncomingAccessControl};
+
+/// Default duration after which a flow without any datagram is closed
+pub const DEFAULT_UDP_FLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Trust Options for a UDP Inlet
+#[derive(Debug)]
+pub struct UdpInletOptions {
+    pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) idle_timeout: Duration,
+}
+
+impl UdpInletOptions {
+    /// Default constructor without Incoming Access Control
+    pub fn new() -> Self {
+        Self {
+            incoming_access_control: Arc::new(AllowAll),
+            idle_timeout: DEFAULT_UDP_FLOW_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control_impl(
+        mut self,
+        access_control: impl IncomingAccessControl,
+    ) -> Self {
+        self.incoming_access_control = Arc::new(access_control);
+        self
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control(
+        mut self,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        self.incoming_access_control = access_control;
+        self
+    }
+
+    /// Close the flow of a client after this duration without any datagram
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub(super) fn setup_flow_control(
+        &self,
+        flow_controls: &FlowControls,
+        addresses: &Addresses,
+        next: &Address,
+    ) {
+        if let Some(flow_control_id) = flow_controls
+            .find_flow_control_with_producer_address(next)
+            .map(|x| x.flow_control_id().clone())
+        {
+            // Allow a sender with corresponding flow_control_id send messages to this address
+            flow_controls.add_consumer(addresses.remote.clone(), &flow_control_id);
+        }
+    }
+}
+
+impl Default for UdpInletOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Trust Options for a UDP Outlet
+#[derive(Debug)]
+pub struct UdpOutletOptions {
+    pub(super) consumer: Vec<FlowControlId>,
+    pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) idle_timeout: Duration,
+}
+
+impl UdpOutletOptions {
+    /// Default constructor without Incoming Access Control
+    pub fn new() -> Self {
+        Self {
+            consumer: vec![],
+            incoming_access_control: Arc::new(AllowAll),
+            idle_timeout: DEFAULT_UDP_FLOW_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control_impl(
+        mut self,
+        access_control: impl IncomingAccessControl,
+    ) -> Self {
+        self.incoming_access_control = Arc::new(access_control);
+        self
+    }
+
+    /// Set Incoming Access Control
+    pub fn with_incoming_access_control(
+        mut self,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Self {
+        self.incoming_access_control = access_control;
+        self
+    }
+
+    /// Close the socket opened for a flow after this duration without any datagram
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Mark that this Outlet listener is a Consumer for to the given [`FlowControlId`]
+    /// Also, in this case spawned Outlets will be marked as Consumers with [`FlowControlId`]
+    /// of the message that was used to create the Outlet
+    pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
+        self.consumer.push(id.clone());
+
+        self
+    }
+
+    pub(super) fn setup_flow_control_for_outlet_listener(
+        &self,
+        flow_controls: &FlowControls,
+        address: &Address,
+    ) {
+        for id in &self.consumer {
+            flow_controls.add_consumer(address.clone(), id);
+        }
+    }
+
+    pub(super) fn setup_flow_control_for_outlet(
+        &self,
+        flow_controls: &FlowControls,
+        addresses: &Addresses,
+        src_addr: &Address,
+    ) {
+        // Check if the Worker that send us this message is a Producer
+        // If yes - outlet worker will be added to that flow control to be able to receive further
+        // messages from that Producer
+        if let Some(producer_flow_control_id) = flow_controls
+            .get_flow_control_with_producer(src_addr)
+            .map(|x| x.flow_control_id().clone())
+        {
+            flow_controls.add_consumer(addresses.remote.clone(), &producer_flow_control_id);
+        }
+    }
+}
+
+impl Default for UdpOutletOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,82 @@
+use crate::portal::addresses::{Addresses, PortalType};
+use crate::portal::{UdpPortalMessage, UdpPortalWorker};
+use crate::UdpOutletOptions;
+use ockam_core::{async_trait, Address, DenyAll, Result, Routed, Worker};
+use ockam_node::{Context, WorkerBuilder};
+use ockam_transport_core::TransportError;
+use std::net::SocketAddr;
+use tracing::debug;
+
+/// A UDP Portal Outlet listen worker
+///
+/// UDP Portal Outlet listen workers are created by `UdpTransport`
+/// after a call is made to
+/// [`UdpTransport::create_outlet`](crate::UdpTransport::create_outlet).
+///
+/// A new `UdpPortalWorker`, with its own socket, is started for each flow of an Inlet.
+pub(crate) struct UdpOutletListenWorker {
+    peer: SocketAddr,
+    options: UdpOutletOptions,
+}
+
+impl UdpOutletListenWorker {
+    pub(crate) async fn start(
+        ctx: &Context,
+        address: Address,
+        peer: SocketAddr,
+        options: UdpOutletOptions,
+    ) -> Result<()> {
+        let access_control = options.incoming_access_control.clone();
+
+        options.setup_flow_control_for_outlet_listener(ctx.flow_controls(), &address);
+
+        let worker = Self { peer, options };
+        WorkerBuilder::new(worker)
+            .with_address(address)
+            .with_incoming_access_control_arc(access_control)
+            .with_outgoing_access_control(DenyAll)
+            .start(ctx)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Worker for UdpOutletListenWorker {
+    type Context = Context;
+    type Message = UdpPortalMessage;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let return_route = msg.return_route();
+        let src_addr = msg.src_addr();
+
+        if let UdpPortalMessage::Ping = msg.body() {
+        } else {
+            return Err(TransportError::Protocol.into());
+        }
+
+        let addresses = Addresses::generate(PortalType::Outlet);
+
+        self.options
+            .setup_flow_control_for_outlet(ctx.flow_controls(), &addresses, &src_addr);
+
+        UdpPortalWorker::start_new_outlet(
+            ctx,
+            self.peer,
+            return_route,
+            addresses.clone(),
+            self.options.idle_timeout,
+            self.options.incoming_access_control.clone(),
+        )
+        .await?;
+
+        debug!("Created Udp Outlet at {}", addresses.remote);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use ockam_core::Message;
+use serde::{Deserialize, Serialize};
+
+/// A command message type for a UDP Portal
+#[derive(Serialize, Deserialize, Message, Debug)]
+pub enum UdpPortalMessage {
+    /// First message that an Inlet flow sends to the Outlet
+    Ping,
+    /// First message that an Outlet flow sends to the Inlet
+    Pong,
+    /// Message to indicate that a flow was closed on one side
+    Disconnect,
+    /// Message with the content of one datagram
+    Payload(Vec<u8>),
+}
+
+/// An internal message type for a UDP Portal
+#[derive(Serialize, Deserialize, Message)]
+pub(crate) enum UdpPortalInternalMessage {
+    /// Datagram received by an Inlet for a given flow
+    Datagram(Vec<u8>),
+    /// The flow has been idle for too long
+    Disconnect,
+}
+
+/// Maximum allowed size for a datagram sent through a portal
+pub const MAX_DATAGRAM_SIZE: usize = 48 * 1024;
+
+/// Size of the buffer used to receive datagrams, larger than any UDP datagram
+pub(super) const RECEIVE_BUFFER_SIZE: usize = 64 * 1024;
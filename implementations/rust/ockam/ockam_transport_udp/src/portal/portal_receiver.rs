@@ -0,0 +1,106 @@
+use crate::portal::portal_message::RECEIVE_BUFFER_SIZE;
+use crate::portal::{FlowActivity, UdpPortalInternalMessage, UdpPortalMessage, MAX_DATAGRAM_SIZE};
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    async_trait, route, Address, Encodable, LocalMessage, Processor, Result, Route,
+    TransportMessage,
+};
+use ockam_node::Context;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+use tracing::{trace, warn};
+
+/// A UDP Portal receiving message processor
+///
+/// UDP Portal receiving message processors are created by an Outlet `UdpPortalWorker`
+/// for each flow. They forward the datagrams sent by the peer to the Inlet, and notify the
+/// worker when the flow has been idle for too long.
+pub(crate) struct UdpPortalRecvProcessor {
+    socket: Arc<UdpSocket>,
+    buf: Vec<u8>,
+    peer: SocketAddr,
+    sender_address: Address,
+    onward_route: Route,
+    activity: FlowActivity,
+    idle_timeout: Duration,
+}
+
+impl UdpPortalRecvProcessor {
+    /// Create a new `UdpPortalRecvProcessor`
+    pub fn new(
+        socket: Arc<UdpSocket>,
+        peer: SocketAddr,
+        sender_address: Address,
+        onward_route: Route,
+        activity: FlowActivity,
+        idle_timeout: Duration,
+    ) -> Self {
+        Self {
+            socket,
+            buf: vec![0; RECEIVE_BUFFER_SIZE],
+            peer,
+            sender_address,
+            onward_route,
+            activity,
+            idle_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Processor for UdpPortalRecvProcessor {
+    type Context = Context;
+
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let remaining = self.idle_timeout.saturating_sub(self.activity.idle_time());
+        if remaining.is_zero() {
+            // Notify Sender that the flow is idle
+            if let Err(err) = ctx
+                .send(
+                    route![self.sender_address.clone()],
+                    UdpPortalInternalMessage::Disconnect,
+                )
+                .await
+            {
+                warn!("Error notifying Udp Portal Sender about idle flow {}", err);
+            }
+            return Ok(false);
+        }
+
+        let (len, from) = match timeout(remaining, self.socket.recv_from(&mut self.buf)).await {
+            Ok(Ok(received)) => received,
+            Ok(Err(err)) => {
+                warn!("Udp Portal failed to receive a datagram: {}", err);
+                return Ok(true);
+            }
+            // the activity is checked again, datagrams may have been sent to the peer
+            Err(_) => return Ok(true),
+        };
+
+        if from != self.peer {
+            trace!("Ignoring a datagram from {}, expected {}", from, self.peer);
+            return Ok(true);
+        }
+
+        if len > MAX_DATAGRAM_SIZE {
+            warn!(
+                "Dropping a datagram of {} bytes from {}, the maximum size is {}",
+                len, from, MAX_DATAGRAM_SIZE
+            );
+            return Ok(true);
+        }
+
+        self.activity.touch();
+        let msg = TransportMessage::v1(
+            self.onward_route.clone(),
+            self.sender_address.clone(),
+            UdpPortalMessage::Payload(self.buf[..len].to_vec()).encode()?,
+        );
+        ctx.forward(LocalMessage::new(msg, vec![])).await?;
+        trace!("Udp Portal forwarded {} bytes from {}", len, from);
+
+        Ok(true)
+    }
+}
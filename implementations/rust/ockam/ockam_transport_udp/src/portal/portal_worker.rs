@@ -0,0 +1,434 @@
+use crate::portal::addresses::{Addresses, PortalType};
+use crate::portal::{
+    FlowActivity, UdpFlows, UdpPortalInternalMessage, UdpPortalMessage, UdpPortalRecvProcessor,
+};
+use core::time::Duration;
+use ockam_core::compat::collections::VecDeque;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    async_trait, Address, AllowAll, AllowOnwardAddresses, AllowSourceAddress, Any, Decodable,
+    DenyAll, IncomingAccessControl, Mailbox, Mailboxes, Result, Route, Routed, Worker,
+};
+use ockam_node::{Context, ProcessorBuilder, WorkerBuilder};
+use ockam_transport_core::TransportError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::net::UdpSocket;
+use tracing::{debug, info, trace, warn};
+
+/// Maximum number of datagrams kept by an Inlet flow while waiting for the Outlet
+const MAX_PENDING_DATAGRAMS: usize = 32;
+
+/// Enumerate all `UdpPortalWorker` states
+///
+/// Possible state transitions are:
+///
+/// `Outlet`: `SendPong` -> `Initialized`
+/// `Inlet`: `SendPing` -> `ReceivePong` -> `Initialized`
+#[derive(Clone)]
+enum State {
+    SendPing { ping_route: Route },
+    SendPong { pong_route: Route },
+    ReceivePong,
+    Initialized,
+}
+
+/// A UDP Portal worker
+///
+/// A UDP Portal worker is responsible for a single flow of datagrams, between one UDP
+/// client of the Inlet and the peer of the Outlet.
+///
+/// On the Inlet side, it is created by `UdpInletListenProcessor` when a datagram is received
+/// from a new client, and shares the socket of the Inlet.
+/// On the Outlet side, it is created by `UdpOutletListenWorker` and binds its own socket,
+/// so that the responses of the peer can be sent back to the right flow.
+pub(crate) struct UdpPortalWorker {
+    state: State,
+    socket: Option<Arc<UdpSocket>>,
+    peer: SocketAddr,
+    addresses: Addresses,
+    remote_route: Option<Route>,
+    activity: FlowActivity,
+    idle_timeout: Duration,
+    // flows of the Inlet, used to unregister the flow when the worker stops
+    flows: Option<UdpFlows>,
+    pending_datagrams: VecDeque<Vec<u8>>,
+    is_disconnecting: bool,
+    portal_type: PortalType,
+}
+
+impl UdpPortalWorker {
+    /// Start a new `UdpPortalWorker` of type [`PortalType::Inlet`]
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn start_new_inlet(
+        ctx: &Context,
+        socket: Arc<UdpSocket>,
+        peer: SocketAddr,
+        ping_route: Route,
+        addresses: Addresses,
+        activity: FlowActivity,
+        flows: UdpFlows,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        let worker = Self::new(
+            State::SendPing { ping_route },
+            Some(socket),
+            peer,
+            addresses,
+            activity,
+            Duration::ZERO,
+            Some(flows),
+            PortalType::Inlet,
+        );
+        // datagrams are received by the Inlet listener
+        worker.start(ctx, ctx.address(), access_control).await
+    }
+
+    /// Start a new `UdpPortalWorker` of type [`PortalType::Outlet`]
+    pub(super) async fn start_new_outlet(
+        ctx: &Context,
+        peer: SocketAddr,
+        pong_route: Route,
+        addresses: Addresses,
+        idle_timeout: Duration,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        let receiver = addresses.receiver.clone();
+        let worker = Self::new(
+            State::SendPong { pong_route },
+            None,
+            peer,
+            addresses,
+            FlowActivity::new(),
+            idle_timeout,
+            None,
+            PortalType::Outlet,
+        );
+        worker.start(ctx, receiver, access_control).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        state: State,
+        socket: Option<Arc<UdpSocket>>,
+        peer: SocketAddr,
+        addresses: Addresses,
+        activity: FlowActivity,
+        idle_timeout: Duration,
+        flows: Option<UdpFlows>,
+        portal_type: PortalType,
+    ) -> Self {
+        Self {
+            state,
+            socket,
+            peer,
+            addresses,
+            remote_route: None,
+            activity,
+            idle_timeout,
+            flows,
+            pending_datagrams: VecDeque::new(),
+            is_disconnecting: false,
+            portal_type,
+        }
+    }
+
+    /// Start a new `UdpPortalWorker`, accepting internal messages from `internal_source` only
+    async fn start(
+        self,
+        ctx: &Context,
+        internal_source: Address,
+        access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        info!(
+            "Creating new {:?} flow at internal: {}, remote: {}",
+            self.portal_type.str(),
+            self.addresses.internal,
+            self.addresses.remote
+        );
+
+        let internal_mailbox = Mailbox::new(
+            self.addresses.internal.clone(),
+            Arc::new(AllowSourceAddress(internal_source)),
+            Arc::new(DenyAll),
+        );
+
+        let remote_mailbox = Mailbox::new(
+            self.addresses.remote.clone(),
+            access_control,
+            Arc::new(AllowAll), // FIXME: @ac Allow to respond anywhere using return_route
+        );
+
+        WorkerBuilder::new(self)
+            .with_mailboxes(Mailboxes::new(internal_mailbox, vec![remote_mailbox]))
+            .start(ctx)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl UdpPortalWorker {
+    fn clone_state(&self) -> State {
+        self.state.clone()
+    }
+
+    /// Bind a new socket for the flow and start a `UdpPortalRecvProcessor`
+    /// receiving the datagrams of the peer
+    async fn start_receiver(&mut self, ctx: &Context, onward_route: Route) -> Result<()> {
+        let bind_addr = if self.peer.is_ipv4() {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
+        } else {
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)
+        };
+        let socket = Arc::new(
+            UdpSocket::bind(bind_addr)
+                .await
+                .map_err(TransportError::from)?,
+        );
+
+        let next_hop = onward_route.next()?.clone();
+        let receiver = UdpPortalRecvProcessor::new(
+            socket.clone(),
+            self.peer,
+            self.addresses.internal.clone(),
+            onward_route,
+            self.activity.clone(),
+            self.idle_timeout,
+        );
+
+        ProcessorBuilder::new(receiver)
+            .with_address(self.addresses.receiver.clone())
+            .with_outgoing_access_control(AllowOnwardAddresses(vec![
+                next_hop,
+                self.addresses.internal.clone(),
+            ])) // Only sends messages to `onward_route` and Sender
+            .start(ctx)
+            .await?;
+
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    async fn handle_send_ping(&self, ctx: &Context, ping_route: Route) -> Result<State> {
+        // Force creation of an Outlet flow on the other side
+        ctx.send_from_address(
+            ping_route,
+            UdpPortalMessage::Ping,
+            self.addresses.remote.clone(),
+        )
+        .await?;
+
+        debug!("Inlet flow at: {} sent ping", self.addresses.internal);
+
+        Ok(State::ReceivePong)
+    }
+
+    async fn handle_send_pong(&mut self, ctx: &Context, pong_route: Route) -> Result<State> {
+        self.start_receiver(ctx, pong_route.clone()).await?;
+
+        // Respond to Inlet
+        ctx.send_from_address(
+            pong_route.clone(),
+            UdpPortalMessage::Pong,
+            self.addresses.remote.clone(),
+        )
+        .await?;
+
+        debug!("Outlet flow at: {} sent pong", self.addresses.internal);
+
+        self.remote_route = Some(pong_route);
+        Ok(State::Initialized)
+    }
+
+    /// Send a datagram received from the client to the other side of the portal
+    async fn send_payload(&self, ctx: &Context, payload: Vec<u8>) -> Result<()> {
+        if let Some(remote_route) = &self.remote_route {
+            ctx.send_from_address(
+                remote_route.clone(),
+                UdpPortalMessage::Payload(payload),
+                self.addresses.remote.clone(),
+            )
+            .await
+        } else {
+            Err(TransportError::PortalInvalidState.into())
+        }
+    }
+
+    /// Send a datagram received from the other side of the portal to the peer
+    async fn send_datagram(&self, payload: Vec<u8>) -> Result<()> {
+        if let Some(socket) = &self.socket {
+            self.activity.touch();
+            // a datagram can be lost, the flow is kept open
+            if let Err(err) = socket.send_to(&payload, self.peer).await {
+                warn!(
+                    "Failed to send a datagram to peer {} with error: {}",
+                    self.peer, err
+                );
+            }
+            Ok(())
+        } else {
+            Err(TransportError::PortalInvalidState.into())
+        }
+    }
+
+    /// Stop the flow, notifying the other side when the flow was closed locally
+    async fn stop(&mut self, ctx: &Context, notify_remote: bool) -> Result<()> {
+        self.is_disconnecting = true;
+
+        if notify_remote {
+            if let Some(remote_route) = self.remote_route.take() {
+                if let Err(err) = ctx
+                    .send_from_address(
+                        remote_route,
+                        UdpPortalMessage::Disconnect,
+                        self.addresses.remote.clone(),
+                    )
+                    .await
+                {
+                    debug!("cannot notify the other side about the flow closure: {err}");
+                }
+            }
+        }
+
+        if let PortalType::Outlet = self.portal_type {
+            // the receiver may already have stopped itself
+            if ctx
+                .stop_processor(self.addresses.receiver.clone())
+                .await
+                .is_ok()
+            {
+                debug!(
+                    "Outlet flow at: {} stopped its receiver",
+                    self.addresses.internal
+                );
+            }
+        }
+
+        ctx.stop_worker(self.addresses.internal.clone()).await?;
+
+        info!(
+            "{:?} flow at: {} stopped",
+            self.portal_type.str(),
+            self.addresses.internal
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Worker for UdpPortalWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        let state = self.clone_state();
+
+        match state {
+            State::SendPing { ping_route } => {
+                self.state = self.handle_send_ping(ctx, ping_route.clone()).await?;
+            }
+            State::SendPong { pong_route } => {
+                self.state = self.handle_send_pong(ctx, pong_route.clone()).await?;
+            }
+            State::ReceivePong | State::Initialized { .. } => {
+                return Err(TransportError::PortalInvalidState.into())
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        if let Some(flows) = &self.flows {
+            flows.remove(&self.peer, &self.addresses.internal);
+        }
+
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        if self.is_disconnecting {
+            return Ok(());
+        }
+
+        // Remove our own address from the route so the other end
+        // knows what to do with the incoming message
+        let mut onward_route = msg.onward_route();
+        let recipient = onward_route.step()?;
+
+        let return_route = msg.return_route();
+
+        if onward_route.next().is_ok() {
+            return Err(TransportError::UnknownRoute.into());
+        }
+
+        if recipient == self.addresses.internal {
+            trace!(
+                "{:?} flow at: {} received internal message",
+                self.portal_type.str(),
+                self.addresses.internal
+            );
+
+            match UdpPortalInternalMessage::decode(msg.payload())? {
+                UdpPortalInternalMessage::Datagram(payload) => match self.state {
+                    State::ReceivePong => {
+                        if self.pending_datagrams.len() == MAX_PENDING_DATAGRAMS {
+                            self.pending_datagrams.pop_front();
+                        }
+                        self.pending_datagrams.push_back(payload);
+                    }
+                    State::Initialized => self.send_payload(ctx, payload).await?,
+                    State::SendPing { .. } | State::SendPong { .. } => {
+                        return Err(TransportError::PortalInvalidState.into())
+                    }
+                },
+                UdpPortalInternalMessage::Disconnect => {
+                    info!(
+                        "Udp flow was idle for {:?} at: {}",
+                        self.portal_type.str(),
+                        self.addresses.internal
+                    );
+                    self.stop(ctx, true).await?;
+                }
+            }
+
+            return Ok(());
+        }
+
+        trace!(
+            "{:?} flow at: {} received remote message",
+            self.portal_type.str(),
+            self.addresses.internal
+        );
+
+        let msg = UdpPortalMessage::decode(msg.payload())?;
+
+        match (self.clone_state(), msg) {
+            (State::ReceivePong, UdpPortalMessage::Pong) => {
+                debug!("Inlet flow at: {} received pong", self.addresses.internal);
+
+                self.remote_route = Some(return_route);
+                self.state = State::Initialized;
+
+                while let Some(payload) = self.pending_datagrams.pop_front() {
+                    self.send_payload(ctx, payload).await?;
+                }
+            }
+            (State::Initialized, UdpPortalMessage::Payload(payload)) => {
+                self.send_datagram(payload).await?;
+            }
+            (State::ReceivePong | State::Initialized, UdpPortalMessage::Disconnect) => {
+                self.stop(ctx, false).await?;
+            }
+            (State::ReceivePong | State::Initialized, _) => {
+                return Err(TransportError::Protocol.into());
+            }
+            (State::SendPing { .. } | State::SendPong { .. }, _) => {
+                return Err(TransportError::PortalInvalidState.into())
+            }
+        }
+
+        Ok(())
+    }
+}
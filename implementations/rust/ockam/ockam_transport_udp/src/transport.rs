@@ -1,7 +1,10 @@
+use crate::portal::{UdpInletListenProcessor, UdpOutletListenWorker};
 use crate::router::{UdpRouter, UdpRouterHandle};
-use ockam_core::{async_trait, Result};
+use crate::{UdpInletOptions, UdpOutletOptions, UdpTransportOptions};
+use ockam_core::{async_trait, Address, AsyncTryClone, Result, Route};
 use ockam_node::{Context, HasContext};
 use ockam_transport_core::TransportError;
+use std::net::{SocketAddr, ToSocketAddrs};
 
 /// High level management interface for UDP transport
 ///
@@ -9,14 +12,37 @@ use ockam_transport_core::TransportError;
 ///
 /// This transport only supports IPv4.
 pub struct UdpTransport {
+    ctx: Context,
     router_handle: UdpRouterHandle,
 }
 
 impl UdpTransport {
     /// Create a new UDP transport for the current node
     pub async fn create(ctx: &Context) -> Result<UdpTransport> {
-        let router_handle = UdpRouter::register(ctx).await?;
-        Ok(Self { router_handle })
+        Self::create_with_options(ctx, UdpTransportOptions::new()).await
+    }
+
+    /// Create a new UDP transport for the current node, with the given options
+    ///
+    /// ```rust
+    /// use ockam_transport_udp::{UdpTransport, UdpTransportOptions};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// // Secure channels can run over a punched hole when both peers use the reliable mode
+    /// let options = UdpTransportOptions::new().with_reliable_delivery();
+    /// let udp = UdpTransport::create_with_options(&ctx, options).await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_with_options(
+        ctx: &Context,
+        options: UdpTransportOptions,
+    ) -> Result<UdpTransport> {
+        let router_handle = UdpRouter::register(ctx, options).await?;
+        Ok(Self {
+            ctx: ctx.async_try_clone().await?,
+            router_handle,
+        })
     }
 
     /// Start listening to incoming datagrams on a specified local address
@@ -27,6 +53,100 @@ impl UdpTransport {
             .map_err(|_| TransportError::InvalidAddress)?;
         self.router_handle.listen(bind_addr).await
     }
+
+    /// Create a UDP Inlet that listens on bind_addr, transforms the datagrams of each client
+    /// into Ockam Routable Messages and forwards them to an Outlet using outlet_route.
+    /// Each client is served by its own flow, closed after being idle for some time.
+    /// Datagrams sent back by the Outlet for a flow are sent to the corresponding client.
+    ///
+    /// ```rust
+    /// use ockam_transport_udp::{UdpInletOptions, UdpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Result, route};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let route_path = route!["outlet"];
+    ///
+    /// let udp = UdpTransport::create(&ctx).await?;
+    /// udp.create_inlet("127.0.0.1:5353", route_path, UdpInletOptions::new()).await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_inlet(
+        &self,
+        bind_addr: impl Into<String>,
+        outlet_route: impl Into<Route>,
+        options: UdpInletOptions,
+    ) -> Result<(SocketAddr, Address)> {
+        let socket_addr = bind_addr
+            .into()
+            .parse()
+            .map_err(|_| TransportError::InvalidAddress)?;
+        UdpInletListenProcessor::start(&self.ctx, outlet_route.into(), socket_addr, options).await
+    }
+
+    /// Stop the UDP inlet at addr, and all its flows
+    pub async fn stop_inlet(&self, addr: impl Into<Address>) -> Result<()> {
+        self.ctx.stop_processor(addr).await?;
+
+        Ok(())
+    }
+
+    /// Create a UDP Outlet Listener at address. For each flow of an Inlet, the Outlet sends
+    /// the datagrams to the peer from a dedicated socket, and sends the datagrams received
+    /// from the peer on that socket back to the Inlet.
+    ///
+    /// ```rust
+    /// use ockam_transport_udp::{UdpOutletOptions, UdpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::Result;
+    /// # async fn test(ctx: Context) -> Result<()> {
+    ///
+    /// let udp = UdpTransport::create(&ctx).await?;
+    /// udp.create_outlet("outlet", "localhost:53", UdpOutletOptions::new()).await?;
+    /// # udp.stop_outlet("outlet").await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_outlet(
+        &self,
+        address: impl Into<Address>,
+        peer: impl Into<String>,
+        options: UdpOutletOptions,
+    ) -> Result<()> {
+        let peer = peer.into();
+        let peer_addr = match peer.parse() {
+            Ok(peer_addr) => peer_addr,
+            // Prefer ip4, like the TCP transport
+            Err(_) => {
+                let addrs: Vec<SocketAddr> = peer
+                    .to_socket_addrs()
+                    .map_err(|_| TransportError::InvalidAddress)?
+                    .collect();
+                addrs
+                    .iter()
+                    .find(|a| a.is_ipv4())
+                    .or_else(|| addrs.first())
+                    .cloned()
+                    .ok_or(TransportError::InvalidAddress)?
+            }
+        };
+        self.create_udp_outlet(address.into(), peer_addr, options)
+            .await
+    }
+
+    /// Create a UDP Outlet Listener at address, sending datagrams to peer
+    pub async fn create_udp_outlet(
+        &self,
+        address: Address,
+        peer: SocketAddr,
+        options: UdpOutletOptions,
+    ) -> Result<()> {
+        UdpOutletListenWorker::start(&self.ctx, address, peer, options).await
+    }
+
+    /// Stop the UDP outlet at addr
+    pub async fn stop_outlet(&self, addr: impl Into<Address>) -> Result<()> {
+        self.ctx.stop_worker(addr).await?;
+        Ok(())
+    }
 }
 
 /// This trait adds a `create_udp_transport` method to any struct returning a Context.
@@ -1,16 +1,87 @@
 use bytes::{Buf, BufMut, BytesMut};
-use ockam_core::TransportMessage;
-use ockam_core::{Decodable, Encodable};
+use ockam_core::{Decodable, Encodable, Message, TransportMessage};
 use ockam_transport_core::TransportError;
+use serde::{Deserialize, Serialize};
 use tokio_util::codec::{Decoder, Encoder};
 
-pub(crate) struct TransportMessageCodec;
+/// Datagram exchanged by the UDP workers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum UdpPacket {
+    /// Message sent as a single datagram, without delivery guarantees
+    Message(TransportMessage),
+    /// Datagram of the reliable mode
+    Reliable(ReliablePacket),
+}
+
+/// Datagram of the reliable mode
+#[derive(Clone, Debug, Serialize, Deserialize, Message, PartialEq, Eq)]
+pub(crate) enum ReliablePacket {
+    Fragment(Fragment),
+    Ack(Ack),
+}
+
+/// Fragment of an encoded [`TransportMessage`].
+///
+/// The fragments of a message have consecutive sequence numbers
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Fragment {
+    /// Random identifier of the sequence numbers of the sender
+    pub(crate) session: u64,
+    pub(crate) sequence: u64,
+    /// First sequence number of the oldest message which is not fully acknowledged
+    pub(crate) base: u64,
+    pub(crate) index: u32,
+    pub(crate) count: u32,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl Fragment {
+    /// Sequence number of the first fragment of the message
+    pub(crate) fn first_sequence(&self) -> u64 {
+        self.sequence.saturating_sub(self.index as u64)
+    }
+}
+
+/// Acknowledgement of a received [`Fragment`]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct Ack {
+    pub(crate) session: u64,
+    pub(crate) sequence: u64,
+}
+
+/// First byte of a datagram carrying a [`UdpPacket::Message`]
+const MESSAGE_MODE: u8 = 0;
+/// First byte of a datagram carrying a [`UdpPacket::Reliable`] packet
+const RELIABLE_MODE: u8 = 1;
+
+/// Each datagram starts with the mode of its sender, so that a peer using another
+/// mode can be detected when its datagrams are received
+pub(crate) struct TransportMessageCodec {
+    reliable: bool,
+}
+
+impl TransportMessageCodec {
+    /// Create a codec sending datagrams with the given mode.
+    /// Datagrams of both modes are decoded
+    pub(crate) fn new(reliable: bool) -> Self {
+        Self { reliable }
+    }
+}
 
-impl Encoder<TransportMessage> for TransportMessageCodec {
+impl Encoder<UdpPacket> for TransportMessageCodec {
     type Error = TransportError;
-    fn encode(&mut self, item: TransportMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let msg_buf = item.encode().map_err(|_| TransportError::SendBadMessage)?;
+    fn encode(&mut self, item: UdpPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (mode, msg_buf) = match item {
+            UdpPacket::Message(msg) if !self.reliable => (MESSAGE_MODE, msg.encode()),
+            UdpPacket::Reliable(packet) if self.reliable => (RELIABLE_MODE, packet.encode()),
+            _ => return Err(TransportError::SendBadMessage),
+        };
+        let msg_buf = msg_buf.map_err(|_| TransportError::SendBadMessage)?;
         let len = msg_buf.len();
+        if len > u16::MAX as usize {
+            return Err(TransportError::Capacity);
+        }
+        dst.put_u8(mode);
         dst.put_u16(len as u16);
         dst.put(&msg_buf[..]);
         Ok(())
@@ -18,17 +89,86 @@ impl Encoder<TransportMessage> for TransportMessageCodec {
 }
 
 impl Decoder for TransportMessageCodec {
-    type Item = TransportMessage;
+    type Item = UdpPacket;
     type Error = TransportError;
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         if src.is_empty() {
             return Ok(None);
         }
+        if src.len() < 3 {
+            src.clear();
+            return Err(TransportError::RecvBadMessage);
+        }
 
+        let mode = src.get_u8();
         let len = src.get_u16() as usize;
-        let msg = TransportMessage::decode(&src.split_to(len)[..])
-            .map_err(|_| TransportError::RecvBadMessage)?;
+        if len > src.len() {
+            src.clear();
+            return Err(TransportError::RecvBadMessage);
+        }
+        let buf = src.split_to(len);
+        let packet = match mode {
+            MESSAGE_MODE => UdpPacket::Message(
+                TransportMessage::decode(&buf[..]).map_err(|_| TransportError::RecvBadMessage)?,
+            ),
+            RELIABLE_MODE => UdpPacket::Reliable(
+                ReliablePacket::decode(&buf[..]).map_err(|_| TransportError::RecvBadMessage)?,
+            ),
+            _ => return Err(TransportError::RecvBadMessage),
+        };
+
+        Ok(Some(packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::route;
+
+    #[test]
+    fn test_reliable_packets_round_trip() {
+        let mut codec = TransportMessageCodec::new(true);
+        let packet = UdpPacket::Reliable(ReliablePacket::Fragment(Fragment {
+            session: 1,
+            sequence: 5,
+            base: 4,
+            index: 1,
+            count: 2,
+            payload: vec![1, 2, 3],
+        }));
+        let mut buf = BytesMut::new();
+        codec.encode(packet.clone(), &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(packet));
+
+        // a plain message can not be sent in reliable mode
+        let message = TransportMessage::v1(route!["a"], route!["b"], vec![]);
+        assert!(codec
+            .encode(UdpPacket::Message(message), &mut BytesMut::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_truncated_datagram_is_rejected() {
+        let mut codec = TransportMessageCodec::new(false);
+        let mut buf = BytesMut::from(&[MESSAGE_MODE, 0, 10, 1, 2][..]);
+        assert!(codec.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_datagram_of_the_other_mode_is_decoded_with_its_mode() {
+        let message = UdpPacket::Message(TransportMessage::v1(route!["a"], route!["b"], vec![]));
+        let mut buf = BytesMut::new();
+        TransportMessageCodec::new(false)
+            .encode(message.clone(), &mut buf)
+            .unwrap();
+        assert_eq!(buf[0], MESSAGE_MODE);
+
+        // a reliable peer sees that the datagram was sent without the reliable mode
+        let mut codec = TransportMessageCodec::new(true);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(message));
 
-        Ok(Some(msg))
+        let mut buf = BytesMut::from(&[7, 0, 0][..]);
+        assert!(codec.decode(&mut buf).is_err());
     }
 }
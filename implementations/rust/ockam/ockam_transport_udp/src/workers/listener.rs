@@ -1,11 +1,16 @@
-use super::TransportMessageCodec;
-use crate::UDP;
+use super::{ReliablePacket, ReliableReceiver, TransportMessageCodec, UdpPacket, UdpSendWorkerMsg};
+use crate::{UdpTransportOptions, UDP};
 use futures_util::stream::SplitStream;
 use futures_util::StreamExt;
-use ockam_core::{async_trait, route, Address, AllowAll, LocalMessage, Processor, Result};
+use ockam_core::{
+    async_trait, route, Address, AllowAll, Decodable, LocalMessage, Processor, Result,
+    TransportMessage,
+};
 use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use std::net::SocketAddr;
 use tokio_util::udp::UdpFramed;
-use tracing::{debug, warn};
+use tracing::{debug, error, warn};
 
 /// A listener for the UDP transport
 ///
@@ -15,31 +20,96 @@ use tracing::{debug, warn};
 /// When a message is received, the address of the paired sender
 /// ([`UdpSendWorker`](crate::workers::UdpSendWorker)) is injected into the message's
 /// return route so that replies are sent to the sender.
+///
+/// In reliable mode, the listener reassembles and orders the messages, and asks the
+/// sender to acknowledge the received fragments
 pub(crate) struct UdpListenProcessor {
     /// The read half of the udnerlying UDP socket.
     stream: SplitStream<UdpFramed<TransportMessageCodec>>,
     /// Address of our sender counterpart
     sender_addr: Address,
+    /// Internal address of our sender counterpart, in reliable mode
+    sender_internal_addr: Address,
+    receiver: Option<ReliableReceiver>,
 }
 
 impl UdpListenProcessor {
     pub(crate) async fn start(
         ctx: &Context,
+        address: Address,
         stream: SplitStream<UdpFramed<TransportMessageCodec>>,
         sender_addr: Address,
+        sender_internal_addr: Address,
+        options: &UdpTransportOptions,
     ) -> Result<()> {
         let processor = Self {
             stream,
             sender_addr,
+            sender_internal_addr,
+            receiver: options
+                .reliable
+                .then(|| ReliableReceiver::new(options.clone())),
         };
-        let addr = Address::random_tagged("UdpListenProcessor");
 
         // FIXME: @ac
-        ctx.start_processor_with_access_control(addr.clone(), processor, AllowAll, AllowAll)
+        ctx.start_processor_with_access_control(address, processor, AllowAll, AllowAll)
             .await?;
 
         Ok(())
     }
+
+    /// Forward a message received from a peer to its next hop
+    async fn forward(
+        &self,
+        ctx: &Context,
+        mut msg: TransportMessage,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        // Set return route to go directly to paired sender, skipping the UDP router
+        msg.return_route = route![
+            self.sender_addr.clone(),
+            Address::new(UDP, addr.to_string()),
+            msg.return_route
+        ];
+
+        debug!(onward_route = %msg.onward_route,
+            return_route = %msg.return_route,
+            "Forwarding UDP message");
+        ctx.forward(LocalMessage::new(msg, vec![])).await
+    }
+
+    /// Handle a datagram of the reliable mode
+    async fn handle_reliable(
+        &mut self,
+        ctx: &Context,
+        packet: ReliablePacket,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let receiver = match &mut self.receiver {
+            Some(receiver) => receiver,
+            None => return Err(TransportError::Protocol.into()),
+        };
+
+        let notification = match packet {
+            ReliablePacket::Ack(ack) => UdpSendWorkerMsg::Acknowledged { peer: addr, ack },
+            ReliablePacket::Fragment(fragment) => {
+                let received = receiver.receive(addr, fragment);
+                for message in received.messages {
+                    match TransportMessage::decode(&message) {
+                        Ok(msg) => self.forward(ctx, msg, addr).await?,
+                        Err(e) => warn!("Failed to decode message from {}: {}", addr, e),
+                    }
+                }
+                match received.ack {
+                    Some(ack) => UdpSendWorkerMsg::SendAck { peer: addr, ack },
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        ctx.send(self.sender_internal_addr.clone(), notification)
+            .await
+    }
 }
 
 #[async_trait]
@@ -52,9 +122,9 @@ impl Processor for UdpListenProcessor {
 
     async fn process(&mut self, ctx: &mut Self::Context) -> Result<bool> {
         debug!("Waiting for incoming UDP datagram...");
-        let (mut msg, addr) = match self.stream.next().await {
+        let (packet, addr) = match self.stream.next().await {
             Some(res) => match res {
-                Ok((msg, addr)) => (msg, addr),
+                Ok((packet, addr)) => (packet, addr),
                 Err(e) => {
                     warn!(
                         "Failed to read message, will wait for next message: {:?}",
@@ -69,17 +139,22 @@ impl Processor for UdpListenProcessor {
             }
         };
 
-        // Set return route to go directly to paired sender, skipping the UDP router
-        msg.return_route = route![
-            self.sender_addr.clone(),
-            Address::new(UDP, addr.to_string()),
-            msg.return_route
-        ];
-
-        debug!(onward_route = %msg.onward_route,
-            return_route = %msg.return_route,
-            "Forwarding UDP message");
-        ctx.forward(LocalMessage::new(msg, vec![])).await?;
+        match packet {
+            UdpPacket::Message(msg) if self.receiver.is_none() => {
+                self.forward(ctx, msg, addr).await?
+            }
+            UdpPacket::Reliable(packet) if self.receiver.is_some() => {
+                self.handle_reliable(ctx, packet, addr).await?
+            }
+            UdpPacket::Message(_) => error!(
+                "Dropping a datagram from {}: the peer does not use the reliable mode of this transport",
+                addr
+            ),
+            UdpPacket::Reliable(_) => error!(
+                "Dropping a datagram from {}: the peer uses the reliable mode, which is not enabled on this transport",
+                addr
+            ),
+        }
 
         Ok(true)
     }
@@ -2,8 +2,10 @@
 
 pub(crate) use codec::*;
 pub(crate) use listener::*;
+pub(crate) use reliable::*;
 pub(crate) use sender::*;
 
 mod codec;
 mod listener;
+mod reliable;
 mod sender;
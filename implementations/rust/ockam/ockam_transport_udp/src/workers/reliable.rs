@@ -0,0 +1,386 @@
+use super::{Ack, Fragment};
+use crate::UdpTransportOptions;
+use ockam_core::Result;
+use ockam_transport_core::TransportError;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Instant;
+use tracing::warn;
+
+/// Sending half of the reliable mode.
+///
+/// The messages sent to each peer are split into fragments with consecutive sequence numbers.
+/// At most `send_window` fragments are waiting for an acknowledgement, the next ones are queued
+pub(crate) struct ReliableSender {
+    options: UdpTransportOptions,
+    peers: HashMap<SocketAddr, PeerSender>,
+}
+
+struct PeerSender {
+    session: u64,
+    next_sequence: u64,
+    in_flight: BTreeMap<u64, InFlight>,
+    queued: VecDeque<Fragment>,
+}
+
+struct InFlight {
+    fragment: Fragment,
+    sent_at: Instant,
+    retransmissions: u32,
+}
+
+impl PeerSender {
+    fn new() -> Self {
+        Self {
+            session: rand::random(),
+            next_sequence: 0,
+            in_flight: BTreeMap::new(),
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// First sequence number of the oldest message which is not fully acknowledged
+    fn base(&self) -> u64 {
+        self.in_flight
+            .values()
+            .map(|f| &f.fragment)
+            .chain(self.queued.iter())
+            .next()
+            .map(|f| f.first_sequence())
+            .unwrap_or(self.next_sequence)
+    }
+
+    /// Move the queued fragments to the window, and return them to be sent
+    fn release(&mut self, window: usize, now: Instant) -> Vec<Fragment> {
+        let mut released = vec![];
+        while self.in_flight.len() < window {
+            let mut fragment = match self.queued.pop_front() {
+                Some(fragment) => fragment,
+                None => break,
+            };
+            fragment.base = self.base().min(fragment.first_sequence());
+            self.in_flight.insert(
+                fragment.sequence,
+                InFlight {
+                    fragment: fragment.clone(),
+                    sent_at: now,
+                    retransmissions: 0,
+                },
+            );
+            released.push(fragment);
+        }
+        released
+    }
+}
+
+impl ReliableSender {
+    pub(crate) fn new(options: UdpTransportOptions) -> Self {
+        Self {
+            options,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Split an encoded message into fragments, and return the fragments which can be sent now
+    pub(crate) fn send(
+        &mut self,
+        peer: SocketAddr,
+        encoded: &[u8],
+        now: Instant,
+    ) -> Result<Vec<Fragment>> {
+        if encoded.len() > self.options.max_message_size {
+            return Err(TransportError::Capacity.into());
+        }
+
+        let state = self.peers.entry(peer).or_insert_with(PeerSender::new);
+        let chunks: Vec<&[u8]> = if encoded.is_empty() {
+            vec![encoded]
+        } else {
+            encoded.chunks(self.options.fragment_size).collect()
+        };
+        let count = chunks.len() as u32;
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            state.queued.push_back(Fragment {
+                session: state.session,
+                sequence: state.next_sequence,
+                base: 0,
+                index: index as u32,
+                count,
+                payload: chunk.to_vec(),
+            });
+            state.next_sequence += 1;
+        }
+
+        Ok(state.release(self.options.send_window, now))
+    }
+
+    /// Handle the acknowledgement of a fragment, and return the fragments which can be sent now
+    pub(crate) fn acknowledge(
+        &mut self,
+        peer: SocketAddr,
+        ack: &Ack,
+        now: Instant,
+    ) -> Vec<Fragment> {
+        match self.peers.get_mut(&peer) {
+            Some(state) if state.session == ack.session => {
+                state.in_flight.remove(&ack.sequence);
+                state.release(self.options.send_window, now)
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Return the fragments which were not acknowledged in time, to be sent again.
+    ///
+    /// When a fragment was sent too many times, the peer is considered unreachable: its
+    /// pending messages are dropped and the next messages start a new session
+    pub(crate) fn retransmit(&mut self, now: Instant) -> Vec<(SocketAddr, Fragment)> {
+        let mut retransmitted = vec![];
+        let mut unreachable = vec![];
+        for (peer, state) in self.peers.iter_mut() {
+            let base = state.base();
+            for in_flight in state.in_flight.values_mut() {
+                if now.duration_since(in_flight.sent_at) < self.options.retransmission_timeout {
+                    continue;
+                }
+                if in_flight.retransmissions >= self.options.max_retransmissions {
+                    unreachable.push(*peer);
+                    break;
+                }
+                in_flight.retransmissions += 1;
+                in_flight.sent_at = now;
+                in_flight.fragment.base = base;
+                retransmitted.push((*peer, in_flight.fragment.clone()));
+            }
+        }
+
+        for peer in unreachable {
+            warn!(
+                "UDP peer {} did not acknowledge the messages sent to it, dropping them",
+                peer
+            );
+            self.peers.remove(&peer);
+            retransmitted.retain(|(p, _)| *p != peer);
+        }
+        retransmitted
+    }
+
+    /// Return true if some fragments are waiting for an acknowledgement
+    pub(crate) fn has_in_flight(&self) -> bool {
+        self.peers.values().any(|s| !s.in_flight.is_empty())
+    }
+}
+
+/// Receiving half of the reliable mode.
+///
+/// The fragments received from each peer are acknowledged, and the messages are delivered
+/// in order once all their fragments are received
+pub(crate) struct ReliableReceiver {
+    options: UdpTransportOptions,
+    peers: HashMap<SocketAddr, PeerReceiver>,
+}
+
+struct PeerReceiver {
+    session: u64,
+    next_sequence: u64,
+    received: BTreeMap<u64, Fragment>,
+}
+
+/// Result of the reception of a fragment
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Received {
+    /// The fragment must be acknowledged
+    pub(crate) ack: Option<Ack>,
+    /// Encoded messages to deliver, in order
+    pub(crate) messages: Vec<Vec<u8>>,
+}
+
+impl ReliableReceiver {
+    pub(crate) fn new(options: UdpTransportOptions) -> Self {
+        Self {
+            options,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Number of sequence numbers which can be buffered ahead of the next message to deliver
+    fn receive_window(&self) -> u64 {
+        (self.options.send_window + self.options.max_fragments()) as u64
+    }
+
+    pub(crate) fn receive(&mut self, peer: SocketAddr, fragment: Fragment) -> Received {
+        let receive_window = self.receive_window();
+        let max_fragments = self.options.max_fragments() as u32;
+
+        let state = self.peers.entry(peer).or_insert_with(|| PeerReceiver {
+            session: fragment.session,
+            next_sequence: fragment.base,
+            received: BTreeMap::new(),
+        });
+        if state.session != fragment.session {
+            // The peer restarted, or gave up on its previous messages
+            *state = PeerReceiver {
+                session: fragment.session,
+                next_sequence: fragment.base,
+                received: BTreeMap::new(),
+            };
+        }
+
+        if fragment.count == 0 || fragment.index >= fragment.count || fragment.count > max_fragments
+        {
+            warn!("Dropping an invalid UDP fragment from {}", peer);
+            return Received::default();
+        }
+
+        let ack = Some(Ack {
+            session: fragment.session,
+            sequence: fragment.sequence,
+        });
+        // Already delivered, the acknowledgement was probably lost
+        if fragment.sequence < state.next_sequence {
+            return Received {
+                ack,
+                messages: vec![],
+            };
+        }
+        // Too far ahead, the peer will send it again
+        if fragment.sequence >= state.next_sequence + receive_window {
+            return Received::default();
+        }
+        state.received.insert(fragment.sequence, fragment);
+
+        let mut messages = vec![];
+        while let Some(first) = state.received.get(&state.next_sequence) {
+            if first.index != 0 {
+                warn!("Dropping an incomplete UDP message from {}", peer);
+                state.received.remove(&state.next_sequence);
+                state.next_sequence += 1;
+                continue;
+            }
+            let count = first.count as u64;
+            let start = state.next_sequence;
+            if !(start..start + count).all(|s| state.received.contains_key(&s)) {
+                break;
+            }
+            let mut message = vec![];
+            for sequence in start..start + count {
+                if let Some(fragment) = state.received.remove(&sequence) {
+                    message.extend_from_slice(&fragment.payload);
+                }
+            }
+            messages.push(message);
+            state.next_sequence = start + count;
+        }
+
+        Received { ack, messages }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    fn options() -> UdpTransportOptions {
+        UdpTransportOptions::new()
+            .with_reliable_delivery()
+            .with_fragment_size(4)
+            .with_max_message_size(64)
+            .with_send_window(4)
+            .with_max_retransmissions(2)
+    }
+
+    fn peer() -> SocketAddr {
+        "127.0.0.1:4000".parse().unwrap()
+    }
+
+    #[test]
+    fn test_messages_are_fragmented_and_reordered() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(options());
+        let mut receiver = ReliableReceiver::new(options());
+
+        let fragments1 = sender.send(peer(), b"hello world", now).unwrap();
+        assert_eq!(fragments1.len(), 3);
+        // only one fragment fits in the window, the others are queued
+        let fragments2 = sender.send(peer(), b"bye!", now).unwrap();
+        assert_eq!(fragments2.len(), 1);
+
+        // the fragments are received in reverse order
+        let mut messages = vec![];
+        for fragment in fragments2
+            .into_iter()
+            .chain(fragments1.clone().into_iter().rev())
+        {
+            let received = receiver.receive(peer(), fragment);
+            assert!(received.ack.is_some());
+            messages.extend(received.messages);
+        }
+        assert_eq!(messages, vec![b"hello world".to_vec(), b"bye!".to_vec()]);
+
+        // a duplicate is acknowledged again but not delivered
+        let duplicate = receiver.receive(peer(), fragments1[0].clone());
+        assert!(duplicate.ack.is_some());
+        assert!(duplicate.messages.is_empty());
+    }
+
+    #[test]
+    fn test_acknowledgements_release_the_window() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(options());
+        let sent = sender.send(peer(), &[0; 24], now).unwrap();
+        assert_eq!(sent.len(), 4);
+
+        let ack = Ack {
+            session: sent[0].session,
+            sequence: sent[0].sequence,
+        };
+        let released = sender.acknowledge(peer(), &ack, now);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].sequence, 4);
+        // the first message is not fully acknowledged yet
+        assert_eq!(released[0].base, 0);
+    }
+
+    #[test]
+    fn test_lost_fragments_are_retransmitted_then_dropped() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(options());
+        let sent = sender.send(peer(), b"hi", now).unwrap();
+        assert!(sender.retransmit(now).is_empty());
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(sender.retransmit(later), vec![(peer(), sent[0].clone())]);
+        let later = later + Duration::from_secs(1);
+        assert_eq!(sender.retransmit(later).len(), 1);
+
+        // the peer is unreachable, the next messages start a new session
+        let later = later + Duration::from_secs(1);
+        assert!(sender.retransmit(later).is_empty());
+        assert!(!sender.has_in_flight());
+        let next = sender.send(peer(), b"hi", later).unwrap();
+        assert_ne!(next[0].session, sent[0].session);
+        assert_eq!(next[0].sequence, 0);
+    }
+
+    #[test]
+    fn test_receiver_joins_an_existing_session() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(options());
+        let first = sender.send(peer(), b"one", now).unwrap();
+        sender.acknowledge(
+            peer(),
+            &Ack {
+                session: first[0].session,
+                sequence: first[0].sequence,
+            },
+            now,
+        );
+
+        // a restarted receiver starts from the base of the fragments it receives
+        let mut receiver = ReliableReceiver::new(options());
+        let second = sender.send(peer(), b"two", now).unwrap();
+        let received = receiver.receive(peer(), second[0].clone());
+        assert_eq!(received.messages, vec![b"two".to_vec()]);
+    }
+}
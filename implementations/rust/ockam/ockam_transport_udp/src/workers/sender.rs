@@ -1,28 +1,171 @@
-use super::TransportMessageCodec;
-use crate::UDP;
+use super::{Ack, Fragment, ReliablePacket, ReliableSender, TransportMessageCodec, UdpPacket};
+use crate::{UdpTransportOptions, UDP};
 use futures_util::{stream::SplitSink, SinkExt};
-use ockam_core::{async_trait, Any, Result, Routed, TransportMessage, Worker};
-use ockam_node::Context;
+use ockam_core::{
+    async_trait, Address, AllowAll, AllowSourceAddress, Any, Decodable, DenyAll, Encodable,
+    Mailbox, Mailboxes, Message, Result, Routed, Worker,
+};
+use ockam_node::{Context, DelayedEvent, WorkerBuilder};
 use ockam_transport_core::TransportError;
+use serde::{Deserialize, Serialize};
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Instant;
 use tokio_util::udp::UdpFramed;
 use tracing::{error, trace, warn};
 
+/// Message sent by the paired [`UdpListenProcessor`](crate::workers::UdpListenProcessor)
+/// to the sender, in reliable mode
+#[derive(Serialize, Deserialize, Message)]
+pub(crate) enum UdpSendWorkerMsg {
+    /// Acknowledge a fragment received from a peer
+    SendAck { peer: SocketAddr, ack: Ack },
+    /// A peer acknowledged a fragment
+    Acknowledged { peer: SocketAddr, ack: Ack },
+}
+
+/// State of the reliable mode of a [`UdpSendWorker`]
+struct Reliability {
+    sender: ReliableSender,
+    internal_address: Address,
+    retransmit_address: Address,
+    retransmit: DelayedEvent<Vec<u8>>,
+    retransmit_scheduled: bool,
+    options: UdpTransportOptions,
+}
+
 /// A sender for the UDP transport
 ///
 /// This worker handles the sending of messages on a
 /// local socket. See [`UdpRouter`](crate::router::UdpRouter) for more details.
 pub(crate) struct UdpSendWorker {
     /// The read half of the udnerlying UDP socket.
-    sink: SplitSink<UdpFramed<TransportMessageCodec>, (TransportMessage, SocketAddr)>,
+    sink: SplitSink<UdpFramed<TransportMessageCodec>, (UdpPacket, SocketAddr)>,
+    reliability: Option<Reliability>,
 }
 
 impl UdpSendWorker {
-    /// Create a new `UdpSendWorker`
-    pub(crate) fn new(
-        sink: SplitSink<UdpFramed<TransportMessageCodec>, (TransportMessage, SocketAddr)>,
-    ) -> Self {
-        Self { sink }
+    /// Create and start a new `UdpSendWorker`.
+    ///
+    /// In reliable mode, the sender receives the acknowledgements handled by the
+    /// listener on its internal address
+    pub(crate) async fn start(
+        ctx: &Context,
+        address: Address,
+        internal_address: Address,
+        listener_address: Address,
+        sink: SplitSink<UdpFramed<TransportMessageCodec>, (UdpPacket, SocketAddr)>,
+        options: &UdpTransportOptions,
+    ) -> Result<()> {
+        let mut additional_mailboxes = vec![];
+        let reliability = if options.reliable {
+            let retransmit_address = Address::random_tagged("UdpSendWorker.retransmit");
+            let retransmit = DelayedEvent::create(ctx, retransmit_address.clone(), vec![]).await?;
+            additional_mailboxes.push(Mailbox::new(
+                internal_address.clone(),
+                Arc::new(AllowSourceAddress(listener_address)),
+                Arc::new(DenyAll),
+            ));
+            additional_mailboxes.push(Mailbox::new(
+                retransmit_address.clone(),
+                Arc::new(AllowSourceAddress(retransmit.address())),
+                Arc::new(DenyAll),
+            ));
+            Some(Reliability {
+                sender: ReliableSender::new(options.clone()),
+                internal_address,
+                retransmit_address,
+                retransmit,
+                retransmit_scheduled: false,
+                options: options.clone(),
+            })
+        } else {
+            None
+        };
+
+        // FIXME: @ac
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(address, Arc::new(AllowAll), Arc::new(AllowAll)),
+            additional_mailboxes,
+        );
+        WorkerBuilder::new(Self { sink, reliability })
+            .with_mailboxes(mailboxes)
+            .start(ctx)
+            .await
+    }
+
+    async fn send_packet(&mut self, packet: UdpPacket, addr: SocketAddr) -> Result<()> {
+        match self.sink.send((packet, addr)).await {
+            Ok(()) => {
+                trace!("Successful send to {}", addr);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed send to {}: {:?}", addr, e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn send_fragments(&mut self, fragments: Vec<(SocketAddr, Fragment)>) -> Result<()> {
+        for (addr, fragment) in fragments {
+            self.send_packet(
+                UdpPacket::Reliable(ReliablePacket::Fragment(fragment)),
+                addr,
+            )
+            .await?;
+        }
+        self.schedule_retransmit().await
+    }
+
+    /// Check the acknowledgements after the retransmission timeout, while fragments are in flight
+    async fn schedule_retransmit(&mut self) -> Result<()> {
+        if let Some(reliability) = &mut self.reliability {
+            if !reliability.retransmit_scheduled && reliability.sender.has_in_flight() {
+                reliability
+                    .retransmit
+                    .schedule(reliability.options.retransmission_timeout)
+                    .await?;
+                reliability.retransmit_scheduled = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the messages of the reliable mode, return false for messages to send to a peer
+    async fn handle_reliability_message(&mut self, msg: &Routed<Any>) -> Result<bool> {
+        let reliability = match &mut self.reliability {
+            Some(reliability) => reliability,
+            None => return Ok(false),
+        };
+
+        if msg.msg_addr() == reliability.retransmit_address {
+            reliability.retransmit_scheduled = false;
+            let fragments = reliability.sender.retransmit(Instant::now());
+            self.send_fragments(fragments).await?;
+            return Ok(true);
+        }
+
+        if msg.msg_addr() == reliability.internal_address {
+            match UdpSendWorkerMsg::decode(msg.payload())? {
+                UdpSendWorkerMsg::SendAck { peer, ack } => {
+                    self.send_packet(UdpPacket::Reliable(ReliablePacket::Ack(ack)), peer)
+                        .await?;
+                }
+                UdpSendWorkerMsg::Acknowledged { peer, ack } => {
+                    let fragments = reliability
+                        .sender
+                        .acknowledge(peer, &ack, Instant::now())
+                        .into_iter()
+                        .map(|f| (peer, f))
+                        .collect();
+                    self.send_fragments(fragments).await?;
+                }
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
     }
 }
 
@@ -36,6 +179,10 @@ impl Worker for UdpSendWorker {
         _ctx: &mut Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
+        if self.handle_reliability_message(&msg).await? {
+            return Ok(());
+        }
+
         // Parse message and remove our address from its routing
         let mut msg = msg.into_transport_message();
         msg.onward_route.step()?;
@@ -74,15 +221,18 @@ impl Worker for UdpSendWorker {
         }
 
         // Send
-        match self.sink.send((msg.clone(), addr)).await {
-            Ok(()) => {
-                trace!("Successful send to {}", addr);
-                Ok(())
-            }
-            Err(e) => {
-                error!("Failed send to {}: {:?}", addr, e);
-                Err(e.into())
+        match &mut self.reliability {
+            Some(reliability) => {
+                let encoded = msg.encode().map_err(|_| TransportError::SendBadMessage)?;
+                let fragments = reliability
+                    .sender
+                    .send(addr, &encoded, Instant::now())?
+                    .into_iter()
+                    .map(|f| (addr, f))
+                    .collect();
+                self.send_fragments(fragments).await
             }
+            None => self.send_packet(UdpPacket::Message(msg), addr).await,
         }
     }
 }
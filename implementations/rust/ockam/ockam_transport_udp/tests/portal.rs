@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+use ockam_core::compat::rand::random;
+use ockam_core::{route, Result};
+use ockam_node::Context;
+use ockam_transport_udp::{UdpInletOptions, UdpOutletOptions, UdpTransport};
+
+const LENGTH: usize = 32;
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Start an outlet to a UDP server echoing every datagram, and an inlet to that outlet
+async fn setup(ctx: &Context, idle_timeout: Duration) -> Result<String> {
+    let udp = UdpTransport::create(ctx).await?;
+
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_address = server.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1024];
+        loop {
+            let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+            server.send_to(&buf[..len], peer).await.unwrap();
+        }
+    });
+
+    udp.create_outlet(
+        "outlet",
+        server_address,
+        UdpOutletOptions::new().with_idle_timeout(idle_timeout),
+    )
+    .await?;
+
+    let (inlet_address, _) = udp
+        .create_inlet(
+            "127.0.0.1:0",
+            route!["outlet"],
+            UdpInletOptions::new().with_idle_timeout(idle_timeout),
+        )
+        .await?;
+
+    Ok(inlet_address.to_string())
+}
+
+async fn send_receive(client: &UdpSocket, inlet_address: &str) {
+    let payload: [u8; LENGTH] = random();
+    client.send_to(&payload, inlet_address).await.unwrap();
+
+    let mut buf = [0u8; 1024];
+    let (len, from) = timeout(TIMEOUT, client.recv_from(&mut buf))
+        .await
+        .expect("the datagram should be echoed")
+        .unwrap();
+    assert_eq!(from.to_string(), inlet_address);
+    assert_eq!(&buf[..len], &payload);
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 10000)]
+async fn portal__several_clients__should_use_separate_flows(ctx: &mut Context) -> Result<()> {
+    let inlet_address = setup(ctx, Duration::from_secs(60)).await?;
+
+    let client1 = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client2 = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    send_receive(&client1, &inlet_address).await;
+    send_receive(&client2, &inlet_address).await;
+    send_receive(&client1, &inlet_address).await;
+    send_receive(&client2, &inlet_address).await;
+
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 15000)]
+async fn portal__idle_flow__should_be_closed_and_reopened(ctx: &mut Context) -> Result<()> {
+    let inlet_address = setup(ctx, Duration::from_secs(1)).await?;
+
+    let initial_workers = ctx.list_workers().await?;
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    send_receive(&client, &inlet_address).await;
+
+    // the inlet and the outlet flows are closed after being idle
+    tokio::time::sleep(Duration::from_secs(4)).await;
+    let mut additional_workers = ctx.list_workers().await?;
+    additional_workers.retain(|w| !initial_workers.contains(w));
+    assert!(additional_workers.is_empty());
+
+    // a new flow is created for the same client
+    send_receive(&client, &inlet_address).await;
+
+    ctx.stop().await
+}
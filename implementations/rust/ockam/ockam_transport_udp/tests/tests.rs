@@ -1,7 +1,7 @@
 use ockam_core::compat::rand::{self, Rng};
 use ockam_core::{route, Address, AllowAll, Result, Routed, Worker};
 use ockam_node::{Context, MessageReceiveOptions, MessageSendReceiveOptions};
-use ockam_transport_udp::{UdpTransport, UDP};
+use ockam_transport_udp::{UdpTransport, UdpTransportOptions, UDP};
 use std::net::SocketAddr;
 use std::time::Duration;
 use tracing::{debug, error, trace};
@@ -192,6 +192,52 @@ async fn send_receive(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
+/// In reliable mode, messages larger than a datagram are fragmented and delivered in order
+#[ockam_macros::test]
+async fn send_receive_reliable(ctx: &mut Context) -> Result<()> {
+    // Find an available port
+    let bind_addr = utils::available_local_ports(1)
+        .await?
+        .first()
+        .unwrap()
+        .to_string();
+
+    // Transport
+    let options = UdpTransportOptions::new().with_reliable_delivery();
+    let transport = UdpTransport::create_with_options(ctx, options).await?;
+
+    // Listener
+    {
+        ctx.start_worker("echoer", Echoer::new()).await?;
+        transport.listen(bind_addr.clone()).await?;
+    };
+
+    // Sender
+    {
+        for len in [16, 100_000] {
+            let msg: String = rand::thread_rng()
+                .sample_iter(&rand::distributions::Alphanumeric)
+                .take(len)
+                .map(char::from)
+                .collect();
+            let r = route![(UDP, bind_addr.clone()), "echoer"];
+            let reply = ctx
+                .send_and_receive_extended::<String>(
+                    r,
+                    msg.clone(),
+                    MessageSendReceiveOptions::new().with_timeout(TIMEOUT),
+                )
+                .await?
+                .body();
+
+            assert_eq!(reply, msg, "Should receive the same message");
+        }
+    };
+
+    ctx.stop().await?;
+    Ok(())
+}
+
 pub struct Echoer {
     prev_src_addr: Option<String>,
 }
@@ -32,6 +32,16 @@ pub enum VaultError {
     InvalidSha256Len,
     /// Invalid Signature Size
     InvalidSignatureSize,
+    /// KEM key generation failed
+    KemKeyGeneration,
+    /// KEM encapsulation failed
+    KemEncapsulate,
+    /// KEM decapsulation failed
+    KemDecapsulate,
+    /// ChaCha20-Poly1305 encryption failed
+    AeadChaCha20Poly1305Encrypt,
+    /// ChaCha20-Poly1305 decryption failed
+    AeadChaCha20Poly1305Decrypt,
 }
 
 impl ockam_core::compat::error::Error for VaultError {}
@@ -54,6 +64,11 @@ impl core::fmt::Display for VaultError {
             Self::KeyNotFound => write!(f, "key not found"),
             Self::InvalidSha256Len => write!(f, "invalid sha256 len"),
             Self::InvalidSignatureSize => write!(f, "invalid signature len"),
+            Self::KemKeyGeneration => write!(f, "kem key generation failed"),
+            Self::KemEncapsulate => write!(f, "kem encapsulation failed"),
+            Self::KemDecapsulate => write!(f, "kem decapsulation failed"),
+            Self::AeadChaCha20Poly1305Encrypt => write!(f, "chacha20-poly1305 encryption failed"),
+            Self::AeadChaCha20Poly1305Decrypt => write!(f, "chacha20-poly1305 decryption failed"),
         }
     }
 }
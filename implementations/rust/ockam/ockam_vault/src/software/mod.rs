@@ -1,4 +1,4 @@
-mod secure_channel_vault;
+pub(crate) mod secure_channel_vault;
 mod signing_vault;
 mod verifying_vault;
 
@@ -12,7 +12,7 @@ use aes_gcm::aes::{Aes128, Aes256};
 use aes_gcm::{AeadCore, AeadInPlace, Aes128Gcm, Aes256Gcm, AesGcm};
 
 /// Depending on the secret type make the right type of encrypting / decrypting algorithm
-pub(super) fn make_aes(stored_secret: &StoredSecret) -> Result<AesGen> {
+pub(crate) fn make_aes(stored_secret: &StoredSecret) -> Result<AesGen> {
     let secret_ref = stored_secret.secret().as_ref();
 
     match stored_secret.attributes() {
@@ -0,0 +1,49 @@
+use crate::constants::{
+    CHACHA20_POLY1305_NONCE_LENGTH_USIZE, CHACHA20_POLY1305_SECRET_LENGTH_U32,
+    CHACHA20_POLY1305_SECRET_LENGTH_USIZE,
+};
+use crate::{Buffer, SecretAttributes, StoredSecret, VaultError};
+
+use ockam_core::Result;
+
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+/// Make a ChaCha20-Poly1305 cipher from a 32 bytes buffer secret
+pub(crate) fn make_chacha(stored_secret: &StoredSecret) -> Result<ChaChaGen> {
+    let secret_ref = stored_secret.secret().as_ref();
+
+    if stored_secret.attributes() != SecretAttributes::Buffer(CHACHA20_POLY1305_SECRET_LENGTH_U32)
+        || secret_ref.len() != CHACHA20_POLY1305_SECRET_LENGTH_USIZE
+    {
+        return Err(VaultError::AeadChaCha20Poly1305Encrypt.into());
+    }
+
+    Ok(ChaChaGen(ChaCha20Poly1305::new(Key::from_slice(
+        secret_ref,
+    ))))
+}
+
+pub(crate) struct ChaChaGen(ChaCha20Poly1305);
+
+impl ChaChaGen {
+    pub fn encrypt_message(&self, msg: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Buffer<u8>> {
+        if nonce.len() != CHACHA20_POLY1305_NONCE_LENGTH_USIZE {
+            return Err(VaultError::AeadChaCha20Poly1305Encrypt.into());
+        }
+
+        self.0
+            .encrypt(Nonce::from_slice(nonce), Payload { aad, msg })
+            .map_err(|_| VaultError::AeadChaCha20Poly1305Encrypt.into())
+    }
+
+    pub fn decrypt_message(&self, msg: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Buffer<u8>> {
+        if nonce.len() != CHACHA20_POLY1305_NONCE_LENGTH_USIZE {
+            return Err(VaultError::AeadChaCha20Poly1305Decrypt.into());
+        }
+
+        self.0
+            .decrypt(Nonce::from_slice(nonce), Payload { aad, msg })
+            .map_err(|_| VaultError::AeadChaCha20Poly1305Decrypt.into())
+    }
+}
@@ -1,4 +1,5 @@
 pub(crate) mod aes;
+pub(crate) mod chacha;
 
 #[allow(clippy::module_inception)]
 mod secure_channel_vault;
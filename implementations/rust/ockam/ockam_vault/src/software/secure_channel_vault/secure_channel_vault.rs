@@ -1,7 +1,10 @@
 use super::aes::make_aes;
+use super::chacha::make_chacha;
 
 use crate::constants::{
-    X25519_PUBLIC_LENGTH_USIZE, X25519_SECRET_LENGTH_U32, X25519_SECRET_LENGTH_USIZE,
+    KYBER768_CIPHERTEXT_LENGTH_USIZE, KYBER768_PUBLIC_KEY_LENGTH_USIZE,
+    KYBER768_SECRET_KEY_LENGTH_U32, KYBER768_SHARED_SECRET_LENGTH_U32, X25519_PUBLIC_LENGTH_USIZE,
+    X25519_SECRET_LENGTH_U32, X25519_SECRET_LENGTH_USIZE,
 };
 use crate::{
     Buffer, KeyId, PublicKey, Secret, SecretAttributes, SecretType, SecureChannelVault,
@@ -397,4 +400,152 @@ impl SecureChannelVault for SoftwareSecureChannelVault {
         let aes = make_aes(&stored_secret)?;
         aes.decrypt_message(cipher_text, nonce, aad)
     }
+
+    async fn aead_chacha20_poly1305_encrypt(
+        &self,
+        key_id: &KeyId,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        let stored_secret = self.get_secret(key_id).await?;
+        let chacha = make_chacha(&stored_secret)?;
+        chacha.encrypt_message(plaintext, nonce, aad)
+    }
+
+    async fn aead_chacha20_poly1305_decrypt(
+        &self,
+        key_id: &KeyId,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        let stored_secret = self.get_secret(key_id).await?;
+        let chacha = make_chacha(&stored_secret)?;
+        chacha.decrypt_message(cipher_text, nonce, aad)
+    }
+
+    async fn generate_ephemeral_kem_secret(&self) -> Result<(KeyId, Buffer<u8>)> {
+        let key_pair =
+            pqc_kyber::keypair(&mut thread_rng()).map_err(|_| VaultError::KemKeyGeneration)?;
+
+        // the decapsulation key is only used by this vault, it is stored as a plain buffer
+        let key_id = self.import_ephemeral_secret_impl(
+            Secret::new(key_pair.secret.to_vec()),
+            SecretAttributes::Buffer(KYBER768_SECRET_KEY_LENGTH_U32),
+        )?;
+
+        Ok((key_id, key_pair.public.to_vec()))
+    }
+
+    async fn kem_encapsulate(&self, encapsulation_key: &[u8]) -> Result<(KeyId, Buffer<u8>)> {
+        if encapsulation_key.len() != KYBER768_PUBLIC_KEY_LENGTH_USIZE {
+            return Err(VaultError::InvalidPublicLength.into());
+        }
+
+        let (ciphertext, shared_secret) =
+            pqc_kyber::encapsulate(encapsulation_key, &mut thread_rng())
+                .map_err(|_| VaultError::KemEncapsulate)?;
+
+        let key_id = self.import_ephemeral_secret_impl(
+            Secret::new(shared_secret.to_vec()),
+            SecretAttributes::Buffer(KYBER768_SHARED_SECRET_LENGTH_U32),
+        )?;
+
+        Ok((key_id, ciphertext.to_vec()))
+    }
+
+    async fn kem_decapsulate(&self, decapsulation_key: &KeyId, ciphertext: &[u8]) -> Result<KeyId> {
+        if ciphertext.len() != KYBER768_CIPHERTEXT_LENGTH_USIZE {
+            return Err(VaultError::KemDecapsulate.into());
+        }
+
+        let stored_secret = self.get_secret(decapsulation_key).await?;
+        if stored_secret.attributes() != SecretAttributes::Buffer(KYBER768_SECRET_KEY_LENGTH_U32) {
+            return Err(VaultError::InvalidKeyType.into());
+        }
+
+        let shared_secret = pqc_kyber::decapsulate(ciphertext, stored_secret.secret().as_ref())
+            .map_err(|_| VaultError::KemDecapsulate)?;
+
+        self.import_ephemeral_secret_impl(
+            Secret::new(shared_secret.to_vec()),
+            SecretAttributes::Buffer(KYBER768_SHARED_SECRET_LENGTH_U32),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHACHA20_POLY1305_SECRET_LENGTH_U32;
+
+    #[tokio::test]
+    async fn test_chacha20_poly1305_encrypt_decrypt() -> Result<()> {
+        let vault = SoftwareSecureChannelVault::create();
+        let key_id = vault
+            .generate_ephemeral_secret(SecretAttributes::Buffer(
+                CHACHA20_POLY1305_SECRET_LENGTH_U32,
+            ))
+            .await?;
+        let nonce = [1u8; 12];
+
+        let cipher_text = vault
+            .aead_chacha20_poly1305_encrypt(&key_id, b"hello", &nonce, b"aad")
+            .await?;
+        let plain_text = vault
+            .aead_chacha20_poly1305_decrypt(&key_id, &cipher_text, &nonce, b"aad")
+            .await?;
+        assert_eq!(plain_text, b"hello");
+
+        // the additional data is authenticated
+        assert!(vault
+            .aead_chacha20_poly1305_decrypt(&key_id, &cipher_text, &nonce, b"other")
+            .await
+            .is_err());
+
+        // aes keys cannot be used
+        let aes_key_id = vault
+            .generate_ephemeral_secret(SecretAttributes::Aes256)
+            .await?;
+        assert!(vault
+            .aead_chacha20_poly1305_encrypt(&aes_key_id, b"hello", &nonce, b"aad")
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_kem_encapsulate_decapsulate() -> Result<()> {
+        let vault1 = SoftwareSecureChannelVault::create();
+        let vault2 = SoftwareSecureChannelVault::create();
+
+        let (decapsulation_key, encapsulation_key) = vault1.generate_ephemeral_kem_secret().await?;
+        assert_eq!(encapsulation_key.len(), KYBER768_PUBLIC_KEY_LENGTH_USIZE);
+
+        let (shared_secret2, ciphertext) = vault2.kem_encapsulate(&encapsulation_key).await?;
+        let shared_secret1 = vault1
+            .kem_decapsulate(&decapsulation_key, &ciphertext)
+            .await?;
+
+        assert_eq!(
+            vault1.get_ephemeral_secret(&shared_secret1)?.secret(),
+            vault2.get_ephemeral_secret(&shared_secret2)?.secret()
+        );
+
+        // invalid inputs are rejected
+        assert!(vault2
+            .kem_encapsulate(&encapsulation_key[1..])
+            .await
+            .is_err());
+        assert!(vault1
+            .kem_decapsulate(&decapsulation_key, &ciphertext[1..])
+            .await
+            .is_err());
+        assert!(vault1
+            .kem_decapsulate(&shared_secret1, &ciphertext)
+            .await
+            .is_err());
+        Ok(())
+    }
 }
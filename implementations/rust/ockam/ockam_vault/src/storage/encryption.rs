@@ -0,0 +1,305 @@
+use crate::constants::{AES256_SECRET_LENGTH_USIZE, AES_NONCE_LENGTH_USIZE};
+use crate::software::secure_channel_vault::aes::make_aes;
+use crate::{Secret, SecretAttributes, StoredSecret};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use cfg_if::cfg_if;
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::sync::Mutex;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{hex_encoding, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Length of the random salt used to derive the storage key
+const SALT_LENGTH: usize = 16;
+
+/// Length of the random secret written to a key file
+const KEY_FILE_SECRET_LENGTH: usize = 32;
+
+/// Additional data authenticated together with the encrypted secrets
+const AAD: &[u8] = b"ockam_vault_storage";
+
+/// Secret used to encrypt the content of a [`PersistentStorage`](crate::storage::PersistentStorage)
+///
+/// An AES-256-GCM key is derived from a passphrase, or from the content of a key file, with Argon2id
+/// and a random salt stored next to the encrypted data. Since the derivation is deliberately slow,
+/// the last derived key is kept in memory.
+pub struct StorageEncryption {
+    secret: Zeroizing<Vec<u8>>,
+    derived_key: Mutex<Option<DerivedKey>>,
+}
+
+struct DerivedKey {
+    salt: Vec<u8>,
+    kdf: KdfParams,
+    key: StoredSecret,
+}
+
+impl StorageEncryption {
+    /// Use a passphrase to encrypt the storage
+    pub fn from_passphrase(passphrase: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(StorageEncryptionError::EmptyPassphrase.into());
+        }
+        Ok(Self::new(passphrase.as_bytes().to_vec()))
+    }
+
+    /// Use the secret contained in a key file, created with [`StorageEncryption::create_key_file`],
+    /// to encrypt the storage
+    pub fn from_key_file(path: &Path) -> Result<Self> {
+        let contents = Zeroizing::new(
+            std::fs::read_to_string(path).map_err(|e| Error::new(Origin::Vault, Kind::Io, e))?,
+        );
+        let secret = Zeroizing::new(
+            hex::decode(contents.trim()).map_err(|_| StorageEncryptionError::InvalidKeyFile)?,
+        );
+        if secret.len() != KEY_FILE_SECRET_LENGTH {
+            return Err(StorageEncryptionError::InvalidKeyFile.into());
+        }
+        Ok(Self::new(secret.to_vec()))
+    }
+
+    /// Create a new key file containing a random secret, readable only by the current user.
+    /// An existing file is never overwritten
+    pub fn create_key_file(path: &Path) -> Result<Self> {
+        let mut secret = Zeroizing::new(vec![0u8; KEY_FILE_SECRET_LENGTH]);
+        thread_rng().fill_bytes(&mut secret);
+
+        use std::io::Write;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        cfg_if! {
+            if #[cfg(unix)] {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+        }
+        let mut file = options
+            .open(path)
+            .map_err(|e| Error::new(Origin::Vault, Kind::Io, e))?;
+        file.write_all(Zeroizing::new(hex::encode(&*secret)).as_bytes())
+            .map_err(|e| Error::new(Origin::Vault, Kind::Io, e))?;
+        file.sync_all()
+            .map_err(|e| Error::new(Origin::Vault, Kind::Io, e))?;
+        Ok(Self::new(secret.to_vec()))
+    }
+
+    fn new(secret: Vec<u8>) -> Self {
+        Self {
+            secret: Zeroizing::new(secret),
+            derived_key: Mutex::new(None),
+        }
+    }
+
+    /// Encrypt some data. The salt and KDF parameters of the previous encrypted data are reused
+    /// in order to avoid a new key derivation
+    pub(crate) fn encrypt(
+        &self,
+        plaintext: &[u8],
+        previous: Option<&EncryptedSecrets>,
+    ) -> Result<EncryptedSecrets> {
+        let (salt, kdf) = match previous {
+            Some(previous) => (previous.salt.clone(), previous.kdf.clone()),
+            None => {
+                let mut salt = vec![0u8; SALT_LENGTH];
+                thread_rng().fill_bytes(&mut salt);
+                (salt, KdfParams::default())
+            }
+        };
+        let mut nonce = vec![0u8; AES_NONCE_LENGTH_USIZE];
+        thread_rng().fill_bytes(&mut nonce);
+
+        let key = self.key(&salt, &kdf)?;
+        let ciphertext = make_aes(&key)?.encrypt_message(plaintext, &nonce, AAD)?;
+        Ok(EncryptedSecrets {
+            kdf,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt some data. This fails if the passphrase or the key file is not the one
+    /// used to encrypt the data
+    pub(crate) fn decrypt(&self, encrypted: &EncryptedSecrets) -> Result<Zeroizing<Vec<u8>>> {
+        let key = self.key(&encrypted.salt, &encrypted.kdf)?;
+        let plaintext = make_aes(&key)?
+            .decrypt_message(&encrypted.ciphertext, &encrypted.nonce, AAD)
+            .map_err(|_| StorageEncryptionError::InvalidSecret)?;
+        Ok(Zeroizing::new(plaintext))
+    }
+
+    /// Return the key derived for a given salt, using the last derived key if possible
+    fn key(&self, salt: &[u8], kdf: &KdfParams) -> Result<StoredSecret> {
+        let mut derived_key = self.derived_key.lock().unwrap();
+        if let Some(derived_key) = derived_key.as_ref() {
+            if derived_key.salt == salt && &derived_key.kdf == kdf {
+                return Ok(derived_key.key.clone());
+            }
+        }
+
+        let key = kdf.derive(&self.secret, salt)?;
+        *derived_key = Some(DerivedKey {
+            salt: salt.to_vec(),
+            kdf: kdf.clone(),
+            key: key.clone(),
+        });
+        Ok(key)
+    }
+}
+
+/// Parameters of the key derivation function, stored with the encrypted data
+/// so that they can be strengthened later on without breaking existing files
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    fn derive(&self, secret: &[u8], salt: &[u8]) -> Result<StoredSecret> {
+        if self.algorithm != "argon2id" {
+            return Err(StorageEncryptionError::UnsupportedKdf(self.algorithm.clone()).into());
+        }
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(AES256_SECRET_LENGTH_USIZE),
+        )
+        .map_err(|e| StorageEncryptionError::KeyDerivation(e.to_string()))?;
+
+        let mut key = vec![0u8; AES256_SECRET_LENGTH_USIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(secret, salt, &mut key)
+            .map_err(|e| StorageEncryptionError::KeyDerivation(e.to_string()))?;
+        Ok(StoredSecret::new(
+            Secret::new(key),
+            SecretAttributes::Aes256,
+        ))
+    }
+}
+
+/// Encrypted content of a vault storage file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EncryptedSecrets {
+    kdf: KdfParams,
+    #[serde(with = "hex_encoding")]
+    salt: Vec<u8>,
+    #[serde(with = "hex_encoding")]
+    nonce: Vec<u8>,
+    #[serde(with = "hex_encoding")]
+    ciphertext: Vec<u8>,
+}
+
+/// Errors raised when encrypting or decrypting a vault storage
+#[derive(Clone, Debug)]
+pub enum StorageEncryptionError {
+    /// The storage is encrypted but no passphrase or key file was given
+    Locked,
+    /// The storage contains plaintext secrets which must be migrated first
+    NotEncrypted,
+    /// The passphrase or key file can not decrypt the storage
+    InvalidSecret,
+    /// An empty passphrase was given
+    EmptyPassphrase,
+    /// The key file doesn't contain a valid secret
+    InvalidKeyFile,
+    /// The key derivation function of the storage is not supported
+    UnsupportedKdf(String),
+    /// The key derivation failed
+    KeyDerivation(String),
+}
+
+impl ockam_core::compat::error::Error for StorageEncryptionError {}
+impl core::fmt::Display for StorageEncryptionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Locked => write!(
+                f,
+                "the vault storage is encrypted, a passphrase or a key file is required"
+            ),
+            Self::NotEncrypted => write!(
+                f,
+                "the vault storage contains unencrypted secrets, it must be encrypted first"
+            ),
+            Self::InvalidSecret => write!(
+                f,
+                "the vault storage can not be decrypted with this passphrase or key file"
+            ),
+            Self::EmptyPassphrase => write!(f, "the passphrase must not be empty"),
+            Self::InvalidKeyFile => write!(f, "the key file must contain a 32 bytes hex secret"),
+            Self::UnsupportedKdf(algorithm) => {
+                write!(f, "unsupported key derivation function {algorithm}")
+            }
+            Self::KeyDerivation(e) => write!(f, "key derivation failed: {e}"),
+        }
+    }
+}
+
+impl From<StorageEncryptionError> for Error {
+    #[track_caller]
+    fn from(err: StorageEncryptionError) -> Self {
+        use StorageEncryptionError::*;
+        let kind = match err {
+            Locked | NotEncrypted | EmptyPassphrase => Kind::Misuse,
+            UnsupportedKdf(_) => Kind::Unsupported,
+            InvalidSecret | InvalidKeyFile | KeyDerivation(_) => Kind::Invalid,
+        };
+
+        Error::new(Origin::Vault, kind, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_encrypt_decrypt() -> Result<()> {
+        let encryption = StorageEncryption::from_passphrase("passphrase")?;
+        let encrypted = encryption.encrypt(b"secrets", None)?;
+        assert_ne!(encrypted.ciphertext, b"secrets".to_vec());
+        assert_eq!(encryption.decrypt(&encrypted)?.as_slice(), b"secrets");
+
+        // the salt is kept but a new nonce is used for each encryption
+        let encrypted_again = encryption.encrypt(b"secrets", Some(&encrypted))?;
+        assert_eq!(encrypted_again.salt, encrypted.salt);
+        assert_ne!(encrypted_again.nonce, encrypted.nonce);
+
+        let other = StorageEncryption::from_passphrase("other passphrase")?;
+        assert!(other.decrypt(&encrypted).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_file() -> Result<()> {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.key");
+        let encryption = StorageEncryption::create_key_file(&path)?;
+        let encrypted = encryption.encrypt(b"secrets", None)?;
+
+        let loaded = StorageEncryption::from_key_file(&path)?;
+        assert_eq!(loaded.decrypt(&encrypted)?.as_slice(), b"secrets");
+
+        // an existing key file is not overwritten
+        assert!(StorageEncryption::create_key_file(&path).is_err());
+        Ok(())
+    }
+}
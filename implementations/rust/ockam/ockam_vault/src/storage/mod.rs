@@ -1,4 +1,7 @@
+/// Encryption of the secrets stored in a file
+mod encryption;
 /// Storage of secrets to a file
 mod persistent_storage;
 
+pub use encryption::*;
 pub use persistent_storage::*;
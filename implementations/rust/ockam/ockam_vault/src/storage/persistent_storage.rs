@@ -1,12 +1,15 @@
+use crate::storage::{EncryptedSecrets, StorageEncryption, StorageEncryptionError};
 use crate::{KeyId, Secret, SecretAttributes, StoredSecret};
 
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::compat::sync::Arc;
-use ockam_core::{async_trait, Result};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
 use ockam_node::{FileValueStorage, InMemoryKeyValueStorage, KeyValueStorage, ValueStorage};
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::path::Path;
+use zeroize::Zeroizing;
 
 /// Storage for a Vault data backed by a file
 /// The `FileValueStorage` implementation takes care of locking / unlocking the underlying file
@@ -14,9 +17,12 @@ use std::path::Path;
 /// WARNING: This implementation provides limited consistency if the same file is reused from
 /// multiple instances and/or processes. For example, if one process deletes a value, the other
 /// process will still have it in its cache and return it on a Get query.
+///
+/// When a [`StorageEncryption`] is provided, the secrets are encrypted before being written to the file
 pub struct PersistentStorage {
-    storage: Arc<FileValueStorage<StoredSecrets>>,
+    storage: Arc<FileValueStorage<VaultFile>>,
     cache: InMemoryKeyValueStorage<KeyId, StoredSecret>,
+    encryption: Option<Arc<StorageEncryption>>,
 }
 
 impl PersistentStorage {
@@ -24,8 +30,161 @@ impl PersistentStorage {
     pub async fn create(path: &Path) -> Result<Arc<dyn KeyValueStorage<KeyId, StoredSecret>>> {
         let storage = Arc::new(FileValueStorage::create(path).await?);
         let cache = InMemoryKeyValueStorage::new();
-        Ok(Arc::new(PersistentStorage { storage, cache }))
+        Ok(Arc::new(PersistentStorage {
+            storage,
+            cache,
+            encryption: None,
+        }))
     }
+
+    /// Create a new file storage for a Vault where secrets are encrypted at rest.
+    ///
+    /// The file must either be new or already encrypted with the same passphrase or key file.
+    /// A file containing plaintext secrets must first be migrated with [`PersistentStorage::encrypt`]
+    pub async fn create_encrypted(
+        path: &Path,
+        encryption: StorageEncryption,
+    ) -> Result<Arc<dyn KeyValueStorage<KeyId, StoredSecret>>> {
+        let storage = Arc::new(FileValueStorage::create(path).await?);
+        let encryption = Some(Arc::new(encryption));
+
+        // check that the file can be decrypted and encrypt a new, empty, file right away
+        let e = encryption.clone();
+        storage
+            .update_value(move |file: VaultFile| {
+                let secrets = file.open(&e)?;
+                VaultFile::seal(&secrets, &file, &e)
+            })
+            .await?;
+
+        let cache = InMemoryKeyValueStorage::new();
+        Ok(Arc::new(PersistentStorage {
+            storage,
+            cache,
+            encryption,
+        }))
+    }
+
+    /// Encrypt the secrets of an existing, plaintext, vault file.
+    /// Nothing is done if the file is already encrypted with the same passphrase or key file
+    pub async fn encrypt(path: &Path, encryption: StorageEncryption) -> Result<()> {
+        if !path.exists() {
+            return Err(Error::new(
+                Origin::Vault,
+                Kind::NotFound,
+                format!("the vault file {} does not exist", path.display()),
+            ));
+        }
+        let storage = FileValueStorage::<VaultFile>::create(path).await?;
+        let encryption = Some(Arc::new(encryption));
+        storage
+            .update_value(move |file: VaultFile| {
+                let secrets = match &file {
+                    VaultFile::Plaintext(secrets) => secrets.clone(),
+                    VaultFile::Encrypted(_) => file.open(&encryption)?,
+                };
+                VaultFile::seal(&secrets, &file, &encryption)
+            })
+            .await
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl PersistentStorage {
+    /// Create a storage for the Vault `name` in the `vault/<name>` namespace of a Sqlite database.
+    /// Contrary to the file storage, this storage is consistent across processes
+    pub fn create_with_sqlite(
+        database: &ockam_node::SqliteDatabase,
+        name: &str,
+    ) -> Arc<dyn KeyValueStorage<KeyId, StoredSecret>> {
+        Arc::new(database.key_value_storage::<KeyId, StoredSecret>(&format!("vault/{name}")))
+    }
+
+    /// Move the secrets of a plaintext vault file to the `vault/<name>` namespace of a Sqlite
+    /// database, then remove the file. Nothing is done if the file does not exist.
+    ///
+    /// An encrypted vault file can not be migrated and returns an error
+    pub async fn migrate_to_sqlite(
+        path: &Path,
+        database: &ockam_node::SqliteDatabase,
+        name: &str,
+    ) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let file = FileValueStorage::<VaultFile>::create(path).await?;
+        let secrets = file.read_value(|file: VaultFile| file.open(&None)).await?;
+        let storage = Self::create_with_sqlite(database, name);
+        for (key_id, secret) in secrets.secrets {
+            storage.put(key_id, secret).await?;
+        }
+        match std::fs::remove_file(path) {
+            // the file may have been migrated concurrently by another process
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(Error::new(Origin::Vault, Kind::Io, e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Content of a vault file: either the plaintext secrets or their encrypted serialization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum VaultFile {
+    Encrypted(EncryptedSecrets),
+    Plaintext(StoredSecrets),
+}
+
+impl Default for VaultFile {
+    fn default() -> Self {
+        VaultFile::Plaintext(StoredSecrets::default())
+    }
+}
+
+impl VaultFile {
+    /// Return the secrets contained in this file, decrypting them if necessary
+    fn open(&self, encryption: &Option<Arc<StorageEncryption>>) -> Result<StoredSecrets> {
+        match (self, encryption) {
+            (VaultFile::Plaintext(secrets), None) => Ok(secrets.clone()),
+            // an empty file is created before the first secret is encrypted
+            (VaultFile::Plaintext(secrets), Some(_)) if secrets.secrets.is_empty() => {
+                Ok(secrets.clone())
+            }
+            // plaintext secrets are never silently accepted in an encrypted storage
+            (VaultFile::Plaintext(_), Some(_)) => Err(StorageEncryptionError::NotEncrypted.into()),
+            (VaultFile::Encrypted(_), None) => Err(StorageEncryptionError::Locked.into()),
+            (VaultFile::Encrypted(encrypted), Some(encryption)) => {
+                let plaintext = encryption.decrypt(encrypted)?;
+                serde_cbor::from_slice(&plaintext).map_err(map_cbor_err)
+            }
+        }
+    }
+
+    /// Create the new content of a file, encrypting the secrets if necessary
+    fn seal(
+        secrets: &StoredSecrets,
+        previous: &VaultFile,
+        encryption: &Option<Arc<StorageEncryption>>,
+    ) -> Result<VaultFile> {
+        match encryption {
+            None => Ok(VaultFile::Plaintext(secrets.clone())),
+            Some(encryption) => {
+                let plaintext = Zeroizing::new(serde_cbor::to_vec(secrets).map_err(map_cbor_err)?);
+                let previous = match previous {
+                    VaultFile::Encrypted(encrypted) => Some(encrypted),
+                    VaultFile::Plaintext(_) => None,
+                };
+                Ok(VaultFile::Encrypted(
+                    encryption.encrypt(&plaintext, previous)?,
+                ))
+            }
+        }
+    }
+}
+
+fn map_cbor_err(e: serde_cbor::Error) -> Error {
+    Error::new(Origin::Vault, Kind::Serialization, e)
 }
 
 /// This struct is serialized to a file in order to persist vault data
@@ -118,9 +277,11 @@ impl KeyValueStorage<KeyId, StoredSecret> for PersistentStorage {
             .put(key_id.clone(), stored_secret.clone())
             .await?;
 
-        let t = move |mut v: StoredSecrets| {
+        let encryption = self.encryption.clone();
+        let t = move |file: VaultFile| {
+            let mut v = file.open(&encryption)?;
             v.add_stored_secret(key_id.clone(), stored_secret.clone());
-            Ok(v)
+            VaultFile::seal(&v, &file, &encryption)
         };
         self.storage.update_value(t).await
     }
@@ -130,17 +291,21 @@ impl KeyValueStorage<KeyId, StoredSecret> for PersistentStorage {
             return Ok(Some(s));
         }
         let k = key_id.clone();
-        let t =
-            move |v: StoredSecrets| -> Result<Option<StoredSecret>> { Ok(v.get_stored_secret(&k)) };
+        let encryption = self.encryption.clone();
+        let t = move |file: VaultFile| -> Result<Option<StoredSecret>> {
+            Ok(file.open(&encryption)?.get_stored_secret(&k))
+        };
         self.storage.read_value(t).await
     }
 
     async fn delete(&self, key_id: &KeyId) -> Result<Option<StoredSecret>> {
         self.cache.delete(key_id).await?;
         let k = key_id.clone();
-        let t = move |mut v: StoredSecrets| -> Result<(StoredSecrets, Option<StoredSecret>)> {
+        let encryption = self.encryption.clone();
+        let t = move |file: VaultFile| -> Result<(VaultFile, Option<StoredSecret>)> {
+            let mut v = file.open(&encryption)?;
             let r = v.delete_stored_secret(&k);
-            Ok((v, r))
+            Ok((VaultFile::seal(&v, &file, &encryption)?, r))
         };
         self.storage.modify_value(t).await
     }
@@ -186,4 +351,94 @@ pub(crate) mod tests {
         assert_eq!(actual, Some(stored_secret));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_encrypted_persistent_storage() -> Result<()> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let storage = PersistentStorage::create_encrypted(
+            temp_file.path(),
+            StorageEncryption::from_passphrase("passphrase")?,
+        )
+        .await?;
+
+        let key_id: KeyId = "key_id".into();
+        let stored_secret = StoredSecret::new(Secret::new(vec![1; 32]), SecretAttributes::Ed25519);
+        storage.put(key_id.clone(), stored_secret.clone()).await?;
+
+        // the secret is not written in plaintext
+        let file_contents = std::fs::read_to_string(temp_file.path()).unwrap();
+        assert!(!file_contents.contains(&"01".repeat(32)));
+
+        // the secret can be read back with the same passphrase only
+        let storage = PersistentStorage::create_encrypted(
+            temp_file.path(),
+            StorageEncryption::from_passphrase("passphrase")?,
+        )
+        .await?;
+        assert_eq!(storage.get(&key_id).await?, Some(stored_secret));
+
+        let wrong_passphrase = PersistentStorage::create_encrypted(
+            temp_file.path(),
+            StorageEncryption::from_passphrase("wrong passphrase")?,
+        )
+        .await;
+        assert!(wrong_passphrase.is_err());
+
+        // an encrypted file can not be read without a passphrase
+        let locked = PersistentStorage::create(temp_file.path()).await?;
+        assert!(locked.get(&key_id).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_plaintext_persistent_storage() -> Result<()> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let storage = PersistentStorage::create(temp_file.path()).await?;
+        let key_id: KeyId = "key_id".into();
+        let stored_secret = StoredSecret::new(Secret::new(vec![1; 32]), SecretAttributes::Ed25519);
+        storage.put(key_id.clone(), stored_secret.clone()).await?;
+
+        // a plaintext file must be migrated before being used as an encrypted storage
+        let not_migrated = PersistentStorage::create_encrypted(
+            temp_file.path(),
+            StorageEncryption::from_passphrase("passphrase")?,
+        )
+        .await;
+        assert!(not_migrated.is_err());
+
+        PersistentStorage::encrypt(
+            temp_file.path(),
+            StorageEncryption::from_passphrase("passphrase")?,
+        )
+        .await?;
+        let storage = PersistentStorage::create_encrypted(
+            temp_file.path(),
+            StorageEncryption::from_passphrase("passphrase")?,
+        )
+        .await?;
+        assert_eq!(storage.get(&key_id).await?, Some(stored_secret));
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_migrate_persistent_storage_to_sqlite() -> Result<()> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let storage = PersistentStorage::create(temp_file.path()).await?;
+        let key_id: KeyId = "key_id".into();
+        let stored_secret = StoredSecret::new(Secret::new(vec![1; 32]), SecretAttributes::Ed25519);
+        storage.put(key_id.clone(), stored_secret.clone()).await?;
+
+        let database = ockam_node::SqliteDatabase::create_in_memory()?;
+        PersistentStorage::migrate_to_sqlite(temp_file.path(), &database, "vault").await?;
+        assert!(!temp_file.path().exists());
+
+        let storage = PersistentStorage::create_with_sqlite(&database, "vault");
+        assert_eq!(storage.get(&key_id).await?, Some(stored_secret));
+
+        // there is nothing left to migrate
+        PersistentStorage::migrate_to_sqlite(temp_file.path(), &database, "vault").await?;
+        assert_eq!(storage.keys().await?, vec![key_id]);
+        Ok(())
+    }
 }
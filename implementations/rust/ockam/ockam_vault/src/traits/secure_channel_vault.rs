@@ -68,4 +68,37 @@ pub trait SecureChannelVault: Send + Sync + 'static {
         nonce: &[u8],
         aad: &[u8],
     ) -> Result<Buffer<u8>>;
+
+    /// Encrypt a payload using ChaCha20-Poly1305.
+    /// The key must be a [`SecretAttributes::Buffer`] secret of 32 bytes
+    async fn aead_chacha20_poly1305_encrypt(
+        &self,
+        key_id: &KeyId,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+
+    /// Decrypt a payload using ChaCha20-Poly1305
+    async fn aead_chacha20_poly1305_decrypt(
+        &self,
+        key_id: &KeyId,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+
+    /// Generate a fresh Kyber768 key pair that is persisted only in memory.
+    /// Kyber768 is the round 3 version of Kyber implemented by `pqc_kyber` 0.7, not ML-KEM-768.
+    /// Return the [`KeyId`] of the decapsulation key and the encapsulation key
+    async fn generate_ephemeral_kem_secret(&self) -> Result<(KeyId, Buffer<u8>)>;
+
+    /// Encapsulate a fresh shared secret with the encapsulation key of the other party.
+    /// Return the [`KeyId`] of the shared secret, persisted only in memory, and the ciphertext
+    /// to send to the other party
+    async fn kem_encapsulate(&self, encapsulation_key: &[u8]) -> Result<(KeyId, Buffer<u8>)>;
+
+    /// Decapsulate the shared secret sent by the other party with a decapsulation key.
+    /// Return the [`KeyId`] of the shared secret, persisted only in memory
+    async fn kem_decapsulate(&self, decapsulation_key: &KeyId, ciphertext: &[u8]) -> Result<KeyId>;
 }
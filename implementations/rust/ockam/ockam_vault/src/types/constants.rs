@@ -50,6 +50,28 @@ pub const AES128_SECRET_LENGTH_U32: u32 = 16;
 /// AES128 private key length.
 pub const AES128_SECRET_LENGTH_USIZE: usize = 16;
 
+/// ChaCha20-Poly1305 nonce length
+pub const CHACHA20_POLY1305_NONCE_LENGTH_USIZE: usize = 12;
+
+/// ChaCha20-Poly1305 key length.
+pub const CHACHA20_POLY1305_SECRET_LENGTH_U32: u32 = 32;
+/// ChaCha20-Poly1305 key length.
+pub const CHACHA20_POLY1305_SECRET_LENGTH_USIZE: usize = 32;
+
+/// Kyber768 encapsulation (public) key length.
+pub const KYBER768_PUBLIC_KEY_LENGTH_USIZE: usize = 1184;
+
+/// Kyber768 decapsulation (private) key length.
+pub const KYBER768_SECRET_KEY_LENGTH_U32: u32 = 2400;
+/// Kyber768 decapsulation (private) key length.
+pub const KYBER768_SECRET_KEY_LENGTH_USIZE: usize = 2400;
+
+/// Kyber768 ciphertext length.
+pub const KYBER768_CIPHERTEXT_LENGTH_USIZE: usize = 1088;
+
+/// Kyber768 shared secret length.
+pub const KYBER768_SHARED_SECRET_LENGTH_U32: u32 = 32;
+
 use static_assertions::const_assert_eq;
 
 const_assert_eq!(X25519_SECRET_LENGTH_U32, X25519_SECRET_LENGTH_USIZE as u32);
@@ -72,3 +94,11 @@ const_assert_eq!(
 );
 const_assert_eq!(AES256_SECRET_LENGTH_U32, AES256_SECRET_LENGTH_USIZE as u32);
 const_assert_eq!(AES128_SECRET_LENGTH_U32, AES128_SECRET_LENGTH_USIZE as u32);
+const_assert_eq!(
+    CHACHA20_POLY1305_SECRET_LENGTH_U32,
+    CHACHA20_POLY1305_SECRET_LENGTH_USIZE as u32
+);
+const_assert_eq!(
+    KYBER768_SECRET_KEY_LENGTH_U32,
+    KYBER768_SECRET_KEY_LENGTH_USIZE as u32
+);
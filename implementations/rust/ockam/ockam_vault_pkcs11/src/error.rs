@@ -0,0 +1,45 @@
+use ockam_core::errcode::{Kind, Origin};
+use thiserror::Error;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("pkcs11 is not configured: {0}")]
+    NotConfigured(String),
+    #[error("pkcs11 module {module} can not be loaded: {error}")]
+    Module { module: String, error: String },
+    #[error("no pkcs11 token found")]
+    TokenNotFound,
+    #[error("pkcs11 error creating new key: {0}")]
+    Create(String),
+    #[error("pkcs11 error signing message with key {keyid}: {error}")]
+    Sign { keyid: String, error: String },
+    #[error("pkcs11 error exporting public key {keyid}: {error}")]
+    Export { keyid: String, error: String },
+    #[error("pkcs11 error deleting key {keyid}: {error}")]
+    Delete { keyid: String, error: String },
+    #[error("pkcs11 error deriving a shared secret with key {keyid}: {error}")]
+    Derive { keyid: String, error: String },
+    #[error("pkcs11 error listing the existing keys: {0}")]
+    List(String),
+    #[error("pkcs11 session error: {0}")]
+    Session(String),
+    #[error("key type is not supported")]
+    UnsupportedKeyType,
+    #[error("public key encoding is incorrect")]
+    InvalidPublicKey,
+    #[error("key was not found")]
+    KeyNotFound,
+}
+
+impl From<Error> for ockam_core::Error {
+    fn from(e: Error) -> Self {
+        let kind = match e {
+            Error::KeyNotFound | Error::TokenNotFound => Kind::NotFound,
+            Error::UnsupportedKeyType => Kind::Unsupported,
+            Error::NotConfigured(_) => Kind::Misuse,
+            _ => Kind::Io,
+        };
+        ockam_core::Error::new(Origin::Other, kind, e)
+    }
+}
@@ -0,0 +1,560 @@
+use crate::error::Error;
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::error::RvError;
+use cryptoki::mechanism::elliptic_curve::{EcKdf, Ecdh1DeriveParams};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::env::get_env;
+use ockam_core::Result;
+use ockam_node::tokio::task;
+use ockam_vault::constants::{
+    ED25519_PUBLIC_LENGTH_USIZE, NIST_P256_PUBLIC_LENGTH_USIZE, X25519_PUBLIC_LENGTH_USIZE,
+};
+use ockam_vault::{KeyId, PublicKey, Secret, SecretType, Signature};
+use once_cell::sync::OnceCell;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tracing as log;
+
+/// DER encoding of the secp256r1 curve OID
+const NIST_P256_PARAMS: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// DER encoding of the Ed25519 curve OID
+const ED25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+/// DER encoding of the X25519 curve OID
+const X25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x6e];
+
+/// Length of the random CKA_ID given to new keys
+const KEY_ID_LENGTH: usize = 16;
+
+/// Label given to the keys created by Ockam, unless configured otherwise
+pub const DEFAULT_KEY_LABEL: &str = "ockam";
+
+/// Environment variable containing the path to the PKCS#11 module
+pub const OCKAM_PKCS11_MODULE: &str = "OCKAM_PKCS11_MODULE";
+/// Environment variable containing the label of the PKCS#11 token
+pub const OCKAM_PKCS11_TOKEN_LABEL: &str = "OCKAM_PKCS11_TOKEN_LABEL";
+/// Environment variable containing the user PIN of the PKCS#11 token
+pub const OCKAM_PKCS11_PIN: &str = "OCKAM_PKCS11_PIN";
+
+/// Loaded PKCS#11 modules, by path. A module is loaded and initialized once per process and
+/// never finalized: finalizing it would invalidate the sessions opened by all its clients
+static CONTEXTS: OnceCell<Mutex<BTreeMap<PathBuf, Pkcs11>>> = OnceCell::new();
+
+/// Clients shared by all the vaults using the same module, token and key label
+static CLIENTS: OnceCell<Mutex<BTreeMap<ClientKey, Arc<Pkcs11Client>>>> = OnceCell::new();
+
+/// Module path, token label and key label of a shared client
+type ClientKey = (PathBuf, Option<String>, String);
+
+/// PKCS#11 configuration.
+#[derive(Clone)]
+pub struct Pkcs11Config {
+    module: PathBuf,
+    token_label: Option<String>,
+    pin: String,
+    key_label: String,
+}
+
+impl fmt::Debug for Pkcs11Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Config")
+            .field("module", &self.module)
+            .field("token_label", &self.token_label)
+            .field("key_label", &self.key_label)
+            .finish()
+    }
+}
+
+impl Pkcs11Config {
+    /// Create a new configuration for a PKCS#11 module and the user PIN of its token.
+    /// The first token available is used unless a token label is specified
+    pub fn new(module: impl Into<PathBuf>, pin: impl Into<String>) -> Self {
+        Self {
+            module: module.into(),
+            token_label: None,
+            pin: pin.into(),
+            key_label: DEFAULT_KEY_LABEL.to_string(),
+        }
+    }
+
+    /// Create a new configuration from the `OCKAM_PKCS11_MODULE`, `OCKAM_PKCS11_TOKEN_LABEL`
+    /// and `OCKAM_PKCS11_PIN` environment variables
+    pub fn from_env() -> Result<Self> {
+        let module = get_env::<String>(OCKAM_PKCS11_MODULE)?.ok_or(Error::NotConfigured(
+            format!("{OCKAM_PKCS11_MODULE} is not set"),
+        ))?;
+        let pin = get_env::<String>(OCKAM_PKCS11_PIN)?.unwrap_or_default();
+        let config = Self::new(module, pin);
+        Ok(match get_env::<String>(OCKAM_PKCS11_TOKEN_LABEL)? {
+            Some(token_label) => config.with_token_label(token_label),
+            None => config,
+        })
+    }
+
+    /// Use the token with the given label
+    pub fn with_token_label(self, token_label: impl Into<String>) -> Self {
+        Self {
+            token_label: Some(token_label.into()),
+            ..self
+        }
+    }
+
+    /// Only use the keys with the given label. New keys are created with this label
+    pub fn with_key_label(self, key_label: impl Into<String>) -> Self {
+        Self {
+            key_label: key_label.into(),
+            ..self
+        }
+    }
+}
+
+/// Key pair stored in a PKCS#11 token
+#[derive(Debug, Clone)]
+pub struct Pkcs11Key {
+    /// Hex encoding of the CKA_ID of the key
+    pub key_id: KeyId,
+    /// Type of the key
+    pub secret_type: SecretType,
+    /// Public key
+    pub public_key: PublicKey,
+}
+
+/// PKCS#11 client.
+///
+/// All the operations are executed in a single, logged in, session.
+/// Private keys are created as sensitive and non-extractable objects.
+pub struct Pkcs11Client {
+    session: Arc<Mutex<Session>>,
+    key_label: String,
+}
+
+impl Pkcs11Client {
+    /// Return the client shared by all the vaults using the same module, token and key label,
+    /// creating it if necessary
+    pub async fn shared(config: Pkcs11Config) -> Result<Arc<Self>> {
+        let key = (
+            config.module.clone(),
+            config.token_label.clone(),
+            config.key_label.clone(),
+        );
+        let clients = CLIENTS.get_or_init(Default::default);
+        if let Some(client) = clients.lock().unwrap().get(&key) {
+            return Ok(client.clone());
+        }
+        let client = Arc::new(Self::create(config).await?);
+        Ok(clients.lock().unwrap().entry(key).or_insert(client).clone())
+    }
+
+    /// Open a new session on the token of a PKCS#11 module, loading the module if necessary
+    pub async fn create(config: Pkcs11Config) -> Result<Self> {
+        let key_label = config.key_label.clone();
+        let session = task::spawn_blocking(move || Self::open(config))
+            .await
+            .map_err(|e| Error::Session(e.to_string()))??;
+        Ok(Self {
+            session: Arc::new(Mutex::new(session)),
+            key_label,
+        })
+    }
+
+    /// Return the context of a module, loading and initializing the module the first time
+    fn context(module: &Path) -> Result<Pkcs11> {
+        let mut contexts = CONTEXTS.get_or_init(Default::default).lock().unwrap();
+        if let Some(context) = contexts.get(module) {
+            return Ok(context.clone());
+        }
+        let module_error = |e: cryptoki::error::Error| Error::Module {
+            module: module.display().to_string(),
+            error: e.to_string(),
+        };
+        let context = Pkcs11::new(module).map_err(module_error)?;
+        match context.initialize(CInitializeArgs::OsThreads) {
+            Ok(()) => (),
+            Err(cryptoki::error::Error::AlreadyInitialized) => (),
+            Err(e) => return Err(module_error(e).into()),
+        }
+        contexts.insert(module.to_path_buf(), context.clone());
+        Ok(context)
+    }
+
+    fn open(config: Pkcs11Config) -> Result<Session> {
+        let module_error = |e: cryptoki::error::Error| Error::Module {
+            module: config.module.display().to_string(),
+            error: e.to_string(),
+        };
+        let context = Self::context(&config.module)?;
+
+        let slots = context.get_slots_with_token().map_err(module_error)?;
+        let slot = match &config.token_label {
+            None => slots.first().cloned(),
+            Some(label) => slots.into_iter().find(|slot| {
+                context
+                    .get_token_info(*slot)
+                    .map(|info| info.label() == label)
+                    .unwrap_or(false)
+            }),
+        }
+        .ok_or(Error::TokenNotFound)?;
+
+        let session = context
+            .open_rw_session(slot)
+            .map_err(|e| Error::Session(e.to_string()))?;
+        match session.login(UserType::User, Some(&AuthPin::new(config.pin))) {
+            Ok(()) => (),
+            Err(cryptoki::error::Error::Pkcs11(RvError::UserAlreadyLoggedIn, ..)) => (),
+            Err(e) => return Err(Error::Session(e.to_string()).into()),
+        }
+        log::debug!(module = %config.module.display(), "opened a pkcs11 session");
+        Ok(session)
+    }
+
+    /// Run a function with the session on a blocking thread
+    async fn with_session<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Session) -> Result<R> + Send + 'static,
+    ) -> Result<R> {
+        let session = self.session.clone();
+        task::spawn_blocking(move || f(&session.lock().unwrap()))
+            .await
+            .map_err(|e| Error::Session(e.to_string()))?
+    }
+
+    /// Generate a new key pair in the token. X25519 keys can only be used to derive secrets,
+    /// other keys can only be used to sign
+    pub async fn generate_key(&self, secret_type: SecretType) -> Result<Pkcs11Key> {
+        log::trace!(?secret_type, "create new key");
+        let params = match secret_type {
+            SecretType::NistP256 => NIST_P256_PARAMS,
+            SecretType::Ed25519 => ED25519_PARAMS,
+            SecretType::X25519 => X25519_PARAMS,
+            SecretType::Buffer | SecretType::Aes => return Err(Error::UnsupportedKeyType.into()),
+        };
+        let mut id = vec![0u8; KEY_ID_LENGTH];
+        thread_rng().fill_bytes(&mut id);
+        let label = self.key_label.as_bytes().to_vec();
+        let is_signing_key = secret_type != SecretType::X25519;
+
+        let mut public_template = vec![
+            Attribute::Token(true),
+            Attribute::Private(false),
+            Attribute::EcParams(params.to_vec()),
+            Attribute::Id(id.clone()),
+            Attribute::Label(label.clone()),
+        ];
+        let mut private_template = vec![
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Id(id.clone()),
+            Attribute::Label(label),
+        ];
+        if is_signing_key {
+            public_template.push(Attribute::Verify(true));
+            private_template.push(Attribute::Sign(true));
+        } else {
+            private_template.push(Attribute::Derive(true));
+        }
+
+        let key_id = hex::encode(&id);
+        self.with_session(move |session| {
+            // a mechanism can't be sent to the blocking thread
+            let mechanism = match secret_type {
+                SecretType::NistP256 => Mechanism::EccKeyPairGen,
+                SecretType::Ed25519 => Mechanism::EccEdwardsKeyPairGen,
+                _ => Mechanism::EccMontgomeryKeyPairGen,
+            };
+            let (public_handle, _) = session
+                .generate_key_pair(&mechanism, &public_template, &private_template)
+                .map_err(|e| Error::Create(e.to_string()))?;
+            let public_key = Self::read_public_key(session, public_handle, &key_id, secret_type)?;
+            log::debug!(%key_id, "created new key");
+            Ok(Pkcs11Key {
+                key_id,
+                secret_type,
+                public_key,
+            })
+        })
+        .await
+    }
+
+    /// Return all the key pairs of the token which have the configured label
+    pub async fn list_keys(&self) -> Result<Vec<Pkcs11Key>> {
+        let label = self.key_label.as_bytes().to_vec();
+        self.with_session(move |session| {
+            let private_keys = session
+                .find_objects(&[
+                    Attribute::Class(ObjectClass::PRIVATE_KEY),
+                    Attribute::Label(label.clone()),
+                ])
+                .map_err(|e| Error::List(e.to_string()))?;
+
+            let mut keys = vec![];
+            for private_key in private_keys {
+                match Self::read_key(session, private_key, &label) {
+                    Ok(key) => keys.push(key),
+                    // the token can contain other keys with the same label, which can't be used
+                    Err(err) => log::error!("Error exporting public key: {err}"),
+                }
+            }
+            Ok(keys)
+        })
+        .await
+    }
+
+    /// Delete a key pair. Return false if the key does not exist
+    ///
+    /// Only the private and public keys with the configured label are deleted,
+    /// like the keys which can be listed, found and used by this client
+    pub async fn delete_key(&self, key_id: &KeyId) -> Result<bool> {
+        log::trace!(%key_id, "delete key");
+        let key_id = key_id.clone();
+        let label = self.key_label.as_bytes().to_vec();
+        self.with_session(move |session| {
+            let delete_error = |e: cryptoki::error::Error| Error::Delete {
+                keyid: key_id.clone(),
+                error: e.to_string(),
+            };
+            let id = Self::decode_key_id(&key_id)?;
+            let mut objects = vec![];
+            for class in [ObjectClass::PRIVATE_KEY, ObjectClass::PUBLIC_KEY] {
+                objects.extend(
+                    session
+                        .find_objects(&[
+                            Attribute::Class(class),
+                            Attribute::Id(id.clone()),
+                            Attribute::Label(label.clone()),
+                        ])
+                        .map_err(delete_error)?,
+                );
+            }
+            if objects.is_empty() {
+                log::debug!(%key_id, "key does not exist");
+                return Ok(false);
+            }
+            for object in objects {
+                session.destroy_object(object).map_err(|e| Error::Delete {
+                    keyid: key_id.clone(),
+                    error: e.to_string(),
+                })?;
+            }
+            log::debug!(%key_id, "key deleted");
+            Ok(true)
+        })
+        .await
+    }
+
+    /// Sign a message with ECDSA over its SHA-256 digest for NIST P-256 keys,
+    /// or with EdDSA for Ed25519 keys
+    pub async fn sign(&self, key: &Pkcs11Key, message: &[u8]) -> Result<Signature> {
+        let key_id = key.key_id.clone();
+        let label = self.key_label.as_bytes().to_vec();
+        log::trace!(%key_id, "sign message");
+        let secret_type = key.secret_type;
+        let data = match secret_type {
+            SecretType::NistP256 => Sha256::digest(message).to_vec(),
+            SecretType::Ed25519 => message.to_vec(),
+            _ => return Err(Error::UnsupportedKeyType.into()),
+        };
+        self.with_session(move |session| {
+            let mechanism = match secret_type {
+                SecretType::NistP256 => Mechanism::Ecdsa,
+                _ => Mechanism::Eddsa,
+            };
+            let handle = Self::find_private_key(session, &key_id, label)?;
+            // the ECDSA signature is returned as r || s, which is the format used by Ockam
+            let signature = session
+                .sign(&mechanism, handle, &data)
+                .map_err(|e| Error::Sign {
+                    keyid: key_id.clone(),
+                    error: e.to_string(),
+                })?;
+            log::debug!(%key_id, "signed message");
+            Ok(Signature::new(signature))
+        })
+        .await
+    }
+
+    /// Compute an Elliptic-Curve Diffie-Hellman shared secret with a private key of the token.
+    /// The private key stays in the token, only the shared secret is returned
+    pub async fn ec_diffie_hellman(
+        &self,
+        key_id: &KeyId,
+        peer_public_key: &PublicKey,
+    ) -> Result<Secret> {
+        let key_id = key_id.clone();
+        let label = self.key_label.as_bytes().to_vec();
+        let peer_public_key = peer_public_key.data().to_vec();
+        self.with_session(move |session| {
+            let derive_error = |e: cryptoki::error::Error| Error::Derive {
+                keyid: key_id.clone(),
+                error: e.to_string(),
+            };
+            let handle = Self::find_private_key(session, &key_id, label)?;
+            let mechanism =
+                Mechanism::Ecdh1Derive(Ecdh1DeriveParams::new(EcKdf::null(), &peer_public_key));
+            let template = [
+                Attribute::Class(ObjectClass::SECRET_KEY),
+                Attribute::KeyType(KeyType::GENERIC_SECRET),
+                Attribute::ValueLen(32.into()),
+                Attribute::Token(false),
+                Attribute::Sensitive(false),
+                Attribute::Extractable(true),
+            ];
+            let shared_secret = session
+                .derive_key(&mechanism, handle, &template)
+                .map_err(derive_error)?;
+            let value = session
+                .get_attributes(shared_secret, &[AttributeType::Value])
+                .map_err(derive_error)?;
+            session
+                .destroy_object(shared_secret)
+                .map_err(derive_error)?;
+
+            match value.into_iter().next() {
+                Some(Attribute::Value(value)) => Ok(Secret::new(value)),
+                _ => Err(Error::Derive {
+                    keyid: key_id.clone(),
+                    error: "missing shared secret value".to_string(),
+                }
+                .into()),
+            }
+        })
+        .await
+    }
+
+    fn read_key(session: &Session, private_key: ObjectHandle, label: &[u8]) -> Result<Pkcs11Key> {
+        let attributes = session
+            .get_attributes(
+                private_key,
+                &[
+                    AttributeType::Id,
+                    AttributeType::KeyType,
+                    AttributeType::EcParams,
+                ],
+            )
+            .map_err(|e| Error::List(e.to_string()))?;
+        let (mut id, mut key_type, mut params) = (None, None, None);
+        for attribute in attributes {
+            match attribute {
+                Attribute::Id(value) => id = Some(value),
+                Attribute::KeyType(value) => key_type = Some(value),
+                Attribute::EcParams(value) => params = Some(value),
+                _ => (),
+            }
+        }
+        let id = id.ok_or(Error::KeyNotFound)?;
+        let secret_type = match (key_type, params.as_deref()) {
+            (Some(KeyType::EC), Some(NIST_P256_PARAMS)) => SecretType::NistP256,
+            (Some(KeyType::EC_EDWARDS), _) => SecretType::Ed25519,
+            (Some(KeyType::EC_MONTGOMERY), _) => SecretType::X25519,
+            _ => return Err(Error::UnsupportedKeyType.into()),
+        };
+
+        let key_id = hex::encode(&id);
+        let public_handle = session
+            .find_objects(&[
+                Attribute::Class(ObjectClass::PUBLIC_KEY),
+                Attribute::Id(id),
+                Attribute::Label(label.to_vec()),
+            ])
+            .map_err(|e| Error::List(e.to_string()))?
+            .into_iter()
+            .next()
+            .ok_or(Error::KeyNotFound)?;
+        let public_key = Self::read_public_key(session, public_handle, &key_id, secret_type)?;
+        Ok(Pkcs11Key {
+            key_id,
+            secret_type,
+            public_key,
+        })
+    }
+
+    fn read_public_key(
+        session: &Session,
+        public_handle: ObjectHandle,
+        key_id: &KeyId,
+        secret_type: SecretType,
+    ) -> Result<PublicKey> {
+        let export_error = |error: String| Error::Export {
+            keyid: key_id.clone(),
+            error,
+        };
+        let point = match session
+            .get_attributes(public_handle, &[AttributeType::EcPoint])
+            .map_err(|e| export_error(e.to_string()))?
+            .into_iter()
+            .next()
+        {
+            Some(Attribute::EcPoint(point)) => point,
+            _ => return Err(export_error("missing EC point".to_string()).into()),
+        };
+        let expected_length = match secret_type {
+            SecretType::NistP256 => NIST_P256_PUBLIC_LENGTH_USIZE,
+            SecretType::Ed25519 => ED25519_PUBLIC_LENGTH_USIZE,
+            SecretType::X25519 => X25519_PUBLIC_LENGTH_USIZE,
+            SecretType::Buffer | SecretType::Aes => return Err(Error::UnsupportedKeyType.into()),
+        };
+        Ok(PublicKey::new(
+            Self::decode_ec_point(point, expected_length)?,
+            secret_type,
+        ))
+    }
+
+    /// The CKA_EC_POINT attribute is a DER octet string, but some tokens return the raw point
+    fn decode_ec_point(point: Vec<u8>, expected_length: usize) -> Result<Vec<u8>> {
+        if point.len() == expected_length {
+            return Ok(point);
+        }
+        if point.len() == expected_length + 2
+            && point[0] == 0x04
+            && point[1] as usize == expected_length
+        {
+            return Ok(point[2..].to_vec());
+        }
+        Err(Error::InvalidPublicKey.into())
+    }
+
+    fn find_private_key(session: &Session, key_id: &KeyId, label: Vec<u8>) -> Result<ObjectHandle> {
+        let id = Self::decode_key_id(key_id)?;
+        session
+            .find_objects(&[
+                Attribute::Class(ObjectClass::PRIVATE_KEY),
+                Attribute::Id(id),
+                Attribute::Label(label),
+            ])
+            .map_err(|e| Error::Session(e.to_string()))?
+            .into_iter()
+            .next()
+            .ok_or(Error::KeyNotFound.into())
+    }
+
+    fn decode_key_id(key_id: &KeyId) -> Result<Vec<u8>> {
+        Ok(hex::decode(key_id).map_err(|_| Error::KeyNotFound)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ec_point() {
+        let point = vec![4u8; NIST_P256_PUBLIC_LENGTH_USIZE];
+        let mut der = vec![0x04, NIST_P256_PUBLIC_LENGTH_USIZE as u8];
+        der.extend_from_slice(&point);
+
+        let decoded = Pkcs11Client::decode_ec_point(der, NIST_P256_PUBLIC_LENGTH_USIZE).unwrap();
+        assert_eq!(decoded, point);
+        let decoded =
+            Pkcs11Client::decode_ec_point(point.clone(), NIST_P256_PUBLIC_LENGTH_USIZE).unwrap();
+        assert_eq!(decoded, point);
+        assert!(Pkcs11Client::decode_ec_point(vec![1, 2, 3], ED25519_PUBLIC_LENGTH_USIZE).is_err());
+    }
+}
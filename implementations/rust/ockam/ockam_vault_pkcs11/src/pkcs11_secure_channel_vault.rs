@@ -0,0 +1,251 @@
+use crate::error::Error;
+use crate::pkcs11_client::{Pkcs11Client, Pkcs11Config, Pkcs11Key};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::{async_trait, Result};
+use ockam_vault::{
+    Buffer, KeyId, PublicKey, Secret, SecretAttributes, SecretType, SecureChannelVault,
+    SmallBuffer, SoftwareSecureChannelVault,
+};
+
+/// Secure channel vault keeping its static X25519 keys in a PKCS#11 token.
+///
+/// The Diffie-Hellman operations using a static key are executed by the token, and only their
+/// result is imported as an ephemeral secret in an in-memory software vault. That vault handles
+/// the ephemeral keys, the key derivation and the encryption of the secure channel messages.
+pub struct Pkcs11SecureChannelVault {
+    client: Arc<Pkcs11Client>,
+    // Static keys stored in the token, see the Pkcs11SigningVault
+    keys: Arc<RwLock<Vec<Pkcs11Key>>>,
+    software_vault: Arc<SoftwareSecureChannelVault>,
+}
+
+impl Pkcs11SecureChannelVault {
+    /// Create a PKCS#11 secure channel vault configured with environment variables
+    pub async fn create() -> Result<Self> {
+        Self::create_with_config(Pkcs11Config::from_env()?).await
+    }
+
+    /// Create a new PKCS#11 secure channel vault
+    pub async fn create_with_config(config: Pkcs11Config) -> Result<Self> {
+        Self::create_with_client(Pkcs11Client::shared(config).await?).await
+    }
+
+    /// Create a new PKCS#11 secure channel vault sharing a session with other vaults
+    pub async fn create_with_client(client: Arc<Pkcs11Client>) -> Result<Self> {
+        let keys = client
+            .list_keys()
+            .await?
+            .into_iter()
+            .filter(|key| key.secret_type == SecretType::X25519)
+            .collect();
+
+        Ok(Self {
+            client,
+            keys: Arc::new(RwLock::new(keys)),
+            software_vault: SoftwareSecureChannelVault::create(),
+        })
+    }
+
+    /// Return list of all the keys stored in the token
+    pub fn keys(&self) -> Vec<KeyId> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .map(|x| x.key_id.clone())
+            .collect()
+    }
+
+    fn get_key(&self, key_id: &KeyId) -> Option<Pkcs11Key> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find(|x| &x.key_id == key_id)
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl SecureChannelVault for Pkcs11SecureChannelVault {
+    async fn generate_static_secret(&self, attributes: SecretAttributes) -> Result<KeyId> {
+        // only the X25519 keys used to authenticate secure channels can be stored in the token
+        if attributes != SecretAttributes::X25519 {
+            return self.software_vault.generate_static_secret(attributes).await;
+        }
+
+        let key = self.client.generate_key(SecretType::X25519).await?;
+        let key_id = key.key_id.clone();
+        self.keys.write().unwrap().push(key);
+
+        Ok(key_id)
+    }
+
+    async fn generate_ephemeral_secret(&self, attributes: SecretAttributes) -> Result<KeyId> {
+        self.software_vault
+            .generate_ephemeral_secret(attributes)
+            .await
+    }
+
+    async fn import_static_secret(
+        &self,
+        secret: Secret,
+        attributes: SecretAttributes,
+    ) -> Result<KeyId> {
+        // private keys are never imported in the token, they are only kept in memory
+        self.software_vault
+            .import_static_secret(secret, attributes)
+            .await
+    }
+
+    async fn import_ephemeral_secret(
+        &self,
+        secret: Secret,
+        attributes: SecretAttributes,
+    ) -> Result<KeyId> {
+        self.software_vault
+            .import_ephemeral_secret(secret, attributes)
+            .await
+    }
+
+    async fn delete_secret(&self, key_id: KeyId) -> Result<bool> {
+        if self.get_key(&key_id).is_none() {
+            return self.software_vault.delete_secret(key_id).await;
+        }
+
+        if self.client.delete_key(&key_id).await? {
+            self.keys.write().unwrap().retain(|x| x.key_id != key_id);
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn get_public_key(&self, key_id: &KeyId) -> Result<PublicKey> {
+        match self.get_key(key_id) {
+            Some(key) => Ok(key.public_key),
+            None => self.software_vault.get_public_key(key_id).await,
+        }
+    }
+
+    async fn get_key_id(&self, public_key: &PublicKey) -> Result<KeyId> {
+        let key_id = self.keys.read().unwrap().iter().find_map(|x| {
+            if &x.public_key == public_key {
+                Some(x.key_id.clone())
+            } else {
+                None
+            }
+        });
+        match key_id {
+            Some(key_id) => Ok(key_id),
+            None => self.software_vault.get_key_id(public_key).await,
+        }
+    }
+
+    async fn get_secret_attributes(&self, key_id: &KeyId) -> Result<SecretAttributes> {
+        match self.get_key(key_id) {
+            Some(_) => Ok(SecretAttributes::X25519),
+            None => self.software_vault.get_secret_attributes(key_id).await,
+        }
+    }
+
+    async fn ec_diffie_hellman(
+        &self,
+        secret: &KeyId,
+        peer_public_key: &PublicKey,
+    ) -> Result<KeyId> {
+        if self.get_key(secret).is_none() {
+            return self
+                .software_vault
+                .ec_diffie_hellman(secret, peer_public_key)
+                .await;
+        }
+
+        if peer_public_key.stype() != SecretType::X25519 {
+            return Err(Error::UnsupportedKeyType.into());
+        }
+        let dh = self
+            .client
+            .ec_diffie_hellman(secret, peer_public_key)
+            .await?;
+        let attributes = SecretAttributes::Buffer(dh.length() as u32);
+        self.software_vault
+            .import_ephemeral_secret(dh, attributes)
+            .await
+    }
+
+    async fn hkdf_sha256(
+        &self,
+        salt: &KeyId,
+        info: &[u8],
+        ikm: Option<&KeyId>,
+        output_attributes: SmallBuffer<SecretAttributes>,
+    ) -> Result<SmallBuffer<KeyId>> {
+        self.software_vault
+            .hkdf_sha256(salt, info, ikm, output_attributes)
+            .await
+    }
+
+    async fn aead_aes_gcm_encrypt(
+        &self,
+        key_id: &KeyId,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.software_vault
+            .aead_aes_gcm_encrypt(key_id, plaintext, nonce, aad)
+            .await
+    }
+
+    async fn aead_aes_gcm_decrypt(
+        &self,
+        key_id: &KeyId,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.software_vault
+            .aead_aes_gcm_decrypt(key_id, cipher_text, nonce, aad)
+            .await
+    }
+
+    async fn aead_chacha20_poly1305_encrypt(
+        &self,
+        key_id: &KeyId,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.software_vault
+            .aead_chacha20_poly1305_encrypt(key_id, plaintext, nonce, aad)
+            .await
+    }
+
+    async fn aead_chacha20_poly1305_decrypt(
+        &self,
+        key_id: &KeyId,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.software_vault
+            .aead_chacha20_poly1305_decrypt(key_id, cipher_text, nonce, aad)
+            .await
+    }
+
+    async fn generate_ephemeral_kem_secret(&self) -> Result<(KeyId, Buffer<u8>)> {
+        self.software_vault.generate_ephemeral_kem_secret().await
+    }
+
+    async fn kem_encapsulate(&self, encapsulation_key: &[u8]) -> Result<(KeyId, Buffer<u8>)> {
+        self.software_vault.kem_encapsulate(encapsulation_key).await
+    }
+
+    async fn kem_decapsulate(&self, decapsulation_key: &KeyId, ciphertext: &[u8]) -> Result<KeyId> {
+        self.software_vault
+            .kem_decapsulate(decapsulation_key, ciphertext)
+            .await
+    }
+}
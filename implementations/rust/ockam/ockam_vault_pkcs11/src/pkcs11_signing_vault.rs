@@ -0,0 +1,123 @@
+use crate::error::Error;
+use crate::pkcs11_client::{Pkcs11Client, Pkcs11Config, Pkcs11Key};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::{async_trait, Result};
+use ockam_vault::{
+    KeyId, PublicKey, SecretAttributes, SecretType, Signature, SigningVault, VaultError,
+};
+
+/// Security module implementation using a PKCS#11 token, for example a Hardware Security Module.
+/// NIST P-256 and Ed25519 keys are supported
+pub struct Pkcs11SigningVault {
+    client: Arc<Pkcs11Client>,
+    // Store the keys in memory
+    // This is fetched at the Vault initialization
+    // and is updated locally during add/delete operations
+    // WARNING: The assumption is that there is no concurrent access to the same keys from
+    // different places.
+    keys: Arc<RwLock<Vec<Pkcs11Key>>>,
+}
+
+impl Pkcs11SigningVault {
+    /// Create a PKCS#11 security module configured with environment variables
+    pub async fn create() -> Result<Self> {
+        Self::create_with_config(Pkcs11Config::from_env()?).await
+    }
+
+    /// Create a new PKCS#11 security module
+    pub async fn create_with_config(config: Pkcs11Config) -> Result<Self> {
+        Self::create_with_client(Pkcs11Client::shared(config).await?).await
+    }
+
+    /// Create a new PKCS#11 security module sharing a session with other vaults
+    pub async fn create_with_client(client: Arc<Pkcs11Client>) -> Result<Self> {
+        let keys = client
+            .list_keys()
+            .await?
+            .into_iter()
+            .filter(|key| is_signing_key(key.secret_type))
+            .collect();
+
+        Ok(Self {
+            client,
+            keys: Arc::new(RwLock::new(keys)),
+        })
+    }
+
+    /// Return list of all keys
+    pub fn keys(&self) -> Vec<KeyId> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .map(|x| x.key_id.clone())
+            .collect()
+    }
+
+    fn get_key(&self, key_id: &KeyId) -> Result<Pkcs11Key> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find(|x| &x.key_id == key_id)
+            .cloned()
+            .ok_or(Error::KeyNotFound.into())
+    }
+}
+
+fn is_signing_key(secret_type: SecretType) -> bool {
+    matches!(secret_type, SecretType::NistP256 | SecretType::Ed25519)
+}
+
+#[async_trait]
+impl SigningVault for Pkcs11SigningVault {
+    async fn generate_key(&self, attributes: SecretAttributes) -> Result<KeyId> {
+        if !is_signing_key(attributes.secret_type()) {
+            return Err(VaultError::InvalidKeyType.into());
+        }
+
+        let key = self.client.generate_key(attributes.secret_type()).await?;
+        let key_id = key.key_id.clone();
+        self.keys.write().unwrap().push(key);
+
+        Ok(key_id)
+    }
+
+    async fn delete_key(&self, key_id: KeyId) -> Result<bool> {
+        if self.client.delete_key(&key_id).await? {
+            self.keys.write().unwrap().retain(|x| x.key_id != key_id);
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn get_public_key(&self, key_id: &KeyId) -> Result<PublicKey> {
+        Ok(self.get_key(key_id)?.public_key)
+    }
+
+    async fn get_key_id(&self, public_key: &PublicKey) -> Result<KeyId> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find_map(|x| {
+                if &x.public_key == public_key {
+                    Some(x.key_id.clone())
+                } else {
+                    None
+                }
+            })
+            .ok_or(Error::KeyNotFound.into())
+    }
+
+    async fn sign(&self, key_id: &KeyId, message: &[u8]) -> Result<Signature> {
+        let key = self.get_key(key_id)?;
+        self.client.sign(&key, message).await
+    }
+
+    async fn number_of_keys(&self) -> Result<usize> {
+        Ok(self.keys.read().unwrap().len())
+    }
+}
@@ -0,0 +1,131 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+use ockam_vault::{
+    SecretAttributes, SecretType, SecureChannelVault, SigningVault, SoftwareSecureChannelVault,
+    SoftwareVerifyingVault, VerifyingVault,
+};
+use ockam_vault_pkcs11::{
+    Pkcs11Client, Pkcs11Config, Pkcs11SecureChannelVault, Pkcs11SigningVault,
+};
+
+/// These tests need to be executed with the following environment variables
+/// OCKAM_PKCS11_MODULE, for example /usr/lib/softhsm/libsofthsm2.so
+/// OCKAM_PKCS11_TOKEN_LABEL
+/// OCKAM_PKCS11_PIN
+/// See the README for the initialization of a SoftHSMv2 token
+
+#[tokio::test]
+#[ignore]
+async fn test_sign_verify() -> Result<()> {
+    let signing_vault = Pkcs11SigningVault::create().await?;
+    let verifier = SoftwareVerifyingVault::new();
+    let message = b"hello world";
+
+    for attributes in [SecretAttributes::NistP256, SecretAttributes::Ed25519] {
+        let key_id = signing_vault.generate_key(attributes).await?;
+        let signature = signing_vault.sign(&key_id, message.as_slice()).await?;
+        let public_key = signing_vault.get_public_key(&key_id).await?;
+
+        assert!(verifier.verify(&public_key, message, &signature).await?);
+
+        signing_vault.delete_key(key_id).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_keys_management() -> Result<()> {
+    let signing_vault = Pkcs11SigningVault::create().await?;
+
+    let number_of_keys1 = signing_vault.number_of_keys().await?;
+
+    let key_id = signing_vault
+        .generate_key(SecretAttributes::NistP256)
+        .await?;
+
+    let number_of_keys2 = signing_vault.number_of_keys().await?;
+    assert_eq!(number_of_keys1 + 1, number_of_keys2);
+
+    let public_key = signing_vault.get_public_key(&key_id).await?;
+
+    let key_id2 = signing_vault.get_key_id(&public_key).await?;
+    assert_eq!(key_id, key_id2);
+
+    // the key is found again when the token is reopened
+    let reopened = Pkcs11SigningVault::create().await?;
+    assert_eq!(reopened.get_public_key(&key_id).await?, public_key);
+
+    signing_vault.delete_key(key_id).await?;
+    let number_of_keys3 = signing_vault.number_of_keys().await?;
+    assert_eq!(number_of_keys2, number_of_keys3 + 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_ec_diffie_hellman() -> Result<()> {
+    let client = Arc::new(Pkcs11Client::create(Pkcs11Config::from_env()?).await?);
+    let pkcs11_vault = Pkcs11SecureChannelVault::create_with_client(client.clone()).await?;
+    let software_vault = SoftwareSecureChannelVault::create();
+
+    let static_key_id = pkcs11_vault
+        .generate_static_secret(SecretAttributes::X25519)
+        .await?;
+    let static_public_key = pkcs11_vault.get_public_key(&static_key_id).await?;
+    assert_eq!(
+        pkcs11_vault.get_key_id(&static_public_key).await?,
+        static_key_id
+    );
+
+    let peer_key_id = software_vault
+        .generate_ephemeral_secret(SecretAttributes::X25519)
+        .await?;
+    let peer_public_key = software_vault.get_public_key(&peer_key_id).await?;
+
+    // both sides compute the same shared secret
+    let dh1 = pkcs11_vault
+        .ec_diffie_hellman(&static_key_id, &peer_public_key)
+        .await?;
+    let dh2 = software_vault
+        .ec_diffie_hellman(&peer_key_id, &static_public_key)
+        .await?;
+
+    let plaintext = b"hello world";
+    let nonce = [0u8; 12];
+    let key1 = pkcs11_vault
+        .hkdf_sha256(&dh1, b"", None, vec![SecretAttributes::Aes256])
+        .await?;
+    let key2 = software_vault
+        .hkdf_sha256(&dh2, b"", None, vec![SecretAttributes::Aes256])
+        .await?;
+    let ciphertext = pkcs11_vault
+        .aead_aes_gcm_encrypt(&key1[0], plaintext, &nonce, b"")
+        .await?;
+    let decrypted = software_vault
+        .aead_aes_gcm_decrypt(&key2[0], &ciphertext, &nonce, b"")
+        .await?;
+    assert_eq!(decrypted.as_slice(), plaintext);
+
+    assert!(pkcs11_vault.delete_secret(static_key_id).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_shared_client() -> Result<()> {
+    let client1 = Pkcs11Client::shared(Pkcs11Config::from_env()?).await?;
+    let client2 = Pkcs11Client::shared(Pkcs11Config::from_env()?).await?;
+    assert!(Arc::ptr_eq(&client1, &client2));
+
+    // dropping another client must not finalize the module used by the shared client
+    drop(Pkcs11Client::create(Pkcs11Config::from_env()?).await?);
+    let key = client1.generate_key(SecretType::NistP256).await?;
+    assert!(client1.delete_key(&key.key_id).await?);
+    assert!(!client1.delete_key(&key.key_id).await?);
+
+    Ok(())
+}